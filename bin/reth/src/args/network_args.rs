@@ -3,11 +3,16 @@
 use crate::version::P2P_CLIENT_VERSION;
 use clap::Args;
 use reth_config::Config;
+use reth_discv5::{Discv5Config, Enr, DEFAULT_DISCOVERY_V5_PORT};
 use reth_net_nat::NatResolver;
 use reth_network::{HelloMessage, NetworkConfigBuilder};
 use reth_primitives::{mainnet_nodes, ChainSpec, NodeRecord};
 use secp256k1::SecretKey;
-use std::{path::PathBuf, sync::Arc};
+use std::{
+    net::{Ipv4Addr, SocketAddr},
+    path::PathBuf,
+    sync::Arc,
+};
 
 /// Parameters for configuring the network more granularity via CLI
 #[derive(Debug, Args)]
@@ -26,6 +31,12 @@ pub struct NetworkArgs {
     #[arg(long)]
     pub trusted_only: bool,
 
+    /// Reserved peer enodes, which always keep a connection slot and are reconnected when the
+    /// connection is lost
+    /// --reserved-peers enode://abcd@192.168.0.1:30303
+    #[arg(long, value_delimiter = ',')]
+    pub reserved_peers: Vec<NodeRecord>,
+
     /// Bootnodes to connect to initially.
     ///
     /// Will fall back to a network-specific default if not specified.
@@ -68,6 +79,12 @@ pub struct NetworkArgs {
     /// Maximum number of inbound requests. default: 30
     #[arg(long)]
     pub max_inbound_peers: Option<usize>,
+
+    /// Capture the messages exchanged with each peer to a file in the given directory.
+    ///
+    /// The captures can be replayed with `reth p2p replay`.
+    #[arg(long, value_name = "DIR")]
+    pub p2p_capture_dir: Option<PathBuf>,
 }
 
 impl NetworkArgs {
@@ -86,17 +103,19 @@ impl NetworkArgs {
         let chain_bootnodes = chain_spec.chain.bootnodes().unwrap_or_else(mainnet_nodes);
         let peers_file = self.peers_file.clone().unwrap_or(default_peers_file);
 
-        // Configure peer connections
-        let peer_config = config
+        // Configure peer connections, the peers file is loaded on top of these
+        let mut config = config.clone();
+        config.peers = config
             .peers
-            .clone()
             .with_max_inbound_opt(self.max_inbound_peers)
             .with_max_outbound_opt(self.max_outbound_peers);
+        if let Some(capture_dir) = &self.p2p_capture_dir {
+            config.sessions = config.sessions.with_capture_dir(capture_dir);
+        }
 
         // Configure basic network stack
         let mut network_config_builder = config
             .network_config(self.nat, self.persistent_peers_file(peers_file), secret_key)
-            .peer_config(peer_config)
             .boot_nodes(self.bootnodes.clone().unwrap_or(chain_bootnodes))
             .chain_spec(chain_spec);
 
@@ -136,6 +155,30 @@ pub struct DiscoveryArgs {
     /// The UDP port to use for P2P discovery/networking. default: 30303
     #[arg(long = "discovery.port", name = "discovery.port", value_name = "DISCOVERY_PORT")]
     pub port: Option<u16>,
+
+    /// Enable Discv5 discovery, which runs alongside Discv4.
+    #[arg(long, conflicts_with = "disable_discovery")]
+    pub enable_discv5_discovery: bool,
+
+    /// The UDP port to use for Discv5 discovery. default: 9000
+    #[arg(
+        long = "discovery.v5.port",
+        name = "discovery.v5.port",
+        value_name = "DISCOVERY_V5_PORT",
+        default_value_t = DEFAULT_DISCOVERY_V5_PORT
+    )]
+    pub discv5_port: u16,
+
+    /// ENRs of the nodes to bootstrap Discv5 discovery from.
+    /// --discovery.v5.bootnodes enr:-abcd,enr:-efgh
+    #[arg(
+        long = "discovery.v5.bootnodes",
+        name = "discovery.v5.bootnodes",
+        value_name = "ENR",
+        value_delimiter = ',',
+        verbatim_doc_comment
+    )]
+    pub discv5_bootnodes: Vec<Enr>,
 }
 
 impl DiscoveryArgs {
@@ -151,6 +194,13 @@ impl DiscoveryArgs {
         if self.disable_discovery || self.disable_discv4_discovery {
             network_config_builder = network_config_builder.disable_discv4_discovery();
         }
+        if self.enable_discv5_discovery {
+            let mut discv5 = Discv5Config::builder();
+            discv5
+                .listen_addr(SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), self.discv5_port))
+                .add_boot_nodes(self.discv5_bootnodes.clone());
+            network_config_builder = network_config_builder.discovery_v5(discv5);
+        }
         network_config_builder
     }
 }
@@ -176,6 +226,30 @@ mod tests {
         assert_eq!(args.nat, NatResolver::ExternalIp("0.0.0.0".parse().unwrap()));
     }
 
+    #[test]
+    fn parse_discv5_args() {
+        let args = CommandParser::<NetworkArgs>::parse_from(["reth"]).args;
+        assert!(!args.discovery.enable_discv5_discovery);
+        assert_eq!(args.discovery.discv5_port, DEFAULT_DISCOVERY_V5_PORT);
+
+        let args = CommandParser::<NetworkArgs>::parse_from([
+            "reth",
+            "--enable-discv5-discovery",
+            "--discovery.v5.port",
+            "9001",
+        ])
+        .args;
+        assert!(args.discovery.enable_discv5_discovery);
+        assert_eq!(args.discovery.discv5_port, 9001);
+
+        assert!(CommandParser::<NetworkArgs>::try_parse_from([
+            "reth",
+            "--enable-discv5-discovery",
+            "--disable-discovery",
+        ])
+        .is_err());
+    }
+
     #[test]
     fn parse_peer_args() {
         let args =
@@ -213,4 +287,22 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn parse_reserved_peer_args() {
+        let args =
+            CommandParser::<NetworkArgs>::parse_from([
+            "reth",
+            "--reserved-peers",
+            "enode://d860a01f9722d78051619d1e2351aba3f43f943f6f00718d1b9baa4101932a1f5011f16bb2b1bb35db20d6fe28fa0bf09636d26a87d31de9ec6203eeedb1f666@18.138.108.67:30303"
+        ])
+        .args;
+
+        assert_eq!(
+            args.reserved_peers,
+            vec![
+            "enode://d860a01f9722d78051619d1e2351aba3f43f943f6f00718d1b9baa4101932a1f5011f16bb2b1bb35db20d6fe28fa0bf09636d26a87d31de9ec6203eeedb1f666@18.138.108.67:30303".parse().unwrap()
+            ]
+        );
+    }
 }
@@ -32,11 +32,14 @@ impl PruningArgs {
                         .map(|contract| PruneMode::Before(contract.block)),
                     account_history: Some(PruneMode::Distance(MINIMUM_PRUNING_DISTANCE)),
                     storage_history: Some(PruneMode::Distance(MINIMUM_PRUNING_DISTANCE)),
+                    block_bodies: None,
                     receipts_log_filter: ReceiptsLogPruneConfig(
                         chain_spec
                             .deposit_contract
                             .as_ref()
-                            .map(|contract| (contract.address, PruneMode::Before(contract.block)))
+                            .map(|contract| {
+                                (contract.address.into(), PruneMode::Before(contract.block))
+                            })
                             .into_iter()
                             .collect(),
                     ),
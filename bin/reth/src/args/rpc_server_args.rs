@@ -32,6 +32,7 @@ use reth_rpc_builder::{
     RpcModuleSelection, RpcServerConfig, RpcServerHandle, ServerBuilder, TransportRpcModuleConfig,
 };
 use reth_rpc_engine_api::{EngineApi, EngineApiServer};
+use reth_stages::SyncProgressTracker;
 use reth_tasks::TaskSpawner;
 use reth_transaction_pool::TransactionPool;
 use std::{
@@ -181,6 +182,7 @@ impl RpcServerArgs {
         events: Events,
         engine_api: Engine,
         jwt_secret: JwtSecret,
+        sync_progress: SyncProgressTracker,
         conf: &mut Conf,
     ) -> eyre::Result<(RpcServerHandle, AuthServerHandle)>
     where
@@ -211,6 +213,7 @@ impl RpcServerArgs {
             .with_network(network)
             .with_events(events)
             .with_executor(executor)
+            .with_sync_progress(sync_progress)
             .build_with_auth_server(module_config, engine_api);
 
         // apply configured customization
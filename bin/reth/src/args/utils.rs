@@ -1,11 +1,13 @@
 //! Clap parser utilities
 
 use reth_primitives::{
-    fs, AllGenesisFormats, BlockHashOrNumber, ChainSpec, DEV, GOERLI, HOLESKY, MAINNET, SEPOLIA,
+    fs, AllGenesisFormats, BlockHashOrNumber, BlockNumber, ChainSpec, DEV, GOERLI, HOLESKY,
+    MAINNET, SEPOLIA,
 };
 use reth_revm::primitives::B256 as H256;
 use std::{
     net::{IpAddr, Ipv4Addr, SocketAddr, ToSocketAddrs},
+    ops::RangeInclusive,
     path::PathBuf,
     str::FromStr,
     sync::Arc,
@@ -59,6 +61,26 @@ pub fn hash_or_num_value_parser(value: &str) -> eyre::Result<BlockHashOrNumber,
     }
 }
 
+/// Parse an inclusive block range, given as `<start>..<end>` (exclusive end) or
+/// `<start>..=<end>`.
+pub fn parse_block_range(value: &str) -> eyre::Result<RangeInclusive<BlockNumber>> {
+    let (start, end) = value
+        .split_once("..")
+        .ok_or_else(|| eyre::eyre!("Block range must be <start>..<end> or <start>..=<end>"))?;
+    let start: BlockNumber = start.parse()?;
+    let end = match end.strip_prefix('=') {
+        Some(end) => end.parse()?,
+        None => end
+            .parse::<BlockNumber>()?
+            .checked_sub(1)
+            .ok_or_else(|| eyre::eyre!("Block range is empty"))?,
+    };
+    if end < start {
+        eyre::bail!("Block range is empty")
+    }
+    Ok(start..=end)
+}
+
 /// Error thrown while parsing a socket address.
 #[derive(thiserror::Error, Debug)]
 pub enum SocketAddressParsingError {
@@ -118,6 +140,14 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_block_ranges() {
+        assert_eq!(parse_block_range("0..8192").unwrap(), 0..=8191);
+        assert_eq!(parse_block_range("10..=20").unwrap(), 10..=20);
+        assert!(parse_block_range("10..10").is_err());
+        assert!(parse_block_range("10").is_err());
+    }
+
     #[test]
     fn parse_socket_addresses() {
         for value in ["localhost:9000", ":9000", "9000"] {
@@ -0,0 +1,335 @@
+//! Command that exports canonical blocks as RLP block files.
+use crate::{
+    args::{utils::chain_spec_value_parser, DatabaseArgs},
+    dirs::{DataDirPath, MaybePlatformPath},
+};
+use clap::{Parser, ValueEnum};
+use eyre::eyre;
+use flate2::write::GzEncoder;
+use reth_db::open_db_read_only;
+use reth_primitives::{BlockNumber, ChainSpec};
+use reth_provider::{BlockNumReader, BlockReader, ProviderFactory};
+use reth_rlp::Encodable;
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Write},
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tracing::info;
+
+/// `reth chain export` command
+///
+/// Writes the canonical blocks of a range as concatenated RLP encoded blocks, the format read by
+/// `reth import` and the [`FileClient`](reth_downloaders::test_utils::FileClient). The output is
+/// split into chunk files, which can be concatenated back into a single file. Chunks that already
+/// exist are skipped, so an interrupted export can be resumed by running the same command again.
+#[derive(Debug, Parser)]
+pub struct ExportCommand {
+    /// The path to the data dir for all reth files and subdirectories.
+    ///
+    /// Defaults to the OS-specific data directory:
+    ///
+    /// - Linux: `$XDG_DATA_HOME/reth/` or `$HOME/.local/share/reth/`
+    /// - Windows: `{FOLDERID_RoamingAppData}/reth/`
+    /// - macOS: `$HOME/Library/Application Support/reth/`
+    #[arg(long, value_name = "DATA_DIR", verbatim_doc_comment, default_value_t)]
+    datadir: MaybePlatformPath<DataDirPath>,
+
+    /// The chain this node is running.
+    ///
+    /// Possible values are either a built-in chain or the path to a chain specification file.
+    ///
+    /// Built-in chains:
+    /// - mainnet
+    /// - goerli
+    /// - sepolia
+    #[arg(
+        long,
+        value_name = "CHAIN_OR_PATH",
+        verbatim_doc_comment,
+        default_value = "mainnet",
+        value_parser = chain_spec_value_parser
+    )]
+    chain: Arc<ChainSpec>,
+
+    #[clap(flatten)]
+    db: DatabaseArgs,
+
+    /// The first block to export.
+    ///
+    /// Defaults to the genesis block.
+    #[arg(long, default_value_t = 0, verbatim_doc_comment)]
+    from: BlockNumber,
+
+    /// The last block to export.
+    ///
+    /// Defaults to the last block in the database.
+    #[arg(long, verbatim_doc_comment)]
+    to: Option<BlockNumber>,
+
+    /// The number of blocks per chunk file.
+    #[arg(long, default_value_t = 100_000, value_parser = clap::value_parser!(u64).range(1..))]
+    chunk_size: u64,
+
+    /// The compression of the chunk files.
+    #[arg(long, value_enum, default_value_t = Compression::None)]
+    compression: Compression,
+
+    /// The directory to write the chunk files to.
+    #[arg(value_name = "OUTPUT_DIR", verbatim_doc_comment)]
+    output_dir: PathBuf,
+}
+
+/// The compression of exported block files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Compression {
+    /// Uncompressed RLP.
+    None,
+    /// Gzip compressed RLP.
+    Gzip,
+    /// Zstandard compressed RLP.
+    Zstd,
+}
+
+impl Compression {
+    /// Returns the file extension of chunk files with this compression.
+    fn extension(&self) -> &'static str {
+        match self {
+            Compression::None => "rlp",
+            Compression::Gzip => "rlp.gz",
+            Compression::Zstd => "rlp.zst",
+        }
+    }
+}
+
+impl ExportCommand {
+    /// Execute `chain export` command
+    pub async fn execute(self) -> eyre::Result<()> {
+        // add network name to data dir
+        let data_dir = self.datadir.unwrap_or_chain_default(self.chain.chain);
+        let db_path = data_dir.db_path();
+        info!(target: "reth::cli", path = ?db_path, "Opening database");
+        let db = open_db_read_only(&db_path, self.db.log_level)?;
+        let factory = ProviderFactory::new(db, self.chain.clone());
+        let provider = factory.provider()?;
+
+        let last_block = provider.last_block_number()?;
+        let to = self.to.unwrap_or(last_block);
+        if to > last_block {
+            eyre::bail!("Block {to} is beyond the last block {last_block} in the database")
+        }
+        if self.from > to {
+            eyre::bail!("Empty block range {}..={to}", self.from)
+        }
+
+        export_chunks(
+            &provider,
+            self.from..=to,
+            self.chunk_size,
+            self.compression,
+            &self.output_dir,
+        )
+    }
+}
+
+/// Exports the blocks of the range to chunk files of `chunk_size` blocks in the output directory.
+///
+/// Chunks that already exist are skipped.
+fn export_chunks<P: BlockReader>(
+    provider: &P,
+    range: RangeInclusive<BlockNumber>,
+    chunk_size: u64,
+    compression: Compression,
+    output_dir: &Path,
+) -> eyre::Result<()> {
+    fs::create_dir_all(output_dir)?;
+    for range in chunk_ranges(*range.start(), *range.end(), chunk_size) {
+        let name = chunk_file_name(&range, compression);
+        let path = output_dir.join(&name);
+        if path.exists() {
+            info!(target: "reth::cli", ?path, "Skipping existing chunk");
+            continue
+        }
+
+        // write to a temporary file first, so that only complete chunks are skipped on resume
+        let tmp_path = output_dir.join(format!("{name}.tmp"));
+        let file = BufWriter::new(File::create(&tmp_path)?);
+        match compression {
+            Compression::None => {
+                let mut writer = file;
+                write_blocks(provider, range.clone(), &mut writer)?;
+                writer.flush()?;
+            }
+            Compression::Gzip => {
+                let mut writer = GzEncoder::new(file, flate2::Compression::default());
+                write_blocks(provider, range.clone(), &mut writer)?;
+                writer.finish()?.flush()?;
+            }
+            Compression::Zstd => {
+                let mut writer = zstd::Encoder::new(file, 0)?;
+                write_blocks(provider, range.clone(), &mut writer)?;
+                writer.finish()?.flush()?;
+            }
+        }
+        fs::rename(&tmp_path, &path)?;
+
+        info!(target: "reth::cli", ?path, start = range.start(), end = range.end(), "Exported chunk");
+    }
+
+    Ok(())
+}
+
+/// Writes the RLP encoded blocks of the range one after another.
+fn write_blocks<P: BlockReader, W: Write>(
+    provider: &P,
+    range: RangeInclusive<BlockNumber>,
+    writer: &mut W,
+) -> eyre::Result<()> {
+    let mut buf = Vec::new();
+    for number in range {
+        let block =
+            provider.block(number.into())?.ok_or_else(|| eyre!("Block {number} not found"))?;
+        buf.clear();
+        block.encode(&mut buf);
+        writer.write_all(&buf)?;
+    }
+    Ok(())
+}
+
+/// Splits the inclusive block range into chunks of `chunk_size` blocks.
+fn chunk_ranges(
+    from: BlockNumber,
+    to: BlockNumber,
+    chunk_size: u64,
+) -> impl Iterator<Item = RangeInclusive<BlockNumber>> {
+    (from..=to)
+        .step_by(chunk_size as usize)
+        .map(move |start| start..=start.saturating_add(chunk_size - 1).min(to))
+}
+
+/// Returns the file name of the chunk holding the given blocks.
+fn chunk_file_name(range: &RangeInclusive<BlockNumber>, compression: Compression) -> String {
+    format!("blocks-{:010}-{:010}.{}", range.start(), range.end(), compression.extension())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use reth_db::test_utils::create_test_rw_db;
+    use reth_downloaders::test_utils::FileClient;
+    use reth_interfaces::{
+        p2p::{
+            bodies::client::BodiesClient,
+            headers::client::{HeadersClient, HeadersRequest},
+        },
+        test_utils::{generators, generators::random_block_range},
+    };
+    use reth_primitives::{HeadersDirection, H256, MAINNET};
+    use reth_provider::BlockWriter;
+    use std::io::{self, BufReader};
+
+    #[test]
+    fn parse_export_blocks_args() {
+        let args = ExportCommand::parse_from([
+            "reth",
+            "--from",
+            "10",
+            "--to",
+            "20",
+            "--compression",
+            "zstd",
+            "out",
+        ]);
+        assert_eq!(args.from, 10);
+        assert_eq!(args.to, Some(20));
+        assert_eq!(args.compression, Compression::Zstd);
+    }
+
+    #[test]
+    fn chunks() {
+        assert_eq!(chunk_ranges(1, 10, 4).collect::<Vec<_>>(), vec![1..=4, 5..=8, 9..=10]);
+        assert_eq!(chunk_ranges(5, 5, 100).collect::<Vec<_>>(), vec![5..=5]);
+        assert_eq!(
+            chunk_file_name(&(1..=4), Compression::Gzip),
+            "blocks-0000000001-0000000004.rlp.gz"
+        );
+    }
+
+    #[test]
+    fn parse_default_range() {
+        let args = ExportCommand::parse_from(["reth", "out"]);
+        assert_eq!(args.from, 0);
+        assert_eq!(args.to, None);
+    }
+
+    #[tokio::test]
+    async fn export_file_client_roundtrip() {
+        let factory = ProviderFactory::new(create_test_rw_db(), MAINNET.clone());
+        let mut rng = generators::rng();
+        let blocks = random_block_range(&mut rng, 0..=9, H256::zero(), 0..3);
+        let provider = factory.provider_rw().unwrap();
+        for block in &blocks {
+            provider.insert_block(block.clone(), None, None).unwrap();
+        }
+        provider.commit().unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        for compression in [Compression::None, Compression::Gzip, Compression::Zstd] {
+            let output_dir = dir.path().join(compression.extension());
+            export_chunks(&factory.provider().unwrap(), 0..=9, 4, compression, &output_dir)
+                .unwrap();
+
+            // decompress and concatenate the chunks, as they would be fed to `reth import`
+            let mut chunks = fs::read_dir(&output_dir)
+                .unwrap()
+                .map(|entry| entry.unwrap().path())
+                .collect::<Vec<_>>();
+            chunks.sort();
+            assert_eq!(chunks.len(), 3);
+            let mut rlp = Vec::new();
+            for chunk in chunks {
+                let file = File::open(chunk).unwrap();
+                match compression {
+                    Compression::None => io::copy(&mut BufReader::new(file), &mut rlp),
+                    Compression::Gzip => io::copy(&mut GzDecoder::new(file), &mut rlp),
+                    Compression::Zstd => io::copy(&mut zstd::Decoder::new(file).unwrap(), &mut rlp),
+                }
+                .unwrap();
+            }
+            let path = output_dir.join("blocks.rlp");
+            fs::write(&path, rlp).unwrap();
+
+            let client = FileClient::new(&path).await.unwrap();
+            assert_eq!(client.max_block(), Some(9));
+            assert_eq!(client.tip(), Some(blocks[9].hash));
+            assert!(client.has_canonical_blocks());
+
+            let headers = client
+                .get_headers(HeadersRequest {
+                    start: 0u64.into(),
+                    limit: 10,
+                    direction: HeadersDirection::Rising,
+                })
+                .await
+                .unwrap()
+                .1;
+            assert_eq!(
+                headers,
+                blocks.iter().map(|block| block.header.header.clone()).collect::<Vec<_>>()
+            );
+
+            let bodies = client
+                .get_block_bodies(blocks.iter().map(|block| block.hash).collect())
+                .await
+                .unwrap()
+                .1;
+            for (body, block) in bodies.iter().zip(&blocks) {
+                assert_eq!(body.transactions, block.body);
+                assert_eq!(body.ommers, block.ommers);
+            }
+        }
+    }
+}
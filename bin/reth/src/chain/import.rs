@@ -8,29 +8,43 @@ use clap::Parser;
 use eyre::Context;
 use futures::{Stream, StreamExt};
 use reth_beacon_consensus::BeaconConsensus;
-use reth_provider::{ProviderFactory, StageCheckpointReader};
+use reth_provider::{
+    BlockHashReader, BlockNumReader, BlockReader, HeaderProvider, ProviderFactory,
+    StageCheckpointReader, StageCheckpointWriter,
+};
 
 use crate::args::{utils::genesis_value_parser, DatabaseArgs};
 use reth_config::Config;
-use reth_db::{database::Database, init_db};
+use reth_db::{database::Database, init_db, tables, transaction::DbTxMut};
 use reth_downloaders::{
     bodies::bodies::BodiesDownloaderBuilder,
     headers::reverse_headers::ReverseHeadersDownloaderBuilder, test_utils::FileClient,
 };
+use reth_era::{Era1Block, Era1File, MAX_BLOCKS_PER_ERA1};
 use reth_interfaces::consensus::Consensus;
-use reth_primitives::{stage::StageId, ChainSpec, H256};
+use reth_primitives::{
+    proofs::calculate_receipt_root,
+    stage::{StageCheckpoint, StageId},
+    BlockNumber, ChainSpec, ReceiptWithBloom, H256, U256,
+};
 use reth_stages::{
     prelude::*,
+    sets::OnlineStages,
     stages::{
         ExecutionStage, ExecutionStageThresholds, HeaderSyncMode, SenderRecoveryStage,
-        TotalDifficultyStage,
+        TotalDifficultyStage, TransactionLookupStage,
     },
 };
-use std::{path::PathBuf, sync::Arc};
+use std::{
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 use tokio::sync::watch;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
-/// Syncs RLP encoded blocks from a file.
+/// Syncs RLP encoded blocks or era1 archives from a file.
 #[derive(Debug, Parser)]
 pub struct ImportCommand {
     /// The path to the configuration file to use.
@@ -71,8 +85,22 @@ pub struct ImportCommand {
     ///
     /// The online stages (headers and bodies) are replaced by a file import, after which the
     /// remaining stages are executed.
+    ///
+    /// Era1 files, or a directory of era1 files, are imported one file at a time after verifying
+    /// their accumulators and total difficulties. If the blocks come with receipts, the receipts
+    /// are written directly instead of executing the blocks, and the state at the last block has
+    /// to be provided with `reth init-state`.
     #[arg(value_name = "IMPORT_PATH", verbatim_doc_comment)]
     path: PathBuf,
+
+    /// The path to a file with the trusted accumulator roots of the era1 epochs, one hex encoded
+    /// root per line in epoch order.
+    ///
+    /// If set, the accumulator of each era1 file must match the trusted root of its epoch.
+    /// Otherwise the era1 files are only checked for consistency with themselves and the local
+    /// chain.
+    #[arg(long, value_name = "FILE", verbatim_doc_comment)]
+    era1_accumulators: Option<PathBuf>,
 }
 
 impl ImportCommand {
@@ -100,37 +128,60 @@ impl ImportCommand {
         let consensus = Arc::new(BeaconConsensus::new(self.chain.clone()));
         info!(target: "reth::cli", "Consensus engine initialized");
 
-        // create a new FileClient
-        info!(target: "reth::cli", "Importing chain file");
-        let file_client = Arc::new(FileClient::new(&self.path).await?);
+        let factory = ProviderFactory::new(db.clone(), self.chain.clone());
+
+        if is_era1_path(&self.path) {
+            self.import_era1(config, db, &consensus, &factory).await?;
+        } else {
+            // create a new FileClient
+            info!(target: "reth::cli", "Importing chain file");
+            let file_client = Arc::new(FileClient::new(&self.path).await?);
+            info!(target: "reth::cli", "Chain file imported");
+
+            self.run_import_pipeline(config, db, &consensus, &factory, file_client, true).await?;
+        }
+
+        info!(target: "reth::cli", "Finishing up");
+        Ok(())
+    }
 
+    /// Imports the blocks of the file client, returning `false` if the import was interrupted.
+    async fn run_import_pipeline<DB, C>(
+        &self,
+        config: Config,
+        db: DB,
+        consensus: &Arc<C>,
+        factory: &ProviderFactory<DB>,
+        file_client: Arc<FileClient>,
+        execute: bool,
+    ) -> eyre::Result<bool>
+    where
+        DB: Database + Clone + Unpin + 'static,
+        C: Consensus + 'static,
+    {
         // override the tip
         let tip = file_client.tip().expect("file client has no tip");
-        info!(target: "reth::cli", "Chain file imported");
 
         let (mut pipeline, events) =
-            self.build_import_pipeline(config, Arc::clone(&db), &consensus, file_client).await?;
+            self.build_import_pipeline(config, db, consensus, file_client, execute).await?;
 
         // override the tip
         pipeline.set_tip(tip);
         debug!(target: "reth::cli", ?tip, "Tip manually set");
 
-        let factory = ProviderFactory::new(&db, self.chain.clone());
-        let provider = factory.provider().map_err(PipelineError::Interface)?;
-
-        let latest_block_number =
-            provider.get_stage_checkpoint(StageId::Finish)?.map(|ch| ch.block_number);
+        let latest_block_number = factory
+            .provider()
+            .map_err(PipelineError::Interface)?
+            .get_stage_checkpoint(StageId::Finish)?
+            .map(|ch| ch.block_number);
         tokio::spawn(handle_events(None, latest_block_number, events));
 
         // Run pipeline
         info!(target: "reth::cli", "Starting sync pipeline");
         tokio::select! {
-            res = pipeline.run() => res?,
-            _ = tokio::signal::ctrl_c() => {},
-        };
-
-        info!(target: "reth::cli", "Finishing up");
-        Ok(())
+            res = pipeline.run() => { res?; Ok(true) },
+            _ = tokio::signal::ctrl_c() => Ok(false),
+        }
     }
 
     async fn build_import_pipeline<DB, C>(
@@ -139,6 +190,7 @@ impl ImportCommand {
         db: DB,
         consensus: &Arc<C>,
         file_client: Arc<FileClient>,
+        execute: bool,
     ) -> eyre::Result<(Pipeline<DB>, impl Stream<Item = NodeEvent>)>
     where
         DB: Database + Clone + Unpin + 'static,
@@ -160,6 +212,31 @@ impl ImportCommand {
         let factory = reth_revm::Factory::new(self.chain.clone());
 
         let max_block = file_client.max_block().unwrap_or(0);
+
+        if !execute {
+            // the blocks come with their receipts, only import them without executing
+            let mut pipeline = Pipeline::builder()
+                .with_tip_sender(tip_tx)
+                .with_max_block(max_block)
+                .add_stages(OnlineStages::new(
+                    HeaderSyncMode::Tip(tip_rx),
+                    consensus.clone(),
+                    header_downloader,
+                    body_downloader,
+                ))
+                .add_stage(SenderRecoveryStage {
+                    commit_threshold: config.stages.sender_recovery.commit_threshold,
+                })
+                .add_stage(TransactionLookupStage::new(
+                    config.stages.transaction_lookup.commit_threshold,
+                    config.prune.map(|prune| prune.parts).unwrap_or_default(),
+                ))
+                .build(db, self.chain.clone());
+
+            let events = pipeline.events().map(Into::into);
+            return Ok((pipeline, events))
+        }
+
         let mut pipeline = Pipeline::builder()
             .with_tip_sender(tip_tx)
             // we want to sync all blocks the file client provides or 0 if empty
@@ -202,6 +279,133 @@ impl ImportCommand {
         Ok((pipeline, events))
     }
 
+    /// Imports the era1 files at the import path one file at a time.
+    ///
+    /// Each file is verified against the trusted accumulator roots, if any, and against the local
+    /// chain, after which its blocks following the local head are imported. If the blocks come
+    /// with receipts, the receipts of all blocks that are not marked as executed yet are written
+    /// after each file, so an interrupted import can be resumed.
+    async fn import_era1<DB, C>(
+        &self,
+        config: Config,
+        db: DB,
+        consensus: &Arc<C>,
+        factory: &ProviderFactory<DB>,
+    ) -> eyre::Result<()>
+    where
+        DB: Database + Clone + Unpin + 'static,
+        C: Consensus + 'static,
+    {
+        let trusted_accumulators =
+            self.era1_accumulators.as_deref().map(read_era1_accumulators).transpose()?;
+        if trusted_accumulators.is_none() {
+            warn!(target: "reth::cli", "No trusted era1 accumulators given, only checking the era1 files for consistency");
+        }
+
+        let mut paths = if self.path.is_dir() {
+            let mut paths = std::fs::read_dir(&self.path)?
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect::<Result<Vec<_>, _>>()?;
+            paths.retain(|path| is_era1_path(path));
+            paths
+        } else {
+            vec![self.path.clone()]
+        };
+        // the file names start with the network and the zero padded epoch
+        paths.sort_unstable();
+
+        // whether the receipts are written instead of executing the blocks, decided by the first
+        // file that is imported
+        let mut import_receipts = None;
+        for path in paths {
+            let file = Era1File::read(BufReader::new(File::open(&path)?))
+                .wrap_err_with(|| format!("Invalid era1 file {}", path.display()))?;
+            if let Some(trusted_accumulators) = &trusted_accumulators {
+                let trusted = trusted_accumulators.get(file.epoch() as usize);
+                if file.start_block() % MAX_BLOCKS_PER_ERA1 as u64 != 0 ||
+                    trusted != Some(&file.accumulator)
+                {
+                    eyre::bail!(
+                        "Era1 file {} does not match the trusted accumulator of epoch {}",
+                        path.display(),
+                        file.epoch()
+                    )
+                }
+            }
+
+            let provider = factory.provider()?;
+            let local_head = provider.last_block_number()?;
+            let executed =
+                provider.get_stage_checkpoint(StageId::Execution)?.unwrap_or_default().block_number;
+            verify_era1_file(&provider, &file, local_head)
+                .wrap_err_with(|| format!("Invalid era1 file {}", path.display()))?;
+            drop(provider);
+            debug!(target: "reth::cli", ?path, accumulator = ?file.accumulator, "Era1 file verified");
+
+            if file.end_block() <= executed {
+                debug!(target: "reth::cli", ?path, "Era1 file already imported");
+                continue
+            }
+
+            let receipts = file
+                .blocks
+                .iter()
+                .filter(|block| block.header.number > executed)
+                .map(|block| {
+                    let receipts = block.receipts.clone()?;
+                    (calculate_receipt_root(&receipts) == block.header.receipts_root)
+                        .then_some((block.header.number, receipts))
+                })
+                .collect::<Option<Vec<_>>>();
+            let write_receipts = *import_receipts.get_or_insert_with(|| {
+                if receipts.is_none() {
+                    info!(target: "reth::cli", "Era1 receipts are incomplete, blocks will be executed");
+                }
+                receipts.is_some()
+            });
+            if write_receipts && receipts.is_none() {
+                eyre::bail!(
+                    "Era1 file {} has incomplete receipts, but the receipts of the previous blocks were imported without execution",
+                    path.display()
+                )
+            }
+
+            let blocks = file
+                .blocks
+                .into_iter()
+                .filter(|block| block.header.number > local_head)
+                .map(Era1Block::into_block)
+                .collect::<Vec<_>>();
+            if !blocks.is_empty() {
+                info!(target: "reth::cli", ?path, blocks = blocks.len(), "Importing era1 file");
+                let file_client = Arc::new(FileClient::from_blocks(blocks));
+                let finished = self
+                    .run_import_pipeline(
+                        config.clone(),
+                        db.clone(),
+                        consensus,
+                        factory,
+                        file_client,
+                        !write_receipts,
+                    )
+                    .await?;
+                if !finished {
+                    return Ok(())
+                }
+            }
+
+            if let Some(receipts) = receipts.filter(|_| write_receipts) {
+                insert_era1_receipts(factory, receipts)?;
+            }
+        }
+
+        if import_receipts == Some(true) {
+            info!(target: "reth::cli", "Era1 receipts written, initialize the state at the tip with `reth init-state`");
+        }
+
+        Ok(())
+    }
+
     /// Loads the reth config
     fn load_config(&self, config_path: PathBuf) -> eyre::Result<Config> {
         confy::load_path::<Config>(config_path.clone())
@@ -209,6 +413,87 @@ impl ImportCommand {
     }
 }
 
+/// Returns `true` if the path is a directory or an era1 file.
+fn is_era1_path(path: &Path) -> bool {
+    path.is_dir() || path.extension().map_or(false, |extension| extension == "era1")
+}
+
+/// Reads the trusted era1 accumulator roots, one hex encoded root per line in epoch order.
+fn read_era1_accumulators(path: &Path) -> eyre::Result<Vec<H256>> {
+    std::fs::read_to_string(path)
+        .wrap_err_with(|| format!("Could not read era1 accumulators {}", path.display()))?
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            line.parse::<H256>().wrap_err_with(|| format!("Invalid era1 accumulator {line}"))
+        })
+        .collect()
+}
+
+/// Verifies that the era1 file continues the local chain.
+///
+/// The blocks up to the local head must be part of the local chain, and the total difficulty of
+/// the first block must follow from the total difficulty of its parent. The total difficulties of
+/// the following blocks were already checked when reading the file.
+fn verify_era1_file<Provider>(
+    provider: &Provider,
+    file: &Era1File,
+    local_head: BlockNumber,
+) -> eyre::Result<()>
+where
+    Provider: BlockHashReader + HeaderProvider,
+{
+    if file.start_block() > local_head + 1 {
+        eyre::bail!("Era1 files are missing blocks {}..{}", local_head + 1, file.start_block())
+    }
+
+    for block in file.blocks.iter().take_while(|block| block.header.number <= local_head) {
+        let number = block.header.number;
+        if provider.block_hash(number)? != Some(block.header.hash) {
+            eyre::bail!("Era1 block {number} does not match the local chain")
+        }
+    }
+
+    let first = &file.blocks[0];
+    let parent_td = match first.header.number.checked_sub(1) {
+        Some(parent) => provider
+            .header_td_by_number(parent)?
+            .ok_or_else(|| eyre::eyre!("Total difficulty of block {parent} not found"))?,
+        None => U256::ZERO,
+    };
+    if first.total_difficulty != parent_td + first.header.difficulty {
+        eyre::bail!(
+            "Era1 total difficulty of block {} does not match the local chain",
+            first.header.number
+        )
+    }
+
+    Ok(())
+}
+
+/// Writes the receipts of imported era1 blocks and marks the blocks as executed.
+fn insert_era1_receipts<DB: Database>(
+    factory: &ProviderFactory<DB>,
+    receipts: Vec<(BlockNumber, Vec<ReceiptWithBloom>)>,
+) -> eyre::Result<()> {
+    let Some(last_block) = receipts.last().map(|(number, _)| *number) else { return Ok(()) };
+
+    let provider_rw = factory.provider_rw()?;
+    for (number, receipts) in receipts {
+        let indices = provider_rw
+            .block_body_indices(number)?
+            .ok_or_else(|| eyre::eyre!("Body indices of block {number} not found"))?;
+        for (tx_number, receipt) in indices.tx_num_range().zip(receipts) {
+            provider_rw.tx_ref().put::<tables::Receipts>(tx_number, receipt.into_receipt())?;
+        }
+    }
+    provider_rw.save_stage_checkpoint(StageId::Execution, StageCheckpoint::new(last_block))?;
+    provider_rw.commit()?;
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
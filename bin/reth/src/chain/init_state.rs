@@ -0,0 +1,470 @@
+use crate::{
+    args::{utils::genesis_value_parser, DatabaseArgs},
+    dirs::{DataDirPath, MaybePlatformPath},
+    init::{init_genesis, insert_state_dump_accounts, insert_state_dump_header, StateDumpAccount},
+};
+use clap::Parser;
+use eyre::Context;
+use reth_db::{database::Database, init_db, tables, transaction::DbTxMut, DatabaseEnv};
+use reth_primitives::{
+    stage::StageCheckpoint, ChainSpec, Header, PruneCheckpoint, PruneMode, PrunePart, SealedHeader,
+    H256, U256,
+};
+use reth_provider::{
+    BlockHashReader, BlockNumReader, ProviderFactory, PruneCheckpointWriter, StageCheckpointWriter,
+};
+use reth_rlp::Decodable;
+use reth_stages::{
+    stages::{AccountHashingStage, MerkleStage, StorageHashingStage},
+    ExecInput, ExecOutput, Stage, StageError,
+};
+use serde::Deserialize;
+use std::{
+    fs::File,
+    io::{BufRead, BufReader},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tracing::{debug, info};
+
+/// Initializes the database from a state dump taken at some block.
+///
+/// The dump is a JSONL file with one account per line, with `address`, `balance`, `nonce`, `code`
+/// and `storage` fields. Storage is keyed by the plain slot. An optional leading `{"root": ..}`
+/// line is checked against the state root of the block header.
+///
+/// The hashed state tables and the state trie are rebuilt from the imported state, the resulting
+/// root is verified against the header and all stage checkpoints are set to the dump block, so the
+/// node continues syncing from there. The database must either be empty or hold the chain up to the
+/// dump block, as imported from era1 files.
+///
+/// The state history before the dump block is not available, so it is marked as pruned, and
+/// historical state queries for earlier blocks fail. If the chain was not imported, the same
+/// applies to receipts, senders and transaction lookups.
+#[derive(Debug, Parser)]
+pub struct InitStateCommand {
+    /// The path to the data dir for all reth files and subdirectories.
+    ///
+    /// Defaults to the OS-specific data directory:
+    ///
+    /// - Linux: `$XDG_DATA_HOME/reth/` or `$HOME/.local/share/reth/`
+    /// - Windows: `{FOLDERID_RoamingAppData}/reth/`
+    /// - macOS: `$HOME/Library/Application Support/reth/`
+    #[arg(long, value_name = "DATA_DIR", verbatim_doc_comment, default_value_t)]
+    datadir: MaybePlatformPath<DataDirPath>,
+
+    /// The chain this node is running.
+    ///
+    /// Possible values are either a built-in chain or the path to a chain specification file.
+    ///
+    /// Built-in chains:
+    /// - mainnet
+    /// - goerli
+    /// - sepolia
+    #[arg(
+        long,
+        value_name = "CHAIN_OR_PATH",
+        verbatim_doc_comment,
+        default_value = "mainnet",
+        value_parser = genesis_value_parser
+    )]
+    chain: Arc<ChainSpec>,
+
+    #[clap(flatten)]
+    db: DatabaseArgs,
+
+    /// The path to the header of the block the state dump was taken at.
+    ///
+    /// The header is RLP encoded, either as raw bytes or as a hex string.
+    #[arg(long, value_name = "HEADER_FILE", verbatim_doc_comment)]
+    header: PathBuf,
+
+    /// The total difficulty at the dump block.
+    ///
+    /// Can be omitted for post-merge blocks of chains with a known final difficulty.
+    #[arg(long, value_name = "TOTAL_DIFFICULTY")]
+    total_difficulty: Option<U256>,
+
+    /// The number of accounts and storage slots written before committing.
+    #[arg(long, default_value_t = 100_000)]
+    commit_threshold: usize,
+
+    /// The path to the JSONL state dump.
+    #[arg(value_name = "STATE_DUMP_FILE", verbatim_doc_comment)]
+    state: PathBuf,
+}
+
+impl InitStateCommand {
+    /// Execute the `init-state` command
+    pub async fn execute(self) -> eyre::Result<()> {
+        info!(target: "reth::cli", "reth init-state starting");
+
+        let header = read_header(&self.header)?;
+        let block_number = header.number;
+        if block_number == 0 {
+            eyre::bail!("State dump header is the genesis header, use `reth init` instead")
+        }
+
+        // add network name to data dir
+        let data_dir = self.datadir.unwrap_or_chain_default(self.chain.chain);
+        let db_path = data_dir.db_path();
+        info!(target: "reth::cli", path = ?db_path, "Opening database");
+        let db = Arc::new(init_db(&db_path, self.db.log_level)?);
+        info!(target: "reth::cli", "Database opened");
+
+        let genesis_hash = init_genesis(db.clone(), self.chain.clone())?;
+        debug!(target: "reth::cli", ?genesis_hash, "Genesis block checked");
+
+        let factory = ProviderFactory::new(&db, self.chain.clone());
+        // the chain up to the dump block may already have been imported, e.g. from era1 files
+        let provider = factory.provider()?;
+        let last_block_number = provider.last_block_number()?;
+        let history_imported = last_block_number != 0;
+        if history_imported &&
+            (last_block_number != block_number ||
+                provider.block_hash(block_number)? != Some(header.hash))
+        {
+            eyre::bail!(
+                "Database contains blocks up to {last_block_number}, which do not end at the state dump header {:?}",
+                header.hash
+            )
+        }
+        drop(provider);
+
+        // the header and its total difficulty are only written if the chain was not imported
+        let total_difficulty = match (history_imported, self.total_difficulty) {
+            (true, _) => None,
+            (false, Some(total_difficulty)) => Some(total_difficulty),
+            (false, None) => match self.chain.paris_block_and_final_difficulty {
+                Some((paris_block, final_difficulty)) if block_number >= paris_block => {
+                    Some(final_difficulty)
+                }
+                _ => eyre::bail!(
+                    "The total difficulty of block {block_number} is unknown, pass --total-difficulty"
+                ),
+            },
+        };
+
+        // drop the genesis state and its history, the dump is the full state at the dump block
+        let tx = db.tx_mut()?;
+        tx.clear::<tables::PlainAccountState>()?;
+        tx.clear::<tables::PlainStorageState>()?;
+        tx.clear::<tables::HashedAccount>()?;
+        tx.clear::<tables::HashedStorage>()?;
+        tx.clear::<tables::AccountsTrie>()?;
+        tx.clear::<tables::StoragesTrie>()?;
+        tx.clear::<tables::AccountChangeSet>()?;
+        tx.clear::<tables::StorageChangeSet>()?;
+        tx.clear::<tables::AccountHistory>()?;
+        tx.clear::<tables::StorageHistory>()?;
+        tx.commit()?;
+
+        info!(target: "reth::cli", path = ?self.state, "Importing state dump");
+        let (accounts, slots) =
+            import_state_dump(&db, &self.state, header.state_root, self.commit_threshold)?;
+        info!(target: "reth::cli", accounts, slots, "State dump imported");
+
+        if let Some(total_difficulty) = total_difficulty {
+            let tx = db.tx_mut()?;
+            insert_state_dump_header::<Arc<DatabaseEnv>>(&tx, header.clone(), total_difficulty)?;
+            tx.commit()?;
+        }
+
+        info!(target: "reth::cli", "Computing state root");
+        let stages: Vec<Box<dyn Stage<Arc<DatabaseEnv>>>> = vec![
+            Box::<AccountHashingStage>::default(),
+            Box::<StorageHashingStage>::default(),
+            Box::new(MerkleStage::default_execution()),
+        ];
+        for mut stage in stages {
+            let stage_id = stage.id();
+            info!(target: "reth::cli", stage = %stage_id, "Running stage");
+
+            let mut provider_rw = factory.provider_rw()?;
+            let mut input =
+                ExecInput { target: Some(block_number), checkpoint: Some(StageCheckpoint::new(0)) };
+            loop {
+                match stage.execute(&provider_rw, input).await {
+                    Ok(ExecOutput { done: true, .. }) => break,
+                    Ok(ExecOutput { checkpoint, done: false }) => {
+                        input.checkpoint = Some(checkpoint);
+                        provider_rw.commit()?;
+                        provider_rw = factory.provider_rw()?;
+                    }
+                    Err(StageError::Validation { error, .. }) => {
+                        return Err(error).wrap_err("State root of the dump does not match header")
+                    }
+                    Err(err) => return Err(err.into()),
+                }
+            }
+            provider_rw.commit()?;
+        }
+
+        let provider_rw = factory.provider_rw()?;
+        provider_rw.update_pipeline_stages(block_number, true)?;
+        // the state history up to and including the dump block is missing, the same goes for the
+        // transaction based data if the chain was not imported, so it's marked as pruned
+        let mut pruned_parts = vec![PrunePart::AccountHistory, PrunePart::StorageHistory];
+        if !history_imported {
+            pruned_parts.extend([
+                PrunePart::Receipts,
+                PrunePart::SenderRecovery,
+                PrunePart::TransactionLookup,
+            ]);
+        }
+        for part in pruned_parts {
+            provider_rw.save_prune_checkpoint(
+                part,
+                PruneCheckpoint {
+                    block_number: Some(block_number),
+                    tx_number: None,
+                    prune_mode: PruneMode::Before(block_number + 1),
+                },
+            )?;
+        }
+        provider_rw.commit()?;
+
+        info!(target: "reth::cli", number = block_number, hash = ?header.hash, state_root = ?header.state_root, "State initialized");
+        Ok(())
+    }
+}
+
+/// A line of the state dump: either the expected state root or an account.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum StateDumpLine {
+    Root(StateDumpRoot),
+    Account(StateDumpAccount),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct StateDumpRoot {
+    root: H256,
+}
+
+/// Reads an RLP encoded header, either raw or hex encoded, and seals it.
+fn read_header(path: &Path) -> eyre::Result<SealedHeader> {
+    let contents = std::fs::read(path)
+        .wrap_err_with(|| format!("Could not read header file {}", path.display()))?;
+    let rlp = std::str::from_utf8(&contents)
+        .ok()
+        .and_then(|hex| hex::decode(hex.trim().trim_start_matches("0x")).ok())
+        .unwrap_or(contents);
+    let header = Header::decode(&mut rlp.as_slice()).wrap_err("Invalid header RLP")?;
+    Ok(header.seal_slow())
+}
+
+/// Streams the state dump into the plain state tables, committing every `commit_threshold`
+/// accounts and storage slots.
+///
+/// Returns the number of imported accounts and storage slots.
+fn import_state_dump<DB: Database>(
+    db: &DB,
+    path: &Path,
+    state_root: H256,
+    commit_threshold: usize,
+) -> eyre::Result<(usize, usize)> {
+    let file = File::open(path)
+        .wrap_err_with(|| format!("Could not open state dump {}", path.display()))?;
+
+    let (mut total_accounts, mut total_slots) = (0, 0);
+    let mut batch = Vec::new();
+    let mut batch_entries = 0;
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue
+        }
+
+        match serde_json::from_str(&line)
+            .wrap_err_with(|| format!("Invalid state dump entry on line {}", index + 1))?
+        {
+            StateDumpLine::Root(StateDumpRoot { root }) => {
+                if root != state_root {
+                    eyre::bail!(
+                        "State dump root {root:?} does not match header state root {state_root:?}"
+                    )
+                }
+            }
+            StateDumpLine::Account(account) => {
+                batch_entries += 1 + account.storage.len();
+                batch.push(account);
+            }
+        }
+
+        if batch_entries >= commit_threshold {
+            total_accounts += batch.len();
+            total_slots += write_batch(db, std::mem::take(&mut batch))?;
+            batch_entries = 0;
+            info!(target: "reth::cli", accounts = total_accounts, slots = total_slots, "Committed state dump batch");
+        }
+    }
+
+    total_accounts += batch.len();
+    total_slots += write_batch(db, batch)?;
+
+    Ok((total_accounts, total_slots))
+}
+
+fn write_batch<DB: Database>(db: &DB, batch: Vec<StateDumpAccount>) -> eyre::Result<usize> {
+    let tx = db.tx_mut()?;
+    let slots = insert_state_dump_accounts::<DB>(&tx, batch)?;
+    tx.commit()?;
+    Ok(slots)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reth_db::transaction::DbTx;
+    use reth_interfaces::provider::ProviderError;
+    use reth_primitives::{
+        proofs::genesis_state_root, stage::StageId, Account, Address, Bytes, Chain, Genesis,
+        GenesisAccount,
+    };
+    use reth_provider::{AccountReader, PruneCheckpointReader, StageCheckpointReader};
+    use reth_rlp::Encodable;
+    use reth_trie::StateRoot;
+    use std::{collections::HashMap, str::FromStr};
+
+    const DUMP_BLOCK: u64 = 5;
+
+    fn chain_spec() -> Arc<ChainSpec> {
+        Arc::new(ChainSpec {
+            chain: Chain::Id(1337),
+            genesis: Genesis {
+                alloc: HashMap::from([(
+                    Address::from_low_u64_be(1),
+                    GenesisAccount { balance: U256::from(1), ..Default::default() },
+                )]),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+    }
+
+    fn dump_accounts() -> HashMap<Address, GenesisAccount> {
+        HashMap::from([
+            (
+                Address::from_low_u64_be(2),
+                GenesisAccount { nonce: Some(1), balance: U256::from(2), ..Default::default() },
+            ),
+            (
+                Address::from_low_u64_be(3),
+                GenesisAccount {
+                    code: Some(Bytes::from(vec![0x60, 0x00])),
+                    storage: Some(HashMap::from([
+                        (H256::from_low_u64_be(1), H256::from_low_u64_be(5)),
+                        (H256::from_low_u64_be(2), H256::from_low_u64_be(6)),
+                    ])),
+                    ..Default::default()
+                },
+            ),
+        ])
+    }
+
+    /// Writes the header and the state dump, with the root line if given, and returns the command
+    /// importing them.
+    fn init_state_command(
+        dir: &Path,
+        state_root: H256,
+        root_line: Option<H256>,
+    ) -> InitStateCommand {
+        let header = Header { number: DUMP_BLOCK, state_root, ..Default::default() };
+        let mut rlp = Vec::new();
+        header.encode(&mut rlp);
+        let header_path = dir.join("header.rlp");
+        std::fs::write(&header_path, hex::encode(rlp)).unwrap();
+
+        let mut lines = Vec::new();
+        if let Some(root) = root_line {
+            lines.push(serde_json::json!({ "root": root }).to_string());
+        }
+        for (address, account) in dump_accounts() {
+            lines.push(
+                serde_json::json!({
+                    "address": address,
+                    "balance": account.balance,
+                    "nonce": account.nonce.unwrap_or_default(),
+                    "code": account.code,
+                    "storage": account.storage.unwrap_or_default(),
+                })
+                .to_string(),
+            );
+        }
+        let state_path = dir.join("state.jsonl");
+        std::fs::write(&state_path, lines.join("\n")).unwrap();
+
+        InitStateCommand {
+            datadir: MaybePlatformPath::from_str(dir.join("datadir").to_str().unwrap()).unwrap(),
+            chain: chain_spec(),
+            db: Default::default(),
+            header: header_path,
+            total_difficulty: Some(U256::from(10)),
+            // commit after every account
+            commit_threshold: 1,
+            state: state_path,
+        }
+    }
+
+    #[tokio::test]
+    async fn init_state_from_dump() {
+        let dir = tempfile::tempdir().unwrap();
+        let state_root = genesis_state_root(&dump_accounts());
+        let command = init_state_command(dir.path(), state_root, Some(state_root));
+        let db_path = command.datadir.unwrap_or_chain_default(command.chain.chain).db_path();
+        command.execute().await.unwrap();
+
+        let factory = ProviderFactory::new(init_db(&db_path, None).unwrap(), chain_spec());
+        let provider = factory.provider().unwrap();
+        assert_eq!(StateRoot::new(provider.tx_ref()).root().unwrap(), state_root);
+        for stage_id in StageId::ALL {
+            assert_eq!(
+                provider.get_stage_checkpoint(stage_id).unwrap().map(|c| c.block_number),
+                Some(DUMP_BLOCK),
+                "{stage_id}"
+            );
+        }
+
+        // the genesis state and its history are dropped, the history before the dump is pruned
+        assert_eq!(provider.basic_account(Address::from_low_u64_be(1)).unwrap(), None);
+        assert_eq!(provider.tx_ref().entries::<tables::AccountChangeSet>().unwrap(), 0);
+        assert_eq!(provider.tx_ref().entries::<tables::StorageChangeSet>().unwrap(), 0);
+        assert_eq!(provider.tx_ref().entries::<tables::AccountHistory>().unwrap(), 0);
+        assert_eq!(provider.tx_ref().entries::<tables::StorageHistory>().unwrap(), 0);
+        for part in [
+            PrunePart::AccountHistory,
+            PrunePart::StorageHistory,
+            PrunePart::Receipts,
+            PrunePart::SenderRecovery,
+            PrunePart::TransactionLookup,
+        ] {
+            assert_eq!(
+                provider.get_prune_checkpoint(part).unwrap().and_then(|c| c.block_number),
+                Some(DUMP_BLOCK),
+                "{part}"
+            );
+        }
+        drop(provider);
+
+        let address = Address::from_low_u64_be(2);
+        assert_eq!(
+            factory.latest().unwrap().basic_account(address).unwrap(),
+            Some(Account { nonce: 1, balance: U256::from(2), bytecode_hash: None })
+        );
+        assert!(matches!(
+            factory.history_by_block_number(DUMP_BLOCK - 1).unwrap().basic_account(address),
+            Err(reth_interfaces::Error::Provider(ProviderError::StateAtBlockPruned(_)))
+        ));
+    }
+
+    #[tokio::test]
+    async fn init_state_root_mismatch() {
+        let dir = tempfile::tempdir().unwrap();
+        // no root line, so the mismatch is only detected by the merkle stage
+        let command = init_state_command(dir.path(), H256::random(), None);
+        let err = command.execute().await.unwrap_err();
+        assert!(err.to_string().contains("does not match header"), "{err:?}");
+    }
+}
@@ -1,7 +1,36 @@
 //! Command line utilities for initializing a chain.
+use clap::{Parser, Subcommand};
 
+mod export;
 mod import;
 mod init;
+mod init_state;
 
+pub use export::ExportCommand;
 pub use import::ImportCommand;
 pub use init::InitCommand;
+pub use init_state::InitStateCommand;
+
+/// `reth chain` command
+#[derive(Debug, Parser)]
+pub struct Command {
+    #[clap(subcommand)]
+    command: Subcommands,
+}
+
+/// `reth chain` subcommands
+#[derive(Subcommand, Debug)]
+pub enum Subcommands {
+    /// Export canonical blocks to RLP block files, as read by `reth import`.
+    #[command(name = "export")]
+    Export(ExportCommand),
+}
+
+impl Command {
+    /// Execute `chain` command
+    pub async fn execute(self) -> eyre::Result<()> {
+        match self.command {
+            Subcommands::Export(command) => command.execute().await,
+        }
+    }
+}
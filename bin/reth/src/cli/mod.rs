@@ -5,7 +5,7 @@ use crate::{
     cli::ext::RethCliExt,
     db, debug_cmd,
     dirs::{LogsDir, PlatformPath},
-    node, p2p, recover,
+    export, node, p2p, prune, recover,
     runner::CliRunner,
     stage, test_vectors,
     version::{LONG_VERSION, SHORT_VERSION},
@@ -85,9 +85,13 @@ impl<Ext: RethCliExt> Cli<Ext> {
         match self.command {
             Commands::Node(command) => runner.run_command_until_exit(|ctx| command.execute(ctx)),
             Commands::Init(command) => runner.run_blocking_until_ctrl_c(command.execute()),
+            Commands::InitState(command) => runner.run_blocking_until_ctrl_c(command.execute()),
             Commands::Import(command) => runner.run_blocking_until_ctrl_c(command.execute()),
+            Commands::Export(command) => runner.run_blocking_until_ctrl_c(command.execute()),
+            Commands::Chain(command) => runner.run_blocking_until_ctrl_c(command.execute()),
             Commands::Db(command) => runner.run_blocking_until_ctrl_c(command.execute()),
             Commands::Stage(command) => runner.run_blocking_until_ctrl_c(command.execute()),
+            Commands::Prune(command) => runner.run_blocking_until_ctrl_c(command.execute()),
             Commands::P2P(command) => runner.run_until_ctrl_c(command.execute()),
             Commands::TestVectors(command) => runner.run_until_ctrl_c(command.execute()),
             Commands::Config(command) => runner.run_until_ctrl_c(command.execute()),
@@ -128,15 +132,27 @@ pub enum Commands<Ext: RethCliExt = ()> {
     /// Initialize the database from a genesis file.
     #[command(name = "init")]
     Init(chain::InitCommand),
+    /// Initialize the database from a state dump taken at some block.
+    #[command(name = "init-state")]
+    InitState(chain::InitStateCommand),
     /// This syncs RLP encoded blocks from a file.
     #[command(name = "import")]
     Import(chain::ImportCommand),
+    /// Export chain data from the database.
+    #[command(name = "export")]
+    Export(export::Command),
+    /// Chain data utilities, such as exporting canonical blocks.
+    #[command(name = "chain")]
+    Chain(chain::Command),
     /// Database debugging utilities
     #[command(name = "db")]
     Db(db::Command),
     /// Manipulate individual stages.
     #[command(name = "stage")]
     Stage(stage::Command),
+    /// Prune the database according to the pruning configuration, without running the node.
+    #[command(name = "prune")]
+    Prune(prune::Command),
     /// P2P Debugging utilities
     #[command(name = "p2p")]
     P2P(p2p::Command),
@@ -0,0 +1,216 @@
+use super::get::maybe_json_value_parser;
+use crate::utils::DbTool;
+use clap::{Parser, ValueEnum};
+use reth_db::{
+    cursor::DbCursorRO,
+    database::Database,
+    table::{Key, Table},
+    transaction::DbTx,
+    RawKey, RawTable, TableViewer, Tables,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::File,
+    io::{self, BufWriter, Read, Write},
+    ops::Bound,
+    path::PathBuf,
+};
+use tracing::info;
+
+/// Magic bytes at the start of every raw table export.
+pub(crate) const RAW_EXPORT_MAGIC: &[u8; 8] = b"RETHTBL1";
+
+/// Number of rows between two progress logs.
+pub(crate) const LOG_INTERVAL: usize = 1_000_000;
+
+/// The arguments for the `reth db export` command
+#[derive(Parser, Debug)]
+pub struct Command {
+    /// The table name
+    #[arg()]
+    pub table: Tables,
+
+    /// The file to write the rows to.
+    ///
+    /// Rows are written to stdout if not set, in which case progress is reported on stderr so it
+    /// doesn't end up in the exported rows.
+    #[arg(long, short)]
+    pub output: Option<PathBuf>,
+
+    /// The encoding of the exported rows.
+    #[arg(long, value_enum, default_value_t = ExportFormat::Jsonl)]
+    pub format: ExportFormat,
+
+    /// The first key to export (inclusive), in the same format as `reth db get`.
+    #[arg(long, value_parser = maybe_json_value_parser)]
+    pub from: Option<String>,
+
+    /// The last key to export (inclusive), in the same format as `reth db get`.
+    #[arg(long, value_parser = maybe_json_value_parser)]
+    pub to: Option<String>,
+}
+
+impl Command {
+    /// Execute `db export` command
+    pub fn execute<DB: Database>(self, tool: &DbTool<'_, DB>) -> eyre::Result<()> {
+        self.table.view(&ExportViewer { tool, args: &self })
+    }
+
+    /// Returns the range of raw keys selected by `--from` and `--to`.
+    pub fn key_range<T: Table>(
+        &self,
+    ) -> eyre::Result<(Bound<RawKey<T::Key>>, Bound<RawKey<T::Key>>)> {
+        assert_eq!(T::NAME, self.table.name());
+
+        Ok((
+            parse_bound::<T::Key>(self.from.as_deref())?,
+            parse_bound::<T::Key>(self.to.as_deref())?,
+        ))
+    }
+
+    fn writer(&self) -> eyre::Result<Box<dyn Write>> {
+        Ok(match &self.output {
+            Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+            None => Box::new(BufWriter::new(io::stdout().lock())),
+        })
+    }
+
+    /// Reports the export progress of the table.
+    ///
+    /// Logs go to stdout as well, so they are only used if the rows are written to a file.
+    fn report_progress(&self, table: &str, exported: usize, finished: bool) {
+        let message = if finished { "Table export finished" } else { "Exporting table" };
+        if self.output.is_some() {
+            info!(target: "reth::cli", table, exported, "{message}");
+        } else {
+            eprintln!("{message} table={table} exported={exported}");
+        }
+    }
+}
+
+/// The encoding of the rows of an exported table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    /// One JSON object with a `key` and a `value` field per line, using the serde representation
+    /// of the table types.
+    Jsonl,
+    /// Length-prefixed keys and values exactly as they are encoded in the database.
+    Raw,
+}
+
+/// A single row of a JSONL table export.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct JsonRow<K, V> {
+    pub(crate) key: K,
+    pub(crate) value: V,
+}
+
+struct ExportViewer<'a, DB: Database> {
+    tool: &'a DbTool<'a, DB>,
+    args: &'a Command,
+}
+
+impl<DB: Database> TableViewer<()> for ExportViewer<'_, DB> {
+    type Error = eyre::Report;
+
+    fn view<T: Table>(&self) -> Result<(), Self::Error> {
+        let range = self.args.key_range::<T>()?;
+        let mut writer = self.args.writer()?;
+
+        if self.args.format == ExportFormat::Raw {
+            write_raw_header(&mut writer, T::NAME)?;
+        }
+
+        let exported = self.tool.db.view(|tx| -> eyre::Result<usize> {
+            let mut cursor = tx.cursor_read::<RawTable<T>>()?;
+
+            let mut exported = 0;
+            for row in cursor.walk_range(range)? {
+                let (key, value) = row?;
+                match self.args.format {
+                    ExportFormat::Jsonl => {
+                        let row = JsonRow { key: key.key()?, value: value.value()? };
+                        serde_json::to_writer(&mut writer, &row)?;
+                        writer.write_all(b"\n")?;
+                    }
+                    ExportFormat::Raw => {
+                        write_raw_row(&mut writer, key.raw_key(), value.raw_value())?
+                    }
+                }
+
+                exported += 1;
+                if exported % LOG_INTERVAL == 0 {
+                    self.args.report_progress(T::NAME, exported, false);
+                }
+            }
+
+            Ok(exported)
+        })??;
+        writer.flush()?;
+
+        self.args.report_progress(T::NAME, exported, true);
+
+        Ok(())
+    }
+}
+
+/// Parses an optional JSON key into an inclusive range bound.
+fn parse_bound<K: Key>(key: Option<&str>) -> eyre::Result<Bound<RawKey<K>>> {
+    Ok(match key {
+        Some(key) => Bound::Included(RawKey::new(serde_json::from_str::<K>(key)?)),
+        None => Bound::Unbounded,
+    })
+}
+
+/// Writes the magic bytes and the table name that start a raw table export.
+pub(crate) fn write_raw_header<W: Write>(writer: &mut W, table: &str) -> io::Result<()> {
+    writer.write_all(RAW_EXPORT_MAGIC)?;
+    write_raw_field(writer, table.as_bytes())
+}
+
+/// Reads the header of a raw table export, returning the name of the exported table.
+pub(crate) fn read_raw_header<R: Read>(reader: &mut R) -> eyre::Result<String> {
+    let mut magic = [0u8; 8];
+    reader.read_exact(&mut magic)?;
+    if &magic != RAW_EXPORT_MAGIC {
+        eyre::bail!("Input is not a raw table export.")
+    }
+
+    match read_raw_field(reader)? {
+        Some(table) => Ok(String::from_utf8(table)?),
+        None => eyre::bail!("Raw table export is missing the table name."),
+    }
+}
+
+/// Writes a single encoded row of a raw table export.
+pub(crate) fn write_raw_row<W: Write>(writer: &mut W, key: &[u8], value: &[u8]) -> io::Result<()> {
+    write_raw_field(writer, key)?;
+    write_raw_field(writer, value)
+}
+
+/// Reads the next encoded row of a raw table export, returning `None` at the end of the input.
+pub(crate) fn read_raw_row<R: Read>(reader: &mut R) -> eyre::Result<Option<(Vec<u8>, Vec<u8>)>> {
+    let Some(key) = read_raw_field(reader)? else { return Ok(None) };
+    match read_raw_field(reader)? {
+        Some(value) => Ok(Some((key, value))),
+        None => eyre::bail!("Raw table export ends in the middle of a row."),
+    }
+}
+
+fn write_raw_field<W: Write>(writer: &mut W, field: &[u8]) -> io::Result<()> {
+    writer.write_all(&(field.len() as u32).to_be_bytes())?;
+    writer.write_all(field)
+}
+
+fn read_raw_field<R: Read>(reader: &mut R) -> eyre::Result<Option<Vec<u8>>> {
+    let mut len = [0u8; 4];
+    match reader.read_exact(&mut len) {
+        Ok(()) => {}
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(err) => return Err(err.into()),
+    }
+
+    let mut field = vec![0u8; u32::from_be_bytes(len) as usize];
+    reader.read_exact(&mut field)?;
+    Ok(Some(field))
+}
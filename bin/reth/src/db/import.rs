@@ -0,0 +1,385 @@
+use super::export::{read_raw_header, read_raw_row, ExportFormat, JsonRow, LOG_INTERVAL};
+use clap::Parser;
+use reth_db::{
+    cursor::{DbCursorRO, DbCursorRW, DbDupCursorRW},
+    database::Database,
+    table::{Decode, Decompress, DupSort, Table},
+    tables::{AccountChangeSet, HashedStorage, PlainStorageState, StorageChangeSet, StoragesTrie},
+    transaction::{DbTx, DbTxMut},
+    RawDupSort, RawKey, RawTable, RawValue, TableRawRow, TableType, TableViewer, Tables,
+};
+use std::{
+    fs::File,
+    io::{BufRead, BufReader},
+    path::PathBuf,
+};
+use tracing::info;
+
+/// The arguments for the `reth db import` command
+#[derive(Parser, Debug)]
+pub struct Command {
+    /// The table name
+    #[arg()]
+    pub table: Tables,
+
+    /// The file produced by `reth db export` to read the rows from.
+    #[arg(long, short)]
+    pub input: PathBuf,
+
+    /// The encoding of the rows in the input file.
+    #[arg(long, value_enum, default_value_t = ExportFormat::Jsonl)]
+    pub format: ExportFormat,
+
+    /// The number of rows to write before committing the database transaction.
+    #[arg(long, default_value_t = 100_000)]
+    pub commit_threshold: usize,
+}
+
+impl Command {
+    /// Execute `db import` command
+    pub fn execute<DB: Database>(self, db: &DB) -> eyre::Result<()> {
+        let importer = ImportViewer { db, args: &self };
+
+        // duplicates of a key can only be appended through a dupsort cursor, which requires the
+        // concrete table type
+        match self.table {
+            Tables::PlainStorageState => importer.import_dupsort::<PlainStorageState>(),
+            Tables::AccountChangeSet => importer.import_dupsort::<AccountChangeSet>(),
+            Tables::StorageChangeSet => importer.import_dupsort::<StorageChangeSet>(),
+            Tables::HashedStorage => importer.import_dupsort::<HashedStorage>(),
+            Tables::StoragesTrie => importer.import_dupsort::<StoragesTrie>(),
+            table => table.view(&importer),
+        }
+    }
+
+    /// Returns an iterator over the rows of the input file for the given table.
+    fn rows<T: Table>(
+        &self,
+    ) -> eyre::Result<Box<dyn Iterator<Item = eyre::Result<TableRawRow<T>>>>> {
+        let mut reader = BufReader::new(File::open(&self.input)?);
+
+        Ok(match self.format {
+            ExportFormat::Jsonl => Box::new(
+                reader
+                    .lines()
+                    .filter(|line| !matches!(line, Ok(line) if line.trim().is_empty()))
+                    .map(|line| {
+                        let row: JsonRow<T::Key, T::Value> = serde_json::from_str(&line?)?;
+                        Ok((RawKey::new(row.key), RawValue::new(row.value)))
+                    }),
+            ),
+            ExportFormat::Raw => {
+                let table = read_raw_header(&mut reader)?;
+                if table != T::NAME {
+                    eyre::bail!("Input contains rows of table {table}, expected {}.", T::NAME)
+                }
+
+                Box::new(std::iter::from_fn(move || read_raw_row(&mut reader).transpose()).map(
+                    |row| {
+                        let (key, value) = row?;
+                        Ok((RawKey::decode(key)?, RawValue::decompress(value)?))
+                    },
+                ))
+            }
+        })
+    }
+}
+
+struct ImportViewer<'a, DB: Database> {
+    db: &'a DB,
+    args: &'a Command,
+}
+
+impl<DB: Database> ImportViewer<'_, DB> {
+    /// Returns the batches of rows to import into the table, each committed separately.
+    fn batches<T: Table>(&self, dupsort: bool) -> eyre::Result<RowBatches<T>> {
+        // Rows can only be appended after the last entry that is already in the table.
+        let mut order = RowOrder::new(dupsort);
+        if let Some((key, value)) = self.db.view(|tx| tx.cursor_read::<RawTable<T>>()?.last())?? {
+            order.check(key.raw_key(), value.raw_value())?;
+        }
+
+        Ok(RowBatches {
+            rows: self.args.rows::<T>()?,
+            order,
+            batch_size: self.args.commit_threshold,
+            imported: 0,
+        })
+    }
+
+    /// Imports the rows of a dupsort table, appending the duplicates of every key.
+    fn import_dupsort<T: DupSort>(&self) -> eyre::Result<()> {
+        let mut batches = self.batches::<T>(true)?;
+        while let Some(batch) = batches.next_batch()? {
+            let tx = self.db.tx_mut()?;
+            let mut cursor = tx.cursor_dup_write::<RawDupSort<T>>()?;
+            for (key, value) in batch {
+                cursor.append_dup(key, value)?;
+            }
+
+            drop(cursor);
+            tx.commit()?;
+        }
+
+        info!(target: "reth::cli", table = T::NAME, imported = batches.imported, "Table import finished");
+
+        Ok(())
+    }
+}
+
+impl<DB: Database> TableViewer<()> for ImportViewer<'_, DB> {
+    type Error = eyre::Report;
+
+    fn view<T: Table>(&self) -> Result<(), Self::Error> {
+        if self.args.table.table_type() == TableType::DupSort {
+            eyre::bail!("Importing {} is not supported.", T::NAME)
+        }
+
+        let mut batches = self.batches::<T>(false)?;
+        while let Some(batch) = batches.next_batch()? {
+            let tx = self.db.tx_mut()?;
+            let mut cursor = tx.cursor_write::<RawTable<T>>()?;
+            for (key, value) in batch {
+                cursor.append(key, value)?;
+            }
+
+            drop(cursor);
+            tx.commit()?;
+        }
+
+        info!(target: "reth::cli", table = T::NAME, imported = batches.imported, "Table import finished");
+
+        Ok(())
+    }
+}
+
+/// Reads the rows of the input in batches, validating their order.
+struct RowBatches<T: Table> {
+    rows: Box<dyn Iterator<Item = eyre::Result<TableRawRow<T>>>>,
+    order: RowOrder,
+    batch_size: usize,
+    imported: usize,
+}
+
+impl<T: Table> RowBatches<T> {
+    /// Returns the next batch of ordered rows, or `None` if all rows were read.
+    fn next_batch(&mut self) -> eyre::Result<Option<Vec<TableRawRow<T>>>> {
+        let mut batch = Vec::with_capacity(self.batch_size.min(LOG_INTERVAL));
+        for row in self.rows.by_ref().take(self.batch_size) {
+            let (key, value) = row?;
+            let imported = self.imported;
+            self.order.check(key.raw_key(), value.raw_value()).map_err(|err| {
+                eyre::eyre!("Row {imported} cannot be imported into {}: {err}", T::NAME)
+            })?;
+            batch.push((key, value));
+
+            self.imported += 1;
+            if self.imported % LOG_INTERVAL == 0 {
+                info!(target: "reth::cli", table = T::NAME, imported = self.imported, "Importing table");
+            }
+        }
+
+        Ok((!batch.is_empty()).then_some(batch))
+    }
+}
+
+/// Validates that rows arrive in the order in which they are stored in the database.
+///
+/// Keys are compared in their encoded form. For dupsort tables the same key can repeat, in which
+/// case the encoded values have to be ordered.
+#[derive(Debug)]
+struct RowOrder {
+    dupsort: bool,
+    last: Option<(Vec<u8>, Vec<u8>)>,
+}
+
+impl RowOrder {
+    fn new(dupsort: bool) -> Self {
+        Self { dupsort, last: None }
+    }
+
+    /// Checks that the row comes after the previously checked one and records it.
+    fn check(&mut self, key: &[u8], value: &[u8]) -> eyre::Result<()> {
+        if let Some((last_key, last_value)) = &self.last {
+            let ordered = match key.cmp(last_key.as_slice()) {
+                std::cmp::Ordering::Greater => true,
+                std::cmp::Ordering::Equal => self.dupsort && value > last_value.as_slice(),
+                std::cmp::Ordering::Less => false,
+            };
+
+            if !ordered {
+                eyre::bail!(
+                    "key 0x{} is not ordered after the previous key 0x{}",
+                    hex::encode(key),
+                    hex::encode(last_key)
+                )
+            }
+        }
+
+        self.last = Some((key.to_vec(), value.to_vec()));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{db::export, utils::DbTool};
+    use reth_db::{test_utils::create_test_rw_db, PlainAccountState, PlainStorageState};
+    use reth_primitives::{Account, Address, StorageEntry, H256, MAINNET, U256};
+
+    fn accounts() -> Vec<(Address, Account)> {
+        (1..=10u64)
+            .map(|i| {
+                (
+                    Address::from_low_u64_be(i),
+                    Account { nonce: i, balance: U256::from(i), bytecode_hash: None },
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn export_import_roundtrip() {
+        for format in [ExportFormat::Jsonl, ExportFormat::Raw] {
+            let source = create_test_rw_db();
+            source
+                .update(|tx| {
+                    for (address, account) in accounts() {
+                        tx.put::<PlainAccountState>(address, account).unwrap();
+                    }
+                })
+                .unwrap();
+
+            let file = tempfile::NamedTempFile::new().unwrap();
+            export::Command {
+                table: Tables::PlainAccountState,
+                output: Some(file.path().to_path_buf()),
+                format,
+                from: Some(serde_json::to_string(&Address::from_low_u64_be(3)).unwrap()),
+                to: Some(serde_json::to_string(&Address::from_low_u64_be(7)).unwrap()),
+            }
+            .execute(&DbTool::new(source.as_ref(), MAINNET.clone()).unwrap())
+            .unwrap();
+
+            let destination = create_test_rw_db();
+            Command {
+                table: Tables::PlainAccountState,
+                input: file.path().to_path_buf(),
+                format,
+                commit_threshold: 2,
+            }
+            .execute(destination.as_ref())
+            .unwrap();
+
+            let imported = destination
+                .view(|tx| {
+                    tx.cursor_read::<PlainAccountState>()
+                        .unwrap()
+                        .walk(None)
+                        .unwrap()
+                        .collect::<Result<Vec<_>, _>>()
+                })
+                .unwrap()
+                .unwrap();
+            assert_eq!(imported, accounts()[2..7].to_vec());
+        }
+    }
+
+    #[test]
+    fn dupsort_export_import_roundtrip() {
+        let entries = (1..=3u64)
+            .flat_map(|i| {
+                (1..=3u64).map(move |slot| {
+                    let entry =
+                        StorageEntry { key: H256::from_low_u64_be(slot), value: U256::from(i) };
+                    (Address::from_low_u64_be(i), entry)
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let source = create_test_rw_db();
+        source
+            .update(|tx| {
+                for (address, entry) in entries.iter() {
+                    tx.put::<PlainStorageState>(*address, *entry).unwrap();
+                }
+            })
+            .unwrap();
+
+        let file = tempfile::NamedTempFile::new().unwrap();
+        export::Command {
+            table: Tables::PlainStorageState,
+            output: Some(file.path().to_path_buf()),
+            format: ExportFormat::Raw,
+            from: None,
+            to: None,
+        }
+        .execute(&DbTool::new(source.as_ref(), MAINNET.clone()).unwrap())
+        .unwrap();
+
+        let destination = create_test_rw_db();
+        Command {
+            table: Tables::PlainStorageState,
+            input: file.path().to_path_buf(),
+            format: ExportFormat::Raw,
+            commit_threshold: 2,
+        }
+        .execute(destination.as_ref())
+        .unwrap();
+
+        let imported = destination
+            .view(|tx| {
+                tx.cursor_read::<PlainStorageState>()
+                    .unwrap()
+                    .walk(None)
+                    .unwrap()
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .unwrap()
+            .unwrap();
+        assert_eq!(imported, entries);
+    }
+
+    #[test]
+    fn rejects_unordered_rows() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let mut lines = String::new();
+        for (address, account) in accounts().into_iter().rev() {
+            lines += &serde_json::to_string(&JsonRow { key: address, value: account }).unwrap();
+            lines += "\n";
+        }
+        std::fs::write(file.path(), lines).unwrap();
+
+        let db = create_test_rw_db();
+        let result = Command {
+            table: Tables::PlainAccountState,
+            input: file.path().to_path_buf(),
+            format: ExportFormat::Jsonl,
+            commit_threshold: 100,
+        }
+        .execute(db.as_ref());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn dupsort_row_order() {
+        let key = Address::from_low_u64_be(1);
+        let entry = |slot: u64| {
+            RawValue::<StorageEntry>::new(StorageEntry {
+                key: H256::from_low_u64_be(slot),
+                value: U256::from(1),
+            })
+        };
+        let raw_key = RawKey::<<PlainStorageState as Table>::Key>::new(key);
+
+        let mut order = RowOrder::new(true);
+        order.check(raw_key.raw_key(), entry(1).raw_value()).unwrap();
+        order.check(raw_key.raw_key(), entry(2).raw_value()).unwrap();
+        assert!(order.check(raw_key.raw_key(), entry(2).raw_value()).is_err());
+
+        let mut order = RowOrder::new(false);
+        order.check(raw_key.raw_key(), entry(1).raw_value()).unwrap();
+        assert!(order.check(raw_key.raw_key(), entry(2).raw_value()).is_err());
+    }
+}
@@ -22,7 +22,9 @@ use std::{
 
 mod clear;
 mod diff;
+mod export;
 mod get;
+mod import;
 mod list;
 /// DB List TUI
 mod tui;
@@ -76,6 +78,10 @@ pub enum Subcommands {
     Diff(diff::Command),
     /// Gets the content of a table for the given key
     Get(get::Command),
+    /// Exports the contents of a table to a file
+    Export(export::Command),
+    /// Imports the contents of a table from a file created by `reth db export`
+    Import(import::Command),
     /// Deletes all database entries
     Drop {
         /// Bypasses the interactive confirmation and drops the database directly
@@ -185,6 +191,15 @@ impl Command {
                 let tool = DbTool::new(&db, self.chain.clone())?;
                 command.execute(&tool)?;
             }
+            Subcommands::Export(command) => {
+                let db = open_db_read_only(&db_path, self.db.log_level)?;
+                let tool = DbTool::new(&db, self.chain.clone())?;
+                command.execute(&tool)?;
+            }
+            Subcommands::Import(command) => {
+                let db = open_db(&db_path, self.db.log_level)?;
+                command.execute(&db)?;
+            }
             Subcommands::Drop { force } => {
                 if !force {
                     // Ask for confirmation
@@ -24,7 +24,10 @@ use reth_interfaces::{
 use reth_network::NetworkHandle;
 use reth_network_api::NetworkInfo;
 use reth_primitives::{fs, stage::StageId, BlockHashOrNumber, BlockNumber, ChainSpec, H256};
-use reth_provider::{BlockExecutionWriter, ProviderFactory, StageCheckpointReader};
+use reth_provider::{
+    BlockExecutionWriter, BlockReader, BundleStateWithReceipts, ExecutorFactory, HeaderProvider,
+    ProviderFactory, StageCheckpointReader,
+};
 use reth_stages::{
     sets::DefaultStages,
     stages::{
@@ -36,6 +39,7 @@ use reth_stages::{
 use reth_tasks::TaskExecutor;
 use std::{
     net::{Ipv4Addr, SocketAddr, SocketAddrV4},
+    ops::RangeInclusive,
     path::PathBuf,
     sync::Arc,
 };
@@ -86,6 +90,11 @@ pub struct Command {
     /// Defaults to `1000`.
     #[arg(long, default_value = "1000")]
     pub interval: u64,
+
+    /// Execute every synced range again, sequentially and in parallel, and check that both
+    /// produce the same state changes and receipts.
+    #[arg(long)]
+    pub compare_parallel: bool,
 }
 
 impl Command {
@@ -201,6 +210,56 @@ impl Command {
         }
     }
 
+    /// Executes the blocks of the range again, both sequentially and in parallel, and checks that
+    /// both produce the same state changes and receipts.
+    fn compare_parallel_execution<DB: Database>(
+        &self,
+        factory: &ProviderFactory<DB>,
+        range: RangeInclusive<BlockNumber>,
+    ) -> eyre::Result<()> {
+        let sequential = self.execute_range(factory, range.clone(), false)?;
+        let parallel = self.execute_range(factory, range.clone(), true)?;
+
+        if sequential.receipts() != parallel.receipts() {
+            eyre::bail!(
+                "Receipts of parallel execution differ from sequential execution in blocks {range:?}"
+            )
+        }
+        if sequential.state() != parallel.state() {
+            eyre::bail!(
+                "State of parallel execution differs from sequential execution in blocks {range:?}"
+            )
+        }
+        info!(target: "reth::cli", from = range.start(), to = range.end(), "Parallel execution matches sequential execution");
+        Ok(())
+    }
+
+    /// Executes the blocks of the range on top of the historical state of the block before it.
+    fn execute_range<DB: Database>(
+        &self,
+        factory: &ProviderFactory<DB>,
+        range: RangeInclusive<BlockNumber>,
+        parallel: bool,
+    ) -> eyre::Result<BundleStateWithReceipts> {
+        let provider = factory.provider()?;
+        let executor_factory =
+            reth_revm::Factory::new(self.chain.clone()).with_parallel_execution(parallel);
+        let mut executor = executor_factory
+            .with_state(factory.history_by_block_number(range.start().saturating_sub(1))?);
+
+        for number in range {
+            let block = provider
+                .block_with_senders(number)?
+                .ok_or_else(|| eyre::eyre!("Block {number} not found"))?;
+            let td = provider
+                .header_td_by_number(number)?
+                .ok_or_else(|| eyre::eyre!("Total difficulty of block {number} not found"))?;
+            executor.execute_and_verify_receipt(&block.block, td, Some(block.senders))?;
+        }
+
+        Ok(executor.take_output_state())
+    }
+
     /// Execute `execution-debug` command
     pub async fn execute(self, ctx: CliContext) -> eyre::Result<()> {
         let config = Config::default();
@@ -271,6 +330,11 @@ impl Command {
             let result = pipeline.run_loop().await?;
             trace!(target: "reth::cli", from = next_block, to = target_block, tip = ?target_block_hash, ?result, "Pipeline finished");
 
+            // Execute the synced blocks again, sequentially and in parallel.
+            if self.compare_parallel {
+                self.compare_parallel_execution(&factory, next_block..=target_block)?;
+            }
+
             // Unwind the pipeline without committing.
             {
                 factory
@@ -284,6 +284,11 @@ impl<D> ChainPath<D> {
         self.0.join("reth.toml").into()
     }
 
+    /// Returns the path to the era1 archive directory for this chain.
+    pub fn era1_path(&self) -> PathBuf {
+        self.0.join("era1").into()
+    }
+
     /// Returns the path to the jwtsecret file for this chain.
     pub fn jwt_path(&self) -> PathBuf {
         self.0.join("jwt.hex").into()
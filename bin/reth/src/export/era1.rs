@@ -0,0 +1,137 @@
+//! Command that exports pre-merge history to era1 files.
+use crate::{
+    args::{
+        utils::{chain_spec_value_parser, parse_block_range},
+        DatabaseArgs,
+    },
+    dirs::{DataDirPath, MaybePlatformPath},
+};
+use clap::Parser;
+use eyre::{eyre, WrapErr};
+use reth_db::open_db_read_only;
+use reth_era::{Era1Block, Era1File, MAX_BLOCKS_PER_ERA1};
+use reth_primitives::{BlockBody, BlockNumber, ChainSpec, Receipt};
+use reth_provider::{BlockReader, HeaderProvider, ProviderFactory, ReceiptProvider};
+use std::{
+    fs::{self, File},
+    io::BufWriter,
+    ops::RangeInclusive,
+    path::PathBuf,
+    sync::Arc,
+};
+use tracing::info;
+
+/// `reth export era1` command
+#[derive(Debug, Parser)]
+pub struct Command {
+    /// The path to the data dir for all reth files and subdirectories.
+    ///
+    /// Defaults to the OS-specific data directory:
+    ///
+    /// - Linux: `$XDG_DATA_HOME/reth/` or `$HOME/.local/share/reth/`
+    /// - Windows: `{FOLDERID_RoamingAppData}/reth/`
+    /// - macOS: `$HOME/Library/Application Support/reth/`
+    #[arg(long, value_name = "DATA_DIR", verbatim_doc_comment, default_value_t)]
+    datadir: MaybePlatformPath<DataDirPath>,
+
+    /// The chain this node is running.
+    ///
+    /// Possible values are either a built-in chain or the path to a chain specification file.
+    ///
+    /// Built-in chains:
+    /// - mainnet
+    /// - goerli
+    /// - sepolia
+    #[arg(
+        long,
+        value_name = "CHAIN_OR_PATH",
+        verbatim_doc_comment,
+        default_value = "mainnet",
+        value_parser = chain_spec_value_parser
+    )]
+    chain: Arc<ChainSpec>,
+
+    #[clap(flatten)]
+    db: DatabaseArgs,
+
+    /// The range of blocks to export, as `<start>..<end>` or `<start>..=<end>`.
+    ///
+    /// One file is written per epoch of 8192 blocks touched by the range.
+    #[arg(long, value_name = "RANGE", value_parser = parse_block_range, verbatim_doc_comment)]
+    range: RangeInclusive<BlockNumber>,
+
+    /// The directory to write the era1 files to.
+    ///
+    /// Defaults to the `era1` directory in the data dir.
+    #[arg(long, value_name = "DIR", verbatim_doc_comment)]
+    output_dir: Option<PathBuf>,
+}
+
+impl Command {
+    /// Execute `export era1` command
+    pub async fn execute(self) -> eyre::Result<()> {
+        // era1 files only hold pre-merge history
+        if let Some((paris_block, _)) = self.chain.paris_block_and_final_difficulty {
+            if *self.range.end() >= paris_block {
+                eyre::bail!(
+                    "Era1 files only hold pre-merge blocks, the merge block is {paris_block}"
+                )
+            }
+        }
+
+        // add network name to data dir
+        let data_dir = self.datadir.unwrap_or_chain_default(self.chain.chain);
+        let output_dir = self.output_dir.clone().unwrap_or_else(|| data_dir.era1_path());
+        fs::create_dir_all(&output_dir)?;
+
+        let db_path = data_dir.db_path();
+        info!(target: "reth::cli", path = ?db_path, "Opening database");
+        let db = open_db_read_only(&db_path, self.db.log_level)?;
+        let factory = ProviderFactory::new(db, self.chain.clone());
+        let provider = factory.provider()?;
+
+        let network = self.chain.chain.to_string();
+        let mut start = *self.range.start();
+        while start <= *self.range.end() {
+            let epoch = start / MAX_BLOCKS_PER_ERA1 as u64;
+            let end = ((epoch + 1) * MAX_BLOCKS_PER_ERA1 as u64 - 1).min(*self.range.end());
+
+            let mut blocks = Vec::with_capacity((end - start + 1) as usize);
+            for number in start..=end {
+                let block = provider
+                    .block(number.into())?
+                    .ok_or_else(|| eyre!("Block {number} not found"))?;
+                let total_difficulty = provider
+                    .header_td_by_number(number)?
+                    .ok_or_else(|| eyre!("Total difficulty of block {number} not found"))?;
+                let receipts = provider
+                    .receipts_by_block(number.into())?
+                    .filter(|receipts| receipts.len() == block.body.len())
+                    .ok_or_else(|| eyre!("Receipts of block {number} not found or pruned"))?;
+
+                let block = block.seal_slow();
+                blocks.push(Era1Block {
+                    header: block.header,
+                    body: BlockBody {
+                        transactions: block.body,
+                        ommers: block.ommers,
+                        withdrawals: block.withdrawals,
+                    },
+                    receipts: Some(receipts.into_iter().map(Receipt::with_bloom).collect()),
+                    total_difficulty,
+                });
+            }
+
+            let file = Era1File::new(blocks)?;
+            let path = output_dir.join(file.file_name(&network));
+            let writer = BufWriter::new(File::create(&path)?);
+            file.write(writer)
+                .wrap_err_with(|| format!("Could not write era1 file {}", path.display()))?;
+            info!(target: "reth::cli", ?path, start, end, accumulator = ?file.accumulator, "Exported era1 file");
+
+            start = end + 1;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,27 @@
+//! `reth export` command. Exports chain data from the database.
+use clap::{Parser, Subcommand};
+
+mod era1;
+
+/// `reth export` command
+#[derive(Debug, Parser)]
+pub struct Command {
+    #[clap(subcommand)]
+    command: Subcommands,
+}
+
+/// `reth export` subcommands
+#[derive(Subcommand, Debug)]
+pub enum Subcommands {
+    /// Export pre-merge blocks, receipts and total difficulties to era1 files.
+    Era1(era1::Command),
+}
+
+impl Command {
+    /// Execute `export` command
+    pub async fn execute(self) -> eyre::Result<()> {
+        match self.command {
+            Subcommands::Era1(command) => command.execute().await,
+        }
+    }
+}
@@ -1,16 +1,20 @@
 //! Reth genesis initialization utility functions.
 use reth_db::{
-    cursor::DbCursorRO,
+    cursor::{DbCursorRO, DbDupCursorRW},
     database::{Database, DatabaseGAT},
     tables,
     transaction::{DbTx, DbTxMut},
 };
-use reth_primitives::{stage::StageId, Account, Bytecode, ChainSpec, StorageEntry, H256, U256};
+use reth_primitives::{
+    stage::StageId, Account, Address, Bytecode, Bytes, ChainSpec, SealedHeader, StorageEntry, H256,
+    U256,
+};
 use reth_provider::{
     bundle_state::{BundleStateInit, RevertsInit},
     BundleStateWithReceipts, DatabaseProviderRW, HashingWriter, HistoryWriter, OriginalValuesKnown,
     ProviderFactory,
 };
+use serde::Deserialize;
 use std::{
     collections::{BTreeMap, HashMap},
     sync::Arc,
@@ -30,6 +34,10 @@ pub enum InitDatabaseError {
         database_hash: H256,
     },
 
+    /// An account appears more than once in a state dump.
+    #[error("Account {0:?} appears more than once in the state dump")]
+    DuplicateStateDumpAccount(Address),
+
     /// Low-level database error.
     #[error(transparent)]
     DBError(#[from] reth_db::DatabaseError),
@@ -213,6 +221,88 @@ pub fn insert_genesis_header<DB: Database>(
     Ok(())
 }
 
+/// A single account entry of a state dump, as consumed by `reth init-state`.
+///
+/// Storage is keyed by the plain (unhashed) slot.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StateDumpAccount {
+    /// The address of the account.
+    pub address: Address,
+    /// The balance of the account.
+    #[serde(default)]
+    pub balance: U256,
+    /// The nonce of the account.
+    #[serde(default)]
+    pub nonce: u64,
+    /// The code of the account, if any.
+    #[serde(default)]
+    pub code: Option<Bytes>,
+    /// The storage of the account.
+    #[serde(default)]
+    pub storage: BTreeMap<H256, U256>,
+}
+
+/// Inserts the accounts of a state dump into the plain state and bytecode tables.
+///
+/// Zero storage values are skipped. Returns the number of written storage entries.
+///
+/// The plain state tables are expected to only contain previously inserted accounts of the same
+/// dump, an account that is already present is rejected with
+/// [InitDatabaseError::DuplicateStateDumpAccount], as its storage would be duplicated.
+pub fn insert_state_dump_accounts<DB: Database>(
+    tx: &<DB as DatabaseGAT<'_>>::TXMut,
+    accounts: impl IntoIterator<Item = StateDumpAccount>,
+) -> Result<usize, InitDatabaseError> {
+    let mut storage_cursor = tx.cursor_dup_write::<tables::PlainStorageState>()?;
+    let mut written_slots = 0;
+
+    for account in accounts {
+        if tx.get::<tables::PlainAccountState>(account.address)?.is_some() {
+            return Err(InitDatabaseError::DuplicateStateDumpAccount(account.address))
+        }
+
+        let bytecode_hash = match account.code {
+            Some(code) if !code.is_empty() => {
+                let bytecode = Bytecode::new_raw(code.0);
+                let hash = bytecode.hash_slow();
+                tx.put::<tables::Bytecodes>(hash, bytecode)?;
+                Some(hash)
+            }
+            _ => None,
+        };
+
+        tx.put::<tables::PlainAccountState>(
+            account.address,
+            Account { nonce: account.nonce, balance: account.balance, bytecode_hash },
+        )?;
+
+        for (key, value) in account.storage.into_iter().filter(|(_, value)| *value != U256::ZERO) {
+            storage_cursor.upsert(account.address, StorageEntry { key, value })?;
+            written_slots += 1;
+        }
+    }
+
+    Ok(written_slots)
+}
+
+/// Inserts the header of the block a state dump was taken at, making it the canonical head.
+pub fn insert_state_dump_header<DB: Database>(
+    tx: &<DB as DatabaseGAT<'_>>::TXMut,
+    header: SealedHeader,
+    total_difficulty: U256,
+) -> Result<(), InitDatabaseError> {
+    let number = header.number;
+
+    tx.put::<tables::CanonicalHeaders>(number, header.hash)?;
+    tx.put::<tables::HeaderNumbers>(header.hash, number)?;
+    tx.put::<tables::BlockBodyIndices>(number, Default::default())?;
+    tx.put::<tables::HeaderTD>(number, total_difficulty.into())?;
+    tx.put::<tables::Headers>(number, header.header)?;
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -224,8 +314,8 @@ mod tests {
         DatabaseEnv,
     };
     use reth_primitives::{
-        Address, Chain, ForkTimestamps, Genesis, GenesisAccount, IntegerList, GOERLI,
-        GOERLI_GENESIS, MAINNET, MAINNET_GENESIS, SEPOLIA, SEPOLIA_GENESIS,
+        Chain, ForkTimestamps, Genesis, GenesisAccount, IntegerList, GOERLI, GOERLI_GENESIS,
+        MAINNET, MAINNET_GENESIS, SEPOLIA, SEPOLIA_GENESIS,
     };
     use std::collections::HashMap;
 
@@ -338,4 +428,44 @@ mod tests {
             )],
         );
     }
+
+    #[test]
+    fn insert_state_dump() {
+        let address = Address::from_low_u64_be(1);
+        let line = r#"{"address":"0x0000000000000000000000000000000000000001","balance":"0x10","nonce":2,"code":"0x6000","storage":{"0x0000000000000000000000000000000000000000000000000000000000000001":"0x1","0x0000000000000000000000000000000000000000000000000000000000000002":"0x0"}}"#;
+        let account: StateDumpAccount = serde_json::from_str(line).unwrap();
+
+        let db = create_test_rw_db();
+        let tx = db.tx_mut().unwrap();
+        let written =
+            insert_state_dump_accounts::<Arc<DatabaseEnv>>(&tx, [account.clone()]).unwrap();
+        tx.commit().unwrap();
+
+        // the zero slot is skipped
+        assert_eq!(written, 1);
+
+        let tx = db.tx().unwrap();
+        let code = Bytecode::new_raw(Bytes::from(vec![0x60, 0x00]).0);
+        assert_eq!(
+            tx.get::<tables::PlainAccountState>(address).unwrap(),
+            Some(Account {
+                nonce: 2,
+                balance: U256::from(16),
+                bytecode_hash: Some(code.hash_slow())
+            })
+        );
+        assert!(tx.get::<tables::Bytecodes>(code.hash_slow()).unwrap().is_some());
+        assert_eq!(
+            collect_table_entries::<Arc<DatabaseEnv>, tables::PlainStorageState>(&tx).unwrap(),
+            vec![(address, StorageEntry::new(H256::from_low_u64_be(1), U256::from(1)))]
+        );
+        drop(tx);
+
+        // the same account in a later batch would duplicate its storage
+        let tx = db.tx_mut().unwrap();
+        assert_eq!(
+            insert_state_dump_accounts::<Arc<DatabaseEnv>>(&tx, [account]),
+            Err(InitDatabaseError::DuplicateStateDumpAccount(address))
+        );
+    }
 }
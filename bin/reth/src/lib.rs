@@ -30,10 +30,12 @@ pub mod config;
 pub mod db;
 pub mod debug_cmd;
 pub mod dirs;
+pub mod export;
 pub mod init;
 pub mod node;
 pub mod p2p;
 pub mod prometheus_exporter;
+pub mod prune;
 pub mod recover;
 pub mod runner;
 pub mod stage;
@@ -30,7 +30,12 @@ use reth_blockchain_tree::{
     config::BlockchainTreeConfig, externals::TreeExternals, BlockchainTree, ShareableBlockchainTree,
 };
 use reth_config::{config::PruneConfig, Config};
-use reth_db::{database::Database, init_db, DatabaseEnv};
+use reth_db::{
+    database::Database,
+    init_db,
+    mdbx::{Env, EnvironmentKind},
+    open_db_read_only, DatabaseEnv,
+};
 use reth_discv4::DEFAULT_DISCOVERY_PORT;
 use reth_downloaders::{
     bodies::bodies::BodiesDownloaderBuilder,
@@ -44,8 +49,11 @@ use reth_interfaces::{
         headers::{client::HeadersClient, downloader::HeaderDownloader},
     },
 };
-use reth_network::{error::NetworkError, NetworkConfig, NetworkHandle, NetworkManager};
-use reth_network_api::NetworkInfo;
+use reth_network::{
+    error::NetworkError, peers::PersistedPeersState, NetworkConfig, NetworkHandle, NetworkManager,
+    SnapFetchClient,
+};
+use reth_network_api::{noop::NoopNetwork, NetworkInfo};
 use reth_primitives::{
     constants::eip4844::{LoadKzgSettingsError, MAINNET_KZG_TRUSTED_SETUP},
     kzg::KzgSettings,
@@ -58,19 +66,21 @@ use reth_provider::{
 };
 use reth_revm::Factory;
 use reth_revm_inspectors::stack::Hook;
+use reth_rpc_builder::{constants, RethRpcModule, RpcModuleSelection};
 use reth_rpc_engine_api::EngineApi;
 use reth_stages::{
     prelude::*,
     stages::{
         AccountHashingStage, ExecutionStage, ExecutionStageThresholds, HeaderSyncMode,
         IndexAccountHistoryStage, IndexStorageHistoryStage, MerkleStage, SenderRecoveryStage,
-        StorageHashingStage, TotalDifficultyStage, TransactionLookupStage,
+        StateSyncStage, StorageHashingStage, TotalDifficultyStage, TransactionLookupStage,
     },
     MetricEventsSender, MetricsListener,
 };
 use reth_tasks::TaskExecutor;
 use reth_transaction_pool::{
-    blobstore::InMemoryBlobStore, TransactionPool, TransactionValidationTaskExecutor,
+    blobstore::InMemoryBlobStore, noop::NoopTransactionPool, TransactionPool,
+    TransactionValidationTaskExecutor,
 };
 use secp256k1::SecretKey;
 use std::{
@@ -83,6 +93,7 @@ use tracing::*;
 
 pub mod cl_events;
 pub mod events;
+pub mod read_only;
 
 /// Start the node
 #[derive(Debug, Parser)]
@@ -179,6 +190,23 @@ pub struct NodeCommand<Ext: RethCliExt = ()> {
     #[clap(flatten)]
     pub pruning: PruningArgs,
 
+    /// Serve RPC from the database at the given path, opened read-only, instead of running a
+    /// full node.
+    ///
+    /// The database has to be written by another node running on the same machine. New canonical
+    /// blocks are picked up by polling the database. Only the `eth`, `debug` and `trace`
+    /// namespaces are enabled by default, and there is no transaction pool or network.
+    #[arg(long, value_name = "PATH", help_heading = "Database")]
+    pub read_only_db: Option<PathBuf>,
+
+    /// Download the state of a recent block from the peers with the `snap` protocol, instead of
+    /// executing all blocks up to it.
+    ///
+    /// This only takes effect on the first sync, before any block is executed. The state history
+    /// before that block is not available.
+    #[arg(long)]
+    pub snap_sync: bool,
+
     /// Additional cli arguments
     #[clap(flatten)]
     pub ext: Ext::Node,
@@ -202,6 +230,8 @@ impl<Ext: RethCliExt> NodeCommand<Ext> {
             db,
             dev,
             pruning,
+            read_only_db,
+            snap_sync,
             ..
         } = self;
         NodeCommand {
@@ -219,6 +249,8 @@ impl<Ext: RethCliExt> NodeCommand<Ext> {
             db,
             dev,
             pruning,
+            read_only_db,
+            snap_sync,
             ext,
         }
     }
@@ -231,6 +263,10 @@ impl<Ext: RethCliExt> NodeCommand<Ext> {
         // Does not do anything on windows.
         raise_fd_limit();
 
+        if let Some(db_path) = self.read_only_db.clone() {
+            return self.execute_read_only(ctx, db_path).await
+        }
+
         // add network name to data dir
         let data_dir = self.datadir.unwrap_or_chain_default(self.chain.chain);
         let config_path = self.config.clone().unwrap_or(data_dir.config_path());
@@ -265,6 +301,7 @@ impl<Ext: RethCliExt> NodeCommand<Ext> {
         debug!(target: "reth::cli", "Spawning metrics listener task");
         let (metrics_tx, metrics_rx) = unbounded_channel();
         let metrics_listener = MetricsListener::new(metrics_rx);
+        let sync_progress = metrics_listener.progress_tracker();
         ctx.task_executor.spawn_critical("metrics listener task", metrics_listener);
 
         let prune_config =
@@ -338,7 +375,7 @@ impl<Ext: RethCliExt> NodeCommand<Ext> {
             secret_key,
             default_peers_path.clone(),
         );
-        let network = self
+        let (network, snap_client) = self
             .start_network(
                 network_config,
                 &ctx.task_executor,
@@ -405,6 +442,7 @@ impl<Ext: RethCliExt> NodeCommand<Ext> {
                     metrics_tx,
                     prune_config.clone(),
                     max_block,
+                    None,
                 )
                 .await?;
 
@@ -425,6 +463,7 @@ impl<Ext: RethCliExt> NodeCommand<Ext> {
                     metrics_tx,
                     prune_config.clone(),
                     max_block,
+                    self.snap_sync.then_some(snap_client),
                 )
                 .await?;
 
@@ -520,6 +559,7 @@ impl<Ext: RethCliExt> NodeCommand<Ext> {
                 blockchain_tree,
                 engine_api,
                 jwt_secret,
+                sync_progress,
                 &mut self.ext,
             )
             .await?;
@@ -545,6 +585,81 @@ impl<Ext: RethCliExt> NodeCommand<Ext> {
         }
     }
 
+    /// Serves RPC from a database that is written by another node, see
+    /// [NodeCommand::read_only_db].
+    async fn execute_read_only(mut self, ctx: CliContext, db_path: PathBuf) -> eyre::Result<()> {
+        info!(target: "reth::cli", path = ?db_path, "Opening database in read-only mode");
+        let db = Arc::new(open_db_read_only(&db_path, self.db.log_level)?);
+        info!(target: "reth::cli", "Database opened");
+
+        self.start_metrics_endpoint(Arc::clone(&db)).await?;
+
+        info!(target: "reth::cli", "{}", DisplayHardforks::from(self.chain.hardforks().clone()));
+
+        // The tree never receives any blocks, it only provides the canonical state notifications
+        // to the RPC.
+        let tree_externals = TreeExternals::new(
+            db.clone(),
+            Arc::new(BeaconConsensus::new(Arc::clone(&self.chain))),
+            Factory::new(self.chain.clone()),
+            Arc::clone(&self.chain),
+        );
+        let tree_config = BlockchainTreeConfig::default();
+        let (canon_state_notification_sender, _receiver) =
+            tokio::sync::broadcast::channel(tree_config.max_reorg_depth() as usize * 2);
+        let blockchain_tree = ShareableBlockchainTree::new(BlockchainTree::new(
+            tree_externals,
+            canon_state_notification_sender.clone(),
+            tree_config,
+            None,
+        )?);
+
+        let factory = ProviderFactory::new(Arc::clone(&db), Arc::clone(&self.chain));
+        let blockchain_db = BlockchainProvider::new(factory.clone(), blockchain_tree.clone())?;
+
+        let watcher = read_only::CanonStateWatcher::new(
+            factory,
+            canon_state_notification_sender,
+            tree_config.max_reorg_depth(),
+        );
+        ctx.task_executor
+            .spawn_critical("canonical state watcher", watcher.run(blockchain_db.clone()));
+        debug!(target: "reth::cli", "Spawned canonical state watcher");
+
+        // Without a transaction pool and network, only the read-only namespaces are useful.
+        let read_only_modules = || {
+            RpcModuleSelection::from([
+                RethRpcModule::Eth,
+                RethRpcModule::Debug,
+                RethRpcModule::Trace,
+            ])
+        };
+        if !self.rpc.http && !self.rpc.ws {
+            self.rpc.http = true;
+        }
+        self.rpc.http_api.get_or_insert_with(read_only_modules);
+        self.rpc.ws_api.get_or_insert_with(read_only_modules);
+        // The default IPC endpoint is taken by the node writing the database.
+        if self.rpc.ipcpath == constants::DEFAULT_IPC_ENDPOINT {
+            self.rpc.ipcdisable = true;
+        }
+        self.adjust_instance_ports();
+
+        let _rpc_server = self
+            .rpc
+            .start_rpc_server(
+                blockchain_db,
+                NoopTransactionPool::default(),
+                NoopNetwork::default(),
+                ctx.task_executor.clone(),
+                blockchain_tree,
+            )
+            .await?;
+        info!(target: "reth::cli", "Serving RPC from the read-only database");
+
+        futures::future::pending().await
+    }
+
     /// Constructs a [Pipeline] that's wired to the network
     #[allow(clippy::too_many_arguments)]
     async fn build_networked_pipeline<DB, Client>(
@@ -557,6 +672,7 @@ impl<Ext: RethCliExt> NodeCommand<Ext> {
         metrics_tx: MetricEventsSender,
         prune_config: Option<PruneConfig>,
         max_block: Option<BlockNumber>,
+        snap_client: Option<SnapFetchClient>,
     ) -> eyre::Result<Pipeline<DB>>
     where
         DB: Database + Unpin + Clone + 'static,
@@ -582,6 +698,7 @@ impl<Ext: RethCliExt> NodeCommand<Ext> {
                 self.debug.continuous,
                 metrics_tx,
                 prune_config,
+                snap_client,
             )
             .await?;
 
@@ -615,9 +732,17 @@ impl<Ext: RethCliExt> NodeCommand<Ext> {
                 config.peers.trusted_nodes.insert(*peer);
             });
         }
+
+        if !self.network.reserved_peers.is_empty() {
+            info!(target: "reth::cli", "Adding reserved nodes");
+            config.peers.reserved_nodes.extend(self.network.reserved_peers.iter().copied());
+        }
     }
 
-    async fn start_metrics_endpoint(&self, db: Arc<DatabaseEnv>) -> eyre::Result<()> {
+    async fn start_metrics_endpoint<E: EnvironmentKind>(
+        &self,
+        db: Arc<Env<E>>,
+    ) -> eyre::Result<()> {
         if let Some(listen_addr) = self.metrics {
             info!(target: "reth::cli", addr = %listen_addr, "Starting metrics endpoint");
             prometheus_exporter::initialize(listen_addr, db, metrics_process::Collector::default())
@@ -628,34 +753,36 @@ impl<Ext: RethCliExt> NodeCommand<Ext> {
     }
 
     /// Spawns the configured network and associated tasks and returns the [NetworkHandle] connected
-    /// to that network.
-    async fn start_network<C, Pool>(
+    /// to that network, and the [SnapFetchClient] to download the state with.
+    async fn start_network<DB, Pool>(
         &self,
-        config: NetworkConfig<C>,
+        config: NetworkConfig<ProviderFactory<DB>>,
         task_executor: &TaskExecutor,
         pool: Pool,
         default_peers_path: PathBuf,
-    ) -> Result<NetworkHandle, NetworkError>
+    ) -> Result<(NetworkHandle, SnapFetchClient), NetworkError>
     where
-        C: BlockReader + HeaderProvider + Clone + Unpin + 'static,
+        DB: Database + Clone + Unpin + 'static,
         Pool: TransactionPool + Unpin + 'static,
     {
         let client = config.client.clone();
-        let (handle, network, txpool, eth) = NetworkManager::builder(config)
+        let mut builder = NetworkManager::builder(config)
             .await?
             .transactions(pool)
-            .request_handler(client)
-            .split_with_handle();
+            .request_handler(client.clone());
+        let (snap, snap_client) = builder.snap(client);
+        let (handle, network, txpool, eth) = builder.split_with_handle();
 
         task_executor.spawn_critical("p2p txpool", txpool);
         task_executor.spawn_critical("p2p eth request handler", eth);
+        task_executor.spawn_critical("p2p snap request handler", snap);
 
         let known_peers_file = self.network.persistent_peers_file(default_peers_path);
         task_executor.spawn_critical_with_signal("p2p network task", |shutdown| {
             run_network_until_shutdown(shutdown, network, known_peers_file)
         });
 
-        Ok(handle)
+        Ok((handle, snap_client))
     }
 
     fn lookup_head(&self, db: Arc<DatabaseEnv>) -> Result<Head, reth_interfaces::Error> {
@@ -784,6 +911,7 @@ impl<Ext: RethCliExt> NodeCommand<Ext> {
         continuous: bool,
         metrics_tx: MetricEventsSender,
         prune_config: Option<PruneConfig>,
+        snap_client: Option<SnapFetchClient>,
     ) -> eyre::Result<Pipeline<DB>>
     where
         DB: Database + Clone + 'static,
@@ -801,7 +929,8 @@ impl<Ext: RethCliExt> NodeCommand<Ext> {
 
         let (tip_tx, tip_rx) = watch::channel(H256::zero());
         use reth_revm_inspectors::stack::InspectorStackConfig;
-        let factory = reth_revm::Factory::new(self.chain.clone());
+        let factory = reth_revm::Factory::new(self.chain.clone())
+            .with_parallel_execution(stage_config.execution.parallel_execution);
 
         let stack_config = InspectorStackConfig {
             use_printer_tracer: self.debug.print_inspector,
@@ -822,63 +951,72 @@ impl<Ext: RethCliExt> NodeCommand<Ext> {
 
         let header_mode =
             if continuous { HeaderSyncMode::Continuous } else { HeaderSyncMode::Tip(tip_rx) };
+        let mut stages = DefaultStages::new(
+            header_mode,
+            Arc::clone(&consensus),
+            header_downloader,
+            body_downloader,
+            factory.clone(),
+        )
+        .set(
+            TotalDifficultyStage::new(consensus)
+                .with_commit_threshold(stage_config.total_difficulty.commit_threshold),
+        )
+        .set(SenderRecoveryStage {
+            commit_threshold: stage_config.sender_recovery.commit_threshold,
+        })
+        .set(
+            ExecutionStage::new(
+                factory,
+                ExecutionStageThresholds {
+                    max_blocks: stage_config.execution.max_blocks,
+                    max_changes: stage_config.execution.max_changes,
+                    max_cumulative_gas: stage_config.execution.max_cumulative_gas,
+                },
+                stage_config
+                    .merkle
+                    .clean_threshold
+                    .max(stage_config.account_hashing.clean_threshold)
+                    .max(stage_config.storage_hashing.clean_threshold),
+                prune_modes.clone(),
+            )
+            .with_state_prefetch(stage_config.execution.prefetch_state)
+            .with_metrics_tx(metrics_tx.clone()),
+        )
+        .set(AccountHashingStage::new(
+            stage_config.account_hashing.clean_threshold,
+            stage_config.account_hashing.commit_threshold,
+        ))
+        .set(StorageHashingStage::new(
+            stage_config.storage_hashing.clean_threshold,
+            stage_config.storage_hashing.commit_threshold,
+        ))
+        .set(
+            MerkleStage::new_execution(stage_config.merkle.clean_threshold)
+                .with_parallel_storage_roots(stage_config.merkle.parallel_storage_roots),
+        )
+        .set(TransactionLookupStage::new(
+            stage_config.transaction_lookup.commit_threshold,
+            prune_modes.clone(),
+        ))
+        .set(IndexAccountHistoryStage::new(
+            stage_config.index_account_history.commit_threshold,
+            prune_modes.clone(),
+        ))
+        .set(IndexStorageHistoryStage::new(
+            stage_config.index_storage_history.commit_threshold,
+            prune_modes,
+        ));
+
+        // Download the state of the pivot instead of executing the blocks up to it
+        if let Some(client) = snap_client {
+            stages = stages.add_before(StateSyncStage::new(client), StageId::Execution);
+        }
+
         let pipeline = builder
             .with_tip_sender(tip_tx)
-            .with_metrics_tx(metrics_tx.clone())
-            .add_stages(
-                DefaultStages::new(
-                    header_mode,
-                    Arc::clone(&consensus),
-                    header_downloader,
-                    body_downloader,
-                    factory.clone(),
-                )
-                .set(
-                    TotalDifficultyStage::new(consensus)
-                        .with_commit_threshold(stage_config.total_difficulty.commit_threshold),
-                )
-                .set(SenderRecoveryStage {
-                    commit_threshold: stage_config.sender_recovery.commit_threshold,
-                })
-                .set(
-                    ExecutionStage::new(
-                        factory,
-                        ExecutionStageThresholds {
-                            max_blocks: stage_config.execution.max_blocks,
-                            max_changes: stage_config.execution.max_changes,
-                            max_cumulative_gas: stage_config.execution.max_cumulative_gas,
-                        },
-                        stage_config
-                            .merkle
-                            .clean_threshold
-                            .max(stage_config.account_hashing.clean_threshold)
-                            .max(stage_config.storage_hashing.clean_threshold),
-                        prune_modes.clone(),
-                    )
-                    .with_metrics_tx(metrics_tx),
-                )
-                .set(AccountHashingStage::new(
-                    stage_config.account_hashing.clean_threshold,
-                    stage_config.account_hashing.commit_threshold,
-                ))
-                .set(StorageHashingStage::new(
-                    stage_config.storage_hashing.clean_threshold,
-                    stage_config.storage_hashing.commit_threshold,
-                ))
-                .set(MerkleStage::new_execution(stage_config.merkle.clean_threshold))
-                .set(TransactionLookupStage::new(
-                    stage_config.transaction_lookup.commit_threshold,
-                    prune_modes.clone(),
-                ))
-                .set(IndexAccountHistoryStage::new(
-                    stage_config.index_account_history.commit_threshold,
-                    prune_modes.clone(),
-                ))
-                .set(IndexStorageHistoryStage::new(
-                    stage_config.index_storage_history.commit_threshold,
-                    prune_modes,
-                )),
-            )
+            .with_metrics_tx(metrics_tx)
+            .add_stages(stages)
             .build(db, self.chain.clone());
 
         Ok(pipeline)
@@ -896,7 +1034,8 @@ impl<Ext: RethCliExt> NodeCommand<Ext> {
 }
 
 /// Drives the [NetworkManager] future until a [Shutdown](reth_tasks::shutdown::Shutdown) signal is
-/// received. If configured, this writes known peers to `persistent_peers_file` afterwards.
+/// received. If configured, this writes known peers to `persistent_peers_file` and their
+/// reputations, backoffs and bans to the sibling [`PersistedPeersState::file_path`] afterwards.
 async fn run_network_until_shutdown<C>(
     shutdown: reth_tasks::shutdown::Shutdown,
     network: NetworkManager<C>,
@@ -925,6 +1064,16 @@ async fn run_network_until_shutdown<C>(
                 }
             }
         }
+
+        let state_file = PersistedPeersState::file_path(&file_path);
+        match network.persisted_peers_state().write_to_file(&state_file) {
+            Ok(_) => {
+                info!(target: "reth::cli", peers_state_file=?state_file, "Wrote network peers state to file");
+            }
+            Err(err) => {
+                warn!(target: "reth::cli", ?err, peers_state_file=?state_file, "Failed to write network peers state to file");
+            }
+        }
     }
 }
 
@@ -0,0 +1,351 @@
+//! Support for serving RPC from the database of another node, opened read-only.
+//!
+//! The node writing the database doesn't share its canonical state notifications with other
+//! processes, so they are synthesized from the changes of the database instead.
+use reth_db::{database::Database, transaction::DbTx};
+use reth_interfaces::Result;
+use reth_primitives::{stage::StageId, BlockNumber, SealedBlockWithSenders, SealedHeader};
+use reth_provider::{
+    BlockHashReader, BlockReader, BundleStateWithReceipts, CanonChainTracker,
+    CanonStateNotification, CanonStateNotificationSender, Chain, DatabaseProvider, HeaderProvider,
+    ProviderError, ProviderFactory, ReceiptProvider, StageCheckpointReader, TransactionsProvider,
+};
+use std::{collections::BTreeMap, ops::RangeInclusive, sync::Arc, time::Duration};
+use tracing::{debug, error, trace};
+
+/// Interval between two checks of the database for new canonical blocks.
+pub const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Synthesizes [CanonStateNotification]s for the blocks that another node commits to the
+/// database.
+///
+/// The database is polled for the [StageId::Finish] checkpoint, which is advanced by both the
+/// pipeline and the blockchain tree once a block is fully processed. Reorgs are detected by
+/// comparing the canonical hashes in the database with the recently notified blocks.
+///
+/// The notified chains contain the blocks with their execution outcome, which is recreated from
+/// the changesets, the plain state and the receipts in the database. Notifications always commit
+/// at least one block, so blocks that are unwound without new blocks replacing them are only
+/// reported as reverted once new blocks are committed.
+#[derive(Debug)]
+pub struct CanonStateWatcher<DB> {
+    factory: ProviderFactory<DB>,
+    sender: CanonStateNotificationSender,
+    /// Recently notified canonical blocks with their execution outcome, by block number.
+    recent: BTreeMap<BlockNumber, (SealedBlockWithSenders, BundleStateWithReceipts)>,
+    /// Notified blocks that were unwound, but not reported as reverted yet.
+    unwound: BTreeMap<BlockNumber, (SealedBlockWithSenders, BundleStateWithReceipts)>,
+    /// Maximum number of blocks kept in [Self::recent] and notified at once.
+    ///
+    /// If the database advances by more blocks between two polls, e.g. during the initial sync,
+    /// no notification is sent and the watcher starts over from the new tip.
+    max_blocks: u64,
+}
+
+impl<DB: Database> CanonStateWatcher<DB> {
+    /// Creates a new watcher that sends the notifications to the given channel.
+    pub fn new(
+        factory: ProviderFactory<DB>,
+        sender: CanonStateNotificationSender,
+        max_blocks: u64,
+    ) -> Self {
+        Self {
+            factory,
+            sender,
+            recent: BTreeMap::new(),
+            unwound: BTreeMap::new(),
+            max_blocks: max_blocks.max(1),
+        }
+    }
+
+    /// Checks the database for canonical chain changes since the last call, and sends the
+    /// notification for them, if any.
+    ///
+    /// Returns the header of the new canonical tip if it changed.
+    pub fn poll(&mut self) -> Result<Option<SealedHeader>> {
+        let provider = self.factory.provider()?;
+        let tip = provider
+            .get_stage_checkpoint(StageId::Finish)?
+            .map(|checkpoint| checkpoint.block_number)
+            .unwrap_or_default();
+        let Some(tip_hash) = provider.block_hash(tip)? else {
+            trace!(target: "reth::cli", %tip, "No canonical blocks in the database yet");
+            return Ok(None)
+        };
+
+        if self.recent.get(&tip).map_or(false, |(block, _)| block.hash == tip_hash) &&
+            self.recent.last_key_value().map_or(false, |(number, _)| *number == tip)
+        {
+            trace!(target: "reth::cli", %tip, "No new canonical blocks");
+            return Ok(None)
+        }
+
+        // Find the highest notified block that is still canonical.
+        let mut fork_block = None;
+        for (number, (block, _)) in self.recent.iter().rev() {
+            if *number <= tip && provider.block_hash(*number)? == Some(block.hash) {
+                fork_block = Some(*number);
+                break
+            }
+        }
+
+        let fork_block = match fork_block {
+            Some(fork_block) if tip - fork_block <= self.max_blocks => fork_block,
+            _ => {
+                // Either the first poll, or the database moved too far since the last one, so
+                // there are no previous blocks to notify the changes against.
+                debug!(target: "reth::cli", %tip, "Resetting canonical state watcher");
+                self.recent = self.blocks_with_state(&provider, tip..=tip)?.into_iter().collect();
+                self.unwound.clear();
+                return provider
+                    .sealed_header(tip)?
+                    .map(Some)
+                    .ok_or_else(|| ProviderError::HeaderNotFound(tip.into()).into())
+            }
+        };
+
+        let mut reverted = self.recent.split_off(&(fork_block + 1));
+        reverted.append(&mut self.unwound);
+        let committed = self.blocks_with_state(&provider, fork_block + 1..=tip)?;
+        let header = provider
+            .sealed_header(tip)?
+            .ok_or_else(|| ProviderError::HeaderNotFound(tip.into()))?;
+        drop(provider);
+
+        if committed.is_empty() {
+            debug!(target: "reth::cli", %tip, "Canonical blocks were unwound");
+            self.unwound = reverted;
+            return Ok(Some(header))
+        }
+
+        let new = Arc::new(Self::chain(committed.iter().map(|(_, entry)| entry.clone())));
+        let notification = if reverted.is_empty() {
+            CanonStateNotification::Commit { new }
+        } else {
+            let old = Arc::new(Self::chain(reverted.into_values()));
+            CanonStateNotification::Reorg { old, new }
+        };
+
+        self.recent.extend(committed);
+        while self.recent.len() as u64 > self.max_blocks {
+            self.recent.pop_first();
+        }
+
+        debug!(target: "reth::cli", %tip, %fork_block, "New canonical blocks in the database");
+
+        // Sending fails only if there are no subscribers, which is fine.
+        let _ = self.sender.send(notification);
+
+        Ok(Some(header))
+    }
+
+    /// Polls the database every [POLL_INTERVAL] and updates the canonical head of the tracker.
+    pub async fn run<Tracker: CanonChainTracker>(mut self, tracker: Tracker) {
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+            match self.poll() {
+                Ok(Some(header)) => tracker.set_canonical_head(header),
+                Ok(None) => {}
+                Err(error) => {
+                    error!(target: "reth::cli", %error, "Failed to poll the database")
+                }
+            }
+        }
+    }
+
+    /// Reads the blocks in the range with the execution outcome of every block.
+    fn blocks_with_state<'a, TX: DbTx<'a>>(
+        &self,
+        provider: &DatabaseProvider<'a, TX>,
+        range: RangeInclusive<BlockNumber>,
+    ) -> Result<Vec<(BlockNumber, (SealedBlockWithSenders, BundleStateWithReceipts))>> {
+        if range.is_empty() {
+            return Ok(Vec::new())
+        }
+
+        // The plain state is at the latest executed block, which is ahead of the finished blocks
+        // while the pipeline runs the stages after the execution.
+        let executed = provider
+            .get_stage_checkpoint(StageId::Execution)?
+            .map(|checkpoint| checkpoint.block_number)
+            .unwrap_or_default();
+        let mut state = if executed >= *range.end() && executed - range.end() <= self.max_blocks {
+            let mut state = provider.block_execution_state(*range.start()..=executed)?;
+            state.revert_to(*range.end());
+            Some(state)
+        } else {
+            None
+        };
+
+        let mut blocks = Vec::new();
+        for number in range {
+            let block = Self::block_with_senders(provider, number)?;
+            let block_state = match state.as_mut().and_then(|state| state.split_at(number)) {
+                Some(block_state) => block_state,
+                None => {
+                    // Only the receipts are available if the plain state is too far ahead.
+                    let receipts = provider
+                        .receipts_by_block(number.into())?
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(Some)
+                        .collect();
+                    BundleStateWithReceipts::new(Default::default(), vec![receipts], number)
+                }
+            };
+            blocks.push((number, (block, block_state)));
+        }
+
+        Ok(blocks)
+    }
+
+    fn block_with_senders(
+        provider: &impl BlockReader,
+        number: BlockNumber,
+    ) -> Result<SealedBlockWithSenders> {
+        let hash =
+            provider.block_hash(number)?.ok_or(ProviderError::HeaderNotFound(number.into()))?;
+        let block =
+            provider.block(number.into())?.ok_or(ProviderError::BlockNotFound(number.into()))?;
+        let body = provider
+            .block_body_indices(number)?
+            .ok_or(ProviderError::BlockBodyIndicesNotFound(number))?;
+
+        // Senders might be pruned, in which case they need to be recovered.
+        let mut senders = provider.senders_by_tx_range(body.tx_num_range())?;
+        if senders.len() != block.body.len() {
+            senders = block.senders().ok_or(ProviderError::MismatchOfTransactionAndSenderId {
+                tx_id: body.first_tx_num(),
+            })?;
+        }
+
+        Ok(SealedBlockWithSenders { block: block.seal(hash), senders })
+    }
+
+    fn chain(
+        blocks: impl IntoIterator<Item = (SealedBlockWithSenders, BundleStateWithReceipts)>,
+    ) -> Chain {
+        let mut chain_blocks = Vec::new();
+        let mut chain_state: Option<BundleStateWithReceipts> = None;
+        for (block, state) in blocks {
+            chain_blocks.push(block);
+            match chain_state.as_mut() {
+                Some(chain_state) => chain_state.extend(state),
+                None => chain_state = Some(state),
+            }
+        }
+        Chain::new(chain_blocks, chain_state.unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reth_db::{
+        models::AccountBeforeTx, tables, test_utils::create_test_rw_db, transaction::DbTxMut,
+    };
+    use reth_interfaces::test_utils::{generators, generators::random_block_range};
+    use reth_primitives::{stage::StageCheckpoint, Account, Address, H256, MAINNET};
+    use reth_provider::{BlockExecutionWriter, BlockWriter, StageCheckpointWriter};
+    use tokio::sync::broadcast;
+
+    #[test]
+    fn synthesizes_notifications() {
+        let db = create_test_rw_db();
+        let factory = ProviderFactory::new(db.clone(), MAINNET.clone());
+        let mut rng = generators::rng();
+
+        let blocks = random_block_range(&mut rng, 0..=10, H256::zero(), 0..3);
+        let insert = |blocks: &[reth_primitives::SealedBlock]| {
+            let provider = factory.provider_rw().unwrap();
+            for block in blocks {
+                provider.insert_block(block.clone(), None, None).unwrap();
+            }
+            provider
+                .save_stage_checkpoint(
+                    StageId::Finish,
+                    StageCheckpoint::new(blocks.last().unwrap().number),
+                )
+                .unwrap();
+            provider.commit().unwrap();
+        };
+        insert(&blocks[..=5]);
+
+        let (sender, mut receiver) = broadcast::channel(16);
+        let mut watcher = CanonStateWatcher::new(factory.clone(), sender, 64);
+
+        // The first poll only remembers the tip.
+        assert_eq!(watcher.poll().unwrap().map(|header| header.hash), Some(blocks[5].hash));
+        assert!(receiver.try_recv().is_err());
+        assert_eq!(watcher.poll().unwrap(), None);
+
+        insert(&blocks[6..]);
+
+        // Blocks 6 and 7 change the state of an account each.
+        let account = |nonce| Account { nonce, ..Default::default() };
+        let provider = factory.provider_rw().unwrap();
+        for number in [6, 7] {
+            let address = Address::from_low_u64_be(number);
+            provider
+                .tx_ref()
+                .put::<tables::AccountChangeSet>(number, AccountBeforeTx { address, info: None })
+                .unwrap();
+            provider.tx_ref().put::<tables::PlainAccountState>(address, account(number)).unwrap();
+        }
+        provider.save_stage_checkpoint(StageId::Execution, StageCheckpoint::new(10)).unwrap();
+        provider.commit().unwrap();
+
+        assert_eq!(watcher.poll().unwrap().map(|header| header.hash), Some(blocks[10].hash));
+        let notification = receiver.try_recv().unwrap();
+        assert_eq!(notification.reverted(), None);
+        let committed = notification.committed().unwrap();
+        assert_eq!(
+            committed.blocks().values().map(|block| block.hash).collect::<Vec<_>>(),
+            blocks[6..].iter().map(|block| block.hash).collect::<Vec<_>>()
+        );
+        for number in [6, 7] {
+            assert_eq!(
+                committed.state().account(&Address::from_low_u64_be(number)),
+                Some(Some(account(number)))
+            );
+        }
+
+        // Unwind the last three blocks.
+        let provider = factory.provider_rw().unwrap();
+        provider.take_block_and_execution_range(&MAINNET, 8..=10).unwrap();
+        provider.save_stage_checkpoint(StageId::Finish, StageCheckpoint::new(7)).unwrap();
+        provider.commit().unwrap();
+
+        // Nothing is notified until new blocks replace the unwound ones.
+        assert_eq!(watcher.poll().unwrap().map(|header| header.hash), Some(blocks[7].hash));
+        assert!(receiver.try_recv().is_err());
+
+        let fork = random_block_range(&mut rng, 8..=9, blocks[7].hash, 0..3);
+        insert(&fork);
+        assert_eq!(watcher.poll().unwrap().map(|header| header.hash), Some(fork[1].hash));
+        let notification = receiver.try_recv().unwrap();
+        assert_eq!(
+            notification.reverted().unwrap().blocks().keys().copied().collect::<Vec<_>>(),
+            vec![8, 9, 10]
+        );
+        assert_eq!(
+            notification
+                .committed()
+                .unwrap()
+                .blocks()
+                .values()
+                .map(|block| block.hash)
+                .collect::<Vec<_>>(),
+            fork.iter().map(|block| block.hash).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn waits_for_blocks() {
+        let factory = ProviderFactory::new(create_test_rw_db(), MAINNET.clone());
+        let (sender, _receiver) = broadcast::channel(16);
+        let mut watcher = CanonStateWatcher::new(factory, sender, 64);
+        assert_eq!(watcher.poll().unwrap(), None);
+    }
+}
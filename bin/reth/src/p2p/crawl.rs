@@ -0,0 +1,655 @@
+//! Command that crawls the discovery network and reports the nodes it reaches.
+use crate::args::utils::parse_duration_from_secs;
+use clap::{Parser, ValueEnum};
+use eyre::{eyre, WrapErr};
+use futures::{stream::FuturesUnordered, SinkExt, StreamExt};
+use reth_discv4::{DiscoveryUpdate, Discv4, Discv4Config};
+use reth_ecies::{stream::ECIESStream, util::pk2id};
+use reth_eth_wire::{
+    DisconnectReason, EthMessage, EthVersion, HelloMessage, ProtocolMessage, Status,
+    UnauthedP2PStream,
+};
+use reth_primitives::{
+    bytes::BytesMut, hex, ChainSpec, ForkFilter, ForkId, Head, NodeRecord, PeerId, H256, U256,
+};
+use reth_rlp::Encodable;
+use secp256k1::{SecretKey, SECP256K1};
+use serde::{Deserialize, Serialize};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet, VecDeque},
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, BufWriter, Write},
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::net::TcpStream;
+use tracing::{debug, info, warn};
+
+/// The interval at which the report is flushed and the crawl state is written to disk.
+const PERSIST_INTERVAL: Duration = Duration::from_secs(10);
+
+/// `reth p2p crawl` command
+///
+/// Walks the discovery network with `FINDNODE` lookups towards random targets, which are uniformly
+/// distributed across the keyspace, and dials every node it finds over RLPx to collect its `Hello`
+/// and `Status` messages. The crawl ends once a number of consecutive lookups did not discover any
+/// new node and all discovered nodes were dialed.
+///
+/// The crawl state is written next to the report, so an interrupted crawl is resumed by running
+/// the same command again.
+#[derive(Debug, Parser)]
+pub struct Command {
+    /// The path of the report.
+    #[arg(long, short, value_name = "FILE", default_value = "crawl.jsonl")]
+    output: PathBuf,
+
+    /// The format of the report.
+    #[arg(long, value_enum, default_value_t = ReportFormat::Jsonl)]
+    format: ReportFormat,
+
+    /// The path of the crawl state file.
+    ///
+    /// Defaults to the path of the report with a `.state.json` extension.
+    #[arg(long, value_name = "FILE")]
+    state: Option<PathBuf>,
+
+    /// The maximum number of concurrent RLPx dials.
+    #[arg(long, default_value_t = 64)]
+    max_concurrent_dials: usize,
+
+    /// The maximum number of concurrent discovery lookups.
+    #[arg(long, default_value_t = 4)]
+    max_concurrent_lookups: usize,
+
+    /// The number of consecutive lookups without new nodes after which the walk stops.
+    #[arg(long, default_value_t = 32)]
+    max_idle_lookups: usize,
+
+    /// The timeout in seconds for dialing a node and completing the handshakes.
+    #[arg(long, value_parser = parse_duration_from_secs, default_value = "10")]
+    dial_timeout: Duration,
+
+    /// Stop the crawl after the given number of seconds.
+    #[arg(long, value_parser = parse_duration_from_secs)]
+    duration: Option<Duration>,
+}
+
+impl Command {
+    /// Execute `p2p crawl` command
+    pub async fn execute(
+        &self,
+        chain: Arc<ChainSpec>,
+        secret_key: SecretKey,
+        discovery_port: u16,
+        boot_nodes: Vec<NodeRecord>,
+    ) -> eyre::Result<()> {
+        let state_path =
+            self.state.clone().unwrap_or_else(|| self.output.with_extension("state.json"));
+        let mut state = CrawlState::load(&state_path)?;
+        // nodes that were reported after the state was last written are not dialed again
+        state.on_reported(ReportWriter::reported_nodes(&self.output, self.format)?);
+        if !state.pending.is_empty() || !state.crawled.is_empty() {
+            info!(target: "reth::cli", path = ?state_path, crawled = state.crawled.len(), pending = state.pending.len(), "Resuming crawl");
+        }
+        if boot_nodes.is_empty() && state.pending.is_empty() {
+            eyre::bail!(
+                "No boot nodes for chain {}. Set a boot node with `--trusted-peer <enode record>`",
+                chain.chain
+            )
+        }
+
+        let discv4_config = Discv4Config::builder()
+            .add_boot_nodes(boot_nodes)
+            // resume the walk from the nodes that were not crawled yet
+            .add_boot_nodes(state.pending.iter().copied())
+            .enable_eip868(true)
+            .enable_lookup(false)
+            .enable_dht_random_walk(false)
+            .build();
+        let local_id = pk2id(&secret_key.public_key(SECP256K1));
+        let local_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), discovery_port);
+        let local_enr = NodeRecord::new(local_addr, local_id);
+        let discv4 = Discv4::spawn(local_addr, local_enr, secret_key, discv4_config).await?;
+        let mut updates = discv4.update_stream().await?;
+
+        let head = Head {
+            hash: chain.genesis_hash(),
+            number: 0,
+            timestamp: chain.genesis.timestamp,
+            difficulty: chain.genesis.difficulty,
+            total_difficulty: chain.genesis.difficulty,
+        };
+        let dialer = Arc::new(Dialer {
+            secret_key,
+            hello: HelloMessage::builder(local_id).port(0).build(),
+            status: Status::spec_builder(&chain, &head).build(),
+            fork_filter: chain.fork_filter(head),
+            timeout: self.dial_timeout,
+        });
+
+        let mut crawler =
+            Crawler { state, state_path, report: ReportWriter::open(&self.output, self.format)? };
+        info!(target: "reth::cli", path = ?self.output, "Starting crawl");
+
+        let deadline = tokio::time::sleep(self.duration.unwrap_or(Duration::MAX));
+        tokio::pin!(deadline);
+        let mut persist_interval = tokio::time::interval(PERSIST_INTERVAL);
+        let mut lookups = FuturesUnordered::new();
+        let mut dials = FuturesUnordered::new();
+        let mut idle_lookups = 0;
+
+        loop {
+            while lookups.len() < self.max_concurrent_lookups &&
+                idle_lookups + lookups.len() < self.max_idle_lookups
+            {
+                let discv4 = discv4.clone();
+                lookups.push(async move { discv4.lookup(PeerId::random()).await });
+            }
+            while dials.len() < self.max_concurrent_dials {
+                let Some(node) = crawler.state.next_pending() else { break };
+                dials.push(dialer.clone().crawl(node));
+            }
+            if lookups.is_empty() && dials.is_empty() {
+                info!(target: "reth::cli", "Crawled all discovered nodes");
+                break
+            }
+
+            tokio::select! {
+                Some(update) = updates.next() => crawler.state.on_discovery_update(update),
+                Some(res) = lookups.next() => {
+                    let discovered = match res {
+                        Ok(nodes) => nodes.into_iter().filter(|node| crawler.state.add_node(*node)).count(),
+                        Err(err) => {
+                            debug!(target: "reth::cli", %err, "Lookup failed");
+                            0
+                        }
+                    };
+                    if discovered == 0 { idle_lookups += 1 } else { idle_lookups = 0 }
+                }
+                Some(mut node) = dials.next() => {
+                    node.enr_fork_id = crawler.state.enr_fork_ids.get(&node.id).copied();
+                    node.enr = crawler.state.enrs.get(&node.id).cloned();
+                    if crawler.state.on_crawled(node.id) {
+                        crawler.report.write(&node)?;
+                    }
+                }
+                _ = persist_interval.tick() => {
+                    crawler.persist()?;
+                    info!(target: "reth::cli", crawled = crawler.state.crawled.len(), pending = crawler.state.pending.len(), dialing = dials.len(), "Crawling");
+                }
+                _ = &mut deadline => {
+                    info!(target: "reth::cli", "Crawl duration elapsed");
+                    break
+                }
+            }
+        }
+
+        crawler.persist()
+    }
+}
+
+/// The format of the crawl report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ReportFormat {
+    /// One JSON object per line.
+    Jsonl,
+    /// Comma separated values with a header row.
+    Csv,
+}
+
+/// Owns the crawl state and the report, and persists both when dropped so an interrupted crawl
+/// can be resumed.
+struct Crawler {
+    state: CrawlState,
+    state_path: PathBuf,
+    report: ReportWriter,
+}
+
+impl Crawler {
+    /// Flushes the report and then writes the state, so the state never refers to nodes that are
+    /// missing from the report.
+    fn persist(&mut self) -> eyre::Result<()> {
+        self.report.flush()?;
+        self.state.save(&self.state_path)
+    }
+}
+
+impl Drop for Crawler {
+    fn drop(&mut self) {
+        if let Err(err) = self.persist() {
+            warn!(target: "reth::cli", %err, "Failed to persist crawl state");
+        }
+    }
+}
+
+/// The resumable state of a crawl.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CrawlState {
+    /// The nodes that were dialed.
+    crawled: HashSet<PeerId>,
+    /// The discovered nodes that were not dialed yet.
+    pending: VecDeque<NodeRecord>,
+    /// The fork ids nodes advertised in their ENR.
+    enr_fork_ids: HashMap<PeerId, ForkId>,
+    /// The base64 encoded ENRs of the nodes.
+    enrs: HashMap<PeerId, String>,
+    /// The nodes that are currently dialed.
+    #[serde(skip)]
+    dialing: HashMap<PeerId, NodeRecord>,
+    /// The ids of the pending nodes.
+    #[serde(skip)]
+    queued: HashSet<PeerId>,
+}
+
+impl CrawlState {
+    /// Loads the state from the given file, or returns an empty state if it doesn't exist.
+    fn load(path: &Path) -> eyre::Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default())
+        }
+        let mut state: Self = serde_json::from_reader(File::open(path)?)
+            .wrap_err_with(|| format!("Failed to read crawl state from {}", path.display()))?;
+        state.queued = state.pending.iter().map(|node| node.id).collect();
+        Ok(state)
+    }
+
+    /// Writes the state to the given file.
+    ///
+    /// Nodes that are currently dialed are stored as pending, so they are dialed again when the
+    /// crawl is resumed.
+    fn save(&self, path: &Path) -> eyre::Result<()> {
+        let mut pending = self.dialing.values().copied().collect::<VecDeque<_>>();
+        pending.extend(self.pending.iter().copied());
+        let snapshot = CrawlStateRef {
+            crawled: &self.crawled,
+            pending: &pending,
+            enr_fork_ids: &self.enr_fork_ids,
+            enrs: &self.enrs,
+        };
+
+        // write to a temporary file first, so an interrupted write doesn't corrupt the state
+        let tmp = path.with_extension("tmp");
+        serde_json::to_writer(BufWriter::new(File::create(&tmp)?), &snapshot)?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// Queues a node for dialing if it wasn't seen before, returns `true` if the node is new.
+    fn add_node(&mut self, node: NodeRecord) -> bool {
+        if self.crawled.contains(&node.id) ||
+            self.dialing.contains_key(&node.id) ||
+            !self.queued.insert(node.id)
+        {
+            return false
+        }
+        self.pending.push_back(node);
+        true
+    }
+
+    /// Returns the next node to dial.
+    fn next_pending(&mut self) -> Option<NodeRecord> {
+        let node = self.pending.pop_front()?;
+        self.queued.remove(&node.id);
+        self.dialing.insert(node.id, node);
+        Some(node)
+    }
+
+    /// Marks the node as crawled, returns `true` if it wasn't crawled before.
+    fn on_crawled(&mut self, id: PeerId) -> bool {
+        self.dialing.remove(&id);
+        self.crawled.insert(id)
+    }
+
+    /// Marks the nodes that are already in the report as crawled.
+    fn on_reported(&mut self, reported: HashSet<PeerId>) {
+        self.crawled.extend(reported);
+        let crawled = &self.crawled;
+        self.pending.retain(|node| !crawled.contains(&node.id));
+        self.queued = self.pending.iter().map(|node| node.id).collect();
+    }
+
+    fn on_discovery_update(&mut self, update: DiscoveryUpdate) {
+        match update {
+            DiscoveryUpdate::Added(node) | DiscoveryUpdate::DiscoveredAtCapacity(node) => {
+                self.add_node(node);
+            }
+            DiscoveryUpdate::EnrForkId(node, fork_id) => {
+                self.add_node(node);
+                self.enr_fork_ids.insert(node.id, fork_id);
+            }
+            DiscoveryUpdate::Enr(node, enr) => {
+                // only keep records that are signed by the node itself
+                if pk2id(&enr.public_key()) == node.id {
+                    self.enrs.insert(node.id, enr.to_base64());
+                }
+            }
+            DiscoveryUpdate::Removed(_) => {}
+            DiscoveryUpdate::Batch(updates) => {
+                for update in updates {
+                    self.on_discovery_update(update);
+                }
+            }
+        }
+    }
+}
+
+/// Borrowed version of [CrawlState] for serialization.
+#[derive(Serialize)]
+struct CrawlStateRef<'a> {
+    crawled: &'a HashSet<PeerId>,
+    pending: &'a VecDeque<NodeRecord>,
+    enr_fork_ids: &'a HashMap<PeerId, ForkId>,
+    enrs: &'a HashMap<PeerId, String>,
+}
+
+/// Dials nodes and performs the `p2p` and `eth` handshakes.
+#[derive(Debug)]
+struct Dialer {
+    secret_key: SecretKey,
+    hello: HelloMessage,
+    status: Status,
+    fork_filter: ForkFilter,
+    timeout: Duration,
+}
+
+impl Dialer {
+    /// Dials the node and returns everything that was learned about it.
+    async fn crawl(self: Arc<Self>, node: NodeRecord) -> CrawledNode {
+        let mut crawled = CrawledNode::new(node);
+        match tokio::time::timeout(self.timeout, self.handshake(node, &mut crawled)).await {
+            Ok(Ok(())) => {}
+            Ok(Err(err)) => crawled.error = Some(format!("{err:#}")),
+            Err(_) => crawled.error = Some("timeout".to_string()),
+        }
+        crawled
+    }
+
+    async fn handshake(&self, node: NodeRecord, crawled: &mut CrawledNode) -> eyre::Result<()> {
+        let stream = TcpStream::connect(node.tcp_addr()).await.wrap_err("tcp")?;
+        let stream =
+            ECIESStream::connect(stream, self.secret_key, node.id).await.wrap_err("ecies")?;
+        let (mut p2p_stream, hello) =
+            UnauthedP2PStream::new(stream).handshake(self.hello.clone()).await.wrap_err("hello")?;
+        crawled.client_version = Some(hello.client_version);
+        crawled.capabilities = hello.capabilities.iter().map(ToString::to_string).collect();
+
+        // the status handshake is done manually, because the status of nodes on other chains is
+        // reported as well
+        let version = EthVersion::try_from(p2p_stream.shared_capability().version())?;
+        let mut status = BytesMut::new();
+        ProtocolMessage::from(EthMessage::Status(Status { version: version as u8, ..self.status }))
+            .encode(&mut status);
+        p2p_stream.send(status.freeze()).await.wrap_err("status")?;
+        let msg = p2p_stream
+            .next()
+            .await
+            .ok_or_else(|| eyre!("status: connection closed"))?
+            .wrap_err("status")?;
+        let _ = p2p_stream.disconnect(DisconnectReason::ClientQuitting).await;
+
+        let status = match ProtocolMessage::decode_message(version, &mut msg.as_ref())?.message {
+            EthMessage::Status(status) => status,
+            msg => eyre::bail!("status: unexpected message {:?}", msg.message_id()),
+        };
+        crawled.eth_version = Some(status.version);
+        crawled.network_id = Some(status.chain.id());
+        crawled.genesis = Some(status.genesis);
+        crawled.best_hash = Some(status.blockhash);
+        crawled.total_difficulty = Some(status.total_difficulty);
+        crawled.fork_id = Some(status.forkid);
+        crawled.fork_compatible = Some(
+            status.genesis == self.status.genesis &&
+                self.fork_filter.validate(status.forkid).is_ok(),
+        );
+        Ok(())
+    }
+}
+
+/// A record of the crawl report.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct CrawledNode {
+    /// The unix timestamp of the dial.
+    timestamp: u64,
+    id: PeerId,
+    ip: IpAddr,
+    tcp_port: u16,
+    udp_port: u16,
+    /// The fork id advertised in the ENR of the node.
+    enr_fork_id: Option<ForkId>,
+    /// The base64 encoded ENR of the node, as published in DNS node lists.
+    enr: Option<String>,
+    client_version: Option<String>,
+    capabilities: Vec<String>,
+    eth_version: Option<u8>,
+    network_id: Option<u64>,
+    genesis: Option<H256>,
+    best_hash: Option<H256>,
+    total_difficulty: Option<U256>,
+    /// The fork id of the `Status` message.
+    fork_id: Option<ForkId>,
+    /// Whether the node is on the crawled chain and its fork id is compatible.
+    fork_compatible: Option<bool>,
+    /// The reason the dial or one of the handshakes failed.
+    error: Option<String>,
+}
+
+impl CrawledNode {
+    const CSV_HEADER: &'static str = "timestamp,id,ip,tcp_port,udp_port,enr_fork_hash,enr_fork_next,enr,client_version,capabilities,eth_version,network_id,genesis,best_hash,total_difficulty,fork_hash,fork_next,fork_compatible,error";
+
+    fn new(node: NodeRecord) -> Self {
+        Self {
+            timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs(),
+            id: node.id,
+            ip: node.address,
+            tcp_port: node.tcp_port,
+            udp_port: node.udp_port,
+            enr_fork_id: None,
+            enr: None,
+            client_version: None,
+            capabilities: Vec::new(),
+            eth_version: None,
+            network_id: None,
+            genesis: None,
+            best_hash: None,
+            total_difficulty: None,
+            fork_id: None,
+            fork_compatible: None,
+            error: None,
+        }
+    }
+
+    /// Returns the record as a CSV row matching [Self::CSV_HEADER].
+    fn to_csv_row(&self) -> String {
+        fn opt<T: ToString>(value: Option<T>) -> String {
+            value.map(|value| value.to_string()).unwrap_or_default()
+        }
+        let fork_hash = |fork_id: Option<ForkId>| opt(fork_id.map(|f| hex::encode(f.hash.0)));
+        let fork_next = |fork_id: Option<ForkId>| opt(fork_id.map(|f| f.next));
+
+        [
+            self.timestamp.to_string(),
+            format!("{:?}", self.id),
+            self.ip.to_string(),
+            self.tcp_port.to_string(),
+            self.udp_port.to_string(),
+            fork_hash(self.enr_fork_id),
+            fork_next(self.enr_fork_id),
+            opt(self.enr.as_ref()),
+            opt(self.client_version.as_ref()),
+            self.capabilities.join(" "),
+            opt(self.eth_version),
+            opt(self.network_id),
+            opt(self.genesis.map(|hash| format!("{hash:?}"))),
+            opt(self.best_hash.map(|hash| format!("{hash:?}"))),
+            opt(self.total_difficulty),
+            fork_hash(self.fork_id),
+            fork_next(self.fork_id),
+            opt(self.fork_compatible),
+            opt(self.error.as_ref()),
+        ]
+        .iter()
+        .map(|field| csv_field(field))
+        .collect::<Vec<_>>()
+        .join(",")
+    }
+}
+
+/// Quotes the field if it contains a separator, quote or line break.
+fn csv_field(field: &str) -> Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+/// Appends records to the report.
+struct ReportWriter {
+    format: ReportFormat,
+    writer: BufWriter<File>,
+}
+
+impl ReportWriter {
+    /// Opens the report for appending, and writes the CSV header if the report is new.
+    fn open(path: &Path, format: ReportFormat) -> eyre::Result<Self> {
+        let contents = fs::read(path).unwrap_or_default();
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let mut writer = BufWriter::new(file);
+        if contents.is_empty() {
+            if format == ReportFormat::Csv {
+                writeln!(writer, "{}", CrawledNode::CSV_HEADER)?;
+            }
+        } else if !contents.ends_with(b"\n") {
+            // terminate a record that was cut off when the crawl was interrupted
+            writeln!(writer)?;
+        }
+        Ok(Self { format, writer })
+    }
+
+    /// Returns the ids of the nodes in an existing report.
+    ///
+    /// Records that can't be parsed, like one that was cut off when the crawl was interrupted, are
+    /// skipped.
+    fn reported_nodes(path: &Path, format: ReportFormat) -> eyre::Result<HashSet<PeerId>> {
+        #[derive(Deserialize)]
+        struct ReportedNode {
+            id: PeerId,
+        }
+
+        if !path.exists() {
+            return Ok(HashSet::new())
+        }
+        let mut ids = HashSet::new();
+        for line in BufReader::new(File::open(path)?).lines() {
+            let line = line?;
+            let id = match format {
+                ReportFormat::Jsonl => {
+                    serde_json::from_str::<ReportedNode>(&line).ok().map(|node| node.id)
+                }
+                ReportFormat::Csv => line.split(',').nth(1).and_then(|id| id.parse().ok()),
+            };
+            ids.extend(id);
+        }
+        Ok(ids)
+    }
+
+    fn write(&mut self, node: &CrawledNode) -> eyre::Result<()> {
+        match self.format {
+            ReportFormat::Jsonl => {
+                serde_json::to_writer(&mut self.writer, node)?;
+                writeln!(self.writer)?;
+            }
+            ReportFormat::Csv => writeln!(self.writer, "{}", node.to_csv_row())?,
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> eyre::Result<()> {
+        Ok(self.writer.flush()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resume_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("crawl.state.json");
+
+        let nodes = (0..3)
+            .map(|port| NodeRecord::new(([127, 0, 0, 1], port).into(), PeerId::random()))
+            .collect::<Vec<_>>();
+        let mut state = CrawlState::default();
+        for node in &nodes {
+            assert!(state.add_node(*node));
+        }
+        assert!(!state.add_node(nodes[0]));
+
+        let dialing = state.next_pending().unwrap();
+        let crawled = state.next_pending().unwrap();
+        state.on_crawled(crawled.id);
+        assert!(!state.add_node(crawled));
+        state.save(&path).unwrap();
+
+        // the node that was dialed while the crawl was interrupted is dialed again
+        let mut state = CrawlState::load(&path).unwrap();
+        assert_eq!(state.crawled, HashSet::from([crawled.id]));
+        assert_eq!(state.pending, VecDeque::from([dialing, nodes[2]]));
+        assert!(!state.add_node(dialing));
+    }
+
+    #[test]
+    fn resume_skips_reported_nodes() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let nodes = (0..3)
+            .map(|port| NodeRecord::new(([127, 0, 0, 1], port).into(), PeerId::random()))
+            .collect::<Vec<_>>();
+        for format in [ReportFormat::Jsonl, ReportFormat::Csv] {
+            let path = dir.path().join(format!("crawl-{format:?}"));
+            let mut report = ReportWriter::open(&path, format).unwrap();
+            report.write(&CrawledNode::new(nodes[0])).unwrap();
+            report.flush().unwrap();
+            drop(report);
+            // a record that was cut off is terminated when the report is opened again
+            OpenOptions::new().append(true).open(&path).unwrap().write_all(b"{\"timest").unwrap();
+            let mut report = ReportWriter::open(&path, format).unwrap();
+            report.write(&CrawledNode::new(nodes[1])).unwrap();
+            report.flush().unwrap();
+
+            let reported = ReportWriter::reported_nodes(&path, format).unwrap();
+            assert_eq!(reported, HashSet::from([nodes[0].id, nodes[1].id]));
+
+            // the state was written before the nodes were reported
+            let mut state = CrawlState::default();
+            for node in &nodes {
+                state.add_node(*node);
+            }
+            state.on_reported(reported);
+            assert_eq!(state.pending, VecDeque::from([nodes[2]]));
+            assert!(!state.add_node(nodes[0]));
+            assert!(!state.on_crawled(nodes[1].id));
+            assert!(state.on_crawled(nodes[2].id));
+        }
+    }
+
+    #[test]
+    fn csv_row() {
+        let node = NodeRecord::new(([127, 0, 0, 1], 30303).into(), PeerId::random());
+        let mut crawled = CrawledNode::new(node);
+        crawled.capabilities = vec!["eth/67".to_string(), "eth/68".to_string()];
+        crawled.enr = Some("enr:-IS4QHCYrYZbAKWCBRlAy5zzaDZXJBGkcnh4MHcBFZntXNFrdvJjX04jRzjzCBOonrkTfj499SZuOh8R33Ls8RRcy5wBgmlkgnY0gmlwhH8AAAGJc2VjcDI1NmsxoQPKY0yuDUmstAHYpMa2_oxVtw0RW_QAdpzBQA8yWM0xOIN1ZHCCdl8".to_string());
+        crawled.error = Some("hello: disconnected, \"too many peers\"".to_string());
+
+        let row = crawled.to_csv_row();
+        assert_eq!(row.split(',').count(), CrawledNode::CSV_HEADER.split(',').count() + 1);
+        assert!(row.contains(",eth/67 eth/68,"));
+        assert!(row.contains(",enr:-IS4Q"));
+        assert!(row.ends_with(",\"hello: disconnected, \"\"too many peers\"\"\""));
+    }
+}
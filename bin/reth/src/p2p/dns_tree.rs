@@ -0,0 +1,130 @@
+//! Command that builds an EIP-1459 DNS tree from a list of node records.
+use crate::args::get_secret_key;
+use clap::{Parser, ValueEnum};
+use enr::Enr;
+use eyre::WrapErr;
+use reth_dns_discovery::{publish::DnsTreeBuilder, tree::LinkEntry};
+use secp256k1::SecretKey;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tracing::info;
+
+/// `reth p2p dns-tree` command
+///
+/// Builds an EIP-1459 node list from signed node records, for example the ENRs collected by `reth
+/// p2p crawl`, signs it with the key of the tree and renders the TXT records to publish at the
+/// domain of the tree.
+#[derive(Debug, Parser)]
+pub struct Command {
+    /// The path of a file with the ENRs to include, one `enr:` record per line.
+    #[arg(long, short, value_name = "FILE")]
+    input: PathBuf,
+
+    /// The path of the secret key the tree is signed with.
+    ///
+    /// A new key is generated if the file doesn't exist.
+    #[arg(long, value_name = "PATH")]
+    key: PathBuf,
+
+    /// The domain the tree is published at.
+    #[arg(long)]
+    domain: String,
+
+    /// Links to other trees to include, e.g. `enrtree://<key>@nodes.example.org`.
+    #[arg(long = "link", value_name = "LINK")]
+    links: Vec<LinkEntry<SecretKey>>,
+
+    /// The sequence number of the tree.
+    ///
+    /// Defaults to the current unix timestamp, so that every update of the tree has a higher
+    /// sequence number.
+    #[arg(long)]
+    sequence: Option<u64>,
+
+    /// The TTL of the records in seconds, used in the zone file.
+    #[arg(long, default_value_t = 3600)]
+    ttl: u32,
+
+    /// The format of the records.
+    #[arg(long, value_enum, default_value_t = TreeFormat::Zone)]
+    format: TreeFormat,
+
+    /// The path to write the records to, instead of printing them.
+    #[arg(long, short, value_name = "FILE")]
+    output: Option<PathBuf>,
+}
+
+impl Command {
+    /// Execute `p2p dns-tree` command
+    pub async fn execute(&self) -> eyre::Result<()> {
+        let key = get_secret_key(&self.key)?;
+        let enrs = read_enrs(&self.input)?;
+        let sequence = self.sequence.unwrap_or_else(|| {
+            SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+        });
+
+        let tree = DnsTreeBuilder::new(sequence)
+            .add_enrs(enrs)
+            .add_links(self.links.iter().cloned())
+            .build(&key)?;
+        info!(target: "reth::cli", link = %tree.link(self.domain.trim_end_matches('.')), sequence, "Built DNS tree");
+
+        let records = match self.format {
+            TreeFormat::Zone => tree.to_zone_file(&self.domain, self.ttl),
+            TreeFormat::Json => tree.to_json(&self.domain),
+        };
+        match &self.output {
+            Some(path) => fs::write(path, records)
+                .wrap_err_with(|| format!("Could not write records to {}", path.display()))?,
+            None => println!("{records}"),
+        }
+        Ok(())
+    }
+}
+
+/// The format of the records of the tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TreeFormat {
+    /// A BIND zone file.
+    Zone,
+    /// A JSON object that maps the names to the content of the TXT records.
+    Json,
+}
+
+/// Reads the ENRs from the file, skipping empty lines and `#` comments.
+fn read_enrs(path: &Path) -> eyre::Result<Vec<Enr<SecretKey>>> {
+    let contents = fs::read_to_string(path)
+        .wrap_err_with(|| format!("Could not read ENRs from {}", path.display()))?;
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| line.parse().map_err(|err| eyre::eyre!("Invalid ENR {line}: {err}")))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use enr::EnrBuilder;
+    use secp256k1::rand::thread_rng;
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn read_enr_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("enrs.txt");
+
+        let enr = EnrBuilder::new("v4")
+            .ip4(Ipv4Addr::LOCALHOST)
+            .tcp4(30303)
+            .build(&SecretKey::new(&mut thread_rng()))
+            .unwrap();
+        fs::write(&path, format!("# crawled nodes\n{}\n\n", enr.to_base64())).unwrap();
+
+        assert_eq!(read_enrs(&path).unwrap(), vec![enr]);
+    }
+}
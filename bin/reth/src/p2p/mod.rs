@@ -12,12 +12,16 @@ use backon::{ConstantBuilder, Retryable};
 use clap::{Parser, Subcommand};
 use reth_config::Config;
 use reth_db::open_db;
-use reth_discv4::NatResolver;
+use reth_discv4::{NatResolver, DEFAULT_DISCOVERY_PORT};
 use reth_interfaces::p2p::bodies::client::BodiesClient;
 use reth_primitives::{BlockHashOrNumber, ChainSpec, NodeRecord};
 use reth_provider::ProviderFactory;
 use std::{path::PathBuf, sync::Arc};
 
+mod crawl;
+mod dns_tree;
+mod replay;
+
 /// `reth p2p` command
 #[derive(Debug, Parser)]
 pub struct Command {
@@ -99,10 +103,22 @@ pub enum Subcommands {
         #[arg(value_parser = hash_or_num_value_parser)]
         id: BlockHashOrNumber,
     },
+    /// Crawl the discovery network and report the reachable nodes
+    Crawl(crawl::Command),
+    /// Build an EIP-1459 DNS tree of nodes to publish
+    DnsTree(dns_tree::Command),
+    /// Replay a capture of the messages exchanged with a peer
+    Replay(replay::Command),
 }
 impl Command {
     /// Execute `p2p` command
     pub async fn execute(&self) -> eyre::Result<()> {
+        match &self.command {
+            Subcommands::Replay(command) => return command.execute().await,
+            Subcommands::DnsTree(command) => return command.execute().await,
+            _ => {}
+        }
+
         let tempdir = tempfile::TempDir::new()?;
         let noop_db = Arc::new(open_db(&tempdir.into_path(), self.db.log_level)?);
 
@@ -126,6 +142,15 @@ impl Command {
         let secret_key_path = self.p2p_secret_key.clone().unwrap_or(default_secret_key_path);
         let p2p_secret_key = get_secret_key(&secret_key_path)?;
 
+        if let Subcommands::Crawl(command) = &self.command {
+            let mut boot_nodes = self.chain.chain.bootnodes().unwrap_or_default();
+            boot_nodes.extend(config.peers.trusted_nodes.iter().copied());
+            let discovery_port = self.discovery.port.unwrap_or(DEFAULT_DISCOVERY_PORT);
+            return command
+                .execute(self.chain.clone(), p2p_secret_key, discovery_port, boot_nodes)
+                .await
+        }
+
         let mut network_config_builder =
             config.network_config(self.nat, None, p2p_secret_key).chain_spec(self.chain.clone());
 
@@ -180,6 +205,9 @@ impl Command {
                 let body = result.into_iter().next().unwrap();
                 println!("Successfully downloaded body: {body:?}")
             }
+            Subcommands::Crawl(_) => unreachable!("crawl doesn't start the network"),
+            Subcommands::Replay(_) => unreachable!("replay doesn't start the network"),
+            Subcommands::DnsTree(_) => unreachable!("dns-tree doesn't start the network"),
         }
 
         Ok(())
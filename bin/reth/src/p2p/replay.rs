@@ -0,0 +1,102 @@
+//! Command that replays a capture of the messages exchanged with a peer.
+use clap::Parser;
+use eyre::WrapErr;
+use futures::{FutureExt, StreamExt};
+use reth_eth_wire::{capture::CaptureFile, EthStream, EthVersion, ProtocolMessage};
+use reth_primitives::hex;
+use std::path::PathBuf;
+
+/// `reth p2p replay` command
+///
+/// Reads a capture written by a node started with `--p2p-capture-dir` and feeds the messages
+/// received from the peer into an `EthStream`, in the order they were received. Every decoded
+/// message is printed, as well as the error the stream yields, if any. Keepalive `Ping` and `Pong`
+/// messages are skipped.
+#[derive(Debug, Parser)]
+pub struct Command {
+    /// The path of the capture file.
+    #[arg(value_name = "FILE")]
+    path: PathBuf,
+}
+
+impl Command {
+    /// Execute `p2p replay` command
+    pub async fn execute(&self) -> eyre::Result<()> {
+        let capture = CaptureFile::read(&self.path)
+            .wrap_err_with(|| format!("Could not read capture {}", self.path.display()))?;
+
+        let header = &capture.header;
+        let capabilities = header
+            .capabilities
+            .iter()
+            .map(|cap| format!("{}/{}", cap.name, cap.version))
+            .collect::<Vec<_>>();
+        println!("Peer: {:?}", header.peer_id);
+        println!("Client: {}", header.client_version);
+        println!("Capabilities: {}", capabilities.join(", "));
+        println!(
+            "Messages: {} inbound, {} outbound",
+            capture.inbound().count(),
+            capture.outbound().count()
+        );
+        if let (Some(first), Some(last)) = (capture.messages.first(), capture.messages.last()) {
+            println!("Duration: {}ms", last.timestamp.saturating_sub(first.timestamp) / 1000);
+        }
+
+        let mut p2p_stream = capture.replay()?;
+        let version = EthVersion::try_from(p2p_stream.shared_capability().version())?;
+
+        // the status message is only accepted by the `EthStream` during the handshake, so it is
+        // decoded separately
+        let mut count = 0;
+        match p2p_stream.next().await {
+            Some(Ok(bytes)) => {
+                count += 1;
+                match ProtocolMessage::decode_message(version, &mut bytes.as_ref()) {
+                    Ok(msg) => println!("#{count} {:?}", msg.message),
+                    Err(err) => println!("#{count} Failed to decode message: {err}"),
+                }
+            }
+            Some(Err(err)) => {
+                println!("Replay ended with error: {err}");
+                return Ok(())
+            }
+            None => return Ok(()),
+        }
+
+        let mut eth_stream = EthStream::new(version, p2p_stream);
+        let mut done = false;
+        loop {
+            while let Some(msg) = eth_stream.inner_mut().take_subprotocol_message() {
+                count += 1;
+                println!(
+                    "#{count} {} message: 0x{}",
+                    msg.capability.capability(),
+                    hex::encode(&msg.message)
+                );
+            }
+            if done {
+                break
+            }
+
+            // the replayed messages are always ready, so the stream is only pending while the
+            // buffered messages of other capabilities were not taken
+            let Some(next) = eth_stream.next().now_or_never() else { continue };
+            match next {
+                Some(Ok(msg)) => {
+                    count += 1;
+                    println!("#{count} {msg:?}");
+                }
+                Some(Err(err)) => {
+                    println!("Replay ended with error: {err}");
+                    break
+                }
+                // print the remaining messages of other capabilities before finishing
+                None => done = true,
+            }
+        }
+
+        println!("Replayed {count} messages");
+        Ok(())
+    }
+}
@@ -7,7 +7,11 @@ use hyper::{
 use metrics::{describe_gauge, gauge};
 use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
 use metrics_util::layers::{PrefixLayer, Stack};
-use reth_db::{database::Database, tables, DatabaseEnv};
+use reth_db::{
+    database::Database,
+    mdbx::{Env, EnvironmentKind},
+    tables,
+};
 use reth_metrics::metrics::Unit;
 use std::{convert::Infallible, net::SocketAddr, sync::Arc};
 use tracing::error;
@@ -69,9 +73,9 @@ async fn start_endpoint<F: Hook + 'static>(
 
 /// Installs Prometheus as the metrics recorder and serves it over HTTP with database and process
 /// metrics.
-pub(crate) async fn initialize(
+pub(crate) async fn initialize<E: EnvironmentKind>(
     listen_addr: SocketAddr,
-    db: Arc<DatabaseEnv>,
+    db: Arc<Env<E>>,
     process: metrics_process::Collector,
 ) -> eyre::Result<()> {
     let db_stats = move || {
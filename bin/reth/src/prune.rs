@@ -0,0 +1,149 @@
+//! Command that prunes the database offline, according to the pruning configuration.
+use crate::{
+    args::{utils::genesis_value_parser, DatabaseArgs, PruningArgs},
+    dirs::{DataDirPath, MaybePlatformPath},
+};
+use clap::Parser;
+use reth_config::Config;
+use reth_db::{database::Database, init_db, open_db_read_only};
+use reth_primitives::{ChainSpec, PruneBatchSizes, PruneModes};
+use reth_provider::{BlockNumReader, ProviderFactory, PruneCheckpointReader};
+use reth_prune::Pruner;
+use std::{path::PathBuf, sync::Arc, time::Instant};
+use tracing::info;
+
+/// `reth prune` command
+#[derive(Debug, Parser)]
+pub struct Command {
+    /// The path to the configuration file to use.
+    #[arg(long, value_name = "FILE", verbatim_doc_comment)]
+    config: Option<PathBuf>,
+
+    /// The path to the data dir for all reth files and subdirectories.
+    ///
+    /// Defaults to the OS-specific data directory:
+    ///
+    /// - Linux: `$XDG_DATA_HOME/reth/` or `$HOME/.local/share/reth/`
+    /// - Windows: `{FOLDERID_RoamingAppData}/reth/`
+    /// - macOS: `$HOME/Library/Application Support/reth/`
+    #[arg(long, value_name = "DATA_DIR", verbatim_doc_comment, default_value_t)]
+    datadir: MaybePlatformPath<DataDirPath>,
+
+    /// The chain this node is running.
+    ///
+    /// Possible values are either a built-in chain or the path to a chain specification file.
+    ///
+    /// Built-in chains:
+    /// - mainnet
+    /// - goerli
+    /// - sepolia
+    #[arg(
+        long,
+        value_name = "CHAIN_OR_PATH",
+        verbatim_doc_comment,
+        default_value = "mainnet",
+        value_parser = genesis_value_parser
+    )]
+    chain: Arc<ChainSpec>,
+
+    #[clap(flatten)]
+    db: DatabaseArgs,
+
+    #[clap(flatten)]
+    pruning: PruningArgs,
+
+    /// Maximum number of rows to delete per prune part before committing the database
+    /// transaction.
+    #[arg(long, default_value_t = 1_000_000)]
+    batch_size: usize,
+
+    /// Only estimate the number of rows that would be deleted, without modifying the database.
+    #[arg(long)]
+    dry_run: bool,
+}
+
+impl Command {
+    /// Execute `prune` command
+    pub async fn execute(self) -> eyre::Result<()> {
+        // Raise the fd limit of the process.
+        // Does not do anything on windows.
+        fdlimit::raise_fd_limit();
+
+        let data_dir = self.datadir.unwrap_or_chain_default(self.chain.chain);
+        let config_path = self.config.clone().unwrap_or(data_dir.config_path());
+        let config: Config = confy::load_path(config_path).unwrap_or_default();
+
+        let Some(prune_config) =
+            self.pruning.prune_config(Arc::clone(&self.chain))?.or(config.prune)
+        else {
+            eyre::bail!("No pruning configuration found, nothing to prune.")
+        };
+
+        let db_path = data_dir.db_path();
+        info!(target: "reth::cli", path = ?db_path, "Opening database");
+        // A dry run only reads the database, so it can run next to a node.
+        if self.dry_run {
+            self.prune(
+                Arc::new(open_db_read_only(&db_path, self.db.log_level)?),
+                prune_config.parts,
+            )
+        } else {
+            self.prune(Arc::new(init_db(&db_path, self.db.log_level)?), prune_config.parts)
+        }
+    }
+
+    /// Prunes the database according to the prune modes, or only estimates the number of rows to
+    /// prune on a dry run.
+    fn prune<DB: Database>(&self, db: Arc<DB>, modes: PruneModes) -> eyre::Result<()> {
+        let factory = ProviderFactory::new(&db, self.chain.clone());
+
+        let tip = factory.provider()?.best_block_number()?;
+        info!(target: "reth::cli", %tip, parts = ?modes, "Pruning database");
+
+        // Block interval of 1 makes the batch sizes apply to every pruner run as they are.
+        let batch_sizes = PruneBatchSizes::default()
+            .with_receipts(self.batch_size)
+            .with_transaction_lookup(self.batch_size)
+            .with_transaction_senders(self.batch_size)
+            .with_account_history(self.batch_size)
+            .with_storage_history(self.batch_size)
+            .with_block_bodies(self.batch_size);
+        let mut pruner = Pruner::new(db.clone(), self.chain.clone(), 1, modes, batch_sizes);
+
+        let estimates = pruner.estimate(tip)?;
+        for (part, rows) in &estimates {
+            info!(target: "reth::cli", %part, rows, "Estimated rows to prune");
+        }
+
+        if self.dry_run {
+            return Ok(())
+        }
+
+        let start = Instant::now();
+        loop {
+            let run_start = Instant::now();
+            let done = pruner.run(tip)?;
+
+            let provider = factory.provider()?;
+            for part in estimates.keys() {
+                let checkpoint = provider.get_prune_checkpoint(*part)?;
+                info!(
+                    target: "reth::cli",
+                    %part,
+                    pruned_block = ?checkpoint.and_then(|checkpoint| checkpoint.block_number),
+                    done,
+                    elapsed = ?run_start.elapsed(),
+                    "Pruned"
+                );
+            }
+
+            if done {
+                break
+            }
+        }
+
+        info!(target: "reth::cli", %tip, elapsed = ?start.elapsed(), "Pruning finished");
+
+        Ok(())
+    }
+}
@@ -129,6 +129,7 @@ async fn dry_run<DB: Database>(
             clean_threshold: u64::MAX, /* Forces updating the root instead of calculating
                                         * from
                                         * scratch */
+            parallel_storage_roots: false,
         }
         .execute(
             &provider,
@@ -152,6 +152,7 @@ impl Command {
                             config.peers.trusted_nodes.insert(*peer);
                         });
                     }
+                    config.peers.reserved_nodes.extend(self.network.reserved_peers.iter().copied());
 
                     let network_secret_path = self
                         .network
@@ -6,7 +6,13 @@ use crate::{
     AppendableChain, BlockBuffer, BlockIndices, BlockchainTreeConfig, BundleStateData,
     TreeExternals,
 };
-use reth_db::{cursor::DbCursorRO, database::Database, tables, transaction::DbTx};
+use reth_db::{
+    cursor::DbCursorRO,
+    database::Database,
+    tables,
+    transaction::{DbTx, DbTxMut},
+    DatabaseError,
+};
 use reth_interfaces::{
     blockchain_tree::{
         error::{BlockchainTreeError, InsertBlockError, InsertBlockErrorKind},
@@ -94,6 +100,18 @@ pub struct BlockchainTree<DB: Database, C: Consensus, EF: ExecutorFactory> {
     /// Metrics for sync stages.
     sync_metrics_tx: Option<MetricEventsSender>,
     prune_modes: Option<PruneModes>,
+    /// Side chain blocks restored from the [tables::SidechainBlocks] table that have not been
+    /// executed yet.
+    ///
+    /// They are inserted into the tree once a new block or a fork choice update references them,
+    /// see [BlockchainTree::insert_restored_blocks].
+    restored_blocks: HashMap<BlockHash, SealedBlockWithSenders>,
+    /// Side chain blocks that were inserted into (`true`) or removed from (`false`) the tree and
+    /// are not yet written to the [tables::SidechainBlocks] table.
+    ///
+    /// The changes are written at the end of every tree update, see
+    /// [BlockchainTree::persist_side_chain_changes], or along with the canonical commit.
+    side_chain_changes: HashMap<BlockHash, bool>,
 }
 
 /// A container that wraps chains and block indices to allow searching for block hashes across all
@@ -134,20 +152,40 @@ impl<DB: Database, C: Consensus, EF: ExecutorFactory> BlockchainTree<DB, C, EF>
                 last_canonical_hashes.last().cloned().unwrap_or_default()
             };
 
+        let last_canonical_hashes = BTreeMap::from_iter(last_canonical_hashes);
+
+        // restore the side chains that are still within the reorg window, they are only executed
+        // once they are needed
+        let mut restored_blocks = HashMap::new();
+        let mut side_chain_changes = HashMap::new();
+        for entry in externals.db.tx()?.cursor_read::<tables::SidechainBlocks>()?.walk(None)? {
+            let (hash, block) = entry?;
+            let block = block.into_block(hash);
+            if block.number > last_finalized_block_number &&
+                last_canonical_hashes.get(&block.number) != Some(&hash)
+            {
+                restored_blocks.insert(hash, block);
+            } else {
+                side_chain_changes.insert(hash, false);
+            }
+        }
+        if !restored_blocks.is_empty() {
+            info!(target: "blockchain_tree", blocks = restored_blocks.len(), "Restored side chain blocks");
+        }
+
         Ok(Self {
             externals,
             buffered_blocks: BlockBuffer::new(config.max_unconnected_blocks()),
             block_chain_id_generator: 0,
             chains: Default::default(),
-            block_indices: BlockIndices::new(
-                last_finalized_block_number,
-                BTreeMap::from_iter(last_canonical_hashes),
-            ),
+            block_indices: BlockIndices::new(last_finalized_block_number, last_canonical_hashes),
             config,
             canon_state_notification_sender,
             metrics: Default::default(),
             sync_metrics_tx: None,
             prune_modes,
+            restored_blocks,
+            side_chain_changes,
         })
     }
 
@@ -511,6 +549,7 @@ impl<DB: Database, C: Consensus, EF: ExecutorFactory> BlockchainTree<DB, C, EF>
             )?;
 
             self.block_indices.insert_non_fork_block(block_number, block_hash, chain_id);
+            self.side_chain_changes.insert(block_hash, true);
 
             if block_kind.extends_canonical_head() {
                 // if the block can be traced back to the canonical head, we were able to fully
@@ -599,11 +638,24 @@ impl<DB: Database, C: Consensus, EF: ExecutorFactory> BlockchainTree<DB, C, EF>
         self.block_chain_id_generator += 1;
 
         self.block_indices.insert_chain(chain_id, &chain);
+        self.side_chain_changes.extend(chain.blocks().values().map(|block| (block.hash, true)));
         // add chain_id -> chain index
         self.chains.insert(chain_id, chain);
         Some(chain_id)
     }
 
+    /// Marks the blocks of a chain that was removed from the tree for removal from the
+    /// [tables::SidechainBlocks] table.
+    fn remove_side_chain_blocks(&mut self, chain: &AppendableChain) {
+        self.side_chain_changes.extend(chain.blocks().values().map(|block| (block.hash, false)));
+    }
+
+    /// Returns the side chain block with the given hash.
+    fn side_chain_block(&self, block_hash: &BlockHash) -> Option<&SealedBlockWithSenders> {
+        let chain_id = self.block_indices.get_blocks_chain_id(block_hash)?;
+        self.chains.get(&chain_id)?.blocks().values().find(|block| block.hash == *block_hash)
+    }
+
     /// Checks the block buffer for the given block.
     pub fn get_buffered_block(&self, hash: &BlockHash) -> Option<&SealedBlockWithSenders> {
         self.buffered_blocks.block_by_hash(hash)
@@ -723,7 +775,90 @@ impl<DB: Database, C: Consensus, EF: ExecutorFactory> BlockchainTree<DB, C, EF>
             return Err(InsertBlockError::consensus_error(err, block.block))
         }
 
-        Ok(InsertPayloadOk::Inserted(self.try_insert_validated_block(block)?))
+        // the block is inserted below, so only the restored blocks it builds on are executed
+        if self.restored_blocks.remove(&block.hash).is_some() {
+            self.side_chain_changes.insert(block.hash, false);
+        }
+        self.insert_restored_blocks(block.parent_hash);
+
+        let status = self.try_insert_validated_block(block);
+        self.persist_side_chain_changes();
+        Ok(InsertPayloadOk::Inserted(status?))
+    }
+
+    /// Executes the restored side chain block with the given hash, along with its restored
+    /// ancestors, and inserts them into the tree.
+    ///
+    /// Blocks that fail to be inserted are dropped.
+    fn insert_restored_blocks(&mut self, block_hash: BlockHash) {
+        let mut blocks = Vec::new();
+        let mut next = block_hash;
+        while let Some(block) = self.restored_blocks.remove(&next) {
+            next = block.parent_hash;
+            blocks.push(block);
+        }
+
+        for block in blocks.into_iter().rev() {
+            let block_hash = block.hash;
+            trace!(target: "blockchain_tree", block = ?block.num_hash(), "Executing restored side chain block");
+            // the block might have been inserted again in the meantime
+            let result = match self.is_block_known(block.num_hash()) {
+                Ok(Some(_)) => continue,
+                Ok(None) => match self.validate_block(&block) {
+                    Ok(_) => self.try_insert_validated_block(block),
+                    Err(err) => Err(InsertBlockError::consensus_error(err, block.block)),
+                },
+                Err(err) => Err(InsertBlockError::new(block.block, err)),
+            };
+            match result {
+                Ok(BlockStatus::Valid | BlockStatus::Accepted) => {}
+                Ok(status) => {
+                    trace!(target: "blockchain_tree", ?block_hash, ?status, "Dropping stale side chain block");
+                    self.side_chain_changes.insert(block_hash, false);
+                }
+                Err(err) => {
+                    debug!(target: "blockchain_tree", ?err, "Failed to insert restored side chain block");
+                    self.side_chain_changes.insert(block_hash, false);
+                }
+            }
+        }
+    }
+
+    /// Writes the side chain changes to the [tables::SidechainBlocks] table.
+    ///
+    /// Failures are logged and the changes are retried with the next update, since the blocks can
+    /// still be fetched from the network if they are lost.
+    fn persist_side_chain_changes(&mut self) {
+        if self.side_chain_changes.is_empty() {
+            return
+        }
+
+        let result = self.externals.db.tx_mut().and_then(|tx| {
+            self.write_side_chain_changes(&tx)?;
+            tx.commit()
+        });
+        match result {
+            Ok(_) => self.side_chain_changes.clear(),
+            Err(err) => {
+                warn!(target: "blockchain_tree", ?err, "Failed to persist side chain blocks")
+            }
+        }
+    }
+
+    /// Writes the side chain changes with the given transaction, without clearing them.
+    fn write_side_chain_changes<'a, TX: DbTxMut<'a>>(&self, tx: &TX) -> Result<(), DatabaseError> {
+        for (block_hash, inserted) in &self.side_chain_changes {
+            // the block might have been removed from the tree after it was inserted
+            match inserted.then(|| self.side_chain_block(block_hash)).flatten() {
+                Some(block) => {
+                    tx.put::<tables::SidechainBlocks>(*block_hash, block.clone().into())?
+                }
+                None => {
+                    tx.delete::<tables::SidechainBlocks>(*block_hash, None)?;
+                }
+            }
+        }
+        Ok(())
     }
 
     /// Finalize blocks up until and including `finalized_block`, and remove them from the tree.
@@ -737,10 +872,22 @@ impl<DB: Database, C: Consensus, EF: ExecutorFactory> BlockchainTree<DB, C, EF>
         while let Some(chain_id) = remove_chains.pop_first() {
             if let Some(chain) = self.chains.remove(&chain_id) {
                 remove_chains.extend(self.block_indices.remove_chain(&chain));
+                self.remove_side_chain_blocks(&chain);
             }
         }
         // clean block buffer.
         self.buffered_blocks.clean_old_blocks(finalized_block);
+        // drop restored blocks that can no longer be reorged to
+        let side_chain_changes = &mut self.side_chain_changes;
+        self.restored_blocks.retain(|hash, block| {
+            let keep = block.number > finalized_block;
+            if !keep {
+                side_chain_changes.insert(*hash, false);
+            }
+            keep
+        });
+
+        self.persist_side_chain_changes();
     }
 
     /// Reads the last `N` canonical hashes from the database and updates the block indices of the
@@ -787,6 +934,7 @@ impl<DB: Database, C: Consensus, EF: ExecutorFactory> BlockchainTree<DB, C, EF>
         while let Some(chain_id) = remove_chains.first() {
             if let Some(chain) = self.chains.remove(chain_id) {
                 remove_chains.extend(self.block_indices.remove_chain(&chain));
+                self.remove_side_chain_blocks(&chain);
             }
         }
 
@@ -806,6 +954,19 @@ impl<DB: Database, C: Consensus, EF: ExecutorFactory> BlockchainTree<DB, C, EF>
             self.try_connect_buffered_blocks(block)
         }
 
+        // drop restored blocks that became canonical
+        let block_indices = &self.block_indices;
+        let side_chain_changes = &mut self.side_chain_changes;
+        self.restored_blocks.retain(|hash, block| {
+            let keep = block_indices.canonical_hash(&block.number) != Some(*hash);
+            if !keep {
+                side_chain_changes.insert(*hash, false);
+            }
+            keep
+        });
+
+        self.persist_side_chain_changes();
+
         Ok(())
     }
 
@@ -906,6 +1067,10 @@ impl<DB: Database, C: Consensus, EF: ExecutorFactory> BlockchainTree<DB, C, EF>
     #[track_caller]
     #[instrument(skip(self), target = "blockchain_tree")]
     pub fn make_canonical(&mut self, block_hash: &BlockHash) -> Result<CanonicalOutcome, Error> {
+        if self.restored_blocks.contains_key(block_hash) {
+            self.insert_restored_blocks(*block_hash);
+        }
+
         let old_block_indices = self.block_indices.clone();
         let old_buffered_blocks = self.buffered_blocks.parent_to_child.clone();
 
@@ -970,6 +1135,8 @@ impl<DB: Database, C: Consensus, EF: ExecutorFactory> BlockchainTree<DB, C, EF>
         }
         // update canonical index
         self.block_indices.canonicalize_blocks(new_canon_chain.blocks());
+        self.side_chain_changes
+            .extend(new_canon_chain.blocks().values().map(|block| (block.hash, false)));
 
         // event about new canonical chain.
         let chain_notification;
@@ -1055,8 +1222,9 @@ impl<DB: Database, C: Consensus, EF: ExecutorFactory> BlockchainTree<DB, C, EF>
         self.canon_state_notification_sender.subscribe()
     }
 
-    /// Canonicalize the given chain and commit it to the database.
-    fn commit_canonical(&self, chain: Chain) -> Result<(), Error> {
+    /// Canonicalize the given chain and commit it to the database, along with the changes to the
+    /// side chains since the last commit.
+    fn commit_canonical(&mut self, chain: Chain) -> Result<(), Error> {
         let provider = DatabaseProvider::new_rw(
             self.externals.db.tx_mut()?,
             self.externals.chain_spec.clone(),
@@ -1072,7 +1240,10 @@ impl<DB: Database, C: Consensus, EF: ExecutorFactory> BlockchainTree<DB, C, EF>
             )
             .map_err(|e| BlockExecutionError::CanonicalCommit { inner: e.to_string() })?;
 
+        self.write_side_chain_changes(provider.tx_ref())?;
+
         provider.commit()?;
+        self.side_chain_changes.clear();
 
         Ok(())
     }
@@ -1092,6 +1263,7 @@ impl<DB: Database, C: Consensus, EF: ExecutorFactory> BlockchainTree<DB, C, EF>
             // insert old canonical chain to BlockchainTree.
             self.insert_chain(AppendableChain::new(old_canon_chain));
         }
+        self.persist_side_chain_changes();
 
         Ok(())
     }
@@ -1645,4 +1817,58 @@ mod tests {
             .with_buffered_blocks(BTreeMap::from([]))
             .assert(&tree);
     }
+
+    #[tokio::test]
+    async fn restore_side_chains() {
+        let data = BlockChainTestData::default_with_numbers(11, 12);
+        let (block1, exec1) = data.blocks[0].clone();
+        let (block2, exec2) = data.blocks[1].clone();
+        let genesis = data.genesis;
+
+        let externals = setup_externals(vec![exec1, exec2.clone()]);
+        let db = externals.db.clone();
+        setup_genesis(db.clone(), genesis);
+
+        let config = BlockchainTreeConfig::new(1, 2, 3, 2);
+        let (sender, _) = tokio::sync::broadcast::channel(10);
+        let mut tree = BlockchainTree::new(externals, sender.clone(), config, None)
+            .expect("failed to create tree");
+        tree.finalize_block(10);
+
+        assert_eq!(
+            tree.insert_block(block1.clone()).unwrap(),
+            InsertPayloadOk::Inserted(BlockStatus::Valid)
+        );
+        assert_eq!(
+            tree.insert_block(block2.clone()).unwrap(),
+            InsertPayloadOk::Inserted(BlockStatus::Valid)
+        );
+        // side chain blocks are persisted once they are inserted
+        assert!(db.tx().unwrap().get::<tables::SidechainBlocks>(block1.hash).unwrap().is_some());
+        assert!(db.tx().unwrap().get::<tables::SidechainBlocks>(block2.hash).unwrap().is_some());
+
+        assert_matches!(
+            tree.make_canonical(&block1.hash),
+            Ok(CanonicalOutcome::Committed { head }) if head.hash == block1.hash
+        );
+        assert!(db.tx().unwrap().get::<tables::SidechainBlocks>(block1.hash).unwrap().is_none());
+        assert!(db.tx().unwrap().get::<tables::SidechainBlocks>(block2.hash).unwrap().is_some());
+        drop(tree);
+
+        // the side chain block is restored on startup, but not executed
+        let mut externals = setup_externals(vec![exec2]);
+        externals.db = db.clone();
+        let mut tree =
+            BlockchainTree::new(externals, sender, config, None).expect("failed to create tree");
+        assert!(tree.chains.is_empty());
+        assert!(tree.restored_blocks.contains_key(&block2.hash));
+
+        // the block is executed once it is needed
+        assert_matches!(
+            tree.make_canonical(&block2.hash),
+            Ok(CanonicalOutcome::Committed { head }) if head.hash == block2.hash
+        );
+        assert!(tree.restored_blocks.is_empty());
+        assert!(db.tx().unwrap().get::<tables::SidechainBlocks>(block2.hash).unwrap().is_none());
+    }
 }
@@ -4,7 +4,7 @@ use reth_downloaders::{
     bodies::bodies::BodiesDownloaderBuilder,
     headers::reverse_headers::ReverseHeadersDownloaderBuilder,
 };
-use reth_network::{NetworkConfigBuilder, PeersConfig, SessionsConfig};
+use reth_network::{peers::PersistedPeersState, NetworkConfigBuilder, PeersConfig, SessionsConfig};
 use reth_primitives::PruneModes;
 use secp256k1::SecretKey;
 use serde::{Deserialize, Serialize};
@@ -34,11 +34,16 @@ impl Config {
         peers_file: Option<PathBuf>,
         secret_key: SecretKey,
     ) -> NetworkConfigBuilder {
+        let peers_state_file = peers_file.as_ref().map(PersistedPeersState::file_path);
         let peer_config = self
             .peers
             .clone()
             .with_basic_nodes_from_file(peers_file)
             .unwrap_or_else(|_| self.peers.clone());
+        let peer_config = peer_config
+            .clone()
+            .with_persisted_state_from_file(peers_state_file)
+            .unwrap_or(peer_config);
 
         let discv4 =
             Discv4Config::builder().external_ip_resolver(Some(nat_resolution_method)).clone();
@@ -212,6 +217,11 @@ pub struct ExecutionConfig {
     pub max_changes: Option<u64>,
     /// The maximum gas to process before the execution stage commits.
     pub max_cumulative_gas: Option<u64>,
+    /// Whether to prefetch the state of the next block on background threads while a block
+    /// executes.
+    pub prefetch_state: bool,
+    /// Whether to execute the transactions of a block speculatively in parallel.
+    pub parallel_execution: bool,
 }
 
 impl Default for ExecutionConfig {
@@ -221,6 +231,8 @@ impl Default for ExecutionConfig {
             max_changes: Some(5_000_000),
             // 50k full blocks of 30M gas
             max_cumulative_gas: Some(30_000_000 * 50_000),
+            prefetch_state: false,
+            parallel_execution: false,
         }
     }
 }
@@ -249,11 +261,14 @@ pub struct MerkleConfig {
     /// The threshold (in number of blocks) for switching from incremental trie building of changes
     /// to whole rebuild.
     pub clean_threshold: u64,
+    /// Whether to compute the storage roots of accounts with changed storage in parallel when
+    /// updating the trie incrementally.
+    pub parallel_storage_roots: bool,
 }
 
 impl Default for MerkleConfig {
     fn default() -> Self {
-        Self { clean_threshold: 50_000 }
+        Self { clean_threshold: 50_000, parallel_storage_roots: false }
     }
 }
 
@@ -22,5 +22,8 @@ pub mod headers;
 /// interacting with the network implementation
 pub mod error;
 
+/// Traits for implementing `snap/1` state clients.
+pub mod snap;
+
 /// Priority enum for BlockHeader and BlockBody requests
 pub mod priority;
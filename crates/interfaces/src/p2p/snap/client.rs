@@ -0,0 +1,30 @@
+use crate::p2p::{download::DownloadClient, error::PeerRequestResult};
+use futures::Future;
+use reth_eth_wire::{
+    AccountRange, ByteCodes, GetAccountRange, GetByteCodes, GetStorageRanges, GetTrieNodes,
+    StorageRanges, TrieNodes,
+};
+use std::pin::Pin;
+
+/// The snap request future type
+pub type SnapFut<T> = Pin<Box<dyn Future<Output = PeerRequestResult<T>> + Send + Sync>>;
+
+/// A client capable of serving `snap/1` requests, see
+/// <https://github.com/ethereum/devp2p/blob/master/caps/snap.md>.
+///
+/// The `request_id` of the requests is assigned by the client, the value set by the caller is
+/// ignored.
+#[auto_impl::auto_impl(&, Arc, Box)]
+pub trait SnapClient: DownloadClient {
+    /// Fetches a range of accounts from the state trie with the given root.
+    fn get_account_range(&self, request: GetAccountRange) -> SnapFut<AccountRange>;
+
+    /// Fetches the storage slots of the given accounts from the state trie with the given root.
+    fn get_storage_ranges(&self, request: GetStorageRanges) -> SnapFut<StorageRanges>;
+
+    /// Fetches the bytecodes with the given hashes.
+    fn get_byte_codes(&self, request: GetByteCodes) -> SnapFut<ByteCodes>;
+
+    /// Fetches the trie nodes at the given paths of the state trie with the given root.
+    fn get_trie_nodes(&self, request: GetTrieNodes) -> SnapFut<TrieNodes>;
+}
@@ -0,0 +1,4 @@
+/// Trait definition for [`SnapClient`]
+///
+/// [`SnapClient`]: client::SnapClient
+pub mod client;
@@ -96,4 +96,7 @@ pub enum ProviderError {
     },
     #[error("State at block #{0} is pruned")]
     StateAtBlockPruned(BlockNumber),
+    /// Thrown when the body of a block was removed by history expiry.
+    #[error("Body of block #{0} is pruned")]
+    BlockBodyPruned(BlockNumber),
 }
@@ -1,5 +1,5 @@
 //! Traits used when interacting with the sync status of the network.
-use reth_primitives::Head;
+use reth_primitives::{stage::StageProgress, Head};
 
 /// A type that provides information about whether the node is currently syncing and the network is
 /// currently serving syncing related requests.
@@ -12,6 +12,13 @@ pub trait SyncStateProvider: Send + Sync {
     fn is_initially_syncing(&self) -> bool;
 }
 
+/// A type that provides the progress of the pipeline stages.
+#[auto_impl::auto_impl(&, Arc, Box)]
+pub trait SyncProgressProvider: std::fmt::Debug + Send + Sync {
+    /// Returns the progress of all stages that reported a checkpoint, in pipeline order.
+    fn sync_progress(&self) -> Vec<StageProgress>;
+}
+
 /// An updater for updating the [SyncState] and status of the network.
 ///
 /// The node is either syncing, or it is idle.
@@ -84,6 +84,27 @@ impl BanList {
         (ips, peers)
     }
 
+    /// Removes all entries.
+    ///
+    /// Returns the removed entries.
+    pub fn clear(&mut self) -> (Vec<IpAddr>, Vec<PeerId>) {
+        let ips = self.banned_ips.drain().map(|(ip, _)| ip).collect();
+        let peers = self.banned_peers.drain().map(|(peer, _)| peer).collect();
+        (ips, peers)
+    }
+
+    /// Returns an iterator over all banned peers and the timestamp until which they are banned,
+    /// `None` if banned indefinitely.
+    pub fn banned_peers(&self) -> impl Iterator<Item = (PeerId, Option<Instant>)> + '_ {
+        self.banned_peers.iter().map(|(peer, until)| (*peer, *until))
+    }
+
+    /// Returns an iterator over all banned ip addresses and the timestamp until which they are
+    /// banned, `None` if banned indefinitely.
+    pub fn banned_ips(&self) -> impl Iterator<Item = (IpAddr, Option<Instant>)> + '_ {
+        self.banned_ips.iter().map(|(ip, until)| (*ip, *until))
+    }
+
     /// Returns true if either the given peer id _or_ ip address is banned.
     #[inline]
     pub fn is_banned(&self, peer_id: &PeerId, ip: &IpAddr) -> bool {
@@ -175,6 +196,22 @@ mod tests {
         assert!(!banlist.is_banned_ip(&ip));
     }
 
+    #[test]
+    fn can_clear() {
+        let peer = PeerId::random();
+        let ip = IpAddr::from([1, 1, 1, 1]);
+        let mut banlist = BanList::default();
+        banlist.ban_peer(peer);
+        banlist.ban_ip_until(ip, Instant::now());
+        assert_eq!(banlist.banned_peers().collect::<Vec<_>>(), vec![(peer, None)]);
+        assert_eq!(banlist.banned_ips().count(), 1);
+
+        let (ips, peers) = banlist.clear();
+        assert_eq!(ips, vec![ip]);
+        assert_eq!(peers, vec![peer]);
+        assert!(!banlist.is_banned(&peer, &ip));
+    }
+
     #[test]
     fn cannot_ban_non_global() {
         let mut ip = IpAddr::from([0, 0, 0, 0]);
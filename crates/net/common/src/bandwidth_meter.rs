@@ -47,6 +47,8 @@ struct BandwidthMeterInner {
     inbound: AtomicU64,
     /// Measures the number of outbound packets
     outbound: AtomicU64,
+    /// The meter that also counts the traffic of this meter, if any
+    parent: Option<BandwidthMeter>,
 }
 
 /// Public shareable struct used for getting bandwidth metering info
@@ -71,6 +73,36 @@ impl BandwidthMeter {
     pub fn total_outbound(&self) -> u64 {
         self.inner.outbound.load(Ordering::Relaxed)
     }
+
+    /// Returns a new [`BandwidthMeter`] whose traffic is also counted by this meter.
+    ///
+    /// This can be used to meter a single stream, while still keeping track of the totals of all
+    /// streams.
+    pub fn child(&self) -> Self {
+        Self {
+            inner: Arc::new(BandwidthMeterInner {
+                inbound: AtomicU64::new(0),
+                outbound: AtomicU64::new(0),
+                parent: Some(self.clone()),
+            }),
+        }
+    }
+
+    /// Adds the number of downloaded bytes to this meter and its parents.
+    fn add_inbound(&self, num_bytes: u64) {
+        self.inner.inbound.fetch_add(num_bytes, Ordering::Relaxed);
+        if let Some(parent) = &self.inner.parent {
+            parent.add_inbound(num_bytes);
+        }
+    }
+
+    /// Adds the number of uploaded bytes to this meter and its parents.
+    fn add_outbound(&self, num_bytes: u64) {
+        self.inner.outbound.fetch_add(num_bytes, Ordering::Relaxed);
+        if let Some(parent) = &self.inner.parent {
+            parent.add_outbound(num_bytes);
+        }
+    }
 }
 
 impl Default for BandwidthMeter {
@@ -79,6 +111,7 @@ impl Default for BandwidthMeter {
             inner: Arc::new(BandwidthMeterInner {
                 inbound: AtomicU64::new(0),
                 outbound: AtomicU64::new(0),
+                parent: None,
             }),
         }
     }
@@ -132,10 +165,7 @@ impl<Stream: AsyncRead> AsyncRead for MeteredStream<Stream> {
             ready!(this.inner.poll_read(cx, buf))?;
             buf.filled().len() - init_num_bytes
         };
-        this.meter
-            .inner
-            .inbound
-            .fetch_add(u64::try_from(num_bytes).unwrap_or(u64::max_value()), Ordering::Relaxed);
+        this.meter.add_inbound(u64::try_from(num_bytes).unwrap_or(u64::max_value()));
         Poll::Ready(Ok(()))
     }
 }
@@ -148,10 +178,7 @@ impl<Stream: AsyncWrite> AsyncWrite for MeteredStream<Stream> {
     ) -> Poll<io::Result<usize>> {
         let this = self.project();
         let num_bytes = ready!(this.inner.poll_write(cx, buf))?;
-        this.meter
-            .inner
-            .outbound
-            .fetch_add(u64::try_from(num_bytes).unwrap_or(u64::max_value()), Ordering::Relaxed);
+        this.meter.add_outbound(u64::try_from(num_bytes).unwrap_or(u64::max_value()));
         Poll::Ready(Ok(num_bytes))
     }
 
@@ -275,4 +302,30 @@ mod tests {
         assert_bandwidth_counts(&shared_client_bandwidth_meter, 8, 8);
         assert_bandwidth_counts(&shared_server_bandwidth_meter, 8, 8);
     }
+
+    #[tokio::test]
+    async fn test_child_meter() {
+        let (client, server) = duplex(64);
+
+        let total_meter = BandwidthMeter::default();
+        let client_meter = total_meter.child();
+
+        let mut metered_client = MeteredStream::new_with_meter(client, client_meter.clone());
+        let mut metered_server = MeteredStream::new(server);
+
+        duplex_stream_ping_pong(&mut metered_client, &mut metered_server).await;
+
+        assert_bandwidth_counts(&client_meter, 4, 4);
+        assert_bandwidth_counts(&total_meter, 4, 4);
+
+        // traffic of the parent is not counted by the child
+        let (other_client, other_server) = duplex(64);
+        let mut metered_other_client =
+            MeteredStream::new_with_meter(other_client, total_meter.clone());
+        let mut metered_other_server = MeteredStream::new(other_server);
+        duplex_stream_ping_pong(&mut metered_other_client, &mut metered_other_server).await;
+
+        assert_bandwidth_counts(&client_meter, 4, 4);
+        assert_bandwidth_counts(&total_meter, 8, 8);
+    }
 }
@@ -0,0 +1,171 @@
+//! A set of configuration parameters for the discv5 service.
+
+use crate::DEFAULT_DISCOVERY_V5_ADDRESS;
+use reth_net_nat::{NatResolver, ResolveNatInterval};
+use reth_primitives::ForkId;
+use std::{
+    net::{IpAddr, SocketAddr},
+    time::Duration,
+};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Configuration parameters of the discv5 service.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Discv5Config {
+    /// The UDP address to listen on. Default: `0.0.0.0:9000`.
+    ///
+    /// The port is advertised in the local ENR, so it must not be `0`.
+    pub listen_addr: SocketAddr,
+    /// The TCP port of the RLPx listener that is advertised in the local ENR. Default: 30303.
+    pub tcp_port: u16,
+    /// The IP address to advertise in the local ENR.
+    ///
+    /// If not set, the IP resolved by the `external_ip_resolver` or the IP of the listen address
+    /// is used if it is specified. Otherwise the address is learned from the `PONG` responses of
+    /// other nodes.
+    pub external_ip: Option<IpAddr>,
+    /// If configured, try to resolve the public IP to advertise in the local ENR.
+    pub external_ip_resolver: Option<NatResolver>,
+    /// If configured and a `external_ip_resolver` is configured, try to resolve the external ip
+    /// using this interval.
+    pub resolve_external_ip_interval: Option<Duration>,
+    /// ENRs of the nodes to boot from.
+    pub bootstrap_nodes: Vec<discv5::Enr>,
+    /// The [`ForkId`] to advertise in the `eth` entry of the local ENR.
+    pub fork_id: Option<ForkId>,
+    /// Whether to automatically lookup peers.
+    pub enable_lookup: bool,
+    /// The rate at which random lookups should be triggered. Default: 20s.
+    pub lookup_interval: Duration,
+    /// The duration after which a request is considered timed out. Default: 1s.
+    pub request_timeout: Duration,
+    /// The default duration for which nodes are banned for. If set to `None`, bans last
+    /// indefinitely. Default is 1 hour.
+    pub ban_duration: Option<Duration>,
+}
+
+impl Discv5Config {
+    /// Returns a new default builder instance
+    pub fn builder() -> Discv5ConfigBuilder {
+        Default::default()
+    }
+
+    /// Returns the corresponding [`ResolveNatInterval`], if a [NatResolver] and an interval was
+    /// configured
+    pub fn resolve_external_ip_interval(&self) -> Option<ResolveNatInterval> {
+        let resolver = self.external_ip_resolver?;
+        let interval = self.resolve_external_ip_interval?;
+        Some(ResolveNatInterval::interval(resolver, interval))
+    }
+}
+
+impl Default for Discv5Config {
+    fn default() -> Self {
+        Self {
+            listen_addr: DEFAULT_DISCOVERY_V5_ADDRESS,
+            tcp_port: reth_discv4::DEFAULT_DISCOVERY_PORT,
+            external_ip: None,
+            external_ip_resolver: None,
+            resolve_external_ip_interval: Some(Duration::from_secs(60 * 5)),
+            bootstrap_nodes: Default::default(),
+            fork_id: None,
+            enable_lookup: true,
+            lookup_interval: Duration::from_secs(20),
+            request_timeout: Duration::from_secs(1),
+            ban_duration: Some(Duration::from_secs(60 * 60)), // 1 hour
+        }
+    }
+}
+
+/// Builder type for [`Discv5Config`]
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Discv5ConfigBuilder {
+    config: Discv5Config,
+}
+
+impl Discv5ConfigBuilder {
+    /// Sets the UDP address to listen on.
+    pub fn listen_addr(&mut self, listen_addr: SocketAddr) -> &mut Self {
+        self.config.listen_addr = listen_addr;
+        self
+    }
+
+    /// Sets the TCP port of the RLPx listener that is advertised in the local ENR.
+    pub fn tcp_port(&mut self, tcp_port: u16) -> &mut Self {
+        self.config.tcp_port = tcp_port;
+        self
+    }
+
+    /// Sets the IP address to advertise in the local ENR.
+    pub fn external_ip(&mut self, external_ip: Option<IpAddr>) -> &mut Self {
+        self.config.external_ip = external_ip;
+        self
+    }
+
+    /// Configures if and how the external IP of the node should be resolved.
+    pub fn external_ip_resolver(&mut self, external_ip_resolver: Option<NatResolver>) -> &mut Self {
+        self.config.external_ip_resolver = external_ip_resolver;
+        self
+    }
+
+    /// Sets the interval at which the external IP is to be resolved.
+    pub fn resolve_external_ip_interval(
+        &mut self,
+        resolve_external_ip_interval: Option<Duration>,
+    ) -> &mut Self {
+        self.config.resolve_external_ip_interval = resolve_external_ip_interval;
+        self
+    }
+
+    /// Adds a boot node
+    pub fn add_boot_node(&mut self, node: discv5::Enr) -> &mut Self {
+        self.config.bootstrap_nodes.push(node);
+        self
+    }
+
+    /// Adds multiple boot nodes
+    pub fn add_boot_nodes(&mut self, nodes: impl IntoIterator<Item = discv5::Enr>) -> &mut Self {
+        self.config.bootstrap_nodes.extend(nodes);
+        self
+    }
+
+    /// Sets the [`ForkId`] to advertise in the `eth` entry of the local ENR.
+    pub fn fork_id(&mut self, fork_id: ForkId) -> &mut Self {
+        self.config.fork_id = Some(fork_id);
+        self
+    }
+
+    /// Whether to automatically lookup peers.
+    pub fn enable_lookup(&mut self, enable_lookup: bool) -> &mut Self {
+        self.config.enable_lookup = enable_lookup;
+        self
+    }
+
+    /// Sets the lookup interval duration.
+    pub fn lookup_interval(&mut self, lookup_interval: Duration) -> &mut Self {
+        self.config.lookup_interval = lookup_interval;
+        self
+    }
+
+    /// Sets the timeout after which requests are considered timed out.
+    pub fn request_timeout(&mut self, duration: Duration) -> &mut Self {
+        self.config.request_timeout = duration;
+        self
+    }
+
+    /// Set the default duration for which nodes are banned for. If set to `None`, bans last
+    /// indefinitely. Default is 1 hour.
+    pub fn ban_duration(&mut self, ban_duration: Option<Duration>) -> &mut Self {
+        self.config.ban_duration = ban_duration;
+        self
+    }
+
+    /// Returns the configured [`Discv5Config`]
+    pub fn build(&self) -> Discv5Config {
+        self.config.clone()
+    }
+}
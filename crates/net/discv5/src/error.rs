@@ -0,0 +1,27 @@
+//! Error types that can occur in this crate.
+
+/// Errors that can occur when launching the discv5 service.
+#[derive(Debug, thiserror::Error)]
+pub enum Discv5Error {
+    /// Failed to bind the UDP socket.
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    /// Failed to create or update the local ENR.
+    #[error("Failed to build local ENR: {0}")]
+    Enr(String),
+    /// Failed to initialize the discv5 service.
+    #[error("Failed to initialize discv5: {0}")]
+    Init(&'static str),
+    /// The discv5 service failed.
+    #[error("discv5 service error: {0:?}")]
+    Service(discv5::Discv5Error),
+}
+
+impl From<discv5::Discv5Error> for Discv5Error {
+    fn from(err: discv5::Discv5Error) -> Self {
+        match err {
+            discv5::Discv5Error::Io(err) => Discv5Error::Io(err),
+            err => Discv5Error::Service(err),
+        }
+    }
+}
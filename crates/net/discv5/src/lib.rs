@@ -0,0 +1,518 @@
+#![cfg_attr(docsrs, feature(doc_cfg))]
+#![doc(
+    html_logo_url = "https://raw.githubusercontent.com/paradigmxyz/reth/main/assets/reth-docs.png",
+    html_favicon_url = "https://avatars0.githubusercontent.com/u/97369466?s=256",
+    issue_tracker_base_url = "https://github.com/paradigmxzy/reth/issues/"
+)]
+#![warn(missing_docs, unused_crate_dependencies)]
+#![deny(unused_must_use, rust_2018_idioms)]
+#![doc(test(
+    no_crate_inject,
+    attr(deny(warnings, rust_2018_idioms), allow(dead_code, unused_variables))
+))]
+
+//! Discovery v5 support: <https://github.com/ethereum/devp2p/blob/master/discv5/discv5.md>
+//!
+//! The wire protocol, including the session handshake with its `WHOAREYOU` challenge, `FINDNODE`
+//! queries and ENR updates, is implemented by the [`discv5`] crate. This crate runs it next to
+//! discv4: it advertises the RLPx endpoint and the `eth` fork id in the local ENR, see
+//! <https://github.com/ethereum/devp2p/blob/master/enr-entries/eth.md>, and converts the ENRs of
+//! discovered nodes into [`NodeRecord`]s.
+//!
+//! This implementation consists of a [`Discv5`] and [`Discv5Service`] pair. The service performs
+//! random lookups and produces a [`Discv5NodeRecordUpdate`] for every discovered node that
+//! advertises an RLPx endpoint. The [`Discv5`] serves as the frontend to interact with the
+//! service.
+//!
+//! ## Feature Flags
+//!
+//! - `serde` (default): Enable serde support
+use enr::{CombinedKey, EnrBuilder, NodeId};
+use futures::{future::BoxFuture, Stream, StreamExt};
+use reth_discv4::EnrForkIdEntry;
+use reth_net_nat::{NatResolver, ResolveNatInterval};
+use reth_primitives::{bytes::BytesMut, keccak256, ForkId, NodeRecord, PeerId};
+use reth_rlp::{Decodable, Encodable};
+use secp256k1::SecretKey;
+use std::{
+    collections::VecDeque,
+    fmt,
+    future::Future,
+    net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV4},
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+use tokio::{sync::mpsc, task::JoinHandle, time::Interval};
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::{debug, trace};
+
+pub mod error;
+pub use error::Discv5Error;
+
+mod config;
+pub use config::{Discv5Config, Discv5ConfigBuilder};
+
+// reexport the ENR type used by discv5
+pub use discv5::Enr;
+
+/// The default port for discv5 via UDP.
+pub const DEFAULT_DISCOVERY_V5_PORT: u16 = 9000;
+
+/// The default address for discv5 via UDP: "0.0.0.0:9000"
+pub const DEFAULT_DISCOVERY_V5_ADDRESS: SocketAddr =
+    SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, DEFAULT_DISCOVERY_V5_PORT));
+
+/// The key of the ENR entry that holds the `eth` fork id.
+const ETH_ENR_KEY: &[u8] = b"eth";
+
+/// The key of the ENR entry consensus layer clients advertise their fork digest with.
+const ETH2_ENR_KEY: &[u8] = b"eth2";
+
+/// The frontend of the discv5 service.
+#[derive(Clone)]
+pub struct Discv5 {
+    /// The underlying discv5 service.
+    inner: Arc<discv5::Discv5>,
+    /// The key used to sign updates of the local ENR.
+    enr_key: Arc<CombinedKey>,
+    /// The local node record.
+    local_node_record: NodeRecord,
+    /// The duration for which nodes are banned for.
+    ban_duration: Option<Duration>,
+}
+
+impl Discv5 {
+    /// Binds a new UDP socket and starts the discv5 service.
+    ///
+    /// The returned [`Discv5Service`] must be spawned or polled to perform lookups and to produce
+    /// discovered nodes.
+    pub async fn bind(
+        secret_key: SecretKey,
+        config: Discv5Config,
+    ) -> Result<(Self, Discv5Service), Discv5Error> {
+        let enr_key = combined_key(&secret_key)?;
+        let local_enr = local_enr(&enr_key, &config)?;
+
+        let mut local_node_record = NodeRecord::from_secret_key(config.listen_addr, &secret_key);
+        local_node_record.tcp_port = config.tcp_port;
+        if let Some(ip) = configured_external_ip(&config) {
+            local_node_record.address = ip;
+        }
+
+        let listen_config =
+            discv5::ListenConfig::from_ip(config.listen_addr.ip(), config.listen_addr.port());
+        let discv5_config = discv5::Discv5ConfigBuilder::new(listen_config)
+            .request_timeout(config.request_timeout)
+            .ban_duration(config.ban_duration)
+            .build();
+
+        let mut discv5 = discv5::Discv5::new(local_enr, combined_key(&secret_key)?, discv5_config)
+            .map_err(Discv5Error::Init)?;
+        discv5.start().await?;
+
+        for enr in config.bootstrap_nodes {
+            if let Err(err) = discv5.add_enr(enr) {
+                debug!(target: "discv5", %err, "Failed to add boot node");
+            }
+        }
+
+        let events = discv5.event_stream().await?;
+        let discv5 = Arc::new(discv5);
+
+        let lookup_interval = config.enable_lookup.then(|| {
+            let mut interval = tokio::time::interval(config.lookup_interval);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            interval
+        });
+
+        let enr_key = Arc::new(enr_key);
+        let service = Discv5Service {
+            discv5: Arc::clone(&discv5),
+            enr_key: Arc::clone(&enr_key),
+            resolve_external_ip_interval: config.resolve_external_ip_interval(),
+            events,
+            lookup_interval,
+            lookup: None,
+            queued_updates: Default::default(),
+            update_listeners: Default::default(),
+        };
+        let this =
+            Self { inner: discv5, enr_key, local_node_record, ban_duration: config.ban_duration };
+
+        Ok((this, service))
+    }
+
+    /// Returns the local node record.
+    pub fn local_node_record(&self) -> NodeRecord {
+        self.local_node_record
+    }
+
+    /// Returns the current local ENR.
+    pub fn local_enr(&self) -> discv5::Enr {
+        self.inner.local_enr()
+    }
+
+    /// Adds the node to the routing table.
+    pub fn add_node(&self, enr: discv5::Enr) {
+        if let Err(err) = self.inner.add_enr(enr) {
+            debug!(target: "discv5", %err, "Failed to add node");
+        }
+    }
+
+    /// Sets the `eth` entry of the local ENR to the given [`ForkId`].
+    ///
+    /// This increments the sequence number of the ENR, so that peers request it again.
+    pub fn set_fork_id(&self, fork_id: ForkId) {
+        let mut buf = BytesMut::new();
+        EnrForkIdEntry::from(fork_id).encode(&mut buf);
+        let enr = self.inner.external_enr();
+        let mut enr = enr.write();
+        if let Err(err) = enr.insert_raw_rlp(ETH_ENR_KEY, buf.freeze(), &self.enr_key) {
+            debug!(target: "discv5", %err, "Failed to update eth fork id of local ENR");
+        }
+    }
+
+    /// Bans the [`PeerId`] and [`IpAddr`] in the discovery service.
+    pub fn ban(&self, peer_id: PeerId, ip: IpAddr) {
+        let expires = self.ban_expiry();
+        self.inner.ban_node(&node_id(peer_id), expires);
+        self.inner.ban_ip(ip, expires);
+    }
+
+    /// Bans the [`IpAddr`] in the discovery service.
+    pub fn ban_ip(&self, ip: IpAddr) {
+        self.inner.ban_ip(ip, self.ban_expiry());
+    }
+
+    fn ban_expiry(&self) -> Option<Instant> {
+        self.ban_duration.map(|duration| Instant::now() + duration)
+    }
+}
+
+impl fmt::Debug for Discv5 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Discv5").field("local_node_record", &self.local_node_record).finish()
+    }
+}
+
+/// The service that drives the discv5 lookups and produces discovered nodes.
+#[must_use = "Stream does nothing unless polled"]
+pub struct Discv5Service {
+    /// The underlying discv5 service.
+    discv5: Arc<discv5::Discv5>,
+    /// The key used to sign updates of the local ENR.
+    enr_key: Arc<CombinedKey>,
+    /// The interval at which to attempt resolving the external IP again.
+    resolve_external_ip_interval: Option<ResolveNatInterval>,
+    /// Events emitted by the discv5 service.
+    events: mpsc::Receiver<discv5::Discv5Event>,
+    /// Triggers random lookups, if enabled.
+    lookup_interval: Option<Interval>,
+    /// The currently active lookup.
+    lookup: Option<BoxFuture<'static, Result<Vec<discv5::Enr>, discv5::QueryError>>>,
+    /// Updates buffered until polled.
+    queued_updates: VecDeque<Discv5NodeRecordUpdate>,
+    /// All subscribers of discovered nodes.
+    update_listeners: Vec<mpsc::Sender<Discv5NodeRecordUpdate>>,
+}
+
+impl Discv5Service {
+    /// Creates a new channel for [`Discv5NodeRecordUpdate`]s
+    pub fn update_stream(&mut self) -> ReceiverStream<Discv5NodeRecordUpdate> {
+        let (tx, rx) = mpsc::channel(512);
+        self.update_listeners.push(tx);
+        ReceiverStream::new(rx)
+    }
+
+    /// Spawns this service onto a new task
+    ///
+    /// Note: requires a running runtime
+    pub fn spawn(mut self) -> JoinHandle<()> {
+        tokio::task::spawn(async move {
+            while let Some(update) = self.next().await {
+                self.notify_listeners(&update);
+            }
+            trace!(target: "discv5", "service terminated");
+        })
+    }
+
+    fn notify_listeners(&mut self, update: &Discv5NodeRecordUpdate) {
+        self.update_listeners.retain(|listener| listener.try_send(update.clone()).is_ok());
+    }
+
+    /// Sets the given IP address as the IP advertised in the local ENR.
+    ///
+    /// This increments the sequence number of the ENR if the IP changed.
+    fn set_external_ip_addr(&mut self, external_ip: IpAddr) {
+        let enr = self.discv5.external_enr();
+        let mut enr = enr.write();
+        let current = match external_ip {
+            IpAddr::V4(_) => enr.ip4().map(IpAddr::V4),
+            IpAddr::V6(_) => enr.ip6().map(IpAddr::V6),
+        };
+        if current != Some(external_ip) {
+            debug!(target: "discv5", ?external_ip, "Updating external ip");
+            if let Err(err) = enr.set_ip(external_ip, &self.enr_key) {
+                debug!(target: "discv5", %err, "Failed to update ip of local ENR");
+            }
+        }
+    }
+
+    fn on_enr(&mut self, enr: &discv5::Enr) {
+        if let Some(update) = convert_enr_node_record(enr) {
+            self.queued_updates.push_back(update);
+        }
+    }
+
+    fn on_event(&mut self, event: discv5::Discv5Event) {
+        match event {
+            discv5::Discv5Event::Discovered(enr) |
+            discv5::Discv5Event::EnrAdded { enr, .. } |
+            discv5::Discv5Event::SessionEstablished(enr, _) => self.on_enr(&enr),
+            _ => {}
+        }
+    }
+}
+
+impl Stream for Discv5Service {
+    type Item = Discv5NodeRecordUpdate;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            if let Some(update) = this.queued_updates.pop_front() {
+                return Poll::Ready(Some(update))
+            }
+
+            if let Some(Poll::Ready(Some(ip))) =
+                this.resolve_external_ip_interval.as_mut().map(|r| r.poll_tick(cx))
+            {
+                this.set_external_ip_addr(ip);
+            }
+
+            if let Some(lookup) = this.lookup.as_mut() {
+                if let Poll::Ready(res) = lookup.as_mut().poll(cx) {
+                    this.lookup = None;
+                    match res {
+                        Ok(enrs) => {
+                            trace!(target: "discv5", found = enrs.len(), "Lookup finished");
+                            for enr in enrs {
+                                this.on_enr(&enr);
+                            }
+                        }
+                        Err(err) => debug!(target: "discv5", ?err, "Lookup failed"),
+                    }
+                    continue
+                }
+            } else if let Some(interval) = this.lookup_interval.as_mut() {
+                if interval.poll_tick(cx).is_ready() {
+                    // topic-free lookup of a random target
+                    this.lookup = Some(Box::pin(this.discv5.find_node(NodeId::random())));
+                    continue
+                }
+            }
+
+            match this.events.poll_recv(cx) {
+                Poll::Ready(Some(event)) => this.on_event(event),
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Discv5Service {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Discv5Service").field("queued_updates", &self.queued_updates).finish()
+    }
+}
+
+/// A node discovered via discv5.
+#[derive(Debug, Clone)]
+pub struct Discv5NodeRecordUpdate {
+    /// Discovered node and it's addresses
+    pub node_record: NodeRecord,
+    /// The forkid of the node, if present in the ENR
+    pub fork_id: Option<ForkId>,
+}
+
+/// Converts an [Enr](discv5::Enr) into a [NodeRecord].
+///
+/// Returns `None` if the ENR does not advertise a TCP endpoint, or if it belongs to a consensus
+/// layer client, which only advertises an `eth2` entry.
+pub fn convert_enr_node_record(enr: &discv5::Enr) -> Option<Discv5NodeRecordUpdate> {
+    let eth = enr.get_raw_rlp(ETH_ENR_KEY);
+    if eth.is_none() && enr.get_raw_rlp(ETH2_ENR_KEY).is_some() {
+        return None
+    }
+
+    let public_key = secp256k1::PublicKey::from_slice(&enr.public_key().encode()).ok()?;
+    let node_record = NodeRecord {
+        address: enr.ip4().map(IpAddr::from).or_else(|| enr.ip6().map(IpAddr::from))?,
+        tcp_port: enr.tcp4().or_else(|| enr.tcp6())?,
+        udp_port: enr.udp4().or_else(|| enr.udp6())?,
+        id: PeerId::from_slice(&public_key.serialize_uncompressed()[1..]),
+    }
+    .into_ipv4_mapped();
+
+    let fork_id = eth.and_then(|mut eth| EnrForkIdEntry::decode(&mut eth).ok()).map(|e| e.fork_id);
+
+    Some(Discv5NodeRecordUpdate { node_record, fork_id })
+}
+
+/// Returns the discv5 [`NodeId`] of the peer.
+fn node_id(peer_id: PeerId) -> NodeId {
+    NodeId::new(&keccak256(peer_id).0)
+}
+
+fn combined_key(secret_key: &SecretKey) -> Result<CombinedKey, Discv5Error> {
+    CombinedKey::secp256k1_from_bytes(&mut secret_key.secret_bytes())
+        .map_err(|err| Discv5Error::Enr(err.to_string()))
+}
+
+/// Returns the external IP that is known without resolving it: either the configured IP or the IP
+/// of a [`NatResolver::ExternalIp`] resolver.
+fn configured_external_ip(config: &Discv5Config) -> Option<IpAddr> {
+    config.external_ip.or(match config.external_ip_resolver {
+        Some(NatResolver::ExternalIp(ip)) => Some(ip),
+        _ => None,
+    })
+}
+
+/// Builds the local ENR that advertises the RLPx endpoint and the `eth` fork id.
+fn local_enr(enr_key: &CombinedKey, config: &Discv5Config) -> Result<discv5::Enr, Discv5Error> {
+    let listen_ip = config.listen_addr.ip();
+    let ip = configured_external_ip(config).or((!listen_ip.is_unspecified()).then_some(listen_ip));
+
+    let mut builder = EnrBuilder::new("v4");
+    if let Some(ip) = ip {
+        builder.ip(ip);
+    }
+    if ip.unwrap_or(listen_ip).is_ipv4() {
+        builder.udp4(config.listen_addr.port()).tcp4(config.tcp_port);
+    } else {
+        builder.udp6(config.listen_addr.port()).tcp6(config.tcp_port);
+    }
+    if let Some(fork_id) = config.fork_id {
+        let mut buf = BytesMut::new();
+        EnrForkIdEntry::from(fork_id).encode(&mut buf);
+        builder.add_value_rlp(ETH_ENR_KEY, buf.freeze());
+    }
+
+    builder.build(enr_key).map_err(|err| Discv5Error::Enr(err.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::thread_rng;
+    use reth_primitives::{bytes::Bytes, ForkHash, Hardfork, MAINNET};
+    use secp256k1::SECP256K1;
+
+    async fn create_discv5(
+        boot_node: Option<discv5::Enr>,
+        fork_id: ForkId,
+    ) -> (Discv5, Discv5Service) {
+        let (secret_key, _) = SECP256K1.generate_keypair(&mut thread_rng());
+        // the port is advertised in the ENR, so it can't be `0`
+        let port = std::net::UdpSocket::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let mut builder = Discv5Config::builder();
+        builder
+            .listen_addr(SocketAddr::from((Ipv4Addr::LOCALHOST, port)))
+            .tcp_port(30303)
+            .fork_id(fork_id)
+            .lookup_interval(Duration::from_millis(200));
+        if let Some(boot_node) = boot_node {
+            builder.add_boot_node(boot_node);
+        }
+        Discv5::bind(secret_key, builder.build()).await.unwrap()
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn discover_boot_node_with_fork_id() {
+        reth_tracing::init_test_tracing();
+        let fork_id = Hardfork::Shanghai.fork_id(&MAINNET).unwrap();
+
+        let (boot_node, boot_service) = create_discv5(None, fork_id).await;
+        let _boot_service = boot_service.spawn();
+
+        let (_node, mut service) = create_discv5(Some(boot_node.local_enr()), fork_id).await;
+        let mut updates = service.update_stream();
+        let _service = service.spawn();
+
+        let update = tokio::time::timeout(Duration::from_secs(10), async {
+            loop {
+                let update = updates.next().await.unwrap();
+                if update.node_record.id == boot_node.local_node_record().id {
+                    return update
+                }
+            }
+        })
+        .await
+        .expect("boot node is discovered");
+
+        assert_eq!(update.node_record.tcp_port, 30303);
+        assert_eq!(update.node_record.udp_port, boot_node.local_node_record().udp_port);
+        assert_eq!(update.fork_id, Some(fork_id));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn update_local_fork_id() {
+        let fork_id = Hardfork::Paris.fork_id(&MAINNET).unwrap();
+        let (discv5, _service) = create_discv5(None, fork_id).await;
+
+        let update = convert_enr_node_record(&discv5.local_enr()).unwrap();
+        assert_eq!(update.node_record, discv5.local_node_record());
+        assert_eq!(update.fork_id, Some(fork_id));
+
+        let seq = discv5.local_enr().seq();
+        let new_fork_id = ForkId { hash: ForkHash([0xde, 0xad, 0xbe, 0xef]), next: 0 };
+        discv5.set_fork_id(new_fork_id);
+
+        let enr = discv5.local_enr();
+        assert!(enr.seq() > seq);
+        assert_eq!(convert_enr_node_record(&enr).unwrap().fork_id, Some(new_fork_id));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn advertise_nat_external_ip() {
+        let (secret_key, _) = SECP256K1.generate_keypair(&mut thread_rng());
+        let port = std::net::UdpSocket::bind("0.0.0.0:0").unwrap().local_addr().unwrap().port();
+        let external_ip = Ipv4Addr::new(1, 2, 3, 4);
+        let config = Discv5Config::builder()
+            .listen_addr(SocketAddr::from((Ipv4Addr::UNSPECIFIED, port)))
+            .external_ip_resolver(Some(NatResolver::ExternalIp(external_ip.into())))
+            .build();
+        let (discv5, mut service) = Discv5::bind(secret_key, config).await.unwrap();
+
+        assert_eq!(discv5.local_enr().ip4(), Some(external_ip));
+        assert_eq!(discv5.local_node_record().address, IpAddr::from(external_ip));
+
+        // a newly resolved IP updates the local ENR
+        let seq = discv5.local_enr().seq();
+        let resolved_ip = Ipv4Addr::new(5, 6, 7, 8);
+        service.set_external_ip_addr(resolved_ip.into());
+        let enr = discv5.local_enr();
+        assert_eq!(enr.ip4(), Some(resolved_ip));
+        assert!(enr.seq() > seq);
+    }
+
+    #[test]
+    fn ignore_consensus_layer_nodes() {
+        let (secret_key, _) = SECP256K1.generate_keypair(&mut thread_rng());
+        let key = combined_key(&secret_key).unwrap();
+
+        let mut builder = EnrBuilder::new("v4");
+        builder.ip4(Ipv4Addr::LOCALHOST).udp4(9000).tcp4(9000);
+        let enr = builder.build(&key).unwrap();
+        let update = convert_enr_node_record(&enr).unwrap();
+        assert_eq!(update.fork_id, None);
+
+        builder.add_value_rlp(ETH2_ENR_KEY, Bytes::from_static(&[0x80]));
+        let enr = builder.build(&key).unwrap();
+        assert!(convert_enr_node_record(&enr).is_none());
+    }
+}
@@ -15,7 +15,7 @@
 //!
 //! ## Feature Flags
 //!
-//! - `serde` (default): Enable serde support
+//! - `serde` (default): Enable serde support, including JSON output of published trees
 //! - `test-utils`: Export utilities for testing
 pub use crate::resolver::{DnsResolver, MapResolver, Resolver};
 use crate::{
@@ -54,6 +54,7 @@ use tracing::{debug, trace, warn};
 
 mod config;
 mod error;
+pub mod publish;
 mod query;
 pub mod resolver;
 mod sync;
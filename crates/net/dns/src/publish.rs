@@ -0,0 +1,327 @@
+//! Support for publishing node lists as [EIP-1459](https://eips.ethereum.org/EIPS/eip-1459) trees.
+//!
+//! The [DnsTreeBuilder] arranges the ENRs and links into the merkle tree described in
+//! [tree](crate::tree) and signs the root. The resulting [DnsTree] can be rendered as the TXT
+//! records of a zone, for example as a BIND zone file.
+//!
+//! Note: only ENRs can be published, since every ENR is signed by the key of the node it
+//! describes. A [NodeRecord](reth_primitives::NodeRecord) can not be converted into one: signed
+//! with the key of the tree, the record would describe a node with a different id. The ENRs can
+//! instead be collected from the network, for example with `reth p2p crawl`, and published with
+//! `reth p2p dns-tree`.
+
+use crate::tree::{BranchEntry, LinkEntry, NodeEntry, TreeRootEntry};
+use data_encoding::BASE32_NOPAD;
+use enr::{Enr, EnrError, EnrKey, EnrKeyUnambiguous};
+use reth_primitives::keccak256;
+use secp256k1::SecretKey;
+use std::{collections::BTreeMap, fmt::Write};
+
+/// The maximum number of children of a branch entry, so that it fits into a single TXT record.
+///
+/// Mirrors geth's `maxChildren`: a branch may not exceed 370 bytes and every child hash is 26
+/// bytes plus the separator.
+const MAX_CHILDREN: usize = 370 / (26 + 1);
+
+/// The maximum length of a single character-string in a TXT record.
+const MAX_TXT_STRING_LEN: usize = 255;
+
+/// Builds a [DnsTree] from a set of ENRs and links to other trees.
+#[derive(Debug, Clone)]
+pub struct DnsTreeBuilder<K: EnrKeyUnambiguous = SecretKey> {
+    enrs: Vec<Enr<K>>,
+    links: Vec<LinkEntry<K>>,
+    sequence_number: u64,
+}
+
+// === impl DnsTreeBuilder ===
+
+impl<K: EnrKeyUnambiguous> DnsTreeBuilder<K> {
+    /// Creates a new builder for a tree with the given sequence number.
+    ///
+    /// The sequence number must be increased with every update of the tree, so that clients
+    /// that already synced it pick up the changes.
+    pub fn new(sequence_number: u64) -> Self {
+        Self { enrs: Vec::new(), links: Vec::new(), sequence_number }
+    }
+
+    /// Adds a node to the tree.
+    pub fn add_enr(mut self, enr: Enr<K>) -> Self {
+        self.enrs.push(enr);
+        self
+    }
+
+    /// Adds multiple nodes to the tree.
+    pub fn add_enrs(mut self, enrs: impl IntoIterator<Item = Enr<K>>) -> Self {
+        self.enrs.extend(enrs);
+        self
+    }
+
+    /// Adds a link to another tree.
+    pub fn add_link(mut self, link: LinkEntry<K>) -> Self {
+        self.links.push(link);
+        self
+    }
+
+    /// Adds multiple links to other trees.
+    pub fn add_links(mut self, links: impl IntoIterator<Item = LinkEntry<K>>) -> Self {
+        self.links.extend(links);
+        self
+    }
+
+    /// Builds the tree and signs its root with the given key.
+    ///
+    /// If a node is included more than once, only the record with the highest sequence number is
+    /// kept.
+    pub fn build(self, key: &K) -> Result<DnsTree<K>, EnrError> {
+        let Self { enrs, links, sequence_number } = self;
+
+        // only keep the latest record of every node, ordered by node id
+        let mut nodes = BTreeMap::new();
+        for enr in enrs {
+            match nodes.get(&enr.node_id()) {
+                Some(existing) if existing.seq() >= enr.seq() => {}
+                _ => {
+                    nodes.insert(enr.node_id(), enr);
+                }
+            }
+        }
+        let nodes = nodes.into_values().map(|enr| NodeEntry { enr }.to_string()).collect();
+
+        let mut links = links.iter().map(ToString::to_string).collect::<Vec<_>>();
+        links.sort();
+        links.dedup();
+
+        let mut entries = BTreeMap::new();
+        let enr_root = build_subtree(nodes, &mut entries);
+        let link_root = build_subtree(links, &mut entries);
+
+        let mut root = TreeRootEntry {
+            enr_root: insert_entry(enr_root, &mut entries),
+            link_root: insert_entry(link_root, &mut entries),
+            sequence_number,
+            signature: Default::default(),
+        };
+        root.sign(key)?;
+
+        Ok(DnsTree { root, entries, public_key: key.public() })
+    }
+}
+
+/// A signed EIP-1459 tree, ready to be published.
+#[derive(Debug, Clone)]
+pub struct DnsTree<K: EnrKeyUnambiguous = SecretKey> {
+    /// The signed root entry.
+    root: TreeRootEntry,
+    /// All other entries of the tree, keyed by their subdomain.
+    entries: BTreeMap<String, String>,
+    /// The public key of the key that signed the root.
+    public_key: K::PublicKey,
+}
+
+// === impl DnsTree ===
+
+impl<K: EnrKeyUnambiguous> DnsTree<K> {
+    /// Returns the signed root entry.
+    pub fn root(&self) -> &TreeRootEntry {
+        &self.root
+    }
+
+    /// Returns the link clients use to sync the tree if it's published at the given domain.
+    pub fn link(&self, domain: impl Into<String>) -> LinkEntry<K> {
+        LinkEntry { domain: domain.into(), pubkey: self.public_key.clone() }
+    }
+
+    /// Returns all TXT records of the tree, keyed by their fully qualified names (without the
+    /// trailing dot) if the tree is published at the given domain.
+    pub fn records(&self, domain: &str) -> BTreeMap<String, String> {
+        std::iter::once((domain.to_string(), self.root.to_string()))
+            .chain(
+                self.entries
+                    .iter()
+                    .map(|(subdomain, entry)| (format!("{subdomain}.{domain}"), entry.clone())),
+            )
+            .collect()
+    }
+
+    /// Renders the records of the tree as a BIND zone file for the given domain.
+    ///
+    /// Entries longer than the 255 bytes a single character-string can hold are split into
+    /// multiple strings, which resolvers join back together.
+    pub fn to_zone_file(&self, domain: &str, ttl: u32) -> String {
+        let domain = domain.trim_end_matches('.');
+        let mut zone = format!("$ORIGIN {domain}.\n");
+        let records = std::iter::once(("@", self.root.to_string())).chain(
+            self.entries.iter().map(|(subdomain, entry)| (subdomain.as_str(), entry.clone())),
+        );
+        for (name, entry) in records {
+            let strings = entry
+                .as_bytes()
+                .chunks(MAX_TXT_STRING_LEN)
+                .map(|chunk| format!("\"{}\"", String::from_utf8_lossy(chunk)))
+                .collect::<Vec<_>>()
+                .join(" ");
+            let _ = writeln!(zone, "{name:<26} {ttl} IN TXT {strings}");
+        }
+        zone
+    }
+
+    /// Renders the records of the tree as a JSON object that maps the fully qualified names to
+    /// the content of the TXT records.
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self, domain: &str) -> String {
+        serde_json::to_string_pretty(&self.records(domain.trim_end_matches('.')))
+            .expect("map of strings is valid json")
+    }
+}
+
+/// Returns the subdomain of an entry: the base32 encoding of the abbreviated keccak256 hash of its
+/// text content.
+fn subdomain(entry: &str) -> String {
+    BASE32_NOPAD.encode(&keccak256(entry.as_bytes())[..16])
+}
+
+/// Inserts the entry and returns its subdomain.
+fn insert_entry(entry: String, entries: &mut BTreeMap<String, String>) -> String {
+    let hash = subdomain(&entry);
+    entries.insert(hash.clone(), entry);
+    hash
+}
+
+/// Arranges the entries into a tree of branch entries and returns its root entry.
+///
+/// All entries, except for the returned root, are inserted into `entries`.
+fn build_subtree(leaves: Vec<String>, entries: &mut BTreeMap<String, String>) -> String {
+    if leaves.len() == 1 {
+        return leaves.into_iter().next().expect("exactly one entry")
+    }
+    if leaves.len() <= MAX_CHILDREN {
+        let children = leaves.into_iter().map(|leaf| insert_entry(leaf, entries)).collect();
+        return BranchEntry { children }.to_string()
+    }
+
+    let mut subtrees = Vec::with_capacity(leaves.len() / MAX_CHILDREN + 1);
+    let mut leaves = leaves.into_iter().peekable();
+    while leaves.peek().is_some() {
+        let subtree = build_subtree(leaves.by_ref().take(MAX_CHILDREN).collect(), entries);
+        entries.insert(subdomain(&subtree), subtree.clone());
+        subtrees.push(subtree);
+    }
+    build_subtree(subtrees, entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DnsDiscoveryConfig, DnsDiscoveryEvent, DnsDiscoveryService, MapResolver};
+    use enr::EnrBuilder;
+    use secp256k1::rand::thread_rng;
+    use std::{collections::HashSet, net::Ipv4Addr, num::NonZeroUsize, sync::Arc, time::Duration};
+    use tokio_stream::StreamExt;
+
+    fn rng_enr(port: u16) -> Enr<SecretKey> {
+        let secret_key = SecretKey::new(&mut thread_rng());
+        EnrBuilder::new("v4")
+            .ip4(Ipv4Addr::LOCALHOST)
+            .udp4(port)
+            .tcp4(port)
+            .build(&secret_key)
+            .unwrap()
+    }
+
+    #[test]
+    fn branches_fit_txt_records() {
+        let key = SecretKey::new(&mut thread_rng());
+        let enrs = (0..100).map(rng_enr).collect::<Vec<_>>();
+        let tree = DnsTreeBuilder::new(1).add_enrs(enrs).build(&key).unwrap();
+
+        // 100 nodes, 8 branches below the root branch, the root branch and the empty link root
+        assert_eq!(tree.entries.len(), 100 + 8 + 1 + 1);
+        for (hash, entry) in &tree.entries {
+            assert_eq!(*hash, subdomain(entry));
+            if entry.starts_with("enrtree-branch:") {
+                assert!(entry.len() <= 370 + "enrtree-branch:".len());
+                entry.parse::<BranchEntry>().unwrap();
+            }
+        }
+        assert!(tree.root().verify::<SecretKey>(&key.public()));
+    }
+
+    #[test]
+    fn keeps_latest_record() {
+        let key = SecretKey::new(&mut thread_rng());
+        let node_key = SecretKey::new(&mut thread_rng());
+        let mut enr =
+            EnrBuilder::new("v4").ip4(Ipv4Addr::LOCALHOST).tcp4(30303).build(&node_key).unwrap();
+        let old = enr.clone();
+        enr.set_tcp4(30304, &node_key).unwrap();
+
+        let tree = DnsTreeBuilder::new(1).add_enr(enr.clone()).add_enr(old).build(&key).unwrap();
+        assert_eq!(tree.entries[&tree.root().enr_root], enr.to_base64());
+    }
+
+    #[test]
+    fn zone_file() {
+        let key = SecretKey::new(&mut thread_rng());
+        let tree = DnsTreeBuilder::new(7).add_enrs((0..3).map(rng_enr)).build(&key).unwrap();
+        let zone = tree.to_zone_file("nodes.example.org.", 3600);
+
+        let mut lines = zone.lines();
+        assert_eq!(lines.next(), Some("$ORIGIN nodes.example.org."));
+        let root = lines.next().unwrap();
+        assert!(root.starts_with("@"));
+        assert!(root.ends_with(&format!("3600 IN TXT \"{}\"", tree.root())));
+        assert_eq!(lines.count(), tree.entries.len());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn resolve_published_tree() {
+        reth_tracing::init_test_tracing();
+
+        let resolver = Arc::new(MapResolver::default());
+
+        // a linked tree with a few more nodes
+        let linked_key = SecretKey::new(&mut thread_rng());
+        let linked_enrs = (100..103).map(rng_enr).collect::<Vec<_>>();
+        let linked_tree =
+            DnsTreeBuilder::new(1).add_enrs(linked_enrs.clone()).build(&linked_key).unwrap();
+        for (name, record) in linked_tree.records("linked.example.org") {
+            resolver.insert(name, record);
+        }
+
+        let key = SecretKey::new(&mut thread_rng());
+        let enrs = (0..30).map(rng_enr).collect::<Vec<_>>();
+        let tree = DnsTreeBuilder::new(1)
+            .add_enrs(enrs.clone())
+            .add_link(linked_tree.link("linked.example.org"))
+            .build(&key)
+            .unwrap();
+        for (name, record) in tree.records("nodes.example.org") {
+            resolver.insert(name, record);
+        }
+
+        let config = DnsDiscoveryConfig {
+            max_requests_per_sec: NonZeroUsize::new(100).unwrap(),
+            ..Default::default()
+        };
+        let mut service = DnsDiscoveryService::new(resolver, config);
+        service.sync_tree_with_link(tree.link("nodes.example.org"));
+
+        let mut expected = enrs
+            .iter()
+            .chain(linked_enrs.iter())
+            .map(|enr| enr.to_base64())
+            .collect::<HashSet<_>>();
+        tokio::time::timeout(Duration::from_secs(10), async {
+            while !expected.is_empty() {
+                match service.next().await.unwrap() {
+                    DnsDiscoveryEvent::Enr(enr) => {
+                        expected.remove(&enr.to_base64());
+                    }
+                }
+            }
+        })
+        .await
+        .expect("all nodes are resolved");
+    }
+}
@@ -201,8 +201,13 @@ impl BranchEntry {
             Ok(hash.to_string())
         }
 
+        let input = input.trim();
+        if input.is_empty() {
+            // a tree without links has an empty link root
+            return Ok(Self { children: Vec::new() })
+        }
         let children =
-            input.trim().split(',').map(ensure_valid_hash).collect::<ParseEntryResult<Vec<_>>>()?;
+            input.split(',').map(ensure_valid_hash).collect::<ParseEntryResult<Vec<_>>>()?;
         Ok(Self { children })
     }
 }
@@ -391,6 +396,14 @@ mod tests {
             _ => unreachable!(),
         }
     }
+    #[test]
+    fn parse_empty_branch_entry() {
+        let s = "enrtree-branch:";
+        let entry: BranchEntry = s.parse().unwrap();
+        assert!(entry.children.is_empty());
+        assert_eq!(entry.to_string(), s);
+    }
+
     #[test]
     fn parse_branch_entry_base32() {
         let s = "enrtree-branch:YNEGZIWHOM7TOOSUATAPTM";
@@ -0,0 +1,423 @@
+use futures::Future;
+use reth_eth_wire::{
+    GetAccountRange, GetByteCodes, GetStorageRanges, GetTrieNodes, SnapAccount, StorageData,
+};
+use reth_interfaces::p2p::{error::RequestError, snap::client::SnapClient};
+use reth_primitives::{keccak256, Account, Bytes, PeerId, H256, U256};
+use reth_rlp::{Decodable, Encodable};
+use reth_trie::{account::EthAccount, verify_range_proof, RangeProofError};
+use std::collections::HashMap;
+use thiserror::Error;
+use tracing::*;
+
+/// The default soft limit of the response size requested from the peers, 512 KiB.
+pub const DEFAULT_RESPONSE_BYTES: u64 = 512 * 1024;
+
+/// The default number of attempts to get a valid response before giving up.
+pub const DEFAULT_MAX_RETRIES: usize = 10;
+
+/// Errors of the [SnapDownloader].
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum SnapDownloadError {
+    /// No valid response was received after the maximum number of attempts.
+    #[error("No valid response after {attempts} attempts. Last error: {error}")]
+    TooManyRetries {
+        /// Number of attempts made.
+        attempts: usize,
+        /// The error of the last attempt.
+        #[source]
+        error: SnapResponseError,
+    },
+}
+
+/// Errors of a single `snap` response, reported to the peer that sent it.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum SnapResponseError {
+    /// The request failed.
+    #[error(transparent)]
+    Request(#[from] RequestError),
+    /// The peer doesn't serve the requested state, e.g. because it's too old.
+    #[error("Peer {0} returned an empty response")]
+    Empty(PeerId),
+    /// The range proof of the response is invalid.
+    #[error("Invalid range proof from peer {peer_id}: {error}")]
+    RangeProof {
+        /// The peer that sent the response.
+        peer_id: PeerId,
+        /// The verification error.
+        #[source]
+        error: RangeProofError,
+    },
+    /// The response contains data that was not requested.
+    #[error("Peer {0} returned data that was not requested")]
+    Unrequested(PeerId),
+    /// A storage slot of the response could not be decoded.
+    #[error("Peer {peer_id} returned an invalid storage slot: {error}")]
+    InvalidSlot {
+        /// The peer that sent the response.
+        peer_id: PeerId,
+        /// The decoding error.
+        #[source]
+        error: reth_rlp::DecodeError,
+    },
+}
+
+/// Verified range of accounts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountRangeResponse {
+    /// The hashed addresses and the accounts, ordered by the hashed address.
+    pub accounts: Vec<(H256, SnapAccount)>,
+    /// Whether the state trie has more accounts after the last one.
+    pub has_more: bool,
+}
+
+/// Verified range of storage slots of a single account.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StorageRangeResponse {
+    /// The hashed address of the account.
+    pub account: H256,
+    /// The hashed slots and their values, ordered by the hashed slot.
+    pub slots: Vec<(H256, U256)>,
+    /// Whether the storage trie has more slots after the last one.
+    pub has_more: bool,
+}
+
+/// Downloads the state trie with the given root from the peers of the [SnapClient], see
+/// <https://github.com/ethereum/devp2p/blob/master/caps/snap.md>.
+///
+/// Every response is verified against the root before it's returned: account and storage ranges
+/// with their range proofs, bytecodes and trie nodes by their hashes. Peers sending invalid
+/// responses are reported and the request is retried, up to the configured number of attempts.
+#[derive(Debug)]
+pub struct SnapDownloader<C> {
+    client: C,
+    /// The root of the state trie to download.
+    root: H256,
+    /// The soft limit of the response size requested from the peers.
+    response_bytes: u64,
+    /// The number of attempts to get a valid response before giving up.
+    max_retries: usize,
+}
+
+impl<C: SnapClient> SnapDownloader<C> {
+    /// Creates a new downloader of the state trie with the given root.
+    pub fn new(client: C, root: H256) -> Self {
+        Self {
+            client,
+            root,
+            response_bytes: DEFAULT_RESPONSE_BYTES,
+            max_retries: DEFAULT_MAX_RETRIES,
+        }
+    }
+
+    /// Sets the soft limit of the response size requested from the peers.
+    pub fn with_response_bytes(mut self, response_bytes: u64) -> Self {
+        self.response_bytes = response_bytes;
+        self
+    }
+
+    /// Sets the number of attempts to get a valid response before giving up.
+    pub fn with_max_retries(mut self, max_retries: usize) -> Self {
+        self.max_retries = max_retries.max(1);
+        self
+    }
+
+    /// Returns the root of the downloaded state trie.
+    pub fn root(&self) -> H256 {
+        self.root
+    }
+
+    /// Downloads the accounts starting at `origin`.
+    pub async fn account_range(
+        &self,
+        origin: H256,
+    ) -> Result<AccountRangeResponse, SnapDownloadError> {
+        self.retry(move || async move {
+            let request = GetAccountRange {
+                request_id: 0,
+                root_hash: self.root,
+                starting_hash: origin,
+                limit_hash: H256::repeat_byte(0xff),
+                response_bytes: self.response_bytes,
+            };
+            let (peer_id, response) = self.client.get_account_range(request).await?.split();
+            if response.accounts.is_empty() && response.proof.is_empty() {
+                return Err(SnapResponseError::Empty(peer_id))
+            }
+
+            let leaves = response
+                .accounts
+                .iter()
+                .map(|data| {
+                    let account = EthAccount::from(Account::from(data.body))
+                        .with_storage_root(data.body.storage_root);
+                    let mut rlp = Vec::with_capacity(account.length());
+                    account.encode(&mut rlp);
+                    (data.hash, rlp)
+                })
+                .collect::<Vec<_>>();
+            let has_more = verify_range_proof(self.root, origin, &leaves, &response.proof)
+                .map_err(|error| SnapResponseError::RangeProof { peer_id, error })?;
+
+            let accounts =
+                response.accounts.into_iter().map(|data| (data.hash, data.body)).collect();
+            Ok(AccountRangeResponse { accounts, has_more })
+        })
+        .await
+    }
+
+    /// Downloads the storage slots of the given accounts, `(hashed address, storage root)`.
+    ///
+    /// The peers might not return the storage of all accounts, so the response contains the
+    /// storage of a prefix of the accounts, at least one. Only the storage of the last of them
+    /// might be incomplete, which is indicated by [StorageRangeResponse::has_more], and can be
+    /// continued with [Self::storage_range].
+    pub async fn storage_ranges(
+        &self,
+        accounts: &[(H256, H256)],
+    ) -> Result<Vec<StorageRangeResponse>, SnapDownloadError> {
+        self.storage_ranges_from(accounts, H256::zero()).await
+    }
+
+    /// Downloads the storage slots of the account starting at `origin`.
+    pub async fn storage_range(
+        &self,
+        account: H256,
+        storage_root: H256,
+        origin: H256,
+    ) -> Result<StorageRangeResponse, SnapDownloadError> {
+        let mut ranges = self.storage_ranges_from(&[(account, storage_root)], origin).await?;
+        Ok(ranges.remove(0))
+    }
+
+    async fn storage_ranges_from(
+        &self,
+        accounts: &[(H256, H256)],
+        origin: H256,
+    ) -> Result<Vec<StorageRangeResponse>, SnapDownloadError> {
+        debug_assert!(!accounts.is_empty(), "no accounts requested");
+        debug_assert!(origin.is_zero() || accounts.len() == 1, "origin for multiple accounts");
+
+        self.retry(move || async move {
+            let request = GetStorageRanges {
+                request_id: 0,
+                root_hash: self.root,
+                account_hashes: accounts.iter().map(|(account, _)| *account).collect(),
+                starting_hash: origin.as_bytes().to_vec().into(),
+                limit_hash: Bytes::default(),
+                response_bytes: self.response_bytes,
+            };
+            let (peer_id, response) = self.client.get_storage_ranges(request).await?.split();
+            if response.slots.is_empty() {
+                return Err(SnapResponseError::Empty(peer_id))
+            }
+            if response.slots.len() > accounts.len() {
+                return Err(SnapResponseError::Unrequested(peer_id))
+            }
+
+            let last = response.slots.len() - 1;
+            let mut ranges = Vec::with_capacity(response.slots.len());
+            for (index, slots) in response.slots.into_iter().enumerate() {
+                let (account, storage_root) = accounts[index];
+                // Only the last range has a proof, all the others have to be complete.
+                let (origin, proof) = if index == last {
+                    (origin, &response.proof[..])
+                } else {
+                    (H256::zero(), &[][..])
+                };
+                let leaves = slots
+                    .iter()
+                    .map(|StorageData { hash, data }| (*hash, data.as_ref()))
+                    .collect::<Vec<_>>();
+                let has_more = verify_range_proof(storage_root, origin, &leaves, proof)
+                    .map_err(|error| SnapResponseError::RangeProof { peer_id, error })?;
+
+                let slots = slots
+                    .into_iter()
+                    .map(|StorageData { hash, data }| {
+                        U256::decode(&mut data.as_ref())
+                            .map(|value| (hash, value))
+                            .map_err(|error| SnapResponseError::InvalidSlot { peer_id, error })
+                    })
+                    .collect::<Result<_, _>>()?;
+                ranges.push(StorageRangeResponse { account, slots, has_more });
+            }
+            Ok(ranges)
+        })
+        .await
+    }
+
+    /// Downloads the bytecodes with the given hashes.
+    ///
+    /// The peers might not return all bytecodes, so the response contains the bytecodes of a
+    /// subset of the hashes, at least one.
+    pub async fn byte_codes(
+        &self,
+        hashes: &[H256],
+    ) -> Result<HashMap<H256, Bytes>, SnapDownloadError> {
+        self.retry(move || async move {
+            let request = GetByteCodes {
+                request_id: 0,
+                hashes: hashes.to_vec(),
+                response_bytes: self.response_bytes,
+            };
+            let (peer_id, response) = self.client.get_byte_codes(request).await?.split();
+            if response.codes.is_empty() {
+                return Err(SnapResponseError::Empty(peer_id))
+            }
+
+            let mut codes = HashMap::with_capacity(response.codes.len());
+            for code in response.codes {
+                let hash = keccak256(&code);
+                if !hashes.contains(&hash) {
+                    return Err(SnapResponseError::Unrequested(peer_id))
+                }
+                codes.insert(hash, code);
+            }
+            Ok(codes)
+        })
+        .await
+    }
+
+    /// Downloads the nodes of the state trie at the given compact encoded paths, and verifies them
+    /// against the expected hashes, `(path, hash)`.
+    ///
+    /// The peers might not return all nodes, so the response contains the nodes of a prefix of the
+    /// paths, at least one.
+    pub async fn account_trie_nodes(
+        &self,
+        paths: &[(Bytes, H256)],
+    ) -> Result<Vec<Bytes>, SnapDownloadError> {
+        self.retry(move || async move {
+            let request = GetTrieNodes {
+                request_id: 0,
+                root_hash: self.root,
+                paths: paths.iter().map(|(path, _)| vec![path.clone()]).collect(),
+                response_bytes: self.response_bytes,
+            };
+            let (peer_id, response) = self.client.get_trie_nodes(request).await?.split();
+            if response.nodes.is_empty() {
+                return Err(SnapResponseError::Empty(peer_id))
+            }
+            if response.nodes.len() > paths.len() {
+                return Err(SnapResponseError::Unrequested(peer_id))
+            }
+            if response.nodes.iter().zip(paths).any(|(node, (_, hash))| keccak256(node) != *hash) {
+                return Err(SnapResponseError::Unrequested(peer_id))
+            }
+            Ok(response.nodes)
+        })
+        .await
+    }
+
+    /// Sends the request until a valid response is received, reporting the peers sending invalid
+    /// ones.
+    async fn retry<T, F, Fut>(&self, mut request: F) -> Result<T, SnapDownloadError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, SnapResponseError>>,
+    {
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            let error = match request().await {
+                Ok(response) => return Ok(response),
+                Err(error) => error,
+            };
+
+            match &error {
+                SnapResponseError::Request(_) | SnapResponseError::Empty(_) => {}
+                SnapResponseError::RangeProof { peer_id, .. } |
+                SnapResponseError::Unrequested(peer_id) |
+                SnapResponseError::InvalidSlot { peer_id, .. } => {
+                    self.client.report_bad_message(*peer_id)
+                }
+            }
+            debug!(target: "downloaders::snap", %error, attempts, "Invalid snap response");
+
+            if attempts >= self.max_retries {
+                return Err(SnapDownloadError::TooManyRetries { attempts, error })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TestSnapClient;
+    use assert_matches::assert_matches;
+    use reth_primitives::{proofs::EMPTY_ROOT, KECCAK_EMPTY};
+
+    fn client(max_entries: usize) -> TestSnapClient {
+        let code = Bytes::from(vec![0x60, 0x00]);
+        let accounts = (0..50u64).map(|i| {
+            let account = Account {
+                nonce: i,
+                balance: U256::from(i * 100),
+                bytecode_hash: (i % 10 == 0).then(|| keccak256(&code)),
+            };
+            let storage = (1..=i % 7)
+                .map(|slot| (keccak256(H256::from_low_u64_be(i * 100 + slot)), U256::from(slot)))
+                .collect();
+            (keccak256(H256::from_low_u64_be(i)), (account, storage))
+        });
+        TestSnapClient::new(accounts, [code]).with_max_entries(max_entries)
+    }
+
+    #[tokio::test]
+    async fn download_state() {
+        let client = client(8);
+        let downloader = SnapDownloader::new(&client, client.state_root());
+
+        let mut accounts = Vec::new();
+        let mut origin = H256::zero();
+        loop {
+            let range = downloader.account_range(origin).await.unwrap();
+            assert!(range.accounts.len() <= 8);
+            let last = range.accounts.last().unwrap().0;
+            accounts.extend(range.accounts);
+            if !range.has_more {
+                break
+            }
+            origin = H256((U256::from_be_bytes(last.0) + U256::from(1)).to_be_bytes());
+        }
+        assert_eq!(accounts.len(), 50);
+
+        let with_storage = accounts
+            .iter()
+            .filter(|(_, account)| account.storage_root != EMPTY_ROOT)
+            .map(|(hash, account)| (*hash, account.storage_root))
+            .collect::<Vec<_>>();
+        let ranges = downloader.storage_ranges(&with_storage).await.unwrap();
+        assert!(!ranges.is_empty());
+        let slots = ranges.iter().map(|range| range.slots.len()).sum::<usize>();
+        assert!(slots <= 8);
+        // All ranges but the last one are complete.
+        assert!(ranges[..ranges.len() - 1].iter().all(|range| !range.has_more));
+
+        let code_hashes = accounts
+            .iter()
+            .map(|(_, account)| account.code_hash)
+            .filter(|hash| *hash != KECCAK_EMPTY)
+            .collect::<Vec<_>>();
+        let codes = downloader.byte_codes(&code_hashes).await.unwrap();
+        assert_eq!(codes.len(), 1);
+        assert!(codes.contains_key(&code_hashes[0]));
+    }
+
+    #[tokio::test]
+    async fn unknown_root() {
+        let client = client(8);
+        let downloader = SnapDownloader::new(&client, H256::random()).with_max_retries(3);
+
+        assert_matches!(
+            downloader.account_range(H256::zero()).await,
+            Err(SnapDownloadError::TooManyRetries {
+                attempts: 3,
+                error: SnapResponseError::Empty(_)
+            })
+        );
+        assert_eq!(client.times_requested(), 3);
+    }
+}
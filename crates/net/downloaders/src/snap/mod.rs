@@ -0,0 +1,6 @@
+/// A downloader of the state at a given root that verifies the responses.
+mod downloader;
+pub use downloader::{
+    AccountRangeResponse, SnapDownloadError, SnapDownloader, SnapResponseError,
+    StorageRangeResponse, DEFAULT_MAX_RETRIES, DEFAULT_RESPONSE_BYTES,
+};
@@ -85,15 +85,24 @@ impl FileClient {
         let mut reader = vec![];
         file.read_to_end(&mut reader).await.unwrap();
 
-        let mut headers = HashMap::new();
-        let mut hash_to_number = HashMap::new();
-        let mut bodies = HashMap::new();
-
         // use with_capacity to make sure the internal buffer contains the entire file
         let mut stream = FramedRead::with_capacity(&reader[..], BlockFileCodec, file_len as usize);
 
+        let mut blocks = Vec::new();
         while let Some(block_res) = stream.next().await {
-            let block = block_res?;
+            blocks.push(block_res?);
+        }
+
+        Ok(Self::from_blocks(blocks))
+    }
+
+    /// Create a new file client from already decoded blocks.
+    pub fn from_blocks(blocks: impl IntoIterator<Item = Block>) -> Self {
+        let mut headers = HashMap::new();
+        let mut hash_to_number = HashMap::new();
+        let mut bodies = HashMap::new();
+
+        for block in blocks {
             let block_hash = block.header.hash_slow();
 
             // add to the internal maps
@@ -111,12 +120,12 @@ impl FileClient {
 
         trace!(blocks = headers.len(), "Initialized file client");
 
-        Ok(Self { headers, hash_to_number, bodies })
+        Self { headers, hash_to_number, bodies }
     }
 
     /// Get the tip hash of the chain.
     pub fn tip(&self) -> Option<H256> {
-        self.headers.get(&(self.headers.len() as u64)).map(|h| h.hash_slow())
+        self.max_block().and_then(|number| self.headers.get(&number)).map(|h| h.hash_slow())
     }
 
     /// Returns the highest block number of this client has or `None` if empty
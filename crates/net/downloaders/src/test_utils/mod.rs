@@ -14,11 +14,13 @@ use tokio_util::codec::FramedWrite;
 mod bodies_client;
 mod file_client;
 mod file_codec;
+mod snap_client;
 
 pub use bodies_client::TestBodiesClient;
 pub use file_client::{FileClient, FileClientError};
 pub(crate) use file_codec::BlockFileCodec;
 use reth_interfaces::test_utils::generators;
+pub use snap_client::TestSnapClient;
 
 /// Metrics scope used for testing.
 pub(crate) const TEST_SCOPE: &str = "downloaders.test";
@@ -0,0 +1,212 @@
+use reth_eth_wire::{
+    AccountData, AccountRange, ByteCodes, GetAccountRange, GetByteCodes, GetStorageRanges,
+    GetTrieNodes, SnapAccount, StorageData, StorageRanges, TrieNodes,
+};
+use reth_interfaces::p2p::{
+    download::DownloadClient,
+    snap::client::{SnapClient, SnapFut},
+};
+use reth_primitives::{keccak256, trie::Nibbles, Account, Bytes, PeerId, H256, KECCAK_EMPTY, U256};
+use reth_rlp::{encode_fixed_size, Encodable};
+use reth_trie::{
+    account::EthAccount,
+    test_utils::{range_proof, trie_nodes, trie_root},
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+/// A [SnapClient] for testing, serving the state it was created with.
+///
+/// The ranges are limited by the number of entries instead of the response size. Only the nodes
+/// of the state trie are served.
+#[derive(Debug, Default)]
+pub struct TestSnapClient {
+    /// Accounts of the state trie, with the RLP of their state trie leaves.
+    accounts: BTreeMap<H256, (SnapAccount, Vec<u8>)>,
+    /// Storages of the accounts, with the RLP of their storage trie leaves.
+    storages: HashMap<H256, BTreeMap<H256, Vec<u8>>>,
+    codes: HashMap<H256, Bytes>,
+    /// Nodes of the state trie by their paths.
+    nodes: BTreeMap<Nibbles, Vec<u8>>,
+    root: H256,
+    max_entries: usize,
+    times_requested: AtomicU64,
+}
+
+impl TestSnapClient {
+    /// Creates a client serving the given hashed accounts, with their storage slots and
+    /// bytecodes.
+    pub fn new(
+        accounts: impl IntoIterator<Item = (H256, (Account, Vec<(H256, U256)>))>,
+        codes: impl IntoIterator<Item = Bytes>,
+    ) -> Self {
+        let mut this = Self { max_entries: usize::MAX, ..Default::default() };
+        for (hashed_address, (account, storage)) in accounts {
+            let storage = storage
+                .into_iter()
+                .filter(|(_, value)| *value != U256::ZERO)
+                .map(|(slot, value)| (slot, encode_fixed_size(&value).to_vec()))
+                .collect::<BTreeMap<_, _>>();
+            let storage_root = trie_root(&storage);
+
+            let snap_account = SnapAccount {
+                nonce: account.nonce,
+                balance: account.balance,
+                storage_root,
+                code_hash: account.bytecode_hash.unwrap_or(KECCAK_EMPTY),
+            };
+            let mut rlp = Vec::new();
+            EthAccount::from(account).with_storage_root(storage_root).encode(&mut rlp);
+
+            this.accounts.insert(hashed_address, (snap_account, rlp));
+            this.storages.insert(hashed_address, storage);
+        }
+        this.codes = codes.into_iter().map(|code| (keccak256(&code), code)).collect();
+        let entries = this.account_entries();
+        this.root = trie_root(&entries);
+        this.nodes = trie_nodes(&entries);
+        this
+    }
+
+    /// Sets the maximum number of accounts, slots, bytecodes or trie nodes per response.
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries.max(1);
+        self
+    }
+
+    /// Returns the root of the served state trie.
+    pub fn state_root(&self) -> H256 {
+        self.root
+    }
+
+    /// Returns the number of requests received.
+    pub fn times_requested(&self) -> u64 {
+        self.times_requested.load(Ordering::Relaxed)
+    }
+
+    /// Returns the leaves of the state trie.
+    fn account_entries(&self) -> BTreeMap<H256, Vec<u8>> {
+        self.accounts.iter().map(|(hash, (_, rlp))| (*hash, rlp.clone())).collect()
+    }
+
+    /// Returns the entries of the range starting at `origin`, with the proof for the range.
+    fn range(
+        &self,
+        entries: &BTreeMap<H256, Vec<u8>>,
+        origin: H256,
+        limit: usize,
+    ) -> (Vec<(H256, Vec<u8>)>, Vec<Bytes>) {
+        let range = entries
+            .range(origin..)
+            .take(limit)
+            .map(|(key, value)| (*key, value.clone()))
+            .collect::<Vec<_>>();
+        let proof = range_proof(entries, origin, range.last().map(|(key, _)| *key));
+        (range, proof)
+    }
+}
+
+impl DownloadClient for TestSnapClient {
+    fn report_bad_message(&self, _peer_id: PeerId) {
+        // noop
+    }
+
+    fn num_connected_peers(&self) -> usize {
+        0
+    }
+}
+
+impl SnapClient for TestSnapClient {
+    fn get_account_range(&self, request: GetAccountRange) -> SnapFut<AccountRange> {
+        self.times_requested.fetch_add(1, Ordering::Relaxed);
+        let response = if request.root_hash == self.root {
+            let (range, proof) =
+                self.range(&self.account_entries(), request.starting_hash, self.max_entries);
+            let accounts = range
+                .into_iter()
+                .map(|(hash, _)| AccountData { hash, body: self.accounts[&hash].0 })
+                .collect();
+            AccountRange { request_id: request.request_id, accounts, proof }
+        } else {
+            AccountRange { request_id: request.request_id, ..Default::default() }
+        };
+        Box::pin(async move { Ok((PeerId::default(), response).into()) })
+    }
+
+    fn get_storage_ranges(&self, request: GetStorageRanges) -> SnapFut<StorageRanges> {
+        self.times_requested.fetch_add(1, Ordering::Relaxed);
+        let mut response = StorageRanges { request_id: request.request_id, ..Default::default() };
+        if request.root_hash == self.root {
+            let mut origin = H256::from_slice(&request.starting_hash);
+            let mut remaining = self.max_entries;
+            for account in request.account_hashes {
+                let Some(storage) = self.storages.get(&account) else { break };
+                let (range, proof) = self.range(storage, origin, remaining);
+                remaining -= range.len();
+
+                // Only the last range can be partial, and it's the only one with a proof.
+                let complete = storage.range(origin..).count() == range.len();
+                let last = !complete || !origin.is_zero() || remaining == 0;
+                response.slots.push(
+                    range
+                        .into_iter()
+                        .map(|(hash, data)| StorageData { hash, data: data.into() })
+                        .collect(),
+                );
+                if last {
+                    if !complete || !origin.is_zero() {
+                        response.proof = proof;
+                    }
+                    break
+                }
+                origin = H256::zero();
+            }
+        }
+        Box::pin(async move { Ok((PeerId::default(), response).into()) })
+    }
+
+    fn get_byte_codes(&self, request: GetByteCodes) -> SnapFut<ByteCodes> {
+        self.times_requested.fetch_add(1, Ordering::Relaxed);
+        let codes = request
+            .hashes
+            .iter()
+            .filter_map(|hash| self.codes.get(hash).cloned())
+            .take(self.max_entries)
+            .collect();
+        let response = ByteCodes { request_id: request.request_id, codes };
+        Box::pin(async move { Ok((PeerId::default(), response).into()) })
+    }
+
+    fn get_trie_nodes(&self, request: GetTrieNodes) -> SnapFut<TrieNodes> {
+        self.times_requested.fetch_add(1, Ordering::Relaxed);
+        let mut nodes = Vec::new();
+        if request.root_hash == self.root {
+            for path in request.paths.iter().take(self.max_entries) {
+                // Only the account trie paths, consisting of a single compact encoded path.
+                let Some(node) = path
+                    .first()
+                    .filter(|_| path.len() == 1)
+                    .and_then(|path| self.nodes.get(&decode_compact_path(path)))
+                else {
+                    break
+                };
+                nodes.push(node.clone().into());
+            }
+        }
+        let response = TrieNodes { request_id: request.request_id, nodes };
+        Box::pin(async move { Ok((PeerId::default(), response).into()) })
+    }
+}
+
+/// Decodes the compact (hex-prefix) encoding of the path of a trie node.
+fn decode_compact_path(path: &[u8]) -> Nibbles {
+    let nibbles = Nibbles::unpack(path);
+    match nibbles.first() {
+        // Odd number of nibbles, only the flag nibble is prepended.
+        Some(flag) if flag & 1 == 1 => nibbles.slice_from(1),
+        Some(_) => nibbles.slice_from(2),
+        None => nibbles,
+    }
+}
@@ -100,6 +100,11 @@ where
     pub fn remote_id(&self) -> PeerId {
         self.remote_id
     }
+
+    /// Returns a reference to the underlying transport.
+    pub fn inner(&self) -> &Io {
+        self.stream.get_ref()
+    }
 }
 
 impl<Io> Stream for ECIESStream<Io>
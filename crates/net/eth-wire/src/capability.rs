@@ -1,6 +1,6 @@
 //! All capability related types
 
-use crate::{version::ParseVersionError, EthMessage, EthVersion};
+use crate::{version::ParseVersionError, EthMessage, EthVersion, SNAP_MESSAGE_COUNT, SNAP_VERSION};
 use reth_codecs::add_arbitrary_tests;
 use reth_primitives::bytes::{BufMut, Bytes};
 use reth_rlp::{Decodable, DecodeError, Encodable, RlpDecodable, RlpEncodable};
@@ -71,6 +71,17 @@ impl Capability {
     pub fn is_eth_v68(&self) -> bool {
         self.name == "eth" && self.version == 68
     }
+
+    /// Returns the `snap/1` capability.
+    pub fn snap() -> Self {
+        Self::new("snap".into(), SNAP_VERSION)
+    }
+
+    /// Whether this is snap v1.
+    #[inline]
+    pub fn is_snap_v1(&self) -> bool {
+        self.name == "snap" && self.version == SNAP_VERSION
+    }
 }
 
 impl fmt::Display for Capability {
@@ -111,6 +122,7 @@ pub struct Capabilities {
     eth_66: bool,
     eth_67: bool,
     eth_68: bool,
+    snap_1: bool,
 }
 
 impl Capabilities {
@@ -149,6 +161,12 @@ impl Capabilities {
     pub fn supports_eth_v68(&self) -> bool {
         self.eth_68
     }
+
+    /// Whether this peer supports the `snap/1` protocol.
+    #[inline]
+    pub fn supports_snap(&self) -> bool {
+        self.snap_1
+    }
 }
 
 impl From<Vec<Capability>> for Capabilities {
@@ -157,6 +175,7 @@ impl From<Vec<Capability>> for Capabilities {
             eth_66: value.iter().any(Capability::is_eth_v66),
             eth_67: value.iter().any(Capability::is_eth_v67),
             eth_68: value.iter().any(Capability::is_eth_v68),
+            snap_1: value.iter().any(Capability::is_snap_v1),
             inner: value,
         }
     }
@@ -176,11 +195,58 @@ impl Decodable for Capabilities {
             eth_66: inner.iter().any(Capability::is_eth_v66),
             eth_67: inner.iter().any(Capability::is_eth_v67),
             eth_68: inner.iter().any(Capability::is_eth_v68),
+            snap_1: inner.iter().any(Capability::is_snap_v1),
             inner,
         })
     }
 }
 
+/// A protocol that can be negotiated during the RLPx handshake: a [`Capability`] together with the
+/// number of messages it reserves in the shared message id space.
+///
+/// The message count is required to determine the message id offsets of all capabilities shared
+/// with a peer, so only capabilities with a known count can be multiplexed over a session.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Protocol {
+    /// The name and version of the protocol.
+    pub cap: Capability,
+    /// The number of messages used by this protocol.
+    pub messages: u8,
+}
+
+impl Protocol {
+    /// Create a new [`Protocol`] for the given capability that uses `messages` message ids.
+    pub fn new(cap: Capability, messages: u8) -> Self {
+        Self { cap, messages }
+    }
+
+    /// Returns the `eth` protocol of the given version.
+    pub fn eth(version: EthVersion) -> Self {
+        Self::new(version.into(), version.total_messages())
+    }
+
+    /// Returns the `snap/1` protocol.
+    pub fn snap() -> Self {
+        Self::new(Capability::snap(), SNAP_MESSAGE_COUNT)
+    }
+
+    /// Returns the capability of this protocol.
+    pub fn capability(&self) -> &Capability {
+        &self.cap
+    }
+
+    /// Returns the number of messages used by this protocol.
+    pub fn messages(&self) -> u8 {
+        self.messages
+    }
+}
+
+impl From<EthVersion> for Protocol {
+    fn from(version: EthVersion) -> Self {
+        Self::eth(version)
+    }
+}
+
 /// This represents a shared capability, its version, and its offset.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[allow(missing_docs)]
@@ -188,16 +254,24 @@ pub enum SharedCapability {
     /// The `eth` capability.
     Eth { version: EthVersion, offset: u8 },
 
-    /// An unknown capability.
-    UnknownCapability { name: SmolStr, version: u8, offset: u8 },
+    /// Any other capability, for example a custom sub-protocol, with its number of messages.
+    UnknownCapability { name: SmolStr, version: u8, offset: u8, messages: u8 },
 }
 
 impl SharedCapability {
-    /// Creates a new [`SharedCapability`] based on the given name, offset, and version.
-    pub(crate) fn new(name: &str, version: u8, offset: u8) -> Result<Self, SharedCapabilityError> {
+    /// Creates a new [`SharedCapability`] based on the given name, offset, version and number of
+    /// messages.
+    ///
+    /// The number of messages is ignored for `eth`, since it is determined by the version.
+    pub(crate) fn new(
+        name: &str,
+        version: u8,
+        offset: u8,
+        messages: u8,
+    ) -> Result<Self, SharedCapabilityError> {
         match name {
             "eth" => Ok(Self::Eth { version: EthVersion::try_from(version)?, offset }),
-            _ => Ok(Self::UnknownCapability { name: name.into(), version, offset }),
+            _ => Ok(Self::UnknownCapability { name: name.into(), version, offset, messages }),
         }
     }
 
@@ -217,6 +291,16 @@ impl SharedCapability {
         }
     }
 
+    /// Returns the [`Capability`] this shared capability was negotiated for.
+    pub fn capability(&self) -> Capability {
+        Capability::new(self.name().into(), self.version() as usize)
+    }
+
+    /// Whether this is the `eth` capability.
+    pub fn is_eth(&self) -> bool {
+        matches!(self, SharedCapability::Eth { .. })
+    }
+
     /// Returns the message ID offset of the current capability.
     pub fn offset(&self) -> u8 {
         match self {
@@ -226,12 +310,59 @@ impl SharedCapability {
     }
 
     /// Returns the number of protocol messages supported by this capability.
-    pub fn num_messages(&self) -> Result<u8, SharedCapabilityError> {
+    pub fn num_messages(&self) -> u8 {
         match self {
-            SharedCapability::Eth { version, .. } => Ok(version.total_messages()),
-            _ => Err(SharedCapabilityError::UnknownCapability),
+            SharedCapability::Eth { version, .. } => version.total_messages(),
+            SharedCapability::UnknownCapability { messages, .. } => *messages,
         }
     }
+
+    /// Whether the given absolute message id belongs to this capability.
+    pub fn contains_message_id(&self, id: u8) -> bool {
+        id >= self.offset() && id - self.offset() < self.num_messages()
+    }
+}
+
+/// All capabilities shared with a peer, ordered by their message id offset.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SharedCapabilities(Vec<SharedCapability>);
+
+impl SharedCapabilities {
+    /// Returns the shared `eth` capability, if any.
+    pub fn eth(&self) -> Option<&SharedCapability> {
+        self.0.iter().find(|cap| cap.is_eth())
+    }
+
+    /// Returns the shared capability with the given name, if any.
+    pub fn find_by_name(&self, name: &str) -> Option<&SharedCapability> {
+        self.0.iter().find(|cap| cap.name() == name)
+    }
+
+    /// Returns the shared capability that the given absolute message id belongs to, if any.
+    pub fn find_by_message_id(&self, id: u8) -> Option<&SharedCapability> {
+        self.0.iter().find(|cap| cap.contains_message_id(id))
+    }
+
+    /// Returns an iterator over all shared capabilities.
+    pub fn iter(&self) -> impl Iterator<Item = &SharedCapability> + '_ {
+        self.0.iter()
+    }
+
+    /// Returns the number of shared capabilities.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns `true` if no capabilities are shared.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl From<Vec<SharedCapability>> for SharedCapabilities {
+    fn from(caps: Vec<SharedCapability>) -> Self {
+        Self(caps)
+    }
 }
 
 /// An error that may occur while creating a [`SharedCapability`].
@@ -240,9 +371,6 @@ pub enum SharedCapabilityError {
     /// Unsupported `eth` version.
     #[error(transparent)]
     UnsupportedVersion(#[from] ParseVersionError),
-    /// Cannot determine the number of messages for unknown capabilities.
-    #[error("cannot determine the number of messages for unknown capabilities")]
-    UnknownCapability,
 }
 
 #[cfg(test)]
@@ -251,7 +379,7 @@ mod tests {
 
     #[test]
     fn from_eth_68() {
-        let capability = SharedCapability::new("eth", 68, 0).unwrap();
+        let capability = SharedCapability::new("eth", 68, 0, 0).unwrap();
 
         assert_eq!(capability.name(), "eth");
         assert_eq!(capability.version(), 68);
@@ -260,7 +388,7 @@ mod tests {
 
     #[test]
     fn from_eth_67() {
-        let capability = SharedCapability::new("eth", 67, 0).unwrap();
+        let capability = SharedCapability::new("eth", 67, 0, 0).unwrap();
 
         assert_eq!(capability.name(), "eth");
         assert_eq!(capability.version(), 67);
@@ -269,7 +397,7 @@ mod tests {
 
     #[test]
     fn from_eth_66() {
-        let capability = SharedCapability::new("eth", 66, 0).unwrap();
+        let capability = SharedCapability::new("eth", 66, 0, 0).unwrap();
 
         assert_eq!(capability.name(), "eth");
         assert_eq!(capability.version(), 66);
@@ -289,5 +417,30 @@ mod tests {
         assert!(capabilities.supports_eth_v66());
         assert!(capabilities.supports_eth_v67());
         assert!(capabilities.supports_eth_v68());
+        assert!(!capabilities.supports_snap());
+    }
+
+    #[test]
+    fn capabilities_supports_snap() {
+        let capabilities: Capabilities =
+            vec![Capability::new("eth".into(), 68), Capability::snap()].into();
+
+        assert!(capabilities.supports_eth());
+        assert!(capabilities.supports_snap());
+    }
+
+    #[test]
+    fn shared_capability_message_ids() {
+        let eth = SharedCapability::new("eth", 68, 0x10, 0).unwrap();
+        let custom = SharedCapability::new("ourproto", 1, 0x10 + 17, 3).unwrap();
+        let shared = SharedCapabilities::from(vec![eth.clone(), custom.clone()]);
+
+        assert_eq!(shared.eth(), Some(&eth));
+        assert_eq!(shared.find_by_message_id(0x10), Some(&eth));
+        assert_eq!(shared.find_by_message_id(0x20), Some(&eth));
+        assert_eq!(shared.find_by_message_id(0x21), Some(&custom));
+        assert_eq!(shared.find_by_message_id(0x23), Some(&custom));
+        assert_eq!(shared.find_by_message_id(0x24), None);
+        assert_eq!(custom.capability(), Capability::new("ourproto".into(), 1));
     }
 }
@@ -0,0 +1,464 @@
+//! Capture of the messages exchanged over a [`P2PStream`] and their replay.
+//!
+//! A [`MessageCapture`] records every message a [`P2PStream`] sends or receives after the `p2p`
+//! handshake, uncompressed and timestamped, to a capture file. A capture file starts with a
+//! [`CaptureHeader`] describing the peer and the negotiated capabilities, followed by the
+//! [`CapturedMessage`]s, all of them RLP encoded.
+//!
+//! A [`CaptureFile`] can be replayed with [`CaptureFile::replay`], which returns a [`P2PStream`]
+//! that yields the captured inbound subprotocol messages in order. This allows reproducing the
+//! behavior of a session with a peer deterministically, without a network connection.
+
+use crate::{
+    capability::{SharedCapabilities, SharedCapability, SharedCapabilityError},
+    errors::{P2PHandshakeError, P2PStreamError},
+    p2pstream::MAX_RESERVED_MESSAGE_ID,
+    P2PMessageID, P2PStream,
+};
+use futures::{Sink, Stream};
+use reth_primitives::{
+    bytes::{BufMut, Bytes, BytesMut},
+    PeerId,
+};
+use reth_rlp::{Decodable, DecodeError, Encodable, RlpDecodable, RlpEncodable};
+use std::{
+    collections::VecDeque,
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    pin::Pin,
+    task::{Context, Poll},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// The version of the capture file format.
+pub const CAPTURE_FORMAT_VERSION: u8 = 1;
+
+/// The extension of capture files.
+pub const CAPTURE_FILE_EXTENSION: &str = "rlpx";
+
+/// The direction of a captured message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptureDirection {
+    /// The message was received from the peer.
+    Inbound,
+    /// The message was sent to the peer.
+    Outbound,
+}
+
+impl Encodable for CaptureDirection {
+    fn encode(&self, out: &mut dyn BufMut) {
+        (*self as u8).encode(out)
+    }
+
+    fn length(&self) -> usize {
+        (*self as u8).length()
+    }
+}
+
+impl Decodable for CaptureDirection {
+    fn decode(buf: &mut &[u8]) -> Result<Self, DecodeError> {
+        match u8::decode(buf)? {
+            0 => Ok(CaptureDirection::Inbound),
+            1 => Ok(CaptureDirection::Outbound),
+            _ => Err(DecodeError::Custom("invalid capture direction")),
+        }
+    }
+}
+
+/// A capability shared with the captured peer.
+#[derive(Debug, Clone, PartialEq, Eq, RlpEncodable, RlpDecodable)]
+pub struct CapturedCapability {
+    /// The name of the capability.
+    pub name: String,
+    /// The negotiated version of the capability.
+    pub version: u8,
+    /// The message id offset of the capability.
+    pub offset: u8,
+    /// The number of messages of the capability.
+    pub messages: u8,
+}
+
+impl From<&SharedCapability> for CapturedCapability {
+    fn from(cap: &SharedCapability) -> Self {
+        Self {
+            name: cap.name().to_string(),
+            version: cap.version(),
+            offset: cap.offset(),
+            messages: cap.num_messages(),
+        }
+    }
+}
+
+/// The header of a capture file.
+#[derive(Debug, Clone, PartialEq, Eq, RlpEncodable, RlpDecodable)]
+pub struct CaptureHeader {
+    /// The version of the capture file format, see [`CAPTURE_FORMAT_VERSION`].
+    pub version: u8,
+    /// The id of the captured peer.
+    pub peer_id: PeerId,
+    /// The client version the peer announced in its `Hello` message.
+    pub client_version: String,
+    /// The capabilities shared with the peer.
+    pub capabilities: Vec<CapturedCapability>,
+}
+
+impl CaptureHeader {
+    /// Creates the header for a capture of the session with the given peer.
+    pub fn new(
+        peer_id: PeerId,
+        client_version: impl Into<String>,
+        shared_capabilities: &SharedCapabilities,
+    ) -> Self {
+        Self {
+            version: CAPTURE_FORMAT_VERSION,
+            peer_id,
+            client_version: client_version.into(),
+            capabilities: shared_capabilities.iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Returns the capabilities that were shared with the peer.
+    pub fn shared_capabilities(&self) -> Result<SharedCapabilities, SharedCapabilityError> {
+        self.capabilities
+            .iter()
+            .map(|cap| SharedCapability::new(&cap.name, cap.version, cap.offset, cap.messages))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Into::into)
+    }
+}
+
+/// A message sent or received over a [`P2PStream`].
+#[derive(Debug, Clone, PartialEq, Eq, RlpEncodable, RlpDecodable)]
+pub struct CapturedMessage {
+    /// Whether the message was sent or received.
+    pub direction: CaptureDirection,
+    /// The time the message was sent or received, in microseconds since the unix epoch.
+    pub timestamp: u64,
+    /// The uncompressed message, starting with its absolute message id.
+    pub message: Bytes,
+}
+
+impl CapturedMessage {
+    /// Returns the absolute message id, if the message is not empty.
+    pub fn id(&self) -> Option<u8> {
+        self.message.first().copied()
+    }
+
+    /// Returns `true` if the message is a `p2p` message other than `Disconnect`, i.e. a `Ping` or
+    /// `Pong` that only affects the keepalive of the session.
+    pub fn is_keepalive(&self) -> bool {
+        self.id().map_or(false, |id| {
+            id <= MAX_RESERVED_MESSAGE_ID && id != P2PMessageID::Disconnect as u8
+        })
+    }
+}
+
+/// Writes the messages of a [`P2PStream`] to a capture file.
+///
+/// Messages are buffered and written to the file when the buffer is full, when
+/// [`MessageCapture::flush`] is called, or when the capture is dropped.
+#[derive(Debug)]
+pub struct MessageCapture {
+    /// The path of the capture file.
+    path: PathBuf,
+    /// The buffered writer of the capture file.
+    writer: BufWriter<File>,
+}
+
+impl MessageCapture {
+    /// Creates a new capture file in the given directory and writes the header.
+    ///
+    /// The file is named after the peer id and the current time, so that multiple sessions with
+    /// the same peer are captured to separate files.
+    pub fn create(dir: impl AsRef<Path>, header: &CaptureHeader) -> io::Result<Self> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+        let path = dir.join(format!(
+            "{:x}-{}.{CAPTURE_FILE_EXTENSION}",
+            header.peer_id,
+            unix_timestamp_micros()
+        ));
+        let writer = BufWriter::new(File::create(&path)?);
+        let mut capture = Self { path, writer };
+        capture.write(header)?;
+        Ok(capture)
+    }
+
+    /// Returns the path of the capture file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Records a message with the given absolute message id and uncompressed payload.
+    pub fn record(
+        &mut self,
+        direction: CaptureDirection,
+        id: u8,
+        payload: &[u8],
+    ) -> io::Result<()> {
+        let mut message = BytesMut::with_capacity(payload.len() + 1);
+        message.put_u8(id);
+        message.extend_from_slice(payload);
+        let message = CapturedMessage {
+            direction,
+            timestamp: unix_timestamp_micros(),
+            message: message.freeze(),
+        };
+        self.write(&message)
+    }
+
+    /// Writes all buffered messages to the capture file.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// Writes the RLP encoding of the given item.
+    fn write(&mut self, item: &impl Encodable) -> io::Result<()> {
+        let mut buf = Vec::with_capacity(item.length());
+        item.encode(&mut buf);
+        self.writer.write_all(&buf)
+    }
+}
+
+/// The contents of a capture file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaptureFile {
+    /// The header of the capture.
+    pub header: CaptureHeader,
+    /// All captured messages in the order they were sent or received.
+    pub messages: Vec<CapturedMessage>,
+}
+
+impl CaptureFile {
+    /// Reads the capture file at the given path.
+    pub fn read(path: impl AsRef<Path>) -> io::Result<Self> {
+        let buf = fs::read(path)?;
+        Self::decode(&buf).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    /// Decodes a capture from its RLP encoding.
+    pub fn decode(mut buf: &[u8]) -> Result<Self, DecodeError> {
+        let header = CaptureHeader::decode(&mut buf)?;
+        if header.version != CAPTURE_FORMAT_VERSION {
+            return Err(DecodeError::Custom("unsupported capture format version"))
+        }
+
+        let mut messages = Vec::new();
+        while !buf.is_empty() {
+            messages.push(CapturedMessage::decode(&mut buf)?);
+        }
+
+        Ok(Self { header, messages })
+    }
+
+    /// Returns an iterator over the messages received from the peer.
+    pub fn inbound(&self) -> impl Iterator<Item = &CapturedMessage> + '_ {
+        self.messages.iter().filter(|msg| msg.direction == CaptureDirection::Inbound)
+    }
+
+    /// Returns an iterator over the messages sent to the peer.
+    pub fn outbound(&self) -> impl Iterator<Item = &CapturedMessage> + '_ {
+        self.messages.iter().filter(|msg| msg.direction == CaptureDirection::Outbound)
+    }
+
+    /// Returns a [`P2PStream`] that yields the inbound messages of the capture, multiplexing all
+    /// capabilities that were shared with the peer.
+    ///
+    /// Captured `p2p` messages other than `Disconnect` are skipped, see
+    /// [`CapturedMessage::is_keepalive`]. The replayed stream never sends a `Ping`, so it would
+    /// reject the captured `Pong`s.
+    ///
+    /// The stream can be wrapped in an [`EthStream`](crate::EthStream) to decode the `eth`
+    /// messages. Note that the captured `Status` message is the first `eth` message, which the
+    /// [`EthStream`](crate::EthStream) only accepts during the handshake.
+    pub fn replay(&self) -> Result<P2PStream<ReplayStream>, P2PStreamError> {
+        let shared_capabilities = self.header.shared_capabilities()?;
+        let eth =
+            shared_capabilities.eth().cloned().ok_or(P2PHandshakeError::NoSharedCapabilities)?;
+        let stream = ReplayStream::new(self.inbound().filter(|msg| !msg.is_keepalive()).cloned());
+        Ok(P2PStream::with_shared_capabilities(stream, eth, shared_capabilities))
+    }
+}
+
+/// A transport that yields the captured inbound messages of a [`CaptureFile`] as compressed
+/// frames, like the [`ECIESStream`](reth_ecies::stream::ECIESStream) a [`P2PStream`] is usually
+/// constructed from.
+///
+/// The stream ends after the last message. Messages sent to the stream are discarded.
+#[derive(Debug)]
+pub struct ReplayStream {
+    /// The remaining messages to yield.
+    messages: VecDeque<CapturedMessage>,
+    /// The snappy encoder used for compressing the messages.
+    encoder: snap::raw::Encoder,
+}
+
+impl ReplayStream {
+    /// Creates a new stream that yields the given messages in order.
+    pub fn new(messages: impl IntoIterator<Item = CapturedMessage>) -> Self {
+        Self { messages: messages.into_iter().collect(), encoder: snap::raw::Encoder::new() }
+    }
+
+    /// Returns the number of messages that were not yet yielded.
+    pub fn remaining(&self) -> usize {
+        self.messages.len()
+    }
+
+    /// Compresses the payload of the message like the peer did when sending it.
+    fn compress(&mut self, message: &[u8]) -> io::Result<BytesMut> {
+        let (&id, payload) = message
+            .split_first()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "empty captured message"))?;
+        let mut compressed = BytesMut::zeroed(1 + snap::raw::max_compress_len(payload.len()));
+        let compressed_size = self
+            .encoder
+            .compress(payload, &mut compressed[1..])
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        compressed.truncate(compressed_size + 1);
+        compressed[0] = id;
+        Ok(compressed)
+    }
+}
+
+impl Stream for ReplayStream {
+    type Item = io::Result<BytesMut>;
+
+    fn poll_next(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let Some(message) = this.messages.pop_front() else { return Poll::Ready(None) };
+        Poll::Ready(Some(this.compress(&message.message)))
+    }
+}
+
+impl Sink<Bytes> for ReplayStream {
+    type Error = io::Error;
+
+    fn poll_ready(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(self: Pin<&mut Self>, _item: Bytes) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Returns the current time in microseconds since the unix epoch.
+fn unix_timestamp_micros() -> u64 {
+    let elapsed = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    u64::try_from(elapsed.as_micros()).unwrap_or(u64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        types::message::RequestPair, EthMessage, EthMessageID, EthStream, EthVersion,
+        GetBlockBodies, NewPooledTransactionHashes66, ProtocolMessage,
+    };
+    use futures::{SinkExt, StreamExt};
+    use reth_primitives::H256;
+
+    /// Returns the eth/67 capability at the default offset.
+    fn eth67() -> SharedCapability {
+        SharedCapability::new("eth", 67, 0x10, 0).unwrap()
+    }
+
+    /// Returns the captured inbound message with the absolute id of the given message.
+    fn inbound(msg: EthMessage) -> CapturedMessage {
+        let mut buf = BytesMut::new();
+        ProtocolMessage::from(msg).encode(&mut buf);
+        buf[0] += eth67().offset();
+        CapturedMessage {
+            direction: CaptureDirection::Inbound,
+            timestamp: 0,
+            message: buf.freeze(),
+        }
+    }
+
+    #[test]
+    fn test_header_roundtrip() {
+        let header = CaptureHeader::new(
+            PeerId::random(),
+            "reth/v0.1.0",
+            &vec![eth67(), SharedCapability::new("snap", 1, 0x21, 8).unwrap()].into(),
+        );
+        let mut buf = Vec::new();
+        header.encode(&mut buf);
+        let decoded = CaptureHeader::decode(&mut buf.as_slice()).unwrap();
+        assert_eq!(decoded, header);
+        assert_eq!(decoded.shared_capabilities().unwrap().len(), 2);
+        assert_eq!(decoded.shared_capabilities().unwrap().eth(), Some(&eth67()));
+    }
+
+    #[tokio::test]
+    async fn test_capture_and_replay() {
+        let dir = std::env::temp_dir().join(format!("reth-capture-{}", rand::random::<u64>()));
+        let messages = vec![
+            EthMessage::NewPooledTransactionHashes66(NewPooledTransactionHashes66(vec![
+                H256::random(),
+            ])),
+            EthMessage::GetBlockBodies(RequestPair {
+                request_id: 1,
+                message: GetBlockBodies(vec![H256::random()]),
+            }),
+        ];
+        let response = EthMessage::GetBlockBodies(RequestPair {
+            request_id: 2,
+            message: GetBlockBodies(vec![H256::random()]),
+        });
+
+        // capture a session whose inbound messages are replayed from memory
+        let header = CaptureHeader::new(PeerId::random(), "reth/v0.1.0", &vec![eth67()].into());
+        let capture = MessageCapture::create(&dir, &header).unwrap();
+        let path = capture.path().to_path_buf();
+        let stream = ReplayStream::new(messages.iter().cloned().map(inbound));
+        let mut p2p_stream = P2PStream::new(stream, eth67());
+        p2p_stream.set_capture(capture);
+        let mut eth_stream = EthStream::new(EthVersion::Eth67, p2p_stream);
+
+        for msg in &messages {
+            assert_eq!(&eth_stream.next().await.unwrap().unwrap(), msg);
+        }
+        eth_stream.send(response.clone()).await.unwrap();
+        assert!(eth_stream.next().await.is_none());
+        drop(eth_stream);
+
+        let capture = CaptureFile::read(&path).unwrap();
+        assert_eq!(capture.header, header);
+        let captured_inbound = capture.inbound().map(|msg| msg.message.clone()).collect::<Vec<_>>();
+        let expected_inbound =
+            messages.iter().cloned().map(|msg| inbound(msg).message).collect::<Vec<_>>();
+        assert_eq!(captured_inbound, expected_inbound);
+        let outbound = capture.outbound().collect::<Vec<_>>();
+        assert_eq!(outbound.len(), 1);
+        assert_eq!(outbound[0].id(), Some(EthMessageID::GetBlockBodies as u8 + eth67().offset()));
+
+        // the pongs of the keepalive are captured as well, but not replayed
+        let pong = CapturedMessage {
+            direction: CaptureDirection::Inbound,
+            timestamp: 0,
+            message: Bytes::from(vec![P2PMessageID::Pong as u8, reth_rlp::EMPTY_LIST_CODE]),
+        };
+        assert!(pong.is_keepalive());
+        let mut capture = capture;
+        capture.messages.insert(1, pong);
+
+        // replaying the capture yields the same messages
+        let mut eth_stream = EthStream::new(EthVersion::Eth67, capture.replay().unwrap());
+        for msg in &messages {
+            assert_eq!(&eth_stream.next().await.unwrap().unwrap(), msg);
+        }
+        assert!(eth_stream.next().await.is_none());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
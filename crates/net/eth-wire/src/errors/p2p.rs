@@ -35,6 +35,8 @@ pub enum P2PStreamError {
     PingBeforeHandshake,
     #[error("too many messages buffered before sending")]
     SendBufferFull,
+    #[error("message id {id} is out of range for capability {capability}")]
+    MessageIdOutOfRange { capability: String, id: u8 },
     #[error("disconnected")]
     Disconnected(DisconnectReason),
     #[error("unknown disconnect reason: {0}")]
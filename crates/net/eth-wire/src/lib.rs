@@ -19,6 +19,7 @@
 
 pub mod builder;
 pub mod capability;
+pub mod capture;
 mod disconnect;
 pub mod errors;
 mod ethstream;
@@ -38,5 +39,7 @@ pub use crate::{
     disconnect::{CanDisconnect, DisconnectReason},
     ethstream::{EthStream, UnauthedEthStream, MAX_MESSAGE_SIZE},
     hello::HelloMessage,
-    p2pstream::{P2PMessage, P2PMessageID, P2PStream, ProtocolVersion, UnauthedP2PStream},
+    p2pstream::{
+        P2PMessage, P2PMessageID, P2PStream, ProtocolVersion, SubprotocolMessage, UnauthedP2PStream,
+    },
 };
@@ -1,10 +1,11 @@
 #![allow(dead_code, unreachable_pub, missing_docs, unused_variables)]
 use crate::{
-    capability::{Capability, SharedCapability},
+    capability::{Capability, Protocol, SharedCapabilities, SharedCapability},
+    capture::{CaptureDirection, MessageCapture},
     disconnect::CanDisconnect,
     errors::{P2PHandshakeError, P2PStreamError},
     pinger::{Pinger, PingerEvent},
-    DisconnectReason, HelloMessage,
+    DisconnectReason, EthVersion, HelloMessage,
 };
 use futures::{Sink, SinkExt, StreamExt};
 use pin_project::pin_project;
@@ -16,7 +17,7 @@ use reth_primitives::{
 };
 use reth_rlp::{Decodable, DecodeError, Encodable, EMPTY_LIST_CODE};
 use std::{
-    collections::{BTreeSet, HashMap, HashSet, VecDeque},
+    collections::{BTreeSet, HashMap, VecDeque},
     io,
     pin::Pin,
     task::{ready, Context, Poll},
@@ -33,7 +34,7 @@ const MAX_PAYLOAD_SIZE: usize = 16 * 1024 * 1024;
 
 /// [`MAX_RESERVED_MESSAGE_ID`] is the maximum message ID reserved for the `p2p` subprotocol. If
 /// there are any incoming messages with an ID greater than this, they are subprotocol messages.
-const MAX_RESERVED_MESSAGE_ID: u8 = 0x0f;
+pub(crate) const MAX_RESERVED_MESSAGE_ID: u8 = 0x0f;
 
 /// [`MAX_P2P_MESSAGE_ID`] is the maximum message ID in use for the `p2p` subprotocol.
 const MAX_P2P_MESSAGE_ID: u8 = P2PMessageID::Pong as u8;
@@ -62,6 +63,17 @@ const GRACE_PERIOD: Duration = Duration::from_secs(2);
 /// encoded data.
 const MAX_P2P_CAPACITY: usize = 2;
 
+/// [`MAX_BUFFERED_SUBPROTOCOL_MESSAGES`] is the maximum number of received messages of capabilities
+/// other than the primary capability that are buffered until they are taken via
+/// [`P2PStream::take_subprotocol_message`]. Once reached, the [`P2PStream`] returns
+/// [`Poll::Pending`] so that the caller can take them before reading from the underlying stream
+/// again.
+///
+/// Callers are expected to take all buffered messages after every poll and to handle slow
+/// consumers of these messages on their side, otherwise the stream stops reading messages of the
+/// primary capability as well.
+const MAX_BUFFERED_SUBPROTOCOL_MESSAGES: usize = 64;
+
 /// An un-authenticated [`P2PStream`]. This is consumed and returns a [`P2PStream`] after the
 /// `Hello` handshake is completed.
 #[pin_project]
@@ -89,8 +101,22 @@ where
     /// Consumes the `UnauthedP2PStream` and returns a `P2PStream` after the `Hello` handshake is
     /// completed successfully. This also returns the `Hello` message sent by the remote peer.
     pub async fn handshake(
+        self,
+        hello: HelloMessage,
+    ) -> Result<(P2PStream<S>, HelloMessage), P2PStreamError> {
+        self.handshake_with_protocols(hello, Vec::new()).await
+    }
+
+    /// Same as [`UnauthedP2PStream::handshake`], but also negotiates the given additional
+    /// protocols.
+    ///
+    /// The capabilities of the protocols must be part of the `Hello` message. Messages of shared
+    /// additional protocols are multiplexed next to `eth`, see
+    /// [`P2PStream::take_subprotocol_message`] and [`P2PStream::start_send_subprotocol`].
+    pub async fn handshake_with_protocols(
         mut self,
         hello: HelloMessage,
+        protocols: Vec<Protocol>,
     ) -> Result<(P2PStream<S>, HelloMessage), P2PStreamError> {
         tracing::trace!(?hello, "sending p2p hello to peer");
 
@@ -150,11 +176,27 @@ where
             })
         }
 
-        // determine shared capabilities (currently returns only one capability)
+        // only capabilities with a known number of messages can be shared
+        let local_protocols = hello
+            .capabilities
+            .into_iter()
+            .filter_map(|cap| {
+                if cap.name == "eth" {
+                    return EthVersion::try_from(cap.version as u8).ok().map(Protocol::eth)
+                }
+                let protocol = protocols.iter().find(|protocol| protocol.cap == cap).cloned();
+                if protocol.is_none() {
+                    tracing::debug!(%cap, "ignoring capability with unknown number of messages");
+                }
+                protocol
+            })
+            .collect();
+
+        // determine shared capabilities
         let capability_res =
-            set_capability_offsets(hello.capabilities, their_hello.capabilities.clone());
+            set_capability_offsets(local_protocols, their_hello.capabilities.clone());
 
-        let shared_capability = match capability_res {
+        let shared_capabilities = match capability_res {
             Err(err) => {
                 // we don't share any capabilities, send a disconnect message
                 self.send_disconnect(DisconnectReason::UselessPeer).await?;
@@ -163,7 +205,10 @@ where
             Ok(cap) => Ok(cap),
         }?;
 
-        let stream = P2PStream::new(self.inner, shared_capability);
+        let shared_capability =
+            shared_capabilities.eth().cloned().expect("eth is always shared; qed");
+        let stream =
+            P2PStream::with_shared_capabilities(self.inner, shared_capability, shared_capabilities);
 
         Ok((stream, their_hello))
     }
@@ -216,8 +261,17 @@ pub struct P2PStream<S> {
     pinger: Pinger,
 
     /// The supported capability for this stream.
+    ///
+    /// This is the primary capability, `eth`, whose messages are yielded by the [Stream] impl and
+    /// accepted by the [Sink] impl.
     shared_capability: SharedCapability,
 
+    /// All capabilities shared with the peer, including the primary capability.
+    shared_capabilities: SharedCapabilities,
+
+    /// Received messages of shared capabilities other than the primary capability.
+    subprotocol_messages: VecDeque<SubprotocolMessage>,
+
     /// Outgoing messages buffered for sending to the underlying stream.
     outgoing_messages: VecDeque<Bytes>,
 
@@ -228,6 +282,9 @@ pub struct P2PStream<S> {
     /// Whether this stream is currently in the process of disconnecting by sending a disconnect
     /// message.
     disconnecting: bool,
+
+    /// Records all sent and received messages to a capture file, if enabled.
+    capture: Option<MessageCapture>,
 }
 
 impl<S> P2PStream<S> {
@@ -235,15 +292,32 @@ impl<S> P2PStream<S> {
     /// New [`P2PStream`]s are assumed to have completed the `p2p` handshake successfully and are
     /// ready to send and receive subprotocol messages.
     pub fn new(inner: S, capability: SharedCapability) -> Self {
+        let shared_capabilities = vec![capability.clone()].into();
+        Self::with_shared_capabilities(inner, capability, shared_capabilities)
+    }
+
+    /// Create a new [`P2PStream`] that multiplexes all given shared capabilities.
+    ///
+    /// Messages of the primary `capability` are handled by the [Stream] and [Sink] impls, messages
+    /// of all other shared capabilities via [`P2PStream::take_subprotocol_message`] and
+    /// [`P2PStream::start_send_subprotocol`].
+    pub fn with_shared_capabilities(
+        inner: S,
+        capability: SharedCapability,
+        shared_capabilities: SharedCapabilities,
+    ) -> Self {
         Self {
             inner,
             encoder: snap::raw::Encoder::new(),
             decoder: snap::raw::Decoder::new(),
             pinger: Pinger::new(PING_INTERVAL, PING_TIMEOUT),
             shared_capability: capability,
+            shared_capabilities,
+            subprotocol_messages: VecDeque::new(),
             outgoing_messages: VecDeque::new(),
             outgoing_message_buffer_capacity: MAX_P2P_CAPACITY,
             disconnecting: false,
+            capture: None,
         }
     }
 
@@ -261,11 +335,105 @@ impl<S> P2PStream<S> {
         self.outgoing_message_buffer_capacity = capacity;
     }
 
+    /// Records all messages sent and received from now on with the given capture.
+    ///
+    /// Outgoing messages are recorded when they are queued for sending. See also
+    /// [`crate::capture`].
+    pub fn set_capture(&mut self, capture: MessageCapture) {
+        self.capture = Some(capture);
+    }
+
+    /// Returns the capture that records the messages of this stream, if enabled.
+    pub fn capture(&self) -> Option<&MessageCapture> {
+        self.capture.as_ref()
+    }
+
+    /// Records the message with the given absolute message id and uncompressed payload, if
+    /// capturing is enabled.
+    ///
+    /// Capturing is disabled if the message can't be written to the capture file.
+    fn capture_message(&mut self, direction: CaptureDirection, id: u8, payload: &[u8]) {
+        let Some(capture) = self.capture.as_mut() else { return };
+        if let Err(err) = capture.record(direction, id, payload) {
+            tracing::warn!(?err, path=?capture.path(), "failed to capture p2p message, disabling capture");
+            self.capture = None;
+        }
+    }
+
     /// Returns the shared capability for this stream.
     pub fn shared_capability(&self) -> &SharedCapability {
         &self.shared_capability
     }
 
+    /// Returns all capabilities shared with the peer.
+    pub fn shared_capabilities(&self) -> &SharedCapabilities {
+        &self.shared_capabilities
+    }
+
+    /// Returns the next received message of a shared capability other than the primary
+    /// capability, if any.
+    ///
+    /// These messages are buffered while the [Stream] is polled. If they are not taken, the stream
+    /// stops reading once [`MAX_BUFFERED_SUBPROTOCOL_MESSAGES`] messages are buffered.
+    pub fn take_subprotocol_message(&mut self) -> Option<SubprotocolMessage> {
+        self.subprotocol_messages.pop_front()
+    }
+
+    /// Returns `true` if the subprotocol message buffer is full.
+    fn is_subprotocol_buffer_full(&self) -> bool {
+        self.subprotocol_messages.len() >= MAX_BUFFERED_SUBPROTOCOL_MESSAGES
+    }
+
+    /// Queues a message of the given shared capability for sending.
+    ///
+    /// The first byte of the message is the message id relative to the capability, like for the
+    /// [Sink] impl. Callers must ensure the stream is ready to send via [`Sink::poll_ready`].
+    pub fn start_send_subprotocol(
+        &mut self,
+        capability: &SharedCapability,
+        item: Bytes,
+    ) -> Result<(), P2PStreamError> {
+        let id = *item.first().ok_or(P2PStreamError::EmptyProtocolMessage)?;
+        if id >= capability.num_messages() {
+            return Err(P2PStreamError::MessageIdOutOfRange {
+                capability: capability.capability().to_string(),
+                id,
+            })
+        }
+        self.queue_message(capability.offset(), item)
+    }
+
+    /// Compresses the message and queues it with the message id shifted by the given offset.
+    fn queue_message(&mut self, offset: u8, item: Bytes) -> Result<(), P2PStreamError> {
+        // ensure we have free capacity
+        if !self.has_outgoing_capacity() {
+            return Err(P2PStreamError::SendBufferFull)
+        }
+
+        let mut compressed = BytesMut::zeroed(1 + snap::raw::max_compress_len(item.len() - 1));
+        let compressed_size =
+            self.encoder.compress(&item[1..], &mut compressed[1..]).map_err(|err| {
+                tracing::debug!(
+                    ?err,
+                    msg=%hex::encode(&item[1..]),
+                    "error compressing p2p message"
+                );
+                err
+            })?;
+
+        // truncate the compressed buffer to the actual compressed size (plus one for the message
+        // id)
+        compressed.truncate(compressed_size + 1);
+
+        // all messages sent in this stream are subprotocol messages, so we need to switch the
+        // message id based on the offset
+        compressed[0] = item[0] + offset;
+        self.capture_message(CaptureDirection::Outbound, compressed[0], &item[1..]);
+        self.outgoing_messages.push_back(compressed.freeze());
+
+        Ok(())
+    }
+
     /// Returns `true` if the connection is about to disconnect.
     pub fn is_disconnecting(&self) -> bool {
         self.disconnecting
@@ -281,6 +449,11 @@ impl<S> P2PStream<S> {
         let pong = P2PMessage::Pong;
         let mut pong_bytes = BytesMut::with_capacity(pong.length());
         pong.encode(&mut pong_bytes);
+        self.capture_message(
+            CaptureDirection::Outbound,
+            P2PMessageID::Pong as u8,
+            &[EMPTY_LIST_CODE],
+        );
         self.outgoing_messages.push_back(pong_bytes.freeze());
     }
 
@@ -289,6 +462,11 @@ impl<S> P2PStream<S> {
         let ping = P2PMessage::Ping;
         let mut ping_bytes = BytesMut::with_capacity(ping.length());
         ping.encode(&mut ping_bytes);
+        self.capture_message(
+            CaptureDirection::Outbound,
+            P2PMessageID::Ping as u8,
+            &[EMPTY_LIST_CODE],
+        );
         self.outgoing_messages.push_back(ping_bytes.freeze());
     }
 
@@ -325,6 +503,7 @@ impl<S> P2PStream<S> {
         // we do not add the capability offset because the disconnect message is a `p2p` reserved
         // message
         compressed[0] = buf[0];
+        self.capture_message(CaptureDirection::Outbound, buf[0], &buf[1..]);
 
         self.outgoing_messages.push_back(compressed.freeze());
         self.disconnecting = true;
@@ -362,6 +541,11 @@ where
             return Poll::Ready(None)
         }
 
+        if this.is_subprotocol_buffer_full() {
+            // stop reading until the buffered messages of other capabilities are taken
+            return Poll::Pending
+        }
+
         // we should loop here to ensure we don't return Poll::Pending if we have a message to
         // return behind any pings we need to respond to
         while let Poll::Ready(res) = this.inner.poll_next_unpin(cx) {
@@ -397,6 +581,7 @@ where
             })?;
 
             let id = *bytes.first().ok_or(P2PStreamError::EmptyProtocolMessage)?;
+            this.capture_message(CaptureDirection::Inbound, id, &decompress_buf[1..]);
             match id {
                 _ if id == P2PMessageID::Ping as u8 => {
                     tracing::trace!("Received Ping, Sending Pong");
@@ -453,6 +638,21 @@ where
                     //  * `eth/67` is reserved message IDs 0x10 - 0x19.
                     //  * `qrs/65` is reserved message IDs 0x1a - 0x21.
                     //
+                    if !this.shared_capability.contains_message_id(id) {
+                        if let Some(cap) = this.shared_capabilities.find_by_message_id(id) {
+                            // message of another shared capability, buffer it until taken
+                            decompress_buf[0] = id - cap.offset();
+                            this.subprotocol_messages.push_back(SubprotocolMessage {
+                                capability: cap.clone(),
+                                message: decompress_buf,
+                            });
+                            if this.is_subprotocol_buffer_full() {
+                                return Poll::Pending
+                            }
+                            continue
+                        }
+                    }
+
                     decompress_buf[0] = bytes[0] - this.shared_capability.offset();
 
                     return Poll::Ready(Some(Ok(decompress_buf)))
@@ -508,34 +708,9 @@ where
     }
 
     fn start_send(self: Pin<&mut Self>, item: Bytes) -> Result<(), Self::Error> {
-        // ensure we have free capacity
-        if !self.has_outgoing_capacity() {
-            return Err(P2PStreamError::SendBufferFull)
-        }
-
-        let this = self.project();
-
-        let mut compressed = BytesMut::zeroed(1 + snap::raw::max_compress_len(item.len() - 1));
-        let compressed_size =
-            this.encoder.compress(&item[1..], &mut compressed[1..]).map_err(|err| {
-                tracing::debug!(
-                    ?err,
-                    msg=%hex::encode(&item[1..]),
-                    "error compressing p2p message"
-                );
-                err
-            })?;
-
-        // truncate the compressed buffer to the actual compressed size (plus one for the message
-        // id)
-        compressed.truncate(compressed_size + 1);
-
-        // all messages sent in this stream are subprotocol messages, so we need to switch the
-        // message id based on the offset
-        compressed[0] = item[0] + this.shared_capability.offset();
-        this.outgoing_messages.push_back(compressed.freeze());
-
-        Ok(())
+        let this = self.get_mut();
+        let offset = this.shared_capability.offset();
+        this.queue_message(offset, item)
     }
 
     /// Returns Poll::Ready(Ok(())) when no buffered items remain and the sink has been successfully
@@ -564,18 +739,30 @@ where
     }
 }
 
+/// A received message of a shared capability other than the primary capability of the
+/// [`P2PStream`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubprotocolMessage {
+    /// The capability the message belongs to.
+    pub capability: SharedCapability,
+    /// The message, starting with the message id relative to the capability.
+    pub message: BytesMut,
+}
+
 /// Determines the offsets for each shared capability between the input list of peer
-/// capabilities and the input list of locally supported capabilities.
+/// capabilities and the input list of locally supported protocols.
 ///
-/// Currently only `eth` versions 66 and 67 are supported.
-/// Additionally, the `p2p` capability version 5 is supported, but is
-/// expected _not_ to be in neither `local_capabilities` or `peer_capabilities`.
+/// The `eth` capability must be shared. Additionally, the `p2p` capability version 5 is supported,
+/// but is expected _not_ to be in neither `local_protocols` or `peer_capabilities`.
 pub fn set_capability_offsets(
-    local_capabilities: Vec<Capability>,
+    local_protocols: Vec<Protocol>,
     peer_capabilities: Vec<Capability>,
-) -> Result<SharedCapability, P2PStreamError> {
+) -> Result<SharedCapabilities, P2PStreamError> {
     // find intersection of capabilities
-    let our_capabilities = local_capabilities.into_iter().collect::<HashSet<_>>();
+    let our_capabilities = local_protocols
+        .into_iter()
+        .map(|protocol| (protocol.cap, protocol.messages))
+        .collect::<HashMap<_, _>>();
 
     // map of capability name to version
     let mut shared_capabilities = HashMap::new();
@@ -597,7 +784,7 @@ pub fn set_capability_offsets(
     // find highest shared version of each shared capability
     for peer_capability in peer_capabilities {
         // if this is Some, we share this capability
-        if our_capabilities.contains(&peer_capability) {
+        if let Some(messages) = our_capabilities.get(&peer_capability) {
             // If multiple versions are shared of the same (equal name) capability, the numerically
             // highest wins, others are ignored
 
@@ -605,7 +792,8 @@ pub fn set_capability_offsets(
             if version.is_none() ||
                 (version.is_some() && peer_capability.version > *version.expect("is some; qed"))
             {
-                shared_capabilities.insert(peer_capability.name.clone(), peer_capability.version);
+                shared_capabilities
+                    .insert(peer_capability.name.clone(), (peer_capability.version, *messages));
                 shared_capability_names.insert(peer_capability.name);
             }
         }
@@ -625,35 +813,25 @@ pub fn set_capability_offsets(
     // alphabetic order.
     let mut offset = MAX_RESERVED_MESSAGE_ID + 1;
     for name in shared_capability_names {
-        let version = shared_capabilities.get(&name).unwrap();
+        let (version, messages) = shared_capabilities[&name];
 
-        let shared_capability = SharedCapability::new(&name, *version as u8, offset)?;
+        let shared_capability = SharedCapability::new(&name, version as u8, offset, messages)?;
 
-        match shared_capability {
-            SharedCapability::UnknownCapability { .. } => {
-                // Capabilities which are not shared are ignored
-                tracing::debug!("unknown capability: name={:?}, version={}", name, version,);
-            }
-            SharedCapability::Eth { .. } => {
-                // increment the offset if the capability is known
-                offset += shared_capability.num_messages()?;
+        offset = offset
+            .checked_add(shared_capability.num_messages())
+            .ok_or(P2PStreamError::HandshakeError(P2PHandshakeError::NoSharedCapabilities))?;
 
-                shared_with_offsets.push(shared_capability);
-            }
-        }
+        shared_with_offsets.push(shared_capability);
     }
 
-    // TODO: support multiple capabilities - we would need a new Stream type to go on top of
-    // `P2PStream` containing its capability. `P2PStream` would still send pings and handle
-    // pongs, but instead contain a map of capabilities to their respective stream / channel.
-    // Each channel would be responsible for containing the offset for that stream and would
-    // only increment / decrement message IDs.
-    // NOTE: since the `P2PStream` currently only supports one capability, we set the
-    // capability with the lowest offset.
-    Ok(shared_with_offsets
-        .first()
-        .ok_or(P2PStreamError::HandshakeError(P2PHandshakeError::NoSharedCapabilities))?
-        .clone())
+    let shared_capabilities = SharedCapabilities::from(shared_with_offsets);
+
+    // all sessions are `eth` sessions, additional protocols are multiplexed next to it
+    if shared_capabilities.eth().is_none() {
+        return Err(P2PStreamError::HandshakeError(P2PHandshakeError::NoSharedCapabilities))
+    }
+
+    Ok(shared_capabilities)
 }
 
 /// This represents only the reserved `p2p` subprotocol messages.
@@ -839,7 +1017,7 @@ impl Decodable for ProtocolVersion {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{DisconnectReason, EthVersion};
+    use crate::DisconnectReason;
     use reth_discv4::DEFAULT_DISCOVERY_PORT;
     use reth_ecies::util::pk2id;
     use secp256k1::{SecretKey, SECP256K1};
@@ -1000,16 +1178,16 @@ mod tests {
 
     #[test]
     fn test_peer_lower_capability_version() {
-        let local_capabilities: Vec<Capability> =
+        let local_capabilities: Vec<Protocol> =
             vec![EthVersion::Eth66.into(), EthVersion::Eth67.into(), EthVersion::Eth68.into()];
         let peer_capabilities: Vec<Capability> = vec![EthVersion::Eth66.into()];
 
-        let shared_capability =
+        let shared_capabilities =
             set_capability_offsets(local_capabilities, peer_capabilities).unwrap();
 
         assert_eq!(
-            shared_capability,
-            SharedCapability::Eth {
+            shared_capabilities.eth().unwrap(),
+            &SharedCapability::Eth {
                 version: EthVersion::Eth66,
                 offset: MAX_RESERVED_MESSAGE_ID + 1
             }
@@ -1018,7 +1196,7 @@ mod tests {
 
     #[test]
     fn test_peer_capability_version_too_low() {
-        let local_capabilities: Vec<Capability> = vec![EthVersion::Eth67.into()];
+        let local_capabilities: Vec<Protocol> = vec![EthVersion::Eth67.into()];
         let peer_capabilities: Vec<Capability> = vec![EthVersion::Eth66.into()];
 
         let shared_capability = set_capability_offsets(local_capabilities, peer_capabilities);
@@ -1031,7 +1209,7 @@ mod tests {
 
     #[test]
     fn test_peer_capability_version_too_high() {
-        let local_capabilities: Vec<Capability> = vec![EthVersion::Eth66.into()];
+        let local_capabilities: Vec<Protocol> = vec![EthVersion::Eth66.into()];
         let peer_capabilities: Vec<Capability> = vec![EthVersion::Eth67.into()];
 
         let shared_capability = set_capability_offsets(local_capabilities, peer_capabilities);
@@ -1042,6 +1220,100 @@ mod tests {
         ))
     }
 
+    #[test]
+    fn test_custom_capability_offsets() {
+        let ours = Capability::new("ourproto".into(), 1);
+        let other = Capability::new("aaa".into(), 1);
+        let local_protocols = vec![
+            EthVersion::Eth68.into(),
+            Protocol::new(ours.clone(), 3),
+            Protocol::new(other.clone(), 2),
+        ];
+        let peer_capabilities =
+            vec![EthVersion::Eth68.into(), ours, other, Capability::new("zzz".into(), 1)];
+
+        let shared_capabilities =
+            set_capability_offsets(local_protocols, peer_capabilities).unwrap();
+
+        // capabilities are ordered by name, `aaa` comes first
+        assert_eq!(shared_capabilities.len(), 3);
+        assert_eq!(
+            shared_capabilities.find_by_name("aaa").unwrap().offset(),
+            MAX_RESERVED_MESSAGE_ID + 1
+        );
+        assert_eq!(shared_capabilities.eth().unwrap().offset(), MAX_RESERVED_MESSAGE_ID + 3);
+        assert_eq!(
+            shared_capabilities.find_by_name("ourproto").unwrap().offset(),
+            MAX_RESERVED_MESSAGE_ID + 3 + EthVersion::Eth68.total_messages()
+        );
+        assert!(shared_capabilities.find_by_name("zzz").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_multiplex_custom_capability() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let local_addr = listener.local_addr().unwrap();
+
+        let protocol = Protocol::new(Capability::new("ourproto".into(), 1), 3);
+        let hello_with_protocol = || {
+            let (mut hello, _) = eth_hello();
+            hello.capabilities.push(protocol.cap.clone());
+            hello
+        };
+
+        let eth_message = Bytes::from_static(&[0x01, 0xc0]);
+        let custom_message = Bytes::from_static(&[0x02, 0xc1, 0x80]);
+
+        let server_protocol = protocol.clone();
+        let server_hello = hello_with_protocol();
+        let expected_eth = eth_message.clone();
+        let expected_custom = custom_message.clone();
+        let handle = tokio::spawn(async move {
+            let (incoming, _) = listener.accept().await.unwrap();
+            let stream = crate::PassthroughCodec::default().framed(incoming);
+
+            let (mut p2p_stream, _) = UnauthedP2PStream::new(stream)
+                .handshake_with_protocols(server_hello, vec![server_protocol])
+                .await
+                .unwrap();
+
+            // the custom message is sent first and buffered, the eth message is yielded
+            let msg = p2p_stream.next().await.unwrap().unwrap();
+            assert_eq!(msg.as_ref(), expected_eth.as_ref());
+
+            let custom = p2p_stream.take_subprotocol_message().unwrap();
+            assert_eq!(custom.capability.name(), "ourproto");
+            assert_eq!(custom.message.as_ref(), expected_custom.as_ref());
+            assert!(p2p_stream.take_subprotocol_message().is_none());
+        });
+
+        let outgoing = TcpStream::connect(local_addr).await.unwrap();
+        let sink = crate::PassthroughCodec::default().framed(outgoing);
+
+        let (mut p2p_stream, _) = UnauthedP2PStream::new(sink)
+            .handshake_with_protocols(hello_with_protocol(), vec![protocol])
+            .await
+            .unwrap();
+
+        let shared = p2p_stream.shared_capabilities().find_by_name("ourproto").cloned().unwrap();
+        assert_eq!(
+            shared.offset(),
+            MAX_RESERVED_MESSAGE_ID + 1 + EthVersion::Eth67.total_messages()
+        );
+
+        // message ids beyond the protocol's message count are rejected
+        assert!(matches!(
+            p2p_stream.start_send_subprotocol(&shared, Bytes::from_static(&[0x03, 0xc0])),
+            Err(P2PStreamError::MessageIdOutOfRange { id: 3, .. })
+        ));
+
+        futures::future::poll_fn(|cx| p2p_stream.poll_ready_unpin(cx)).await.unwrap();
+        p2p_stream.start_send_subprotocol(&shared, custom_message).unwrap();
+        p2p_stream.send(eth_message).await.unwrap();
+
+        handle.await.unwrap();
+    }
+
     #[test]
     fn snappy_decode_encode_ping() {
         let snappy_ping = b"\x02\x01\0\xc0";
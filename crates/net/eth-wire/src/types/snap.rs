@@ -0,0 +1,510 @@
+//! Implements the `snap/1` protocol messages.
+//!
+//! See also <https://github.com/ethereum/devp2p/blob/master/caps/snap.md>
+use reth_codecs::derive_arbitrary;
+use reth_primitives::{
+    bytes::{Buf, BufMut},
+    proofs::EMPTY_ROOT,
+    Account, Bytes, H256, KECCAK_EMPTY, U256,
+};
+use reth_rlp::{Decodable, DecodeError, Encodable, Header, RlpDecodable, RlpEncodable};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The version of the `snap` protocol.
+pub const SNAP_VERSION: usize = 1;
+
+/// The number of message IDs reserved by the `snap/1` protocol.
+pub const SNAP_MESSAGE_COUNT: u8 = 8;
+
+/// An account in the "slim" format used by the `snap` protocol.
+///
+/// It's the same as the account in the state trie, except that the empty storage root and the
+/// empty code hash are encoded as empty byte strings.
+#[derive_arbitrary(rlp)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SnapAccount {
+    /// Account nonce.
+    pub nonce: u64,
+    /// Account balance.
+    pub balance: U256,
+    /// Root of the account storage trie.
+    pub storage_root: H256,
+    /// Hash of the account bytecode.
+    pub code_hash: H256,
+}
+
+impl SnapAccount {
+    fn encode_hash(hash: &H256, empty: H256, out: &mut dyn BufMut) {
+        if *hash == empty {
+            out.put_u8(reth_rlp::EMPTY_STRING_CODE);
+        } else {
+            hash.encode(out);
+        }
+    }
+
+    fn hash_length(hash: &H256, empty: H256) -> usize {
+        if *hash == empty {
+            1
+        } else {
+            hash.length()
+        }
+    }
+
+    fn decode_hash(buf: &mut &[u8], empty: H256) -> Result<H256, DecodeError> {
+        if buf.first() == Some(&reth_rlp::EMPTY_STRING_CODE) {
+            buf.advance(1);
+            Ok(empty)
+        } else {
+            H256::decode(buf)
+        }
+    }
+
+    fn payload_length(&self) -> usize {
+        self.nonce.length() +
+            self.balance.length() +
+            Self::hash_length(&self.storage_root, EMPTY_ROOT) +
+            Self::hash_length(&self.code_hash, KECCAK_EMPTY)
+    }
+}
+
+impl From<SnapAccount> for Account {
+    fn from(account: SnapAccount) -> Self {
+        Account {
+            nonce: account.nonce,
+            balance: account.balance,
+            bytecode_hash: (account.code_hash != KECCAK_EMPTY).then_some(account.code_hash),
+        }
+    }
+}
+
+impl Encodable for SnapAccount {
+    fn encode(&self, out: &mut dyn BufMut) {
+        Header { list: true, payload_length: self.payload_length() }.encode(out);
+        self.nonce.encode(out);
+        self.balance.encode(out);
+        Self::encode_hash(&self.storage_root, EMPTY_ROOT, out);
+        Self::encode_hash(&self.code_hash, KECCAK_EMPTY, out);
+    }
+
+    fn length(&self) -> usize {
+        let payload_length = self.payload_length();
+        payload_length + reth_rlp::length_of_length(payload_length)
+    }
+}
+
+impl Decodable for SnapAccount {
+    fn decode(buf: &mut &[u8]) -> Result<Self, DecodeError> {
+        let header = Header::decode(buf)?;
+        if !header.list {
+            return Err(DecodeError::UnexpectedString)
+        }
+        let started_len = buf.len();
+
+        let this = Self {
+            nonce: u64::decode(buf)?,
+            balance: U256::decode(buf)?,
+            storage_root: Self::decode_hash(buf, EMPTY_ROOT)?,
+            code_hash: Self::decode_hash(buf, KECCAK_EMPTY)?,
+        };
+
+        let consumed = started_len - buf.len();
+        if consumed != header.payload_length {
+            return Err(DecodeError::ListLengthMismatch {
+                expected: header.payload_length,
+                got: consumed,
+            })
+        }
+        Ok(this)
+    }
+}
+
+/// A request for the accounts of the state trie with the given root, starting at `starting_hash`.
+#[derive_arbitrary(rlp)]
+#[derive(Clone, Debug, PartialEq, Eq, RlpEncodable, RlpDecodable, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GetAccountRange {
+    /// The id of the request.
+    pub request_id: u64,
+    /// Root of the state trie to serve the accounts from.
+    pub root_hash: H256,
+    /// Hash of the first account to retrieve.
+    pub starting_hash: H256,
+    /// Hash of the account after which to stop serving data.
+    pub limit_hash: H256,
+    /// Soft limit at which to stop returning data.
+    pub response_bytes: u64,
+}
+
+/// An account of an [`AccountRange`] response.
+#[derive_arbitrary(rlp)]
+#[derive(Clone, Debug, PartialEq, Eq, RlpEncodable, RlpDecodable, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AccountData {
+    /// Hash of the account address.
+    pub hash: H256,
+    /// The account in the slim format.
+    pub body: SnapAccount,
+}
+
+/// The response to [`GetAccountRange`], containing consecutive accounts and the merkle proofs for
+/// the starting hash and the last returned account.
+#[derive_arbitrary(rlp)]
+#[derive(Clone, Debug, PartialEq, Eq, RlpEncodable, RlpDecodable, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AccountRange {
+    /// The id of the request this is a response to.
+    pub request_id: u64,
+    /// The accounts, ordered by their hashes.
+    pub accounts: Vec<AccountData>,
+    /// The trie nodes proving the range.
+    pub proof: Vec<Bytes>,
+}
+
+/// A request for the storage slots of multiple accounts of the state trie with the given root.
+///
+/// The starting and limit hashes only apply to the first account. They are byte strings, because
+/// they are allowed to be empty.
+#[derive_arbitrary(rlp)]
+#[derive(Clone, Debug, PartialEq, Eq, RlpEncodable, RlpDecodable, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GetStorageRanges {
+    /// The id of the request.
+    pub request_id: u64,
+    /// Root of the state trie to serve the storage slots from.
+    pub root_hash: H256,
+    /// Hashes of the addresses of the accounts to retrieve the storage slots for.
+    pub account_hashes: Vec<H256>,
+    /// Hash of the first storage slot to retrieve.
+    pub starting_hash: Bytes,
+    /// Hash of the storage slot after which to stop serving data.
+    pub limit_hash: Bytes,
+    /// Soft limit at which to stop returning data.
+    pub response_bytes: u64,
+}
+
+/// A storage slot of a [`StorageRanges`] response.
+#[derive_arbitrary(rlp)]
+#[derive(Clone, Debug, PartialEq, Eq, RlpEncodable, RlpDecodable, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct StorageData {
+    /// Hash of the storage slot key.
+    pub hash: H256,
+    /// The RLP encoded value of the storage slot.
+    pub data: Bytes,
+}
+
+/// The response to [`GetStorageRanges`], containing the storage slots of the requested accounts.
+///
+/// Only the last storage range may be partial, in which case the merkle proofs for it are
+/// attached.
+#[derive_arbitrary(rlp)]
+#[derive(Clone, Debug, PartialEq, Eq, RlpEncodable, RlpDecodable, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct StorageRanges {
+    /// The id of the request this is a response to.
+    pub request_id: u64,
+    /// The storage slots of each account, ordered by their hashes.
+    pub slots: Vec<Vec<StorageData>>,
+    /// The trie nodes proving the last storage range.
+    pub proof: Vec<Bytes>,
+}
+
+/// A request for contract bytecodes by their hashes.
+#[derive_arbitrary(rlp)]
+#[derive(Clone, Debug, PartialEq, Eq, RlpEncodable, RlpDecodable, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GetByteCodes {
+    /// The id of the request.
+    pub request_id: u64,
+    /// The code hashes to retrieve the bytecodes for.
+    pub hashes: Vec<H256>,
+    /// Soft limit at which to stop returning data.
+    pub response_bytes: u64,
+}
+
+/// The response to [`GetByteCodes`], containing the bytecodes in the order of the request.
+///
+/// Unavailable bytecodes are skipped.
+#[derive_arbitrary(rlp)]
+#[derive(Clone, Debug, PartialEq, Eq, RlpEncodable, RlpDecodable, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ByteCodes {
+    /// The id of the request this is a response to.
+    pub request_id: u64,
+    /// The requested bytecodes.
+    pub codes: Vec<Bytes>,
+}
+
+/// A request for trie nodes of the state trie with the given root, by their paths.
+#[derive_arbitrary(rlp)]
+#[derive(Clone, Debug, PartialEq, Eq, RlpEncodable, RlpDecodable, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GetTrieNodes {
+    /// The id of the request.
+    pub request_id: u64,
+    /// Root of the state trie to serve the nodes from.
+    pub root_hash: H256,
+    /// The compact-encoded paths of the nodes to retrieve.
+    ///
+    /// The first path of each set is in the account trie, the following ones are in the storage
+    /// trie of that account. A set with a single path requests a node of the account trie.
+    pub paths: Vec<Vec<Bytes>>,
+    /// Soft limit at which to stop returning data.
+    pub response_bytes: u64,
+}
+
+/// The response to [`GetTrieNodes`], containing the RLP encoded trie nodes in the order of the
+/// requested paths.
+#[derive_arbitrary(rlp)]
+#[derive(Clone, Debug, PartialEq, Eq, RlpEncodable, RlpDecodable, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TrieNodes {
+    /// The id of the request this is a response to.
+    pub request_id: u64,
+    /// The requested trie nodes.
+    pub nodes: Vec<Bytes>,
+}
+
+/// Represents message IDs for `snap/1` protocol messages, relative to the offset of the
+/// capability.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum SnapMessageID {
+    /// [`GetAccountRange`] request.
+    GetAccountRange = 0x00,
+    /// [`AccountRange`] response.
+    AccountRange = 0x01,
+    /// [`GetStorageRanges`] request.
+    GetStorageRanges = 0x02,
+    /// [`StorageRanges`] response.
+    StorageRanges = 0x03,
+    /// [`GetByteCodes`] request.
+    GetByteCodes = 0x04,
+    /// [`ByteCodes`] response.
+    ByteCodes = 0x05,
+    /// [`GetTrieNodes`] request.
+    GetTrieNodes = 0x06,
+    /// [`TrieNodes`] response.
+    TrieNodes = 0x07,
+}
+
+impl Encodable for SnapMessageID {
+    fn encode(&self, out: &mut dyn BufMut) {
+        out.put_u8(*self as u8);
+    }
+    fn length(&self) -> usize {
+        1
+    }
+}
+
+impl Decodable for SnapMessageID {
+    fn decode(buf: &mut &[u8]) -> Result<Self, DecodeError> {
+        let id = buf.first().ok_or(DecodeError::InputTooShort)?;
+        let id = match id {
+            0x00 => SnapMessageID::GetAccountRange,
+            0x01 => SnapMessageID::AccountRange,
+            0x02 => SnapMessageID::GetStorageRanges,
+            0x03 => SnapMessageID::StorageRanges,
+            0x04 => SnapMessageID::GetByteCodes,
+            0x05 => SnapMessageID::ByteCodes,
+            0x06 => SnapMessageID::GetTrieNodes,
+            0x07 => SnapMessageID::TrieNodes,
+            _ => return Err(DecodeError::Custom("Invalid message ID")),
+        };
+        buf.advance(1);
+        Ok(id)
+    }
+}
+
+/// Represents a message in the `snap/1` protocol.
+///
+/// All messages are request-response pairs, and carry the request id as their first field.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[allow(missing_docs)]
+pub enum SnapMessage {
+    GetAccountRange(GetAccountRange),
+    AccountRange(AccountRange),
+    GetStorageRanges(GetStorageRanges),
+    StorageRanges(StorageRanges),
+    GetByteCodes(GetByteCodes),
+    ByteCodes(ByteCodes),
+    GetTrieNodes(GetTrieNodes),
+    TrieNodes(TrieNodes),
+}
+
+impl SnapMessage {
+    /// Returns the message's ID.
+    pub fn message_id(&self) -> SnapMessageID {
+        match self {
+            SnapMessage::GetAccountRange(_) => SnapMessageID::GetAccountRange,
+            SnapMessage::AccountRange(_) => SnapMessageID::AccountRange,
+            SnapMessage::GetStorageRanges(_) => SnapMessageID::GetStorageRanges,
+            SnapMessage::StorageRanges(_) => SnapMessageID::StorageRanges,
+            SnapMessage::GetByteCodes(_) => SnapMessageID::GetByteCodes,
+            SnapMessage::ByteCodes(_) => SnapMessageID::ByteCodes,
+            SnapMessage::GetTrieNodes(_) => SnapMessageID::GetTrieNodes,
+            SnapMessage::TrieNodes(_) => SnapMessageID::TrieNodes,
+        }
+    }
+
+    /// Returns the request id of the message.
+    pub fn request_id(&self) -> u64 {
+        match self {
+            SnapMessage::GetAccountRange(msg) => msg.request_id,
+            SnapMessage::AccountRange(msg) => msg.request_id,
+            SnapMessage::GetStorageRanges(msg) => msg.request_id,
+            SnapMessage::StorageRanges(msg) => msg.request_id,
+            SnapMessage::GetByteCodes(msg) => msg.request_id,
+            SnapMessage::ByteCodes(msg) => msg.request_id,
+            SnapMessage::GetTrieNodes(msg) => msg.request_id,
+            SnapMessage::TrieNodes(msg) => msg.request_id,
+        }
+    }
+
+    /// Decodes a message prefixed with its relative message ID.
+    pub fn decode_message(buf: &mut &[u8]) -> Result<Self, DecodeError> {
+        let message = match SnapMessageID::decode(buf)? {
+            SnapMessageID::GetAccountRange => {
+                SnapMessage::GetAccountRange(GetAccountRange::decode(buf)?)
+            }
+            SnapMessageID::AccountRange => SnapMessage::AccountRange(AccountRange::decode(buf)?),
+            SnapMessageID::GetStorageRanges => {
+                SnapMessage::GetStorageRanges(GetStorageRanges::decode(buf)?)
+            }
+            SnapMessageID::StorageRanges => SnapMessage::StorageRanges(StorageRanges::decode(buf)?),
+            SnapMessageID::GetByteCodes => SnapMessage::GetByteCodes(GetByteCodes::decode(buf)?),
+            SnapMessageID::ByteCodes => SnapMessage::ByteCodes(ByteCodes::decode(buf)?),
+            SnapMessageID::GetTrieNodes => SnapMessage::GetTrieNodes(GetTrieNodes::decode(buf)?),
+            SnapMessageID::TrieNodes => SnapMessage::TrieNodes(TrieNodes::decode(buf)?),
+        };
+        Ok(message)
+    }
+}
+
+/// Encodes the message prefixed with its relative message ID.
+impl Encodable for SnapMessage {
+    fn encode(&self, out: &mut dyn BufMut) {
+        self.message_id().encode(out);
+        match self {
+            SnapMessage::GetAccountRange(msg) => msg.encode(out),
+            SnapMessage::AccountRange(msg) => msg.encode(out),
+            SnapMessage::GetStorageRanges(msg) => msg.encode(out),
+            SnapMessage::StorageRanges(msg) => msg.encode(out),
+            SnapMessage::GetByteCodes(msg) => msg.encode(out),
+            SnapMessage::ByteCodes(msg) => msg.encode(out),
+            SnapMessage::GetTrieNodes(msg) => msg.encode(out),
+            SnapMessage::TrieNodes(msg) => msg.encode(out),
+        }
+    }
+
+    fn length(&self) -> usize {
+        self.message_id().length() +
+            match self {
+                SnapMessage::GetAccountRange(msg) => msg.length(),
+                SnapMessage::AccountRange(msg) => msg.length(),
+                SnapMessage::GetStorageRanges(msg) => msg.length(),
+                SnapMessage::StorageRanges(msg) => msg.length(),
+                SnapMessage::GetByteCodes(msg) => msg.length(),
+                SnapMessage::ByteCodes(msg) => msg.length(),
+                SnapMessage::GetTrieNodes(msg) => msg.length(),
+                SnapMessage::TrieNodes(msg) => msg.length(),
+            }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use hex_literal::hex;
+
+    fn encode<T: Encodable>(value: T) -> Vec<u8> {
+        let mut buf = vec![];
+        value.encode(&mut buf);
+        buf
+    }
+
+    #[test]
+    fn slim_account() {
+        let empty = SnapAccount {
+            nonce: 1,
+            balance: U256::from(2),
+            storage_root: EMPTY_ROOT,
+            code_hash: KECCAK_EMPTY,
+        };
+        let encoded = encode(empty);
+        assert_eq!(encoded, hex!("c401028080"));
+        assert_eq!(empty.length(), encoded.len());
+        assert_eq!(SnapAccount::decode(&mut &encoded[..]).unwrap(), empty);
+
+        let contract = SnapAccount { storage_root: H256::repeat_byte(3), ..empty };
+        let encoded = encode(contract);
+        assert_eq!(contract.length(), encoded.len());
+        assert_eq!(SnapAccount::decode(&mut &encoded[..]).unwrap(), contract);
+    }
+
+    #[test]
+    fn message_roundtrip() {
+        let messages = [
+            SnapMessage::GetAccountRange(GetAccountRange {
+                request_id: 1,
+                root_hash: H256::repeat_byte(1),
+                starting_hash: H256::zero(),
+                limit_hash: H256::repeat_byte(0xff),
+                response_bytes: 512 * 1024,
+            }),
+            SnapMessage::AccountRange(AccountRange {
+                request_id: 1,
+                accounts: vec![AccountData {
+                    hash: H256::repeat_byte(2),
+                    body: SnapAccount { nonce: 1, ..Default::default() },
+                }],
+                proof: vec![Bytes::from([0xc0])],
+            }),
+            SnapMessage::GetStorageRanges(GetStorageRanges {
+                request_id: 2,
+                root_hash: H256::repeat_byte(1),
+                account_hashes: vec![H256::repeat_byte(2)],
+                starting_hash: Bytes::default(),
+                limit_hash: Bytes::default(),
+                response_bytes: 1024,
+            }),
+            SnapMessage::StorageRanges(StorageRanges {
+                request_id: 2,
+                slots: vec![vec![StorageData {
+                    hash: H256::repeat_byte(3),
+                    data: Bytes::from([0x01]),
+                }]],
+                proof: vec![],
+            }),
+            SnapMessage::GetByteCodes(GetByteCodes {
+                request_id: 3,
+                hashes: vec![H256::repeat_byte(4)],
+                response_bytes: 1024,
+            }),
+            SnapMessage::ByteCodes(ByteCodes {
+                request_id: 3,
+                codes: vec![Bytes::from([0x60, 0x00])],
+            }),
+            SnapMessage::GetTrieNodes(GetTrieNodes {
+                request_id: 4,
+                root_hash: H256::repeat_byte(1),
+                paths: vec![vec![Bytes::from([0x00])]],
+                response_bytes: 1024,
+            }),
+            SnapMessage::TrieNodes(TrieNodes { request_id: 4, nodes: vec![Bytes::from([0xc0])] }),
+        ];
+
+        for (id, message) in messages.into_iter().enumerate() {
+            assert_eq!(message.message_id() as usize, id);
+            let encoded = encode(message.clone());
+            assert_eq!(message.length(), encoded.len());
+            assert_eq!(encoded[0], id as u8);
+            assert_eq!(SnapMessage::decode_message(&mut &encoded[..]).unwrap(), message);
+        }
+    }
+}
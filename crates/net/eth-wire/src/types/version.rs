@@ -27,14 +27,14 @@ impl EthVersion {
     /// The latest known eth version
     pub const LATEST: EthVersion = EthVersion::Eth68;
 
-    /// Returns the total number of messages the protocol version supports.
+    /// Returns the number of message ids the protocol version reserves.
+    ///
+    /// This is the highest message id plus one. eth/67 and eth/68 removed the `GetNodeData` and
+    /// `NodeData` messages, but their ids stay reserved, so all versions reserve 17 ids, like geth.
+    /// This matters for the offsets of capabilities that are ordered after `eth`.
     pub fn total_messages(&self) -> u8 {
         match self {
-            EthVersion::Eth66 => 15,
-            EthVersion::Eth67 | EthVersion::Eth68 => {
-                // eth/67,68 are eth/66 minus GetNodeData and NodeData messages
-                13
-            }
+            EthVersion::Eth66 | EthVersion::Eth67 | EthVersion::Eth68 => 17,
         }
     }
 }
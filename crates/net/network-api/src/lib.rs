@@ -19,10 +19,14 @@
 //!
 //! - `serde` (default): Enable serde support
 use async_trait::async_trait;
-use reth_eth_wire::{DisconnectReason, EthVersion, Status};
+use reth_eth_wire::{DisconnectReason, EthMessageID, EthVersion, Status};
 use reth_primitives::{NodeRecord, PeerId};
 use reth_rpc_types::NetworkStatus;
-use std::{net::SocketAddr, sync::Arc};
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
 
 pub use error::NetworkError;
 pub use reputation::{Reputation, ReputationChangeKind};
@@ -79,6 +83,11 @@ pub trait Peers: PeersInfo {
         self.add_peer_kind(peer, PeerKind::Trusted, addr);
     }
 
+    /// Adds a reserved peer to the peer set.
+    fn add_reserved_peer(&self, peer: PeerId, addr: SocketAddr) {
+        self.add_peer_kind(peer, PeerKind::Reserved, addr);
+    }
+
     /// Adds a peer to the known peer set, with the given kind.
     fn add_peer_kind(&self, peer: PeerId, kind: PeerKind, addr: SocketAddr);
 
@@ -99,6 +108,30 @@ pub trait Peers: PeersInfo {
 
     /// Get the reputation of a peer.
     async fn reputation_by_id(&self, peer_id: PeerId) -> Result<Option<Reputation>, NetworkError>;
+
+    /// Returns all active bans of peers and ip addresses.
+    async fn bans(&self) -> Result<Vec<Ban>, NetworkError>;
+
+    /// Lifts all bans of peers and ip addresses.
+    fn clear_bans(&self);
+}
+
+/// An active ban of a peer or an ip address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ban {
+    /// What is banned.
+    pub target: BanTarget,
+    /// Until when the ban is in effect, `None` if banned indefinitely.
+    pub until: Option<SystemTime>,
+}
+
+/// The target of a [`Ban`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BanTarget {
+    /// A banned peer.
+    Peer(PeerId),
+    /// A banned ip address.
+    Ip(IpAddr),
 }
 
 /// Represents the kind of peer
@@ -109,6 +142,11 @@ pub enum PeerKind {
     Basic,
     /// Trusted peer.
     Trusted,
+    /// Reserved peer.
+    ///
+    /// Reserved peers are trusted, but always keep a connection slot outside of the configured
+    /// inbound and outbound limits and are reconnected aggressively.
+    Reserved,
 }
 
 /// Info about an active peer session.
@@ -130,6 +168,31 @@ pub struct PeerInfo {
     pub eth_version: EthVersion,
     /// The Status message the peer sent for the `eth` handshake
     pub status: Status,
+    /// Traffic and request statistics of the session
+    pub stats: SessionStats,
+}
+
+/// Traffic and request statistics of an active session.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SessionStats {
+    /// Number of bytes received from the peer, including the handshake.
+    pub bytes_in: u64,
+    /// Number of bytes sent to the peer, including the handshake.
+    pub bytes_out: u64,
+    /// Number of `eth` messages received from the peer per message id, without the ids of which
+    /// no message was received.
+    pub messages_in: Vec<(EthMessageID, u64)>,
+    /// Number of `eth` messages sent to the peer per message id, without the ids of which no
+    /// message was sent.
+    pub messages_out: Vec<(EthMessageID, u64)>,
+    /// Number of responses the peer sent to our requests.
+    pub responses: u64,
+    /// Number of our requests the peer did not respond to in time.
+    pub timed_out_requests: u64,
+    /// The mean time the peer took to respond to a request.
+    pub mean_request_latency: Duration,
+    /// The longest time the peer took to respond to a request.
+    pub max_request_latency: Duration,
 }
 
 /// The direction of the connection.
@@ -4,7 +4,7 @@
 //! generic over it.
 
 use crate::{
-    NetworkError, NetworkInfo, PeerInfo, PeerKind, Peers, PeersInfo, Reputation,
+    Ban, NetworkError, NetworkInfo, PeerInfo, PeerKind, Peers, PeersInfo, Reputation,
     ReputationChangeKind,
 };
 use async_trait::async_trait;
@@ -82,4 +82,10 @@ impl Peers for NoopNetwork {
     async fn reputation_by_id(&self, _peer_id: PeerId) -> Result<Option<Reputation>, NetworkError> {
         Ok(None)
     }
+
+    async fn bans(&self) -> Result<Vec<Ban>, NetworkError> {
+        Ok(vec![])
+    }
+
+    fn clear_bans(&self) {}
 }
@@ -1,9 +1,13 @@
 //! Builder support for configuring the entire setup.
 
 use crate::{
-    eth_requests::EthRequestHandler, transactions::TransactionsManager, NetworkHandle,
-    NetworkManager,
+    eth_requests::EthRequestHandler,
+    protocol::ProtocolHandler,
+    snap_requests::{SnapProtocolHandler, SnapRequestHandler},
+    transactions::TransactionsManager,
+    NetworkHandle, NetworkManager, SnapFetchClient,
 };
+use reth_provider::ProviderFactory;
 use reth_transaction_pool::TransactionPool;
 use tokio::sync::mpsc;
 
@@ -11,6 +15,10 @@ use tokio::sync::mpsc;
 /// 256 requests with malicious 10MB body requests is 2.6GB which can be absorbed by the node.
 pub(crate) const ETH_REQUEST_CHANNEL_CAPACITY: usize = 256;
 
+/// We set the max channel capacity of the SnapRequestHandler to 128, since serving the proofs
+/// of a snap request is more expensive than serving an eth request.
+pub(crate) const SNAP_REQUEST_CHANNEL_CAPACITY: usize = 128;
+
 /// A builder that can configure all components of the network.
 pub struct NetworkBuilder<C, Tx, Eth> {
     pub(crate) network: NetworkManager<C>,
@@ -59,4 +67,29 @@ impl<C, Tx, Eth> NetworkBuilder<C, Tx, Eth> {
         let request_handler = EthRequestHandler::new(client, peers, rx);
         NetworkBuilder { network, request_handler, transactions }
     }
+
+    /// Creates a new [`SnapRequestHandler`] and a [`SnapFetchClient`] and wires them to the
+    /// network.
+    ///
+    /// This registers the `snap/1` sub-protocol, see [`SnapProtocolHandler`]. Serving `snap` is
+    /// optional, so the handler is returned instead of being kept in the builder.
+    pub fn snap<DB>(
+        &mut self,
+        factory: ProviderFactory<DB>,
+    ) -> (SnapRequestHandler<DB>, SnapFetchClient) {
+        let (tx, rx) = mpsc::channel(SNAP_REQUEST_CHANNEL_CAPACITY);
+        let handler = SnapProtocolHandler::new(tx);
+        let client = handler.client(self.network.peers_handle());
+        self.network.add_rlpx_sub_protocol(handler);
+        (SnapRequestHandler::new(factory, rx), client)
+    }
+
+    /// Registers a handler for a custom RLPx sub-protocol.
+    ///
+    /// The protocol is announced in the `Hello` message and the handler receives a
+    /// [`ProtocolConnection`](crate::protocol::ProtocolConnection) for every session with a peer
+    /// that shares it.
+    pub fn add_rlpx_sub_protocol(&mut self, handler: impl ProtocolHandler) {
+        self.network.add_rlpx_sub_protocol(handler)
+    }
 }
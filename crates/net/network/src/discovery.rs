@@ -6,6 +6,7 @@ use crate::{
 };
 use futures::StreamExt;
 use reth_discv4::{DiscoveryUpdate, Discv4, Discv4Config, EnrForkIdEntry};
+use reth_discv5::{Discv5, Discv5Config, Discv5NodeRecordUpdate};
 use reth_dns_discovery::{
     DnsDiscoveryConfig, DnsDiscoveryHandle, DnsDiscoveryService, DnsNodeRecordUpdate, DnsResolver,
 };
@@ -30,6 +31,8 @@ pub struct Discovery {
     ///
     /// These nodes can be ephemeral and are updated via the discovery protocol.
     discovered_nodes: HashMap<PeerId, SocketAddr>,
+    /// The last [`ForkId`] reported for each discovered node.
+    fork_ids: HashMap<PeerId, ForkId>,
     /// Local ENR of the discovery service.
     local_enr: NodeRecord,
     /// Handler to interact with the Discovery v4 service
@@ -38,6 +41,12 @@ pub struct Discovery {
     discv4_updates: Option<ReceiverStream<DiscoveryUpdate>>,
     /// The handle to the spawned discv4 service
     _discv4_service: Option<JoinHandle<()>>,
+    /// Handler to interact with the Discovery v5 service
+    discv5: Option<Discv5>,
+    /// All nodes discovered by the discv5 service.
+    discv5_updates: Option<ReceiverStream<Discv5NodeRecordUpdate>>,
+    /// The handle to the spawned discv5 service
+    _discv5_service: Option<JoinHandle<()>>,
     /// Handler to interact with the DNS discovery service
     _dns_discovery: Option<DnsDiscoveryHandle>,
     /// Updates from the DNS discovery service.
@@ -53,12 +62,14 @@ pub struct Discovery {
 impl Discovery {
     /// Spawns the discovery service.
     ///
-    /// This will spawn the [`reth_discv4::Discv4Service`] onto a new task and establish a listener
-    /// channel to receive all discovered nodes.
+    /// This will spawn the [`reth_discv4::Discv4Service`] and, if configured, the
+    /// [`reth_discv5::Discv5Service`] onto new tasks and establish listener channels to receive all
+    /// discovered nodes.
     pub async fn new(
         discovery_addr: SocketAddr,
         sk: SecretKey,
         discv4_config: Option<Discv4Config>,
+        discv5_config: Option<Discv5Config>,
         dns_discovery_config: Option<DnsDiscoveryConfig>,
     ) -> Result<Self, NetworkError> {
         // setup discv4
@@ -76,6 +87,24 @@ impl Discovery {
             (None, None, None)
         };
 
+        // setup discv5
+        let (discv5, discv5_updates, _discv5_service) = if let Some(disc_config) = discv5_config {
+            let discv5_addr = disc_config.listen_addr;
+            let (discv5, mut discv5_service) =
+                Discv5::bind(sk, disc_config).await.map_err(|err| match err {
+                    reth_discv5::Discv5Error::Io(err) => {
+                        NetworkError::from_io_error(err, ServiceKind::Discovery(discv5_addr))
+                    }
+                    err => NetworkError::Discv5(err),
+                })?;
+            let discv5_updates = discv5_service.update_stream();
+            // spawn the service
+            let _discv5_service = discv5_service.spawn();
+            (Some(discv5), Some(discv5_updates), Some(_discv5_service))
+        } else {
+            (None, None, None)
+        };
+
         // setup DNS discovery
         let (_dns_discovery, dns_discovery_updates, _dns_disc_service) =
             if let Some(dns_config) = dns_discovery_config {
@@ -96,7 +125,11 @@ impl Discovery {
             discv4,
             discv4_updates,
             _discv4_service,
+            discv5,
+            discv5_updates,
+            _discv5_service,
             discovered_nodes: Default::default(),
+            fork_ids: Default::default(),
             queued_events: Default::default(),
             _dns_disc_service,
             _dns_discovery,
@@ -115,27 +148,36 @@ impl Discovery {
         self.discovery_listeners.retain_mut(|listener| listener.send(event.clone()).is_ok());
     }
 
-    /// Updates the `eth:ForkId` field in discv4.
+    /// Updates the `eth:ForkId` field in discv4 and discv5.
     #[allow(unused)]
     pub(crate) fn update_fork_id(&self, fork_id: ForkId) {
         if let Some(discv4) = &self.discv4 {
             // use forward-compatible forkid entry
             discv4.set_eip868_rlp("eth".as_bytes().to_vec(), EnrForkIdEntry::from(fork_id))
         }
+        if let Some(discv5) = &self.discv5 {
+            discv5.set_fork_id(fork_id)
+        }
     }
 
-    /// Bans the [`IpAddr`] in the discovery service.
+    /// Bans the [`IpAddr`] in the discovery services.
     pub(crate) fn ban_ip(&self, ip: IpAddr) {
         if let Some(discv4) = &self.discv4 {
             discv4.ban_ip(ip)
         }
+        if let Some(discv5) = &self.discv5 {
+            discv5.ban_ip(ip)
+        }
     }
 
-    /// Bans the [`PeerId`] and [`IpAddr`] in the discovery service.
+    /// Bans the [`PeerId`] and [`IpAddr`] in the discovery services.
     pub(crate) fn ban(&self, peer_id: PeerId, ip: IpAddr) {
         if let Some(discv4) = &self.discv4 {
             discv4.ban(peer_id, ip)
         }
+        if let Some(discv5) = &self.discv5 {
+            discv5.ban(peer_id, ip)
+        }
     }
 
     /// Returns the id with which the local identifies itself in the network
@@ -151,11 +193,19 @@ impl Discovery {
     }
 
     /// Processes an incoming [NodeRecord] update from a discovery service
+    ///
+    /// Nodes are deduplicated across all discovery services, a [ForkId] reported for an already
+    /// discovered node is emitted as [DiscoveryEvent::EnrForkId] if it changed.
     fn on_node_record_update(&mut self, record: NodeRecord, fork_id: Option<ForkId>) {
         let id = record.id;
         let addr = record.tcp_addr();
+        let fork_id = fork_id.filter(|fork_id| self.on_fork_id(id, *fork_id));
         match self.discovered_nodes.entry(id) {
-            Entry::Occupied(_entry) => {}
+            Entry::Occupied(_entry) => {
+                if let Some(fork_id) = fork_id {
+                    self.queued_events.push_back(DiscoveryEvent::EnrForkId(id, fork_id))
+                }
+            }
             Entry::Vacant(entry) => {
                 entry.insert(addr);
                 self.queued_events.push_back(DiscoveryEvent::NewNode(
@@ -165,16 +215,25 @@ impl Discovery {
         }
     }
 
+    /// Records the [ForkId] reported for the node, returns `true` if it changed.
+    fn on_fork_id(&mut self, id: PeerId, fork_id: ForkId) -> bool {
+        self.fork_ids.insert(id, fork_id) != Some(fork_id)
+    }
+
     fn on_discv4_update(&mut self, update: DiscoveryUpdate) {
         match update {
             DiscoveryUpdate::Added(record) => {
                 self.on_node_record_update(record, None);
             }
             DiscoveryUpdate::EnrForkId(node, fork_id) => {
-                self.queued_events.push_back(DiscoveryEvent::EnrForkId(node.id, fork_id))
+                if self.on_fork_id(node.id, fork_id) {
+                    self.queued_events.push_back(DiscoveryEvent::EnrForkId(node.id, fork_id))
+                }
             }
+            DiscoveryUpdate::Enr(..) => {}
             DiscoveryUpdate::Removed(node) => {
                 self.discovered_nodes.remove(&node);
+                self.fork_ids.remove(&node);
             }
             DiscoveryUpdate::Batch(updates) => {
                 for update in updates {
@@ -202,6 +261,12 @@ impl Discovery {
                 self.on_discv4_update(update)
             }
 
+            while let Some(Poll::Ready(Some(update))) =
+                self.discv5_updates.as_mut().map(|updates| updates.poll_next_unpin(cx))
+            {
+                self.on_node_record_update(update.node_record, update.fork_id);
+            }
+
             while let Some(Poll::Ready(Some(update))) =
                 self.dns_discovery_updates.as_mut().map(|updates| updates.poll_next_unpin(cx))
             {
@@ -227,6 +292,7 @@ impl Discovery {
 
         Self {
             discovered_nodes: Default::default(),
+            fork_ids: Default::default(),
             local_enr: NodeRecord {
                 address: IpAddr::V4(std::net::Ipv4Addr::UNSPECIFIED),
                 tcp_port: 0,
@@ -237,6 +303,9 @@ impl Discovery {
             discv4_updates: Default::default(),
             queued_events: Default::default(),
             _discv4_service: Default::default(),
+            discv5: None,
+            discv5_updates: None,
+            _discv5_service: None,
             _dns_discovery: None,
             dns_discovery_updates: None,
             _dns_disc_service: None,
@@ -266,9 +335,51 @@ mod tests {
         let mut rng = thread_rng();
         let (secret_key, _) = SECP256K1.generate_keypair(&mut rng);
         let discovery_addr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0));
-        let _discovery =
-            Discovery::new(discovery_addr, secret_key, Default::default(), Default::default())
-                .await
-                .unwrap();
+        let _discovery = Discovery::new(
+            discovery_addr,
+            secret_key,
+            Default::default(),
+            Default::default(),
+            Default::default(),
+        )
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_discv5_dedup() {
+        let mut rng = thread_rng();
+        let (secret_key, _) = SECP256K1.generate_keypair(&mut rng);
+        let mut discovery = Discovery::noop();
+        let record = NodeRecord::from_secret_key("127.0.0.1:30303".parse().unwrap(), &secret_key);
+        let fork_id = ForkId { hash: reth_primitives::ForkHash([0xde, 0xad, 0xbe, 0xef]), next: 0 };
+
+        // discovered via discv4
+        discovery.on_node_record_update(record, None);
+        // discovered again via discv5, with the `eth` entry
+        discovery.on_node_record_update(record, Some(fork_id));
+        // the unchanged fork id is not reported again
+        discovery.on_node_record_update(record, Some(fork_id));
+        discovery.on_discv4_update(DiscoveryUpdate::EnrForkId(record, fork_id));
+
+        assert!(matches!(
+            discovery.queued_events.pop_front(),
+            Some(DiscoveryEvent::NewNode(DiscoveredEvent::EventQueued { peer_id, fork_id: None, .. }))
+                if peer_id == record.id
+        ));
+        assert!(matches!(
+            discovery.queued_events.pop_front(),
+            Some(DiscoveryEvent::EnrForkId(peer_id, id)) if peer_id == record.id && id == fork_id
+        ));
+        assert!(discovery.queued_events.is_empty());
+
+        // a changed fork id is reported
+        let next_fork_id = ForkId { next: 1, ..fork_id };
+        discovery.on_node_record_update(record, Some(next_fork_id));
+        assert!(matches!(
+            discovery.queued_events.pop_front(),
+            Some(DiscoveryEvent::EnrForkId(peer_id, id)) if peer_id == record.id && id == next_fork_id
+        ));
+        assert!(discovery.queued_events.is_empty());
     }
 }
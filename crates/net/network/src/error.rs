@@ -43,6 +43,9 @@ pub enum NetworkError {
     /// IO error when creating the discovery service
     #[error("Failed to launch discovery service: {0}")]
     Discovery(io::Error),
+    /// Error when launching the discv5 service failed for a reason other than IO
+    #[error("Failed to launch discv5 service: {0}")]
+    Discv5(reth_discv5::Discv5Error),
     /// Error when setting up the DNS resolver failed
     ///
     /// See also [DnsResolver](reth_dns_discovery::DnsResolver::from_system_conf)
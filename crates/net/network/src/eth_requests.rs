@@ -6,7 +6,7 @@ use reth_eth_wire::{
     BlockBodies, BlockHeaders, GetBlockBodies, GetBlockHeaders, GetNodeData, GetReceipts, NodeData,
     Receipts,
 };
-use reth_interfaces::p2p::error::RequestResult;
+use reth_interfaces::{p2p::error::RequestResult, provider::ProviderError, Error};
 use reth_primitives::{BlockBody, BlockHashOrNumber, Header, HeadersDirection, PeerId};
 use reth_provider::{BlockReader, HeaderProvider, ReceiptProvider};
 use std::{
@@ -18,6 +18,7 @@ use std::{
 };
 use tokio::sync::{mpsc::Receiver, oneshot};
 use tokio_stream::wrappers::ReceiverStream;
+use tracing::warn;
 
 // Limits: <https://github.com/ethereum/go-ethereum/blob/b0d44338bbcefee044f1f635a84487cbbd8f0538/eth/protocols/eth/handler.go#L34-L56>
 
@@ -45,7 +46,7 @@ const APPROX_RECEIPT_SIZE: usize = 24 * 1024;
 const APPROX_BODY_SIZE: usize = 24 * 1024;
 
 /// Maximum size of replies to data retrievals.
-const SOFT_RESPONSE_LIMIT: usize = 2 * 1024 * 1024;
+pub(crate) const SOFT_RESPONSE_LIMIT: usize = 2 * 1024 * 1024;
 
 /// Estimated size in bytes of an RLP encoded header.
 const APPROX_HEADER_SIZE: usize = 500;
@@ -168,7 +169,16 @@ where
         let mut total_bytes = APPROX_BODY_SIZE;
 
         for hash in request.0 {
-            if let Some(block) = self.client.block_by_hash(hash).unwrap_or_default() {
+            let block = match self.client.block_by_hash(hash) {
+                Ok(block) => block,
+                // requests behind the history expiry horizon are served the bodies up to there
+                Err(Error::Provider(ProviderError::BlockBodyPruned(_))) => None,
+                Err(err) => {
+                    warn!(target: "net", ?err, ?hash, "Failed to read requested block body");
+                    None
+                }
+            };
+            if let Some(block) = block {
                 let body = BlockBody {
                     transactions: block.body,
                     ommers: block.ommers,
@@ -39,8 +39,15 @@
 //!
 //!        * Responds to incoming ETH related requests: `Headers`, `Bodies`
 //!
+//!    - `SNAP request Task`: is an optional spawned
+//!      [`SnapRequestHandler`](crate::snap_requests::SnapRequestHandler) future that:
+//!
+//!        * Responds to incoming SNAP requests from the hashed state of the latest block, which are
+//!          delegated by the sessions that share the `snap/1` sub-protocol
+//!
 //!    - `Discovery Task`: is a spawned [`Discv4`](reth_discv4::Discv4) future that handles peer
-//!      discovery and emits new peers to the `Network`
+//!      discovery and emits new peers to the `Network`. If enabled, a
+//!      [`Discv5`](reth_discv5::Discv5) future runs alongside it.
 //!
 //!    - [`NetworkManager`] task advances the state of the `Network`, which includes:
 //!
@@ -138,7 +145,10 @@ mod message;
 mod metrics;
 mod network;
 pub mod peers;
+pub mod protocol;
 mod session;
+mod snap_client;
+pub mod snap_requests;
 mod state;
 mod swarm;
 pub mod transactions;
@@ -156,5 +166,6 @@ pub use session::{
     PendingSessionHandle, PendingSessionHandshakeError, SessionCommand, SessionEvent, SessionId,
     SessionLimits, SessionManager, SessionsConfig,
 };
+pub use snap_client::{SnapFetchClient, SNAP_REQUEST_TIMEOUT};
 
 pub use reth_eth_wire::{DisconnectReason, HelloBuilder, HelloMessage};
@@ -25,7 +25,8 @@ use crate::{
     message::{NewBlockMessage, PeerMessage, PeerRequest, PeerRequestSender},
     metrics::{DisconnectMetrics, NetworkMetrics, NETWORK_POOL_TRANSACTIONS_SCOPE},
     network::{NetworkHandle, NetworkHandleMessage},
-    peers::{PeersHandle, PeersManager},
+    peers::{PeersHandle, PeersManager, PersistedPeersState},
+    protocol::ProtocolHandler,
     session::SessionManager,
     state::NetworkState,
     swarm::{NetworkConnectionState, Swarm, SwarmEvent},
@@ -140,6 +141,13 @@ impl<C> NetworkManager<C> {
         self.to_eth_request_handler = Some(tx);
     }
 
+    /// Registers a handler for a custom RLPx sub-protocol.
+    ///
+    /// The protocol is negotiated with all peers that connect after this call.
+    pub fn add_rlpx_sub_protocol(&mut self, handler: impl ProtocolHandler) {
+        self.swarm.sessions_mut().add_rlpx_sub_protocol(handler)
+    }
+
     /// Returns the [`NetworkHandle`] that can be cloned and shared.
     ///
     /// The [`NetworkHandle`] can be used to interact with this [`NetworkManager`]
@@ -167,6 +175,7 @@ where
             client,
             secret_key,
             mut discovery_v4_config,
+            mut discovery_v5_config,
             discovery_addr,
             listener_addr,
             peers_config,
@@ -180,6 +189,7 @@ where
             status,
             fork_filter,
             dns_discovery_config,
+            extra_protocols,
             ..
         } = config;
 
@@ -198,9 +208,26 @@ where
             disc_config
         });
 
-        let discovery =
-            Discovery::new(discovery_addr, secret_key, discovery_v4_config, dns_discovery_config)
-                .await?;
+        discovery_v5_config = discovery_v5_config.map(|mut disc_config| {
+            // advertise the RLPx listener and the fork id in the ENR
+            disc_config.tcp_port = listener_address.lock().port();
+            disc_config.fork_id = Some(status.forkid);
+            // advertise the same external IP as discv4
+            if disc_config.external_ip_resolver.is_none() {
+                disc_config.external_ip_resolver =
+                    discovery_v4_config.as_ref().and_then(|config| config.external_ip_resolver);
+            }
+            disc_config
+        });
+
+        let discovery = Discovery::new(
+            discovery_addr,
+            secret_key,
+            discovery_v4_config,
+            discovery_v5_config,
+            dns_discovery_config,
+        )
+        .await?;
         // need to retrieve the addr here since provided port could be `0`
         let local_peer_id = discovery.local_id();
 
@@ -215,6 +242,7 @@ where
             hello_message,
             fork_filter,
             bandwidth_meter.clone(),
+            extra_protocols,
         );
 
         let state = NetworkState::new(
@@ -319,6 +347,13 @@ where
         self.swarm.state().peers().iter_peers()
     }
 
+    /// Returns the reputations, backoffs and bans of the peer set that should survive a restart.
+    ///
+    /// See also [`PeersConfig::with_persisted_state`](crate::PeersConfig::with_persisted_state).
+    pub fn persisted_peers_state(&self) -> PersistedPeersState {
+        self.swarm.state().peers().persisted_state()
+    }
+
     /// Returns a new [`PeersHandle`] that can be cloned and shared.
     ///
     /// The [`PeersHandle`] can be used to interact with the network's peer set.
@@ -351,6 +386,15 @@ where
         }
     }
 
+    /// Updates the metrics that show how connections are distributed across subnets.
+    fn update_subnet_metrics(&self) {
+        let distribution = self.swarm.state().peers().subnet_distribution();
+        self.metrics.incoming_subnets.set(distribution.inbound_subnets as f64);
+        self.metrics.outgoing_subnets.set(distribution.outbound_subnets as f64);
+        self.metrics.max_incoming_per_subnet.set(distribution.max_inbound_per_subnet as f64);
+        self.metrics.max_outgoing_per_subnet.set(distribution.max_outbound_per_subnet as f64);
+    }
+
     /// Event hook for an unexpected message from the peer.
     fn on_invalid_message(
         &mut self,
@@ -693,6 +737,7 @@ where
                                     .peers_mut()
                                     .on_incoming_session_established(peer_id, remote_addr);
                             }
+                            this.update_subnet_metrics();
                             this.event_listeners.notify(NetworkEvent::SessionEstablished {
                                 peer_id,
                                 remote_addr,
@@ -758,6 +803,7 @@ where
                             if let Some(reason) = reason {
                                 this.disconnect_metrics.increment(reason);
                             }
+                            this.update_subnet_metrics();
                             this.metrics.backed_off_peers.set(
                                 this.swarm.state().peers().num_backed_off_peers().saturating_sub(1)
                                     as f64,
@@ -827,6 +873,7 @@ where
                                     .on_pending_session_gracefully_closed(&peer_id);
                             }
                             this.metrics.closed_sessions.increment(1);
+                            this.update_subnet_metrics();
                             this.metrics
                                 .outgoing_connections
                                 .set(this.swarm.state().peers().num_outbound_connections() as f64);
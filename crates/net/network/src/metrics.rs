@@ -1,6 +1,6 @@
 use reth_eth_wire::DisconnectReason;
 use reth_metrics::{
-    metrics::{Counter, Gauge},
+    metrics::{Counter, Gauge, Histogram},
     Metrics,
 };
 
@@ -32,6 +32,18 @@ pub struct NetworkMetrics {
     /// Number of active outgoing connections
     pub(crate) outgoing_connections: Gauge,
 
+    /// Number of distinct subnets of active incoming connections
+    pub(crate) incoming_subnets: Gauge,
+
+    /// Number of distinct subnets of active outgoing connections
+    pub(crate) outgoing_subnets: Gauge,
+
+    /// Highest number of active incoming connections with a single subnet
+    pub(crate) max_incoming_per_subnet: Gauge,
+
+    /// Highest number of active outgoing connections with a single subnet
+    pub(crate) max_outgoing_per_subnet: Gauge,
+
     /// Total Number of incoming connections handled
     pub(crate) total_incoming_connections: Counter,
 
@@ -71,6 +83,27 @@ pub struct TransactionsManagerMetrics {
     pub(crate) inflight_transaction_requests: Gauge,
     /// How often we failed to send a request to the peer because the channel was full.
     pub(crate) egress_peer_channel_full: Counter,
+    /// Total number of announced transactions that were not fetched because too many announced
+    /// transactions were already tracked
+    pub(crate) ignored_announced_transactions: Counter,
+}
+
+/// Metrics for the sessions with the peers of a single client, labelled by the client name
+#[derive(Metrics)]
+#[metrics(scope = "network.sessions")]
+pub struct ClientSessionMetrics {
+    /// Number of bytes received from peers running the client
+    pub(crate) bytes_in: Counter,
+    /// Number of bytes sent to peers running the client
+    pub(crate) bytes_out: Counter,
+    /// Number of eth messages received from peers running the client
+    pub(crate) messages_in: Counter,
+    /// Number of eth messages sent to peers running the client
+    pub(crate) messages_out: Counter,
+    /// Number of requests to peers running the client that timed out
+    pub(crate) timed_out_requests: Counter,
+    /// Time in seconds peers running the client took to respond to requests
+    pub(crate) request_latency: Histogram,
 }
 
 /// Metrics for Disconnection types
@@ -152,3 +185,20 @@ pub struct EthRequestHandlerMetrics {
     /// Number of received bodies requests
     pub(crate) received_bodies_requests: Counter,
 }
+
+/// Metrics for the SnapRequestHandler
+#[derive(Metrics)]
+#[metrics(scope = "network")]
+pub struct SnapRequestHandlerMetrics {
+    /// Number of received account range requests
+    pub(crate) received_account_range_requests: Counter,
+
+    /// Number of received storage ranges requests
+    pub(crate) received_storage_ranges_requests: Counter,
+
+    /// Number of received bytecodes requests
+    pub(crate) received_byte_codes_requests: Counter,
+
+    /// Number of received trie nodes requests
+    pub(crate) received_trie_nodes_requests: Counter,
+}
@@ -8,7 +8,7 @@ use reth_eth_wire::{DisconnectReason, NewBlock, NewPooledTransactionHashes, Shar
 use reth_interfaces::sync::{NetworkSyncUpdater, SyncState, SyncStateProvider};
 use reth_net_common::bandwidth_meter::BandwidthMeter;
 use reth_network_api::{
-    NetworkError, NetworkInfo, PeerInfo, PeerKind, Peers, PeersInfo, Reputation,
+    Ban, NetworkError, NetworkInfo, PeerInfo, PeerKind, Peers, PeersInfo, Reputation,
     ReputationChangeKind,
 };
 use reth_primitives::{Head, NodeRecord, PeerId, TransactionSigned, H256};
@@ -239,6 +239,15 @@ impl Peers for NetworkHandle {
         let _ = self.manager().send(NetworkHandleMessage::GetReputationById(peer_id, tx));
         Ok(rx.await?)
     }
+
+    async fn bans(&self) -> Result<Vec<Ban>, NetworkError> {
+        Ok(self.inner.peers.bans().await)
+    }
+
+    /// Sends a command to the peer set to lift all bans.
+    fn clear_bans(&self) {
+        self.inner.peers.clear_bans()
+    }
 }
 
 #[async_trait]
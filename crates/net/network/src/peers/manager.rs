@@ -1,7 +1,15 @@
 use crate::{
     error::{BackoffKind, SessionError},
     peers::{
+        persisted::{
+            instant_to_system_time, instant_to_unix_secs, unix_secs_to_instant, PersistedIpBan,
+            PersistedPeer, PersistedPeerBan, PersistedPeersState,
+        },
         reputation::{is_banned_reputation, DEFAULT_REPUTATION},
+        subnet::{
+            IpConnectionCounts, SubnetDistribution, DEFAULT_MAX_PEERS_PER_IP,
+            DEFAULT_MAX_PEERS_PER_SUBNET,
+        },
         ReputationChangeWeights, DEFAULT_MAX_CONCURRENT_DIALS, DEFAULT_MAX_PEERS_INBOUND,
         DEFAULT_MAX_PEERS_OUTBOUND,
     },
@@ -10,9 +18,10 @@ use crate::{
 use futures::StreamExt;
 use reth_eth_wire::{errors::EthStreamError, DisconnectReason};
 use reth_net_common::ban_list::BanList;
-use reth_network_api::{PeerKind, ReputationChangeKind};
+use reth_network_api::{Ban, BanTarget, PeerKind, ReputationChangeKind};
 use reth_primitives::{ForkId, NodeRecord, PeerId};
 use std::{
+    cmp::Reverse,
     collections::{hash_map::Entry, HashMap, HashSet, VecDeque},
     fmt::Display,
     io::{self, ErrorKind},
@@ -73,6 +82,19 @@ impl PeersHandle {
 
         rx.await.unwrap_or_default()
     }
+
+    /// Returns all active bans of peers and ip addresses.
+    pub async fn bans(&self) -> Vec<Ban> {
+        let (tx, rx) = oneshot::channel();
+        self.send(PeerCommand::GetBans(tx));
+
+        rx.await.unwrap_or_default()
+    }
+
+    /// Lifts all bans of peers and ip addresses.
+    pub fn clear_bans(&self) {
+        self.send(PeerCommand::ClearBans);
+    }
 }
 
 /// Maintains the state of _all_ the peers known to the network.
@@ -109,6 +131,9 @@ pub(crate) struct PeersManager {
     backoff_durations: PeerBackoffDurations,
     /// If non-trusted peers should be connected to
     connect_trusted_nodes_only: bool,
+    /// How long to wait before reconnecting to a reserved peer after its connection was closed or
+    /// failed.
+    reserved_reconnect_interval: Duration,
     /// Timestamp of the last time [Self::tick] was called.
     last_tick: Instant,
     /// Maximum number of backoff attempts before we give up on a peer and dropping.
@@ -126,17 +151,28 @@ impl PeersManager {
             ban_duration,
             backoff_durations,
             trusted_nodes,
+            reserved_nodes,
+            reserved_reconnect_interval,
             connect_trusted_nodes_only,
             basic_nodes,
             max_backoff_count,
+            persisted_state,
         } = config;
         let (manager_tx, handle_rx) = mpsc::unbounded_channel();
         let now = Instant::now();
 
         // We use half of the interval to decrease the max duration to `150%` in worst case
-        let unban_interval = ban_duration.min(backoff_durations.low) / 2;
+        let unban_interval =
+            ban_duration.min(backoff_durations.low).min(reserved_reconnect_interval) / 2;
 
-        let mut peers = HashMap::with_capacity(trusted_nodes.len() + basic_nodes.len());
+        let mut peers =
+            HashMap::with_capacity(reserved_nodes.len() + trusted_nodes.len() + basic_nodes.len());
+
+        for NodeRecord { address, tcp_port, udp_port: _, id } in reserved_nodes {
+            peers
+                .entry(id)
+                .or_insert_with(|| Peer::reserved(SocketAddr::from((address, tcp_port))));
+        }
 
         for NodeRecord { address, tcp_port, udp_port: _, id } in trusted_nodes {
             peers.entry(id).or_insert_with(|| Peer::trusted(SocketAddr::from((address, tcp_port))));
@@ -146,7 +182,7 @@ impl PeersManager {
             peers.entry(id).or_insert_with(|| Peer::new(SocketAddr::from((address, tcp_port))));
         }
 
-        Self {
+        let mut manager = Self {
             peers,
             manager_tx,
             handle_rx: UnboundedReceiverStream::new(handle_rx),
@@ -163,8 +199,120 @@ impl PeersManager {
             ban_duration,
             backoff_durations,
             connect_trusted_nodes_only,
+            reserved_reconnect_interval,
             last_tick: Instant::now(),
             max_backoff_count,
+        };
+
+        if let Some(state) = persisted_state {
+            manager.restore_state(state);
+        }
+
+        manager
+    }
+
+    /// Restores reputations, backoffs and bans from a previous run.
+    ///
+    /// Expired backoffs and bans are skipped. Banned peers that are not trusted are removed from
+    /// the set, as they would have been rejected when discovered.
+    fn restore_state(&mut self, state: PersistedPeersState) {
+        let PersistedPeersState { peers, banned_peers, banned_ips } = state;
+
+        for PersistedPeerBan { id, until } in banned_peers {
+            match until {
+                Some(until) => {
+                    if let Some(until) = unix_secs_to_instant(until) {
+                        self.ban_list.ban_peer_until(id, until);
+                    }
+                }
+                None => self.ban_list.ban_peer(id),
+            }
+        }
+
+        for PersistedIpBan { ip, until } in banned_ips {
+            match until {
+                Some(until) => {
+                    if let Some(until) = unix_secs_to_instant(until) {
+                        self.ban_list.ban_ip_until(ip, until);
+                    }
+                }
+                None => self.ban_list.ban_ip(ip),
+            }
+        }
+
+        let ban_list = &self.ban_list;
+        self.peers.retain(|peer_id, peer| {
+            peer.is_trusted() || !ban_list.is_banned(peer_id, &peer.addr.ip())
+        });
+
+        for PersistedPeer { id, reputation, severe_backoff_counter, backoff_until } in peers {
+            let Some(peer) = self.peers.get_mut(&id) else { continue };
+            peer.reputation = reputation;
+            peer.severe_backoff_counter = severe_backoff_counter;
+            if peer.is_banned() && !self.ban_list.is_banned_peer(&id) {
+                // the ban expired in the meantime
+                peer.unban();
+            }
+            if let Some(until) = backoff_until.and_then(unix_secs_to_instant) {
+                self.backoff_peer_until(id, until);
+            }
+        }
+    }
+
+    /// Returns the reputations, backoffs and bans that should survive a restart.
+    pub(crate) fn persisted_state(&self) -> PersistedPeersState {
+        let peers = self
+            .peers
+            .iter()
+            .filter_map(|(peer_id, peer)| {
+                let backoff_until = self.backed_off_peers.get(peer_id).copied();
+                if peer.reputation == DEFAULT_REPUTATION &&
+                    peer.severe_backoff_counter == 0 &&
+                    backoff_until.is_none()
+                {
+                    return None
+                }
+                Some(PersistedPeer {
+                    id: *peer_id,
+                    reputation: peer.reputation,
+                    severe_backoff_counter: peer.severe_backoff_counter,
+                    backoff_until: backoff_until.map(instant_to_unix_secs),
+                })
+            })
+            .collect();
+        let banned_peers = self
+            .ban_list
+            .banned_peers()
+            .map(|(id, until)| PersistedPeerBan { id, until: until.map(instant_to_unix_secs) })
+            .collect();
+        let banned_ips = self
+            .ban_list
+            .banned_ips()
+            .map(|(ip, until)| PersistedIpBan { ip, until: until.map(instant_to_unix_secs) })
+            .collect();
+
+        PersistedPeersState { peers, banned_peers, banned_ips }
+    }
+
+    /// Returns all active bans of peers and ip addresses.
+    pub(crate) fn bans(&self) -> Vec<Ban> {
+        let peers =
+            self.ban_list.banned_peers().map(|(peer_id, until)| (BanTarget::Peer(peer_id), until));
+        let ips = self.ban_list.banned_ips().map(|(ip, until)| (BanTarget::Ip(ip), until));
+        peers
+            .chain(ips)
+            .map(|(target, until)| Ban { target, until: until.map(instant_to_system_time) })
+            .collect()
+    }
+
+    /// Lifts all bans and resets the reputation of the unbanned peers.
+    pub(crate) fn clear_bans(&mut self) {
+        let (_, unbanned_peers) = self.ban_list.clear();
+        for peer_id in unbanned_peers {
+            if let Some(peer) = self.peers.get_mut(&peer_id) {
+                peer.unban();
+            }
+            self.queued_actions.push_back(PeerAction::UnBanPeer { peer_id });
         }
     }
 
@@ -205,7 +353,13 @@ impl PeersManager {
     /// Invoked when a new _incoming_ tcp connection is accepted.
     ///
     /// returns an error if the inbound ip address is on the ban list or
-    /// we have reached our limit for max inbound connections
+    /// we have reached our limit for max inbound connections, either in total or with the ip
+    /// address or its subnet
+    ///
+    /// Connections from the ip address of a reserved peer are accepted regardless of the limits,
+    /// so that a reserved peer can always connect. The session is disconnected once established if
+    /// it turns out to belong to a different peer that exceeds the limits, see
+    /// [Self::on_incoming_session_established].
     pub(crate) fn on_incoming_pending_session(
         &mut self,
         addr: IpAddr,
@@ -213,8 +367,13 @@ impl PeersManager {
         if self.ban_list.is_banned_ip(&addr) {
             return Err(InboundConnectionError::IpBanned)
         }
-        if !self.connection_info.has_in_capacity() {
-            return Err(InboundConnectionError::ExceedsLimit(self.connection_info.max_inbound))
+        if !self.is_reserved_ip(&addr) {
+            if !self.connection_info.has_in_capacity() {
+                return Err(InboundConnectionError::ExceedsLimit(self.connection_info.max_inbound))
+            }
+            if self.connection_info.exceeds_inbound_ip_limits(&addr) {
+                return Err(InboundConnectionError::ExceedsIpLimit)
+            }
         }
         // keep track of new connection
         self.connection_info.inc_in();
@@ -256,6 +415,10 @@ impl PeersManager {
     ///
     /// If the reputation of the peer is below the `BANNED_REPUTATION` threshold, a disconnect will
     /// be scheduled.
+    ///
+    /// If the peer is reserved, the session is moved from the regular inbound slots to the reserved
+    /// slots. Otherwise the session is disconnected if it exceeds the inbound limits, which is
+    /// possible if it was accepted as a connection from the ip address of a reserved peer.
     pub(crate) fn on_incoming_session_established(&mut self, peer_id: PeerId, addr: SocketAddr) {
         // we only need to check the peer id here as the ip address will have been checked at
         // on_inbound_pending_session. We also check if the peer is in the backoff list here.
@@ -267,23 +430,67 @@ impl PeersManager {
         // start a new tick, so the peer is not immediately rewarded for the time since last tick
         self.tick();
 
-        match self.peers.entry(peer_id) {
-            Entry::Occupied(mut entry) => {
-                let value = entry.get_mut();
+        // multiple pending sessions from the same subnet can be accepted concurrently, so the
+        // limits are checked again, before the new session is counted
+        let (is_trusted, is_reserved) = self
+            .peers
+            .get(&peer_id)
+            .map_or((false, false), |peer| (peer.is_trusted(), peer.is_reserved()));
+        let exceeds_ip_limits =
+            !is_trusted && self.connection_info.exceeds_inbound_ip_limits(&addr.ip());
+        // the session already occupies an inbound slot
+        let exceeds_in_capacity =
+            !is_reserved && self.connection_info.num_inbound > self.connection_info.max_inbound;
+
+        let peer = match self.peers.entry(peer_id) {
+            Entry::Occupied(entry) => {
+                let value = entry.into_mut();
                 if value.is_banned() {
                     self.queued_actions.push_back(PeerAction::DisconnectBannedIncoming { peer_id });
                     return
                 }
+                self.connection_info.move_ip(value.state, PeerConnectionState::In, value.addr.ip());
                 value.state = PeerConnectionState::In;
+                if value.is_reserved() {
+                    self.connection_info.move_state(value.state, true);
+                }
+                value
             }
             Entry::Vacant(entry) => {
                 // peer is missing in the table, we add it but mark it as to be removed after
                 // disconnect, because we only know the outgoing port
                 let mut peer = Peer::with_state(addr, PeerConnectionState::In);
                 peer.remove_after_disconnect = true;
-                entry.insert(peer);
+                self.connection_info.inc_ip(peer.state, addr.ip());
                 self.queued_actions.push_back(PeerAction::PeerAdded(peer_id));
+                entry.insert(peer)
             }
+        };
+
+        if exceeds_ip_limits || exceeds_in_capacity {
+            trace!(target: "net::peers", ?peer_id, ?addr, exceeds_ip_limits, exceeds_in_capacity, "too many incoming connections");
+            peer.state.disconnect();
+            self.queued_actions.push_back(PeerAction::Disconnect {
+                peer_id,
+                reason: Some(DisconnectReason::TooManyPeers),
+            });
+        }
+    }
+
+    /// Returns `true` if the ip address belongs to a reserved peer.
+    fn is_reserved_ip(&self, ip: &IpAddr) -> bool {
+        self.peers.values().any(|peer| peer.is_reserved() && peer.addr.ip() == *ip)
+    }
+
+    /// Returns how the active connections are distributed across subnets.
+    pub(crate) fn subnet_distribution(&self) -> SubnetDistribution {
+        let inbound = &self.connection_info.inbound_ips;
+        let outbound = &self.connection_info.outbound_ips;
+        SubnetDistribution {
+            inbound_subnets: inbound.num_subnets(),
+            outbound_subnets: outbound.num_subnets(),
+            max_inbound_per_subnet: inbound.max_per_subnet(),
+            max_outbound_per_subnet: outbound.max_per_subnet(),
         }
     }
 
@@ -308,6 +515,12 @@ impl PeersManager {
         }
     }
 
+    /// Puts the reserved peer in timeout until it should be reconnected.
+    fn backoff_reserved_peer(&mut self, peer_id: PeerId) {
+        let until = std::time::Instant::now() + self.reserved_reconnect_interval;
+        self.backoff_peer_until(peer_id, until);
+    }
+
     /// Unbans the peer
     fn unban_peer(&mut self, peer_id: PeerId) {
         self.ban_list.unban_peer(&peer_id);
@@ -343,8 +556,13 @@ impl PeersManager {
     }
 
     /// Apply the corresponding reputation change to the given peer
+    ///
+    /// Reserved peers are never banned or disconnected, so their reputation is not changed.
     pub(crate) fn apply_reputation_change(&mut self, peer_id: &PeerId, rep: ReputationChangeKind) {
         let outcome = if let Some(peer) = self.peers.get_mut(peer_id) {
+            if peer.is_reserved() {
+                return
+            }
             // First check if we should reset the reputation
             if rep.is_reset() {
                 peer.reset_reputation()
@@ -374,13 +592,18 @@ impl PeersManager {
 
     /// Gracefully disconnected a pending _outgoing_ session
     pub(crate) fn on_pending_session_gracefully_closed(&mut self, peer_id: &PeerId) {
-        if let Some(peer) = self.peers.get_mut(peer_id) {
+        let is_reserved = if let Some(peer) = self.peers.get_mut(peer_id) {
+            self.connection_info.decr_ip(peer.state, peer.addr.ip());
             peer.state = PeerConnectionState::Idle;
+            peer.is_reserved()
         } else {
             return
-        }
+        };
 
-        self.connection_info.decr_out();
+        self.connection_info.decr_state(PeerConnectionState::Out, is_reserved);
+        if is_reserved {
+            self.backoff_reserved_peer(*peer_id);
+        }
     }
 
     /// Invoked when an _outgoing_ pending session was closed during authentication or the
@@ -398,9 +621,15 @@ impl PeersManager {
     pub(crate) fn on_active_session_gracefully_closed(&mut self, peer_id: PeerId) {
         match self.peers.entry(peer_id) {
             Entry::Occupied(mut entry) => {
-                self.connection_info.decr_state(entry.get().state);
+                self.connection_info.decr_state(entry.get().state, entry.get().is_reserved());
+                self.connection_info.decr_ip(entry.get().state, entry.get().addr.ip());
 
-                if entry.get().remove_after_disconnect && !entry.get().is_trusted() {
+                if entry.get().is_reserved() {
+                    entry.get_mut().severe_backoff_counter = 0;
+                    entry.get_mut().state = PeerConnectionState::Idle;
+                    self.backoff_reserved_peer(peer_id);
+                    return
+                } else if entry.get().remove_after_disconnect && !entry.get().is_trusted() {
                     // this peer should be removed from the set
                     entry.remove();
                     self.queued_actions.push_back(PeerAction::PeerRemoved(peer_id));
@@ -452,12 +681,20 @@ impl PeersManager {
     ) {
         trace!(target: "net::peers", ?remote_addr, ?peer_id, ?err, "handling failed connection");
 
-        if err.is_fatal_protocol_error() {
+        if let Some(peer) = self.peers.get_mut(peer_id).filter(|peer| peer.is_reserved()) {
+            // reserved peers are never removed or banned, instead they're reconnected after the
+            // configured interval
+            self.connection_info.decr_state(peer.state, true);
+            self.connection_info.decr_ip(peer.state, peer.addr.ip());
+            peer.state = PeerConnectionState::Idle;
+            self.backoff_reserved_peer(*peer_id);
+        } else if err.is_fatal_protocol_error() {
             trace!(target: "net::peers", ?remote_addr, ?peer_id, ?err, "fatal connection error");
             // remove the peer to which we can't establish a connection due to protocol related
             // issues.
             if let Some((peer_id, peer)) = self.peers.remove_entry(peer_id) {
-                self.connection_info.decr_state(peer.state);
+                self.connection_info.decr_state(peer.state, false);
+                self.connection_info.decr_ip(peer.state, peer.addr.ip());
                 self.queued_actions.push_back(PeerAction::PeerRemoved(peer_id));
             }
 
@@ -495,7 +732,8 @@ impl PeersManager {
                     peer.reputation = peer.reputation.saturating_add(reputation_change.as_i32());
                 };
 
-                self.connection_info.decr_state(peer.state);
+                self.connection_info.decr_state(peer.state, false);
+                self.connection_info.decr_ip(peer.state, peer.addr.ip());
                 peer.state = PeerConnectionState::Idle;
 
                 if peer.severe_backoff_counter > self.max_backoff_count && !peer.is_trusted() {
@@ -526,9 +764,10 @@ impl PeersManager {
     pub(crate) fn on_already_connected(&mut self, direction: Direction) {
         match direction {
             Direction::Incoming => {}
-            Direction::Outgoing(_) => {
+            Direction::Outgoing(peer_id) => {
                 // need to decrement the outgoing counter
-                self.connection_info.decr_out();
+                let is_reserved = self.peers.get(&peer_id).map_or(false, |peer| peer.is_reserved());
+                self.connection_info.decr_state(PeerConnectionState::Out, is_reserved);
             }
         }
     }
@@ -559,9 +798,19 @@ impl PeersManager {
         self.add_peer_kind(peer_id, PeerKind::Trusted, addr, None)
     }
 
+    /// Called for a newly discovered reserved peer.
+    ///
+    /// If the peer already exists, then the address and kind will be updated.
+    #[cfg(test)]
+    pub(crate) fn add_reserved_peer(&mut self, peer_id: PeerId, addr: SocketAddr) {
+        self.add_peer_kind(peer_id, PeerKind::Reserved, addr, None)
+    }
+
     /// Called for a newly discovered peer.
     ///
-    /// If the peer already exists, then the address, kind and fork_id will be updated.
+    /// If the peer already exists, then the address and fork_id will be updated. The kind is only
+    /// updated if it's an upgrade, so that rediscovering a trusted or reserved peer does not demote
+    /// it.
     pub(crate) fn add_peer_kind(
         &mut self,
         peer_id: PeerId,
@@ -576,8 +825,20 @@ impl PeersManager {
         match self.peers.entry(peer_id) {
             Entry::Occupied(mut entry) => {
                 let peer = entry.get_mut();
-                peer.kind = kind;
+                let is_upgrade = matches!(
+                    (peer.kind, kind),
+                    (PeerKind::Basic, _) | (PeerKind::Trusted, PeerKind::Reserved)
+                );
+                if is_upgrade {
+                    if kind == PeerKind::Reserved {
+                        // the active session now occupies a reserved slot
+                        self.connection_info.move_state(peer.state, true);
+                    }
+                    peer.kind = kind;
+                }
                 peer.fork_id = fork_id;
+                self.connection_info.decr_ip(peer.state, peer.addr.ip());
+                self.connection_info.inc_ip(peer.state, addr.ip());
                 peer.addr = addr;
 
                 if peer.state.is_incoming() {
@@ -621,13 +882,15 @@ impl PeersManager {
                 peer_id,
                 reason: Some(DisconnectReason::DisconnectRequested),
             })
+        } else {
+            self.connection_info.decr_ip(peer.state, peer.addr.ip());
         }
     }
 
     /// Removes the tracked node from the trusted set.
     pub(crate) fn remove_peer_from_trusted_set(&mut self, peer_id: PeerId) {
         let Entry::Occupied(mut entry) = self.peers.entry(peer_id) else { return };
-        if !entry.get().is_trusted() {
+        if entry.get().kind != PeerKind::Trusted {
             return
         }
 
@@ -636,7 +899,21 @@ impl PeersManager {
         peer.kind = PeerKind::Basic;
     }
 
-    /// Returns the idle peer with the highest reputation.
+    /// Removes the tracked node from the reserved set.
+    ///
+    /// An active session is kept, but moved to the regular slots.
+    pub(crate) fn remove_peer_from_reserved_set(&mut self, peer_id: PeerId) {
+        let Entry::Occupied(mut entry) = self.peers.entry(peer_id) else { return };
+        if !entry.get().is_reserved() {
+            return
+        }
+
+        let peer = entry.get_mut();
+        self.connection_info.move_state(peer.state, false);
+        peer.kind = PeerKind::Basic;
+    }
+
+    /// Returns the idle peer from the least occupied subnet with the highest reputation.
     ///
     /// Peers that are `trusted`, see [PeerKind], are prioritized as long as they're not currently
     /// marked as banned or backed off.
@@ -644,13 +921,22 @@ impl PeersManager {
     /// If `connect_trusted_nodes_only` is enabled, see [PeersConfig], then this will only consider
     /// `trusted` peers.
     ///
+    /// Peers that are not `trusted` are skipped if another outgoing connection with their ip
+    /// address or subnet would exceed the configured limits.
+    ///
+    /// Reserved peers are never returned, they're dialed by [Self::fill_reserved_slots].
+    ///
     /// Returns `None` if no peer is available.
     fn best_unconnected(&mut self) -> Option<(PeerId, &mut Peer)> {
+        let connection_info = &self.connection_info;
         let mut unconnected = self.peers.iter_mut().filter(|(_, peer)| {
             !peer.is_backed_off() &&
                 !peer.is_banned() &&
+                !peer.is_reserved() &&
                 peer.state.is_unconnected() &&
-                (!self.connect_trusted_nodes_only || peer.is_trusted())
+                (!self.connect_trusted_nodes_only || peer.is_trusted()) &&
+                (peer.is_trusted() ||
+                    !connection_info.exceeds_outbound_ip_limits(&peer.addr.ip()))
         });
 
         // keep track of the best peer, if there's one
@@ -666,8 +952,12 @@ impl PeersManager {
                 return Some((*maybe_better.0, maybe_better.1))
             }
 
-            // otherwise we keep track of the best peer using the reputation
-            if maybe_better.1.reputation > best_peer.1.reputation {
+            // otherwise we keep track of the best peer, preferring peers from subnets we're
+            // connected to less, then using the reputation
+            let rank = |peer: &Peer| {
+                (Reverse(connection_info.outbound_ips.num_subnet(&peer.addr.ip())), peer.reputation)
+            };
+            if rank(maybe_better.1) > rank(best_peer.1) {
                 best_peer = maybe_better;
             }
         }
@@ -682,10 +972,12 @@ impl PeersManager {
     fn fill_outbound_slots(&mut self) {
         self.tick();
 
+        self.fill_reserved_slots();
+
         // as long as there a slots available try to fill them with the best peers
         let mut new_outbound_dials = 1;
         while self.connection_info.has_out_capacity() {
-            let action = {
+            let (action, ip) = {
                 let (peer_id, peer) = match self.best_unconnected() {
                     Some(peer) => peer,
                     _ => break,
@@ -699,10 +991,11 @@ impl PeersManager {
                 trace!(target : "net::peers",  ?peer_id, addr=?peer.addr, "schedule outbound connection");
 
                 peer.state = PeerConnectionState::Out;
-                PeerAction::Connect { peer_id, remote_addr: peer.addr }
+                (PeerAction::Connect { peer_id, remote_addr: peer.addr }, peer.addr.ip())
             };
 
             self.connection_info.inc_out();
+            self.connection_info.inc_ip(PeerConnectionState::Out, ip);
 
             self.queued_actions.push_back(action);
 
@@ -713,6 +1006,25 @@ impl PeersManager {
         }
     }
 
+    /// Queues [`PeerAction::Connect`] actions for all reserved peers that are not connected and
+    /// not waiting to be reconnected.
+    ///
+    /// Reserved peers have dedicated slots, so they're dialed regardless of the outbound limits.
+    fn fill_reserved_slots(&mut self) {
+        let reserved = self.peers.iter_mut().filter(|(_, peer)| {
+            peer.is_reserved() && peer.state.is_unconnected() && !peer.is_backed_off()
+        });
+        for (peer_id, peer) in reserved {
+            trace!(target : "net::peers",  ?peer_id, addr=?peer.addr, "schedule reserved connection");
+
+            peer.state = PeerConnectionState::Out;
+            self.connection_info.inc_state(peer.state, true);
+            self.connection_info.inc_ip(peer.state, peer.addr.ip());
+            self.queued_actions
+                .push_back(PeerAction::Connect { peer_id: *peer_id, remote_addr: peer.addr });
+        }
+    }
+
     /// Advances the state.
     ///
     /// Event hooks invoked externally may trigger a new [`PeerAction`] that are buffered until
@@ -739,6 +1051,10 @@ impl PeersManager {
                     PeerCommand::GetPeers(tx) => {
                         let _ = tx.send(self.iter_peers().collect());
                     }
+                    PeerCommand::GetBans(tx) => {
+                        let _ = tx.send(self.bans());
+                    }
+                    PeerCommand::ClearBans => self.clear_bans(),
                 }
             }
 
@@ -757,15 +1073,22 @@ impl PeersManager {
 
                 // clear the backoff list of expired backoffs, and mark the relevant peers as
                 // ready to be dialed
+                let mut released_reserved = false;
                 self.backed_off_peers.retain(|peer_id, until| {
                     if now > *until {
                         if let Some(peer) = self.peers.get_mut(peer_id) {
                             peer.backed_off = false;
+                            released_reserved |= peer.is_reserved();
                         }
                         return false
                     }
                     true
-                })
+                });
+
+                // reserved peers are reconnected as soon as their timeout expired
+                if released_reserved {
+                    self.fill_reserved_slots();
+                }
             }
 
             while self.refill_slots_interval.poll_tick(cx).is_ready() {
@@ -795,6 +1118,16 @@ pub struct ConnectionInfo {
     /// Counter for currently occupied slots for active inbound connections.
     #[cfg_attr(feature = "serde", serde(skip))]
     num_inbound: usize,
+    /// Counter for currently occupied reserved slots for outbound connections.
+    ///
+    /// Reserved slots are not limited by `max_outbound`.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    num_reserved_outbound: usize,
+    /// Counter for currently occupied reserved slots for inbound connections.
+    ///
+    /// Reserved slots are not limited by `max_inbound`.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    num_reserved_inbound: usize,
     /// Maximum allowed outbound connections.
     max_outbound: usize,
     /// Maximum allowed inbound connections.
@@ -802,6 +1135,25 @@ pub struct ConnectionInfo {
     /// Maximum allowed concurrent outbound dials.
     #[cfg_attr(feature = "serde", serde(default))]
     max_concurrent_outbound_dials: usize,
+    /// Maximum allowed connections per direction with a single ip address.
+    ///
+    /// Only applies to globally routable addresses. Trusted peers are exempt, except for pending
+    /// incoming connections, whose peer is not known yet. Pending incoming connections from the
+    /// ip address of a reserved peer are exempt as well.
+    max_per_ip: usize,
+    /// Maximum allowed connections per direction with a single subnet, /24 for IPv4 and /64 for
+    /// IPv6.
+    ///
+    /// See also `max_per_ip`.
+    max_per_subnet: usize,
+    /// Ip addresses of the peers with an incoming connection, including those that are being
+    /// disconnected.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    inbound_ips: IpConnectionCounts,
+    /// Ip addresses of the peers with a pending or active outgoing connection, including those
+    /// that are being disconnected.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    outbound_ips: IpConnectionCounts,
 }
 
 // === impl ConnectionInfo ===
@@ -817,14 +1169,95 @@ impl ConnectionInfo {
         self.num_inbound < self.max_inbound
     }
 
-    fn decr_state(&mut self, state: PeerConnectionState) {
+    /// Returns `true` if another incoming connection with the ip address would exceed the per ip
+    /// or per subnet limits.
+    fn exceeds_inbound_ip_limits(&self, ip: &IpAddr) -> bool {
+        self.inbound_ips.exceeds_limits(ip, self.max_per_ip, self.max_per_subnet)
+    }
+
+    /// Returns `true` if another outgoing connection with the ip address would exceed the per ip
+    /// or per subnet limits.
+    fn exceeds_outbound_ip_limits(&self, ip: &IpAddr) -> bool {
+        self.outbound_ips.exceeds_limits(ip, self.max_per_ip, self.max_per_subnet)
+    }
+
+    /// Returns the ip address counts of the direction of a connection in the given state.
+    fn ip_counts_mut(&mut self, state: PeerConnectionState) -> Option<&mut IpConnectionCounts> {
         match state {
-            PeerConnectionState::Idle => {}
-            PeerConnectionState::DisconnectingIn | PeerConnectionState::In => self.decr_in(),
-            PeerConnectionState::DisconnectingOut | PeerConnectionState::Out => self.decr_out(),
+            PeerConnectionState::Idle => None,
+            PeerConnectionState::DisconnectingIn | PeerConnectionState::In => {
+                Some(&mut self.inbound_ips)
+            }
+            PeerConnectionState::DisconnectingOut | PeerConnectionState::Out => {
+                Some(&mut self.outbound_ips)
+            }
+        }
+    }
+
+    /// Counts the ip address of a peer that entered the given connection state.
+    fn inc_ip(&mut self, state: PeerConnectionState, ip: IpAddr) {
+        if let Some(counts) = self.ip_counts_mut(state) {
+            counts.insert(ip)
+        }
+    }
+
+    /// Releases the ip address of a peer that left the given connection state.
+    fn decr_ip(&mut self, state: PeerConnectionState, ip: IpAddr) {
+        if let Some(counts) = self.ip_counts_mut(state) {
+            counts.remove(ip)
         }
     }
 
+    /// Moves the ip address of a peer from one connection state to another.
+    fn move_ip(&mut self, from: PeerConnectionState, to: PeerConnectionState, ip: IpAddr) {
+        self.decr_ip(from, ip);
+        self.inc_ip(to, ip);
+    }
+
+    /// Occupies a slot for a connection in the given state, either a reserved or a regular one.
+    fn inc_state(&mut self, state: PeerConnectionState, reserved: bool) {
+        match (state, reserved) {
+            (PeerConnectionState::Idle, _) => {}
+            (PeerConnectionState::DisconnectingIn | PeerConnectionState::In, false) => {
+                self.inc_in()
+            }
+            (PeerConnectionState::DisconnectingOut | PeerConnectionState::Out, false) => {
+                self.inc_out()
+            }
+            (PeerConnectionState::DisconnectingIn | PeerConnectionState::In, true) => {
+                self.num_reserved_inbound += 1
+            }
+            (PeerConnectionState::DisconnectingOut | PeerConnectionState::Out, true) => {
+                self.num_reserved_outbound += 1
+            }
+        }
+    }
+
+    /// Releases the slot of a connection in the given state, either a reserved or a regular one.
+    fn decr_state(&mut self, state: PeerConnectionState, reserved: bool) {
+        match (state, reserved) {
+            (PeerConnectionState::Idle, _) => {}
+            (PeerConnectionState::DisconnectingIn | PeerConnectionState::In, false) => {
+                self.decr_in()
+            }
+            (PeerConnectionState::DisconnectingOut | PeerConnectionState::Out, false) => {
+                self.decr_out()
+            }
+            (PeerConnectionState::DisconnectingIn | PeerConnectionState::In, true) => {
+                self.num_reserved_inbound -= 1
+            }
+            (PeerConnectionState::DisconnectingOut | PeerConnectionState::Out, true) => {
+                self.num_reserved_outbound -= 1
+            }
+        }
+    }
+
+    /// Moves a connection in the given state to a reserved slot, or back to a regular slot.
+    fn move_state(&mut self, state: PeerConnectionState, to_reserved: bool) {
+        self.decr_state(state, !to_reserved);
+        self.inc_state(state, to_reserved);
+    }
+
     fn decr_out(&mut self) {
         self.num_outbound -= 1;
     }
@@ -847,9 +1280,15 @@ impl Default for ConnectionInfo {
         ConnectionInfo {
             num_outbound: 0,
             num_inbound: 0,
+            num_reserved_outbound: 0,
+            num_reserved_inbound: 0,
             max_outbound: DEFAULT_MAX_PEERS_OUTBOUND,
             max_inbound: DEFAULT_MAX_PEERS_INBOUND,
             max_concurrent_outbound_dials: DEFAULT_MAX_CONCURRENT_DIALS,
+            max_per_ip: DEFAULT_MAX_PEERS_PER_IP,
+            max_per_subnet: DEFAULT_MAX_PEERS_PER_SUBNET,
+            inbound_ips: Default::default(),
+            outbound_ips: Default::default(),
         }
     }
 }
@@ -886,6 +1325,10 @@ impl Peer {
         Self { kind: PeerKind::Trusted, ..Self::new(addr) }
     }
 
+    fn reserved(addr: SocketAddr) -> Self {
+        Self { kind: PeerKind::Reserved, ..Self::new(addr) }
+    }
+
     fn with_state(addr: SocketAddr, state: PeerConnectionState) -> Self {
         Self {
             addr,
@@ -952,10 +1395,16 @@ impl Peer {
         self.reputation = DEFAULT_REPUTATION
     }
 
-    /// Returns whether this peer is trusted
+    /// Returns whether this peer is trusted, reserved peers are trusted as well
     #[inline]
     fn is_trusted(&self) -> bool {
-        matches!(self.kind, PeerKind::Trusted)
+        matches!(self.kind, PeerKind::Trusted | PeerKind::Reserved)
+    }
+
+    /// Returns whether this peer is reserved
+    #[inline]
+    fn is_reserved(&self) -> bool {
+        matches!(self.kind, PeerKind::Reserved)
     }
 }
 
@@ -1033,6 +1482,10 @@ pub(crate) enum PeerCommand {
     GetPeer(PeerId, oneshot::Sender<Option<Peer>>),
     /// Get node information on all peers
     GetPeers(oneshot::Sender<Vec<NodeRecord>>),
+    /// Get all active bans
+    GetBans(oneshot::Sender<Vec<Ban>>),
+    /// Lift all bans
+    ClearBans,
 }
 
 /// Actions the peer manager can trigger.
@@ -1077,6 +1530,15 @@ pub struct PeersConfig {
     pub refill_slots_interval: Duration,
     /// Trusted nodes to connect to.
     pub trusted_nodes: HashSet<NodeRecord>,
+    /// Reserved nodes to always stay connected to.
+    ///
+    /// Reserved nodes are trusted, but occupy dedicated slots outside of the configured inbound
+    /// and outbound limits and are never banned.
+    pub reserved_nodes: HashSet<NodeRecord>,
+    /// How long to wait before reconnecting to a reserved node after its connection was closed or
+    /// failed.
+    #[cfg_attr(feature = "serde", serde(with = "humantime_serde"))]
+    pub reserved_reconnect_interval: Duration,
     /// Connect to trusted nodes only?
     pub connect_trusted_nodes_only: bool,
     /// Maximum number of backoff attempts before we give up on a peer and dropping.
@@ -1107,6 +1569,9 @@ pub struct PeersConfig {
     ///
     /// The backoff duration increases with number of backoff attempts.
     pub backoff_durations: PeerBackoffDurations,
+    /// Reputations, backoffs and bans of a previous run to restore.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub persisted_state: Option<PersistedPeersState>,
 }
 
 impl Default for PeersConfig {
@@ -1120,9 +1585,12 @@ impl Default for PeersConfig {
             ban_duration: Duration::from_secs(60 * 60 * 12),
             backoff_durations: Default::default(),
             trusted_nodes: Default::default(),
+            reserved_nodes: Default::default(),
+            reserved_reconnect_interval: Duration::from_secs(5),
             connect_trusted_nodes_only: false,
             basic_nodes: Default::default(),
             max_backoff_count: 5,
+            persisted_state: None,
         }
     }
 }
@@ -1174,12 +1642,37 @@ impl PeersConfig {
         self
     }
 
+    /// Maximum allowed connections per direction with a single ip address.
+    pub fn with_max_per_ip(mut self, max_per_ip: usize) -> Self {
+        self.connection_info.max_per_ip = max_per_ip;
+        self
+    }
+
+    /// Maximum allowed connections per direction with a single subnet, /24 for IPv4 and /64 for
+    /// IPv6.
+    pub fn with_max_per_subnet(mut self, max_per_subnet: usize) -> Self {
+        self.connection_info.max_per_subnet = max_per_subnet;
+        self
+    }
+
     /// Nodes to always connect to.
     pub fn with_trusted_nodes(mut self, nodes: HashSet<NodeRecord>) -> Self {
         self.trusted_nodes = nodes;
         self
     }
 
+    /// Nodes to always stay connected to, with dedicated slots.
+    pub fn with_reserved_nodes(mut self, nodes: HashSet<NodeRecord>) -> Self {
+        self.reserved_nodes = nodes;
+        self
+    }
+
+    /// How long to wait before reconnecting to a reserved node.
+    pub fn with_reserved_reconnect_interval(mut self, interval: Duration) -> Self {
+        self.reserved_reconnect_interval = interval;
+        self
+    }
+
     /// Connect only to trusted nodes.
     pub fn with_connect_trusted_nodes_only(mut self, trusted_only: bool) -> Self {
         self.connect_trusted_nodes_only = trusted_only;
@@ -1213,6 +1706,25 @@ impl PeersConfig {
         let nodes: HashSet<NodeRecord> = serde_json::from_reader(reader)?;
         Ok(self.with_basic_nodes(nodes))
     }
+
+    /// Restores reputations, backoffs and bans of a previous run.
+    pub fn with_persisted_state(mut self, state: PersistedPeersState) -> Self {
+        self.persisted_state = Some(state);
+        self
+    }
+
+    /// Read the state of a previous run from file, see [`PersistedPeersState::file_path`].
+    /// Ignored if None.
+    pub fn with_persisted_state_from_file(
+        self,
+        optional_file: Option<impl AsRef<Path>>,
+    ) -> Result<Self, io::Error> {
+        let Some(file_path) = optional_file else { return Ok(self) };
+        match PersistedPeersState::read_from_file(file_path)? {
+            Some(state) => Ok(self.with_persisted_state(state)),
+            None => Ok(self),
+        }
+    }
 }
 
 /// The durations to use when a backoff should be applied to a peer.
@@ -1275,6 +1787,7 @@ impl Default for PeerBackoffDurations {
 #[derive(Debug, Error)]
 pub enum InboundConnectionError {
     ExceedsLimit(usize),
+    ExceedsIpLimit,
     IpBanned,
 }
 
@@ -1286,13 +1799,14 @@ impl Display for InboundConnectionError {
 
 #[cfg(test)]
 mod test {
-    use super::PeersManager;
+    use super::{InboundConnectionError, PeersManager};
     use crate::{
         error::BackoffKind,
         peers::{
             manager::{ConnectionInfo, PeerBackoffDurations, PeerConnectionState},
-            reputation::DEFAULT_REPUTATION,
-            PeerAction,
+            reputation::{BANNED_REPUTATION, DEFAULT_REPUTATION},
+            subnet::SubnetDistribution,
+            PeerAction, PersistedPeer, PersistedPeerBan, PersistedPeersState,
         },
         session::PendingSessionHandshakeError,
         PeersConfig,
@@ -1303,7 +1817,7 @@ mod test {
         DisconnectReason,
     };
     use reth_net_common::ban_list::BanList;
-    use reth_network_api::ReputationChangeKind;
+    use reth_network_api::{PeerKind, ReputationChangeKind};
     use reth_primitives::{PeerId, H512};
     use std::{
         collections::HashSet,
@@ -2028,13 +2542,13 @@ mod test {
         let mut info = ConnectionInfo::default();
         info.inc_in();
 
-        info.decr_state(PeerConnectionState::In);
+        info.decr_state(PeerConnectionState::In, false);
         assert_eq!(info.num_inbound, 0);
         assert_eq!(info.num_outbound, 0);
 
         info.inc_out();
 
-        info.decr_state(PeerConnectionState::Out);
+        info.decr_state(PeerConnectionState::Out, false);
         assert_eq!(info.num_inbound, 0);
         assert_eq!(info.num_outbound, 0);
     }
@@ -2204,4 +2718,408 @@ mod test {
             .count();
         assert_eq!(dials, peer_manager.connection_info.max_concurrent_outbound_dials);
     }
+
+    #[tokio::test]
+    async fn test_inbound_ip_limits() {
+        let config = PeersConfig::default().with_max_per_ip(1).with_max_per_subnet(2);
+        let mut peers = PeersManager::new(config);
+
+        let first = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4)), 8008);
+        assert!(peers.on_incoming_pending_session(first.ip()).is_ok());
+        peers.on_incoming_session_established(PeerId::random(), first);
+        assert!(matches!(
+            peers.on_incoming_pending_session(first.ip()),
+            Err(InboundConnectionError::ExceedsIpLimit)
+        ));
+
+        let second = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(1, 2, 3, 5)), 8008);
+        assert!(peers.on_incoming_pending_session(second.ip()).is_ok());
+        peers.on_incoming_session_established(PeerId::random(), second);
+
+        // the subnet is full
+        assert!(matches!(
+            peers.on_incoming_pending_session(IpAddr::V4(Ipv4Addr::new(1, 2, 3, 6))),
+            Err(InboundConnectionError::ExceedsIpLimit)
+        ));
+        assert!(peers.on_incoming_pending_session(IpAddr::V4(Ipv4Addr::new(1, 2, 4, 6))).is_ok());
+
+        // local addresses are not limited
+        let local = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+        for _ in 0..3 {
+            assert!(peers.on_incoming_pending_session(local).is_ok());
+            peers.on_incoming_session_established(PeerId::random(), SocketAddr::new(local, 8008));
+        }
+
+        let distribution = peers.subnet_distribution();
+        assert_eq!(distribution.inbound_subnets, 1);
+        assert_eq!(distribution.max_inbound_per_subnet, 2);
+    }
+
+    #[tokio::test]
+    async fn test_inbound_ip_limits_on_established() {
+        let mut peers = PeersManager::new(PeersConfig::default().with_max_per_ip(1));
+        let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4)), 8008);
+
+        // both pending sessions are accepted before either is established
+        assert!(peers.on_incoming_pending_session(addr.ip()).is_ok());
+        assert!(peers.on_incoming_pending_session(addr.ip()).is_ok());
+
+        let first = PeerId::random();
+        let second = PeerId::random();
+        peers.on_incoming_session_established(first, addr);
+        peers.on_incoming_session_established(second, addr);
+
+        assert_eq!(peers.peers.get(&first).unwrap().state, PeerConnectionState::In);
+        assert_eq!(peers.peers.get(&second).unwrap().state, PeerConnectionState::DisconnectingIn);
+        assert!(peers.queued_actions.iter().any(|action| matches!(
+            action,
+            PeerAction::Disconnect { peer_id, reason: Some(DisconnectReason::TooManyPeers) }
+                if *peer_id == second
+        )));
+
+        peers.on_active_session_gracefully_closed(second);
+        assert_eq!(peers.num_inbound_connections(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_ip_counts_follow_connection_state() {
+        let mut peers = PeersManager::default();
+
+        let incoming = PeerId::random();
+        let incoming_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4)), 8008);
+        peers.on_incoming_pending_session(incoming_addr.ip()).unwrap();
+        peers.on_incoming_session_established(incoming, incoming_addr);
+        assert_eq!(peers.connection_info.inbound_ips.num_ip(&incoming_addr.ip()), 1);
+
+        let outgoing = PeerId::random();
+        let outgoing_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(5, 6, 7, 8)), 8008);
+        peers.add_peer(outgoing, outgoing_addr, None);
+        peers.fill_outbound_slots();
+        assert_eq!(peers.connection_info.outbound_ips.num_ip(&outgoing_addr.ip()), 1);
+
+        // the discovered address of a connected peer changes
+        let moved_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(5, 6, 8, 8)), 8008);
+        peers.add_peer(outgoing, moved_addr, None);
+        assert_eq!(peers.connection_info.outbound_ips.num_ip(&outgoing_addr.ip()), 0);
+        assert_eq!(peers.connection_info.outbound_ips.num_ip(&moved_addr.ip()), 1);
+
+        peers.on_active_session_gracefully_closed(incoming);
+        peers.on_pending_session_gracefully_closed(&outgoing);
+        assert_eq!(peers.subnet_distribution(), SubnetDistribution::default());
+    }
+
+    #[tokio::test]
+    async fn test_outbound_subnet_diversity() {
+        let mut peers = PeersManager::new(PeersConfig::default().with_max_outbound(2));
+        let first = PeerId::random();
+        let same_subnet = PeerId::random();
+        let other_subnet = PeerId::random();
+        peers.add_peer(first, SocketAddr::new(IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4)), 8008), None);
+        peers.add_peer(
+            same_subnet,
+            SocketAddr::new(IpAddr::V4(Ipv4Addr::new(1, 2, 3, 5)), 8008),
+            None,
+        );
+        peers.add_peer(
+            other_subnet,
+            SocketAddr::new(IpAddr::V4(Ipv4Addr::new(5, 6, 7, 8)), 8008),
+            None,
+        );
+        peers.peers.get_mut(&first).unwrap().reputation = 200;
+        peers.peers.get_mut(&same_subnet).unwrap().reputation = 100;
+
+        peers.fill_outbound_slots();
+
+        // the peer from the other subnet is preferred despite its lower reputation
+        let dials = peers
+            .queued_actions
+            .iter()
+            .filter_map(|action| match action {
+                PeerAction::Connect { peer_id, .. } => Some(*peer_id),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(dials, vec![first, other_subnet]);
+        assert_eq!(peers.subnet_distribution().outbound_subnets, 2);
+    }
+
+    #[tokio::test]
+    async fn test_outbound_subnet_limit() {
+        let mut peers = PeersManager::new(PeersConfig::default().with_max_per_subnet(1));
+        for i in 1..=3 {
+            let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(1, 2, 3, i)), 8008);
+            peers.add_peer(PeerId::random(), addr, None);
+        }
+
+        peers.fill_outbound_slots();
+        assert_eq!(peers.num_outbound_connections(), 1);
+
+        // trusted peers are exempt from the limits
+        let trusted = PeerId::random();
+        peers.add_trusted_peer(
+            trusted,
+            SocketAddr::new(IpAddr::V4(Ipv4Addr::new(1, 2, 3, 9)), 8008),
+        );
+        peers.fill_outbound_slots();
+        assert_eq!(peers.num_outbound_connections(), 2);
+        assert_eq!(peers.peers.get(&trusted).unwrap().state, PeerConnectionState::Out);
+    }
+
+    #[tokio::test]
+    async fn test_restore_persisted_state() {
+        let socket_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 1, 2)), 8008);
+        let banned_ip = IpAddr::V4(Ipv4Addr::new(1, 1, 1, 1));
+        let banned = PeerId::random();
+        let backed_off = PeerId::random();
+        let basic = PeerId::random();
+        let nodes: HashSet<_> = [banned, backed_off, basic]
+            .into_iter()
+            .map(|id| NodeRecord::new(socket_addr, id))
+            .collect();
+
+        let mut peers = PeersManager::new(PeersConfig::default().with_basic_nodes(nodes.clone()));
+        peers.peers.get_mut(&banned).unwrap().reputation = BANNED_REPUTATION;
+        peers.ban_peer(banned);
+        peers.ban_ip(banned_ip);
+        peers.peers.get_mut(&backed_off).unwrap().severe_backoff_counter = 2;
+        peers.backoff_peer_until(
+            backed_off,
+            std::time::Instant::now() + Duration::from_secs(60 * 60),
+        );
+
+        let state = peers.persisted_state();
+        assert_eq!(state.peers.len(), 2);
+        assert_eq!(state.banned_peers.len(), 1);
+        assert_eq!(state.banned_ips.len(), 1);
+
+        let config = PeersConfig::default().with_basic_nodes(nodes).with_persisted_state(state);
+        let restored = PeersManager::new(config);
+
+        // banned peers are not added to the set
+        assert!(!restored.peers.contains_key(&banned));
+        assert!(restored.ban_list.is_banned_peer(&banned));
+        assert!(restored.ban_list.is_banned_ip(&banned_ip));
+        assert_eq!(restored.bans().len(), 2);
+
+        let peer = restored.peers.get(&backed_off).unwrap();
+        assert!(peer.is_backed_off());
+        assert_eq!(peer.severe_backoff_counter, 2);
+        assert!(restored.backed_off_peers.contains_key(&backed_off));
+
+        let peer = restored.peers.get(&basic).unwrap();
+        assert_eq!(peer.reputation, DEFAULT_REPUTATION);
+        assert!(!peer.is_backed_off());
+    }
+
+    #[tokio::test]
+    async fn test_restore_expired_ban() {
+        let socket_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 1, 2)), 8008);
+        let peer = PeerId::random();
+        let state = PersistedPeersState {
+            peers: vec![PersistedPeer {
+                id: peer,
+                reputation: BANNED_REPUTATION,
+                severe_backoff_counter: 0,
+                backoff_until: Some(1),
+            }],
+            banned_peers: vec![PersistedPeerBan { id: peer, until: Some(1) }],
+            banned_ips: vec![],
+        };
+        let config = PeersConfig::default()
+            .with_basic_nodes(HashSet::from([NodeRecord::new(socket_addr, peer)]))
+            .with_persisted_state(state);
+        let peers = PeersManager::new(config);
+
+        assert!(peers.bans().is_empty());
+        let peer = peers.peers.get(&peer).unwrap();
+        assert!(!peer.is_banned());
+        assert!(!peer.is_backed_off());
+    }
+
+    #[tokio::test]
+    async fn test_clear_bans() {
+        let peer = PeerId::random();
+        let socket_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 1, 2)), 8008);
+        let mut peers = PeersManager::default();
+        peers.add_peer(peer, socket_addr, None);
+        peers.apply_reputation_change(&peer, ReputationChangeKind::BadProtocol);
+        peers.ban_ip(IpAddr::V4(Ipv4Addr::new(1, 1, 1, 1)));
+        assert_eq!(peers.bans().len(), 2);
+
+        match event!(peers) {
+            PeerAction::PeerAdded(peer_id) => assert_eq!(peer_id, peer),
+            _ => unreachable!(),
+        }
+        match event!(peers) {
+            PeerAction::BanPeer { peer_id } => assert_eq!(peer_id, peer),
+            _ => unreachable!(),
+        }
+
+        let handle = peers.handle();
+        handle.clear_bans();
+
+        match event!(peers) {
+            PeerAction::UnBanPeer { peer_id } => assert_eq!(peer_id, peer),
+            _ => unreachable!(),
+        }
+        assert!(peers.bans().is_empty());
+        assert_eq!(peers.peers.get(&peer).unwrap().reputation, DEFAULT_REPUTATION);
+    }
+
+    fn reserved_node(id: PeerId, addr: SocketAddr) -> NodeRecord {
+        NodeRecord { address: addr.ip(), tcp_port: addr.port(), udp_port: addr.port(), id }
+    }
+
+    #[tokio::test]
+    async fn test_reserved_peers_outside_limits() {
+        let reserved = PeerId::random();
+        let reserved_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 1, 2)), 8008);
+        let config = PeersConfig::default()
+            .with_max_outbound(0)
+            .with_max_inbound(1)
+            .with_reserved_nodes(HashSet::from([reserved_node(reserved, reserved_addr)]));
+        let mut peers = PeersManager::new(config);
+
+        // reserved peers are dialed even though there are no outbound slots
+        peers.fill_outbound_slots();
+        match event!(peers) {
+            PeerAction::Connect { peer_id, remote_addr } => {
+                assert_eq!(peer_id, reserved);
+                assert_eq!(remote_addr, reserved_addr);
+            }
+            _ => unreachable!(),
+        }
+        assert_eq!(peers.num_outbound_connections(), 0);
+        assert_eq!(peers.connection_info.num_reserved_outbound, 1);
+
+        // incoming reserved sessions are moved to the reserved slots
+        let inbound = PeerId::random();
+        let inbound_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 1, 3)), 8008);
+        peers.add_reserved_peer(inbound, inbound_addr);
+        assert!(peers.on_incoming_pending_session(inbound_addr.ip()).is_ok());
+        peers.on_incoming_session_established(inbound, inbound_addr);
+        assert_eq!(peers.num_inbound_connections(), 0);
+        assert_eq!(peers.connection_info.num_reserved_inbound, 1);
+        assert!(peers.on_incoming_pending_session(inbound_addr.ip()).is_ok());
+
+        // reserved peers are never banned
+        peers.apply_reputation_change(&reserved, ReputationChangeKind::BadProtocol);
+        assert_eq!(peers.get_reputation(&reserved), Some(DEFAULT_REPUTATION));
+        assert!(peers.bans().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_reserved_peer_reconnect() {
+        let peer = PeerId::random();
+        let socket_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 1, 2)), 8008);
+        let reserved_reconnect_interval = Duration::from_millis(200);
+        let config = PeersConfig::default()
+            .with_reserved_nodes(HashSet::from([reserved_node(peer, socket_addr)]))
+            .with_reserved_reconnect_interval(reserved_reconnect_interval);
+        let mut peers = PeersManager::new(config);
+
+        peers.fill_outbound_slots();
+        match event!(peers) {
+            PeerAction::Connect { peer_id, .. } => {
+                assert_eq!(peer_id, peer);
+            }
+            _ => unreachable!(),
+        }
+
+        // a fatal error would remove and ban a regular peer
+        peers.on_active_session_dropped(
+            &socket_addr,
+            &peer,
+            &EthStreamError::P2PStreamError(P2PStreamError::Disconnected(
+                DisconnectReason::UselessPeer,
+            )),
+        );
+        assert!(peers.bans().is_empty());
+        assert_eq!(peers.connection_info.num_reserved_outbound, 0);
+        let reserved = peers.peers.get(&peer).unwrap();
+        assert_eq!(reserved.state, PeerConnectionState::Idle);
+        assert!(reserved.is_backed_off());
+
+        tokio::time::sleep(reserved_reconnect_interval).await;
+
+        match event!(peers) {
+            PeerAction::Connect { peer_id, .. } => {
+                assert_eq!(peer_id, peer);
+            }
+            _ => unreachable!(),
+        }
+        assert_eq!(peers.connection_info.num_reserved_outbound, 1);
+    }
+
+    #[tokio::test]
+    async fn test_reserved_peer_kind_changes() {
+        let peer = PeerId::random();
+        let socket_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 1, 2)), 8008);
+        let mut peers = PeersManager::default();
+        peers.add_peer(peer, socket_addr, None);
+        peers.fill_outbound_slots();
+        assert_eq!(peers.num_outbound_connections(), 1);
+
+        // the active session moves to a reserved slot
+        peers.add_reserved_peer(peer, socket_addr);
+        assert_eq!(peers.num_outbound_connections(), 0);
+        assert_eq!(peers.connection_info.num_reserved_outbound, 1);
+
+        // rediscovering the peer does not demote it
+        peers.add_peer(peer, socket_addr, None);
+        peers.add_trusted_peer(peer, socket_addr);
+        assert_eq!(peers.peers.get(&peer).unwrap().kind, PeerKind::Reserved);
+
+        peers.remove_peer_from_reserved_set(peer);
+        assert_eq!(peers.peers.get(&peer).unwrap().kind, PeerKind::Basic);
+        assert_eq!(peers.num_outbound_connections(), 1);
+        assert_eq!(peers.connection_info.num_reserved_outbound, 0);
+
+        peers.on_active_session_gracefully_closed(peer);
+        assert_eq!(peers.num_outbound_connections(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_reserved_ip_inbound_headroom() {
+        let reserved = PeerId::random();
+        let reserved_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4)), 8008);
+        let config = PeersConfig::default()
+            .with_max_inbound(1)
+            .with_max_per_ip(1)
+            .with_max_per_subnet(1)
+            .with_reserved_nodes(HashSet::from([reserved_node(reserved, reserved_addr)]));
+        let mut peers = PeersManager::new(config);
+
+        // a regular peer from the subnet of the reserved peer occupies the only inbound slot
+        let regular_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(1, 2, 3, 5)), 8008);
+        assert!(peers.on_incoming_pending_session(regular_addr.ip()).is_ok());
+        peers.on_incoming_session_established(PeerId::random(), regular_addr);
+        assert!(matches!(
+            peers.on_incoming_pending_session(IpAddr::V4(Ipv4Addr::new(5, 6, 7, 8))),
+            Err(InboundConnectionError::ExceedsLimit(1))
+        ));
+        assert!(matches!(
+            peers.on_incoming_pending_session(IpAddr::V4(Ipv4Addr::new(1, 2, 3, 6))),
+            Err(InboundConnectionError::ExceedsLimit(1) | InboundConnectionError::ExceedsIpLimit)
+        ));
+
+        // the reserved peer can still connect, twice from its ip even though the per ip limit is 1
+        assert!(peers.on_incoming_pending_session(reserved_addr.ip()).is_ok());
+        assert!(peers.on_incoming_pending_session(reserved_addr.ip()).is_ok());
+        peers.on_incoming_session_established(reserved, reserved_addr);
+        assert_eq!(peers.connection_info.num_reserved_inbound, 1);
+        assert!(peers.queued_actions.iter().all(|action| !matches!(
+            action,
+            PeerAction::Disconnect { peer_id, .. } if *peer_id == reserved
+        )));
+
+        // another peer behind the ip of the reserved peer is disconnected
+        let other = PeerId::random();
+        peers.on_incoming_session_established(other, reserved_addr);
+        assert!(peers.queued_actions.iter().any(|action| matches!(
+            action,
+            PeerAction::Disconnect { peer_id, reason: Some(DisconnectReason::TooManyPeers) }
+                if *peer_id == other
+        )));
+    }
 }
@@ -1,10 +1,13 @@
 //! Peer related implementations
 
 mod manager;
+mod persisted;
 mod reputation;
+mod subnet;
 
 pub(crate) use manager::{InboundConnectionError, PeerAction, PeersManager};
 pub use manager::{Peer, PeersConfig, PeersHandle};
+pub use persisted::{PersistedIpBan, PersistedPeer, PersistedPeerBan, PersistedPeersState};
 pub use reputation::ReputationChangeWeights;
 pub use reth_network_api::PeerKind;
 
@@ -0,0 +1,169 @@
+//! Peer state that survives restarts.
+//!
+//! The known peers file only stores [`NodeRecord`](reth_primitives::NodeRecord)s. The
+//! [`PersistedPeersState`] is stored next to it and keeps track of reputations, backoffs and bans,
+//! so that peers we banned for bad behaviour are not dialed again right after a restart.
+
+use reth_primitives::PeerId;
+use std::{
+    io::{self, ErrorKind},
+    net::IpAddr,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tracing::info;
+
+/// The persisted state of the peer set.
+///
+/// All timestamps are unix timestamps in seconds.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PersistedPeersState {
+    /// Peers with a non-default reputation or backoff state.
+    pub peers: Vec<PersistedPeer>,
+    /// Banned peers.
+    pub banned_peers: Vec<PersistedPeerBan>,
+    /// Banned ip addresses.
+    pub banned_ips: Vec<PersistedIpBan>,
+}
+
+// === impl PersistedPeersState ===
+
+impl PersistedPeersState {
+    /// Returns the path of the state file that belongs to the given known peers file.
+    ///
+    /// For `known-peers.json` this is `known-peers.state.json` in the same directory.
+    pub fn file_path(peers_file: impl AsRef<Path>) -> PathBuf {
+        peers_file.as_ref().with_extension("state.json")
+    }
+
+    /// Reads the state from the given file.
+    ///
+    /// Returns `None` if the file does not exist.
+    pub fn read_from_file(path: impl AsRef<Path>) -> Result<Option<Self>, io::Error> {
+        let reader = match std::fs::File::open(path.as_ref()) {
+            Ok(file) => io::BufReader::new(file),
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => Err(e)?,
+        };
+        info!(target: "net::peers", file = %path.as_ref().display(), "Loading saved peers state");
+        Ok(Some(serde_json::from_reader(reader)?))
+    }
+
+    /// Writes the state to the given file, creating the parent directory if necessary.
+    pub fn write_to_file(&self, path: impl AsRef<Path>) -> Result<(), io::Error> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let state = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, state)
+    }
+}
+
+/// The persisted reputation and backoff state of a single peer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PersistedPeer {
+    /// The identifier of the peer.
+    pub id: PeerId,
+    /// The reputation of the peer.
+    pub reputation: i32,
+    /// How often the peer was backed off due to a severe
+    /// [`BackoffKind`](crate::error::BackoffKind).
+    pub severe_backoff_counter: u32,
+    /// Until when the peer is backed off, if it is.
+    pub backoff_until: Option<u64>,
+}
+
+/// A persisted ban of a peer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PersistedPeerBan {
+    /// The banned peer.
+    pub id: PeerId,
+    /// Until when the peer is banned, `None` if banned indefinitely.
+    pub until: Option<u64>,
+}
+
+/// A persisted ban of an ip address.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PersistedIpBan {
+    /// The banned ip address.
+    pub ip: IpAddr,
+    /// Until when the ip is banned, `None` if banned indefinitely.
+    pub until: Option<u64>,
+}
+
+/// Converts the monotonic [`Instant`] into the corresponding [`SystemTime`].
+pub(crate) fn instant_to_system_time(instant: Instant) -> SystemTime {
+    let now = Instant::now();
+    let system_now = SystemTime::now();
+    if instant >= now {
+        system_now.checked_add(instant - now).unwrap_or(system_now)
+    } else {
+        system_now.checked_sub(now - instant).unwrap_or(system_now)
+    }
+}
+
+/// Converts the [`Instant`] into a unix timestamp in seconds.
+pub(crate) fn instant_to_unix_secs(instant: Instant) -> u64 {
+    instant_to_system_time(instant).duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+/// Converts the unix timestamp in seconds into an [`Instant`].
+///
+/// Returns `None` if the timestamp already passed.
+pub(crate) fn unix_secs_to_instant(secs: u64) -> Option<Instant> {
+    let remaining =
+        (UNIX_EPOCH + Duration::from_secs(secs)).duration_since(SystemTime::now()).ok()?;
+    Instant::now().checked_add(remaining)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn state_file_path() {
+        assert_eq!(
+            PersistedPeersState::file_path("/data/known-peers.json"),
+            PathBuf::from("/data/known-peers.state.json")
+        );
+    }
+
+    #[test]
+    fn unix_secs_roundtrip() {
+        let until = Instant::now() + Duration::from_secs(60 * 60);
+        let restored = unix_secs_to_instant(instant_to_unix_secs(until)).unwrap();
+        let diff = if restored > until { restored - until } else { until - restored };
+        assert!(diff <= Duration::from_secs(1));
+
+        let passed = instant_to_unix_secs(Instant::now()) - 10;
+        assert!(unix_secs_to_instant(passed).is_none());
+    }
+
+    #[test]
+    fn file_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = PersistedPeersState::file_path(dir.path().join("known-peers.json"));
+        assert_eq!(PersistedPeersState::read_from_file(&path).unwrap(), None);
+
+        let state = PersistedPeersState {
+            peers: vec![PersistedPeer {
+                id: PeerId::random(),
+                reputation: -1024,
+                severe_backoff_counter: 2,
+                backoff_until: Some(1_700_000_000),
+            }],
+            banned_peers: vec![PersistedPeerBan { id: PeerId::random(), until: None }],
+            banned_ips: vec![PersistedIpBan {
+                ip: IpAddr::from([1, 1, 1, 1]),
+                until: Some(1_700_000_000),
+            }],
+        };
+        state.write_to_file(&path).unwrap();
+        assert_eq!(PersistedPeersState::read_from_file(&path).unwrap(), Some(state));
+    }
+}
@@ -0,0 +1,173 @@
+//! Tracking of the ip addresses and subnets of connected peers.
+//!
+//! An attacker that controls many addresses of a single subnet should not be able to occupy all
+//! slots of the node, see also the connection limits of [`PeersConfig`](crate::PeersConfig).
+
+use reth_net_common::ban_list::is_global;
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+};
+
+/// Default maximum number of connections per direction with a single ip address.
+pub(crate) const DEFAULT_MAX_PEERS_PER_IP: usize = 4;
+
+/// Default maximum number of connections per direction with a single subnet.
+pub(crate) const DEFAULT_MAX_PEERS_PER_SUBNET: usize = 8;
+
+/// The subnet of an ip address, /24 for IPv4 and /64 for IPv6.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct Subnet(IpAddr);
+
+// === impl Subnet ===
+
+impl Subnet {
+    /// Returns the subnet the ip address belongs to.
+    pub(crate) fn new(ip: IpAddr) -> Self {
+        match ip {
+            IpAddr::V4(ip) => {
+                let [a, b, c, _] = ip.octets();
+                Self(Ipv4Addr::new(a, b, c, 0).into())
+            }
+            IpAddr::V6(ip) => {
+                let [a, b, c, d, ..] = ip.segments();
+                Self(Ipv6Addr::new(a, b, c, d, 0, 0, 0, 0).into())
+            }
+        }
+    }
+}
+
+/// Counts connections per ip address and per subnet.
+///
+/// Only globally routable addresses are counted, so that local setups with many peers on the same
+/// host are not limited.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct IpConnectionCounts {
+    ips: HashMap<IpAddr, usize>,
+    subnets: HashMap<Subnet, usize>,
+}
+
+// === impl IpConnectionCounts ===
+
+impl IpConnectionCounts {
+    /// Counts a connection with the given ip address.
+    pub(crate) fn insert(&mut self, ip: IpAddr) {
+        if !is_global(&ip) {
+            return
+        }
+        *self.ips.entry(ip).or_default() += 1;
+        *self.subnets.entry(Subnet::new(ip)).or_default() += 1;
+    }
+
+    /// Removes a connection with the given ip address that was previously counted.
+    pub(crate) fn remove(&mut self, ip: IpAddr) {
+        if !is_global(&ip) {
+            return
+        }
+        decrement(&mut self.ips, ip);
+        decrement(&mut self.subnets, Subnet::new(ip));
+    }
+
+    /// Returns the number of connections with the given ip address.
+    pub(crate) fn num_ip(&self, ip: &IpAddr) -> usize {
+        self.ips.get(ip).copied().unwrap_or_default()
+    }
+
+    /// Returns the number of connections with the subnet of the given ip address.
+    pub(crate) fn num_subnet(&self, ip: &IpAddr) -> usize {
+        self.subnets.get(&Subnet::new(*ip)).copied().unwrap_or_default()
+    }
+
+    /// Returns `true` if another connection with the given ip address would exceed the limits.
+    pub(crate) fn exceeds_limits(
+        &self,
+        ip: &IpAddr,
+        max_per_ip: usize,
+        max_per_subnet: usize,
+    ) -> bool {
+        is_global(ip) && (self.num_ip(ip) >= max_per_ip || self.num_subnet(ip) >= max_per_subnet)
+    }
+
+    /// Returns the number of distinct subnets.
+    pub(crate) fn num_subnets(&self) -> usize {
+        self.subnets.len()
+    }
+
+    /// Returns the highest number of connections with a single subnet.
+    pub(crate) fn max_per_subnet(&self) -> usize {
+        self.subnets.values().copied().max().unwrap_or_default()
+    }
+}
+
+/// Decrements the count of the key and removes the entry once it drops to zero.
+fn decrement<K: std::hash::Hash + Eq>(counts: &mut HashMap<K, usize>, key: K) {
+    if let Entry::Occupied(mut entry) = counts.entry(key) {
+        *entry.get_mut() -= 1;
+        if *entry.get() == 0 {
+            entry.remove();
+        }
+    }
+}
+
+/// The distribution of the active connections across subnets.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct SubnetDistribution {
+    /// Number of distinct subnets of incoming connections.
+    pub(crate) inbound_subnets: usize,
+    /// Number of distinct subnets of outgoing connections.
+    pub(crate) outbound_subnets: usize,
+    /// Highest number of incoming connections with a single subnet.
+    pub(crate) max_inbound_per_subnet: usize,
+    /// Highest number of outgoing connections with a single subnet.
+    pub(crate) max_outbound_per_subnet: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subnet_masks() {
+        assert_eq!(
+            Subnet::new(IpAddr::from([1, 2, 3, 4])),
+            Subnet::new(IpAddr::from([1, 2, 3, 200]))
+        );
+        assert_ne!(
+            Subnet::new(IpAddr::from([1, 2, 3, 4])),
+            Subnet::new(IpAddr::from([1, 2, 4, 4]))
+        );
+
+        let ip: IpAddr = "2001:db8:1:2:3:4:5:6".parse().unwrap();
+        let same: IpAddr = "2001:db8:1:2:ffff::1".parse().unwrap();
+        let other: IpAddr = "2001:db8:1:3:3:4:5:6".parse().unwrap();
+        assert_eq!(Subnet::new(ip), Subnet::new(same));
+        assert_ne!(Subnet::new(ip), Subnet::new(other));
+    }
+
+    #[test]
+    fn count_limits() {
+        let mut counts = IpConnectionCounts::default();
+        counts.insert(IpAddr::from([1, 2, 3, 4]));
+        counts.insert(IpAddr::from([1, 2, 3, 5]));
+        counts.insert(IpAddr::from([5, 6, 7, 8]));
+        // local addresses are not counted
+        counts.insert(IpAddr::from([127, 0, 0, 1]));
+
+        assert_eq!(counts.num_ip(&IpAddr::from([1, 2, 3, 4])), 1);
+        assert_eq!(counts.num_subnet(&IpAddr::from([1, 2, 3, 9])), 2);
+        assert_eq!(counts.num_subnets(), 2);
+        assert_eq!(counts.max_per_subnet(), 2);
+
+        assert!(counts.exceeds_limits(&IpAddr::from([1, 2, 3, 4]), 1, 8));
+        assert!(counts.exceeds_limits(&IpAddr::from([1, 2, 3, 9]), 1, 2));
+        assert!(!counts.exceeds_limits(&IpAddr::from([1, 2, 3, 9]), 1, 3));
+        assert!(!counts.exceeds_limits(&IpAddr::from([127, 0, 0, 1]), 0, 0));
+
+        counts.remove(IpAddr::from([1, 2, 3, 4]));
+        counts.remove(IpAddr::from([5, 6, 7, 8]));
+        counts.remove(IpAddr::from([127, 0, 0, 1]));
+        assert_eq!(counts.num_ip(&IpAddr::from([1, 2, 3, 4])), 0);
+        assert_eq!(counts.num_subnet(&IpAddr::from([1, 2, 3, 9])), 1);
+        assert_eq!(counts.num_subnets(), 1);
+    }
+}
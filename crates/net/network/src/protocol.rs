@@ -0,0 +1,185 @@
+//! Support for custom RLPx sub-protocols.
+//!
+//! Next to `eth`, a session can multiplex additional capabilities, for example a private
+//! `ourproto/1`. A [`ProtocolHandler`] registered via
+//! [`NetworkConfig::add_rlpx_sub_protocol`](crate::NetworkConfig::add_rlpx_sub_protocol) or
+//! [`NetworkBuilder::add_rlpx_sub_protocol`](crate::NetworkBuilder::add_rlpx_sub_protocol) is
+//! announced in the `Hello` message and receives a [`ProtocolConnection`] for every established
+//! session with a peer that shares the protocol.
+
+use futures::{Stream, StreamExt};
+use reth_eth_wire::capability::{Capability, Protocol, SharedCapability};
+use reth_network_api::Direction;
+use reth_primitives::{
+    bytes::{Bytes, BytesMut},
+    PeerId,
+};
+use std::{
+    fmt,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tokio::sync::mpsc::{
+    self,
+    error::{SendError, TrySendError},
+};
+use tokio_stream::wrappers::ReceiverStream;
+
+/// The capacity of the channels between a session and a [`ProtocolConnection`], in both
+/// directions.
+pub const PROTOCOL_CONNECTION_CHANNEL_CAPACITY: usize = 64;
+
+/// A handler for a custom RLPx sub-protocol.
+///
+/// The handler is invoked for every established session in which the peer shares the
+/// [`ProtocolHandler::protocol`].
+pub trait ProtocolHandler: fmt::Debug + Send + Sync + 'static {
+    /// The protocol to announce in the `Hello` message.
+    fn protocol(&self) -> Protocol;
+
+    /// Invoked when a session with a peer that shares the protocol was established.
+    ///
+    /// Messages of the protocol are exchanged via the given [`ProtocolConnection`]. Dropping the
+    /// connection stops delivering messages of the protocol, but keeps the session alive.
+    ///
+    /// The connection must keep up with the messages received from the peer: if it falls behind by
+    /// more than [`PROTOCOL_CONNECTION_CHANNEL_CAPACITY`] messages, the session drops the protocol
+    /// and the connection's stream ends, so that a stalled handler can't hold up `eth` messages.
+    fn on_connection(&self, connection: ProtocolConnection);
+}
+
+/// All registered custom RLPx sub-protocols.
+#[derive(Debug, Clone, Default)]
+pub struct RlpxSubProtocols {
+    handlers: Vec<Arc<dyn ProtocolHandler>>,
+}
+
+// === impl RlpxSubProtocols ===
+
+impl RlpxSubProtocols {
+    /// Registers a new handler.
+    ///
+    /// A handler for a capability that is already registered replaces the existing one.
+    pub fn push(&mut self, handler: impl ProtocolHandler) {
+        let handler: Arc<dyn ProtocolHandler> = Arc::new(handler);
+        let cap = handler.protocol().cap;
+        self.handlers.retain(|existing| existing.protocol().cap != cap);
+        self.handlers.push(handler);
+    }
+
+    /// Returns the protocols of all registered handlers.
+    pub fn protocols(&self) -> Vec<Protocol> {
+        self.handlers.iter().map(|handler| handler.protocol()).collect()
+    }
+
+    /// Returns the handler for the given capability, if registered.
+    pub fn handler(&self, cap: &Capability) -> Option<&Arc<dyn ProtocolHandler>> {
+        self.handlers.iter().find(|handler| handler.protocol().cap == *cap)
+    }
+
+    /// Returns `true` if no handlers are registered.
+    pub fn is_empty(&self) -> bool {
+        self.handlers.is_empty()
+    }
+}
+
+/// The connection of a custom sub-protocol with a single peer.
+///
+/// This is a [`Stream`] of the messages received from the peer. The first byte of every message is
+/// the message id relative to the protocol, which also applies to messages sent via
+/// [`ProtocolConnection::send`].
+///
+/// The stream ends if the session was closed, or if the session dropped the protocol because the
+/// stream was not polled fast enough, see [`ProtocolHandler::on_connection`].
+#[derive(Debug)]
+pub struct ProtocolConnection {
+    /// The peer this connection belongs to.
+    peer_id: PeerId,
+    /// The direction of the session.
+    direction: Direction,
+    /// The negotiated capability.
+    capability: SharedCapability,
+    /// Messages received from the peer.
+    from_session: ReceiverStream<BytesMut>,
+    /// Messages to send to the peer.
+    to_session: mpsc::Sender<Bytes>,
+}
+
+// === impl ProtocolConnection ===
+
+impl ProtocolConnection {
+    /// Returns the identifier of the remote peer.
+    pub fn peer_id(&self) -> PeerId {
+        self.peer_id
+    }
+
+    /// Returns the direction of the session.
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    /// Returns the negotiated capability.
+    pub fn capability(&self) -> &SharedCapability {
+        &self.capability
+    }
+
+    /// Sends a message to the peer.
+    ///
+    /// Returns an error if the session was closed.
+    pub async fn send(&self, msg: Bytes) -> Result<(), SendError<Bytes>> {
+        self.to_session.send(msg).await
+    }
+
+    /// Tries to send a message to the peer without waiting for capacity.
+    pub fn try_send(&self, msg: Bytes) -> Result<(), TrySendError<Bytes>> {
+        self.to_session.try_send(msg)
+    }
+
+    /// Returns a sender that can be used to send messages to the peer.
+    pub fn sender(&self) -> mpsc::Sender<Bytes> {
+        self.to_session.clone()
+    }
+}
+
+impl Stream for ProtocolConnection {
+    type Item = BytesMut;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().from_session.poll_next_unpin(cx)
+    }
+}
+
+/// The session side of a [`ProtocolConnection`].
+#[derive(Debug)]
+pub(crate) struct ProtocolSession {
+    /// The negotiated capability.
+    pub(crate) capability: SharedCapability,
+    /// Messages received from the peer.
+    pub(crate) to_connection: mpsc::Sender<BytesMut>,
+    /// Messages to send to the peer.
+    pub(crate) from_connection: ReceiverStream<Bytes>,
+}
+
+/// Creates a connected [`ProtocolConnection`] and [`ProtocolSession`] pair.
+pub(crate) fn protocol_connection(
+    peer_id: PeerId,
+    direction: Direction,
+    capability: SharedCapability,
+) -> (ProtocolConnection, ProtocolSession) {
+    let (to_connection, from_session) = mpsc::channel(PROTOCOL_CONNECTION_CHANNEL_CAPACITY);
+    let (to_session, from_connection) = mpsc::channel(PROTOCOL_CONNECTION_CHANNEL_CAPACITY);
+    let connection = ProtocolConnection {
+        peer_id,
+        direction,
+        capability: capability.clone(),
+        from_session: ReceiverStream::new(from_session),
+        to_session,
+    };
+    let session = ProtocolSession {
+        capability,
+        to_connection,
+        from_connection: ReceiverStream::new(from_connection),
+    };
+    (connection, session)
+}
@@ -2,9 +2,11 @@
 
 use crate::{
     message::{NewBlockMessage, PeerMessage, PeerRequest, PeerResponse, PeerResponseResult},
+    protocol::ProtocolSession,
     session::{
         config::INITIAL_REQUEST_TIMEOUT,
         handle::{ActiveSessionMessage, SessionCommand},
+        stats::ActiveSessionStats,
         SessionId,
     },
 };
@@ -13,15 +15,15 @@ use fnv::FnvHashMap;
 use futures::{stream::Fuse, SinkExt, StreamExt};
 use reth_ecies::stream::ECIESStream;
 use reth_eth_wire::{
-    capability::Capabilities,
+    capability::{Capabilities, SharedCapability},
     errors::{EthHandshakeError, EthStreamError, P2PStreamError},
     message::{EthBroadcastMessage, RequestPair},
-    DisconnectReason, EthMessage, EthStream, P2PStream,
+    DisconnectReason, EthMessage, EthStream, P2PStream, SubprotocolMessage,
 };
 use reth_interfaces::p2p::error::RequestError;
 use reth_metrics::common::mpsc::MeteredSender;
 use reth_net_common::bandwidth_meter::MeteredStream;
-use reth_primitives::PeerId;
+use reth_primitives::{bytes::Bytes, PeerId};
 use std::{
     collections::VecDeque,
     future::Future,
@@ -97,6 +99,10 @@ pub(crate) struct ActiveSession {
     pub(crate) protocol_breach_request_timeout: Duration,
     /// Used to reserve a slot to guarantee that the termination message is delivered
     pub(crate) terminate_message: Option<(PollSender<ActiveSessionMessage>, ActiveSessionMessage)>,
+    /// Connections of the custom sub-protocols shared with the peer.
+    pub(crate) protocol_sessions: Vec<ProtocolSession>,
+    /// Traffic and request statistics, shared with the session's handle.
+    pub(crate) stats: Arc<ActiveSessionStats>,
 }
 
 impl ActiveSession {
@@ -118,6 +124,62 @@ impl ActiveSession {
         self.queued_outgoing.shrink_to_fit();
     }
 
+    /// Returns the next message a sub-protocol connection wants to send to the peer.
+    ///
+    /// Connections that were dropped are removed.
+    fn poll_next_protocol_message(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Option<(SharedCapability, Bytes)> {
+        let mut idx = 0;
+        while idx < self.protocol_sessions.len() {
+            let protocol = &mut self.protocol_sessions[idx];
+            match protocol.from_connection.poll_next_unpin(cx) {
+                Poll::Ready(Some(msg)) => return Some((protocol.capability.clone(), msg)),
+                Poll::Ready(None) => {
+                    trace!(target: "net::session", capability=?protocol.capability, remote_peer_id=?self.remote_peer_id, "sub-protocol connection dropped");
+                    self.protocol_sessions.swap_remove(idx);
+                }
+                Poll::Pending => idx += 1,
+            }
+        }
+        None
+    }
+
+    /// Delivers the sub-protocol messages buffered by the connection to the sub-protocol
+    /// connections.
+    ///
+    /// This never waits for a connection: a sub-protocol whose connection is full is dropped, so
+    /// that a stalled handler can't stop the session from reading `eth` messages.
+    ///
+    /// Returns `true` if any message was taken from the connection.
+    fn deliver_protocol_messages(&mut self) -> bool {
+        let mut progress = false;
+        while let Some(msg) = self.conn.inner_mut().take_subprotocol_message() {
+            progress = true;
+            let Some(idx) =
+                self.protocol_sessions.iter().position(|p| p.capability == msg.capability)
+            else {
+                // the sub-protocol is no longer active in this session
+                continue
+            };
+
+            let SubprotocolMessage { capability, message } = msg;
+            match self.protocol_sessions[idx].to_connection.try_send(message) {
+                Ok(()) => {}
+                Err(TrySendError::Full(_)) => {
+                    debug!(target: "net::session", ?capability, remote_peer_id=?self.remote_peer_id, "sub-protocol connection is not keeping up, dropping sub-protocol");
+                    self.protocol_sessions.swap_remove(idx);
+                }
+                Err(TrySendError::Closed(_)) => {
+                    trace!(target: "net::session", ?capability, remote_peer_id=?self.remote_peer_id, "sub-protocol connection dropped");
+                    self.protocol_sessions.swap_remove(idx);
+                }
+            }
+        }
+        progress
+    }
+
     /// Handle a message read from the connection.
     ///
     /// Returns an error if the message is considered to be in violation of the protocol.
@@ -149,6 +211,10 @@ impl ActiveSession {
                 let RequestPair { request_id, message } = $resp;
                 #[allow(clippy::collapsible_match)]
                 if let Some(req) = self.inflight_requests.remove(&request_id) {
+                    // late responses were already recorded as timed out
+                    if req.is_waiting() {
+                        self.stats.on_response(req.timestamp.elapsed());
+                    }
                     match req.request {
                         RequestState::Waiting(PeerRequest::$item { response, .. }) => {
                             let _ = response.send(Ok(message));
@@ -362,6 +428,7 @@ impl ActiveSession {
     /// Report back that this session has been closed.
     fn emit_disconnect(&mut self, cx: &mut Context<'_>) -> Poll<()> {
         trace!(target: "net::session", remote_peer_id=?self.remote_peer_id, "emitting disconnect");
+        self.stats.record_bandwidth();
         let msg = ActiveSessionMessage::Disconnected {
             peer_id: self.remote_peer_id,
             remote_addr: self.remote_addr,
@@ -374,6 +441,7 @@ impl ActiveSession {
 
     /// Report back that this session has been closed due to an error
     fn close_on_error(&mut self, error: EthStreamError, cx: &mut Context<'_>) -> Poll<()> {
+        self.stats.record_bandwidth();
         let msg = ActiveSessionMessage::ClosedOnConnectionError {
             peer_id: self.remote_peer_id,
             remote_addr: self.remote_addr,
@@ -431,6 +499,7 @@ impl ActiveSession {
                 if req.is_waiting() {
                     debug!(target: "net::session", ?id, remote_peer_id=?self.remote_peer_id, "timed out outgoing request");
                     req.timeout();
+                    self.stats.on_request_timeout();
                 } else if now - req.timestamp > self.protocol_breach_request_timeout {
                     return true
                 }
@@ -550,14 +619,33 @@ impl Future for ActiveSession {
                 if let Some(msg) = this.queued_outgoing.pop_front() {
                     progress = true;
                     let res = match msg {
-                        OutgoingMessage::Eth(msg) => this.conn.start_send_unpin(msg),
-                        OutgoingMessage::Broadcast(msg) => this.conn.start_send_broadcast(msg),
+                        OutgoingMessage::Eth(msg) => {
+                            this.stats.on_message_out(msg.message_id());
+                            this.conn.start_send_unpin(msg)
+                        }
+                        OutgoingMessage::Broadcast(msg) => {
+                            this.stats.on_message_out(msg.message_id());
+                            this.conn.start_send_broadcast(msg)
+                        }
                     };
                     if let Err(err) = res {
                         debug!(target: "net::session", ?err,  remote_peer_id=?this.remote_peer_id, "failed to send message");
                         // notify the manager
                         return this.close_on_error(err, cx)
                     }
+                } else if let Some((capability, msg)) = this.poll_next_protocol_message(cx) {
+                    progress = true;
+                    match this.conn.inner_mut().start_send_subprotocol(&capability, msg) {
+                        Ok(()) => {}
+                        Err(err @ P2PStreamError::MessageIdOutOfRange { .. }) => {
+                            // the local handler sent an invalid message, which is dropped
+                            debug!(target: "net::session", ?err, remote_peer_id=?this.remote_peer_id, "invalid sub-protocol message");
+                        }
+                        Err(err) => {
+                            debug!(target: "net::session", ?err, remote_peer_id=?this.remote_peer_id, "failed to send sub-protocol message");
+                            return this.close_on_error(err.into(), cx)
+                        }
+                    }
                 } else {
                     // no more messages to send over the wire
                     break
@@ -593,8 +681,22 @@ impl Future for ActiveSession {
                     }
                 }
 
+                // deliver sub-protocol messages read from the connection
+                if this.deliver_protocol_messages() {
+                    progress = true;
+                }
+
                 match this.conn.poll_next_unpin(cx) {
-                    Poll::Pending => break,
+                    Poll::Pending => {
+                        // the connection may have buffered sub-protocol messages while reading and
+                        // stops reading once its buffer is full, so we need to read again after
+                        // delivering them
+                        if this.deliver_protocol_messages() {
+                            progress = true;
+                            continue 'receive
+                        }
+                        break
+                    }
                     Poll::Ready(None) => {
                         if this.is_disconnecting() {
                             break
@@ -607,6 +709,7 @@ impl Future for ActiveSession {
                         match res {
                             Ok(msg) => {
                                 trace!(target: "net::session", msg_id=?msg.message_id(), remote_peer_id=?this.remote_peer_id, "received eth message");
+                                this.stats.on_message_in(msg.message_id());
                                 // decode and handle message
                                 match this.on_incoming_message(msg) {
                                     OnIncomingMessageOutcome::Ok => {
@@ -647,6 +750,7 @@ impl Future for ActiveSession {
             }
         }
 
+        this.stats.record_bandwidth();
         this.shrink_to_fit();
 
         Poll::Pending
@@ -848,8 +952,10 @@ mod tests {
                 remote_addr,
                 self.secret_key,
                 self.hello.clone(),
+                Vec::new(),
                 self.status,
                 self.fork_filter.clone(),
+                None,
             ));
 
             let mut stream = ReceiverStream::new(pending_sessions_rx);
@@ -893,6 +999,11 @@ mod tests {
                         )),
                         protocol_breach_request_timeout: PROTOCOL_BREACH_REQUEST_TIMEOUT,
                         terminate_message: None,
+                        protocol_sessions: Vec::new(),
+                        stats: Arc::new(ActiveSessionStats::new(
+                            self.bandwidth_meter.clone(),
+                            "reth",
+                        )),
                     }
                 }
                 ev => {
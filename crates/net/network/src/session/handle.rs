@@ -1,7 +1,7 @@
 //! Session handles
 use crate::{
     message::PeerMessage,
-    session::{Direction, SessionId},
+    session::{stats::ActiveSessionStats, Direction, SessionId},
 };
 use reth_ecies::{stream::ECIESStream, ECIESError};
 use reth_eth_wire::{
@@ -10,7 +10,7 @@ use reth_eth_wire::{
     DisconnectReason, EthStream, EthVersion, P2PStream, Status,
 };
 use reth_net_common::bandwidth_meter::MeteredStream;
-use reth_network_api::PeerInfo;
+use reth_network_api::{PeerInfo, SessionStats};
 use reth_primitives::PeerId;
 use std::{io, net::SocketAddr, sync::Arc, time::Instant};
 use tokio::{
@@ -77,6 +77,8 @@ pub struct ActiveSessionHandle {
     pub(crate) local_addr: Option<SocketAddr>,
     /// The Status message the peer sent for the `eth` handshake
     pub(crate) status: Status,
+    /// Traffic and request statistics recorded by the session.
+    pub(crate) stats: Arc<ActiveSessionStats>,
 }
 
 // === impl ActiveSessionHandle ===
@@ -137,6 +139,11 @@ impl ActiveSessionHandle {
         self.remote_addr
     }
 
+    /// Returns the traffic and request statistics of the session.
+    pub fn stats(&self) -> SessionStats {
+        self.stats.snapshot()
+    }
+
     /// Extracts the [PeerInfo] from the session handle.
     pub(crate) fn peer_info(&self) -> PeerInfo {
         PeerInfo {
@@ -148,6 +155,7 @@ impl ActiveSessionHandle {
             client_version: self.client_version.clone(),
             eth_version: self.version,
             status: self.status,
+            stats: self.stats.snapshot(),
         }
     }
 }
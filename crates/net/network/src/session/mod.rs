@@ -2,13 +2,15 @@
 use crate::{
     message::PeerMessage,
     metrics::SessionManagerMetrics,
-    session::{active::ActiveSession, config::SessionCounter},
+    protocol::{protocol_connection, ProtocolHandler, RlpxSubProtocols},
+    session::{active::ActiveSession, config::SessionCounter, stats::ActiveSessionStats},
 };
 use fnv::FnvHashMap;
 use futures::{future::Either, io, FutureExt, StreamExt};
 use reth_ecies::{stream::ECIESStream, ECIESError};
 use reth_eth_wire::{
-    capability::{Capabilities, CapabilityMessage},
+    capability::{Capabilities, CapabilityMessage, Protocol},
+    capture::{CaptureHeader, MessageCapture},
     errors::EthStreamError,
     DisconnectReason, EthVersion, HelloMessage, Status, UnauthedEthStream, UnauthedP2PStream,
 };
@@ -24,6 +26,7 @@ use std::{
     collections::HashMap,
     future::Future,
     net::SocketAddr,
+    path::PathBuf,
     sync::{atomic::AtomicU64, Arc},
     task::{Context, Poll},
     time::{Duration, Instant},
@@ -34,11 +37,12 @@ use tokio::{
     sync::{mpsc, oneshot},
 };
 use tokio_stream::wrappers::ReceiverStream;
-use tracing::{instrument, trace};
+use tracing::{debug, instrument, trace};
 
 mod active;
 mod config;
 mod handle;
+mod stats;
 pub use crate::message::PeerRequestSender;
 pub use config::{SessionLimits, SessionsConfig};
 pub use handle::{
@@ -65,12 +69,16 @@ pub struct SessionManager {
     /// If an [ActiveSession] does not receive a response at all within this duration then it is
     /// considered a protocol violation and the session will initiate a drop.
     protocol_breach_request_timeout: Duration,
+    /// The directory to capture the messages of each session to, if enabled.
+    capture_dir: Option<PathBuf>,
     /// The secret key used for authenticating sessions.
     secret_key: SecretKey,
     /// The `Status` message to send to peers.
     status: Status,
     /// THe `HelloMessage` message to send to peers.
     hello_message: HelloMessage,
+    /// Custom RLPx sub-protocols negotiated next to `eth`.
+    extra_protocols: RlpxSubProtocols,
     /// The [`ForkFilter`] used to validate the peer's `Status` message.
     fork_filter: ForkFilter,
     /// Size of the command buffer per session.
@@ -113,21 +121,30 @@ impl SessionManager {
         config: SessionsConfig,
         executor: Box<dyn TaskSpawner>,
         status: Status,
-        hello_message: HelloMessage,
+        mut hello_message: HelloMessage,
         fork_filter: ForkFilter,
         bandwidth_meter: BandwidthMeter,
+        extra_protocols: RlpxSubProtocols,
     ) -> Self {
         let (pending_sessions_tx, pending_sessions_rx) = mpsc::channel(config.session_event_buffer);
         let (active_session_tx, active_session_rx) = mpsc::channel(config.session_event_buffer);
 
+        for protocol in extra_protocols.protocols() {
+            if !hello_message.capabilities.contains(&protocol.cap) {
+                hello_message.capabilities.push(protocol.cap);
+            }
+        }
+
         Self {
             next_id: 0,
             counter: SessionCounter::new(config.limits),
             initial_internal_request_timeout: config.initial_internal_request_timeout,
             protocol_breach_request_timeout: config.protocol_breach_request_timeout,
+            capture_dir: config.capture_dir,
             secret_key,
             status,
             hello_message,
+            extra_protocols,
             fork_filter,
             session_command_buffer: config.session_command_buffer,
             executor,
@@ -165,6 +182,17 @@ impl SessionManager {
         self.hello_message.clone()
     }
 
+    /// Registers a handler for a custom RLPx sub-protocol.
+    ///
+    /// The protocol is announced in the `Hello` message of all new sessions.
+    pub(crate) fn add_rlpx_sub_protocol(&mut self, handler: impl ProtocolHandler) {
+        let cap = handler.protocol().cap;
+        self.extra_protocols.push(handler);
+        if !self.hello_message.capabilities.contains(&cap) {
+            self.hello_message.capabilities.push(cap);
+        }
+    }
+
     /// Spawns the given future onto a new task that is tracked in the `spawned_tasks`
     /// [`JoinSet`](tokio::task::JoinSet).
     fn spawn<F>(&self, f: F)
@@ -208,11 +236,13 @@ impl SessionManager {
 
         let (disconnect_tx, disconnect_rx) = oneshot::channel();
         let pending_events = self.pending_sessions_tx.clone();
-        let metered_stream = MeteredStream::new_with_meter(stream, self.bandwidth_meter.clone());
+        let metered_stream = MeteredStream::new_with_meter(stream, self.bandwidth_meter.child());
         let secret_key = self.secret_key;
         let hello_message = self.hello_message.clone();
+        let extra_protocols = self.extra_protocols.protocols();
         let status = self.status;
         let fork_filter = self.fork_filter.clone();
+        let capture_dir = self.capture_dir.clone();
         self.spawn(start_pending_incoming_session(
             disconnect_rx,
             session_id,
@@ -221,8 +251,10 @@ impl SessionManager {
             remote_addr,
             secret_key,
             hello_message,
+            extra_protocols,
             status,
             fork_filter,
+            capture_dir,
         ));
 
         let handle = PendingSessionHandle {
@@ -243,9 +275,11 @@ impl SessionManager {
             let pending_events = self.pending_sessions_tx.clone();
             let secret_key = self.secret_key;
             let hello_message = self.hello_message.clone();
+            let extra_protocols = self.extra_protocols.protocols();
             let fork_filter = self.fork_filter.clone();
             let status = self.status;
-            let band_with_meter = self.bandwidth_meter.clone();
+            let band_with_meter = self.bandwidth_meter.child();
+            let capture_dir = self.capture_dir.clone();
             self.spawn(start_pending_outbound_session(
                 disconnect_rx,
                 pending_events,
@@ -254,9 +288,11 @@ impl SessionManager {
                 remote_peer_id,
                 secret_key,
                 hello_message,
+                extra_protocols,
                 status,
                 fork_filter,
                 band_with_meter,
+                capture_dir,
             ));
 
             let handle = PendingSessionHandle {
@@ -439,6 +475,25 @@ impl SessionManager {
                 // negotiated version
                 let version = conn.version();
 
+                // the connection is metered by a meter of its own
+                let meter = conn.inner().inner().inner().get_bandwidth_meter().clone();
+                let stats = Arc::new(ActiveSessionStats::new(meter, &client_id));
+
+                // hand out the connections of all shared custom sub-protocols
+                let protocol_sessions = conn
+                    .inner()
+                    .shared_capabilities()
+                    .iter()
+                    .filter(|cap| !cap.is_eth())
+                    .filter_map(|cap| {
+                        let handler = self.extra_protocols.handler(&cap.capability())?;
+                        let (connection, session) =
+                            protocol_connection(peer_id, direction, cap.clone());
+                        handler.on_connection(connection);
+                        Some(session)
+                    })
+                    .collect();
+
                 let session = ActiveSession {
                     next_id: 0,
                     remote_peer_id: peer_id,
@@ -459,6 +514,8 @@ impl SessionManager {
                     internal_request_timeout: Arc::clone(&timeout),
                     protocol_breach_request_timeout: self.protocol_breach_request_timeout,
                     terminate_message: None,
+                    protocol_sessions,
+                    stats: Arc::clone(&stats),
                 };
 
                 self.spawn(session);
@@ -476,6 +533,7 @@ impl SessionManager {
                     client_version: Arc::clone(&client_version),
                     remote_addr,
                     local_addr,
+                    stats,
                 };
 
                 self.active_sessions.insert(peer_id, handle);
@@ -723,8 +781,10 @@ pub(crate) async fn start_pending_incoming_session(
     remote_addr: SocketAddr,
     secret_key: SecretKey,
     hello: HelloMessage,
+    extra_protocols: Vec<Protocol>,
     status: Status,
     fork_filter: ForkFilter,
+    capture_dir: Option<PathBuf>,
 ) {
     authenticate(
         disconnect_rx,
@@ -735,8 +795,10 @@ pub(crate) async fn start_pending_incoming_session(
         secret_key,
         Direction::Incoming,
         hello,
+        extra_protocols,
         status,
         fork_filter,
+        capture_dir,
     )
     .await
 }
@@ -752,9 +814,11 @@ async fn start_pending_outbound_session(
     remote_peer_id: PeerId,
     secret_key: SecretKey,
     hello: HelloMessage,
+    extra_protocols: Vec<Protocol>,
     status: Status,
     fork_filter: ForkFilter,
     bandwidth_meter: BandwidthMeter,
+    capture_dir: Option<PathBuf>,
 ) {
     let stream = match TcpStream::connect(remote_addr).await {
         Ok(stream) => MeteredStream::new_with_meter(stream, bandwidth_meter),
@@ -779,8 +843,10 @@ async fn start_pending_outbound_session(
         secret_key,
         Direction::Outgoing(remote_peer_id),
         hello,
+        extra_protocols,
         status,
         fork_filter,
+        capture_dir,
     )
     .await
 }
@@ -796,8 +862,10 @@ async fn authenticate(
     secret_key: SecretKey,
     direction: Direction,
     hello: HelloMessage,
+    extra_protocols: Vec<Protocol>,
     status: Status,
     fork_filter: ForkFilter,
+    capture_dir: Option<PathBuf>,
 ) {
     let local_addr = stream.inner().local_addr().ok();
     let stream = match get_eciess_stream(stream, secret_key, direction).await {
@@ -824,8 +892,10 @@ async fn authenticate(
         local_addr,
         direction,
         hello,
+        extra_protocols,
         status,
         fork_filter,
+        capture_dir,
     )
     .boxed();
 
@@ -872,21 +942,39 @@ async fn authenticate_stream(
     local_addr: Option<SocketAddr>,
     direction: Direction,
     hello: HelloMessage,
+    extra_protocols: Vec<Protocol>,
     status: Status,
     fork_filter: ForkFilter,
+    capture_dir: Option<PathBuf>,
 ) -> PendingSessionEvent {
     // conduct the p2p handshake and return the authenticated stream
-    let (p2p_stream, their_hello) = match stream.handshake(hello).await {
-        Ok(stream_res) => stream_res,
-        Err(err) => {
-            return PendingSessionEvent::Disconnected {
-                remote_addr,
-                session_id,
-                direction,
-                error: Some(err.into()),
+    let (mut p2p_stream, their_hello) =
+        match stream.handshake_with_protocols(hello, extra_protocols).await {
+            Ok(stream_res) => stream_res,
+            Err(err) => {
+                return PendingSessionEvent::Disconnected {
+                    remote_addr,
+                    session_id,
+                    direction,
+                    error: Some(err.into()),
+                }
+            }
+        };
+
+    // capture all messages after the hello handshake, including the status handshake
+    if let Some(capture_dir) = capture_dir {
+        let header = CaptureHeader::new(
+            their_hello.id,
+            their_hello.client_version.clone(),
+            p2p_stream.shared_capabilities(),
+        );
+        match MessageCapture::create(&capture_dir, &header) {
+            Ok(capture) => p2p_stream.set_capture(capture),
+            Err(err) => {
+                debug!(target : "net::session", ?err, ?capture_dir, "failed to create capture file")
             }
         }
-    };
+    }
 
     // if the hello handshake was successful we can try status handshake
     //
@@ -0,0 +1,198 @@
+//! Traffic and request statistics of active sessions.
+
+use crate::metrics::ClientSessionMetrics;
+use reth_eth_wire::EthMessageID;
+use reth_net_common::bandwidth_meter::BandwidthMeter;
+use reth_network_api::SessionStats;
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+/// Number of `eth` message ids, see [`EthMessageID`].
+const NUM_MESSAGE_IDS: usize = EthMessageID::Receipts as usize + 1;
+
+/// Statistics of an active session.
+///
+/// These are recorded by the [`ActiveSession`](super::ActiveSession) and shared with its
+/// [`ActiveSessionHandle`](super::ActiveSessionHandle), which reports them as part of the
+/// [`PeerInfo`](reth_network_api::PeerInfo). The traffic is also recorded as
+/// [`ClientSessionMetrics`], labelled by the name of the client the peer is running.
+#[derive(Debug)]
+pub(crate) struct ActiveSessionStats {
+    /// Meters the bytes sent and received over the connection of the session.
+    meter: BandwidthMeter,
+    /// Number of received messages, indexed by message id.
+    messages_in: [AtomicU64; NUM_MESSAGE_IDS],
+    /// Number of sent messages, indexed by message id.
+    messages_out: [AtomicU64; NUM_MESSAGE_IDS],
+    /// Number of responses to our requests.
+    responses: AtomicU64,
+    /// Number of our requests that timed out.
+    timed_out_requests: AtomicU64,
+    /// Sum of the latencies of all responses in microseconds.
+    total_request_latency: AtomicU64,
+    /// Highest latency of a response in microseconds.
+    max_request_latency: AtomicU64,
+    /// Number of received bytes that were already recorded as metrics.
+    recorded_bytes_in: AtomicU64,
+    /// Number of sent bytes that were already recorded as metrics.
+    recorded_bytes_out: AtomicU64,
+    /// Metrics of all sessions with peers running the same client.
+    metrics: ClientSessionMetrics,
+}
+
+// === impl ActiveSessionStats ===
+
+impl ActiveSessionStats {
+    /// Creates new statistics for the session whose connection is metered by the given meter.
+    ///
+    /// The metrics are labelled by the client name, which is the first part of the client version
+    /// the peer announced, for example `Geth` for `Geth/v1.12.0-stable/linux-amd64/go1.20.5`.
+    /// Clients that are not well known are labelled as `other`.
+    pub(crate) fn new(meter: BandwidthMeter, client_version: &str) -> Self {
+        let metrics = ClientSessionMetrics::new_with_labels(&[(
+            "client",
+            client_name(client_version).to_string(),
+        )]);
+        Self {
+            meter,
+            messages_in: Default::default(),
+            messages_out: Default::default(),
+            responses: Default::default(),
+            timed_out_requests: Default::default(),
+            total_request_latency: Default::default(),
+            max_request_latency: Default::default(),
+            recorded_bytes_in: Default::default(),
+            recorded_bytes_out: Default::default(),
+            metrics,
+        }
+    }
+
+    /// Records a message received from the peer.
+    pub(crate) fn on_message_in(&self, id: EthMessageID) {
+        self.messages_in[id as usize].fetch_add(1, Ordering::Relaxed);
+        self.metrics.messages_in.increment(1);
+    }
+
+    /// Records a message sent to the peer.
+    pub(crate) fn on_message_out(&self, id: EthMessageID) {
+        self.messages_out[id as usize].fetch_add(1, Ordering::Relaxed);
+        self.metrics.messages_out.increment(1);
+    }
+
+    /// Records a response to one of our requests that took the given time.
+    pub(crate) fn on_response(&self, latency: Duration) {
+        let micros = u64::try_from(latency.as_micros()).unwrap_or(u64::MAX);
+        self.responses.fetch_add(1, Ordering::Relaxed);
+        self.total_request_latency.fetch_add(micros, Ordering::Relaxed);
+        self.max_request_latency.fetch_max(micros, Ordering::Relaxed);
+        self.metrics.request_latency.record(latency);
+    }
+
+    /// Records a request the peer did not respond to in time.
+    pub(crate) fn on_request_timeout(&self) {
+        self.timed_out_requests.fetch_add(1, Ordering::Relaxed);
+        self.metrics.timed_out_requests.increment(1);
+    }
+
+    /// Records the bytes sent and received since the last call as metrics.
+    pub(crate) fn record_bandwidth(&self) {
+        let bytes_in = self.meter.total_inbound();
+        let recorded_in = self.recorded_bytes_in.swap(bytes_in, Ordering::Relaxed);
+        self.metrics.bytes_in.increment(bytes_in.saturating_sub(recorded_in));
+
+        let bytes_out = self.meter.total_outbound();
+        let recorded_out = self.recorded_bytes_out.swap(bytes_out, Ordering::Relaxed);
+        self.metrics.bytes_out.increment(bytes_out.saturating_sub(recorded_out));
+    }
+
+    /// Returns a snapshot of the statistics.
+    pub(crate) fn snapshot(&self) -> SessionStats {
+        let responses = self.responses.load(Ordering::Relaxed);
+        let total_request_latency = self.total_request_latency.load(Ordering::Relaxed);
+        SessionStats {
+            bytes_in: self.meter.total_inbound(),
+            bytes_out: self.meter.total_outbound(),
+            messages_in: message_counts(&self.messages_in),
+            messages_out: message_counts(&self.messages_out),
+            responses,
+            timed_out_requests: self.timed_out_requests.load(Ordering::Relaxed),
+            mean_request_latency: Duration::from_micros(
+                total_request_latency.checked_div(responses).unwrap_or_default(),
+            ),
+            max_request_latency: Duration::from_micros(
+                self.max_request_latency.load(Ordering::Relaxed),
+            ),
+        }
+    }
+}
+
+/// Returns the non-zero message counts with their message id.
+fn message_counts(counts: &[AtomicU64; NUM_MESSAGE_IDS]) -> Vec<(EthMessageID, u64)> {
+    counts
+        .iter()
+        .enumerate()
+        .filter_map(|(id, count)| {
+            let count = count.load(Ordering::Relaxed);
+            let id = EthMessageID::try_from(id).ok()?;
+            (count > 0).then_some((id, count))
+        })
+        .collect()
+}
+
+/// The clients the metrics are labelled by, all other clients are labelled as `other`.
+///
+/// This keeps the number of label values bounded, since the client version is chosen by the peer.
+const KNOWN_CLIENTS: &[&str] =
+    &["Geth", "Nethermind", "Erigon", "Besu", "reth", "Nimbus", "EthereumJS", "OpenEthereum"];
+
+/// Returns the name of the client from the announced client version, if it's one of the
+/// [`KNOWN_CLIENTS`], or `other`.
+fn client_name(client_version: &str) -> &'static str {
+    let name = client_version.split('/').next().unwrap_or_default().trim();
+    KNOWN_CLIENTS
+        .iter()
+        .find(|client| client.eq_ignore_ascii_case(name))
+        .copied()
+        .unwrap_or("other")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_client_name() {
+        assert_eq!(client_name("Geth/v1.12.0-stable/linux-amd64/go1.20.5"), "Geth");
+        assert_eq!(client_name("reth/v0.1.0"), "reth");
+        assert_eq!(client_name("erigon/v2.48.1/linux-amd64/go1.20.6"), "Erigon");
+        assert_eq!(client_name("my-custom-client/v1.0.0"), "other");
+        assert_eq!(client_name(""), "other");
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let stats = ActiveSessionStats::new(BandwidthMeter::default(), "reth/v0.1.0");
+        assert_eq!(stats.snapshot(), SessionStats::default());
+
+        stats.on_message_in(EthMessageID::BlockHeaders);
+        stats.on_message_in(EthMessageID::BlockHeaders);
+        stats.on_message_in(EthMessageID::Transactions);
+        stats.on_message_out(EthMessageID::GetBlockHeaders);
+        stats.on_response(Duration::from_millis(100));
+        stats.on_response(Duration::from_millis(300));
+        stats.on_request_timeout();
+
+        let snapshot = stats.snapshot();
+        assert_eq!(
+            snapshot.messages_in,
+            vec![(EthMessageID::Transactions, 1), (EthMessageID::BlockHeaders, 2)]
+        );
+        assert_eq!(snapshot.messages_out, vec![(EthMessageID::GetBlockHeaders, 1)]);
+        assert_eq!(snapshot.responses, 2);
+        assert_eq!(snapshot.timed_out_requests, 1);
+        assert_eq!(snapshot.mean_request_latency, Duration::from_millis(200));
+        assert_eq!(snapshot.max_request_latency, Duration::from_millis(300));
+    }
+}
@@ -0,0 +1,205 @@
+//! A client implementation that can download the state from the network using `snap/1`.
+
+use crate::peers::PeersHandle;
+use parking_lot::RwLock;
+use reth_eth_wire::{
+    AccountRange, ByteCodes, GetAccountRange, GetByteCodes, GetStorageRanges, GetTrieNodes,
+    SnapMessage, StorageRanges, TrieNodes,
+};
+use reth_interfaces::p2p::{
+    download::DownloadClient,
+    error::RequestError,
+    snap::client::{SnapClient, SnapFut},
+};
+use reth_network_api::ReputationChangeKind;
+use reth_primitives::{PeerId, WithPeerId};
+use std::{
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::sync::{mpsc, oneshot, Notify};
+
+/// Default timeout for a single `snap` request.
+pub const SNAP_REQUEST_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Front-end API for downloading the state from the peers that share `snap/1`.
+///
+/// The requests are sent to the peers in turns through the connections of the
+/// [SnapProtocolHandler](crate::snap_requests::SnapProtocolHandler), which match the responses by
+/// their request id. If there are no such peers, requests wait until one connects.
+#[derive(Debug, Clone)]
+pub struct SnapFetchClient {
+    /// The peers that share `snap/1`.
+    peers: SnapPeers,
+    /// The handle to the peers, to penalize peers for bad responses.
+    peers_handle: PeersHandle,
+    /// The id of the next request.
+    next_request_id: Arc<AtomicU64>,
+    /// The time to wait for a response.
+    timeout: Duration,
+}
+
+// === impl SnapFetchClient ===
+
+impl SnapFetchClient {
+    /// Create a new instance that sends requests to the given peers.
+    pub(crate) fn new(peers: SnapPeers, peers_handle: PeersHandle) -> Self {
+        Self {
+            peers,
+            peers_handle,
+            next_request_id: Default::default(),
+            timeout: SNAP_REQUEST_TIMEOUT,
+        }
+    }
+
+    /// Sets the time to wait for a response.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Returns the id for the next request.
+    fn next_request_id(&self) -> u64 {
+        self.next_request_id.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Sends the request to the next peer and waits for the response, which is mapped to the
+    /// expected type.
+    fn send_request<T: Send + Sync + 'static>(
+        &self,
+        request: SnapMessage,
+        response: fn(SnapMessage) -> Option<T>,
+    ) -> SnapFut<T> {
+        let peers = self.peers.clone();
+        let timeout = self.timeout;
+        Box::pin(async move {
+            let (peer_id, to_peer) = peers.next_peer().await;
+
+            let (tx, rx) = oneshot::channel();
+            to_peer
+                .send(SnapPeerRequest { request, response: tx })
+                .await
+                .map_err(|_| RequestError::ConnectionDropped)?;
+            let message = tokio::time::timeout(timeout, rx)
+                .await
+                .map_err(|_| RequestError::Timeout)?
+                .map_err(|_| RequestError::ConnectionDropped)?;
+
+            response(message)
+                .map(|response| WithPeerId::new(peer_id, response))
+                .ok_or(RequestError::BadResponse)
+        })
+    }
+}
+
+impl DownloadClient for SnapFetchClient {
+    fn report_bad_message(&self, peer_id: PeerId) {
+        self.peers_handle.reputation_change(peer_id, ReputationChangeKind::BadMessage);
+    }
+
+    fn num_connected_peers(&self) -> usize {
+        self.peers.len()
+    }
+}
+
+impl SnapClient for SnapFetchClient {
+    fn get_account_range(&self, mut request: GetAccountRange) -> SnapFut<AccountRange> {
+        request.request_id = self.next_request_id();
+        self.send_request(SnapMessage::GetAccountRange(request), |message| match message {
+            SnapMessage::AccountRange(response) => Some(response),
+            _ => None,
+        })
+    }
+
+    fn get_storage_ranges(&self, mut request: GetStorageRanges) -> SnapFut<StorageRanges> {
+        request.request_id = self.next_request_id();
+        self.send_request(SnapMessage::GetStorageRanges(request), |message| match message {
+            SnapMessage::StorageRanges(response) => Some(response),
+            _ => None,
+        })
+    }
+
+    fn get_byte_codes(&self, mut request: GetByteCodes) -> SnapFut<ByteCodes> {
+        request.request_id = self.next_request_id();
+        self.send_request(SnapMessage::GetByteCodes(request), |message| match message {
+            SnapMessage::ByteCodes(response) => Some(response),
+            _ => None,
+        })
+    }
+
+    fn get_trie_nodes(&self, mut request: GetTrieNodes) -> SnapFut<TrieNodes> {
+        request.request_id = self.next_request_id();
+        self.send_request(SnapMessage::GetTrieNodes(request), |message| match message {
+            SnapMessage::TrieNodes(response) => Some(response),
+            _ => None,
+        })
+    }
+}
+
+/// A request to send to a peer over its `snap/1` connection.
+#[derive(Debug)]
+pub(crate) struct SnapPeerRequest {
+    /// The request, with the id the response is matched by.
+    pub(crate) request: SnapMessage,
+    /// The sender for the response.
+    pub(crate) response: oneshot::Sender<SnapMessage>,
+}
+
+/// The peers with an active `snap/1` connection, shared between the connections and the
+/// [SnapFetchClient].
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SnapPeers {
+    inner: Arc<SnapPeersInner>,
+}
+
+#[derive(Debug, Default)]
+struct SnapPeersInner {
+    /// The channels to the connections of the peers.
+    peers: RwLock<Vec<(PeerId, mpsc::Sender<SnapPeerRequest>)>>,
+    /// The index of the peer to send the next request to.
+    next: AtomicUsize,
+    /// Notified when a peer is added.
+    added: Notify,
+}
+
+// === impl SnapPeers ===
+
+impl SnapPeers {
+    /// Adds the channel to the connection of the peer.
+    pub(crate) fn add(&self, peer_id: PeerId, to_connection: mpsc::Sender<SnapPeerRequest>) {
+        self.inner.peers.write().push((peer_id, to_connection));
+        self.inner.added.notify_waiters();
+    }
+
+    /// Removes the channel to the connection of the peer, if it's still the given one.
+    pub(crate) fn remove(&self, peer_id: PeerId, to_connection: &mpsc::Sender<SnapPeerRequest>) {
+        self.inner
+            .peers
+            .write()
+            .retain(|(id, tx)| *id != peer_id || !tx.same_channel(to_connection));
+    }
+
+    /// Returns the number of peers.
+    pub(crate) fn len(&self) -> usize {
+        self.inner.peers.read().len()
+    }
+
+    /// Returns the next peer in turn, waiting for one to connect if there are none.
+    async fn next_peer(&self) -> (PeerId, mpsc::Sender<SnapPeerRequest>) {
+        loop {
+            // registered before checking the peers, so that no addition is missed
+            let added = self.inner.added.notified();
+            {
+                let peers = self.inner.peers.read();
+                if !peers.is_empty() {
+                    let index = self.inner.next.fetch_add(1, Ordering::Relaxed) % peers.len();
+                    return peers[index].clone()
+                }
+            }
+            added.await;
+        }
+    }
+}
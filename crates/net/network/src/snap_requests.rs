@@ -0,0 +1,826 @@
+//! State range management for the p2p network.
+
+use crate::{
+    eth_requests::SOFT_RESPONSE_LIMIT,
+    metrics::SnapRequestHandlerMetrics,
+    peers::PeersHandle,
+    protocol::{ProtocolConnection, ProtocolHandler},
+    snap_client::{SnapPeerRequest, SnapPeers},
+    SnapFetchClient,
+};
+use futures::{stream::FuturesUnordered, FutureExt, StreamExt};
+use reth_db::{
+    cursor::{DbCursorRO, DbDupCursorRO},
+    database::Database,
+    tables,
+    transaction::DbTx,
+    DatabaseError,
+};
+use reth_eth_wire::{
+    capability::Protocol, AccountData, AccountRange, ByteCodes, GetAccountRange, GetByteCodes,
+    GetStorageRanges, GetTrieNodes, SnapAccount, SnapMessage, StorageData, StorageRanges,
+    TrieNodes,
+};
+use reth_interfaces::p2p::error::RequestResult;
+use reth_primitives::{
+    bytes::{self, BytesMut},
+    stage::StageId,
+    trie::Nibbles,
+    Bytes, PeerId, StorageEntry, H256, KECCAK_EMPTY,
+};
+use reth_provider::{HeaderProvider, ProviderFactory, StageCheckpointReader};
+use reth_rlp::Encodable;
+use reth_trie::{proof::Proof, ProofError, StorageRoot, StorageRootError};
+use std::{
+    collections::HashMap,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tokio::sync::{
+    mpsc::{self, Receiver, Sender},
+    oneshot,
+};
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::debug;
+
+// Limits: <https://github.com/ethereum/go-ethereum/blob/0d45d72d7093a4a6bc7b5ee6d28a1bbb4c8be2e8/eth/protocols/snap/handler.go#L34-L57>
+
+/// Maximum number of bytecodes to serve.
+///
+/// Used to limit lookups.
+const MAX_CODES_SERVE: usize = 1024;
+
+/// Maximum number of trie nodes to serve.
+///
+/// Used to limit lookups. Every node is rebuilt from the intermediate nodes in the database, so
+/// this is more expensive than a plain lookup.
+const MAX_TRIE_NODES_SERVE: usize = 1024;
+
+/// Manages `snap` requests on top of the p2p network.
+///
+/// The requests are answered from the hashed state and the trie tables, which are only available
+/// for the latest state. Requests for any other state root are answered with empty responses, as
+/// the protocol demands for unavailable states.
+///
+/// This can be spawned to another task and is supposed to be run as background service.
+#[must_use = "Manager does nothing unless polled."]
+pub struct SnapRequestHandler<DB> {
+    /// The factory for read-only database transactions.
+    factory: ProviderFactory<DB>,
+    /// Incoming requests from the sessions, delegated by the [SnapProtocolHandler].
+    incoming_requests: ReceiverStream<IncomingSnapRequest>,
+    /// Metrics for the snap request handler.
+    metrics: SnapRequestHandlerMetrics,
+}
+
+// === impl SnapRequestHandler ===
+impl<DB> SnapRequestHandler<DB> {
+    /// Create a new instance
+    pub fn new(factory: ProviderFactory<DB>, incoming: Receiver<IncomingSnapRequest>) -> Self {
+        let metrics = Default::default();
+        Self { factory, incoming_requests: ReceiverStream::new(incoming), metrics }
+    }
+}
+
+impl<DB> SnapRequestHandler<DB>
+where
+    DB: Database,
+{
+    /// Returns the accounts of the requested range, with the proofs for its boundaries.
+    fn get_account_range_response(
+        &self,
+        request: &GetAccountRange,
+    ) -> Result<AccountRange, SnapServeError> {
+        let mut response = AccountRange { request_id: request.request_id, ..Default::default() };
+
+        let provider = self.factory.provider()?;
+        if served_state_root(&provider)? != Some(request.root_hash) {
+            return Ok(response)
+        }
+        let tx = provider.tx_ref();
+
+        let limit = response_limit(request.response_bytes);
+        let mut total_bytes = 0;
+
+        let mut cursor = tx.cursor_read::<tables::HashedAccount>()?;
+        let mut entry = cursor.seek(request.starting_hash)?;
+        while let Some((hash, account)) = entry {
+            let storage_root = StorageRoot::new_hashed(tx, hash).root()?;
+            let account = AccountData {
+                hash,
+                body: SnapAccount {
+                    nonce: account.nonce,
+                    balance: account.balance,
+                    storage_root,
+                    code_hash: account.bytecode_hash.unwrap_or(KECCAK_EMPTY),
+                },
+            };
+
+            total_bytes += account.length();
+            response.accounts.push(account);
+
+            if hash >= request.limit_hash || total_bytes >= limit {
+                break
+            }
+
+            entry = cursor.next()?;
+        }
+
+        let mut targets = vec![Nibbles::unpack(request.starting_hash)];
+        targets.extend(response.accounts.last().map(|account| Nibbles::unpack(account.hash)));
+        response.proof = Proof::new(tx).account_multiproof(targets)?.into_values().collect();
+
+        Ok(response)
+    }
+
+    /// Returns the storage slots of the requested accounts.
+    ///
+    /// Only the last storage range can be partial, in which case the proofs for its boundaries
+    /// are attached.
+    fn get_storage_ranges_response(
+        &self,
+        request: &GetStorageRanges,
+    ) -> Result<StorageRanges, SnapServeError> {
+        let mut response = StorageRanges { request_id: request.request_id, ..Default::default() };
+
+        let provider = self.factory.provider()?;
+        if served_state_root(&provider)? != Some(request.root_hash) {
+            return Ok(response)
+        }
+        let tx = provider.tx_ref();
+
+        let limit = response_limit(request.response_bytes);
+        let mut total_bytes = 0;
+
+        let mut cursor = tx.cursor_dup_read::<tables::HashedStorage>()?;
+        for (idx, hashed_address) in request.account_hashes.iter().copied().enumerate() {
+            if total_bytes >= limit {
+                break
+            }
+
+            // The starting hash applies to the first account, the limit hash only if there's a
+            // single account.
+            let origin = if idx == 0 {
+                hash_from_bytes(&request.starting_hash, H256::zero())
+            } else {
+                H256::zero()
+            };
+            let limit_hash = if request.account_hashes.len() == 1 {
+                hash_from_bytes(&request.limit_hash, H256::repeat_byte(0xff))
+            } else {
+                H256::repeat_byte(0xff)
+            };
+
+            let mut slots = Vec::new();
+            let mut aborted = false;
+            let mut entry = cursor.seek_by_key_subkey(hashed_address, origin)?;
+            while let Some(StorageEntry { key, value }) = entry {
+                if total_bytes >= limit {
+                    aborted = true;
+                    break
+                }
+
+                let slot = StorageData {
+                    hash: key,
+                    data: reth_rlp::encode_fixed_size(&value).to_vec().into(),
+                };
+                total_bytes += slot.length();
+                slots.push(slot);
+
+                if key >= limit_hash {
+                    break
+                }
+
+                entry = cursor.next_dup_val()?;
+            }
+
+            // Nothing of the storage of this account was served, don't claim it's empty.
+            if aborted && slots.is_empty() {
+                break
+            }
+
+            // A range not starting at the beginning of the storage or cut short by the response
+            // size is partial, and has to be proven.
+            let last = slots.last().map(|slot| slot.hash);
+            response.slots.push(slots);
+            if !origin.is_zero() || aborted {
+                let targets = [Some(origin), last].into_iter().flatten().map(Nibbles::unpack);
+                response.proof = Proof::new(tx)
+                    .storage_multiproof(hashed_address, targets)?
+                    .into_values()
+                    .collect();
+                break
+            }
+        }
+
+        Ok(response)
+    }
+
+    /// Returns the requested bytecodes, skipping the unknown ones.
+    fn get_byte_codes_response(&self, request: &GetByteCodes) -> Result<ByteCodes, SnapServeError> {
+        let mut response = ByteCodes { request_id: request.request_id, ..Default::default() };
+
+        let provider = self.factory.provider()?;
+        let tx = provider.tx_ref();
+
+        let limit = response_limit(request.response_bytes);
+        let mut total_bytes = 0;
+
+        for hash in request.hashes.iter().copied().take(MAX_CODES_SERVE) {
+            let code = if hash == KECCAK_EMPTY {
+                Bytes::default()
+            } else if let Some(code) = tx.get::<tables::Bytecodes>(hash)? {
+                code.original_bytes().into()
+            } else {
+                continue
+            };
+
+            total_bytes += code.len();
+            response.codes.push(code);
+
+            if total_bytes >= limit {
+                break
+            }
+        }
+
+        Ok(response)
+    }
+
+    /// Returns the requested trie nodes in the order of their paths.
+    ///
+    /// Nodes that don't exist are served as empty byte strings, to keep the order of the
+    /// response.
+    fn get_trie_nodes_response(&self, request: &GetTrieNodes) -> Result<TrieNodes, SnapServeError> {
+        let mut response = TrieNodes { request_id: request.request_id, ..Default::default() };
+
+        let provider = self.factory.provider()?;
+        if served_state_root(&provider)? != Some(request.root_hash) {
+            return Ok(response)
+        }
+        let proof = Proof::new(provider.tx_ref());
+
+        let limit = response_limit(request.response_bytes);
+        let mut total_bytes = 0;
+
+        'sets: for pathset in &request.paths {
+            let (nodes, paths) = match pathset.as_slice() {
+                [] => continue,
+                // A node of the account trie, by its compact encoded path.
+                [path] => {
+                    let path = decode_compact_path(path);
+                    (proof.account_multiproof([path.clone()])?, vec![path])
+                }
+                // Nodes of the storage trie of the account with the given hash.
+                [account, paths @ ..] => {
+                    if account.len() != H256::len_bytes() {
+                        break
+                    }
+                    let paths: Vec<_> =
+                        paths.iter().map(|path| decode_compact_path(path)).collect();
+                    let nodes =
+                        proof.storage_multiproof(H256::from_slice(account), paths.clone())?;
+                    (nodes, paths)
+                }
+            };
+
+            for path in paths {
+                let node = nodes.get(&path).cloned().unwrap_or_default();
+                total_bytes += node.len();
+                response.nodes.push(node);
+
+                if total_bytes >= limit || response.nodes.len() >= MAX_TRIE_NODES_SERVE {
+                    break 'sets
+                }
+            }
+        }
+
+        Ok(response)
+    }
+
+    fn on_account_range_request(
+        &mut self,
+        _peer_id: PeerId,
+        request: GetAccountRange,
+        response: oneshot::Sender<RequestResult<AccountRange>>,
+    ) {
+        self.metrics.received_account_range_requests.increment(1);
+        let range = self.get_account_range_response(&request).unwrap_or_else(|err| {
+            debug!(target: "net::snap", ?err, "Failed to serve account range");
+            AccountRange { request_id: request.request_id, ..Default::default() }
+        });
+        let _ = response.send(Ok(range));
+    }
+
+    fn on_storage_ranges_request(
+        &mut self,
+        _peer_id: PeerId,
+        request: GetStorageRanges,
+        response: oneshot::Sender<RequestResult<StorageRanges>>,
+    ) {
+        self.metrics.received_storage_ranges_requests.increment(1);
+        let ranges = self.get_storage_ranges_response(&request).unwrap_or_else(|err| {
+            debug!(target: "net::snap", ?err, "Failed to serve storage ranges");
+            StorageRanges { request_id: request.request_id, ..Default::default() }
+        });
+        let _ = response.send(Ok(ranges));
+    }
+
+    fn on_byte_codes_request(
+        &mut self,
+        _peer_id: PeerId,
+        request: GetByteCodes,
+        response: oneshot::Sender<RequestResult<ByteCodes>>,
+    ) {
+        self.metrics.received_byte_codes_requests.increment(1);
+        let codes = self.get_byte_codes_response(&request).unwrap_or_else(|err| {
+            debug!(target: "net::snap", ?err, "Failed to serve bytecodes");
+            ByteCodes { request_id: request.request_id, ..Default::default() }
+        });
+        let _ = response.send(Ok(codes));
+    }
+
+    fn on_trie_nodes_request(
+        &mut self,
+        _peer_id: PeerId,
+        request: GetTrieNodes,
+        response: oneshot::Sender<RequestResult<TrieNodes>>,
+    ) {
+        self.metrics.received_trie_nodes_requests.increment(1);
+        let nodes = self.get_trie_nodes_response(&request).unwrap_or_else(|err| {
+            debug!(target: "net::snap", ?err, "Failed to serve trie nodes");
+            TrieNodes { request_id: request.request_id, ..Default::default() }
+        });
+        let _ = response.send(Ok(nodes));
+    }
+}
+
+/// An endless future.
+///
+/// This should be spawned or used as part of `tokio::select!`.
+impl<DB> Future for SnapRequestHandler<DB>
+where
+    DB: Database + Unpin,
+{
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        loop {
+            match this.incoming_requests.poll_next_unpin(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(None) => return Poll::Ready(()),
+                Poll::Ready(Some(incoming)) => match incoming {
+                    IncomingSnapRequest::GetAccountRange { peer_id, request, response } => {
+                        this.on_account_range_request(peer_id, request, response)
+                    }
+                    IncomingSnapRequest::GetStorageRanges { peer_id, request, response } => {
+                        this.on_storage_ranges_request(peer_id, request, response)
+                    }
+                    IncomingSnapRequest::GetByteCodes { peer_id, request, response } => {
+                        this.on_byte_codes_request(peer_id, request, response)
+                    }
+                    IncomingSnapRequest::GetTrieNodes { peer_id, request, response } => {
+                        this.on_trie_nodes_request(peer_id, request, response)
+                    }
+                },
+            }
+        }
+    }
+}
+
+/// The maximum number of requests of a single peer that are served at the same time.
+///
+/// No more messages are read from the connection while this many requests are being served, which
+/// pushes back on the peer without stalling the other protocols of the session.
+const MAX_CONCURRENT_SERVED_REQUESTS: usize = 4;
+
+/// The capacity of the channel of requests to send to a single peer.
+const SNAP_PEER_REQUEST_CHANNEL_CAPACITY: usize = 16;
+
+/// The [ProtocolHandler] of the `snap/1` sub-protocol.
+///
+/// Registering it announces `snap/1` in the `Hello` message. The requests of every session that
+/// shares the protocol are delegated to the [SnapRequestHandler], and the requests of the
+/// [SnapFetchClient] are sent over these sessions.
+#[derive(Debug)]
+pub struct SnapProtocolHandler {
+    /// Sender half of the channel of the [SnapRequestHandler].
+    to_request_handler: Sender<IncomingSnapRequest>,
+    /// The peers with an active `snap/1` connection.
+    peers: SnapPeers,
+}
+
+// === impl SnapProtocolHandler ===
+
+impl SnapProtocolHandler {
+    /// Create a new instance that delegates requests to the given channel.
+    pub fn new(to_request_handler: Sender<IncomingSnapRequest>) -> Self {
+        Self { to_request_handler, peers: Default::default() }
+    }
+
+    /// Returns a new [SnapFetchClient] that sends requests over the connections of this handler.
+    pub fn client(&self, peers_handle: PeersHandle) -> SnapFetchClient {
+        SnapFetchClient::new(self.peers.clone(), peers_handle)
+    }
+}
+
+impl ProtocolHandler for SnapProtocolHandler {
+    fn protocol(&self) -> Protocol {
+        Protocol::snap()
+    }
+
+    fn on_connection(&self, connection: ProtocolConnection) {
+        tokio::spawn(run_snap_connection(
+            connection,
+            self.to_request_handler.clone(),
+            self.peers.clone(),
+        ));
+    }
+}
+
+/// Drives the `snap/1` connection with a single peer.
+///
+/// The requests of the peer are answered by the [SnapRequestHandler], up to
+/// [MAX_CONCURRENT_SERVED_REQUESTS] at a time. The requests of the [SnapFetchClient] are sent to
+/// the peer, and the responses of the peer are matched with them by their request id.
+///
+/// The connection is dropped if the peer sends a message that can't be decoded, which keeps the
+/// session alive.
+async fn run_snap_connection(
+    mut connection: ProtocolConnection,
+    to_request_handler: Sender<IncomingSnapRequest>,
+    peers: SnapPeers,
+) {
+    let peer_id = connection.peer_id();
+    let (to_connection, mut outgoing) = mpsc::channel(SNAP_PEER_REQUEST_CHANNEL_CAPACITY);
+    peers.add(peer_id, to_connection.clone());
+
+    let mut served = FuturesUnordered::new();
+    let mut pending: HashMap<u64, oneshot::Sender<SnapMessage>> = HashMap::new();
+
+    loop {
+        let message = tokio::select! {
+            message = connection.next(), if served.len() < MAX_CONCURRENT_SERVED_REQUESTS => {
+                // the session was closed
+                let Some(message) = message else { break };
+                match SnapMessage::decode_message(&mut &message[..]) {
+                    Ok(message) => message,
+                    Err(err) => {
+                        debug!(target: "net::snap", ?peer_id, ?err, "Failed to decode snap message");
+                        break
+                    }
+                }
+            }
+            Some(response) = served.next(), if !served.is_empty() => {
+                // the request handler was dropped
+                let Some(response) = response else { break };
+                if connection.send(encode(&response)).await.is_err() {
+                    break
+                }
+                continue
+            }
+            Some(SnapPeerRequest { request, response }) = outgoing.recv() => {
+                pending.retain(|_, response| !response.is_closed());
+                pending.insert(request.request_id(), response);
+                if connection.send(encode(&request)).await.is_err() {
+                    break
+                }
+                continue
+            }
+        };
+
+        let to_request_handler = to_request_handler.clone();
+        match message {
+            SnapMessage::GetAccountRange(request) => served.push(
+                delegate(to_request_handler, |response| IncomingSnapRequest::GetAccountRange {
+                    peer_id,
+                    request,
+                    response,
+                })
+                .map(|response| response.map(SnapMessage::AccountRange))
+                .boxed(),
+            ),
+            SnapMessage::GetStorageRanges(request) => {
+                served.push(
+                    delegate(to_request_handler, |response| {
+                        IncomingSnapRequest::GetStorageRanges { peer_id, request, response }
+                    })
+                    .map(|response| response.map(SnapMessage::StorageRanges))
+                    .boxed(),
+                )
+            }
+            SnapMessage::GetByteCodes(request) => served.push(
+                delegate(to_request_handler, |response| IncomingSnapRequest::GetByteCodes {
+                    peer_id,
+                    request,
+                    response,
+                })
+                .map(|response| response.map(SnapMessage::ByteCodes))
+                .boxed(),
+            ),
+            SnapMessage::GetTrieNodes(request) => served.push(
+                delegate(to_request_handler, |response| IncomingSnapRequest::GetTrieNodes {
+                    peer_id,
+                    request,
+                    response,
+                })
+                .map(|response| response.map(SnapMessage::TrieNodes))
+                .boxed(),
+            ),
+            response => match pending.remove(&response.request_id()) {
+                Some(tx) => {
+                    let _ = tx.send(response);
+                }
+                None => {
+                    let id = response.message_id();
+                    debug!(target: "net::snap", ?peer_id, ?id, "Received unsolicited snap response");
+                }
+            },
+        }
+    }
+
+    peers.remove(peer_id, &to_connection);
+}
+
+/// Encodes the message with its message id.
+fn encode(message: &SnapMessage) -> bytes::Bytes {
+    let mut buf = BytesMut::with_capacity(message.length());
+    message.encode(&mut buf);
+    buf.freeze()
+}
+
+/// Sends a request to the [SnapRequestHandler] and waits for the response.
+///
+/// Returns `None` if the request handler was dropped.
+async fn delegate<T>(
+    to_request_handler: Sender<IncomingSnapRequest>,
+    request: impl FnOnce(oneshot::Sender<RequestResult<T>>) -> IncomingSnapRequest,
+) -> Option<T> {
+    let (tx, rx) = oneshot::channel();
+    to_request_handler.send(request(tx)).await.ok()?;
+    rx.await.ok()?.ok()
+}
+
+/// Errors that can occur while serving a `snap` request.
+///
+/// The request is answered with an empty response instead.
+#[derive(Debug, thiserror::Error)]
+enum SnapServeError {
+    #[error(transparent)]
+    Provider(#[from] reth_interfaces::Error),
+    #[error(transparent)]
+    Database(#[from] DatabaseError),
+    #[error(transparent)]
+    StorageRoot(#[from] StorageRootError),
+    #[error(transparent)]
+    Proof(#[from] ProofError),
+}
+
+/// Returns the state root of the state in the hashed state and trie tables.
+///
+/// The tables only match a state if the hashing and merkle stages are at the same block, which
+/// they aren't in the middle of a pipeline run. In that case, no state is served.
+fn served_state_root<P>(provider: &P) -> Result<Option<H256>, SnapServeError>
+where
+    P: StageCheckpointReader + HeaderProvider,
+{
+    let block_number =
+        provider.get_stage_checkpoint(StageId::MerkleExecute)?.unwrap_or_default().block_number;
+    for stage_id in [StageId::AccountHashing, StageId::StorageHashing] {
+        let checkpoint = provider.get_stage_checkpoint(stage_id)?.unwrap_or_default();
+        if checkpoint.block_number != block_number {
+            return Ok(None)
+        }
+    }
+    Ok(provider.header_by_number(block_number)?.map(|header| header.state_root))
+}
+
+/// Caps the requested response size at the [SOFT_RESPONSE_LIMIT].
+fn response_limit(response_bytes: u64) -> usize {
+    usize::try_from(response_bytes).unwrap_or(usize::MAX).min(SOFT_RESPONSE_LIMIT)
+}
+
+/// Converts a hash of a request that is allowed to be empty, like go-ethereum's `BytesToHash`.
+fn hash_from_bytes(bytes: &[u8], empty: H256) -> H256 {
+    if bytes.is_empty() {
+        return empty
+    }
+    let bytes = &bytes[bytes.len().saturating_sub(H256::len_bytes())..];
+    let mut hash = H256::zero();
+    hash.0[H256::len_bytes() - bytes.len()..].copy_from_slice(bytes);
+    hash
+}
+
+/// Decodes the compact (hex-prefix) encoding of the path of a trie node.
+fn decode_compact_path(path: &[u8]) -> Nibbles {
+    let nibbles = Nibbles::unpack(path);
+    match nibbles.first() {
+        // Odd number of nibbles, only the flag nibble is prepended.
+        Some(flag) if flag & 1 == 1 => nibbles.slice_from(1),
+        Some(_) => nibbles.slice_from(2),
+        None => nibbles,
+    }
+}
+
+/// All `snap` requests delegated by the network.
+#[derive(Debug)]
+#[allow(missing_docs)]
+pub enum IncomingSnapRequest {
+    /// Request a range of accounts of the state trie from the peer.
+    ///
+    /// The response should be sent through the channel.
+    GetAccountRange {
+        peer_id: PeerId,
+        request: GetAccountRange,
+        response: oneshot::Sender<RequestResult<AccountRange>>,
+    },
+    /// Request ranges of storage slots of accounts from the peer.
+    ///
+    /// The response should be sent through the channel.
+    GetStorageRanges {
+        peer_id: PeerId,
+        request: GetStorageRanges,
+        response: oneshot::Sender<RequestResult<StorageRanges>>,
+    },
+    /// Request bytecodes by their hashes from the peer.
+    ///
+    /// The response should be sent through the channel.
+    GetByteCodes {
+        peer_id: PeerId,
+        request: GetByteCodes,
+        response: oneshot::Sender<RequestResult<ByteCodes>>,
+    },
+    /// Request trie nodes by their paths from the peer.
+    ///
+    /// The response should be sent through the channel.
+    GetTrieNodes {
+        peer_id: PeerId,
+        request: GetTrieNodes,
+        response: oneshot::Sender<RequestResult<TrieNodes>>,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reth_db::{
+        cursor::DbCursorRW, test_utils::create_test_rw_db, transaction::DbTxMut, DatabaseEnv,
+    };
+    use reth_primitives::{keccak256, Account, Bytecode, Header, MAINNET, U256};
+    use reth_trie::{account::EthAccount, verify_range_proof, StateRoot};
+    use std::sync::Arc;
+    use tokio::sync::mpsc;
+
+    const ACCOUNTS: u64 = 100;
+    const SLOTS: u64 = 100;
+
+    /// Returns a handler serving the state at genesis, along with the state root, the hashed
+    /// address of the only contract and its code.
+    fn handler_with_state() -> (SnapRequestHandler<Arc<DatabaseEnv>>, H256, H256, Bytes) {
+        let factory = ProviderFactory::new(create_test_rw_db(), MAINNET.clone());
+        let provider = factory.provider_rw().unwrap();
+        let tx = provider.tx_ref();
+
+        let code = Bytes::from([0x60, 0x00, 0x60, 0x00, 0xf3]);
+        let code_hash = keccak256(&code);
+        tx.put::<tables::Bytecodes>(code_hash, Bytecode::new_raw(code.clone().into())).unwrap();
+
+        let contract = keccak256(H256::zero());
+        for i in 0..ACCOUNTS {
+            let hashed_address = keccak256(H256::from_low_u64_be(i));
+            let bytecode_hash = (hashed_address == contract).then_some(code_hash);
+            let account = Account { nonce: i, balance: U256::from(i), bytecode_hash };
+            tx.put::<tables::HashedAccount>(hashed_address, account).unwrap();
+        }
+
+        let mut cursor = tx.cursor_dup_write::<tables::HashedStorage>().unwrap();
+        for i in 0..SLOTS {
+            let entry =
+                StorageEntry { key: keccak256(H256::from_low_u64_be(i)), value: U256::from(i + 1) };
+            cursor.upsert(contract, entry).unwrap();
+        }
+        drop(cursor);
+
+        let (root, updates) = StateRoot::new(tx).root_with_updates().unwrap();
+        updates.flush(tx).unwrap();
+        tx.put::<tables::Headers>(0, Header { state_root: root, ..Default::default() }).unwrap();
+        provider.commit().unwrap();
+
+        let (_, rx) = mpsc::channel(1);
+        let handler = SnapRequestHandler::new(factory, rx);
+        (handler, root, contract, code)
+    }
+
+    #[test]
+    fn serves_account_range() {
+        let (handler, root, _, _) = handler_with_state();
+
+        let request = GetAccountRange {
+            request_id: 1,
+            root_hash: root,
+            starting_hash: H256::zero(),
+            limit_hash: H256::repeat_byte(0xff),
+            response_bytes: 1000,
+        };
+        let response = handler.get_account_range_response(&request).unwrap();
+        assert_eq!(response.request_id, 1);
+        assert!(!response.accounts.is_empty());
+        assert!(response.accounts.len() < ACCOUNTS as usize);
+
+        let leaves = response
+            .accounts
+            .iter()
+            .map(|AccountData { hash, body }| {
+                let mut rlp = Vec::new();
+                EthAccount::from(Account::from(*body))
+                    .with_storage_root(body.storage_root)
+                    .encode(&mut rlp);
+                (*hash, rlp)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(verify_range_proof(root, H256::zero(), &leaves, &response.proof), Ok(true));
+
+        // The state isn't available for any other root.
+        let request = GetAccountRange { root_hash: H256::random(), ..request };
+        let response = handler.get_account_range_response(&request).unwrap();
+        assert!(response.accounts.is_empty());
+        assert!(response.proof.is_empty());
+    }
+
+    #[test]
+    fn serves_storage_ranges() {
+        let (handler, root, contract, _) = handler_with_state();
+        let provider = handler.factory.provider().unwrap();
+        let storage_root = StorageRoot::new_hashed(provider.tx_ref(), contract).root().unwrap();
+
+        // A partial range has a proof.
+        let request = GetStorageRanges {
+            request_id: 1,
+            root_hash: root,
+            account_hashes: vec![contract],
+            starting_hash: Bytes::default(),
+            limit_hash: Bytes::default(),
+            response_bytes: 1000,
+        };
+        let response = handler.get_storage_ranges_response(&request).unwrap();
+        assert_eq!(response.slots.len(), 1);
+        assert!(response.slots[0].len() < SLOTS as usize);
+        let leaves =
+            response.slots[0].iter().map(|slot| (slot.hash, slot.data.clone())).collect::<Vec<_>>();
+        assert_eq!(
+            verify_range_proof(storage_root, H256::zero(), &leaves, &response.proof),
+            Ok(true)
+        );
+
+        // Complete ranges don't, and accounts without storage have empty ranges.
+        let request = GetStorageRanges {
+            account_hashes: vec![contract, keccak256(H256::from_low_u64_be(1))],
+            response_bytes: u64::MAX,
+            ..request
+        };
+        let response = handler.get_storage_ranges_response(&request).unwrap();
+        assert_eq!(response.slots.len(), 2);
+        assert_eq!(response.slots[0].len(), SLOTS as usize);
+        assert!(response.slots[1].is_empty());
+        assert!(response.proof.is_empty());
+    }
+
+    #[test]
+    fn serves_byte_codes() {
+        let (handler, _, _, code) = handler_with_state();
+
+        let request = GetByteCodes {
+            request_id: 1,
+            hashes: vec![keccak256(&code), H256::random(), KECCAK_EMPTY],
+            response_bytes: 1000,
+        };
+        let response = handler.get_byte_codes_response(&request).unwrap();
+        assert_eq!(response.codes, vec![code, Bytes::default()]);
+    }
+
+    #[test]
+    fn serves_trie_nodes() {
+        let (handler, root, contract, _) = handler_with_state();
+        let provider = handler.factory.provider().unwrap();
+        let storage_root = StorageRoot::new_hashed(provider.tx_ref(), contract).root().unwrap();
+
+        let root_path = Bytes::from(Nibbles::default().encode_path_leaf(false));
+        let missing_path = Bytes::from(Nibbles::unpack(H256::random()).encode_path_leaf(false));
+        let request = GetTrieNodes {
+            request_id: 1,
+            root_hash: root,
+            paths: vec![
+                vec![root_path.clone()],
+                vec![Bytes::from(contract.as_bytes()), root_path, missing_path],
+            ],
+            response_bytes: 100_000,
+        };
+        let response = handler.get_trie_nodes_response(&request).unwrap();
+        assert_eq!(response.nodes.len(), 3);
+        assert_eq!(keccak256(&response.nodes[0]), root);
+        assert_eq!(keccak256(&response.nodes[1]), storage_root);
+        assert!(response.nodes[2].is_empty());
+    }
+}
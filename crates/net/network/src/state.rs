@@ -277,6 +277,7 @@ where
         match kind {
             PeerKind::Basic => self.peers_manager.remove_peer(peer_id),
             PeerKind::Trusted => self.peers_manager.remove_peer_from_trusted_set(peer_id),
+            PeerKind::Reserved => self.peers_manager.remove_peer_from_reserved_set(peer_id),
         }
     }
 
@@ -215,6 +215,13 @@ where
                                 DisconnectReason::TooManyPeers,
                             );
                         }
+                        InboundConnectionError::ExceedsIpLimit => {
+                            trace!(target: "net", ?remote_addr, "Exceeded incoming connection limit for ip or subnet; disconnecting");
+                            self.sessions.disconnect_incoming_connection(
+                                stream,
+                                DisconnectReason::TooManyPeers,
+                            );
+                        }
                     }
                     return None
                 }
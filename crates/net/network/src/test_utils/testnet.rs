@@ -2,7 +2,8 @@
 
 use crate::{
     builder::ETH_REQUEST_CHANNEL_CAPACITY, error::NetworkError, eth_requests::EthRequestHandler,
-    NetworkConfig, NetworkConfigBuilder, NetworkEvent, NetworkHandle, NetworkManager,
+    protocol::ProtocolHandler, NetworkConfig, NetworkConfigBuilder, NetworkEvent, NetworkHandle,
+    NetworkManager,
 };
 use futures::{FutureExt, StreamExt};
 use pin_project::pin_project;
@@ -234,6 +235,11 @@ where
         self.network.handle().clone()
     }
 
+    /// Registers a handler for a custom RLPx sub-protocol on the peer's network.
+    pub fn add_rlpx_sub_protocol(&mut self, handler: impl ProtocolHandler) {
+        self.network.add_rlpx_sub_protocol(handler)
+    }
+
     /// Set a new request handler that's connected to the peer's network
     pub fn install_request_handler(&mut self) {
         let (tx, rx) = channel(ETH_REQUEST_CHANNEL_CAPACITY);
@@ -0,0 +1,532 @@
+//! Fetching of transactions that were announced by peers.
+
+use super::{
+    Peer, GET_POOLED_TRANSACTION_SOFT_LIMIT_NUM_HASHES,
+    GET_POOLED_TRANSACTION_SOFT_LIMIT_SIZE_BYTES,
+};
+use crate::message::PeerRequest;
+use futures::{stream::FuturesUnordered, Future, FutureExt, Stream, StreamExt};
+use reth_eth_wire::{GetPooledTransactions, PooledTransactions};
+use reth_interfaces::p2p::error::{RequestError, RequestResult};
+use reth_primitives::{PeerId, PooledTransactionsElement, TxHash, TxType};
+use reth_rlp::Encodable;
+use std::{
+    collections::{hash_map::Entry, HashMap, VecDeque},
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tokio::sync::{oneshot, oneshot::error::RecvError};
+
+/// Maximum number of announced transactions that are tracked until they are fetched.
+///
+/// Announcements of further transactions are ignored until fetches complete.
+const MAX_TRACKED_ANNOUNCEMENTS: usize = GET_POOLED_TRANSACTION_SOFT_LIMIT_NUM_HASHES * 128;
+
+/// Maximum difference in bytes between the announced and the delivered size of a transaction.
+///
+/// Clients differ in whether the announced size of a typed transaction includes the RLP header of
+/// the transaction envelope.
+const MAX_ANNOUNCED_SIZE_DEVIATION: usize = 8;
+
+/// Fetches the transactions announced via `NewPooledTransactionHashes` messages.
+///
+/// Every announced hash is requested from a single peer at a time, no matter how many peers
+/// announced it. At most one `GetPooledTransactions` request is in flight per peer, which includes
+/// as many hashes as fit into the response soft limit, based on the sizes announced by `eth/68`
+/// peers. If a peer fails to deliver a transaction, it is requested from the next peer that
+/// announced it.
+///
+/// Delivered transactions are validated against the type and size the peer announced.
+#[derive(Default)]
+pub(super) struct TransactionFetcher {
+    /// All currently active requests for pooled transactions.
+    inflight_requests: FuturesUnordered<GetPooledTxRequestFut>,
+    /// The hashes requested from each peer with an active request.
+    requests_by_peer: HashMap<PeerId, Vec<TxHash>>,
+    /// All announced transactions that were not fetched yet.
+    announced: HashMap<TxHash, AnnouncedTransaction>,
+    /// Announced transactions that are not requested from any peer, in the order they were
+    /// announced.
+    queued: VecDeque<TxHash>,
+}
+
+// === impl TransactionFetcher ===
+
+impl TransactionFetcher {
+    /// Returns the number of active requests.
+    pub(super) fn num_inflight_requests(&self) -> usize {
+        self.inflight_requests.len()
+    }
+
+    /// Tracks the transactions announced by the peer, which are fetched on the next
+    /// [`TransactionFetcher::schedule`].
+    ///
+    /// Returns the number of transactions that were ignored because too many transactions are
+    /// tracked.
+    pub(super) fn on_announcement(
+        &mut self,
+        peer_id: PeerId,
+        announced: impl IntoIterator<Item = (TxHash, Option<AnnouncedMetadata>)>,
+    ) -> usize {
+        let mut num_ignored = 0;
+        for (hash, metadata) in announced {
+            let is_full = self.announced.len() >= MAX_TRACKED_ANNOUNCEMENTS;
+            match self.announced.entry(hash) {
+                Entry::Occupied(mut entry) => {
+                    // the transaction is already being fetched, so the peer is only an alternative
+                    entry.get_mut().add_announcer(peer_id, metadata);
+                }
+                Entry::Vacant(entry) => {
+                    if is_full {
+                        num_ignored += 1;
+                        continue
+                    }
+                    entry.insert(AnnouncedTransaction {
+                        announcers: vec![(peer_id, metadata)],
+                        inflight: None,
+                    });
+                    self.queued.push_back(hash);
+                }
+            }
+        }
+        num_ignored
+    }
+
+    /// Requests the queued transactions from the peers that announced them.
+    ///
+    /// Each transaction is requested from the first idle peer that announced it, batched with the
+    /// other transactions requested from the peer. Transactions that were only announced by busy
+    /// peers stay queued, transactions that were only announced by disconnected peers are dropped.
+    ///
+    /// Returns the number of requests that could not be sent because the peer's channel is full.
+    pub(super) fn schedule(&mut self, peers: &HashMap<PeerId, Peer>) -> usize {
+        let Self { inflight_requests, requests_by_peer, announced, queued } = self;
+
+        let mut batches: HashMap<PeerId, RequestBatch> = HashMap::new();
+        let mut still_queued = VecDeque::new();
+        while let Some(hash) = queued.pop_front() {
+            let Some(tx) = announced.get_mut(&hash) else { continue };
+            tx.announcers.retain(|(peer_id, _)| peers.contains_key(peer_id));
+            if tx.announcers.is_empty() {
+                announced.remove(&hash);
+                continue
+            }
+
+            let next = tx.announcers.iter().position(|(peer_id, metadata)| {
+                !requests_by_peer.contains_key(peer_id) &&
+                    batches.get(peer_id).map_or(true, |batch| batch.has_room(*metadata))
+            });
+            match next {
+                Some(idx) => {
+                    let (peer_id, metadata) = tx.announcers.remove(idx);
+                    batches.entry(peer_id).or_default().push(hash, metadata);
+                    tx.inflight = Some((peer_id, metadata));
+                }
+                None => still_queued.push_back(hash),
+            }
+        }
+        *queued = still_queued;
+
+        let mut num_channel_full = 0;
+        for (peer_id, batch) in batches {
+            let (response, rx) = oneshot::channel();
+            let req = PeerRequest::GetPooledTransactions {
+                request: GetPooledTransactions(batch.hashes.clone()),
+                response,
+            };
+
+            if peers[&peer_id].request_tx.try_send(req).is_ok() {
+                inflight_requests.push(GetPooledTxRequestFut::new(peer_id, rx));
+                requests_by_peer.insert(peer_id, batch.hashes);
+            } else {
+                // peer channel is saturated, try again later
+                num_channel_full += 1;
+                for hash in batch.hashes {
+                    if let Some(tx) = announced.get_mut(&hash) {
+                        if let Some(announcer) = tx.inflight.take() {
+                            tx.announcers.insert(0, announcer);
+                        }
+                        queued.push_back(hash);
+                    }
+                }
+            }
+        }
+
+        num_channel_full
+    }
+
+    /// Handles the transactions the peer delivered in response to a request.
+    fn on_transactions_fetched(
+        &mut self,
+        peer_id: PeerId,
+        requested: Vec<TxHash>,
+        transactions: Vec<PooledTransactionsElement>,
+    ) -> FetchEvent {
+        let mut valid = Vec::with_capacity(transactions.len());
+        let mut num_invalid = 0;
+        for tx in transactions {
+            let metadata = match self.announced.get(tx.hash()).and_then(|tx| tx.inflight) {
+                Some((inflight_peer, metadata)) if inflight_peer == peer_id => metadata,
+                _ => {
+                    // the transaction was not requested from the peer
+                    num_invalid += 1;
+                    continue
+                }
+            };
+
+            if metadata.map_or(true, |metadata| metadata.matches(&tx)) {
+                self.announced.remove(tx.hash());
+                valid.push(tx);
+            } else {
+                // the peer announced a different transaction, so it is fetched from another peer
+                num_invalid += 1;
+            }
+        }
+
+        self.requeue(peer_id, requested);
+
+        FetchEvent::TransactionsFetched { peer_id, transactions: valid, num_invalid }
+    }
+
+    /// Queues the requested transactions that were not delivered by the peer again, so they are
+    /// requested from another peer that announced them.
+    fn requeue(&mut self, peer_id: PeerId, requested: Vec<TxHash>) {
+        for hash in requested {
+            let Some(tx) = self.announced.get_mut(&hash) else { continue };
+            if tx.inflight.map_or(false, |(inflight_peer, _)| inflight_peer == peer_id) {
+                tx.inflight = None;
+                self.queued.push_back(hash);
+            }
+        }
+    }
+}
+
+impl Stream for TransactionFetcher {
+    type Item = FetchEvent;
+
+    /// Advances all requests and yields the outcome of the next completed request.
+    ///
+    /// Note: this stream never ends.
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let Poll::Ready(Some(GetPooledTxResponse { peer_id, result })) =
+            this.inflight_requests.poll_next_unpin(cx)
+        else {
+            return Poll::Pending
+        };
+
+        let requested = this.requests_by_peer.remove(&peer_id).unwrap_or_default();
+        let event = match result {
+            Ok(Ok(PooledTransactions(transactions))) => {
+                this.on_transactions_fetched(peer_id, requested, transactions)
+            }
+            Ok(Err(error)) => {
+                this.requeue(peer_id, requested);
+                FetchEvent::FetchError { peer_id, error }
+            }
+            Err(_) => {
+                // request channel closed/dropped
+                this.requeue(peer_id, requested);
+                FetchEvent::FetchError { peer_id, error: RequestError::ChannelClosed }
+            }
+        };
+        Poll::Ready(Some(event))
+    }
+}
+
+/// The outcome of a request for announced transactions.
+#[derive(Debug)]
+pub(super) enum FetchEvent {
+    /// The peer delivered transactions.
+    TransactionsFetched {
+        /// The peer the transactions were requested from.
+        peer_id: PeerId,
+        /// The requested transactions that match what the peer announced.
+        transactions: Vec<PooledTransactionsElement>,
+        /// Number of delivered transactions that were not requested, or don't match the type or
+        /// size the peer announced.
+        num_invalid: usize,
+    },
+    /// The request failed.
+    FetchError {
+        /// The peer the transactions were requested from.
+        peer_id: PeerId,
+        /// The reason the request failed.
+        error: RequestError,
+    },
+}
+
+/// The type and size of a transaction announced by an `eth/68` peer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct AnnouncedMetadata {
+    /// The announced transaction type.
+    pub(super) tx_type: u8,
+    /// The announced size of the encoded transaction.
+    pub(super) size: usize,
+}
+
+impl AnnouncedMetadata {
+    /// Returns `true` if the delivered transaction matches the announced type and size.
+    fn matches(&self, tx: &PooledTransactionsElement) -> bool {
+        let tx_type = tx.tx_type();
+        if u8::from(tx_type) != self.tx_type {
+            return false
+        }
+        // clients differ in whether the announced size of a blob transaction includes its sidecar,
+        // so only the size of other transactions is validated
+        tx_type == TxType::EIP4844 ||
+            tx.length().abs_diff(self.size) <= MAX_ANNOUNCED_SIZE_DEVIATION
+    }
+}
+
+/// A transaction that was announced but not fetched yet.
+#[derive(Debug)]
+struct AnnouncedTransaction {
+    /// Peers that announced the transaction and were not asked for it yet, with the metadata
+    /// they announced.
+    announcers: Vec<(PeerId, Option<AnnouncedMetadata>)>,
+    /// The peer the transaction is currently requested from, with the metadata it announced.
+    inflight: Option<(PeerId, Option<AnnouncedMetadata>)>,
+}
+
+impl AnnouncedTransaction {
+    /// Adds the peer as an alternative to fetch the transaction from, if it's not already known.
+    fn add_announcer(&mut self, peer_id: PeerId, metadata: Option<AnnouncedMetadata>) {
+        let is_inflight =
+            self.inflight.map_or(false, |(inflight_peer, _)| inflight_peer == peer_id);
+        if !is_inflight && !self.announcers.iter().any(|(announcer, _)| *announcer == peer_id) {
+            self.announcers.push((peer_id, metadata));
+        }
+    }
+}
+
+/// The hashes of a `GetPooledTransactions` request that is about to be sent.
+#[derive(Debug, Default)]
+struct RequestBatch {
+    /// The requested hashes.
+    hashes: Vec<TxHash>,
+    /// The sum of the announced sizes of the requested transactions.
+    size: usize,
+}
+
+impl RequestBatch {
+    /// Returns `true` if the transaction with the given metadata fits into the batch.
+    ///
+    /// The size of transactions announced without metadata is unknown, these are only limited by
+    /// the number of hashes.
+    fn has_room(&self, metadata: Option<AnnouncedMetadata>) -> bool {
+        if self.hashes.len() >= GET_POOLED_TRANSACTION_SOFT_LIMIT_NUM_HASHES {
+            return false
+        }
+        let size = metadata.map_or(0, |metadata| metadata.size);
+        self.hashes.is_empty() || self.size + size <= GET_POOLED_TRANSACTION_SOFT_LIMIT_SIZE_BYTES
+    }
+
+    fn push(&mut self, hash: TxHash, metadata: Option<AnnouncedMetadata>) {
+        self.hashes.push(hash);
+        self.size += metadata.map_or(0, |metadata| metadata.size);
+    }
+}
+
+/// An inflight request for `PooledTransactions` from a peer
+struct GetPooledTxRequest {
+    peer_id: PeerId,
+    response: oneshot::Receiver<RequestResult<PooledTransactions>>,
+}
+
+struct GetPooledTxResponse {
+    peer_id: PeerId,
+    result: Result<RequestResult<PooledTransactions>, RecvError>,
+}
+
+#[must_use = "futures do nothing unless polled"]
+#[pin_project::pin_project]
+struct GetPooledTxRequestFut {
+    #[pin]
+    inner: Option<GetPooledTxRequest>,
+}
+
+impl GetPooledTxRequestFut {
+    fn new(
+        peer_id: PeerId,
+        response: oneshot::Receiver<RequestResult<PooledTransactions>>,
+    ) -> Self {
+        Self { inner: Some(GetPooledTxRequest { peer_id, response }) }
+    }
+}
+
+impl Future for GetPooledTxRequestFut {
+    type Output = GetPooledTxResponse;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut req = self.as_mut().project().inner.take().expect("polled after completion");
+        match req.response.poll_unpin(cx) {
+            Poll::Ready(result) => {
+                Poll::Ready(GetPooledTxResponse { peer_id: req.peer_id, result })
+            }
+            Poll::Pending => {
+                self.project().inner.set(Some(req));
+                Poll::Pending
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cache::LruCache, message::PeerRequestSender};
+    use reth_eth_wire::EthVersion;
+    use reth_interfaces::test_utils::generators::{self, random_signed_tx};
+    use std::{num::NonZeroUsize, sync::Arc};
+    use tokio::sync::mpsc;
+
+    fn new_peer(peer_id: PeerId) -> (Peer, mpsc::Receiver<PeerRequest>) {
+        let (tx, rx) = mpsc::channel(1);
+        let peer = Peer {
+            transactions: LruCache::new(NonZeroUsize::new(16).unwrap()),
+            request_tx: PeerRequestSender::new(peer_id, tx),
+            version: EthVersion::Eth68,
+            client_version: Arc::new(String::new()),
+        };
+        (peer, rx)
+    }
+
+    fn random_pooled_tx() -> PooledTransactionsElement {
+        let mut rng = generators::rng();
+        PooledTransactionsElement::try_from_broadcast(random_signed_tx(&mut rng)).unwrap()
+    }
+
+    fn metadata_of(tx: &PooledTransactionsElement) -> Option<AnnouncedMetadata> {
+        Some(AnnouncedMetadata { tx_type: tx.tx_type().into(), size: tx.length() })
+    }
+
+    /// Returns the hashes of the request that was sent to the peer, and the response channel.
+    fn recv_request(
+        rx: &mut mpsc::Receiver<PeerRequest>,
+    ) -> (Vec<TxHash>, oneshot::Sender<RequestResult<PooledTransactions>>) {
+        match rx.try_recv().expect("request was sent") {
+            PeerRequest::GetPooledTransactions { request, response } => (request.0, response),
+            req => panic!("unexpected request {req:?}"),
+        }
+    }
+
+    #[test]
+    fn test_request_hash_once() {
+        let (peer_a, peer_b) = (PeerId::random(), PeerId::random());
+        let (a, mut rx_a) = new_peer(peer_a);
+        let (b, mut rx_b) = new_peer(peer_b);
+        let peers = HashMap::from([(peer_a, a), (peer_b, b)]);
+
+        let hash = TxHash::random();
+        let mut fetcher = TransactionFetcher::default();
+        assert_eq!(fetcher.on_announcement(peer_a, [(hash, None)]), 0);
+        assert_eq!(fetcher.on_announcement(peer_b, [(hash, None)]), 0);
+        assert_eq!(fetcher.schedule(&peers), 0);
+
+        assert_eq!(fetcher.num_inflight_requests(), 1);
+        let (hashes, _response) = recv_request(&mut rx_a);
+        assert_eq!(hashes, vec![hash]);
+        assert!(rx_b.try_recv().is_err());
+
+        // announcing it again while it is requested doesn't trigger another request
+        fetcher.on_announcement(peer_b, [(hash, None)]);
+        fetcher.schedule(&peers);
+        assert!(rx_b.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_batch_by_announced_size() {
+        let peer_id = PeerId::random();
+        let (peer, mut rx) = new_peer(peer_id);
+        let peers = HashMap::from([(peer_id, peer)]);
+
+        let size = GET_POOLED_TRANSACTION_SOFT_LIMIT_SIZE_BYTES / 2;
+        let announced = (0..3)
+            .map(|_| (TxHash::random(), Some(AnnouncedMetadata { tx_type: 2, size })))
+            .collect::<Vec<_>>();
+
+        let mut fetcher = TransactionFetcher::default();
+        fetcher.on_announcement(peer_id, announced.clone());
+        fetcher.schedule(&peers);
+
+        let (hashes, _response) = recv_request(&mut rx);
+        assert_eq!(hashes, vec![announced[0].0, announced[1].0]);
+        assert_eq!(fetcher.queued, VecDeque::from([announced[2].0]));
+    }
+
+    #[tokio::test]
+    async fn test_request_from_alternate_announcer() {
+        let (peer_a, peer_b) = (PeerId::random(), PeerId::random());
+        let (a, mut rx_a) = new_peer(peer_a);
+        let (b, mut rx_b) = new_peer(peer_b);
+        let peers = HashMap::from([(peer_a, a), (peer_b, b)]);
+
+        let tx = random_pooled_tx();
+        let mut fetcher = TransactionFetcher::default();
+        fetcher.on_announcement(peer_a, [(*tx.hash(), None)]);
+        fetcher.on_announcement(peer_b, [(*tx.hash(), None)]);
+        fetcher.schedule(&peers);
+
+        let (_, response) = recv_request(&mut rx_a);
+        response.send(Err(RequestError::Timeout)).unwrap();
+        match fetcher.next().await.unwrap() {
+            FetchEvent::FetchError { peer_id, error } => {
+                assert_eq!(peer_id, peer_a);
+                assert_eq!(error, RequestError::Timeout);
+            }
+            ev => panic!("unexpected event {ev:?}"),
+        }
+
+        fetcher.schedule(&peers);
+        let (hashes, response) = recv_request(&mut rx_b);
+        assert_eq!(hashes, vec![*tx.hash()]);
+        response.send(Ok(PooledTransactions(vec![tx.clone()]))).unwrap();
+        match fetcher.next().await.unwrap() {
+            FetchEvent::TransactionsFetched { peer_id, transactions, num_invalid } => {
+                assert_eq!(peer_id, peer_b);
+                assert_eq!(transactions, vec![tx]);
+                assert_eq!(num_invalid, 0);
+            }
+            ev => panic!("unexpected event {ev:?}"),
+        }
+        assert!(fetcher.announced.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_validate_announced_metadata() {
+        let (peer_a, peer_b) = (PeerId::random(), PeerId::random());
+        let (a, mut rx_a) = new_peer(peer_a);
+        let (b, mut rx_b) = new_peer(peer_b);
+        let peers = HashMap::from([(peer_a, a), (peer_b, b)]);
+
+        let tx = random_pooled_tx();
+        let wrong_type = Some(AnnouncedMetadata { tx_type: 2, size: tx.length() });
+        let mut fetcher = TransactionFetcher::default();
+        fetcher.on_announcement(peer_a, [(*tx.hash(), wrong_type)]);
+        fetcher.on_announcement(peer_b, [(*tx.hash(), metadata_of(&tx))]);
+        fetcher.schedule(&peers);
+
+        let (_, response) = recv_request(&mut rx_a);
+        response.send(Ok(PooledTransactions(vec![tx.clone()]))).unwrap();
+        match fetcher.next().await.unwrap() {
+            FetchEvent::TransactionsFetched { transactions, num_invalid, .. } => {
+                assert!(transactions.is_empty());
+                assert_eq!(num_invalid, 1);
+            }
+            ev => panic!("unexpected event {ev:?}"),
+        }
+
+        // the transaction is requested from the peer that announced the correct metadata
+        fetcher.schedule(&peers);
+        let (_, response) = recv_request(&mut rx_b);
+        response.send(Ok(PooledTransactions(vec![tx.clone()]))).unwrap();
+        match fetcher.next().await.unwrap() {
+            FetchEvent::TransactionsFetched { transactions, num_invalid, .. } => {
+                assert_eq!(transactions, vec![tx]);
+                assert_eq!(num_invalid, 0);
+            }
+            ev => panic!("unexpected event {ev:?}"),
+        }
+    }
+}
@@ -3,11 +3,11 @@
 use crate::{
     cache::LruCache,
     manager::NetworkEvent,
-    message::{PeerRequest, PeerRequestSender},
+    message::PeerRequestSender,
     metrics::{TransactionsManagerMetrics, NETWORK_POOL_TRANSACTIONS_SCOPE},
     NetworkHandle,
 };
-use futures::{stream::FuturesUnordered, Future, FutureExt, StreamExt};
+use futures::{stream::FuturesUnordered, Future, StreamExt};
 use reth_eth_wire::{
     EthVersion, GetPooledTransactions, NewPooledTransactionHashes, NewPooledTransactionHashes66,
     NewPooledTransactionHashes68, PooledTransactions, Transactions,
@@ -27,16 +27,19 @@ use reth_transaction_pool::{
     PropagatedTransactions, TransactionPool, ValidPoolTransaction,
 };
 use std::{
-    collections::{hash_map::Entry, HashMap},
+    collections::{hash_map::Entry, HashMap, HashSet},
     num::NonZeroUsize,
     pin::Pin,
     sync::Arc,
     task::{Context, Poll},
 };
-use tokio::sync::{mpsc, oneshot, oneshot::error::RecvError};
+use tokio::sync::{mpsc, oneshot};
 use tokio_stream::wrappers::{ReceiverStream, UnboundedReceiverStream};
 use tracing::{debug, trace};
 
+mod fetcher;
+use fetcher::{AnnouncedMetadata, FetchEvent, TransactionFetcher};
+
 /// Cache limit of transactions to keep track of for a single peer.
 const PEER_TRANSACTION_CACHE_LIMIT: usize = 1024 * 10;
 
@@ -52,8 +55,12 @@ const MAX_FULL_TRANSACTIONS_PACKET_SIZE: usize = 100 * 1024;
 const GET_POOLED_TRANSACTION_SOFT_LIMIT_NUM_HASHES: usize = 256;
 
 /// Softlimit for the response size of a GetPooledTransactions message (2MB)
+const GET_POOLED_TRANSACTION_SOFT_LIMIT_SIZE_BYTES: usize = 2 * 1024 * 1024;
+
+/// Softlimit for the response size of a GetPooledTransactions message, see
+/// [`GET_POOLED_TRANSACTION_SOFT_LIMIT_SIZE_BYTES`]
 const GET_POOLED_TRANSACTION_SOFT_LIMIT_SIZE: GetPooledTransactionLimit =
-    GetPooledTransactionLimit::SizeSoftLimit(2 * 1024 * 1024);
+    GetPooledTransactionLimit::SizeSoftLimit(GET_POOLED_TRANSACTION_SOFT_LIMIT_SIZE_BYTES);
 
 /// The future for inserting a function into the pool
 pub type PoolImportFuture = Pin<Box<dyn Future<Output = PoolResult<TxHash>> + Send + 'static>>;
@@ -103,8 +110,8 @@ pub struct TransactionsManager<Pool> {
     ///
     /// From which we get all new incoming transaction related messages.
     network_events: UnboundedReceiverStream<NetworkEvent>,
-    /// All currently active requests for pooled transactions.
-    inflight_requests: FuturesUnordered<GetPooledTxRequestFut>,
+    /// Fetches the transactions announced by peers.
+    transaction_fetcher: TransactionFetcher,
     /// All currently pending transactions grouped by peers.
     ///
     /// This way we can track incoming transactions and prevent multiple pool imports for the same
@@ -146,7 +153,7 @@ impl<Pool: TransactionPool> TransactionsManager<Pool> {
             pool,
             network,
             network_events,
-            inflight_requests: Default::default(),
+            transaction_fetcher: Default::default(),
             transactions_by_peers: Default::default(),
             pool_imports: Default::default(),
             peers: Default::default(),
@@ -180,7 +187,9 @@ where
 
     #[inline]
     fn update_request_metrics(&self) {
-        self.metrics.inflight_transaction_requests.set(self.inflight_requests.len() as f64);
+        self.metrics
+            .inflight_transaction_requests
+            .set(self.transaction_fetcher.num_inflight_requests() as f64);
     }
 
     /// Request handler for an incoming request for transactions
@@ -312,6 +321,9 @@ where
     }
 
     /// Request handler for an incoming `NewPooledTransactionHashes`
+    ///
+    /// The announced transactions that are unknown to the pool are handed to the
+    /// [`TransactionFetcher`], which requests each of them from one of the peers that announced it.
     fn on_new_pooled_transaction_hashes(
         &mut self,
         peer_id: PeerId,
@@ -322,47 +334,59 @@ where
             return
         }
 
+        let mut announced = match msg {
+            NewPooledTransactionHashes::Eth66(msg) => {
+                msg.0.into_iter().map(|hash| (hash, None)).collect::<Vec<_>>()
+            }
+            NewPooledTransactionHashes::Eth68(msg) => {
+                // every hash must be announced with the type and size of the transaction
+                if msg.types.len() != msg.hashes.len() || msg.sizes.len() != msg.hashes.len() {
+                    self.report_peer(peer_id, ReputationChangeKind::BadMessage);
+                    return
+                }
+                msg.hashes
+                    .into_iter()
+                    .zip(msg.types)
+                    .zip(msg.sizes)
+                    .map(|((hash, tx_type), size)| {
+                        (hash, Some(AnnouncedMetadata { tx_type, size }))
+                    })
+                    .collect()
+            }
+        };
+
         let mut num_already_seen = 0;
 
         if let Some(peer) = self.peers.get_mut(&peer_id) {
-            let mut hashes = msg.into_hashes();
             // keep track of the transactions the peer knows
-            for tx in hashes.iter().copied() {
-                if !peer.transactions.insert(tx) {
+            for (hash, _) in announced.iter() {
+                if !peer.transactions.insert(*hash) {
                     num_already_seen += 1;
                 }
             }
 
-            self.pool.retain_unknown(&mut hashes);
-
-            if hashes.is_empty() {
-                // nothing to request
-                return
-            }
-
-            // enforce recommended soft limit, however the peer may enforce an arbitrary limit on
-            // the response (2MB)
-            hashes.truncate(GET_POOLED_TRANSACTION_SOFT_LIMIT_NUM_HASHES);
-
-            // request the missing transactions
-            let (response, rx) = oneshot::channel();
-            let req = PeerRequest::GetPooledTransactions {
-                request: GetPooledTransactions(hashes),
-                response,
-            };
-
-            if peer.request_tx.try_send(req).is_ok() {
-                self.inflight_requests.push(GetPooledTxRequestFut::new(peer_id, rx))
-            } else {
-                // peer channel is saturated, drop the request
-                self.metrics.egress_peer_channel_full.increment(1);
-                return
-            }
-
             if num_already_seen > 0 {
                 self.metrics.messages_with_already_seen_hashes.increment(1);
                 debug!(target: "net::tx", num_hashes=%num_already_seen, ?peer_id, client=?peer.client_version, "Peer sent already seen hashes");
             }
+        } else {
+            return
+        }
+
+        // only fetch transactions that are neither in the pool nor about to be imported
+        let mut hashes: Vec<_> = announced.iter().map(|(hash, _)| *hash).collect();
+        self.pool.retain_unknown(&mut hashes);
+        let unknown = hashes.into_iter().collect::<HashSet<_>>();
+        announced.retain(|(hash, _)| {
+            unknown.contains(hash) && !self.transactions_by_peers.contains_key(hash)
+        });
+
+        if !announced.is_empty() {
+            let num_ignored = self.transaction_fetcher.on_announcement(peer_id, announced);
+            if num_ignored > 0 {
+                self.metrics.ignored_announced_transactions.increment(num_ignored as u64);
+            }
+            self.request_announced_transactions();
         }
 
         if num_already_seen > 0 {
@@ -370,6 +394,27 @@ where
         }
     }
 
+    /// Requests the transactions queued in the [`TransactionFetcher`] from idle peers.
+    fn request_announced_transactions(&mut self) {
+        let num_channel_full = self.transaction_fetcher.schedule(&self.peers);
+        if num_channel_full > 0 {
+            self.metrics.egress_peer_channel_full.increment(num_channel_full as u64);
+        }
+    }
+
+    /// Handles the outcome of a request for announced transactions.
+    fn on_fetch_event(&mut self, event: FetchEvent) {
+        match event {
+            FetchEvent::TransactionsFetched { peer_id, transactions, num_invalid } => {
+                self.import_transactions(peer_id, transactions, TransactionSource::Response);
+                if num_invalid > 0 {
+                    self.report_peer(peer_id, ReputationChangeKind::BadTransactions);
+                }
+            }
+            FetchEvent::FetchError { peer_id, error } => self.on_request_error(peer_id, error),
+        }
+    }
+
     /// Handles dedicated transaction events related to the `eth` protocol.
     fn on_network_tx_event(&mut self, event: NetworkTransactionEvent) {
         match event {
@@ -594,21 +639,16 @@ where
         this.update_request_metrics();
 
         // Advance all requests.
-        while let Poll::Ready(Some(GetPooledTxResponse { peer_id, result })) =
-            this.inflight_requests.poll_next_unpin(cx)
-        {
-            match result {
-                Ok(Ok(txs)) => {
-                    this.import_transactions(peer_id, txs.0, TransactionSource::Response)
-                }
-                Ok(Err(req_err)) => {
-                    this.on_request_error(peer_id, req_err);
-                }
-                Err(_) => {
-                    // request channel closed/dropped
-                    this.on_request_error(peer_id, RequestError::ChannelClosed)
-                }
-            }
+        let mut has_fetch_events = false;
+        while let Poll::Ready(Some(event)) = this.transaction_fetcher.poll_next_unpin(cx) {
+            this.on_fetch_event(event);
+            has_fetch_events = true;
+        }
+
+        // request the transactions that were not delivered from alternative peers, and the queued
+        // transactions from the peers that became idle
+        if has_fetch_events {
+            this.request_announced_transactions();
         }
 
         this.update_request_metrics();
@@ -757,7 +797,7 @@ impl PooledTransactionsHashesBuilder {
 enum TransactionSource {
     /// Transactions were broadcast to us via [`Transactions`] message.
     Broadcast,
-    /// Transactions were sent as the response of [`GetPooledTxRequest`] issued by us.
+    /// Transactions were sent as the response of a [`GetPooledTransactions`] request issued by us.
     Response,
 }
 
@@ -770,50 +810,6 @@ impl TransactionSource {
     }
 }
 
-/// An inflight request for `PooledTransactions` from a peer
-struct GetPooledTxRequest {
-    peer_id: PeerId,
-    response: oneshot::Receiver<RequestResult<PooledTransactions>>,
-}
-
-struct GetPooledTxResponse {
-    peer_id: PeerId,
-    result: Result<RequestResult<PooledTransactions>, RecvError>,
-}
-
-#[must_use = "futures do nothing unless polled"]
-#[pin_project::pin_project]
-struct GetPooledTxRequestFut {
-    #[pin]
-    inner: Option<GetPooledTxRequest>,
-}
-
-impl GetPooledTxRequestFut {
-    fn new(
-        peer_id: PeerId,
-        response: oneshot::Receiver<RequestResult<PooledTransactions>>,
-    ) -> Self {
-        Self { inner: Some(GetPooledTxRequest { peer_id, response }) }
-    }
-}
-
-impl Future for GetPooledTxRequestFut {
-    type Output = GetPooledTxResponse;
-
-    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        let mut req = self.as_mut().project().inner.take().expect("polled after completion");
-        match req.response.poll_unpin(cx) {
-            Poll::Ready(result) => {
-                Poll::Ready(GetPooledTxResponse { peer_id: req.peer_id, result })
-            }
-            Poll::Pending => {
-                self.project().inner.set(Some(req));
-                Poll::Pending
-            }
-        }
-    }
-}
-
 /// Tracks a single peer
 struct Peer {
     /// Keeps track of transactions that we know the peer has seen.
@@ -854,6 +850,7 @@ pub enum NetworkTransactionEvent {
 mod tests {
     use super::*;
     use crate::{test_utils::Testnet, NetworkConfigBuilder, NetworkManager};
+    use futures::FutureExt;
     use reth_interfaces::sync::{NetworkSyncUpdater, SyncState};
     use reth_network_api::NetworkInfo;
     use reth_provider::test_utils::NoopProvider;
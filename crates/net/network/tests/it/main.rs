@@ -1,8 +1,10 @@
 mod clique;
 mod connect;
 mod geth;
+mod multiplex;
 mod requests;
 mod session;
+mod snap;
 mod startup;
 
 fn main() {}
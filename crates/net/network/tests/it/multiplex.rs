@@ -0,0 +1,192 @@
+//! Custom RLPx sub-protocol tests
+
+use futures::StreamExt;
+use reth_eth_wire::capability::{Capability, Protocol};
+use reth_interfaces::p2p::headers::client::{HeadersClient, HeadersRequest};
+use reth_network::{
+    protocol::{ProtocolConnection, ProtocolHandler, PROTOCOL_CONNECTION_CHANNEL_CAPACITY},
+    test_utils::{NetworkEventStream, Testnet},
+    NetworkEvent,
+};
+use reth_network_api::{NetworkInfo, Peers, PeersInfo};
+use reth_primitives::{
+    bytes::{BufMut, Bytes, BytesMut},
+    Header, HeadersDirection, H256,
+};
+use reth_provider::test_utils::MockEthProvider;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Message ids of the `ourproto/1` test protocol.
+const PING: u8 = 0x00;
+const PONG: u8 = 0x01;
+
+/// Forwards all connections of `ourproto/1` to the test.
+#[derive(Debug)]
+struct OurProtoHandler {
+    connections: mpsc::UnboundedSender<ProtocolConnection>,
+}
+
+impl OurProtoHandler {
+    fn new() -> (Self, mpsc::UnboundedReceiver<ProtocolConnection>) {
+        let (connections, rx) = mpsc::unbounded_channel();
+        (Self { connections }, rx)
+    }
+}
+
+impl ProtocolHandler for OurProtoHandler {
+    fn protocol(&self) -> Protocol {
+        Protocol::new(Capability::new("ourproto".into(), 1), 2)
+    }
+
+    fn on_connection(&self, connection: ProtocolConnection) {
+        let _ = self.connections.send(connection);
+    }
+}
+
+fn message(id: u8, payload: &[u8]) -> Bytes {
+    let mut buf = BytesMut::with_capacity(1 + payload.len());
+    buf.put_u8(id);
+    buf.put_slice(payload);
+    buf.freeze()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_custom_sub_protocol_ping_pong() {
+    reth_tracing::init_test_tracing();
+
+    let mut net = Testnet::create(2).await;
+
+    let (handler0, mut connections0) = OurProtoHandler::new();
+    let (handler1, mut connections1) = OurProtoHandler::new();
+    net.peers_mut()[0].add_rlpx_sub_protocol(handler0);
+    net.peers_mut()[1].add_rlpx_sub_protocol(handler1);
+
+    let mut handles = net.handles();
+    let handle0 = handles.next().unwrap();
+    let handle1 = handles.next().unwrap();
+    drop(handles);
+
+    let handle = net.spawn();
+
+    handle0.add_peer(*handle1.peer_id(), handle1.local_addr());
+
+    let mut conn0 = connections0.recv().await.unwrap();
+    let mut conn1 = connections1.recv().await.unwrap();
+    assert_eq!(conn0.peer_id(), *handle1.peer_id());
+    assert_eq!(conn1.peer_id(), *handle0.peer_id());
+    assert_eq!(conn0.capability().name(), "ourproto");
+
+    conn0.send(message(PING, b"hello")).await.unwrap();
+    let ping = conn1.next().await.unwrap();
+    assert_eq!(ping.as_ref(), message(PING, b"hello").as_ref());
+
+    conn1.send(message(PONG, b"world")).await.unwrap();
+    let pong = conn0.next().await.unwrap();
+    assert_eq!(pong.as_ref(), message(PONG, b"world").as_ref());
+
+    handle.terminate().await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_custom_sub_protocol_not_shared() {
+    reth_tracing::init_test_tracing();
+
+    let mut net = Testnet::create(2).await;
+
+    let (handler, mut connections) = OurProtoHandler::new();
+    net.peers_mut()[0].add_rlpx_sub_protocol(handler);
+
+    let mut handles = net.handles();
+    let handle0 = handles.next().unwrap();
+    let handle1 = handles.next().unwrap();
+    drop(handles);
+
+    let handle = net.spawn();
+
+    let mut events = handle0.event_listener().take(2);
+    handle0.add_peer(*handle1.peer_id(), handle1.local_addr());
+
+    while let Some(event) = events.next().await {
+        match event {
+            NetworkEvent::PeerAdded(peer_id) => {
+                assert_eq!(handle1.peer_id(), &peer_id);
+            }
+            NetworkEvent::SessionEstablished { peer_id, capabilities, .. } => {
+                assert_eq!(handle1.peer_id(), &peer_id);
+                assert!(!capabilities
+                    .capabilities()
+                    .contains(&Capability::new("ourproto".into(), 1)));
+            }
+            ev => {
+                panic!("unexpected event: {ev:?}")
+            }
+        }
+    }
+
+    // the session is established without the protocol
+    assert!(connections.try_recv().is_err());
+
+    handle.terminate().await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_stalled_sub_protocol_handler() {
+    reth_tracing::init_test_tracing();
+
+    let mock_provider = MockEthProvider::default();
+    let mut net = Testnet::create_with(2, mock_provider.clone()).await;
+    net.for_each_mut(|peer| peer.install_request_handler());
+
+    let (handler0, mut connections0) = OurProtoHandler::new();
+    let (handler1, mut connections1) = OurProtoHandler::new();
+    net.peers_mut()[0].add_rlpx_sub_protocol(handler0);
+    net.peers_mut()[1].add_rlpx_sub_protocol(handler1);
+
+    let handle0 = net.peers()[0].handle();
+    let mut events0 = NetworkEventStream::new(handle0.event_listener());
+    let handle1 = net.peers()[1].handle();
+
+    let handle = net.spawn();
+    let fetch0 = handle0.fetch_client().await.unwrap();
+
+    handle0.add_peer(*handle1.peer_id(), handle1.local_addr());
+    let connected = events0.next_session_established().await.unwrap();
+    assert_eq!(connected, *handle1.peer_id());
+
+    let conn0 = connections0.recv().await.unwrap();
+    // the handler of the second peer never reads from its connection
+    let mut conn1 = connections1.recv().await.unwrap();
+
+    let num_messages = PROTOCOL_CONNECTION_CHANNEL_CAPACITY * 4;
+    for idx in 0..num_messages {
+        conn0.send(message(PING, &idx.to_be_bytes())).await.unwrap();
+    }
+
+    // `eth` requests are still served while the handler is stalled
+    let hash = H256::random();
+    let header = Header { number: 1, ..Default::default() };
+    mock_provider.add_header(hash, header.clone());
+    let req = HeadersRequest { start: hash.into(), limit: 1, direction: HeadersDirection::Falling };
+    let res = fetch0.get_headers(req).await;
+    assert!(res.is_ok(), "{res:?}");
+    assert_eq!(res.unwrap().1, vec![header]);
+
+    // the stalled sub-protocol was dropped, so its connection ends after the messages it could
+    // buffer
+    let received = tokio::time::timeout(Duration::from_secs(10), async {
+        let mut received = 0;
+        while conn1.next().await.is_some() {
+            received += 1;
+        }
+        received
+    })
+    .await
+    .expect("stalled sub-protocol connection was not dropped");
+    assert!(received <= PROTOCOL_CONNECTION_CHANNEL_CAPACITY);
+
+    // the session itself is still alive
+    assert_eq!(handle0.num_connected_peers(), 1);
+
+    handle.terminate().await;
+}
@@ -0,0 +1,149 @@
+//! Tests for serving and fetching `snap/1` requests
+
+use futures::StreamExt;
+use reth_db::{tables, test_utils::create_test_rw_db, transaction::DbTxMut, DatabaseEnv};
+use reth_eth_wire::{capability::Protocol, GetAccountRange, SnapMessage};
+use reth_interfaces::p2p::{download::DownloadClient, snap::client::SnapClient};
+use reth_network::{
+    protocol::{ProtocolConnection, ProtocolHandler},
+    snap_requests::{SnapProtocolHandler, SnapRequestHandler},
+    test_utils::Testnet,
+};
+use reth_network_api::{NetworkInfo, Peers};
+use reth_primitives::{bytes::BytesMut, keccak256, Account, Header, H256, MAINNET, U256};
+use reth_provider::ProviderFactory;
+use reth_rlp::Encodable;
+use reth_trie::StateRoot;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+const ACCOUNTS: u64 = 10;
+
+/// Forwards all `snap/1` connections to the test, to send raw requests.
+#[derive(Debug)]
+struct RawSnapHandler {
+    connections: mpsc::UnboundedSender<ProtocolConnection>,
+}
+
+impl ProtocolHandler for RawSnapHandler {
+    fn protocol(&self) -> Protocol {
+        Protocol::snap()
+    }
+
+    fn on_connection(&self, connection: ProtocolConnection) {
+        let _ = self.connections.send(connection);
+    }
+}
+
+/// Creates a database with [ACCOUNTS] accounts in the hashed state, and returns it with its
+/// state root.
+fn served_state() -> (ProviderFactory<Arc<DatabaseEnv>>, H256) {
+    let factory = ProviderFactory::new(create_test_rw_db(), MAINNET.clone());
+    let provider = factory.provider_rw().unwrap();
+    let tx = provider.tx_ref();
+    for i in 0..ACCOUNTS {
+        let account = Account { nonce: i, balance: U256::from(i), bytecode_hash: None };
+        tx.put::<tables::HashedAccount>(keccak256(H256::from_low_u64_be(i)), account).unwrap();
+    }
+    let (root, updates) = StateRoot::new(tx).root_with_updates().unwrap();
+    updates.flush(tx).unwrap();
+    tx.put::<tables::Headers>(0, Header { state_root: root, ..Default::default() }).unwrap();
+    provider.commit().unwrap();
+    (factory, root)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_serve_account_range() {
+    reth_tracing::init_test_tracing();
+
+    // the state served by the first peer
+    let (factory, root) = served_state();
+
+    let mut net = Testnet::create(2).await;
+
+    let (to_request_handler, incoming) = mpsc::channel(1);
+    net.peers_mut()[0].add_rlpx_sub_protocol(SnapProtocolHandler::new(to_request_handler));
+    tokio::spawn(SnapRequestHandler::new(factory, incoming));
+
+    let (connections, mut connections_rx) = mpsc::unbounded_channel();
+    net.peers_mut()[1].add_rlpx_sub_protocol(RawSnapHandler { connections });
+
+    let mut handles = net.handles();
+    let handle0 = handles.next().unwrap();
+    let handle1 = handles.next().unwrap();
+    drop(handles);
+
+    let handle = net.spawn();
+
+    handle1.add_peer(*handle0.peer_id(), handle0.local_addr());
+
+    let mut conn = connections_rx.recv().await.unwrap();
+    assert_eq!(conn.peer_id(), *handle0.peer_id());
+
+    let request = SnapMessage::GetAccountRange(GetAccountRange {
+        request_id: 7,
+        root_hash: root,
+        starting_hash: H256::zero(),
+        limit_hash: H256::repeat_byte(0xff),
+        response_bytes: 512 * 1024,
+    });
+    let mut buf = BytesMut::new();
+    request.encode(&mut buf);
+    conn.send(buf.freeze()).await.unwrap();
+
+    let response = conn.next().await.unwrap();
+    let SnapMessage::AccountRange(range) = SnapMessage::decode_message(&mut &response[..]).unwrap()
+    else {
+        panic!("unexpected response")
+    };
+    assert_eq!(range.request_id, 7);
+    assert_eq!(range.accounts.len(), ACCOUNTS as usize);
+    assert!(range.accounts.windows(2).all(|accounts| accounts[0].hash < accounts[1].hash));
+
+    handle.terminate().await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_fetch_account_range() {
+    reth_tracing::init_test_tracing();
+
+    // the state served by the first peer
+    let (factory, root) = served_state();
+
+    let mut net = Testnet::create(2).await;
+
+    let (to_request_handler, incoming) = mpsc::channel(1);
+    net.peers_mut()[0].add_rlpx_sub_protocol(SnapProtocolHandler::new(to_request_handler));
+    tokio::spawn(SnapRequestHandler::new(factory, incoming));
+
+    // the second peer only fetches
+    let (to_request_handler, _incoming) = mpsc::channel(1);
+    let handler = SnapProtocolHandler::new(to_request_handler);
+    let client = handler.client(net.peers()[1].handle().peers_handle().clone());
+    net.peers_mut()[1].add_rlpx_sub_protocol(handler);
+
+    let mut handles = net.handles();
+    let handle0 = handles.next().unwrap();
+    let handle1 = handles.next().unwrap();
+    drop(handles);
+
+    let handle = net.spawn();
+
+    handle1.add_peer(*handle0.peer_id(), handle0.local_addr());
+
+    // the request waits for the peer to connect
+    let request = GetAccountRange {
+        request_id: 0,
+        root_hash: root,
+        starting_hash: H256::zero(),
+        limit_hash: H256::repeat_byte(0xff),
+        response_bytes: 512 * 1024,
+    };
+    let (peer_id, range) = client.get_account_range(request).await.unwrap().split();
+    assert_eq!(peer_id, *handle0.peer_id());
+    assert_eq!(range.accounts.len(), ACCOUNTS as usize);
+    assert!(range.accounts.windows(2).all(|accounts| accounts[0].hash < accounts[1].hash));
+    assert_eq!(client.num_connected_peers(), 1);
+
+    handle.terminate().await;
+}
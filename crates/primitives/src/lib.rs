@@ -84,7 +84,7 @@ pub use net::{
 pub use peer::{PeerId, WithPeerId};
 pub use prune::{
     PruneBatchSizes, PruneCheckpoint, PruneMode, PruneModes, PrunePart, PrunePartError,
-    ReceiptsLogPruneConfig, MINIMUM_PRUNING_DISTANCE,
+    ReceiptsLogFilter, ReceiptsLogFilterError, ReceiptsLogPruneConfig, MINIMUM_PRUNING_DISTANCE,
 };
 pub use receipt::{Receipt, ReceiptWithBloom, ReceiptWithBloomRef};
 pub use revm_primitives::JumpMap;
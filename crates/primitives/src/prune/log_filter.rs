@@ -0,0 +1,197 @@
+use crate::{Address, Log, H256};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{fmt, str::FromStr};
+use thiserror::Error;
+
+/// Wildcard used in place of the address to match logs emitted by any contract.
+const ANY_ADDRESS: &str = "*";
+
+/// Filter selecting the logs whose receipts are retained by
+/// [ReceiptsLogPruneConfig](crate::ReceiptsLogPruneConfig).
+///
+/// A log matches if it was emitted by `address` and its first topic (the event signature) is
+/// `topic0`. A missing field matches any value, but at least one of them is always set.
+///
+/// It's represented as a string, so it can be used as a key in the configuration file:
+/// - `"0xa0b8…eb48"` matches all logs emitted by the contract.
+/// - `"0xa0b8…eb48:0xddf2…b3ef"` matches logs of the contract with the given `topic0`.
+/// - `"*:0xddf2…b3ef"` matches logs with the given `topic0` emitted by any contract.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ReceiptsLogFilter {
+    address: Option<Address>,
+    topic0: Option<H256>,
+}
+
+impl ReceiptsLogFilter {
+    /// Creates a filter matching all logs emitted by the contract.
+    pub fn address(address: Address) -> Self {
+        Self { address: Some(address), topic0: None }
+    }
+
+    /// Creates a filter matching logs with the given `topic0` emitted by any contract.
+    pub fn topic0(topic0: H256) -> Self {
+        Self { address: None, topic0: Some(topic0) }
+    }
+
+    /// Creates a filter matching logs with the given `topic0` emitted by the contract.
+    pub fn address_and_topic0(address: Address, topic0: H256) -> Self {
+        Self { address: Some(address), topic0: Some(topic0) }
+    }
+
+    /// Returns `true` if the log is selected by this filter.
+    pub fn matches(&self, log: &Log) -> bool {
+        self.address.map_or(true, |address| address == log.address) &&
+            self.topic0.map_or(true, |topic0| log.topics.first() == Some(&topic0))
+    }
+}
+
+impl From<Address> for ReceiptsLogFilter {
+    fn from(address: Address) -> Self {
+        Self::address(address)
+    }
+}
+
+impl fmt::Display for ReceiptsLogFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.address {
+            Some(address) => write!(f, "{address:?}")?,
+            None => f.write_str(ANY_ADDRESS)?,
+        }
+        if let Some(topic0) = self.topic0 {
+            write!(f, ":{topic0:?}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Error while parsing a [ReceiptsLogFilter].
+#[derive(Debug, Error, PartialEq, Eq, Clone)]
+pub enum ReceiptsLogFilterError {
+    /// The contract address is not valid.
+    #[error("Invalid contract address: {0}")]
+    Address(String),
+    /// The topic is not valid.
+    #[error("Invalid topic: {0}")]
+    Topic(String),
+    /// The filter matches all logs.
+    #[error("Filter has to specify a contract address, a topic or both")]
+    Empty,
+}
+
+impl FromStr for ReceiptsLogFilter {
+    type Err = ReceiptsLogFilterError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (address, topic0) = match s.split_once(':') {
+            Some((address, topic0)) => (address, Some(topic0)),
+            None => (s, None),
+        };
+
+        let address = match address {
+            ANY_ADDRESS => None,
+            address => Some(
+                address
+                    .parse::<Address>()
+                    .map_err(|_| ReceiptsLogFilterError::Address(address.to_string()))?,
+            ),
+        };
+        let topic0 = topic0
+            .map(|topic0| {
+                topic0
+                    .parse::<H256>()
+                    .map_err(|_| ReceiptsLogFilterError::Topic(topic0.to_string()))
+            })
+            .transpose()?;
+
+        if address.is_none() && topic0.is_none() {
+            return Err(ReceiptsLogFilterError::Empty)
+        }
+
+        Ok(Self { address, topic0 })
+    }
+}
+
+impl Serialize for ReceiptsLogFilter {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ReceiptsLogFilter {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PruneMode, ReceiptsLogPruneConfig};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn parse_filter() {
+        let address = Address::from_low_u64_be(1);
+        let topic0 = H256::from_low_u64_be(2);
+
+        for filter in [
+            ReceiptsLogFilter::address(address),
+            ReceiptsLogFilter::topic0(topic0),
+            ReceiptsLogFilter::address_and_topic0(address, topic0),
+        ] {
+            assert_eq!(filter.to_string().parse::<ReceiptsLogFilter>(), Ok(filter));
+        }
+
+        assert_eq!("*".parse::<ReceiptsLogFilter>(), Err(ReceiptsLogFilterError::Empty));
+        assert!(matches!(
+            "0x01:0x02".parse::<ReceiptsLogFilter>(),
+            Err(ReceiptsLogFilterError::Address(_))
+        ));
+        assert!(matches!(
+            format!("{address:?}:0x02").parse::<ReceiptsLogFilter>(),
+            Err(ReceiptsLogFilterError::Topic(_))
+        ));
+    }
+
+    #[test]
+    fn filter_matches() {
+        let address = Address::from_low_u64_be(1);
+        let topic0 = H256::from_low_u64_be(2);
+        let log = |address, topics| Log { address, topics, ..Default::default() };
+
+        let filter = ReceiptsLogFilter::address(address);
+        assert!(filter.matches(&log(address, vec![])));
+        assert!(!filter.matches(&log(Address::zero(), vec![topic0])));
+
+        let filter = ReceiptsLogFilter::topic0(topic0);
+        assert!(filter.matches(&log(Address::zero(), vec![topic0])));
+        assert!(!filter.matches(&log(address, vec![])));
+        // Only the first topic is the event signature.
+        assert!(!filter.matches(&log(address, vec![H256::zero(), topic0])));
+
+        let filter = ReceiptsLogFilter::address_and_topic0(address, topic0);
+        assert!(filter.matches(&log(address, vec![topic0, H256::zero()])));
+        assert!(!filter.matches(&log(Address::zero(), vec![topic0])));
+        assert!(!filter.matches(&log(address, vec![H256::zero()])));
+    }
+
+    #[test]
+    fn deserialize_config() {
+        let address = Address::from_low_u64_be(1);
+        let topic0 = H256::from_low_u64_be(2);
+
+        let config: ReceiptsLogPruneConfig = serde_json::from_str(&format!(
+            r#"{{ "{address:?}": {{ "before": 10 }}, "*:{topic0:?}": {{ "distance": 1000 }} }}"#
+        ))
+        .unwrap();
+
+        assert_eq!(
+            config,
+            ReceiptsLogPruneConfig(BTreeMap::from([
+                (address.into(), PruneMode::Before(10)),
+                (ReceiptsLogFilter::topic0(topic0), PruneMode::Distance(1000)),
+            ]))
+        );
+    }
+}
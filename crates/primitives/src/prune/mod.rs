@@ -1,21 +1,26 @@
 mod batch_sizes;
 mod checkpoint;
+mod log_filter;
 mod mode;
 mod part;
 mod target;
 
-use crate::{Address, BlockNumber};
+use crate::{BlockNumber, Log};
 pub use batch_sizes::PruneBatchSizes;
 pub use checkpoint::PruneCheckpoint;
+pub use log_filter::{ReceiptsLogFilter, ReceiptsLogFilterError};
 pub use mode::PruneMode;
 pub use part::{PrunePart, PrunePartError};
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 pub use target::{PruneModes, MINIMUM_PRUNING_DISTANCE};
 
-/// Configuration for pruning receipts not associated with logs emitted by the specified contracts.
+/// Configuration for pruning receipts not associated with logs matching the specified filters.
+///
+/// Each filter selects logs by contract address, event signature (`topic0`) or both, and has its
+/// own [PruneMode] defining from which block the matching receipts are retained.
 #[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
-pub struct ReceiptsLogPruneConfig(pub BTreeMap<Address, PruneMode>);
+pub struct ReceiptsLogPruneConfig(pub BTreeMap<ReceiptsLogFilter, PruneMode>);
 
 impl ReceiptsLogPruneConfig {
     /// Checks if the configuration is empty
@@ -28,25 +33,25 @@ impl ReceiptsLogPruneConfig {
     ///
     /// Example:
     ///
-    /// `{ addrA: Before(872), addrB: Before(500), addrC: Distance(128) }`
+    /// `{ filterA: Before(872), filterB: Before(500), filterC: Distance(128) }`
     ///  
     ///    for `tip: 1000`, gets transformed to a map such as:
     ///
-    /// `{ 500: [addrB], 872: [addrA, addrC] }`
+    /// `{ 500: [filterB], 872: [filterA, filterC] }`
     ///
     /// The [`BlockNumber`] key of the new map should be viewed as `PruneMode::Before(block)`, which
     /// makes the previous result equivalent to
     ///
-    /// `{ Before(500): [addrB], Before(872): [addrA, addrC] }`
+    /// `{ Before(500): [filterB], Before(872): [filterA, filterC] }`
     pub fn group_by_block(
         &self,
         tip: BlockNumber,
         pruned_block: Option<BlockNumber>,
-    ) -> Result<BTreeMap<BlockNumber, Vec<&Address>>, PrunePartError> {
+    ) -> Result<BTreeMap<BlockNumber, Vec<&ReceiptsLogFilter>>, PrunePartError> {
         let mut map = BTreeMap::new();
         let pruned_block = pruned_block.unwrap_or_default();
 
-        for (address, mode) in self.0.iter() {
+        for (filter, mode) in self.0.iter() {
             // Getting `None`, means that there is nothing to prune yet, so we need it to include in
             // the BTreeMap (block = 0), otherwise it will be excluded.
             // Reminder that this BTreeMap works as an inclusion list that excludes (prunes) all
@@ -61,7 +66,7 @@ impl ReceiptsLogPruneConfig {
                     1,
             );
 
-            map.entry(block).or_insert_with(Vec::new).push(address)
+            map.entry(block).or_insert_with(Vec::new).push(filter)
         }
         Ok(map)
     }
@@ -87,4 +92,12 @@ impl ReceiptsLogPruneConfig {
 
         Ok(lowest.map(|lowest| lowest.max(pruned_block)))
     }
+
+    /// Returns `true` if any of the log filters matches any of the logs.
+    pub fn matches_any<'a>(
+        filters: impl IntoIterator<Item = &'a ReceiptsLogFilter>,
+        logs: &[Log],
+    ) -> bool {
+        filters.into_iter().any(|filter| logs.iter().any(|log| filter.matches(log)))
+    }
 }
@@ -18,6 +18,11 @@ pub enum PrunePart {
     AccountHistory,
     /// Prune part responsible for the `StorageChangeSet` and `StorageHistory` tables.
     StorageHistory,
+    /// Prune part responsible for the `Transactions`, `BlockBodyIndices`, `BlockOmmers` and
+    /// `BlockWithdrawals` tables, i.e. EIP-4444 history expiry of block bodies.
+    ///
+    /// Headers are kept, so pruned blocks can still be resolved by hash and number.
+    BlockBodies,
 }
 
 /// PrunePart error type.
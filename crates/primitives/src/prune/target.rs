@@ -37,8 +37,15 @@ pub struct PruneModes {
         deserialize_with = "deserialize_opt_prune_mode_with_min_blocks::<64, _>"
     )]
     pub storage_history: Option<PruneMode>,
-    /// Receipts pruning configuration by retaining only those receipts that contain logs emitted
-    /// by the specified addresses, discarding others. This setting is overridden by `receipts`.
+    /// Block bodies pruning configuration, also known as history expiry. Transactions, ommers,
+    /// withdrawals and body indices of the blocks behind the configured horizon are removed.
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_opt_prune_mode_with_min_blocks::<64, _>"
+    )]
+    pub block_bodies: Option<PruneMode>,
+    /// Receipts pruning configuration by retaining only those receipts that contain logs matching
+    /// the specified filters, discarding others. This setting is overridden by `receipts`.
     ///
     /// The [`BlockNumber`] represents the starting block from which point onwards the receipts are
     /// preserved.
@@ -103,6 +110,7 @@ impl PruneModes {
         (transaction_lookup, TransactionLookup, None),
         (receipts, Receipts, Some(64)),
         (account_history, AccountHistory, Some(64)),
-        (storage_history, StorageHistory, Some(64))
+        (storage_history, StorageHistory, Some(64)),
+        (block_bodies, BlockBodies, Some(64))
     );
 }
@@ -156,6 +156,19 @@ pub struct IndexHistoryCheckpoint {
     pub progress: EntitiesCheckpoint,
 }
 
+/// Saves the progress of StateSync stage.
+#[main_codec]
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct StateSyncCheckpoint {
+    /// The block whose state is being downloaded.
+    pub pivot: BlockNumber,
+    /// The next hashed address to download the accounts from. `None` if all accounts are
+    /// downloaded.
+    pub next_account: Option<H256>,
+    /// Progress measured in accounts, with the total estimated from the downloaded key range.
+    pub progress: EntitiesCheckpoint,
+}
+
 /// Saves the progress of abstract stage iterating over or downloading entities.
 #[main_codec]
 #[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
@@ -235,7 +248,10 @@ impl StageCheckpoint {
             StageUnitCheckpoint::IndexHistory(IndexHistoryCheckpoint {
                 progress: entities,
                 ..
-            }) => Some(entities),
+            }) |
+            StageUnitCheckpoint::StateSync(StateSyncCheckpoint { progress: entities, .. }) => {
+                Some(entities)
+            }
         }
     }
 }
@@ -267,6 +283,8 @@ pub enum StageUnitCheckpoint {
     Headers(HeadersCheckpoint),
     /// Saves the progress of Index History stage.
     IndexHistory(IndexHistoryCheckpoint),
+    /// Saves the progress of StateSync stage.
+    StateSync(StateSyncCheckpoint),
 }
 
 /// Generates:
@@ -382,6 +400,15 @@ stage_unit_checkpoints!(
         index_history_stage_checkpoint,
         /// Sets the stage checkpoint to index history.
         with_index_history_stage_checkpoint
+    ),
+    (
+        6,
+        StateSync,
+        StateSyncCheckpoint,
+        /// Returns the state sync stage checkpoint, if any.
+        state_sync_stage_checkpoint,
+        /// Sets the stage checkpoint to state sync.
+        with_state_sync_stage_checkpoint
     )
 );
 
@@ -450,6 +477,14 @@ mod tests {
                     total: u32::MAX as u64 + rng.gen::<u64>(),
                 },
             }),
+            StageUnitCheckpoint::StateSync(StateSyncCheckpoint {
+                pivot: rng.gen(),
+                next_account: Some(H256::from_low_u64_be(rng.gen())),
+                progress: EntitiesCheckpoint {
+                    processed: rng.gen::<u32>() as u64,
+                    total: u32::MAX as u64 + rng.gen::<u64>(),
+                },
+            }),
         ];
 
         for checkpoint in checkpoints {
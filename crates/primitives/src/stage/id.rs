@@ -38,6 +38,12 @@ impl StageId {
         StageId::Finish,
     ];
 
+    /// The id of the optional stage that downloads the state of a pivot block from the peers,
+    /// instead of executing all blocks up to it.
+    ///
+    /// It's not part of [StageId::ALL], since it's only added to the pipeline on request.
+    pub const STATE_SYNC: StageId = StageId::Other("StateSync");
+
     /// Return stage id formatted as string.
     pub fn as_str(&self) -> &str {
         match self {
@@ -7,5 +7,8 @@ mod checkpoints;
 pub use checkpoints::{
     AccountHashingCheckpoint, CheckpointBlockRange, EntitiesCheckpoint, ExecutionCheckpoint,
     HeadersCheckpoint, IndexHistoryCheckpoint, MerkleCheckpoint, StageCheckpoint,
-    StageUnitCheckpoint, StorageHashingCheckpoint,
+    StageUnitCheckpoint, StateSyncCheckpoint, StorageHashingCheckpoint,
 };
+
+mod progress;
+pub use progress::{ProgressUnit, StageProgress};
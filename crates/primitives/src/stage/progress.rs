@@ -0,0 +1,34 @@
+use crate::BlockNumber;
+use serde::{Deserialize, Serialize};
+
+/// The unit a stage measures its progress in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ProgressUnit {
+    /// Blocks, for stages that only report block checkpoints.
+    Blocks,
+    /// Gas, reported by the execution stage.
+    Gas,
+    /// Stage specific entities, e.g. headers, accounts or changesets.
+    Entities,
+}
+
+/// The progress of a stage, along with its recent throughput.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StageProgress {
+    /// The name of the stage.
+    pub stage: String,
+    /// The last block checkpoint of the stage.
+    pub checkpoint: BlockNumber,
+    /// The unit of `processed`, `total` and `throughput`.
+    pub unit: ProgressUnit,
+    /// The number of processed units.
+    pub processed: u64,
+    /// The total number of units to process, if known.
+    pub total: Option<u64>,
+    /// The number of units processed per second, if the stage made progress recently.
+    pub throughput: Option<f64>,
+    /// The estimated number of seconds until the stage reaches its target, if known.
+    pub eta: Option<u64>,
+}
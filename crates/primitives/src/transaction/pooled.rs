@@ -2,7 +2,8 @@
 //! response to `GetPooledTransactions`.
 use crate::{
     Address, BlobTransaction, Bytes, Signature, Transaction, TransactionSigned,
-    TransactionSignedEcRecovered, TxEip1559, TxEip2930, TxHash, TxLegacy, EIP4844_TX_TYPE_ID, H256,
+    TransactionSignedEcRecovered, TxEip1559, TxEip2930, TxHash, TxLegacy, TxType,
+    EIP4844_TX_TYPE_ID, H256,
 };
 use bytes::Buf;
 use derive_more::{AsRef, Deref};
@@ -78,6 +79,16 @@ impl PooledTransactionsElement {
         }
     }
 
+    /// Returns the type of the transaction.
+    pub fn tx_type(&self) -> TxType {
+        match self {
+            Self::Legacy { .. } => TxType::Legacy,
+            Self::Eip2930 { .. } => TxType::EIP2930,
+            Self::Eip1559 { .. } => TxType::EIP1559,
+            Self::BlobTransaction(_) => TxType::EIP4844,
+        }
+    }
+
     /// Returns the signature of the transaction.
     pub fn signature(&self) -> &Signature {
         match self {
@@ -8,18 +8,18 @@ use reth_db::{
     models::{storage_sharded_key::StorageShardedKey, BlockNumberAddress, ShardedKey},
     table::Table,
     tables,
-    transaction::DbTxMut,
+    transaction::{DbTx, DbTxMut},
     BlockNumberList,
 };
 use reth_primitives::{
     BlockNumber, ChainSpec, PruneBatchSizes, PruneCheckpoint, PruneMode, PruneModes, PrunePart,
-    TxNumber, MINIMUM_PRUNING_DISTANCE,
+    ReceiptsLogPruneConfig, TxNumber, MINIMUM_PRUNING_DISTANCE,
 };
 use reth_provider::{
-    BlockReader, DatabaseProviderRW, ProviderFactory, PruneCheckpointReader, PruneCheckpointWriter,
-    TransactionsProvider,
+    BlockReader, DatabaseProvider, DatabaseProviderRW, ProviderFactory, PruneCheckpointReader,
+    PruneCheckpointWriter, TransactionsProvider,
 };
-use std::{ops::RangeInclusive, sync::Arc, time::Instant};
+use std::{collections::BTreeMap, ops::RangeInclusive, sync::Arc, time::Instant};
 use tracing::{debug, error, instrument, trace};
 
 /// Result of [Pruner::run] execution.
@@ -219,6 +219,38 @@ impl<DB: Database> Pruner<DB> {
             );
         }
 
+        // The other parts were pruned in this transaction already, so their checkpoints are up to
+        // date.
+        if let Some((to_block, prune_mode)) =
+            self.block_bodies_prune_target(tip_block_number, |part| {
+                Ok(provider
+                    .get_prune_checkpoint(part)?
+                    .and_then(|checkpoint| checkpoint.block_number))
+            })?
+        {
+            trace!(
+                target: "pruner",
+                prune_part = ?PrunePart::BlockBodies,
+                %to_block,
+                ?prune_mode,
+                "Got target block to prune"
+            );
+
+            let part_start = Instant::now();
+            let part_done = self.prune_block_bodies(&provider, to_block, prune_mode)?;
+            done = done && part_done;
+            self.metrics
+                .get_prune_part_metrics(PrunePart::BlockBodies)
+                .duration_seconds
+                .record(part_start.elapsed())
+        } else {
+            trace!(
+                target: "pruner",
+                prune_part = ?PrunePart::BlockBodies,
+                "No target block to prune"
+            );
+        }
+
         provider.commit()?;
         self.last_pruned_block_number = Some(tip_block_number);
 
@@ -229,6 +261,205 @@ impl<DB: Database> Pruner<DB> {
         Ok(done)
     }
 
+    /// Estimates the number of rows that [Pruner::run] needs to delete for every configured prune
+    /// part to reach its target block at the provided tip. The database is not modified.
+    ///
+    /// Rows are counted in the same units as [PruneBatchSizes]. For [PrunePart::ContractLogs], all
+    /// receipts in the range are counted without checking them against the log filters, so the
+    /// estimate is an upper bound.
+    pub fn estimate(
+        &self,
+        tip_block_number: BlockNumber,
+    ) -> Result<BTreeMap<PrunePart, usize>, PrunerError> {
+        // Read-only, so that estimating doesn't wait for or block a running node.
+        let provider = self.provider_factory.provider()?;
+        let mut estimates = BTreeMap::new();
+
+        let tx_range_len = |range: Option<RangeInclusive<TxNumber>>| {
+            range.map_or(0, |range| (range.end() + 1).saturating_sub(*range.start()) as usize)
+        };
+
+        if let Some((to_block, _)) = self.modes.prune_target_block_receipts(tip_block_number)? {
+            let range = self.get_next_tx_num_range_from_checkpoint(
+                &provider,
+                PrunePart::Receipts,
+                to_block,
+            )?;
+            estimates.insert(PrunePart::Receipts, tx_range_len(range));
+        }
+
+        if !self.modes.receipts_log_filter.is_empty() {
+            let to_block = PruneMode::Distance(MINIMUM_PRUNING_DISTANCE)
+                .prune_target_block(
+                    tip_block_number,
+                    MINIMUM_PRUNING_DISTANCE,
+                    PrunePart::ContractLogs,
+                )?
+                .map(|(bn, _)| bn)
+                .unwrap_or_default();
+            let from_tx_number = match provider
+                .get_prune_checkpoint(PrunePart::ContractLogs)?
+                .and_then(|checkpoint| checkpoint.block_number)
+            {
+                Some(block) => provider
+                    .block_body_indices(block)?
+                    .map(|block| block.last_tx_num() + 1)
+                    .unwrap_or(0),
+                None => 0,
+            };
+            let range = provider
+                .block_body_indices(to_block)?
+                .map(|body| from_tx_number..=body.last_tx_num());
+            estimates.insert(PrunePart::ContractLogs, tx_range_len(range));
+        }
+
+        if let Some((to_block, _)) =
+            self.modes.prune_target_block_transaction_lookup(tip_block_number)?
+        {
+            let range = self.get_next_tx_num_range_from_checkpoint(
+                &provider,
+                PrunePart::TransactionLookup,
+                to_block,
+            )?;
+            estimates.insert(PrunePart::TransactionLookup, tx_range_len(range));
+        }
+
+        if let Some((to_block, _)) =
+            self.modes.prune_target_block_sender_recovery(tip_block_number)?
+        {
+            let range = self.get_next_tx_num_range_from_checkpoint(
+                &provider,
+                PrunePart::SenderRecovery,
+                to_block,
+            )?;
+            estimates.insert(PrunePart::SenderRecovery, tx_range_len(range));
+        }
+
+        if let Some((to_block, _)) =
+            self.modes.prune_target_block_account_history(tip_block_number)?
+        {
+            let rows = match self.get_next_block_range_from_checkpoint(
+                &provider,
+                PrunePart::AccountHistory,
+                to_block,
+            )? {
+                Some(range) => provider
+                    .tx_ref()
+                    .cursor_read::<tables::AccountChangeSet>()?
+                    .walk_range(range)?
+                    .count(),
+                None => 0,
+            };
+            estimates.insert(PrunePart::AccountHistory, rows);
+        }
+
+        if let Some((to_block, _)) =
+            self.modes.prune_target_block_storage_history(tip_block_number)?
+        {
+            let rows = match self.get_next_block_range_from_checkpoint(
+                &provider,
+                PrunePart::StorageHistory,
+                to_block,
+            )? {
+                Some(range) => provider
+                    .tx_ref()
+                    .cursor_read::<tables::StorageChangeSet>()?
+                    .walk_range(BlockNumberAddress::range(range))?
+                    .count(),
+                None => 0,
+            };
+            estimates.insert(PrunePart::StorageHistory, rows);
+        }
+
+        if self.modes.block_bodies.is_some() {
+            // Assumes that the other parts reach their targets in the same run.
+            let target = self.block_bodies_prune_target(tip_block_number, |part| {
+                let target = match part {
+                    PrunePart::Receipts => {
+                        self.modes.prune_target_block_receipts(tip_block_number)?
+                    }
+                    PrunePart::ContractLogs => PruneMode::Distance(MINIMUM_PRUNING_DISTANCE)
+                        .prune_target_block(
+                            tip_block_number,
+                            MINIMUM_PRUNING_DISTANCE,
+                            PrunePart::ContractLogs,
+                        )?,
+                    PrunePart::TransactionLookup => {
+                        self.modes.prune_target_block_transaction_lookup(tip_block_number)?
+                    }
+                    PrunePart::SenderRecovery => {
+                        self.modes.prune_target_block_sender_recovery(tip_block_number)?
+                    }
+                    _ => None,
+                };
+                Ok(target.map(|(block_number, _)| block_number))
+            })?;
+            let range = match target {
+                Some((to_block, _)) => self.get_next_tx_num_range_from_checkpoint(
+                    &provider,
+                    PrunePart::BlockBodies,
+                    to_block,
+                )?,
+                None => None,
+            };
+            estimates.insert(PrunePart::BlockBodies, tx_range_len(range));
+        }
+
+        Ok(estimates)
+    }
+
+    /// Returns the block to prune block bodies to at the provided tip, and the configured prune
+    /// mode.
+    ///
+    /// The parts that are pruned by transaction number look up the transactions of a block in the
+    /// block body indices, and transaction lookup pruning also needs the transactions to calculate
+    /// their hashes. Block bodies are thus never pruned further than any of the configured parts,
+    /// whose pruned block is returned by `pruned_block`. Returns `None` if one of these parts
+    /// hasn't pruned anything yet.
+    fn block_bodies_prune_target(
+        &self,
+        tip_block_number: BlockNumber,
+        mut pruned_block: impl FnMut(PrunePart) -> Result<Option<BlockNumber>, PrunerError>,
+    ) -> Result<Option<(BlockNumber, PruneMode)>, PrunerError> {
+        let Some((mut to_block, prune_mode)) =
+            self.modes.prune_target_block_block_bodies(tip_block_number)?
+        else {
+            return Ok(None)
+        };
+
+        let parts = [
+            (PrunePart::Receipts, self.modes.receipts.is_some()),
+            (PrunePart::ContractLogs, !self.modes.receipts_log_filter.is_empty()),
+            (PrunePart::TransactionLookup, self.modes.transaction_lookup.is_some()),
+            (PrunePart::SenderRecovery, self.modes.sender_recovery.is_some()),
+        ];
+        for (part, _) in parts.into_iter().filter(|(_, enabled)| *enabled) {
+            let Some(block_number) = pruned_block(part)? else {
+                trace!(
+                    target: "pruner",
+                    prune_part = ?PrunePart::BlockBodies,
+                    waiting_for = ?part,
+                    "Waiting for pruning of dependent part"
+                );
+                return Ok(None)
+            };
+
+            // Contract logs pruning starts after the transactions of its checkpoint block, so
+            // the body indices of that block have to be kept.
+            let block_number = if part == PrunePart::ContractLogs {
+                match block_number.checked_sub(1) {
+                    Some(block_number) => block_number,
+                    None => return Ok(None),
+                }
+            } else {
+                block_number
+            };
+            to_block = to_block.min(block_number);
+        }
+
+        Ok(Some((to_block, prune_mode)))
+    }
+
     /// Returns `true` if the pruning is needed at the provided tip block number.
     /// This determined by the check against minimum pruning interval and last pruned block number.
     pub fn is_pruning_needed(&self, tip_block_number: BlockNumber) -> bool {
@@ -259,9 +490,9 @@ impl<DB: Database> Pruner<DB> {
     /// 2. If checkpoint doesn't exist, use block 0.
     ///
     /// To get the range end: use block `to_block`.
-    fn get_next_block_range_from_checkpoint(
+    fn get_next_block_range_from_checkpoint<'a, TX: DbTx<'a>>(
         &self,
-        provider: &DatabaseProviderRW<'_, DB>,
+        provider: &DatabaseProvider<'a, TX>,
         prune_part: PrunePart,
         to_block: BlockNumber,
     ) -> reth_interfaces::Result<Option<RangeInclusive<BlockNumber>>> {
@@ -289,9 +520,9 @@ impl<DB: Database> Pruner<DB> {
     /// 2. If checkpoint doesn't exist, return 0.
     ///
     /// To get the range end: get last tx number for the provided `to_block`.
-    fn get_next_tx_num_range_from_checkpoint(
+    fn get_next_tx_num_range_from_checkpoint<'a, TX: DbTx<'a>>(
         &self,
-        provider: &DatabaseProviderRW<'_, DB>,
+        provider: &DatabaseProvider<'a, TX>,
         prune_part: PrunePart,
         to_block: BlockNumber,
     ) -> reth_interfaces::Result<Option<RangeInclusive<TxNumber>>> {
@@ -409,36 +640,36 @@ impl<DB: Database> Pruner<DB> {
         };
 
         // Figure out what receipts have already been pruned, so we can have an accurate
-        // `address_filter`
-        let address_filter =
+        // `log_filter`
+        let log_filter =
             self.modes.receipts_log_filter.group_by_block(tip_block_number, last_pruned_block)?;
 
         // Splits all transactions in different block ranges. Each block range will have its own
-        // filter address list and will check it while going through the table
+        // log filter list and will check it while going through the table
         //
         // Example:
-        // For an `address_filter` such as:
-        // { block9: [a1, a2], block20: [a3, a4, a5] }
+        // For a `log_filter` such as:
+        // { block9: [f1, f2], block20: [f3, f4, f5] }
         //
         // The following structures will be created in the exact order as showed:
         // `block_ranges`: [
-        //    (block0, block8, 0 addresses),
-        //    (block9, block19, 2 addresses),
-        //    (block20, to_block, 5 addresses)
+        //    (block0, block8, 0 filters),
+        //    (block9, block19, 2 filters),
+        //    (block20, to_block, 5 filters)
         //  ]
-        // `filtered_addresses`: [a1, a2, a3, a4, a5]
+        // `filters`: [f1, f2, f3, f4, f5]
         //
         // The first range will delete all receipts between block0 - block8
         // The second range will delete all receipts between block9 - 19, except the ones with
-        //     emitter logs from these addresses: [a1, a2].
+        //     logs matching these filters: [f1, f2].
         // The third range will delete all receipts between block20 - to_block, except the ones with
-        //     emitter logs from these addresses: [a1, a2, a3, a4, a5]
+        //     logs matching these filters: [f1, f2, f3, f4, f5]
         let mut block_ranges = vec![];
-        let mut blocks_iter = address_filter.iter().peekable();
-        let mut filtered_addresses = vec![];
+        let mut blocks_iter = log_filter.iter().peekable();
+        let mut filters = vec![];
 
-        while let Some((start_block, addresses)) = blocks_iter.next() {
-            filtered_addresses.extend_from_slice(addresses);
+        while let Some((start_block, block_filters)) = blocks_iter.next() {
+            filters.extend_from_slice(block_filters);
 
             // This will clear all receipts before the first  appearance of a contract log or since
             // the block after the last pruned one.
@@ -452,22 +683,22 @@ impl<DB: Database> Pruner<DB> {
             let end_block =
                 blocks_iter.peek().map(|(next_block, _)| *next_block - 1).unwrap_or(to_block);
 
-            // Addresses in lower block ranges, are still included in the inclusion list for future
+            // Filters in lower block ranges, are still included in the inclusion list for future
             // ranges.
-            block_ranges.push((*start_block, end_block, filtered_addresses.len()));
+            block_ranges.push((*start_block, end_block, filters.len()));
         }
 
         trace!(
             target: "pruner",
             ?block_ranges,
-            ?filtered_addresses,
-            "Calculated block ranges and filtered addresses",
+            ?filters,
+            "Calculated block ranges and log filters",
         );
 
         let mut limit = self.batch_sizes.receipts(self.min_block_interval);
         let mut done = true;
         let mut last_pruned_transaction = None;
-        for (start_block, end_block, num_addresses) in block_ranges {
+        for (start_block, end_block, num_filters) in block_ranges {
             let block_range = start_block..=end_block;
 
             // Calculate the transaction range from this block range
@@ -491,10 +722,10 @@ impl<DB: Database> Pruner<DB> {
                 tx_range,
                 limit,
                 |(tx_num, receipt)| {
-                    let skip = num_addresses > 0 &&
-                        receipt.logs.iter().any(|log| {
-                            filtered_addresses[..num_addresses].contains(&&log.address)
-                        });
+                    let skip = ReceiptsLogPruneConfig::matches_any(
+                        filters[..num_filters].iter().copied(),
+                        &receipt.logs,
+                    );
 
                     if skip {
                         last_skipped_transaction = *tx_num;
@@ -787,6 +1018,92 @@ impl<DB: Database> Pruner<DB> {
         Ok(done)
     }
 
+    /// Prune block bodies up to the provided block, inclusive, respecting the batch size.
+    ///
+    /// Transactions are pruned first. Body indices, ommers and withdrawals are then pruned up to
+    /// the block of the last pruned transaction, even if some of its transactions are left for the
+    /// next run, so a partially pruned body is never returned by the providers.
+    #[instrument(level = "trace", skip(self, provider), target = "pruner")]
+    fn prune_block_bodies(
+        &self,
+        provider: &DatabaseProviderRW<'_, DB>,
+        to_block: BlockNumber,
+        prune_mode: PruneMode,
+    ) -> PrunerResult {
+        let checkpoint = provider.get_prune_checkpoint(PrunePart::BlockBodies)?;
+
+        let mut done = true;
+        let mut last_pruned_transaction = checkpoint.and_then(|checkpoint| checkpoint.tx_number);
+        let mut last_pruned_block = to_block;
+
+        if let Some(tx_range) =
+            self.get_next_tx_num_range_from_checkpoint(provider, PrunePart::BlockBodies, to_block)?
+        {
+            let mut last_pruned = *tx_range.end();
+            let deleted;
+            (deleted, done) = provider.prune_table_with_range::<tables::Transactions>(
+                tx_range,
+                self.batch_sizes.block_bodies(self.min_block_interval),
+                |_| false,
+                |row| last_pruned = row.0,
+            )?;
+            trace!(target: "pruner", %deleted, %done, "Pruned block bodies (transactions)");
+
+            if !done {
+                last_pruned_block = provider
+                    .transaction_block(last_pruned)?
+                    .ok_or(PrunerError::InconsistentData("Block for transaction is not found"))?;
+            }
+            last_pruned_transaction = Some(last_pruned);
+        } else {
+            trace!(target: "pruner", "No block body transactions to prune");
+        }
+
+        let block_range = checkpoint
+            .and_then(|checkpoint| checkpoint.block_number)
+            .map(|block_number| block_number + 1)
+            .unwrap_or_default()..=last_pruned_block;
+        let mut deleted = 0;
+        deleted += provider
+            .prune_table_with_range::<tables::BlockBodyIndices>(
+                block_range.clone(),
+                usize::MAX,
+                |_| false,
+                |_| {},
+            )?
+            .0;
+        deleted += provider
+            .prune_table_with_range::<tables::BlockOmmers>(
+                block_range.clone(),
+                usize::MAX,
+                |_| false,
+                |_| {},
+            )?
+            .0;
+        deleted += provider
+            .prune_table_with_range::<tables::BlockWithdrawals>(
+                block_range.clone(),
+                usize::MAX,
+                |_| false,
+                |_| {},
+            )?
+            .0;
+        trace!(target: "pruner", %deleted, ?block_range, "Pruned block bodies (block tables)");
+
+        provider.save_prune_checkpoint(
+            PrunePart::BlockBodies,
+            PruneCheckpoint {
+                // If there's more transactions to prune, set the checkpoint block number to
+                // previous, so we could finish pruning its transactions on the next run.
+                block_number: if done { Some(to_block) } else { last_pruned_block.checked_sub(1) },
+                tx_number: last_pruned_transaction,
+                prune_mode,
+            },
+        )?;
+
+        Ok(done)
+    }
+
     /// Prune history indices up to the provided block, inclusive.
     ///
     /// Returns total number of processed (walked) and deleted entities.
@@ -903,18 +1220,21 @@ mod tests {
         cursor::DbCursorRO, tables, test_utils::create_test_rw_db, transaction::DbTx,
         BlockNumberList,
     };
-    use reth_interfaces::test_utils::{
-        generators,
-        generators::{
-            random_block_range, random_changeset_range, random_eoa_account,
-            random_eoa_account_range, random_log, random_receipt,
+    use reth_interfaces::{
+        provider::ProviderError,
+        test_utils::{
+            generators,
+            generators::{
+                random_block_range, random_changeset_range, random_eoa_account,
+                random_eoa_account_range, random_log, random_receipt,
+            },
         },
     };
     use reth_primitives::{
         BlockNumber, PruneBatchSizes, PruneCheckpoint, PruneMode, PruneModes, PrunePart,
-        ReceiptsLogPruneConfig, TxNumber, H256, MAINNET,
+        ReceiptsLogFilter, ReceiptsLogPruneConfig, TxNumber, H256, MAINNET,
     };
-    use reth_provider::{PruneCheckpointReader, TransactionsProvider};
+    use reth_provider::{BlockReader, PruneCheckpointReader, TransactionsProvider};
     use reth_stages::test_utils::TestTransaction;
     use std::{collections::BTreeMap, ops::AddAssign};
 
@@ -937,6 +1257,71 @@ mod tests {
         assert!(pruner.is_pruning_needed(third_block_number));
     }
 
+    #[test]
+    fn estimate() {
+        let tx = TestTransaction::default();
+        let mut rng = generators::rng();
+
+        let tip = 100;
+        let blocks = random_block_range(&mut rng, 0..=tip, H256::zero(), 1..5);
+        tx.insert_blocks(blocks.iter(), None).expect("insert blocks");
+
+        let mut receipts = Vec::new();
+        for block in &blocks {
+            for transaction in &block.body {
+                receipts
+                    .push((receipts.len() as u64, random_receipt(&mut rng, transaction, Some(0))));
+            }
+        }
+        tx.insert_receipts(receipts).expect("insert receipts");
+
+        let receipts_before_block = 20;
+        let mut pruner = Pruner::new(
+            tx.inner_raw(),
+            MAINNET.clone(),
+            1,
+            PruneModes {
+                receipts: Some(PruneMode::Before(receipts_before_block)),
+                sender_recovery: Some(PruneMode::Full),
+                ..Default::default()
+            },
+            PruneBatchSizes::default().with_receipts(10).with_transaction_senders(10),
+        );
+
+        let transactions_before = |block_number: BlockNumber| {
+            blocks
+                .iter()
+                .filter(|block| block.number < block_number)
+                .map(|block| block.body.len())
+                .sum::<usize>()
+        };
+
+        assert_eq!(
+            pruner.estimate(tip).unwrap(),
+            BTreeMap::from([
+                (PrunePart::Receipts, transactions_before(receipts_before_block)),
+                (PrunePart::SenderRecovery, transactions_before(tip + 1)),
+            ])
+        );
+
+        let mut done = false;
+        while !done {
+            done = pruner.run(tip).unwrap();
+
+            let remaining_receipts = tx.table::<tables::Receipts>().unwrap().len() -
+                (transactions_before(tip + 1) - transactions_before(receipts_before_block));
+            assert_eq!(
+                pruner.estimate(tip).unwrap().get(&PrunePart::Receipts),
+                Some(&remaining_receipts)
+            );
+        }
+
+        assert_eq!(
+            pruner.estimate(tip).unwrap(),
+            BTreeMap::from([(PrunePart::Receipts, 0), (PrunePart::SenderRecovery, 0)])
+        );
+    }
+
     #[test]
     fn prune_receipts() {
         let tx = TestTransaction::default();
@@ -1353,6 +1738,160 @@ mod tests {
         test_prune(2000, 3, true);
     }
 
+    #[test]
+    fn prune_block_bodies_ahead_of_receipts() {
+        let tx = TestTransaction::default();
+        let mut rng = generators::rng();
+
+        let tip = 100;
+        let blocks = random_block_range(&mut rng, 0..=tip, H256::zero(), 1..10);
+        tx.insert_blocks(blocks.iter(), None).expect("insert blocks");
+
+        let mut receipts = Vec::new();
+        for block in &blocks {
+            for transaction in &block.body {
+                receipts
+                    .push((receipts.len() as u64, random_receipt(&mut rng, transaction, Some(0))));
+            }
+        }
+        tx.insert_receipts(receipts).expect("insert receipts");
+
+        let prune = |receipts_before: BlockNumber| {
+            let mut pruner = Pruner::new(
+                tx.inner_raw(),
+                MAINNET.clone(),
+                1,
+                PruneModes {
+                    receipts: Some(PruneMode::Before(receipts_before)),
+                    block_bodies: Some(PruneMode::Before(31)),
+                    ..Default::default()
+                },
+                PruneBatchSizes::default().with_receipts(10).with_block_bodies(10),
+            );
+            while !pruner.run(tip).expect("prune") {}
+
+            let provider = tx.inner();
+            (
+                provider
+                    .get_prune_checkpoint(PrunePart::Receipts)
+                    .unwrap()
+                    .and_then(|checkpoint| checkpoint.block_number),
+                provider
+                    .get_prune_checkpoint(PrunePart::BlockBodies)
+                    .unwrap()
+                    .and_then(|checkpoint| checkpoint.block_number),
+            )
+        };
+
+        // Block bodies are only pruned as far as the receipts, so that the receipts can still look
+        // up the transactions of the blocks they have yet to prune.
+        assert_eq!(prune(11), (Some(10), Some(10)));
+        assert_eq!(prune(21), (Some(20), Some(20)));
+        assert_eq!(
+            tx.table::<tables::Receipts>().unwrap().len(),
+            blocks.iter().skip(21).map(|block| block.body.len()).sum::<usize>()
+        );
+        assert_eq!(tx.table::<tables::BlockBodyIndices>().unwrap().len(), blocks.len() - 21);
+    }
+
+    #[test]
+    fn prune_block_bodies() {
+        let tx = TestTransaction::default();
+        let mut rng = generators::rng();
+
+        let blocks = random_block_range(&mut rng, 0..=100, H256::zero(), 0..10);
+        tx.insert_blocks(blocks.iter(), None).expect("insert blocks");
+        tx.insert_tx_hash_numbers(
+            blocks
+                .iter()
+                .flat_map(|block| &block.body)
+                .enumerate()
+                .map(|(tx_num, transaction)| (transaction.hash, tx_num as TxNumber)),
+        )
+        .expect("insert tx hash numbers");
+
+        let to_block = 20;
+        let prune_mode = PruneMode::Before(to_block + 1);
+        let pruner = Pruner::new(
+            tx.inner_raw(),
+            MAINNET.clone(),
+            1,
+            PruneModes { block_bodies: Some(prune_mode), ..Default::default() },
+            // Less than total amount of blocks to prune to test the batching logic
+            PruneBatchSizes::default().with_block_bodies(10),
+        );
+
+        let run_prune = || {
+            let provider = tx.inner_rw();
+            let result = pruner.prune_block_bodies(&provider, to_block, prune_mode);
+            assert_matches!(result, Ok(_));
+            let done = result.unwrap();
+            provider.commit().expect("commit");
+
+            // Bodies are never partially visible, every block either has all of its transactions
+            // or no body indices at all.
+            let provider = tx.inner();
+            for block in &blocks {
+                if let Some(indices) = provider.block_body_indices(block.number).unwrap() {
+                    assert_eq!(
+                        provider.transactions_by_tx_range(indices.tx_num_range()).unwrap().len(),
+                        block.body.len()
+                    );
+                }
+            }
+
+            done
+        };
+
+        while !run_prune() {}
+
+        assert_eq!(
+            tx.table::<tables::Transactions>().unwrap().len(),
+            blocks.iter().skip(to_block as usize + 1).map(|block| block.body.len()).sum::<usize>()
+        );
+        assert_eq!(
+            tx.table::<tables::BlockBodyIndices>().unwrap().len(),
+            blocks.len() - (to_block as usize + 1)
+        );
+        assert_eq!(
+            tx.inner()
+                .get_prune_checkpoint(PrunePart::BlockBodies)
+                .unwrap()
+                .and_then(|checkpoint| checkpoint.block_number),
+            Some(to_block)
+        );
+
+        let provider = tx.inner();
+        assert_matches!(
+            provider.block(to_block.into()),
+            Err(reth_interfaces::Error::Provider(ProviderError::BlockBodyPruned(number))) if number == to_block
+        );
+        assert_matches!(provider.block((to_block + 1).into()), Ok(Some(_)));
+
+        // The hash lookup of expired transactions is kept, so it has to report the expiry.
+        let pruned_tx = blocks[..=to_block as usize]
+            .iter()
+            .flat_map(|block| &block.body)
+            .next()
+            .expect("pruned transaction")
+            .hash;
+        assert_matches!(
+            provider.transaction_by_hash(pruned_tx),
+            Err(reth_interfaces::Error::Provider(ProviderError::BlockBodyPruned(_)))
+        );
+        assert_matches!(
+            provider.transaction_by_hash_with_meta(pruned_tx),
+            Err(reth_interfaces::Error::Provider(ProviderError::BlockBodyPruned(_)))
+        );
+        let kept_tx = blocks[to_block as usize + 1..]
+            .iter()
+            .flat_map(|block| &block.body)
+            .next()
+            .expect("kept transaction")
+            .hash;
+        assert_matches!(provider.transaction_by_hash(kept_tx), Ok(Some(_)));
+    }
+
     #[test]
     fn prune_storage_history() {
         let tx = TestTransaction::default();
@@ -1525,8 +2064,10 @@ mod tests {
 
             let prune_before_block: usize = 20;
             let prune_mode = PruneMode::Before(prune_before_block as u64);
-            let receipts_log_filter =
-                ReceiptsLogPruneConfig(BTreeMap::from([(deposit_contract_addr, prune_mode)]));
+            let receipts_log_filter = ReceiptsLogPruneConfig(BTreeMap::from([(
+                deposit_contract_addr.into(),
+                prune_mode,
+            )]));
             let pruner = Pruner::new(
                 tx.inner_raw(),
                 MAINNET.clone(),
@@ -1579,4 +2120,99 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn prune_receipts_by_log_topics() {
+        let tx = TestTransaction::default();
+        let mut rng = generators::rng();
+
+        let tip = 300;
+        let blocks = random_block_range(&mut rng, 0..=tip, H256::zero(), 1..5);
+        tx.insert_blocks(blocks.iter(), None).expect("insert blocks");
+
+        let (deposit_contract_addr, _) = random_eoa_account(&mut rng);
+        let transfer_topic = H256::from_low_u64_be(1);
+        let deposit_topic = H256::from_low_u64_be(2);
+
+        // The last transaction of every block emits a transfer event from a random contract, and
+        // the first one emits a deposit event from the deposit contract.
+        let mut receipts = Vec::new();
+        let mut transfers = Vec::new();
+        for block in &blocks {
+            for (txi, transaction) in block.body.iter().enumerate() {
+                let mut receipt = random_receipt(&mut rng, transaction, Some(1));
+                if txi == 0 {
+                    let mut log = random_log(&mut rng, Some(deposit_contract_addr), Some(1));
+                    log.topics[0] = deposit_topic;
+                    receipt.logs.push(log);
+                }
+                if txi == block.body.len() - 1 {
+                    let mut log = random_log(&mut rng, None, Some(3));
+                    log.topics[0] = transfer_topic;
+                    receipt.logs.push(log);
+                    transfers.push((block.number, receipts.len() as u64));
+                }
+                receipts.push((receipts.len() as u64, receipt));
+            }
+        }
+        tx.insert_receipts(receipts).expect("insert receipts");
+
+        let transfers_before_block = 20;
+        let deposits_distance = 200;
+        let receipts_log_filter = ReceiptsLogPruneConfig(BTreeMap::from([
+            (ReceiptsLogFilter::topic0(transfer_topic), PruneMode::Before(transfers_before_block)),
+            (
+                ReceiptsLogFilter::address_and_topic0(deposit_contract_addr, deposit_topic),
+                PruneMode::Distance(deposits_distance),
+            ),
+        ]));
+
+        let run_prune = || {
+            let provider = tx.inner_rw();
+            let pruner = Pruner::new(
+                tx.inner_raw(),
+                MAINNET.clone(),
+                5,
+                PruneModes {
+                    receipts_log_filter: receipts_log_filter.clone(),
+                    ..Default::default()
+                },
+                PruneBatchSizes::default().with_receipts(10),
+            );
+
+            let result = pruner.prune_receipts_by_logs(&provider, tip);
+            assert_matches!(result, Ok(_));
+            provider.commit().expect("commit");
+            result.unwrap()
+        };
+
+        while !run_prune() {}
+
+        let provider = tx.inner();
+        let mut cursor = provider.tx_ref().cursor_read::<tables::Receipts>().unwrap();
+        for receipt in cursor.walk(None).unwrap() {
+            let (tx_num, receipt) = receipt.unwrap();
+            let block = provider.transaction_block(tx_num).unwrap().unwrap();
+
+            // Each receipt is retained by one of the filters, or is part of the unprunable
+            // receipts set by tip - 128
+            assert!(
+                (block >= transfers_before_block &&
+                    receipt.logs.iter().any(|log| log.topics[0] == transfer_topic)) ||
+                    (block > tip - deposits_distance &&
+                        receipt.logs.iter().any(|log| {
+                            log.address == deposit_contract_addr && log.topics[0] == deposit_topic
+                        })) ||
+                    block > tip - 128,
+            );
+        }
+
+        // Receipts of all transfers are retained from the configured block.
+        for (block, tx_num) in transfers {
+            assert_eq!(
+                provider.tx_ref().get::<tables::Receipts>(tx_num).unwrap().is_some(),
+                block >= transfers_before_block || block > tip - 128
+            );
+        }
+    }
 }
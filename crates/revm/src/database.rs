@@ -1,11 +1,19 @@
+use parking_lot::RwLock;
 use reth_interfaces::Error;
-use reth_primitives::{H160, H256, KECCAK_EMPTY, U256};
-use reth_provider::StateProvider;
+use reth_metrics::{metrics::Counter, Metrics};
+use reth_primitives::{
+    Account, Address, BlockNumber, Bytecode as RethBytecode, Bytes, StorageKey, StorageValue, H160,
+    H256, KECCAK_EMPTY, U256,
+};
+use reth_provider::{
+    AccountReader, BlockHashReader, BundleStateWithReceipts, StateProvider, StateRootProvider,
+};
 use revm::{
     db::{CacheDB, DatabaseRef},
     primitives::{AccountInfo, Bytecode},
     Database, StateDBBox,
 };
+use std::{collections::HashMap, sync::Arc};
 
 /// SubState of database. Uses revm internal cache with binding to reth StateProvider trait.
 pub type SubState<DB> = CacheDB<StateProviderDatabase<DB>>;
@@ -102,3 +110,167 @@ impl<DB: StateProvider> DatabaseRef for StateProviderDatabase<DB> {
         Ok(self.0.block_hash(number.try_into().unwrap())?.unwrap_or_default())
     }
 }
+
+/// A cache of state read from the database, shared between the executor and the threads that
+/// prefetch the state of upcoming blocks.
+///
+/// The cache holds the values of the state the [CachedStateProvider] reads from, so it must be
+/// discarded once that state changes.
+#[derive(Debug, Clone, Default)]
+pub struct StateCache(Arc<StateCacheInner>);
+
+#[derive(Debug, Default)]
+struct StateCacheInner {
+    accounts: RwLock<HashMap<Address, Option<Account>>>,
+    storage: RwLock<HashMap<(Address, StorageKey), StorageValue>>,
+    bytecodes: RwLock<HashMap<H256, Option<RethBytecode>>>,
+    metrics: StateCacheMetrics,
+}
+
+impl StateCache {
+    /// Returns the cached account, `Some(None)` if the account is cached as non-existent.
+    pub fn account(&self, address: &Address) -> Option<Option<Account>> {
+        self.0.accounts.read().get(address).copied()
+    }
+
+    /// Caches an account.
+    pub fn insert_account(&self, address: Address, account: Option<Account>) {
+        self.0.accounts.write().insert(address, account);
+        self.0.metrics.prefetched_entries.increment(1);
+    }
+
+    /// Returns the cached storage value.
+    pub fn storage(&self, address: Address, key: StorageKey) -> Option<StorageValue> {
+        self.0.storage.read().get(&(address, key)).copied()
+    }
+
+    /// Caches a storage value.
+    pub fn insert_storage(&self, address: Address, key: StorageKey, value: StorageValue) {
+        self.0.storage.write().insert((address, key), value);
+        self.0.metrics.prefetched_entries.increment(1);
+    }
+
+    /// Returns the cached bytecode, `Some(None)` if the bytecode is cached as non-existent.
+    pub fn bytecode(&self, code_hash: &H256) -> Option<Option<RethBytecode>> {
+        self.0.bytecodes.read().get(code_hash).cloned()
+    }
+
+    /// Returns `true` if the bytecode is cached.
+    pub fn contains_bytecode(&self, code_hash: &H256) -> bool {
+        self.0.bytecodes.read().contains_key(code_hash)
+    }
+
+    /// Caches a bytecode.
+    pub fn insert_bytecode(&self, code_hash: H256, bytecode: Option<RethBytecode>) {
+        self.0.bytecodes.write().insert(code_hash, bytecode);
+        self.0.metrics.prefetched_entries.increment(1);
+    }
+}
+
+/// Metrics of the [StateCache].
+///
+/// The hit rate is the number of hits divided by the number of lookups of each kind.
+#[derive(Metrics)]
+#[metrics(scope = "executor.state_cache")]
+struct StateCacheMetrics {
+    /// The number of entries loaded into the cache.
+    prefetched_entries: Counter,
+    /// The number of account lookups served from the cache.
+    account_hits: Counter,
+    /// The number of account lookups that missed the cache.
+    account_misses: Counter,
+    /// The number of storage lookups served from the cache.
+    storage_hits: Counter,
+    /// The number of storage lookups that missed the cache.
+    storage_misses: Counter,
+    /// The number of bytecode lookups served from the cache.
+    bytecode_hits: Counter,
+    /// The number of bytecode lookups that missed the cache.
+    bytecode_misses: Counter,
+}
+
+/// A [StateProvider] that serves accounts, storage and bytecodes from a [StateCache] before
+/// falling back to the wrapped provider.
+///
+/// Wrapped by [StateProviderDatabase], this puts the prefetched state behind the revm database
+/// adapter.
+#[derive(Debug)]
+pub struct CachedStateProvider<SP> {
+    inner: SP,
+    cache: StateCache,
+}
+
+impl<SP: StateProvider> CachedStateProvider<SP> {
+    /// Creates a new provider reading through the cache.
+    pub fn new(inner: SP, cache: StateCache) -> Self {
+        Self { inner, cache }
+    }
+
+    /// Returns the cache of the provider.
+    pub fn cache(&self) -> &StateCache {
+        &self.cache
+    }
+}
+
+impl<SP: StateProvider> BlockHashReader for CachedStateProvider<SP> {
+    fn block_hash(&self, number: BlockNumber) -> reth_interfaces::Result<Option<H256>> {
+        self.inner.block_hash(number)
+    }
+
+    fn canonical_hashes_range(
+        &self,
+        start: BlockNumber,
+        end: BlockNumber,
+    ) -> reth_interfaces::Result<Vec<H256>> {
+        self.inner.canonical_hashes_range(start, end)
+    }
+}
+
+impl<SP: StateProvider> AccountReader for CachedStateProvider<SP> {
+    fn basic_account(&self, address: Address) -> reth_interfaces::Result<Option<Account>> {
+        if let Some(account) = self.cache.account(&address) {
+            self.cache.0.metrics.account_hits.increment(1);
+            return Ok(account)
+        }
+        self.cache.0.metrics.account_misses.increment(1);
+        self.inner.basic_account(address)
+    }
+}
+
+impl<SP: StateProvider> StateRootProvider for CachedStateProvider<SP> {
+    fn state_root(&self, post_state: BundleStateWithReceipts) -> reth_interfaces::Result<H256> {
+        self.inner.state_root(post_state)
+    }
+}
+
+impl<SP: StateProvider> StateProvider for CachedStateProvider<SP> {
+    fn storage(
+        &self,
+        account: Address,
+        storage_key: StorageKey,
+    ) -> reth_interfaces::Result<Option<StorageValue>> {
+        if let Some(value) = self.cache.storage(account, storage_key) {
+            self.cache.0.metrics.storage_hits.increment(1);
+            return Ok(Some(value))
+        }
+        self.cache.0.metrics.storage_misses.increment(1);
+        self.inner.storage(account, storage_key)
+    }
+
+    fn bytecode_by_hash(&self, code_hash: H256) -> reth_interfaces::Result<Option<RethBytecode>> {
+        if let Some(bytecode) = self.cache.bytecode(&code_hash) {
+            self.cache.0.metrics.bytecode_hits.increment(1);
+            return Ok(bytecode)
+        }
+        self.cache.0.metrics.bytecode_misses.increment(1);
+        self.inner.bytecode_by_hash(code_hash)
+    }
+
+    fn proof(
+        &self,
+        address: Address,
+        keys: &[H256],
+    ) -> reth_interfaces::Result<(Vec<Bytes>, H256, Vec<Vec<Bytes>>)> {
+        self.inner.proof(address, keys)
+    }
+}
@@ -12,12 +12,13 @@ use std::sync::Arc;
 pub struct Factory {
     chain_spec: Arc<ChainSpec>,
     stack: Option<InspectorStack>,
+    parallel_execution: bool,
 }
 
 impl Factory {
     /// Create new factory
     pub fn new(chain_spec: Arc<ChainSpec>) -> Self {
-        Self { chain_spec, stack: None }
+        Self { chain_spec, stack: None, parallel_execution: false }
     }
 
     /// Sets the inspector stack for all generated executors.
@@ -31,6 +32,13 @@ impl Factory {
         self.stack = Some(InspectorStack::new(config));
         self
     }
+
+    /// Enables or disables the parallel execution of the transactions of a block for all
+    /// generated executors.
+    pub fn with_parallel_execution(mut self, enabled: bool) -> Self {
+        self.parallel_execution = enabled;
+        self
+    }
 }
 
 impl ExecutorFactory for Factory {
@@ -43,6 +51,7 @@ impl ExecutorFactory for Factory {
         if let Some(ref stack) = self.stack {
             evm.set_stack(stack.clone());
         }
+        evm.set_parallel_execution(self.parallel_execution);
         evm
     }
 
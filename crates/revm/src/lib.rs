@@ -22,6 +22,12 @@ mod factory;
 /// new revm account state executor
 pub mod processor;
 
+/// Prefetching of the state that blocks are likely to touch.
+pub mod prefetch;
+
+/// Optimistic parallel execution of the transactions of a block.
+pub mod parallel;
+
 /// State changes that are not related to transactions.
 pub mod state_change;
 
@@ -0,0 +1,284 @@
+use crate::env::fill_tx_env;
+use parking_lot::Mutex;
+use rayon::prelude::*;
+use reth_primitives::{Address, TransactionSigned, H256, U256};
+use revm::{
+    interpreter::{opcode, InstructionResult, Interpreter},
+    primitives::{AccountInfo, Bytecode, Env, ResultAndState},
+    Database, EVMData, Inspector, EVM,
+};
+use std::collections::HashMap;
+
+/// The outcome of a transaction executed speculatively against the state at the start of the
+/// block.
+#[derive(Debug)]
+pub struct SpeculativeExecution {
+    /// The execution result, `None` if the execution failed.
+    pub result: Option<ResultAndState>,
+    /// The state read during the execution.
+    pub reads: ReadSet,
+    /// Whether the execution depended on the balance of the block beneficiary.
+    ///
+    /// Every transaction pays its fee to the beneficiary, so the beneficiary balance is only
+    /// treated as a conflict if the transaction observed it.
+    pub observed_beneficiary: bool,
+}
+
+impl SpeculativeExecution {
+    /// Validates the speculative execution against the current state and returns its result if
+    /// all values read by the transaction are unchanged.
+    ///
+    /// The beneficiary balance increment of the speculative result is rebased on the current
+    /// beneficiary balance, so the returned state can be committed as if the transaction had been
+    /// executed in order.
+    pub fn validate<DB: Database>(
+        self,
+        db: &mut DB,
+        beneficiary: Address,
+    ) -> Result<Option<ResultAndState>, DB::Error> {
+        let Some(mut result) = self.result else { return Ok(None) };
+        if self.observed_beneficiary {
+            return Ok(None)
+        }
+
+        for (address, read) in &self.reads.accounts {
+            let current = db.basic(*address)?;
+            let unchanged = if *address == beneficiary {
+                same_account_except_balance(read.as_ref(), current.as_ref())
+            } else {
+                same_account(read.as_ref(), current.as_ref())
+            };
+            if !unchanged {
+                return Ok(None)
+            }
+        }
+        for ((address, index), read) in &self.reads.storage {
+            if db.storage(*address, *index)? != *read {
+                return Ok(None)
+            }
+        }
+
+        // rebase the fee payment on the current beneficiary balance
+        if let (Some(Some(read)), Some(account)) =
+            (self.reads.accounts.get(&beneficiary), result.state.get_mut(&beneficiary))
+        {
+            let Some(increment) = account.info.balance.checked_sub(read.balance) else {
+                return Ok(None)
+            };
+            let current = db.basic(beneficiary)?.map(|info| info.balance).unwrap_or_default();
+            account.info.balance = current + increment;
+        }
+
+        Ok(Some(result))
+    }
+}
+
+/// The accounts and storage slots read by a transaction, along with the values it observed.
+#[derive(Debug, Default)]
+pub struct ReadSet {
+    /// The accounts read, without their bytecode.
+    pub accounts: HashMap<Address, Option<AccountInfo>>,
+    /// The storage slots read.
+    pub storage: HashMap<(Address, U256), U256>,
+}
+
+/// Executes the transactions of a block in parallel, each against the state of `db`.
+///
+/// None of the results are committed. The results are only equal to an in order execution if
+/// they pass [SpeculativeExecution::validate] once the preceding transactions are committed.
+///
+/// All workers read through a single lock on `db`, so only the EVM computation runs in parallel.
+/// If the state of the block is not cached yet, the workers mostly wait for each other's database
+/// reads and there is no speedup over sequential execution.
+pub fn execute_speculatively<DB>(
+    db: &mut DB,
+    env: &Env,
+    transactions: &[TransactionSigned],
+    senders: &[Address],
+) -> Vec<SpeculativeExecution>
+where
+    DB: Database + Send,
+{
+    let beneficiary = env.block.coinbase;
+    let base = Mutex::new(db);
+    transactions
+        .par_iter()
+        .zip(senders.par_iter())
+        .map(|(transaction, sender)| {
+            let mut env = env.clone();
+            fill_tx_env(&mut env.tx, transaction, *sender);
+
+            let mut evm = EVM::with_env(env);
+            evm.database(ReadTrackingDatabase { base: &base, reads: ReadSet::default() });
+            // a transaction sent by the beneficiary depends on its balance
+            let mut observer =
+                BeneficiaryObserver { beneficiary, observed: *sender == beneficiary };
+            let result = evm.inspect(&mut observer).ok();
+
+            SpeculativeExecution {
+                result,
+                reads: evm.db.take().map(|db| db.reads).unwrap_or_default(),
+                observed_beneficiary: observer.observed,
+            }
+        })
+        .collect()
+}
+
+/// A [Database] that reads from a shared database and records the values it read.
+struct ReadTrackingDatabase<'a, 'b, DB> {
+    base: &'a Mutex<&'b mut DB>,
+    reads: ReadSet,
+}
+
+impl<DB: Database> Database for ReadTrackingDatabase<'_, '_, DB> {
+    type Error = DB::Error;
+
+    fn basic(&mut self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        let info = self.base.lock().basic(address)?;
+        self.reads.accounts.entry(address).or_insert_with(|| {
+            info.as_ref().map(|info| AccountInfo { code: None, ..info.clone() })
+        });
+        Ok(info)
+    }
+
+    fn code_by_hash(&mut self, code_hash: H256) -> Result<Bytecode, Self::Error> {
+        // bytecode is addressed by its hash, so it can not conflict
+        self.base.lock().code_by_hash(code_hash)
+    }
+
+    fn storage(&mut self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        let value = self.base.lock().storage(address, index)?;
+        self.reads.storage.entry((address, index)).or_insert(value);
+        Ok(value)
+    }
+
+    fn block_hash(&mut self, number: U256) -> Result<H256, Self::Error> {
+        // block hashes do not change within a block
+        self.base.lock().block_hash(number)
+    }
+}
+
+/// An [Inspector] that detects whether a transaction depends on the balance of the block
+/// beneficiary, either by querying it or by running code of the beneficiary account.
+struct BeneficiaryObserver {
+    beneficiary: Address,
+    observed: bool,
+}
+
+impl<DB: Database> Inspector<DB> for BeneficiaryObserver {
+    fn step(
+        &mut self,
+        interpreter: &mut Interpreter,
+        _data: &mut EVMData<'_, DB>,
+    ) -> InstructionResult {
+        if interpreter.contract.address == self.beneficiary {
+            self.observed = true;
+        } else if interpreter.current_opcode() == opcode::BALANCE {
+            if let Ok(word) = interpreter.stack().peek(0) {
+                let address: Address = H256::from(word.to_be_bytes()).into();
+                self.observed |= address == self.beneficiary;
+            }
+        }
+        InstructionResult::Continue
+    }
+}
+
+fn same_account(a: Option<&AccountInfo>, b: Option<&AccountInfo>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => {
+            a.balance == b.balance && same_account_except_balance(Some(a), Some(b))
+        }
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+/// Compares the accounts ignoring their balance, except for whether it is zero, which affects
+/// whether the account is empty.
+fn same_account_except_balance(a: Option<&AccountInfo>, b: Option<&AccountInfo>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => {
+            a.nonce == b.nonce &&
+                a.code_hash == b.code_hash &&
+                (a.balance == U256::ZERO) == (b.balance == U256::ZERO)
+        }
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use revm::db::{CacheDB, EmptyDB};
+
+    #[test]
+    fn detect_stale_reads() {
+        let beneficiary = Address::from_low_u64_be(1);
+        let account = Address::from_low_u64_be(2);
+        let mut db = CacheDB::new(EmptyDB::default());
+        db.insert_account_info(account, AccountInfo { nonce: 1, ..Default::default() });
+
+        let execution = |reads| SpeculativeExecution {
+            result: Some(ResultAndState {
+                result: revm::primitives::ExecutionResult::Revert {
+                    gas_used: 0,
+                    output: Default::default(),
+                },
+                state: Default::default(),
+            }),
+            reads,
+            observed_beneficiary: false,
+        };
+
+        let mut reads = ReadSet::default();
+        reads.accounts.insert(account, Some(AccountInfo { nonce: 1, ..Default::default() }));
+        assert!(execution(reads).validate(&mut db, beneficiary).unwrap().is_some());
+
+        let mut reads = ReadSet::default();
+        reads.accounts.insert(account, None);
+        assert!(execution(reads).validate(&mut db, beneficiary).unwrap().is_none());
+
+        let mut reads = ReadSet::default();
+        reads.storage.insert((account, U256::from(1)), U256::from(1));
+        assert!(execution(reads).validate(&mut db, beneficiary).unwrap().is_none());
+    }
+
+    #[test]
+    fn beneficiary_balance_is_rebased() {
+        let beneficiary = Address::from_low_u64_be(1);
+        let mut db = CacheDB::new(EmptyDB::default());
+        db.insert_account_info(
+            beneficiary,
+            AccountInfo { balance: U256::from(15), ..Default::default() },
+        );
+
+        let mut state = HashMap::new();
+        state.insert(
+            beneficiary,
+            revm::primitives::Account::from(AccountInfo {
+                balance: U256::from(12),
+                ..Default::default()
+            }),
+        );
+        let mut reads = ReadSet::default();
+        reads.accounts.insert(
+            beneficiary,
+            Some(AccountInfo { balance: U256::from(10), ..Default::default() }),
+        );
+        let execution = SpeculativeExecution {
+            result: Some(ResultAndState {
+                result: revm::primitives::ExecutionResult::Revert {
+                    gas_used: 0,
+                    output: Default::default(),
+                },
+                state: state.into_iter().collect(),
+            }),
+            reads,
+            observed_beneficiary: false,
+        };
+
+        let result = execution.validate(&mut db, beneficiary).unwrap().unwrap();
+        assert_eq!(result.state[&beneficiary].info.balance, U256::from(17));
+    }
+}
@@ -0,0 +1,165 @@
+use crate::database::StateCache;
+use reth_interfaces::Error;
+use reth_primitives::{
+    Address, Block, Header, Transaction, TransactionKind, Withdrawal, H256, KECCAK_EMPTY,
+};
+use reth_provider::StateProvider;
+use std::collections::{HashMap, HashSet};
+
+/// The state a block is likely to touch, extracted from the block without executing it.
+///
+/// The targets cover the block beneficiaries, withdrawal recipients, senders and recipients of
+/// transactions and the entries of their access lists.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PrefetchTargets {
+    /// The accounts to load.
+    pub accounts: HashSet<Address>,
+    /// The accounts to load the bytecode of.
+    pub code: HashSet<Address>,
+    /// The storage slots to load.
+    pub storage: HashMap<Address, HashSet<H256>>,
+}
+
+impl PrefetchTargets {
+    /// Extracts the targets of a block, given the senders of its transactions.
+    pub fn from_block(block: &Block, senders: &[Address]) -> Self {
+        let mut targets = Self::default();
+        targets.extend_with_header(&block.header, &block.ommers);
+        if let Some(withdrawals) = &block.withdrawals {
+            targets.extend_with_withdrawals(withdrawals);
+        }
+        for (transaction, sender) in block.body.iter().zip(senders) {
+            targets.extend_with_transaction(*sender, transaction);
+        }
+        targets
+    }
+
+    /// Adds the beneficiaries of the block and its ommers.
+    pub fn extend_with_header(&mut self, header: &Header, ommers: &[Header]) {
+        self.accounts.insert(header.beneficiary);
+        self.accounts.extend(ommers.iter().map(|ommer| ommer.beneficiary));
+    }
+
+    /// Adds the recipients of the withdrawals.
+    pub fn extend_with_withdrawals(&mut self, withdrawals: &[Withdrawal]) {
+        self.accounts.extend(withdrawals.iter().map(|withdrawal| withdrawal.address));
+    }
+
+    /// Adds the sender, the recipient and the access list of the transaction.
+    pub fn extend_with_transaction(&mut self, sender: Address, transaction: &Transaction) {
+        self.accounts.insert(sender);
+        if let TransactionKind::Call(to) = transaction.kind() {
+            self.accounts.insert(*to);
+            self.code.insert(*to);
+        }
+
+        let access_list = match transaction {
+            Transaction::Legacy(_) => return,
+            Transaction::Eip2930(tx) => &tx.access_list,
+            Transaction::Eip1559(tx) => &tx.access_list,
+            Transaction::Eip4844(tx) => &tx.access_list,
+        };
+        for item in &access_list.0 {
+            self.accounts.insert(item.address);
+            self.code.insert(item.address);
+            if !item.storage_keys.is_empty() {
+                self.storage.entry(item.address).or_default().extend(&item.storage_keys);
+            }
+        }
+    }
+
+    /// Returns the total number of targets.
+    pub fn len(&self) -> usize {
+        self.accounts.len() + self.storage.values().map(HashSet::len).sum::<usize>()
+    }
+
+    /// Returns `true` if there are no targets.
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty() && self.storage.is_empty()
+    }
+
+    /// Loads the targets from the provider into the cache, skipping entries that are already
+    /// cached.
+    pub fn load_into<SP: StateProvider>(
+        &self,
+        provider: &SP,
+        cache: &StateCache,
+    ) -> Result<(), Error> {
+        for address in &self.accounts {
+            let account = match cache.account(address) {
+                Some(account) => account,
+                None => {
+                    let account = provider.basic_account(*address)?;
+                    cache.insert_account(*address, account);
+                    account
+                }
+            };
+
+            if !self.code.contains(address) {
+                continue
+            }
+            if let Some(code_hash) = account.and_then(|account| account.bytecode_hash) {
+                if code_hash != KECCAK_EMPTY && !cache.contains_bytecode(&code_hash) {
+                    cache.insert_bytecode(code_hash, provider.bytecode_by_hash(code_hash)?);
+                }
+            }
+        }
+
+        for (address, keys) in &self.storage {
+            for key in keys {
+                if cache.storage(*address, *key).is_none() {
+                    let value = provider.storage(*address, *key)?.unwrap_or_default();
+                    cache.insert_storage(*address, *key, value);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reth_primitives::{
+        AccessList, AccessListItem, Signature, TransactionSigned, TxEip1559, TxLegacy,
+    };
+
+    #[test]
+    fn extract_block_targets() {
+        let sender = Address::from_low_u64_be(1);
+        let to = Address::from_low_u64_be(2);
+        let listed = Address::from_low_u64_be(3);
+        let slot = H256::from_low_u64_be(4);
+        let beneficiary = Address::from_low_u64_be(5);
+        let withdrawal = Address::from_low_u64_be(6);
+
+        let legacy =
+            Transaction::Legacy(TxLegacy { to: TransactionKind::Create, ..Default::default() });
+        let eip1559 = Transaction::Eip1559(TxEip1559 {
+            to: TransactionKind::Call(to),
+            access_list: AccessList(vec![AccessListItem {
+                address: listed,
+                storage_keys: vec![slot],
+            }]),
+            ..Default::default()
+        });
+        let block = Block {
+            header: Header { beneficiary, ..Default::default() },
+            body: [legacy, eip1559]
+                .into_iter()
+                .map(|tx| {
+                    TransactionSigned::from_transaction_and_signature(tx, Signature::default())
+                })
+                .collect(),
+            ommers: vec![],
+            withdrawals: Some(vec![Withdrawal { address: withdrawal, ..Default::default() }]),
+        };
+
+        let targets = PrefetchTargets::from_block(&block, &[sender, sender]);
+        assert_eq!(targets.accounts, HashSet::from([sender, to, listed, beneficiary, withdrawal]));
+        assert_eq!(targets.code, HashSet::from([to, listed]));
+        assert_eq!(targets.storage, HashMap::from([(listed, HashSet::from([slot]))]));
+        assert_eq!(targets.len(), 6);
+    }
+}
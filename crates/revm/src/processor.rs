@@ -3,6 +3,7 @@ use crate::{
     env::{fill_cfg_and_block_env, fill_tx_env},
     eth_dao_fork::{DAO_HARDFORK_BENEFICIARY, DAO_HARDKFORK_ACCOUNTS},
     into_reth_log,
+    parallel::execute_speculatively,
     stack::{InspectorStack, InspectorStackConfig},
     state_change::post_block_balance_increments,
 };
@@ -12,8 +13,8 @@ use reth_interfaces::{
 };
 use reth_primitives::{
     Address, Block, BlockNumber, Bloom, ChainSpec, Hardfork, Header, PruneMode, PruneModes,
-    PrunePartError, Receipt, ReceiptWithBloom, TransactionSigned, H256, MINIMUM_PRUNING_DISTANCE,
-    U256,
+    PrunePartError, Receipt, ReceiptWithBloom, ReceiptsLogFilter, ReceiptsLogPruneConfig,
+    TransactionSigned, H256, MINIMUM_PRUNING_DISTANCE, U256,
 };
 use reth_provider::{
     BlockExecutor, BlockExecutorStats, BundleStateWithReceipts, PrunableBlockExecutor,
@@ -62,12 +63,14 @@ pub struct EVMProcessor<'a> {
     tip: Option<BlockNumber>,
     /// Pruning configuration.
     prune_modes: PruneModes,
-    /// Memoized address pruning filter.
-    /// Empty implies that there is going to be addresses to include in the filter in a future
-    /// block. None means there isn't any kind of configuration.
-    pruning_address_filter: Option<(u64, Vec<Address>)>,
+    /// Memoized log pruning filter, along with the next block whose filters are not included yet.
+    /// Empty implies that there is going to be filters to include in a future block. None means
+    /// there isn't any kind of configuration.
+    pruning_log_filter: Option<(u64, Vec<ReceiptsLogFilter>)>,
     /// Execution stats
     stats: BlockExecutorStats,
+    /// Whether the transactions of a block are executed speculatively in parallel.
+    parallel_execution: bool,
 }
 
 impl<'a> EVMProcessor<'a> {
@@ -87,8 +90,9 @@ impl<'a> EVMProcessor<'a> {
             first_block: None,
             tip: None,
             prune_modes: PruneModes::none(),
-            pruning_address_filter: None,
+            pruning_log_filter: None,
             stats: BlockExecutorStats::default(),
+            parallel_execution: false,
         }
     }
 
@@ -117,8 +121,9 @@ impl<'a> EVMProcessor<'a> {
             first_block: None,
             tip: None,
             prune_modes: PruneModes::none(),
-            pruning_address_filter: None,
+            pruning_log_filter: None,
             stats: BlockExecutorStats::default(),
+            parallel_execution: false,
         }
     }
 
@@ -127,6 +132,13 @@ impl<'a> EVMProcessor<'a> {
         self.stack = stack;
     }
 
+    /// Enables or disables the parallel execution of the transactions of a block.
+    ///
+    /// See [EVMProcessor::execute_transactions_parallel].
+    pub fn set_parallel_execution(&mut self, enabled: bool) {
+        self.parallel_execution = enabled;
+    }
+
     /// Returns a reference to the database
     pub fn db_mut(&mut self) -> &mut StateDBBox<'a, Error> {
         // Option will be removed from EVM in the future.
@@ -265,6 +277,12 @@ impl<'a> EVMProcessor<'a> {
 
         self.init_env(&block.header, total_difficulty);
 
+        if self.parallel_execution &&
+            !block.body.iter().any(|tx| self.stack.should_inspect(&self.evm.env, tx.hash()))
+        {
+            return self.execute_transactions_parallel(block, senders)
+        }
+
         let mut cumulative_gas_used = 0;
         let mut receipts = Vec::with_capacity(block.body.len());
         for (transaction, sender) in block.body.iter().zip(senders) {
@@ -311,6 +329,96 @@ impl<'a> EVMProcessor<'a> {
         Ok((receipts, cumulative_gas_used))
     }
 
+    /// Runs the transactions of the block speculatively in parallel and commits them in order.
+    ///
+    /// Every transaction is first executed against the state at the start of the block while
+    /// recording the state it reads. The results are then committed in order: a result is only
+    /// committed if none of the values read by the transaction were changed by the preceding
+    /// transactions, otherwise the transaction is executed again on top of them. This produces
+    /// the same state changes and receipts as [EVMProcessor::execute_transactions].
+    ///
+    /// The speculative executions share the database of the processor, so their reads are
+    /// serialized. This is only faster than sequential execution if the state of the block is
+    /// already cached, e.g. by the prefetcher, and the transactions rarely conflict.
+    ///
+    /// Assumes the environment has been filled via `init_env`.
+    fn execute_transactions_parallel(
+        &mut self,
+        block: &Block,
+        senders: Vec<Address>,
+    ) -> Result<(Vec<Receipt>, u64), BlockExecutionError> {
+        let time = Instant::now();
+        let env = self.evm.env.clone();
+        let speculative = execute_speculatively(self.db_mut(), &env, &block.body, &senders);
+        self.stats.execution_duration += time.elapsed();
+
+        let mut cumulative_gas_used = 0;
+        let mut reexecuted = 0;
+        let mut receipts = Vec::with_capacity(block.body.len());
+        for ((transaction, sender), speculative) in block.body.iter().zip(senders).zip(speculative)
+        {
+            // The sum of the transaction’s gas limit, Tg, and the gas utilized in this block prior,
+            // must be no greater than the block’s gasLimit.
+            let block_available_gas = block.header.gas_limit - cumulative_gas_used;
+            if transaction.gas_limit() > block_available_gas {
+                return Err(BlockValidationError::TransactionGasLimitMoreThanAvailableBlockGas {
+                    transaction_gas_limit: transaction.gas_limit(),
+                    block_available_gas,
+                }
+                .into())
+            }
+
+            let validated = speculative
+                .validate(self.db_mut(), block.beneficiary)
+                .map_err(|_| BlockExecutionError::ProviderError)?;
+            let ResultAndState { result, state } = match validated {
+                Some(result_and_state) => result_and_state,
+                None => {
+                    // The transaction conflicts with a preceding one, execute it again.
+                    let time = Instant::now();
+                    reexecuted += 1;
+                    let result_and_state = self.transact(transaction, sender)?;
+                    self.stats.execution_duration += time.elapsed();
+                    result_and_state
+                }
+            };
+            trace!(
+                target: "evm",
+                ?transaction, ?result, ?state,
+                "Executed transaction"
+            );
+            let time = Instant::now();
+
+            self.db_mut().commit(state);
+
+            self.stats.apply_state_duration += time.elapsed();
+
+            // append gas used
+            cumulative_gas_used += result.gas_used();
+
+            // Push transaction changeset and calculate header bloom filter for receipt.
+            receipts.push(Receipt {
+                tx_type: transaction.tx_type(),
+                // Success flag was added in `EIP-658: Embedding transaction status code in
+                // receipts`.
+                success: result.is_success(),
+                cumulative_gas_used,
+                // convert to reth log
+                logs: result.into_logs().into_iter().map(into_reth_log).collect(),
+            });
+        }
+
+        debug!(
+            target: "evm",
+            number = block.number,
+            transactions = block.body.len(),
+            reexecuted,
+            "Executed transactions in parallel"
+        );
+
+        Ok((receipts, cumulative_gas_used))
+    }
+
     /// Execute the block, verify gas usage and apply post-block state changes.
     fn execute_inner(
         &mut self,
@@ -412,19 +520,21 @@ impl<'a> EVMProcessor<'a> {
         let contract_log_pruner = self.prune_modes.receipts_log_filter.group_by_block(tip, None)?;
 
         if !contract_log_pruner.is_empty() {
-            let (prev_block, filter) = self.pruning_address_filter.get_or_insert((0, Vec::new()));
-            for (_, addresses) in contract_log_pruner.range(*prev_block..=block_number) {
-                filter.extend(addresses.iter().copied());
+            let (next_block, filter) = self.pruning_log_filter.get_or_insert((0, Vec::new()));
+            if *next_block <= block_number {
+                for (_, filters) in contract_log_pruner.range(*next_block..=block_number) {
+                    filter.extend(filters.iter().copied());
+                }
+                *next_block = block_number + 1;
             }
         }
 
         for receipt in receipts.iter_mut() {
             let inner_receipt = receipt.as_ref().expect("receipts have not been pruned");
 
-            // If there is an address_filter, and it does not contain any of the
-            // contract addresses, then remove this receipts
-            if let Some((_, filter)) = &self.pruning_address_filter {
-                if !inner_receipt.logs.iter().any(|log| filter.contains(&log.address)) {
+            // If there is a log filter, and none of the logs match it, then remove this receipt
+            if let Some((_, filter)) = &self.pruning_log_filter {
+                if !ReceiptsLogPruneConfig::matches_any(filter, &inner_receipt.logs) {
                     receipt.take();
                 }
             }
@@ -529,3 +639,80 @@ pub fn verify_receipt<'a>(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reth_primitives::{
+        ChainSpecBuilder, Signature, Transaction, TransactionKind, TxLegacy, MAINNET,
+    };
+    use reth_provider::test_utils::{ExtendedAccount, MockEthProvider};
+
+    fn transfer(nonce: u64, to: Address) -> TransactionSigned {
+        TransactionSigned::from_transaction_and_signature(
+            Transaction::Legacy(TxLegacy {
+                chain_id: Some(1),
+                nonce,
+                gas_price: 2,
+                gas_limit: 21_000,
+                to: TransactionKind::Call(to),
+                value: 1_000,
+                input: Default::default(),
+            }),
+            Signature::default(),
+        )
+    }
+
+    #[test]
+    fn parallel_execution_matches_sequential() {
+        let alice = Address::from_low_u64_be(1);
+        let bob = Address::from_low_u64_be(2);
+        let carol = Address::from_low_u64_be(3);
+        let dave = Address::from_low_u64_be(4);
+        let beneficiary = Address::from_low_u64_be(5);
+
+        let provider = MockEthProvider::default();
+        provider.add_account(alice, ExtendedAccount::new(0, U256::from(1_000_000_000)));
+        // bob can only pay for his transfer with the value he receives from alice
+        provider.add_account(bob, ExtendedAccount::new(0, U256::from(42_000)));
+        provider.add_account(dave, ExtendedAccount::new(0, U256::from(1_000_000_000)));
+
+        // the second transfer of alice depends on her nonce, the transfer of bob on his balance
+        // and the transfer of dave on nothing
+        let block = Block {
+            header: Header {
+                beneficiary,
+                gas_limit: 1_000_000,
+                gas_used: 4 * 21_000,
+                base_fee_per_gas: Some(1),
+                ..Default::default()
+            },
+            body: vec![transfer(0, bob), transfer(1, bob), transfer(0, carol), transfer(0, carol)],
+            ommers: vec![],
+            withdrawals: Some(vec![]),
+        };
+        let senders = vec![alice, alice, bob, dave];
+
+        let chain_spec = Arc::new(
+            ChainSpecBuilder::default()
+                .chain(MAINNET.chain)
+                .genesis(MAINNET.genesis.clone())
+                .shanghai_activated()
+                .build(),
+        );
+        let execute = |parallel_execution| {
+            let mut executor = EVMProcessor::new_with_db(
+                chain_spec.clone(),
+                StateProviderDatabase::new(provider.clone()),
+            );
+            executor.set_parallel_execution(parallel_execution);
+            executor.execute(&block, U256::ZERO, Some(senders.clone())).unwrap();
+            executor.take_output_state()
+        };
+
+        let sequential = execute(false);
+        let parallel = execute(true);
+        assert_eq!(parallel, sequential);
+        assert_eq!(sequential.account(&carol).unwrap().unwrap().balance, U256::from(2_000));
+    }
+}
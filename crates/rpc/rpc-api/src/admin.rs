@@ -1,6 +1,6 @@
 use jsonrpsee::{core::RpcResult, proc_macros::rpc};
 use reth_primitives::NodeRecord;
-use reth_rpc_types::{NodeInfo, PeerInfo};
+use reth_rpc_types::{BanInfo, NodeInfo, PeerInfo};
 
 /// Admin namespace rpc interface that gives access to several non-standard RPC methods.
 #[cfg_attr(not(feature = "client"), rpc(server, namespace = "admin"))]
@@ -28,6 +28,20 @@ pub trait AdminApi {
     #[method(name = "removeTrustedPeer")]
     fn remove_trusted_peer(&self, record: NodeRecord) -> RpcResult<bool>;
 
+    /// Adds the given node record to the reserved peerset.
+    ///
+    /// Reserved peers occupy dedicated slots outside of the configured peer limits and are
+    /// reconnected whenever their connection is lost.
+    #[method(name = "addReservedPeer")]
+    fn add_reserved_peer(&self, record: NodeRecord) -> RpcResult<bool>;
+
+    /// Removes a remote node from the reserved peer set, but it does not disconnect it
+    /// automatically.
+    ///
+    /// Returns true if the peer was successfully removed.
+    #[method(name = "removeReservedPeer")]
+    fn remove_reserved_peer(&self, record: NodeRecord) -> RpcResult<bool>;
+
     /// The peers administrative property can be queried for all the information known about the
     /// connected remote nodes at the networking granularity. These include general information
     /// about the nodes themselves as participants of the devp2p P2P overlay protocol, as well as
@@ -35,6 +49,16 @@ pub trait AdminApi {
     #[method(name = "peers")]
     async fn peers(&self) -> RpcResult<Vec<PeerInfo>>;
 
+    /// Returns all active bans of peers and ip addresses.
+    #[method(name = "bans")]
+    async fn bans(&self) -> RpcResult<Vec<BanInfo>>;
+
+    /// Lifts all bans of peers and ip addresses.
+    ///
+    /// Returns true if the bans were cleared.
+    #[method(name = "clearBans")]
+    fn clear_bans(&self) -> RpcResult<bool>;
+
     /// Creates an RPC subscription which serves events received from the network.
     #[subscription(
         name = "peerEvents",
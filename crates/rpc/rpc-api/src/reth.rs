@@ -1,5 +1,5 @@
 use jsonrpsee::{core::RpcResult, proc_macros::rpc};
-use reth_primitives::{Address, BlockId, U256};
+use reth_primitives::{stage::StageProgress, Address, BlockId, U256};
 use std::collections::HashMap;
 
 /// Reth API namespace for reth-specific methods
@@ -12,4 +12,9 @@ pub trait RethApi {
         &self,
         block_id: BlockId,
     ) -> RpcResult<HashMap<Address, U256>>;
+
+    /// Returns the progress of the pipeline stages, with their throughput and the estimated time
+    /// until they reach their targets.
+    #[method(name = "syncProgress")]
+    async fn reth_sync_progress(&self) -> RpcResult<Vec<StageProgress>>;
 }
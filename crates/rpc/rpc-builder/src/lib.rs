@@ -110,6 +110,7 @@ use jsonrpsee::{
     server::{IdProvider, Server, ServerHandle},
     Methods, RpcModule,
 };
+use reth_interfaces::sync::SyncProgressProvider;
 use reth_ipc::server::IpcServer;
 use reth_network_api::{NetworkInfo, Peers};
 use reth_provider::{
@@ -134,6 +135,7 @@ use std::{
     fmt,
     net::{Ipv4Addr, SocketAddr, SocketAddrV4},
     str::FromStr,
+    sync::Arc,
 };
 use strum::{AsRefStr, EnumString, EnumVariantNames, ParseError, VariantNames};
 use tower::layer::util::{Identity, Stack};
@@ -212,6 +214,8 @@ pub struct RpcModuleBuilder<Provider, Pool, Network, Tasks, Events> {
     executor: Tasks,
     /// Provides access to chain events, such as new blocks, required by pubsub.
     events: Events,
+    /// Provides the progress of the pipeline sync, required by the `reth` namespace.
+    sync_progress: Option<Arc<dyn SyncProgressProvider>>,
 }
 
 // === impl RpcBuilder ===
@@ -227,7 +231,7 @@ impl<Provider, Pool, Network, Tasks, Events>
         executor: Tasks,
         events: Events,
     ) -> Self {
-        Self { provider, pool, network, executor, events }
+        Self { provider, pool, network, executor, events, sync_progress: None }
     }
 
     /// Configure the provider instance.
@@ -235,8 +239,8 @@ impl<Provider, Pool, Network, Tasks, Events>
     where
         P: BlockReader + StateProviderFactory + EvmEnvProvider + 'static,
     {
-        let Self { pool, network, executor, events, .. } = self;
-        RpcModuleBuilder { provider, network, pool, executor, events }
+        let Self { pool, network, executor, events, sync_progress, .. } = self;
+        RpcModuleBuilder { provider, network, pool, executor, events, sync_progress }
     }
 
     /// Configure the transaction pool instance.
@@ -244,8 +248,8 @@ impl<Provider, Pool, Network, Tasks, Events>
     where
         P: TransactionPool + 'static,
     {
-        let Self { provider, network, executor, events, .. } = self;
-        RpcModuleBuilder { provider, network, pool, executor, events }
+        let Self { provider, network, executor, events, sync_progress, .. } = self;
+        RpcModuleBuilder { provider, network, pool, executor, events, sync_progress }
     }
 
     /// Configure a [NoopTransactionPool] instance.
@@ -256,13 +260,14 @@ impl<Provider, Pool, Network, Tasks, Events>
     pub fn with_noop_pool(
         self,
     ) -> RpcModuleBuilder<Provider, NoopTransactionPool, Network, Tasks, Events> {
-        let Self { provider, executor, events, network, .. } = self;
+        let Self { provider, executor, events, network, sync_progress, .. } = self;
         RpcModuleBuilder {
             provider,
             executor,
             events,
             network,
             pool: NoopTransactionPool::default(),
+            sync_progress,
         }
     }
 
@@ -271,8 +276,8 @@ impl<Provider, Pool, Network, Tasks, Events>
     where
         N: NetworkInfo + Peers + 'static,
     {
-        let Self { provider, pool, executor, events, .. } = self;
-        RpcModuleBuilder { provider, network, pool, executor, events }
+        let Self { provider, pool, executor, events, sync_progress, .. } = self;
+        RpcModuleBuilder { provider, network, pool, executor, events, sync_progress }
     }
 
     /// Configure a [NoopNetwork] instance.
@@ -281,8 +286,15 @@ impl<Provider, Pool, Network, Tasks, Events>
     /// This is only intended for allow easier setup of namespaces that depend on the [EthApi] which
     /// requires a [NetworkInfo] implementation.
     pub fn with_noop_network(self) -> RpcModuleBuilder<Provider, Pool, NoopNetwork, Tasks, Events> {
-        let Self { provider, pool, executor, events, .. } = self;
-        RpcModuleBuilder { provider, pool, executor, events, network: NoopNetwork::default() }
+        let Self { provider, pool, executor, events, sync_progress, .. } = self;
+        RpcModuleBuilder {
+            provider,
+            pool,
+            executor,
+            events,
+            network: NoopNetwork::default(),
+            sync_progress,
+        }
     }
 
     /// Configure the task executor to use for additional tasks.
@@ -293,8 +305,8 @@ impl<Provider, Pool, Network, Tasks, Events>
     where
         T: TaskSpawner + 'static,
     {
-        let Self { pool, network, provider, events, .. } = self;
-        RpcModuleBuilder { provider, network, pool, executor, events }
+        let Self { pool, network, provider, events, sync_progress, .. } = self;
+        RpcModuleBuilder { provider, network, pool, executor, events, sync_progress }
     }
 
     /// Configure [TokioTaskExecutor] as the task executor to use for additional tasks.
@@ -304,8 +316,24 @@ impl<Provider, Pool, Network, Tasks, Events>
     pub fn with_tokio_executor(
         self,
     ) -> RpcModuleBuilder<Provider, Pool, Network, TokioTaskExecutor, Events> {
-        let Self { pool, network, provider, events, .. } = self;
-        RpcModuleBuilder { provider, network, pool, events, executor: TokioTaskExecutor::default() }
+        let Self { pool, network, provider, events, sync_progress, .. } = self;
+        RpcModuleBuilder {
+            provider,
+            network,
+            pool,
+            events,
+            executor: TokioTaskExecutor::default(),
+            sync_progress,
+        }
+    }
+
+    /// Configure the provider of the sync progress returned by the `reth` namespace.
+    pub fn with_sync_progress<P>(mut self, sync_progress: P) -> Self
+    where
+        P: SyncProgressProvider + 'static,
+    {
+        self.sync_progress = Some(Arc::new(sync_progress));
+        self
     }
 
     /// Configure the event subscriber instance
@@ -313,8 +341,8 @@ impl<Provider, Pool, Network, Tasks, Events>
     where
         E: CanonStateSubscriptions + 'static,
     {
-        let Self { provider, pool, executor, network, .. } = self;
-        RpcModuleBuilder { provider, network, pool, executor, events }
+        let Self { provider, pool, executor, network, sync_progress, .. } = self;
+        RpcModuleBuilder { provider, network, pool, executor, events, sync_progress }
     }
 }
 
@@ -353,7 +381,7 @@ where
     {
         let mut modules = TransportRpcModules::default();
 
-        let Self { provider, pool, network, executor, events } = self;
+        let Self { provider, pool, network, executor, events, sync_progress } = self;
 
         let TransportRpcModuleConfig { http, ws, ipc, config } = module_config.clone();
 
@@ -365,6 +393,7 @@ where
             events,
             config.unwrap_or_default(),
         );
+        registry.sync_progress = sync_progress;
 
         modules.config = module_config;
         modules.http = registry.maybe_module(http.as_ref());
@@ -383,7 +412,7 @@ where
     pub fn build(self, module_config: TransportRpcModuleConfig) -> TransportRpcModules<()> {
         let mut modules = TransportRpcModules::default();
 
-        let Self { provider, pool, network, executor, events } = self;
+        let Self { provider, pool, network, executor, events, sync_progress } = self;
 
         if !module_config.is_empty() {
             let TransportRpcModuleConfig { http, ws, ipc, config } = module_config.clone();
@@ -396,6 +425,7 @@ where
                 events,
                 config.unwrap_or_default(),
             );
+            registry.sync_progress = sync_progress;
 
             modules.config = module_config;
             modules.http = registry.maybe_module(http.as_ref());
@@ -702,6 +732,8 @@ pub struct RethModuleRegistry<Provider, Pool, Network, Tasks, Events> {
     tracing_call_guard: TracingCallGuard,
     /// Contains the [Methods] of a module
     modules: HashMap<RethRpcModule, Methods>,
+    /// Provides the progress of the pipeline sync
+    sync_progress: Option<Arc<dyn SyncProgressProvider>>,
 }
 
 // === impl RethModuleRegistry ===
@@ -728,9 +760,19 @@ impl<Provider, Pool, Network, Tasks, Events>
             tracing_call_guard: TracingCallGuard::new(config.eth.max_tracing_requests),
             config,
             events,
+            sync_progress: None,
         }
     }
 
+    /// Configures the provider of the sync progress returned by the `reth` namespace.
+    pub fn set_sync_progress<P>(&mut self, sync_progress: P) -> &mut Self
+    where
+        P: SyncProgressProvider + 'static,
+    {
+        self.sync_progress = Some(Arc::new(sync_progress));
+        self
+    }
+
     /// Returns a reference to the pool
     pub fn pool(&self) -> &Pool {
         &self.pool
@@ -880,7 +922,13 @@ where
     pub fn register_reth(&mut self) -> &mut Self {
         self.modules.insert(
             RethRpcModule::Reth,
-            RethApi::new(self.provider.clone(), Box::new(self.executor.clone())).into_rpc().into(),
+            RethApi::new(
+                self.provider.clone(),
+                Box::new(self.executor.clone()),
+                self.sync_progress.clone(),
+            )
+            .into_rpc()
+            .into(),
         );
         self
     }
@@ -971,11 +1019,13 @@ where
                         .into_rpc()
                         .into(),
                         RethRpcModule::Ots => OtterscanApi::new(eth_api.clone()).into_rpc().into(),
-                        RethRpcModule::Reth => {
-                            RethApi::new(self.provider.clone(), Box::new(self.executor.clone()))
-                                .into_rpc()
-                                .into()
-                        }
+                        RethRpcModule::Reth => RethApi::new(
+                            self.provider.clone(),
+                            Box::new(self.executor.clone()),
+                            self.sync_progress.clone(),
+                        )
+                        .into_rpc()
+                        .into(),
                     })
                     .clone()
             })
@@ -56,7 +56,11 @@ where
     AdminApiClient::remove_peer(client, node).await.unwrap();
     AdminApiClient::add_trusted_peer(client, node).await.unwrap();
     AdminApiClient::remove_trusted_peer(client, node).await.unwrap();
+    AdminApiClient::add_reserved_peer(client, node).await.unwrap();
+    AdminApiClient::remove_reserved_peer(client, node).await.unwrap();
     AdminApiClient::node_info(client).await.unwrap();
+    AdminApiClient::bans(client).await.unwrap();
+    AdminApiClient::clear_bans(client).await.unwrap();
 }
 
 async fn test_basic_eth_calls<C>(client: &C)
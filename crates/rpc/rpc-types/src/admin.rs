@@ -88,6 +88,22 @@ pub struct EthProtocolInfo {
     pub genesis: H256,
 }
 
+/// An active ban of a peer or an ip address, as returned by `admin_bans`.
+///
+/// Exactly one of `id` and `ip` is set.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BanInfo {
+    /// ID of the banned peer.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<PeerId>,
+    /// The banned ip address.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ip: Option<IpAddr>,
+    /// Unix timestamp in seconds until which the ban is in effect, `None` if banned
+    /// indefinitely.
+    pub until: Option<u64>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -101,4 +117,13 @@ mod tests {
         let de_serialized: NodeInfo = serde_json::from_str(&serialized).unwrap();
         assert_eq!(info, de_serialized)
     }
+
+    #[test]
+    fn test_serialize_ban_info() {
+        let ban =
+            BanInfo { id: None, ip: Some(IpAddr::from([1, 1, 1, 1])), until: Some(1_700_000_000) };
+        let serialized = serde_json::to_string(&ban).unwrap();
+        assert_eq!(serialized, r#"{"ip":"1.1.1.1","until":1700000000}"#);
+        assert_eq!(serde_json::from_str::<BanInfo>(&serialized).unwrap(), ban);
+    }
 }
@@ -54,6 +54,31 @@ pub struct PeerInfo {
     pub network: PeerNetworkInfo,
     /// Protocols information
     pub protocols: PeerProtocolsInfo,
+    /// Traffic and request statistics of the session
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stats: Option<PeerStatsInfo>,
+}
+
+/// Traffic and request statistics of a peer session
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeerStatsInfo {
+    /// Number of bytes received from the peer
+    pub bytes_in: u64,
+    /// Number of bytes sent to the peer
+    pub bytes_out: u64,
+    /// Number of messages received from the peer, by message name
+    pub messages_in: BTreeMap<String, u64>,
+    /// Number of messages sent to the peer, by message name
+    pub messages_out: BTreeMap<String, u64>,
+    /// Number of responses the peer sent to our requests
+    pub responses: u64,
+    /// Number of our requests the peer did not respond to in time
+    pub timed_out_requests: u64,
+    /// Mean time in milliseconds the peer took to respond to a request
+    pub mean_request_latency_ms: u64,
+    /// Longest time in milliseconds the peer took to respond to a request
+    pub max_request_latency_ms: u64,
 }
 
 /// Peer network information
@@ -1,10 +1,14 @@
 use crate::result::ToRpcResult;
 use async_trait::async_trait;
 use jsonrpsee::core::RpcResult;
-use reth_network_api::{NetworkInfo, PeerKind, Peers};
+use reth_network_api::{BanTarget, NetworkInfo, PeerKind, Peers, SessionStats};
 use reth_primitives::NodeRecord;
 use reth_rpc_api::AdminApiServer;
-use reth_rpc_types::{NodeInfo, PeerEthProtocolInfo, PeerInfo, PeerNetworkInfo, PeerProtocolsInfo};
+use reth_rpc_types::{
+    BanInfo, NodeInfo, PeerEthProtocolInfo, PeerInfo, PeerNetworkInfo, PeerProtocolsInfo,
+    PeerStatsInfo,
+};
+use std::time::UNIX_EPOCH;
 
 /// `admin` API implementation.
 ///
@@ -50,6 +54,18 @@ where
         Ok(true)
     }
 
+    /// Handler for `admin_addReservedPeer`
+    fn add_reserved_peer(&self, record: NodeRecord) -> RpcResult<bool> {
+        self.network.add_reserved_peer(record.id, record.tcp_addr());
+        Ok(true)
+    }
+
+    /// Handler for `admin_removeReservedPeer`
+    fn remove_reserved_peer(&self, record: NodeRecord) -> RpcResult<bool> {
+        self.network.remove_peer(record.id, PeerKind::Reserved);
+        Ok(true)
+    }
+
     async fn peers(&self) -> RpcResult<Vec<PeerInfo>> {
         let peers = self.network.get_peers().await.to_rpc_result()?;
         let peers = peers
@@ -73,12 +89,39 @@ where
                     }),
                     pip: None,
                 },
+                stats: Some(peer_stats_info(peer.stats)),
             })
             .collect();
 
         Ok(peers)
     }
 
+    /// Handler for `admin_bans`
+    async fn bans(&self) -> RpcResult<Vec<BanInfo>> {
+        let bans = self.network.bans().await.to_rpc_result()?;
+        let bans = bans
+            .into_iter()
+            .map(|ban| {
+                let (id, ip) = match ban.target {
+                    BanTarget::Peer(peer_id) => (Some(peer_id), None),
+                    BanTarget::Ip(ip) => (None, Some(ip)),
+                };
+                let until = ban
+                    .until
+                    .map(|until| until.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs());
+                BanInfo { id, ip, until }
+            })
+            .collect();
+
+        Ok(bans)
+    }
+
+    /// Handler for `admin_clearBans`
+    fn clear_bans(&self) -> RpcResult<bool> {
+        self.network.clear_bans();
+        Ok(true)
+    }
+
     /// Handler for `admin_nodeInfo`
     async fn node_info(&self) -> RpcResult<NodeInfo> {
         let enr = self.network.local_node_record();
@@ -96,6 +139,23 @@ where
     }
 }
 
+/// Converts the [SessionStats] of a peer into its rpc representation.
+fn peer_stats_info(stats: SessionStats) -> PeerStatsInfo {
+    let message_counts = |counts: Vec<(_, u64)>| {
+        counts.into_iter().map(|(id, count)| (format!("{id:?}"), count)).collect()
+    };
+    PeerStatsInfo {
+        bytes_in: stats.bytes_in,
+        bytes_out: stats.bytes_out,
+        messages_in: message_counts(stats.messages_in),
+        messages_out: message_counts(stats.messages_out),
+        responses: stats.responses,
+        timed_out_requests: stats.timed_out_requests,
+        mean_request_latency_ms: stats.mean_request_latency.as_millis() as u64,
+        max_request_latency_ms: stats.max_request_latency.as_millis() as u64,
+    }
+}
+
 impl<N> std::fmt::Debug for AdminApi<N> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("AdminApi").finish_non_exhaustive()
@@ -1,8 +1,8 @@
 use crate::eth::error::{EthApiError, EthResult};
 use async_trait::async_trait;
 use jsonrpsee::core::RpcResult;
-use reth_interfaces::Result;
-use reth_primitives::{Address, BlockId, U256};
+use reth_interfaces::{sync::SyncProgressProvider, Result};
+use reth_primitives::{stage::StageProgress, Address, BlockId, U256};
 use reth_provider::{BlockReaderIdExt, ChangeSetReader, StateProviderFactory};
 use reth_rpc_api::RethApiServer;
 use reth_tasks::TaskSpawner;
@@ -25,10 +25,24 @@ impl<Provider> RethApi<Provider> {
     }
 
     /// Create a new instance of the [RethApi]
-    pub fn new(provider: Provider, task_spawner: Box<dyn TaskSpawner>) -> Self {
-        let inner = Arc::new(RethApiInner { provider, task_spawner });
+    pub fn new(
+        provider: Provider,
+        task_spawner: Box<dyn TaskSpawner>,
+        sync_progress: Option<Arc<dyn SyncProgressProvider>>,
+    ) -> Self {
+        let inner = Arc::new(RethApiInner { provider, task_spawner, sync_progress });
         Self { inner }
     }
+
+    /// Returns the progress of the pipeline stages.
+    pub fn sync_progress(&self) -> EthResult<Vec<StageProgress>> {
+        let sync_progress = self
+            .inner
+            .sync_progress
+            .as_ref()
+            .ok_or(EthApiError::Unsupported("sync progress is not tracked"))?;
+        Ok(sync_progress.sync_progress())
+    }
 }
 
 impl<Provider> RethApi<Provider>
@@ -95,6 +109,11 @@ where
     ) -> RpcResult<HashMap<Address, U256>> {
         Ok(RethApi::balance_changes_in_block(self, block_id).await?)
     }
+
+    /// Handler for `reth_syncProgress`
+    async fn reth_sync_progress(&self) -> RpcResult<Vec<StageProgress>> {
+        Ok(RethApi::sync_progress(self)?)
+    }
 }
 
 impl<Provider> std::fmt::Debug for RethApi<Provider> {
@@ -114,4 +133,6 @@ struct RethApiInner<Provider> {
     provider: Provider,
     /// The type that can spawn tasks which would otherwise block.
     task_spawner: Box<dyn TaskSpawner>,
+    /// Provides the progress of the pipeline sync, if tracked.
+    sync_progress: Option<Arc<dyn SyncProgressProvider>>,
 }
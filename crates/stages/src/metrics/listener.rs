@@ -1,4 +1,4 @@
-use crate::metrics::SyncMetrics;
+use crate::metrics::{SyncMetrics, SyncProgressTracker};
 use reth_primitives::{
     constants::MGAS_TO_GAS,
     stage::{StageCheckpoint, StageId},
@@ -46,12 +46,18 @@ pub enum MetricEvent {
 pub struct MetricsListener {
     events_rx: UnboundedReceiver<MetricEvent>,
     pub(crate) sync_metrics: SyncMetrics,
+    progress: SyncProgressTracker,
 }
 
 impl MetricsListener {
     /// Creates a new [MetricsListener] with the provided receiver of [MetricEvent].
     pub fn new(events_rx: UnboundedReceiver<MetricEvent>) -> Self {
-        Self { events_rx, sync_metrics: SyncMetrics::default() }
+        Self { events_rx, sync_metrics: SyncMetrics::default(), progress: Default::default() }
+    }
+
+    /// Returns a handle to the progress of the stages, as reported to this listener.
+    pub fn progress_tracker(&self) -> SyncProgressTracker {
+        self.progress.clone()
     }
 
     fn handle_event(&mut self, event: MetricEvent) {
@@ -70,20 +76,21 @@ impl MetricsListener {
                 }
             }
             MetricEvent::StageCheckpoint { stage_id, checkpoint, max_block_number } => {
+                let progress = self.progress.record(stage_id, checkpoint, max_block_number);
                 let stage_metrics = self.sync_metrics.get_stage_metrics(stage_id);
 
                 stage_metrics.checkpoint.set(checkpoint.block_number as f64);
+                stage_metrics.entities_processed.set(progress.processed as f64);
 
-                let (processed, total) = match checkpoint.entities() {
-                    Some(entities) => (entities.processed, Some(entities.total)),
-                    None => (checkpoint.block_number, max_block_number),
-                };
-
-                stage_metrics.entities_processed.set(processed as f64);
-
-                if let Some(total) = total {
+                if let Some(total) = progress.total {
                     stage_metrics.entities_total.set(total as f64);
                 }
+                if let Some(throughput) = progress.throughput {
+                    stage_metrics.entities_per_second.set(throughput);
+                }
+                if let Some(eta) = progress.eta {
+                    stage_metrics.eta_seconds.set(eta as f64);
+                }
             }
             MetricEvent::ExecutionStageGas { gas } => self
                 .sync_metrics
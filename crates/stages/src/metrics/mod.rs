@@ -1,5 +1,7 @@
 mod listener;
+mod progress;
 mod sync_metrics;
 
 pub use listener::{MetricEvent, MetricEventsSender, MetricsListener};
+pub use progress::SyncProgressTracker;
 use sync_metrics::*;
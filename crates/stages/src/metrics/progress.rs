@@ -0,0 +1,184 @@
+use parking_lot::RwLock;
+use reth_interfaces::sync::SyncProgressProvider;
+use reth_primitives::{
+    stage::{ProgressUnit, StageCheckpoint, StageId, StageProgress},
+    BlockNumber,
+};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// The window over which the throughput of a stage is measured.
+const THROUGHPUT_WINDOW: Duration = Duration::from_secs(5 * 60);
+
+/// Tracks the progress of the pipeline stages from the checkpoints they report, and estimates
+/// their throughput and the time until they reach their targets.
+///
+/// The tracker is cheap to clone, all clones share the same state.
+#[derive(Debug, Clone, Default)]
+pub struct SyncProgressTracker {
+    stages: Arc<RwLock<HashMap<StageId, StageProgressState>>>,
+}
+
+impl SyncProgressTracker {
+    /// Records a checkpoint reported by a stage and returns the updated progress of the stage.
+    ///
+    /// `max_block_number` is the total for stages that only report block checkpoints.
+    pub fn record(
+        &self,
+        stage_id: StageId,
+        checkpoint: StageCheckpoint,
+        max_block_number: Option<BlockNumber>,
+    ) -> StageProgress {
+        self.record_at(stage_id, checkpoint, max_block_number, Instant::now())
+    }
+
+    fn record_at(
+        &self,
+        stage_id: StageId,
+        checkpoint: StageCheckpoint,
+        max_block_number: Option<BlockNumber>,
+        now: Instant,
+    ) -> StageProgress {
+        let (unit, processed, total) = match checkpoint.entities() {
+            Some(entities) if stage_id == StageId::Execution => {
+                (ProgressUnit::Gas, entities.processed, Some(entities.total))
+            }
+            Some(entities) => (ProgressUnit::Entities, entities.processed, Some(entities.total)),
+            None => (ProgressUnit::Blocks, checkpoint.block_number, max_block_number),
+        };
+
+        let mut stages = self.stages.write();
+        let state = stages.entry(stage_id).or_insert_with(|| StageProgressState {
+            checkpoint: checkpoint.block_number,
+            unit,
+            processed,
+            total,
+            samples: VecDeque::new(),
+        });
+
+        // the samples are not comparable after an unwind or a change of the unit
+        if state.unit != unit || processed < state.processed {
+            state.samples.clear();
+        }
+        state.checkpoint = checkpoint.block_number;
+        state.unit = unit;
+        state.processed = processed;
+        state.total = total;
+
+        state.samples.push_back((now, processed));
+        while state.samples.len() > 2 &&
+            state.samples.front().map_or(false, |(at, _)| now - *at > THROUGHPUT_WINDOW)
+        {
+            state.samples.pop_front();
+        }
+
+        state.progress(stage_id)
+    }
+}
+
+impl SyncProgressProvider for SyncProgressTracker {
+    fn sync_progress(&self) -> Vec<StageProgress> {
+        let stages = self.stages.read();
+        let mut progress = stages.iter().collect::<Vec<_>>();
+        // known stages in pipeline order, followed by custom stages
+        progress.sort_by_key(|(stage_id, _)| {
+            StageId::ALL.iter().position(|id| id == *stage_id).unwrap_or(StageId::ALL.len())
+        });
+        progress.into_iter().map(|(stage_id, state)| state.progress(*stage_id)).collect()
+    }
+}
+
+#[derive(Debug)]
+struct StageProgressState {
+    checkpoint: BlockNumber,
+    unit: ProgressUnit,
+    processed: u64,
+    total: Option<u64>,
+    /// The processed units at the time of each reported checkpoint within the throughput window.
+    samples: VecDeque<(Instant, u64)>,
+}
+
+impl StageProgressState {
+    /// Returns the number of units processed per second over the samples.
+    fn throughput(&self) -> Option<f64> {
+        let (first_at, first) = self.samples.front()?;
+        let (last_at, last) = self.samples.back()?;
+        let elapsed = last_at.duration_since(*first_at).as_secs_f64();
+        (elapsed > 0.0).then(|| (last - first) as f64 / elapsed)
+    }
+
+    fn progress(&self, stage_id: StageId) -> StageProgress {
+        let throughput = self.throughput();
+        let eta = match (self.total, throughput) {
+            (Some(total), _) if total <= self.processed => Some(0),
+            (Some(total), Some(throughput)) if throughput > 0.0 => {
+                Some(((total - self.processed) as f64 / throughput).ceil() as u64)
+            }
+            _ => None,
+        };
+
+        StageProgress {
+            stage: stage_id.to_string(),
+            checkpoint: self.checkpoint,
+            unit: self.unit,
+            processed: self.processed,
+            total: self.total,
+            throughput,
+            eta,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reth_primitives::stage::{EntitiesCheckpoint, ExecutionCheckpoint, StageUnitCheckpoint};
+
+    #[test]
+    fn throughput_and_eta() {
+        let tracker = SyncProgressTracker::default();
+        let start = Instant::now();
+        let checkpoint = |processed| StageCheckpoint {
+            block_number: processed / 10,
+            stage_checkpoint: Some(StageUnitCheckpoint::Execution(ExecutionCheckpoint {
+                progress: EntitiesCheckpoint { processed, total: 1_000 },
+                ..Default::default()
+            })),
+        };
+
+        let progress = tracker.record_at(StageId::Execution, checkpoint(100), None, start);
+        assert_eq!(progress.unit, ProgressUnit::Gas);
+        assert_eq!(progress.throughput, None);
+        assert_eq!(progress.eta, None);
+
+        let progress = tracker.record_at(
+            StageId::Execution,
+            checkpoint(300),
+            None,
+            start + Duration::from_secs(10),
+        );
+        assert_eq!(progress.throughput, Some(20.0));
+        assert_eq!(progress.eta, Some(35));
+
+        // unwinds reset the samples
+        let progress = tracker.record_at(
+            StageId::Execution,
+            checkpoint(200),
+            None,
+            start + Duration::from_secs(20),
+        );
+        assert_eq!(progress.throughput, None);
+
+        tracker.record_at(StageId::Headers, StageCheckpoint::new(5), Some(10), start);
+        let stages = tracker.sync_progress();
+        assert_eq!(
+            stages.iter().map(|stage| stage.stage.as_str()).collect::<Vec<_>>(),
+            ["Headers", "Execution"]
+        );
+        assert_eq!(stages[0].unit, ProgressUnit::Blocks);
+        assert_eq!(stages[0].total, Some(10));
+    }
+}
@@ -26,6 +26,10 @@ pub(crate) struct StageMetrics {
     pub(crate) entities_processed: Gauge,
     /// The number of total entities of the last commit for a stage, if applicable.
     pub(crate) entities_total: Gauge,
+    /// The number of entities processed per second, measured over the last minutes.
+    pub(crate) entities_per_second: Gauge,
+    /// The estimated number of seconds until the stage reaches its target.
+    pub(crate) eta_seconds: Gauge,
 }
 
 /// Execution stage metrics.
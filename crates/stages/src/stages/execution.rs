@@ -3,6 +3,7 @@ use crate::{
     MetricEventsSender, Stage, StageError, UnwindInput, UnwindOutput,
 };
 use num_traits::Zero;
+use rayon::prelude::*;
 use reth_db::{
     cursor::{DbCursorRO, DbCursorRW, DbDupCursorRO},
     database::Database,
@@ -19,10 +20,15 @@ use reth_primitives::{
 };
 use reth_provider::{
     BlockReader, DatabaseProviderRW, ExecutorFactory, HeaderProvider, LatestStateProviderRef,
-    OriginalValuesKnown, ProviderError,
+    OriginalValuesKnown, ProviderError, StageCheckpointReader,
+};
+use reth_revm::{
+    database::{CachedStateProvider, StateCache},
+    prefetch::PrefetchTargets,
 };
 use std::{
     ops::RangeInclusive,
+    thread::ScopedJoinHandle,
     time::{Duration, Instant},
 };
 use tracing::*;
@@ -55,6 +61,11 @@ use tracing::*;
 /// - [tables::AccountHistory] to remove change set and apply old values to
 /// - [tables::PlainAccountState] [tables::StorageHistory] to remove change set and apply old values
 /// to [tables::PlainStorageState]
+///
+/// If the state of a pivot block was downloaded by [`super::StateSyncStage`], the plain state only
+/// holds the changes of the blocks executed after the pivot. The rest of the state is read from
+/// [tables::HashedAccount] and [tables::HashedStorage] instead, and the output state is also
+/// written to those tables, since they can't be rebuilt from the plain state.
 // false positive, we cannot derive it if !DB: Debug.
 #[allow(missing_debug_implementations)]
 pub struct ExecutionStage<EF: ExecutorFactory> {
@@ -70,6 +81,8 @@ pub struct ExecutionStage<EF: ExecutorFactory> {
     external_clean_threshold: u64,
     /// Pruning configuration.
     prune_modes: PruneModes,
+    /// Whether to prefetch the state of the next block while a block executes.
+    prefetch_state: bool,
 }
 
 impl<EF: ExecutorFactory> ExecutionStage<EF> {
@@ -86,6 +99,7 @@ impl<EF: ExecutorFactory> ExecutionStage<EF> {
             executor_factory,
             thresholds,
             prune_modes,
+            prefetch_state: false,
         }
     }
 
@@ -107,6 +121,16 @@ impl<EF: ExecutorFactory> ExecutionStage<EF> {
         self
     }
 
+    /// Enables or disables prefetching the state of the next block into a cache shared with the
+    /// executor while a block executes.
+    ///
+    /// The state is prefetched with separate read-only transactions, which requires the state
+    /// tables to have no uncommitted changes when the stage starts, as is the case in the pipeline.
+    pub fn with_state_prefetch(mut self, enabled: bool) -> Self {
+        self.prefetch_state = enabled;
+        self
+    }
+
     /// Execute the stage.
     pub fn execute_inner<DB: Database>(
         &mut self,
@@ -120,10 +144,18 @@ impl<EF: ExecutorFactory> ExecutionStage<EF> {
         let start_block = input.next_block();
         let max_block = input.target();
         let prune_modes = self.adjust_prune_modes(provider, start_block, max_block)?;
-
-        // Build executor
-        let mut executor =
-            self.executor_factory.with_state(LatestStateProviderRef::new(provider.tx_ref()));
+        let state_synced = provider.state_sync_pivot()?.is_some();
+
+        // Build executor, reading through the prefetched state if enabled
+        let state_cache = self.prefetch_state.then(StateCache::default);
+        let state_provider =
+            LatestStateProviderRef::new(provider.tx_ref()).with_hashed_state_fallback(state_synced);
+        let mut executor = match &state_cache {
+            Some(cache) => self
+                .executor_factory
+                .with_state(CachedStateProvider::new(state_provider, cache.clone())),
+            None => self.executor_factory.with_state(state_provider),
+        };
         executor.set_prune_modes(prune_modes);
         executor.set_tip(max_block);
 
@@ -139,51 +171,77 @@ impl<EF: ExecutorFactory> ExecutionStage<EF> {
 
         let mut cumulative_gas = 0;
 
-        for block_number in start_block..=max_block {
-            let time = Instant::now();
-            let td = provider
-                .header_td_by_number(block_number)?
-                .ok_or_else(|| ProviderError::HeaderNotFound(block_number.into()))?;
-            let block = provider
-                .block_with_senders(block_number)?
-                .ok_or_else(|| ProviderError::BlockNotFound(block_number.into()))?;
-
-            fetch_block_duration += time.elapsed();
+        std::thread::scope(|scope| -> Result<(), StageError> {
+            let mut prefetch: Option<ScopedJoinHandle<'_, ()>> = None;
+            for block_number in start_block..=max_block {
+                let time = Instant::now();
+                let td = provider
+                    .header_td_by_number(block_number)?
+                    .ok_or_else(|| ProviderError::HeaderNotFound(block_number.into()))?;
+                let block = provider
+                    .block_with_senders(block_number)?
+                    .ok_or_else(|| ProviderError::BlockNotFound(block_number.into()))?;
+
+                fetch_block_duration += time.elapsed();
+
+                // Warm the state of the next block while this one executes, unless the previous
+                // prefetch is still running
+                if let Some(cache) = &state_cache {
+                    let idle = prefetch.as_ref().map_or(true, ScopedJoinHandle::is_finished);
+                    if block_number < max_block && idle {
+                        let db = provider.db();
+                        let cache = cache.clone();
+                        prefetch = Some(scope.spawn(move || {
+                            let next_block = block_number + 1;
+                            if let Err(error) =
+                                prefetch_block(db, next_block, state_synced, &cache)
+                            {
+                                debug!(target: "sync::stages::execution", number = next_block, %error, "Failed to prefetch block state");
+                            }
+                        }));
+                    }
+                }
 
-            cumulative_gas += block.gas_used;
+                cumulative_gas += block.gas_used;
 
-            // Configure the executor to use the current state.
-            trace!(target: "sync::stages::execution", number = block_number, txs = block.body.len(), "Executing block");
+                // Configure the executor to use the current state.
+                trace!(target: "sync::stages::execution", number = block_number, txs = block.body.len(), "Executing block");
 
-            let time = Instant::now();
-            // Execute the block
-            let (block, senders) = block.into_components();
-            executor.execute_and_verify_receipt(&block, td, Some(senders)).map_err(|error| {
-                StageError::ExecutionError { block: block.header.clone().seal_slow(), error }
-            })?;
+                let time = Instant::now();
+                // Execute the block
+                let (block, senders) = block.into_components();
+                executor.execute_and_verify_receipt(&block, td, Some(senders)).map_err(
+                    |error| StageError::ExecutionError {
+                        block: block.header.clone().seal_slow(),
+                        error,
+                    },
+                )?;
 
-            execution_duration += time.elapsed();
+                execution_duration += time.elapsed();
 
-            // Gas metrics
-            if let Some(metrics_tx) = &mut self.metrics_tx {
-                let _ =
-                    metrics_tx.send(MetricEvent::ExecutionStageGas { gas: block.header.gas_used });
-            }
+                // Gas metrics
+                if let Some(metrics_tx) = &mut self.metrics_tx {
+                    let _ = metrics_tx
+                        .send(MetricEvent::ExecutionStageGas { gas: block.header.gas_used });
+                }
 
-            stage_progress = block_number;
+                stage_progress = block_number;
 
-            stage_checkpoint.progress.processed += block.gas_used;
+                stage_checkpoint.progress.processed += block.gas_used;
 
-            // Check if we should commit now
-            let bundle_size_hint = executor.size_hint().unwrap_or_default() as u64;
-            if self.thresholds.is_end_of_batch(
-                block_number - start_block,
-                bundle_size_hint,
-                cumulative_gas,
-            ) {
-                break
+                // Check if we should commit now
+                let bundle_size_hint = executor.size_hint().unwrap_or_default() as u64;
+                if self.thresholds.is_end_of_batch(
+                    block_number - start_block,
+                    bundle_size_hint,
+                    cumulative_gas,
+                ) {
+                    break
+                }
             }
-        }
+            Ok(())
+        })?;
+
         let time = Instant::now();
         let state = executor.take_output_state();
         let write_preparation_duration = time.elapsed();
@@ -191,6 +249,9 @@ impl<EF: ExecutorFactory> ExecutionStage<EF> {
         let time = Instant::now();
         // write output
         state.write_to_db(provider.tx_ref(), OriginalValuesKnown::Yes)?;
+        if state_synced {
+            state.hash_state_slow().write_to_db(provider.tx_ref())?;
+        }
         let db_write_duration = time.elapsed();
         info!(target: "sync::stages::execution", block_fetch=?fetch_block_duration, execution=?execution_duration, 
             write_preperation=?write_preparation_duration, write=?db_write_duration, " Execution duration.");
@@ -235,6 +296,59 @@ impl<EF: ExecutorFactory> ExecutionStage<EF> {
     }
 }
 
+/// Loads the state that the block is likely to touch into the cache.
+///
+/// The block is read with a separate read-only transaction and the senders of its transactions are
+/// recovered, so that the prefetch doesn't depend on the sender recovery stage.
+fn prefetch_block<DB: Database>(
+    db: &DB,
+    block_number: BlockNumber,
+    state_synced: bool,
+    cache: &StateCache,
+) -> Result<(), StageError> {
+    let tx = db.tx()?;
+    let (Some(header), Some(body)) = (
+        tx.get::<tables::Headers>(block_number)?,
+        tx.get::<tables::BlockBodyIndices>(block_number)?,
+    ) else {
+        return Ok(())
+    };
+    let ommers = tx
+        .get::<tables::BlockOmmers>(block_number)?
+        .map(|stored| stored.ommers)
+        .unwrap_or_default();
+    let withdrawals = tx.get::<tables::BlockWithdrawals>(block_number)?;
+    let transactions = tx
+        .cursor_read::<tables::Transactions>()?
+        .walk_range(body.tx_num_range())?
+        .map(|entry| entry.map(|(_, transaction)| transaction))
+        .collect::<Result<Vec<_>, _>>()?;
+    let Some(senders) = transactions
+        .par_iter()
+        .map(|transaction| transaction.recover_signer())
+        .collect::<Option<Vec<_>>>()
+    else {
+        // the invalid signature is reported by the execution of the block
+        return Ok(())
+    };
+
+    let mut targets = PrefetchTargets::default();
+    targets.extend_with_header(&header, &ommers);
+    if let Some(withdrawals) = withdrawals {
+        targets.extend_with_withdrawals(&withdrawals.withdrawals);
+    }
+    for (transaction, sender) in transactions.iter().zip(senders) {
+        targets.extend_with_transaction(sender, transaction);
+    }
+    targets.load_into(
+        &LatestStateProviderRef::new(&tx).with_hashed_state_fallback(state_synced),
+        cache,
+    )?;
+    trace!(target: "sync::stages::execution", number = block_number, targets = targets.len(), "Prefetched block state");
+
+    Ok(())
+}
+
 fn execution_checkpoint<DB: Database>(
     provider: &DatabaseProviderRW<'_, &DB>,
     start_block: BlockNumber,
@@ -763,6 +877,49 @@ mod tests {
         );
     }
 
+    #[test]
+    fn prefetch_block_state() {
+        let state_db = create_test_rw_db();
+        let factory = ProviderFactory::new(state_db.as_ref(), MAINNET.clone());
+        let provider = factory.provider_rw().unwrap();
+        let mut genesis_rlp = hex!("f901faf901f5a00000000000000000000000000000000000000000000000000000000000000000a01dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347942adc25665018aa1fe0e6bc666dac8fc2697ff9baa045571b40ae66ca7480791bbb2887286e4e4c4b1b298b191c889d6959023a32eda056e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421a056e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421b901000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000083020000808502540be400808000a00000000000000000000000000000000000000000000000000000000000000000880000000000000000c0c0").as_slice();
+        let genesis = SealedBlock::decode(&mut genesis_rlp).unwrap();
+        let mut block_rlp = hex!("f90262f901f9a075c371ba45999d87f4542326910a11af515897aebce5265d3f6acd1f1161f82fa01dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347942adc25665018aa1fe0e6bc666dac8fc2697ff9baa098f2dcd87c8ae4083e7017a05456c14eea4b1db2032126e27b3b1563d57d7cc0a08151d548273f6683169524b66ca9fe338b9ce42bc3540046c828fd939ae23bcba03f4e5c2ec5b2170b711d97ee755c160457bb58d8daa338e835ec02ae6860bbabb901000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000083020000018502540be40082a8798203e800a00000000000000000000000000000000000000000000000000000000000000000880000000000000000f863f861800a8405f5e10094100000000000000000000000000000000000000080801ba07e09e26678ed4fac08a249ebe8ed680bf9051a5e14ad223e4b2b9d26e0208f37a05f6e3f188e3e6eab7d7d3b6568f5eac7d687b08d307d3154ccd8c87b4630509bc0").as_slice();
+        let block = SealedBlock::decode(&mut block_rlp).unwrap();
+        provider.insert_block(genesis, None, None).unwrap();
+        provider.insert_block(block.clone(), None, None).unwrap();
+
+        let acc1 = H160(hex!("1000000000000000000000000000000000000000"));
+        let acc2 = H160(hex!("a94f5374fce5edbc8e2a8697c15331677e6ebf0b"));
+        let code = hex!("5a465a905090036002900360015500");
+        let code_hash = keccak256(code);
+        let account1 = Account { nonce: 0, balance: U256::ZERO, bytecode_hash: Some(code_hash) };
+        let account2 = Account {
+            nonce: 0,
+            balance: U256::from(0x3635c9adc5dea00000u128),
+            bytecode_hash: None,
+        };
+        provider.tx_ref().put::<tables::PlainAccountState>(acc1, account1).unwrap();
+        provider.tx_ref().put::<tables::PlainAccountState>(acc2, account2).unwrap();
+        provider
+            .tx_ref()
+            .put::<tables::Bytecodes>(code_hash, Bytecode::new_raw(code.to_vec().into()))
+            .unwrap();
+        provider.commit().unwrap();
+
+        let cache = StateCache::default();
+        prefetch_block(state_db.as_ref(), 1, false, &cache).unwrap();
+
+        // the recipient and its code, the recovered sender and the beneficiary are cached
+        assert_eq!(cache.account(&acc1), Some(Some(account1)));
+        assert_eq!(cache.account(&acc2), Some(Some(account2)));
+        assert_eq!(cache.account(&block.beneficiary), Some(None));
+        assert_eq!(cache.bytecode(&code_hash), Some(Some(Bytecode::new_raw(code.to_vec().into()))));
+
+        // blocks that are not in the database are skipped
+        prefetch_block(state_db.as_ref(), 2, false, &cache).unwrap();
+    }
+
     #[tokio::test]
     async fn sanity_execute_unwind() {
         // TODO cleanup the setup after https://github.com/paradigmxyz/reth/issues/332
@@ -16,7 +16,7 @@ use reth_primitives::{
         StageId,
     },
 };
-use reth_provider::{AccountExtReader, DatabaseProviderRW, HashingWriter};
+use reth_provider::{AccountExtReader, DatabaseProviderRW, HashingWriter, StageCheckpointReader};
 use std::{
     cmp::max,
     fmt::Debug,
@@ -142,6 +142,12 @@ impl<DB: Database> Stage<DB> for AccountHashingStage {
             return Ok(ExecOutput::done(input.checkpoint()))
         }
 
+        // After a state sync, the plain state is incomplete and the execution stage writes the
+        // hashed state itself.
+        if provider.state_sync_pivot()?.is_some() {
+            return Ok(ExecOutput::done(StageCheckpoint::new(input.target())))
+        }
+
         let (from_block, to_block) = input.next_block_range().into_inner();
 
         // if there are more blocks then threshold it is faster to go over Plain state and hash all
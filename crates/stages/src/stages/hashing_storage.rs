@@ -16,7 +16,7 @@ use reth_primitives::{
     },
     StorageEntry,
 };
-use reth_provider::{DatabaseProviderRW, HashingWriter, StorageReader};
+use reth_provider::{DatabaseProviderRW, HashingWriter, StageCheckpointReader, StorageReader};
 use std::{collections::BTreeMap, fmt::Debug};
 use tracing::*;
 
@@ -62,6 +62,12 @@ impl<DB: Database> Stage<DB> for StorageHashingStage {
             return Ok(ExecOutput::done(input.checkpoint()))
         }
 
+        // After a state sync, the plain state is incomplete and the execution stage writes the
+        // hashed state itself.
+        if provider.state_sync_pivot()?.is_some() {
+            return Ok(ExecOutput::done(StageCheckpoint::new(input.target())))
+        }
+
         let (from_block, to_block) = input.next_block_range().into_inner();
 
         // if there are more blocks then threshold it is faster to go over Plain state and hash all
@@ -51,6 +51,9 @@ pub enum MerkleStage {
         /// The threshold (in number of blocks) for switching from incremental trie building
         /// of changes to whole rebuild.
         clean_threshold: u64,
+        /// Whether the storage roots of accounts with changed storage are computed in parallel
+        /// when the trie is updated incrementally.
+        parallel_storage_roots: bool,
     },
     /// The unwind portion of the merkle stage.
     Unwind,
@@ -64,7 +67,10 @@ pub enum MerkleStage {
 impl MerkleStage {
     /// Stage default for the [MerkleStage::Execution].
     pub fn default_execution() -> Self {
-        Self::Execution { clean_threshold: MERKLE_STAGE_DEFAULT_CLEAN_THRESHOLD }
+        Self::Execution {
+            clean_threshold: MERKLE_STAGE_DEFAULT_CLEAN_THRESHOLD,
+            parallel_storage_roots: false,
+        }
     }
 
     /// Stage default for the [MerkleStage::Unwind].
@@ -74,7 +80,19 @@ impl MerkleStage {
 
     /// Create new instance of [MerkleStage::Execution].
     pub fn new_execution(clean_threshold: u64) -> Self {
-        Self::Execution { clean_threshold }
+        Self::Execution { clean_threshold, parallel_storage_roots: false }
+    }
+
+    /// Enables or disables the parallel computation of storage roots for
+    /// [MerkleStage::Execution].
+    ///
+    /// The storage roots are computed with separate read-only transactions, which requires the
+    /// hashing stages to be committed before this stage runs.
+    pub fn with_parallel_storage_roots(mut self, enabled: bool) -> Self {
+        if let Self::Execution { parallel_storage_roots, .. } = &mut self {
+            *parallel_storage_roots = enabled;
+        }
+        self
     }
 
     /// Check that the computed state root matches the root in the expected header.
@@ -152,14 +170,16 @@ impl<DB: Database> Stage<DB> for MerkleStage {
         provider: &DatabaseProviderRW<'_, &DB>,
         input: ExecInput,
     ) -> Result<ExecOutput, StageError> {
-        let threshold = match self {
+        let (threshold, parallel_storage_roots) = match self {
             MerkleStage::Unwind => {
                 info!(target: "sync::stages::merkle::unwind", "Stage is always skipped");
                 return Ok(ExecOutput::done(StageCheckpoint::new(input.target())))
             }
-            MerkleStage::Execution { clean_threshold } => *clean_threshold,
+            MerkleStage::Execution { clean_threshold, parallel_storage_roots } => {
+                (*clean_threshold, *parallel_storage_roots)
+            }
             #[cfg(any(test, feature = "test-utils"))]
-            MerkleStage::Both { clean_threshold } => *clean_threshold,
+            MerkleStage::Both { clean_threshold } => (*clean_threshold, false),
         };
 
         let range = input.next_block_range();
@@ -248,10 +268,18 @@ impl<DB: Database> Stage<DB> for MerkleStage {
                 }
             }
         } else {
-            debug!(target: "sync::stages::merkle::exec", current = ?current_block_number, target = ?to_block, "Updating trie");
-            let (root, updates) =
-                StateRoot::incremental_root_with_updates(provider.tx_ref(), range)
+            debug!(target: "sync::stages::merkle::exec", current = ?current_block_number, target = ?to_block, parallel_storage_roots, "Updating trie");
+            let mut calculator = StateRoot::incremental_root_calculator(provider.tx_ref(), range)
+                .map_err(|e| StageError::Fatal(Box::new(e)))?;
+            if parallel_storage_roots {
+                // the trie tables are not modified before this point, so the storage roots can be
+                // computed from the committed state
+                calculator = calculator
+                    .with_parallel_storage_roots(provider.db())
                     .map_err(|e| StageError::Fatal(Box::new(e)))?;
+            }
+            let (root, updates) =
+                calculator.root_with_updates().map_err(|e| StageError::Fatal(Box::new(e)))?;
             updates.flush(provider.tx_ref())?;
 
             let total_hashed_entries = (provider.tx_ref().entries::<tables::HashedAccount>()? +
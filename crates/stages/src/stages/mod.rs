@@ -18,6 +18,8 @@ mod index_storage_history;
 mod merkle;
 /// The sender recovery stage.
 mod sender_recovery;
+/// The state sync stage.
+mod state_sync;
 /// The total difficulty stage
 mod total_difficulty;
 /// The transaction lookup stage
@@ -33,6 +35,7 @@ pub use index_account_history::*;
 pub use index_storage_history::*;
 pub use merkle::*;
 pub use sender_recovery::*;
+pub use state_sync::*;
 pub use total_difficulty::*;
 pub use tx_lookup::*;
 
@@ -0,0 +1,720 @@
+use crate::{ExecInput, ExecOutput, Stage, StageError, UnwindInput, UnwindOutput};
+use reth_db::{
+    cursor::{DbCursorRO, DbDupCursorRW},
+    database::Database,
+    tables,
+    transaction::{DbTx, DbTxMut},
+};
+use reth_downloaders::snap::SnapDownloader;
+use reth_eth_wire::SnapAccount;
+use reth_interfaces::p2p::snap::client::SnapClient;
+use reth_primitives::{
+    proofs::EMPTY_ROOT,
+    stage::{EntitiesCheckpoint, StageCheckpoint, StageId, StateSyncCheckpoint},
+    trie::{nodes::CHILD_INDEX_RANGE, BranchNodeCompact, Nibbles, StoredNibbles},
+    Account, Bytecode, Bytes, StorageEntry, H256, KECCAK_EMPTY, U256,
+};
+use reth_provider::{
+    DatabaseProviderRW, HeaderProvider, ProviderError, StageCheckpointReader, StageCheckpointWriter,
+};
+use reth_trie::{
+    branch_node_children,
+    prefix_set::{PrefixSet, PrefixSetMut},
+    updates::{TrieKey, TrieOp},
+    StateRoot,
+};
+use std::collections::HashSet;
+use tracing::*;
+
+/// The id of the [StateSyncStage].
+pub const STATE_SYNC: StageId = StageId::STATE_SYNC;
+
+/// The maximum number of accounts to request the storage of at once.
+const MAX_STORAGE_ACCOUNTS: usize = 128;
+
+/// The maximum number of bytecodes or trie nodes to request at once.
+const MAX_HASHES: usize = 64;
+
+/// The state sync stage downloads the state of the target block from the peers using the `snap`
+/// protocol, instead of executing all blocks up to it.
+///
+/// The accounts are downloaded range by range into [tables::HashedAccount], together with their
+/// storage into [tables::HashedStorage] and their bytecodes into [tables::Bytecodes]. Every
+/// response is verified against the state root of the target block, the pivot.
+///
+/// If the pivot changes while the ranges are downloaded, e.g. because the node was restarted with
+/// a newer target, the download continues from where it left off against the new state root, and
+/// the accounts downloaded for the previous pivot are healed afterwards: the nodes of the state
+/// trie of the peers are compared with the local trie, and the accounts under the mismatching
+/// subtries are downloaded again.
+///
+/// Once the state root matches, the intermediate hashes are stored and the checkpoints of the
+/// execution, hashing and merkle stages are set to the pivot, so that the blocks before the pivot
+/// are never executed.
+///
+/// The `snap` protocol serves the state by hashed keys only, so the plain state tables are left
+/// empty. Since the checkpoint of this stage keeps the pivot, the
+/// [ExecutionStage][crate::stages::ExecutionStage] and the state providers read the accounts and
+/// storage slots missing in the plain state from the hashed state, and the history before the
+/// pivot is reported as unavailable.
+///
+/// The stage is skipped if the state was already built by executing the blocks.
+#[derive(Debug)]
+pub struct StateSyncStage<C> {
+    /// The client to download the state with.
+    client: C,
+    /// The maximum number of account ranges to download before committing.
+    commit_threshold: u64,
+    /// The maximum number of rounds of healing before giving up.
+    max_heal_rounds: usize,
+}
+
+impl<C> StateSyncStage<C> {
+    /// Create new instance of [StateSyncStage].
+    pub fn new(client: C) -> Self {
+        Self { client, commit_threshold: 1_000, max_heal_rounds: 8 }
+    }
+
+    /// Sets the maximum number of account ranges to download before committing.
+    pub fn with_commit_threshold(mut self, commit_threshold: u64) -> Self {
+        self.commit_threshold = commit_threshold.max(1);
+        self
+    }
+
+    /// Sets the maximum number of rounds of healing before giving up.
+    pub fn with_max_heal_rounds(mut self, max_heal_rounds: usize) -> Self {
+        self.max_heal_rounds = max_heal_rounds;
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl<DB: Database, C: SnapClient> Stage<DB> for StateSyncStage<C> {
+    /// Return the id of the stage
+    fn id(&self) -> StageId {
+        STATE_SYNC
+    }
+
+    /// Execute the stage.
+    async fn execute(
+        &mut self,
+        provider: &DatabaseProviderRW<'_, &DB>,
+        input: ExecInput,
+    ) -> Result<ExecOutput, StageError> {
+        if input.target_reached() {
+            return Ok(ExecOutput::done(input.checkpoint()))
+        }
+
+        let pivot = input.target();
+        let mut checkpoint = match input.checkpoint().state_sync_stage_checkpoint() {
+            Some(checkpoint) if checkpoint.next_account.is_some() => {
+                if checkpoint.pivot != pivot {
+                    debug!(target: "sync::stages::state_sync", previous = checkpoint.pivot, pivot, "Pivot changed, downloaded state will be healed");
+                }
+                StateSyncCheckpoint { pivot, ..checkpoint }
+            }
+            // The state was downloaded already, the later blocks are executed on top of it.
+            Some(checkpoint) => {
+                return Ok(ExecOutput::done(
+                    StageCheckpoint::new(pivot).with_state_sync_stage_checkpoint(checkpoint),
+                ))
+            }
+            None => {
+                if has_state(provider)? {
+                    info!(target: "sync::stages::state_sync", "State already exists, skipping state sync");
+                    return Ok(ExecOutput::done(StageCheckpoint::new(pivot)))
+                }
+
+                let tx = provider.tx_ref();
+                tx.clear::<tables::HashedAccount>()?;
+                tx.clear::<tables::HashedStorage>()?;
+                StateSyncCheckpoint {
+                    pivot,
+                    next_account: Some(H256::zero()),
+                    progress: EntitiesCheckpoint::default(),
+                }
+            }
+        };
+
+        let header =
+            provider.sealed_header(pivot)?.ok_or(ProviderError::HeaderNotFound(pivot.into()))?;
+        let downloader = SnapDownloader::new(&self.client, header.state_root);
+
+        let mut ranges = 0;
+        while let Some(origin) = checkpoint.next_account {
+            if ranges >= self.commit_threshold {
+                info!(target: "sync::stages::state_sync", pivot, progress = %checkpoint.progress, "Downloading state");
+                return Ok(ExecOutput {
+                    checkpoint: input.checkpoint().with_state_sync_stage_checkpoint(checkpoint),
+                    done: false,
+                })
+            }
+
+            let range = downloader.account_range(origin).await.map_err(recoverable)?;
+            let next = range.accounts.last().and_then(|(last, _)| increment(*last));
+
+            checkpoint.progress.processed += range.accounts.len() as u64;
+            download_accounts(provider, &downloader, range.accounts).await?;
+
+            checkpoint.next_account = if range.has_more { next } else { None };
+            checkpoint.progress.total =
+                estimate_total(checkpoint.progress.processed, checkpoint.next_account);
+            ranges += 1;
+        }
+
+        // The first round computes the state root from scratch, the next ones only recompute the
+        // subtries of the accounts that were downloaded again.
+        let mut changed_accounts = None;
+        let mut root_node = None;
+        let mut round = 0;
+        loop {
+            let (root, node) = compute_state_root(provider, changed_accounts.take())?;
+            root_node = node.or(root_node);
+            if root == header.state_root {
+                break
+            }
+            if round >= self.max_heal_rounds {
+                return Err(StageError::Fatal(
+                    format!(
+                        "state root mismatch after {round} healing rounds: expected {:?}, got {root:?}",
+                        header.state_root
+                    )
+                    .into(),
+                ))
+            }
+
+            round += 1;
+            let stale = find_stale_prefixes(provider, &downloader, root_node.clone()).await?;
+            debug!(target: "sync::stages::state_sync", round, prefixes = stale.len(), "Healing state trie");
+            let mut changed = PrefixSetMut::default();
+            for prefix in stale {
+                for hashed_address in resync_prefix(provider, &downloader, prefix).await? {
+                    changed.insert(Nibbles::unpack(hashed_address));
+                }
+            }
+            changed_accounts = Some(changed.freeze());
+        }
+
+        // The downloaded state is the state at the pivot that the execution, hashing and merkle
+        // stages would have produced.
+        for stage_id in [
+            StageId::Execution,
+            StageId::AccountHashing,
+            StageId::StorageHashing,
+            StageId::MerkleUnwind,
+            StageId::MerkleExecute,
+        ] {
+            provider.save_stage_checkpoint(stage_id, StageCheckpoint::new(pivot))?;
+        }
+
+        info!(target: "sync::stages::state_sync", pivot, root = ?header.state_root, accounts = checkpoint.progress.processed, "State downloaded");
+        Ok(ExecOutput::done(
+            StageCheckpoint::new(pivot).with_state_sync_stage_checkpoint(checkpoint),
+        ))
+    }
+
+    /// Unwind the stage.
+    async fn unwind(
+        &mut self,
+        provider: &DatabaseProviderRW<'_, &DB>,
+        input: UnwindInput,
+    ) -> Result<UnwindOutput, StageError> {
+        match input.checkpoint.state_sync_stage_checkpoint() {
+            // The stage was skipped, or the blocks after the pivot are unwound, both by the other
+            // stages with their changesets.
+            None => return Ok(UnwindOutput { checkpoint: StageCheckpoint::new(input.unwind_to) }),
+            Some(checkpoint) if checkpoint.pivot <= input.unwind_to => {
+                return Ok(UnwindOutput {
+                    checkpoint: StageCheckpoint::new(input.unwind_to)
+                        .with_state_sync_stage_checkpoint(checkpoint),
+                })
+            }
+            Some(_) => {}
+        }
+
+        // The downloaded state has no changesets to unwind it below the pivot with, so it's
+        // cleared together with the plain state that was built on top of it, and the state is
+        // downloaded again for a new pivot.
+        let tx = provider.tx_ref();
+        tx.clear::<tables::HashedAccount>()?;
+        tx.clear::<tables::HashedStorage>()?;
+        tx.clear::<tables::AccountsTrie>()?;
+        tx.clear::<tables::StoragesTrie>()?;
+        tx.clear::<tables::PlainAccountState>()?;
+        tx.clear::<tables::PlainStorageState>()?;
+        for stage_id in [
+            StageId::Execution,
+            StageId::AccountHashing,
+            StageId::StorageHashing,
+            StageId::MerkleUnwind,
+            StageId::MerkleExecute,
+        ] {
+            provider.save_stage_checkpoint(stage_id, StageCheckpoint::new(0))?;
+        }
+
+        Ok(UnwindOutput { checkpoint: StageCheckpoint::new(input.unwind_to) })
+    }
+}
+
+fn recoverable(error: impl std::error::Error + Send + Sync + 'static) -> StageError {
+    StageError::Recoverable(Box::new(error))
+}
+
+/// Returns `true` if the execution or hashing stages already built the state.
+fn has_state<DB: Database>(provider: &DatabaseProviderRW<'_, &DB>) -> Result<bool, StageError> {
+    for stage_id in [StageId::Execution, StageId::AccountHashing] {
+        if provider.get_stage_checkpoint(stage_id)?.unwrap_or_default().block_number > 0 {
+            return Ok(true)
+        }
+    }
+    Ok(false)
+}
+
+/// Returns the hash following the given one, if any.
+fn increment(hash: H256) -> Option<H256> {
+    U256::from_be_bytes(hash.0).checked_add(U256::from(1)).map(|next| H256(next.to_be_bytes()))
+}
+
+/// Estimates the total number of accounts from the part of the key space that is downloaded.
+fn estimate_total(processed: u64, next_account: Option<H256>) -> u64 {
+    let Some(next_account) = next_account else { return processed };
+    let covered = u64::from_be_bytes(next_account[..8].try_into().expect("slice of 8 bytes"));
+    if covered == 0 {
+        return processed
+    }
+    (processed as u128 * u64::MAX as u128 / covered as u128) as u64
+}
+
+/// Writes the accounts, and downloads their storage and bytecodes.
+///
+/// The previous storage of the accounts is removed.
+async fn download_accounts<DB: Database, C: SnapClient>(
+    provider: &DatabaseProviderRW<'_, &DB>,
+    downloader: &SnapDownloader<C>,
+    accounts: Vec<(H256, SnapAccount)>,
+) -> Result<(), StageError> {
+    let tx = provider.tx_ref();
+    {
+        let mut storage_cursor = tx.cursor_dup_write::<tables::HashedStorage>()?;
+        for (hashed_address, account) in &accounts {
+            if storage_cursor.seek_exact(*hashed_address)?.is_some() {
+                storage_cursor.delete_current_duplicates()?;
+            }
+            tx.put::<tables::HashedAccount>(*hashed_address, Account::from(*account))?;
+        }
+    }
+
+    let mut pending = accounts
+        .iter()
+        .filter(|(_, account)| account.storage_root != EMPTY_ROOT)
+        .map(|(hashed_address, account)| (*hashed_address, account.storage_root))
+        .collect::<Vec<_>>();
+    while !pending.is_empty() {
+        let batch = &pending[..pending.len().min(MAX_STORAGE_ACCOUNTS)];
+        let ranges = downloader.storage_ranges(batch).await.map_err(recoverable)?;
+        let served = ranges.len();
+
+        for (mut range, (_, storage_root)) in ranges.into_iter().zip(batch) {
+            loop {
+                let next = range.slots.last().and_then(|(last, _)| increment(*last));
+                for (key, value) in range.slots {
+                    tx.put::<tables::HashedStorage>(range.account, StorageEntry { key, value })?;
+                }
+                match next {
+                    Some(next) if range.has_more => {
+                        range = downloader
+                            .storage_range(range.account, *storage_root, next)
+                            .await
+                            .map_err(recoverable)?;
+                    }
+                    _ => break,
+                }
+            }
+        }
+        pending.drain(..served);
+    }
+
+    let mut missing = Vec::new();
+    for code_hash in accounts
+        .iter()
+        .map(|(_, account)| account.code_hash)
+        .filter(|code_hash| *code_hash != KECCAK_EMPTY)
+        .collect::<HashSet<_>>()
+    {
+        if tx.get::<tables::Bytecodes>(code_hash)?.is_none() {
+            missing.push(code_hash);
+        }
+    }
+    while !missing.is_empty() {
+        let codes = downloader
+            .byte_codes(&missing[..missing.len().min(MAX_HASHES)])
+            .await
+            .map_err(recoverable)?;
+        for (code_hash, code) in &codes {
+            tx.put::<tables::Bytecodes>(*code_hash, Bytecode::new_raw(code.clone().into()))?;
+        }
+        missing.retain(|code_hash| !codes.contains_key(code_hash));
+    }
+
+    Ok(())
+}
+
+/// Computes the state root and stores the intermediate hashes.
+///
+/// Without the changed accounts, the root is computed from scratch. Otherwise, only the subtries
+/// with the changed accounts are recomputed.
+///
+/// Returns the root and the root node of the account trie, which is not stored.
+fn compute_state_root<DB: Database>(
+    provider: &DatabaseProviderRW<'_, &DB>,
+    changed_accounts: Option<PrefixSet>,
+) -> Result<(H256, Option<BranchNodeCompact>), StageError> {
+    let tx = provider.tx_ref();
+    let state_root = match changed_accounts {
+        Some(changed_accounts) => {
+            StateRoot::new(tx).with_changed_account_prefixes(changed_accounts)
+        }
+        None => {
+            tx.clear::<tables::AccountsTrie>()?;
+            tx.clear::<tables::StoragesTrie>()?;
+            StateRoot::new(tx)
+        }
+    };
+
+    let (root, updates) =
+        state_root.root_with_updates().map_err(|e| StageError::Fatal(Box::new(e)))?;
+    let root_node = match updates.get(&TrieKey::AccountNode(StoredNibbles::from(Vec::new()))) {
+        Some(TrieOp::Update(node)) => Some(node.clone()),
+        _ => None,
+    };
+    updates.flush(tx)?;
+
+    Ok((root, root_node))
+}
+
+/// Walks the state trie of the peers from the root, and returns the prefixes of the subtries that
+/// are different in the local trie.
+async fn find_stale_prefixes<DB: Database, C: SnapClient>(
+    provider: &DatabaseProviderRW<'_, &DB>,
+    downloader: &SnapDownloader<C>,
+    root_node: Option<BranchNodeCompact>,
+) -> Result<Vec<Nibbles>, StageError> {
+    let tx = provider.tx_ref();
+    let mut stale = Vec::new();
+    let mut queue = vec![(Nibbles::default(), downloader.root())];
+
+    while !queue.is_empty() {
+        let batch = queue.split_off(queue.len().saturating_sub(MAX_HASHES));
+        let paths = batch
+            .iter()
+            .map(|(path, hash)| (Bytes::from(path.encode_path_leaf(false)), *hash))
+            .collect::<Vec<_>>();
+        let nodes = downloader.account_trie_nodes(&paths).await.map_err(recoverable)?;
+        // Nodes that were not served are requested again.
+        queue.extend_from_slice(&batch[nodes.len()..]);
+
+        for ((path, _), node) in batch.into_iter().zip(nodes) {
+            let local = if path.is_empty() {
+                root_node.clone()
+            } else {
+                tx.get::<tables::AccountsTrie>(StoredNibbles::from(path.hex_data.to_vec()))?
+            };
+
+            // Extension and leaf nodes, or subtries without the intermediate hashes stored
+            // locally are downloaded entirely.
+            let children = branch_node_children(&node).map_err(recoverable)?;
+            let (Some(children), Some(local)) = (children, local) else {
+                stale.push(path);
+                continue
+            };
+
+            for nibble in CHILD_INDEX_RANGE {
+                let child_path = path.join(&Nibbles::from_hex(vec![nibble]));
+                let local_hash =
+                    local.hash_mask.is_bit_set(nibble).then(|| local.hash_for_nibble(nibble));
+                match children[nibble as usize] {
+                    Some(hash) if Some(hash) == local_hash => {}
+                    Some(hash) if local_hash.is_some() => queue.push((child_path, hash)),
+                    Some(_) => stale.push(child_path),
+                    None if local.state_mask.is_bit_set(nibble) => stale.push(child_path),
+                    None => {}
+                }
+            }
+        }
+    }
+
+    Ok(stale)
+}
+
+/// Removes the local accounts under the prefix, and downloads them again.
+///
+/// The intermediate hashes of the storage of the removed accounts are removed too, so that their
+/// storage roots are recomputed. Returns the hashed addresses of the removed and downloaded
+/// accounts.
+async fn resync_prefix<DB: Database, C: SnapClient>(
+    provider: &DatabaseProviderRW<'_, &DB>,
+    downloader: &SnapDownloader<C>,
+    prefix: Nibbles,
+) -> Result<Vec<H256>, StageError> {
+    let bound = |fill: u8| {
+        let mut nibbles = prefix.hex_data.to_vec();
+        nibbles.resize(64, fill);
+        H256::from_slice(&Nibbles::from_hex(nibbles).pack())
+    };
+    let (start, end) = (bound(0), bound(0xf));
+
+    let mut changed = Vec::new();
+    {
+        let tx = provider.tx_ref();
+        let mut account_cursor = tx.cursor_write::<tables::HashedAccount>()?;
+        let mut storage_cursor = tx.cursor_dup_write::<tables::HashedStorage>()?;
+        let mut storage_trie_cursor = tx.cursor_dup_write::<tables::StoragesTrie>()?;
+        let mut walker = account_cursor.walk_range(start..=end)?;
+        while let Some((hashed_address, _)) = walker.next().transpose()? {
+            if storage_cursor.seek_exact(hashed_address)?.is_some() {
+                storage_cursor.delete_current_duplicates()?;
+            }
+            if storage_trie_cursor.seek_exact(hashed_address)?.is_some() {
+                storage_trie_cursor.delete_current_duplicates()?;
+            }
+            walker.delete_current()?;
+            changed.push(hashed_address);
+        }
+    }
+
+    let mut origin = start;
+    loop {
+        let range = downloader.account_range(origin).await.map_err(recoverable)?;
+        let complete =
+            !range.has_more || range.accounts.last().map_or(true, |(last, _)| *last >= end);
+        let accounts = range
+            .accounts
+            .into_iter()
+            .filter(|(hashed_address, _)| *hashed_address <= end)
+            .collect::<Vec<_>>();
+        let next = accounts.last().and_then(|(last, _)| increment(*last));
+        changed.extend(accounts.iter().map(|(hashed_address, _)| *hashed_address));
+        download_accounts(provider, downloader, accounts).await?;
+
+        match next {
+            Some(next) if !complete => origin = next,
+            _ => return Ok(changed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TestTransaction;
+    use reth_downloaders::test_utils::TestSnapClient;
+    use reth_interfaces::test_utils::generators::{self, random_block, random_eoa_account};
+    use reth_primitives::{keccak256, MAINNET};
+    use reth_provider::ProviderFactory;
+    use std::collections::BTreeMap;
+
+    type State = BTreeMap<H256, (Account, Vec<(H256, U256)>)>;
+
+    /// Generates a random state with some contracts with storage, returning the accounts by
+    /// their hashed addresses, and the bytecodes.
+    fn random_state(count: u64) -> (State, Vec<Bytes>) {
+        let mut rng = generators::rng();
+        let mut accounts = BTreeMap::new();
+        let mut codes = Vec::new();
+        for i in 0..count {
+            let (address, mut account) = random_eoa_account(&mut rng);
+            let mut storage = Vec::new();
+            if i % 3 == 0 {
+                let code = Bytes::from(i.to_be_bytes().to_vec());
+                account.bytecode_hash = Some(keccak256(&code));
+                codes.push(code);
+                storage = (1..=i % 20 + 1)
+                    .map(|slot| (keccak256(H256::from_low_u64_be(slot)), U256::from(i * slot + 1)))
+                    .collect();
+            }
+            accounts.insert(keccak256(address), (account, storage));
+        }
+        (accounts, codes)
+    }
+
+    fn insert_pivot(tx: &TestTransaction, number: u64, state_root: H256) {
+        let mut rng = generators::rng();
+        let mut header = random_block(&mut rng, number, None, Some(0), None).header.unseal();
+        header.state_root = state_root;
+        tx.insert_headers(std::iter::once(&header.seal_slow())).unwrap();
+    }
+
+    async fn run(
+        tx: &TestTransaction,
+        stage: &mut StateSyncStage<TestSnapClient>,
+        input: ExecInput,
+    ) -> Result<ExecOutput, StageError> {
+        let factory = ProviderFactory::new(tx.tx.as_ref(), MAINNET.clone());
+        let provider = factory.provider_rw()?;
+        let output = stage.execute(&provider, input).await?;
+        provider.commit()?;
+        Ok(output)
+    }
+
+    /// Runs the stage until it's done.
+    async fn sync(
+        tx: &TestTransaction,
+        stage: &mut StateSyncStage<TestSnapClient>,
+        mut input: ExecInput,
+    ) -> Result<StageCheckpoint, StageError> {
+        loop {
+            let output = run(tx, stage, input).await?;
+            if output.done {
+                return Ok(output.checkpoint)
+            }
+            input.checkpoint = Some(output.checkpoint);
+        }
+    }
+
+    fn assert_state(tx: &TestTransaction, accounts: &State) {
+        assert_eq!(
+            tx.table::<tables::HashedAccount>().unwrap(),
+            accounts.iter().map(|(hash, (account, _))| (*hash, *account)).collect::<Vec<_>>()
+        );
+
+        let mut storage = accounts
+            .iter()
+            .flat_map(|(hash, (_, storage))| {
+                storage
+                    .iter()
+                    .map(|(key, value)| (*hash, StorageEntry { key: *key, value: *value }))
+            })
+            .collect::<Vec<_>>();
+        storage.sort_by_key(|(hash, entry)| (*hash, entry.key));
+        assert_eq!(tx.table::<tables::HashedStorage>().unwrap(), storage);
+    }
+
+    #[tokio::test]
+    async fn downloads_state() {
+        let tx = TestTransaction::default();
+        let (accounts, codes) = random_state(200);
+        let client = TestSnapClient::new(accounts.clone(), codes.clone()).with_max_entries(16);
+        insert_pivot(&tx, 10, client.state_root());
+
+        let mut stage = StateSyncStage::new(client).with_commit_threshold(3);
+        let input = ExecInput { target: Some(10), checkpoint: None };
+        let checkpoint = sync(&tx, &mut stage, input).await.unwrap();
+        assert_eq!(checkpoint.block_number, 10);
+        assert_eq!(checkpoint.entities().map(|entities| entities.processed), Some(200));
+
+        assert_state(&tx, &accounts);
+        assert_eq!(tx.table::<tables::Bytecodes>().unwrap().len(), codes.len());
+        let provider = tx.inner();
+        for stage_id in [
+            StageId::Execution,
+            StageId::AccountHashing,
+            StageId::StorageHashing,
+            StageId::MerkleExecute,
+        ] {
+            let checkpoint = provider.get_stage_checkpoint(stage_id).unwrap();
+            assert_eq!(checkpoint.map(|checkpoint| checkpoint.block_number), Some(10));
+        }
+
+        // The state was downloaded, so the next run is a no-op that keeps the pivot.
+        let mut stage = StateSyncStage::new(TestSnapClient::default());
+        let input = ExecInput { target: Some(20), checkpoint: Some(checkpoint) };
+        let checkpoint = sync(&tx, &mut stage, input).await.unwrap();
+        assert_eq!(checkpoint.block_number, 20);
+        assert_eq!(
+            checkpoint.state_sync_stage_checkpoint().map(|checkpoint| checkpoint.pivot),
+            Some(10)
+        );
+    }
+
+    #[tokio::test]
+    async fn skips_executed_state() {
+        let tx = TestTransaction::default();
+        insert_pivot(&tx, 10, H256::random());
+        let factory = ProviderFactory::new(tx.tx.as_ref(), MAINNET.clone());
+        let provider = factory.provider_rw().unwrap();
+        provider.save_stage_checkpoint(StageId::Execution, StageCheckpoint::new(5)).unwrap();
+        provider.commit().unwrap();
+
+        let mut stage = StateSyncStage::new(TestSnapClient::default());
+        let input = ExecInput { target: Some(10), checkpoint: None };
+        assert_eq!(sync(&tx, &mut stage, input).await.unwrap(), StageCheckpoint::new(10));
+        assert!(tx.table::<tables::HashedAccount>().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn unwinds_below_pivot() {
+        let tx = TestTransaction::default();
+        let (accounts, codes) = random_state(20);
+        let client = TestSnapClient::new(accounts, codes);
+        insert_pivot(&tx, 10, client.state_root());
+
+        let mut stage = StateSyncStage::new(client);
+        let input = ExecInput { target: Some(10), checkpoint: None };
+        let checkpoint = sync(&tx, &mut stage, input).await.unwrap();
+
+        // Unwinding the blocks after the pivot keeps the downloaded state.
+        let factory = ProviderFactory::new(tx.tx.as_ref(), MAINNET.clone());
+        let provider = factory.provider_rw().unwrap();
+        let input = UnwindInput { checkpoint, unwind_to: 10, bad_block: None };
+        let output = stage.unwind(&provider, input).await.unwrap();
+        assert_eq!(
+            output.checkpoint.state_sync_stage_checkpoint(),
+            checkpoint.state_sync_stage_checkpoint()
+        );
+        assert_eq!(provider.tx_ref().entries::<tables::HashedAccount>().unwrap(), 20);
+
+        // Unwinding below the pivot removes it.
+        let input = UnwindInput { checkpoint, unwind_to: 9, bad_block: None };
+        let output = stage.unwind(&provider, input).await.unwrap();
+        assert_eq!(output.checkpoint, StageCheckpoint::new(9));
+        assert_eq!(provider.tx_ref().entries::<tables::HashedAccount>().unwrap(), 0);
+        let checkpoint = provider.get_stage_checkpoint(StageId::Execution).unwrap();
+        assert_eq!(checkpoint, Some(StageCheckpoint::new(0)));
+    }
+
+    #[tokio::test]
+    async fn heals_changed_pivot() {
+        let tx = TestTransaction::default();
+        let (mut accounts, codes) = random_state(100);
+        let client = TestSnapClient::new(accounts.clone(), codes.clone()).with_max_entries(10);
+        insert_pivot(&tx, 10, client.state_root());
+
+        // Download a part of the state of the first pivot.
+        let mut stage = StateSyncStage::new(client).with_commit_threshold(3);
+        let input = ExecInput { target: Some(10), checkpoint: None };
+        let output = run(&tx, &mut stage, input).await.unwrap();
+        assert!(!output.done);
+
+        // Change the balances and the storage of some of the downloaded accounts, and remove one.
+        let downloaded = tx.table::<tables::HashedAccount>().unwrap();
+        for (hashed_address, _) in downloaded.iter().step_by(5) {
+            let (account, storage) = accounts.get_mut(hashed_address).unwrap();
+            account.balance += U256::from(1);
+            if let Some((_, value)) = storage.first_mut() {
+                *value += U256::from(1);
+            }
+        }
+        accounts.remove(&downloaded[1].0);
+
+        // Continue with the new pivot.
+        let client = TestSnapClient::new(accounts.clone(), codes).with_max_entries(10);
+        insert_pivot(&tx, 11, client.state_root());
+        let mut stage = StateSyncStage::new(client).with_commit_threshold(3);
+        let input = ExecInput { target: Some(11), checkpoint: Some(output.checkpoint) };
+        let checkpoint = sync(&tx, &mut stage, input).await.unwrap();
+        assert_eq!(checkpoint.block_number, 11);
+
+        assert_state(&tx, &accounts);
+    }
+
+    #[tokio::test]
+    async fn unknown_root() {
+        let tx = TestTransaction::default();
+        let (accounts, codes) = random_state(10);
+        insert_pivot(&tx, 10, H256::random());
+
+        let mut stage = StateSyncStage::new(TestSnapClient::new(accounts, codes));
+        let input = ExecInput { target: Some(10), checkpoint: None };
+        assert!(matches!(sync(&tx, &mut stage, input).await, Err(StageError::Recoverable(_))));
+    }
+}
@@ -30,7 +30,7 @@ use crate::{
             accounts::{AccountBeforeTx, BlockNumberAddress},
             blocks::{HeaderHash, StoredBlockOmmers},
             storage_sharded_key::StorageShardedKey,
-            ShardedKey, StoredBlockBodyIndices, StoredBlockWithdrawals,
+            ShardedKey, StoredBlockBodyIndices, StoredBlockWithdrawals, StoredSidechainBlock,
         },
     },
 };
@@ -51,7 +51,7 @@ pub enum TableType {
 }
 
 /// Number of tables that should be present inside database.
-pub const NUM_TABLES: usize = 26;
+pub const NUM_TABLES: usize = 27;
 
 /// The general purpose of this is to use with a combination of Tables enum,
 /// by implementing a `TableViewer` trait you can operate on db tables in an abstract way.
@@ -184,7 +184,8 @@ tables!([
     (TxSenders, TableType::Table),
     (SyncStage, TableType::Table),
     (SyncStageProgress, TableType::Table),
-    (PruneCheckpoints, TableType::Table)
+    (PruneCheckpoints, TableType::Table),
+    (SidechainBlocks, TableType::Table)
 ]);
 
 #[macro_export]
@@ -421,6 +422,12 @@ table!(
     ( PruneCheckpoints ) PrunePart | PruneCheckpoint
 );
 
+table!(
+    /// Stores the non-canonical blocks of the blockchain tree, so that side chains within the
+    /// reorg window survive a restart.
+    ( SidechainBlocks ) BlockHash | StoredSidechainBlock
+);
+
 /// Alias Types
 
 /// List with transaction numbers.
@@ -461,6 +468,7 @@ mod tests {
         (TableType::Table, SyncStage::const_name()),
         (TableType::Table, SyncStageProgress::const_name()),
         (TableType::Table, PruneCheckpoints::const_name()),
+        (TableType::Table, SidechainBlocks::const_name()),
     ];
 
     #[test]
@@ -16,7 +16,7 @@ use serde::{Deserialize, Serialize};
 ///
 /// [`Address`] is the subkey.
 #[derive_arbitrary(compact)]
-#[derive(Debug, Default, Clone, Eq, PartialEq, Serialize)]
+#[derive(Debug, Default, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct AccountBeforeTx {
     /// Address for the account. Acts as `DupSort::SubKey`.
     pub address: Address,
@@ -1,7 +1,10 @@
 //! Block related models and types.
 
 use reth_codecs::{main_codec, Compact};
-use reth_primitives::{Header, TxNumber, Withdrawal, H256};
+use reth_primitives::{
+    Address, BlockHash, Header, SealedBlock, SealedBlockWithSenders, TransactionSignedNoHash,
+    TxNumber, Withdrawal, H256,
+};
 use std::ops::Range;
 
 /// Total number of transactions.
@@ -84,6 +87,48 @@ pub struct StoredBlockWithdrawals {
     pub withdrawals: Vec<Withdrawal>,
 }
 
+/// The storage representation of a non-canonical block of the blockchain tree.
+#[main_codec]
+#[derive(Debug, Default, Eq, PartialEq, Clone)]
+pub struct StoredSidechainBlock {
+    /// The block header.
+    pub header: Header,
+    /// The block transactions.
+    pub transactions: Vec<TransactionSignedNoHash>,
+    /// The recovered senders of the transactions.
+    pub senders: Vec<Address>,
+    /// The block headers of this block's uncles.
+    pub ommers: Vec<Header>,
+    /// The block withdrawals.
+    pub withdrawals: Option<Vec<Withdrawal>>,
+}
+
+impl StoredSidechainBlock {
+    /// Converts the stored block back into a block with the given hash.
+    pub fn into_block(self, hash: BlockHash) -> SealedBlockWithSenders {
+        let block = SealedBlock {
+            header: self.header.seal(hash),
+            body: self.transactions.into_iter().map(|tx| tx.with_hash()).collect(),
+            ommers: self.ommers,
+            withdrawals: self.withdrawals,
+        };
+        SealedBlockWithSenders { block, senders: self.senders }
+    }
+}
+
+impl From<SealedBlockWithSenders> for StoredSidechainBlock {
+    fn from(block: SealedBlockWithSenders) -> Self {
+        let SealedBlockWithSenders { block, senders } = block;
+        Self {
+            header: block.header.unseal(),
+            transactions: block.body.into_iter().map(Into::into).collect(),
+            senders,
+            ommers: block.ommers,
+            withdrawals: block.withdrawals,
+        }
+    }
+}
+
 /// Hash of the block header. Value for [`CanonicalHeaders`][crate::tables::CanonicalHeaders]
 pub type HeaderHash = H256;
 
@@ -102,6 +147,21 @@ mod test {
         );
     }
 
+    #[test]
+    fn sidechain_block_roundtrip() {
+        let block = SealedBlockWithSenders {
+            block: SealedBlock {
+                header: Header { number: 1, ..Default::default() }.seal_slow(),
+                withdrawals: Some(vec![Withdrawal::default()]),
+                ..Default::default()
+            },
+            senders: vec![],
+        };
+        let stored = StoredSidechainBlock::from(block.clone());
+        let decoded = StoredSidechainBlock::decompress::<Vec<_>>(stored.compress()).unwrap();
+        assert_eq!(decoded.into_block(block.hash), block);
+    }
+
     #[test]
     fn block_indices() {
         let first_tx_num = 10;
@@ -0,0 +1,95 @@
+//! The era1 accumulator root.
+//!
+//! The accumulator is the SSZ `hash_tree_root` of a `List[HeaderRecord, 8192]`, where a header
+//! record is the container `{block_hash: Bytes32, total_difficulty: uint256}`.
+
+use crate::MAX_BLOCKS_PER_ERA1;
+use reth_primitives::{H256, U256};
+use sha2::{Digest, Sha256};
+
+/// The depth of the merkle tree over the maximum number of header records.
+const DEPTH: usize = MAX_BLOCKS_PER_ERA1.trailing_zeros() as usize;
+
+/// An entry of the accumulator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeaderRecord {
+    /// The hash of the block.
+    pub block_hash: H256,
+    /// The total difficulty at the block.
+    pub total_difficulty: U256,
+}
+
+impl HeaderRecord {
+    /// Returns the SSZ hash tree root of the record.
+    fn tree_root(&self) -> [u8; 32] {
+        hash_pair(&self.block_hash.0, &self.total_difficulty.to_le_bytes::<32>())
+    }
+}
+
+/// Computes the accumulator root of the given header records.
+///
+/// # Panics
+///
+/// If there are more than [MAX_BLOCKS_PER_ERA1] records.
+pub fn accumulator_root(records: &[HeaderRecord]) -> H256 {
+    assert!(records.len() <= MAX_BLOCKS_PER_ERA1, "too many header records");
+
+    // zero_hashes[i] is the root of an empty subtree of depth i
+    let mut zero_hashes = [[0u8; 32]; DEPTH + 1];
+    for depth in 1..=DEPTH {
+        zero_hashes[depth] = hash_pair(&zero_hashes[depth - 1], &zero_hashes[depth - 1]);
+    }
+
+    let mut layer = records.iter().map(HeaderRecord::tree_root).collect::<Vec<_>>();
+    for zero_hash in zero_hashes.iter().take(DEPTH) {
+        if layer.len() % 2 == 1 {
+            layer.push(*zero_hash);
+        }
+        layer = layer.chunks(2).map(|pair| hash_pair(&pair[0], &pair[1])).collect();
+    }
+    let root = layer.first().copied().unwrap_or(zero_hashes[DEPTH]);
+
+    // mix in the length of the list
+    let mut length = [0u8; 32];
+    length[..8].copy_from_slice(&(records.len() as u64).to_le_bytes());
+    H256(hash_pair(&root, &length))
+}
+
+fn hash_pair(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_accumulator() {
+        // the root of an empty list is the zero subtree root mixed with a zero length
+        let mut zero = [0u8; 32];
+        for _ in 0..DEPTH {
+            zero = hash_pair(&zero, &zero);
+        }
+        assert_eq!(accumulator_root(&[]), H256(hash_pair(&zero, &[0u8; 32])));
+    }
+
+    #[test]
+    fn single_record() {
+        let record =
+            HeaderRecord { block_hash: H256::from_low_u64_be(1), total_difficulty: U256::from(2) };
+
+        let mut node = record.tree_root();
+        let mut zero = [0u8; 32];
+        for _ in 0..DEPTH {
+            node = hash_pair(&node, &zero);
+            zero = hash_pair(&zero, &zero);
+        }
+        let mut length = [0u8; 32];
+        length[0] = 1;
+
+        assert_eq!(accumulator_root(&[record]), H256(hash_pair(&node, &length)));
+    }
+}
@@ -0,0 +1,163 @@
+//! The e2store framing used by era and era1 files.
+//!
+//! An e2store file is a sequence of entries, each prefixed with an 8 byte header:
+//!
+//! ```text
+//! type: [2]byte | length: LE uint32 | reserved: [2]byte (zero) | data: [length]byte
+//! ```
+//!
+//! See <https://github.com/status-im/nimbus-eth2/blob/stable/docs/e2store.md>
+
+use crate::Era1Error;
+use std::io::{self, Read, Write};
+
+/// The length of an entry header.
+pub const HEADER_LEN: usize = 8;
+
+/// The version entry, which must be the first entry of every e2store file.
+pub const VERSION: u16 = 0x3265;
+
+/// A single entry of an e2store file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// The type of the entry.
+    pub entry_type: u16,
+    /// The raw data of the entry.
+    pub data: Vec<u8>,
+}
+
+impl Entry {
+    /// Creates a new entry.
+    pub fn new(entry_type: u16, data: Vec<u8>) -> Self {
+        Self { entry_type, data }
+    }
+
+    /// Returns the version entry.
+    pub fn version() -> Self {
+        Self::new(VERSION, Vec::new())
+    }
+
+    /// Returns the length of the entry once written, including the header.
+    pub fn encoded_len(&self) -> usize {
+        HEADER_LEN + self.data.len()
+    }
+}
+
+/// Reads e2store entries one after another.
+#[derive(Debug)]
+pub struct E2StoreReader<R> {
+    reader: R,
+    /// The offset of the next entry from the start of the stream.
+    position: u64,
+}
+
+impl<R: Read> E2StoreReader<R> {
+    /// Creates a new reader starting at the beginning of the stream.
+    pub fn new(reader: R) -> Self {
+        Self { reader, position: 0 }
+    }
+
+    /// Returns the offset of the next entry from the start of the stream.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Reads the next entry, returning `None` at the end of the stream.
+    pub fn read_entry(&mut self) -> Result<Option<Entry>, Era1Error> {
+        let mut header = [0u8; HEADER_LEN];
+        match self.reader.read_exact(&mut header) {
+            Ok(()) => {}
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(err) => return Err(err.into()),
+        }
+
+        let entry_type = u16::from_le_bytes([header[0], header[1]]);
+        let len = u32::from_le_bytes([header[2], header[3], header[4], header[5]]) as usize;
+        if header[6] != 0 || header[7] != 0 {
+            return Err(Era1Error::InvalidEntryHeader { position: self.position })
+        }
+
+        let mut data = vec![0u8; len];
+        self.reader.read_exact(&mut data)?;
+        self.position += (HEADER_LEN + len) as u64;
+
+        Ok(Some(Entry { entry_type, data }))
+    }
+}
+
+/// Writes e2store entries one after another.
+#[derive(Debug)]
+pub struct E2StoreWriter<W> {
+    writer: W,
+    /// The offset of the next entry from the start of the stream.
+    position: u64,
+}
+
+impl<W: Write> E2StoreWriter<W> {
+    /// Creates a new writer starting at the beginning of the stream.
+    pub fn new(writer: W) -> Self {
+        Self { writer, position: 0 }
+    }
+
+    /// Returns the offset of the next entry from the start of the stream.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Writes a single entry.
+    pub fn write_entry(&mut self, entry: &Entry) -> Result<(), Era1Error> {
+        let len = u32::try_from(entry.data.len()).map_err(|_| Era1Error::EntryTooLarge)?;
+
+        let mut header = [0u8; HEADER_LEN];
+        header[..2].copy_from_slice(&entry.entry_type.to_le_bytes());
+        header[2..6].copy_from_slice(&len.to_le_bytes());
+        self.writer.write_all(&header)?;
+        self.writer.write_all(&entry.data)?;
+        self.position += entry.encoded_len() as u64;
+
+        Ok(())
+    }
+
+    /// Flushes the underlying writer.
+    pub fn flush(&mut self) -> Result<(), Era1Error> {
+        Ok(self.writer.flush()?)
+    }
+
+    /// Consumes the writer, returning the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_roundtrip() {
+        let entries =
+            vec![Entry::version(), Entry::new(0x03, vec![1, 2, 3]), Entry::new(0x07, vec![])];
+
+        let mut writer = E2StoreWriter::new(Vec::new());
+        for entry in &entries {
+            writer.write_entry(entry).unwrap();
+        }
+        assert_eq!(writer.position(), 8 + 11 + 8);
+        let buf = writer.into_inner();
+        assert_eq!(&buf[..8], &[0x65, 0x32, 0, 0, 0, 0, 0, 0]);
+
+        let mut reader = E2StoreReader::new(buf.as_slice());
+        let mut decoded = Vec::new();
+        while let Some(entry) = reader.read_entry().unwrap() {
+            decoded.push(entry);
+        }
+        assert_eq!(decoded, entries);
+    }
+
+    #[test]
+    fn rejects_reserved_bytes() {
+        let buf = [0x03, 0, 0, 0, 0, 0, 1, 0];
+        let mut reader = E2StoreReader::new(&buf[..]);
+        assert!(matches!(reader.read_entry(), Err(Era1Error::InvalidEntryHeader { position: 0 })));
+    }
+}
@@ -0,0 +1,403 @@
+//! The era1 file format for pre-merge execution layer history.
+//!
+//! ```text
+//! era1 := Version | block-tuple* | other-entries* | Accumulator | BlockIndex
+//! block-tuple := CompressedHeader | CompressedBody | CompressedReceipts | TotalDifficulty
+//! ```
+//!
+//! Headers, bodies and receipts are RLP encoded and compressed with the snappy framing format. The
+//! total difficulty is a little endian `uint256`. The block index holds the number of the first
+//! block, the offset of each block tuple relative to the start of the index entry and the number of
+//! blocks.
+//!
+//! See <https://github.com/ethereum/go-ethereum/blob/master/internal/era/era.go>
+
+use crate::{
+    accumulator::{accumulator_root, HeaderRecord},
+    e2s::{E2StoreReader, E2StoreWriter, Entry, VERSION},
+    Era1Error,
+};
+use reth_primitives::{
+    Block, BlockBody, BlockNumber, Header, ReceiptWithBloom, SealedHeader, H256, U256,
+};
+use reth_rlp::{Decodable, Encodable, Header as RlpHeader};
+use std::io::{Read, Write};
+
+/// Entry type of a snappy compressed RLP header.
+pub const COMPRESSED_HEADER: u16 = 0x03;
+/// Entry type of a snappy compressed RLP block body.
+pub const COMPRESSED_BODY: u16 = 0x04;
+/// Entry type of snappy compressed RLP receipts.
+pub const COMPRESSED_RECEIPTS: u16 = 0x05;
+/// Entry type of a little endian total difficulty.
+pub const TOTAL_DIFFICULTY: u16 = 0x06;
+/// Entry type of the accumulator root.
+pub const ACCUMULATOR: u16 = 0x07;
+/// Entry type of the block index.
+pub const BLOCK_INDEX: u16 = 0x3266;
+
+/// The maximum number of blocks in a single era1 file.
+pub const MAX_BLOCKS_PER_ERA1: usize = 8192;
+
+/// A block of an era1 file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Era1Block {
+    /// The sealed block header.
+    pub header: SealedHeader,
+    /// The block body.
+    pub body: BlockBody,
+    /// The receipts of the block.
+    ///
+    /// `None` if the receipts carry pre-Byzantium intermediate state roots instead of a status,
+    /// which can't be represented by a [ReceiptWithBloom].
+    pub receipts: Option<Vec<ReceiptWithBloom>>,
+    /// The total difficulty at this block.
+    pub total_difficulty: U256,
+}
+
+impl Era1Block {
+    /// Returns the accumulator record of the block.
+    pub fn header_record(&self) -> HeaderRecord {
+        HeaderRecord { block_hash: self.header.hash, total_difficulty: self.total_difficulty }
+    }
+
+    /// Returns the block without receipts and total difficulty.
+    pub fn into_block(self) -> Block {
+        Block {
+            header: self.header.unseal(),
+            body: self.body.transactions,
+            ommers: self.body.ommers,
+            withdrawals: self.body.withdrawals,
+        }
+    }
+}
+
+/// An era1 file: a sequence of consecutive blocks and their accumulator root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Era1File {
+    /// The blocks of the file.
+    pub blocks: Vec<Era1Block>,
+    /// The accumulator root of the blocks.
+    pub accumulator: H256,
+}
+
+impl Era1File {
+    /// Creates a new file from consecutive blocks, computing the accumulator root.
+    ///
+    /// Returns an error if there are no or too many blocks, if the blocks are not consecutive or if
+    /// their total difficulties are inconsistent.
+    pub fn new(blocks: Vec<Era1Block>) -> Result<Self, Era1Error> {
+        if blocks.is_empty() || blocks.len() > MAX_BLOCKS_PER_ERA1 {
+            return Err(Era1Error::InvalidBlockCount(blocks.len()))
+        }
+
+        for (parent, block) in blocks.iter().zip(blocks.iter().skip(1)) {
+            if block.header.number != parent.header.number + 1 ||
+                block.header.parent_hash != parent.header.hash
+            {
+                return Err(Era1Error::NonSequentialBlock {
+                    number: block.header.number,
+                    previous: parent.header.number,
+                })
+            }
+            if block.total_difficulty != parent.total_difficulty + block.header.difficulty {
+                return Err(Era1Error::InvalidTotalDifficulty(block.header.number))
+            }
+        }
+
+        let records = blocks.iter().map(Era1Block::header_record).collect::<Vec<_>>();
+        let accumulator = accumulator_root(&records);
+        Ok(Self { blocks, accumulator })
+    }
+
+    /// Returns the number of the first block in the file.
+    pub fn start_block(&self) -> BlockNumber {
+        self.blocks[0].header.number
+    }
+
+    /// Returns the number of the last block in the file.
+    pub fn end_block(&self) -> BlockNumber {
+        self.start_block() + self.blocks.len() as u64 - 1
+    }
+
+    /// Returns the epoch of the file.
+    pub fn epoch(&self) -> u64 {
+        self.start_block() / MAX_BLOCKS_PER_ERA1 as u64
+    }
+
+    /// Returns the canonical file name of the file, e.g. `mainnet-00000-5ec1ffb8.era1`.
+    pub fn file_name(&self, network: &str) -> String {
+        let short_hash =
+            self.accumulator.0[..4].iter().map(|byte| format!("{byte:02x}")).collect::<String>();
+        format!("{network}-{:05}-{short_hash}.era1", self.epoch())
+    }
+
+    /// Writes the file, returning the underlying writer.
+    ///
+    /// Returns an error if the receipts of a block are unknown.
+    pub fn write<W: Write>(&self, writer: W) -> Result<W, Era1Error> {
+        let mut writer = E2StoreWriter::new(writer);
+        writer.write_entry(&Entry::version())?;
+
+        let mut offsets = Vec::with_capacity(self.blocks.len());
+        for block in &self.blocks {
+            let receipts =
+                block.receipts.as_ref().ok_or(Era1Error::MissingReceipts(block.header.number))?;
+
+            offsets.push(writer.position());
+            writer.write_entry(&Entry::new(
+                COMPRESSED_HEADER,
+                compress(&rlp_encode(&block.header.header))?,
+            ))?;
+            writer
+                .write_entry(&Entry::new(COMPRESSED_BODY, compress(&rlp_encode(&block.body))?))?;
+            writer
+                .write_entry(&Entry::new(COMPRESSED_RECEIPTS, compress(&rlp_encode(receipts))?))?;
+            writer.write_entry(&Entry::new(
+                TOTAL_DIFFICULTY,
+                block.total_difficulty.to_le_bytes::<32>().to_vec(),
+            ))?;
+        }
+
+        writer.write_entry(&Entry::new(ACCUMULATOR, self.accumulator.0.to_vec()))?;
+
+        let index_position = writer.position();
+        let mut index = Vec::with_capacity(16 + 8 * offsets.len());
+        index.extend_from_slice(&self.start_block().to_le_bytes());
+        for offset in offsets {
+            index.extend_from_slice(&(offset as i64 - index_position as i64).to_le_bytes());
+        }
+        index.extend_from_slice(&(self.blocks.len() as u64).to_le_bytes());
+        writer.write_entry(&Entry::new(BLOCK_INDEX, index))?;
+
+        writer.flush()?;
+        Ok(writer.into_inner())
+    }
+
+    /// Reads a file, verifying the block index, the total difficulties and the accumulator root.
+    pub fn read<R: Read>(reader: R) -> Result<Self, Era1Error> {
+        let mut reader = E2StoreReader::new(reader);
+        match reader.read_entry()? {
+            Some(entry) if entry.entry_type == VERSION => {}
+            _ => return Err(Era1Error::MissingVersion),
+        }
+
+        let mut blocks = Vec::new();
+        let mut offsets = Vec::new();
+        let accumulator = loop {
+            let position = reader.position();
+            let entry = reader.read_entry()?.ok_or(Era1Error::UnexpectedEnd)?;
+            match entry.entry_type {
+                COMPRESSED_HEADER => {
+                    let header = Header::decode(&mut decompress(&entry.data)?.as_slice())?;
+                    let body = expect_entry(&mut reader, COMPRESSED_BODY)?;
+                    let body = BlockBody::decode(&mut decompress(&body)?.as_slice())?;
+                    let receipts = expect_entry(&mut reader, COMPRESSED_RECEIPTS)?;
+                    let receipts = decode_receipts(&decompress(&receipts)?)?;
+                    let total_difficulty = expect_entry(&mut reader, TOTAL_DIFFICULTY)?;
+                    if total_difficulty.len() != 32 {
+                        return Err(Era1Error::InvalidEntryLength(total_difficulty.len()))
+                    }
+
+                    offsets.push(position);
+                    blocks.push(Era1Block {
+                        header: header.seal_slow(),
+                        body,
+                        receipts,
+                        total_difficulty: U256::from_le_slice(&total_difficulty),
+                    });
+                }
+                ACCUMULATOR => {
+                    if entry.data.len() != 32 {
+                        return Err(Era1Error::InvalidEntryLength(entry.data.len()))
+                    }
+                    break H256::from_slice(&entry.data)
+                }
+                // other entries are allowed and ignored
+                _ => {}
+            }
+        };
+
+        let index_position = reader.position();
+        let index = expect_entry(&mut reader, BLOCK_INDEX)?;
+        if index.len() != 16 + 8 * blocks.len() {
+            return Err(Era1Error::InvalidBlockIndex)
+        }
+        let index = index
+            .chunks_exact(8)
+            .map(|chunk| u64::from_le_bytes(chunk.try_into().expect("8 bytes")))
+            .collect::<Vec<_>>();
+        let start_block = blocks.first().map(|block| block.header.number).unwrap_or_default();
+        let index_offsets = &index[1..index.len() - 1];
+        if index[0] != start_block ||
+            index[index.len() - 1] != blocks.len() as u64 ||
+            index_offsets.iter().zip(offsets).any(|(index_offset, offset)| {
+                *index_offset as i64 != offset as i64 - index_position as i64
+            })
+        {
+            return Err(Era1Error::InvalidBlockIndex)
+        }
+
+        let file = Self::new(blocks)?;
+        if file.accumulator != accumulator {
+            return Err(Era1Error::AccumulatorMismatch {
+                expected: accumulator,
+                got: file.accumulator,
+            })
+        }
+
+        Ok(file)
+    }
+}
+
+/// Reads the next entry, which must be of the given type, and returns its data.
+fn expect_entry<R: Read>(
+    reader: &mut E2StoreReader<R>,
+    expected: u16,
+) -> Result<Vec<u8>, Era1Error> {
+    let entry = reader.read_entry()?.ok_or(Era1Error::UnexpectedEnd)?;
+    if entry.entry_type != expected {
+        return Err(Era1Error::UnexpectedEntry { expected, found: entry.entry_type })
+    }
+    Ok(entry.data)
+}
+
+/// Decodes the receipts of a block, returning `None` if they carry pre-Byzantium state roots.
+fn decode_receipts(rlp: &[u8]) -> Result<Option<Vec<ReceiptWithBloom>>, Era1Error> {
+    let buf = &mut &rlp[..];
+    let list = RlpHeader::decode(buf)?;
+    if !list.list {
+        return Err(reth_rlp::DecodeError::UnexpectedString.into())
+    }
+    if list.payload_length > buf.len() {
+        return Err(reth_rlp::DecodeError::InputTooShort.into())
+    }
+
+    // legacy receipts are lists, whose first field is either the status or a 32 byte state root
+    let mut items = &buf[..list.payload_length];
+    while !items.is_empty() {
+        let item = RlpHeader::decode(&mut items)?;
+        if item.payload_length > items.len() {
+            return Err(reth_rlp::DecodeError::InputTooShort.into())
+        }
+        if item.list {
+            let first = RlpHeader::decode(&mut &items[..item.payload_length])?;
+            if !first.list && first.payload_length == 32 {
+                return Ok(None)
+            }
+        }
+        items = &items[item.payload_length..];
+    }
+
+    Ok(Some(Vec::<ReceiptWithBloom>::decode(&mut &rlp[..])?))
+}
+
+fn rlp_encode<T: Encodable + ?Sized>(value: &T) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(value.length());
+    value.encode(&mut buf);
+    buf
+}
+
+fn compress(data: &[u8]) -> Result<Vec<u8>, Era1Error> {
+    let mut compressed = Vec::new();
+    let mut encoder = snap::write::FrameEncoder::new(&mut compressed);
+    encoder.write_all(data)?;
+    encoder.flush()?;
+    drop(encoder);
+    Ok(compressed)
+}
+
+fn decompress(data: &[u8]) -> Result<Vec<u8>, Era1Error> {
+    let mut decompressed = Vec::new();
+    snap::read::FrameDecoder::new(data).read_to_end(&mut decompressed)?;
+    Ok(decompressed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reth_primitives::{Bloom, Log, Receipt, TxType};
+
+    fn test_blocks(count: u64) -> Vec<Era1Block> {
+        let mut blocks: Vec<Era1Block> = Vec::new();
+        for number in 0..count {
+            let parent = blocks.last();
+            let header = Header {
+                number,
+                parent_hash: parent.map(|parent| parent.header.hash).unwrap_or_default(),
+                difficulty: U256::from(number + 1),
+                ..Default::default()
+            };
+            let receipt = Receipt {
+                tx_type: TxType::Legacy,
+                success: true,
+                cumulative_gas_used: 21_000,
+                logs: vec![Log::default()],
+            };
+            blocks.push(Era1Block {
+                total_difficulty: parent.map(|parent| parent.total_difficulty).unwrap_or_default() +
+                    header.difficulty,
+                header: header.seal_slow(),
+                body: BlockBody::default(),
+                receipts: Some(vec![ReceiptWithBloom::new(receipt, Bloom::default())]),
+            });
+        }
+        blocks
+    }
+
+    #[test]
+    fn era1_roundtrip() {
+        let file = Era1File::new(test_blocks(10)).unwrap();
+        let buf = file.write(Vec::new()).unwrap();
+
+        let decoded = Era1File::read(buf.as_slice()).unwrap();
+        assert_eq!(decoded, file);
+        assert_eq!(decoded.start_block(), 0);
+        assert_eq!(decoded.end_block(), 9);
+        assert!(file.file_name("mainnet").starts_with("mainnet-00000-"));
+    }
+
+    #[test]
+    fn rejects_inconsistent_blocks() {
+        let mut blocks = test_blocks(3);
+        blocks[2].total_difficulty += U256::from(1);
+        assert!(matches!(Era1File::new(blocks), Err(Era1Error::InvalidTotalDifficulty(2))));
+
+        let mut blocks = test_blocks(3);
+        blocks.remove(1);
+        assert!(matches!(
+            Era1File::new(blocks),
+            Err(Era1Error::NonSequentialBlock { number: 2, previous: 0 })
+        ));
+    }
+
+    #[test]
+    fn rejects_accumulator_mismatch() {
+        let mut file = Era1File::new(test_blocks(4)).unwrap();
+        file.accumulator = H256::random();
+        let buf = file.write(Vec::new()).unwrap();
+
+        assert!(matches!(
+            Era1File::read(buf.as_slice()),
+            Err(Era1Error::AccumulatorMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn pre_byzantium_receipts() {
+        // [root, cumulative gas, bloom, logs]
+        let mut receipt = Vec::new();
+        H256::random().encode(&mut receipt);
+        21_000u64.encode(&mut receipt);
+        Bloom::default().encode(&mut receipt);
+        Vec::<Log>::new().encode(&mut receipt);
+        let mut rlp = Vec::new();
+        RlpHeader { list: true, payload_length: receipt.len() }.encode(&mut rlp);
+        rlp.extend(receipt);
+        let mut receipts = Vec::new();
+        RlpHeader { list: true, payload_length: rlp.len() }.encode(&mut receipts);
+        receipts.extend(rlp);
+
+        assert_eq!(decode_receipts(&receipts).unwrap(), None);
+    }
+}
@@ -0,0 +1,67 @@
+use reth_primitives::{BlockNumber, H256};
+
+/// Errors that can occur when reading or writing era1 files.
+#[derive(Debug, thiserror::Error)]
+pub enum Era1Error {
+    /// An I/O error.
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    /// An RLP decoding error.
+    #[error(transparent)]
+    Rlp(#[from] reth_rlp::DecodeError),
+    /// The reserved bytes of an entry header are not zero.
+    #[error("invalid e2store entry header at offset {position}")]
+    InvalidEntryHeader {
+        /// The offset of the entry.
+        position: u64,
+    },
+    /// An entry does not fit the 32 bit length of the entry header.
+    #[error("e2store entry too large")]
+    EntryTooLarge,
+    /// The file does not start with a version entry.
+    #[error("missing e2store version entry")]
+    MissingVersion,
+    /// An entry of an unexpected type was found.
+    #[error("unexpected entry type {found:#06x}, expected {expected:#06x}")]
+    UnexpectedEntry {
+        /// The expected entry type.
+        expected: u16,
+        /// The found entry type.
+        found: u16,
+    },
+    /// The file ended before all expected entries were read.
+    #[error("unexpected end of era1 file")]
+    UnexpectedEnd,
+    /// An entry has an invalid length.
+    #[error("invalid length {0} of era1 entry")]
+    InvalidEntryLength(usize),
+    /// The receipts of a block to write are unknown.
+    #[error("missing receipts of block {0}")]
+    MissingReceipts(BlockNumber),
+    /// The block index does not match the contents of the file.
+    #[error("invalid era1 block index")]
+    InvalidBlockIndex,
+    /// A block does not follow its predecessor in the file.
+    #[error("block {number} does not follow block {previous}")]
+    NonSequentialBlock {
+        /// The number of the block.
+        number: BlockNumber,
+        /// The number of the previous block.
+        previous: BlockNumber,
+    },
+    /// The total difficulty of a block is not the total difficulty of its parent plus the
+    /// difficulty of the block.
+    #[error("invalid total difficulty of block {0}")]
+    InvalidTotalDifficulty(BlockNumber),
+    /// An era1 file holds more than 8192 blocks or no blocks at all.
+    #[error("invalid number of blocks {0} in era1 file")]
+    InvalidBlockCount(usize),
+    /// The accumulator root of the file does not match the contained headers.
+    #[error("accumulator mismatch: expected {expected:?}, got {got:?}")]
+    AccumulatorMismatch {
+        /// The accumulator root stored in the file.
+        expected: H256,
+        /// The accumulator root computed from the headers.
+        got: H256,
+    },
+}
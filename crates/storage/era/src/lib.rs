@@ -0,0 +1,26 @@
+#![cfg_attr(docsrs, feature(doc_cfg))]
+#![doc(
+    html_logo_url = "https://raw.githubusercontent.com/paradigmxyz/reth/main/assets/reth-docs.png",
+    html_favicon_url = "https://avatars0.githubusercontent.com/u/97369466?s=256",
+    issue_tracker_base_url = "https://github.com/paradigmxzy/reth/issues/"
+)]
+#![warn(missing_docs, unreachable_pub, unused_crate_dependencies)]
+#![deny(unused_must_use, rust_2018_idioms)]
+#![doc(test(
+    no_crate_inject,
+    attr(deny(warnings, rust_2018_idioms), allow(dead_code, unused_variables))
+))]
+
+//! Reading and writing of [era1](https://github.com/ethereum/go-ethereum/blob/master/internal/era/era.go)
+//! archives of pre-merge execution layer history.
+//!
+//! An era1 file holds up to 8192 consecutive blocks with their receipts and total difficulty,
+//! framed as e2store entries and committed to by an accumulator root.
+
+pub mod accumulator;
+pub mod e2s;
+mod era1;
+mod error;
+
+pub use era1::*;
+pub use error::Era1Error;
@@ -46,7 +46,10 @@ impl<DB: Database> ProviderFactory<DB> {
     /// [`BlockHashReader`].  This may fail if the inner read/write database transaction fails to
     /// open.
     pub fn provider_rw(&self) -> Result<DatabaseProviderRW<'_, DB>> {
-        Ok(DatabaseProviderRW(DatabaseProvider::new_rw(self.db.tx_mut()?, self.chain_spec.clone())))
+        Ok(DatabaseProviderRW(
+            DatabaseProvider::new_rw(self.db.tx_mut()?, self.chain_spec.clone()),
+            &self.db,
+        ))
     }
 }
 
@@ -83,7 +86,11 @@ impl<DB: Database> ProviderFactory<DB> {
     /// Storage provider for latest block
     pub fn latest(&self) -> Result<StateProviderBox<'_>> {
         trace!(target: "providers::db", "Returning latest state provider");
-        Ok(Box::new(LatestStateProvider::new(self.db.tx()?)))
+        let provider = self.provider()?;
+        let state_synced = provider.state_sync_pivot()?.is_some();
+        Ok(Box::new(
+            LatestStateProvider::new(provider.into_tx()).with_hashed_state_fallback(state_synced),
+        ))
     }
 
     /// Storage provider for state at that given block
@@ -92,11 +99,15 @@ impl<DB: Database> ProviderFactory<DB> {
         mut block_number: BlockNumber,
     ) -> Result<StateProviderBox<'_>> {
         let provider = self.provider()?;
+        let state_sync_pivot = provider.state_sync_pivot()?;
 
         if block_number == provider.best_block_number().unwrap_or_default() &&
             block_number == provider.last_block_number().unwrap_or_default()
         {
-            return Ok(Box::new(LatestStateProvider::new(provider.into_tx())))
+            return Ok(Box::new(
+                LatestStateProvider::new(provider.into_tx())
+                    .with_hashed_state_fallback(state_sync_pivot.is_some()),
+            ))
         }
 
         // +1 as the changeset that we want is the one that was applied after this block.
@@ -107,23 +118,26 @@ impl<DB: Database> ProviderFactory<DB> {
         let storage_history_prune_checkpoint =
             provider.get_prune_checkpoint(PrunePart::StorageHistory)?;
 
-        let mut state_provider = HistoricalStateProvider::new(provider.into_tx(), block_number);
+        let mut state_provider = HistoricalStateProvider::new(provider.into_tx(), block_number)
+            .with_hashed_state_fallback(state_sync_pivot.is_some());
 
         // If we pruned account or storage history, we can't return state on every historical block.
         // Instead, we should cap it at the latest prune checkpoint for corresponding prune part.
-        if let Some(prune_checkpoint_block_number) =
-            account_history_prune_checkpoint.and_then(|checkpoint| checkpoint.block_number)
+        // The same applies to the history before the pivot block of the state sync, which was
+        // never written.
+        if let Some(lowest_block_number) = account_history_prune_checkpoint
+            .and_then(|checkpoint| checkpoint.block_number)
+            .max(state_sync_pivot)
         {
-            state_provider = state_provider.with_lowest_available_account_history_block_number(
-                prune_checkpoint_block_number + 1,
-            );
+            state_provider = state_provider
+                .with_lowest_available_account_history_block_number(lowest_block_number + 1);
         }
-        if let Some(prune_checkpoint_block_number) =
-            storage_history_prune_checkpoint.and_then(|checkpoint| checkpoint.block_number)
+        if let Some(lowest_block_number) = storage_history_prune_checkpoint
+            .and_then(|checkpoint| checkpoint.block_number)
+            .max(state_sync_pivot)
         {
-            state_provider = state_provider.with_lowest_available_storage_history_block_number(
-                prune_checkpoint_block_number + 1,
-            );
+            state_provider = state_provider
+                .with_lowest_available_storage_history_block_number(lowest_block_number + 1);
         }
 
         Ok(Box::new(state_provider))
@@ -59,6 +59,8 @@ pub type DatabaseProviderRO<'this, DB> = DatabaseProvider<'this, <DB as Database
 #[derive(Debug)]
 pub struct DatabaseProviderRW<'this, DB: Database>(
     pub DatabaseProvider<'this, <DB as DatabaseGAT<'this>>::TXMut>,
+    /// The database the transaction was opened on.
+    pub(crate) &'this DB,
 );
 
 impl<'this, DB: Database> Deref for DatabaseProviderRW<'this, DB> {
@@ -85,6 +87,13 @@ impl<'this, DB: Database> DatabaseProviderRW<'this, DB> {
     pub fn into_tx(self) -> <DB as DatabaseGAT<'this>>::TXMut {
         self.0.into_tx()
     }
+
+    /// Returns the database the transaction was opened on.
+    ///
+    /// Transactions opened on the database don't see the uncommitted changes of this provider.
+    pub fn db(&self) -> &'this DB {
+        self.1
+    }
 }
 
 /// A provider struct that fetchs data from the database.
@@ -156,6 +165,26 @@ where
     Ok(Vec::new())
 }
 
+/// Groups the receipts by the blocks they belong to.
+fn receipts_by_block(
+    block_bodies: &[(BlockNumber, StoredBlockBodyIndices)],
+    receipts: Vec<(TxNumber, Receipt)>,
+) -> Vec<Vec<Option<Receipt>>> {
+    let mut receipt_iter = receipts.into_iter();
+    block_bodies
+        .iter()
+        .map(|(_, block_body)| {
+            let mut block_receipts = Vec::with_capacity(block_body.tx_count as usize);
+            for _ in block_body.tx_num_range() {
+                if let Some((_, receipt)) = receipt_iter.next() {
+                    block_receipts.push(Some(receipt));
+                }
+            }
+            block_receipts
+        })
+        .collect()
+}
+
 impl<'this, TX: DbTx<'this>> DatabaseProvider<'this, TX> {
     /// Creates a provider with an inner read-only transaction.
     pub fn new(tx: TX, chain_spec: Arc<ChainSpec>) -> Self {
@@ -187,64 +216,63 @@ impl<'this, TX: DbTx<'this>> DatabaseProvider<'this, TX> {
             .walk(Some(T::Key::default()))?
             .collect::<std::result::Result<Vec<_>, DatabaseError>>()
     }
-}
-
-impl<'this, TX: DbTxMut<'this> + DbTx<'this>> DatabaseProvider<'this, TX> {
-    /// Commit database transaction.
-    pub fn commit(self) -> Result<bool> {
-        Ok(self.tx.commit()?)
-    }
 
-    // TODO(joshie) TEMPORARY should be moved to trait providers
-
-    /// Unwind or peek at last N blocks of state recreating the [`BundleStateWithReceipts`].
+    /// Reads the execution outcome of the blocks in the range from the changesets, the plain state
+    /// and the receipts, the same way the state of unwound blocks is recreated.
     ///
-    /// If UNWIND it set to true tip and latest state will be unwind
-    /// and returned back with all the blocks
-    ///
-    /// If UNWIND is false we will just read the state/blocks and return them.
-    ///
-    /// 1. Iterate over the [BlockBodyIndices][tables::BlockBodyIndices] table to get all
-    /// the transaction ids.
-    /// 2. Iterate over the [StorageChangeSet][tables::StorageChangeSet] table
-    /// and the [AccountChangeSet][tables::AccountChangeSet] tables in reverse order to reconstruct
-    /// the changesets.
-    ///     - In order to have both the old and new values in the changesets, we also access the
-    ///       plain state tables.
-    /// 3. While iterating over the changeset tables, if we encounter a new account or storage slot,
-    /// we:
-    ///     1. Take the old value from the changeset
-    ///     2. Take the new value from the plain state
-    ///     3. Save the old value to the local state
-    /// 4. While iterating over the changeset tables, if we encounter an account/storage slot we
-    /// have seen before we:
-    ///     1. Take the old value from the changeset
-    ///     2. Take the new value from the local state
-    ///     3. Set the local state to the value in the changeset
-    fn unwind_or_peek_state<const UNWIND: bool>(
+    /// The plain state is used as the state after the last block of the range, so the range has to
+    /// end at the latest executed block.
+    pub fn block_execution_state(
         &self,
         range: RangeInclusive<BlockNumber>,
     ) -> Result<BundleStateWithReceipts> {
-        if range.is_empty() {
+        let block_bodies = self
+            .tx
+            .cursor_read::<tables::BlockBodyIndices>()?
+            .walk_range(range.clone())?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let (Some((first_block, first_body)), Some((_, last_body))) =
+            (block_bodies.first(), block_bodies.last())
+        else {
             return Ok(BundleStateWithReceipts::default())
-        }
-        let start_block_number = *range.start();
-
-        // We are not removing block meta as it is used to get block changesets.
-        let block_bodies = self.get_or_take::<tables::BlockBodyIndices, false>(range.clone())?;
+        };
+        let first_block = *first_block;
+        let tx_range = first_body.first_tx_num()..=last_body.last_tx_num();
 
-        // get transaction receipts
-        let from_transaction_num =
-            block_bodies.first().expect("already checked if there are blocks").1.first_tx_num();
-        let to_transaction_num =
-            block_bodies.last().expect("already checked if there are blocks").1.last_tx_num();
+        let storage_changeset = self
+            .tx
+            .cursor_read::<tables::StorageChangeSet>()?
+            .walk_range(BlockNumberAddress::range(range.clone()))?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let account_changeset = self
+            .tx
+            .cursor_read::<tables::AccountChangeSet>()?
+            .walk_range(range)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let (state, reverts) = self.bundle_state_init(account_changeset, storage_changeset)?;
 
-        let storage_range = BlockNumberAddress::range(range.clone());
+        let receipts = self
+            .tx
+            .cursor_read::<tables::Receipts>()?
+            .walk_range(tx_range)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
 
-        let storage_changeset =
-            self.get_or_take::<tables::StorageChangeSet, UNWIND>(storage_range)?;
-        let account_changeset = self.get_or_take::<tables::AccountChangeSet, UNWIND>(range)?;
+        Ok(BundleStateWithReceipts::new_init(
+            state,
+            reverts,
+            Vec::new(),
+            receipts_by_block(&block_bodies, receipts),
+            first_block,
+        ))
+    }
 
+    /// Creates the bundle state and the reverts of the changesets, with the changed accounts and
+    /// storage slots set to their values in the plain state.
+    fn bundle_state_init(
+        &self,
+        account_changeset: Vec<(BlockNumber, AccountBeforeTx)>,
+        storage_changeset: Vec<(BlockNumberAddress, StorageEntry)>,
+    ) -> Result<(BundleStateInit, RevertsInit)> {
         // iterate previous value and get plain state value to create changeset
         // Double option around Account represent if Account state is know (first option) and
         // account is removed (Second Option)
@@ -255,8 +283,8 @@ impl<'this, TX: DbTxMut<'this> + DbTx<'this>> DatabaseProvider<'this, TX> {
         // state of end range. We should rename the functions or add support to access
         // History state. Accessing history state can be tricky but we are not gaining
         // anything.
-        let mut plain_accounts_cursor = self.tx.cursor_write::<tables::PlainAccountState>()?;
-        let mut plain_storage_cursor = self.tx.cursor_dup_write::<tables::PlainStorageState>()?;
+        let mut plain_accounts_cursor = self.tx.cursor_read::<tables::PlainAccountState>()?;
+        let mut plain_storage_cursor = self.tx.cursor_dup_read::<tables::PlainStorageState>()?;
 
         let mut reverts: RevertsInit = HashMap::new();
 
@@ -312,7 +340,100 @@ impl<'this, TX: DbTxMut<'this> + DbTx<'this>> DatabaseProvider<'this, TX> {
                 .push(old_storage);
         }
 
+        Ok((state, reverts))
+    }
+
+    /// Returns [ProviderError::BlockBodyPruned] if the body of the block was removed by
+    /// [PrunePart::BlockBodies] pruning.
+    ///
+    /// Should only be called once the body indices of the block were found to be missing, so the
+    /// prune checkpoint lookup stays out of the hot path.
+    fn ensure_block_body_not_pruned(&self, block_number: BlockNumber) -> Result<()> {
+        let pruned_block = self
+            .get_prune_checkpoint(PrunePart::BlockBodies)?
+            .and_then(|checkpoint| checkpoint.block_number);
+        if pruned_block.map_or(false, |pruned_block| block_number <= pruned_block) {
+            return Err(ProviderError::BlockBodyPruned(block_number).into())
+        }
+        Ok(())
+    }
+
+    /// Returns [ProviderError::BlockBodyPruned] if the transaction was removed by
+    /// [PrunePart::BlockBodies] pruning.
+    ///
+    /// The transaction hash and block indices are kept, so this should only be called once the
+    /// transaction was found to be missing.
+    fn ensure_transaction_not_pruned(&self, id: TxNumber) -> Result<()> {
+        if let Some(block_number) = self.transaction_block(id)? {
+            self.ensure_block_body_not_pruned(block_number)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'this, TX: DbTxMut<'this> + DbTx<'this>> DatabaseProvider<'this, TX> {
+    /// Commit database transaction.
+    pub fn commit(self) -> Result<bool> {
+        Ok(self.tx.commit()?)
+    }
+
+    // TODO(joshie) TEMPORARY should be moved to trait providers
+
+    /// Unwind or peek at last N blocks of state recreating the [`BundleStateWithReceipts`].
+    ///
+    /// If UNWIND it set to true tip and latest state will be unwind
+    /// and returned back with all the blocks
+    ///
+    /// If UNWIND is false we will just read the state/blocks and return them.
+    ///
+    /// 1. Iterate over the [BlockBodyIndices][tables::BlockBodyIndices] table to get all
+    /// the transaction ids.
+    /// 2. Iterate over the [StorageChangeSet][tables::StorageChangeSet] table
+    /// and the [AccountChangeSet][tables::AccountChangeSet] tables in reverse order to reconstruct
+    /// the changesets.
+    ///     - In order to have both the old and new values in the changesets, we also access the
+    ///       plain state tables.
+    /// 3. While iterating over the changeset tables, if we encounter a new account or storage slot,
+    /// we:
+    ///     1. Take the old value from the changeset
+    ///     2. Take the new value from the plain state
+    ///     3. Save the old value to the local state
+    /// 4. While iterating over the changeset tables, if we encounter an account/storage slot we
+    /// have seen before we:
+    ///     1. Take the old value from the changeset
+    ///     2. Take the new value from the local state
+    ///     3. Set the local state to the value in the changeset
+    fn unwind_or_peek_state<const UNWIND: bool>(
+        &self,
+        range: RangeInclusive<BlockNumber>,
+    ) -> Result<BundleStateWithReceipts> {
+        if range.is_empty() {
+            return Ok(BundleStateWithReceipts::default())
+        }
+        let start_block_number = *range.start();
+
+        // We are not removing block meta as it is used to get block changesets.
+        let block_bodies = self.get_or_take::<tables::BlockBodyIndices, false>(range.clone())?;
+
+        // get transaction receipts
+        let from_transaction_num =
+            block_bodies.first().expect("already checked if there are blocks").1.first_tx_num();
+        let to_transaction_num =
+            block_bodies.last().expect("already checked if there are blocks").1.last_tx_num();
+
+        let storage_range = BlockNumberAddress::range(range.clone());
+
+        let storage_changeset =
+            self.get_or_take::<tables::StorageChangeSet, UNWIND>(storage_range)?;
+        let account_changeset = self.get_or_take::<tables::AccountChangeSet, UNWIND>(range)?;
+
+        let (state, reverts) = self.bundle_state_init(account_changeset, storage_changeset)?;
+
         if UNWIND {
+            let mut plain_accounts_cursor = self.tx.cursor_write::<tables::PlainAccountState>()?;
+            let mut plain_storage_cursor =
+                self.tx.cursor_dup_write::<tables::PlainStorageState>()?;
+
             // iterate over local plain state remove all account and all storages.
             for (address, (old_account, new_account, storage)) in state.iter() {
                 // revert account if needed.
@@ -348,27 +469,14 @@ impl<'this, TX: DbTxMut<'this> + DbTx<'this>> DatabaseProvider<'this, TX> {
         }
 
         // iterate over block body and create ExecutionResult
-        let mut receipt_iter = self
-            .get_or_take::<tables::Receipts, UNWIND>(from_transaction_num..=to_transaction_num)?
-            .into_iter();
-
-        let mut receipts = Vec::new();
-        // loop break if we are at the end of the blocks.
-        for (_, block_body) in block_bodies.into_iter() {
-            let mut block_receipts = Vec::with_capacity(block_body.tx_count as usize);
-            for _ in block_body.tx_num_range() {
-                if let Some((_, receipt)) = receipt_iter.next() {
-                    block_receipts.push(Some(receipt));
-                }
-            }
-            receipts.push(block_receipts);
-        }
+        let receipts = self
+            .get_or_take::<tables::Receipts, UNWIND>(from_transaction_num..=to_transaction_num)?;
 
         Ok(BundleStateWithReceipts::new_init(
             state,
             reverts,
             Vec::new(),
-            receipts,
+            receipts_by_block(&block_bodies, receipts),
             start_block_number,
         ))
     }
@@ -978,6 +1086,8 @@ impl<'this, TX: DbTx<'this>> BlockReader for DatabaseProvider<'this, TX> {
     /// If the header for this block is not found, this returns `None`.
     /// If the header is found, but the transactions either do not exist, or are not indexed, this
     /// will return None.
+    /// If the body of the block was removed by history expiry, this returns
+    /// [ProviderError::BlockBodyPruned].
     fn block(&self, id: BlockHashOrNumber) -> Result<Option<Block>> {
         if let Some(number) = self.convert_hash_or_number(id)? {
             if let Some(header) = self.header_by_number(number)? {
@@ -1050,7 +1160,10 @@ impl<'this, TX: DbTx<'this>> BlockReader for DatabaseProvider<'this, TX> {
         // `None`.
         let body = match self.block_body_indices(block_number)? {
             Some(body) => body,
-            None => return Ok(None),
+            None => {
+                self.ensure_block_body_not_pruned(block_number)?;
+                return Ok(None)
+            }
         };
 
         let tx_range = body.tx_num_range();
@@ -1093,7 +1206,11 @@ impl<'this, TX: DbTx<'this>> TransactionsProvider for DatabaseProvider<'this, TX
 
     fn transaction_by_hash(&self, hash: TxHash) -> Result<Option<TransactionSigned>> {
         if let Some(id) = self.transaction_id(hash)? {
-            Ok(self.transaction_by_id_no_hash(id)?.map(|tx| TransactionSigned {
+            let Some(tx) = self.transaction_by_id_no_hash(id)? else {
+                self.ensure_transaction_not_pruned(id)?;
+                return Ok(None)
+            };
+            Ok(Some(TransactionSigned {
                 hash,
                 signature: tx.signature,
                 transaction: tx.transaction,
@@ -1110,7 +1227,11 @@ impl<'this, TX: DbTx<'this>> TransactionsProvider for DatabaseProvider<'this, TX
     ) -> Result<Option<(TransactionSigned, TransactionMeta)>> {
         let mut transaction_cursor = self.tx.cursor_read::<tables::TransactionBlock>()?;
         if let Some(transaction_id) = self.transaction_id(tx_hash)? {
-            if let Some(tx) = self.transaction_by_id_no_hash(transaction_id)? {
+            let tx = self.transaction_by_id_no_hash(transaction_id)?;
+            if tx.is_none() {
+                self.ensure_transaction_not_pruned(transaction_id)?;
+            }
+            if let Some(tx) = tx {
                 let transaction = TransactionSigned {
                     hash: tx_hash,
                     signature: tx.signature,
@@ -1170,6 +1291,7 @@ impl<'this, TX: DbTx<'this>> TransactionsProvider for DatabaseProvider<'this, TX
                     Ok(Some(transactions))
                 }
             }
+            self.ensure_block_body_not_pruned(block_number)?;
         }
         Ok(None)
     }
@@ -1252,6 +1374,7 @@ impl<'this, TX: DbTx<'this>> ReceiptProvider for DatabaseProvider<'this, TX> {
                     Ok(Some(receipts))
                 }
             }
+            self.ensure_block_body_not_pruned(number)?;
         }
         Ok(None)
     }
@@ -1,6 +1,7 @@
 use crate::{
-    providers::state::macros::delegate_provider_impls, AccountReader, BlockHashReader,
-    BundleStateWithReceipts, ProviderError, StateProvider, StateRootProvider,
+    providers::state::{hashed_account, hashed_storage, macros::delegate_provider_impls},
+    AccountReader, BlockHashReader, BundleStateWithReceipts, ProviderError, StateProvider,
+    StateRootProvider,
 };
 use reth_db::{
     cursor::{DbCursorRO, DbDupCursorRO},
@@ -34,6 +35,9 @@ pub struct HistoricalStateProviderRef<'a, 'b, TX: DbTx<'a>> {
     block_number: BlockNumber,
     /// Lowest blocks at which different parts of the state are available.
     lowest_available_blocks: LowestAvailableBlocks,
+    /// Whether to read accounts and storage slots missing in the plain state from the hashed
+    /// state.
+    hashed_state_fallback: bool,
     /// Phantom lifetime `'a`
     _phantom: PhantomData<&'a TX>,
 }
@@ -53,6 +57,7 @@ impl<'a, 'b, TX: DbTx<'a>> HistoricalStateProviderRef<'a, 'b, TX> {
             tx,
             block_number,
             lowest_available_blocks: Default::default(),
+            hashed_state_fallback: false,
             _phantom: PhantomData {},
         }
     }
@@ -64,7 +69,21 @@ impl<'a, 'b, TX: DbTx<'a>> HistoricalStateProviderRef<'a, 'b, TX> {
         block_number: BlockNumber,
         lowest_available_blocks: LowestAvailableBlocks,
     ) -> Self {
-        Self { tx, block_number, lowest_available_blocks, _phantom: PhantomData {} }
+        Self {
+            tx,
+            block_number,
+            lowest_available_blocks,
+            hashed_state_fallback: false,
+            _phantom: PhantomData {},
+        }
+    }
+
+    /// Enables reading accounts and storage slots missing in the plain state from the hashed
+    /// state, see
+    /// [LatestStateProviderRef::with_hashed_state_fallback](crate::LatestStateProviderRef::with_hashed_state_fallback).
+    pub fn with_hashed_state_fallback(mut self, enabled: bool) -> Self {
+        self.hashed_state_fallback = enabled;
+        self
     }
 
     /// Lookup an account in the AccountHistory table
@@ -174,7 +193,10 @@ impl<'a, 'b, TX: DbTx<'a>> AccountReader for HistoricalStateProviderRef<'a, 'b,
                 })?
                 .info),
             HistoryInfo::InPlainState | HistoryInfo::MaybeInPlainState => {
-                Ok(self.tx.get::<tables::PlainAccountState>(address)?)
+                match self.tx.get::<tables::PlainAccountState>(address)? {
+                    None if self.hashed_state_fallback => hashed_account(self.tx, address),
+                    account => Ok(account),
+                }
             }
         }
     }
@@ -223,13 +245,21 @@ impl<'a, 'b, TX: DbTx<'a>> StateProvider for HistoricalStateProviderRef<'a, 'b,
                     })?
                     .value,
             )),
-            HistoryInfo::InPlainState | HistoryInfo::MaybeInPlainState => Ok(self
-                .tx
-                .cursor_dup_read::<tables::PlainStorageState>()?
-                .seek_by_key_subkey(address, storage_key)?
-                .filter(|entry| entry.key == storage_key)
-                .map(|entry| entry.value)
-                .or(Some(StorageValue::ZERO))),
+            HistoryInfo::InPlainState | HistoryInfo::MaybeInPlainState => {
+                let value = self
+                    .tx
+                    .cursor_dup_read::<tables::PlainStorageState>()?
+                    .seek_by_key_subkey(address, storage_key)?
+                    .filter(|entry| entry.key == storage_key)
+                    .map(|entry| entry.value);
+                match value {
+                    None if self.hashed_state_fallback => {
+                        Ok(hashed_storage(self.tx, address, storage_key)?
+                            .or(Some(StorageValue::ZERO)))
+                    }
+                    value => Ok(value.or(Some(StorageValue::ZERO))),
+                }
+            }
         }
     }
 
@@ -257,6 +287,9 @@ pub struct HistoricalStateProvider<'a, TX: DbTx<'a>> {
     block_number: BlockNumber,
     /// Lowest blocks at which different parts of the state are available.
     lowest_available_blocks: LowestAvailableBlocks,
+    /// Whether to read accounts and storage slots missing in the plain state from the hashed
+    /// state.
+    hashed_state_fallback: bool,
     /// Phantom lifetime `'a`
     _phantom: PhantomData<&'a TX>,
 }
@@ -268,10 +301,19 @@ impl<'a, TX: DbTx<'a>> HistoricalStateProvider<'a, TX> {
             tx,
             block_number,
             lowest_available_blocks: Default::default(),
+            hashed_state_fallback: false,
             _phantom: PhantomData {},
         }
     }
 
+    /// Enables reading accounts and storage slots missing in the plain state from the hashed
+    /// state, see
+    /// [LatestStateProviderRef::with_hashed_state_fallback](crate::LatestStateProviderRef::with_hashed_state_fallback).
+    pub fn with_hashed_state_fallback(mut self, enabled: bool) -> Self {
+        self.hashed_state_fallback = enabled;
+        self
+    }
+
     /// Set the lowest block number at which the account history is available.
     pub fn with_lowest_available_account_history_block_number(
         mut self,
@@ -298,6 +340,7 @@ impl<'a, TX: DbTx<'a>> HistoricalStateProvider<'a, TX> {
             self.block_number,
             self.lowest_available_blocks,
         )
+        .with_hashed_state_fallback(self.hashed_state_fallback)
     }
 }
 
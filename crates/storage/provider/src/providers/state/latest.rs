@@ -1,6 +1,6 @@
 use crate::{
-    providers::state::macros::delegate_provider_impls, AccountReader, BlockHashReader,
-    BundleStateWithReceipts, StateProvider, StateRootProvider,
+    providers::state::{hashed_account, hashed_storage, macros::delegate_provider_impls},
+    AccountReader, BlockHashReader, BundleStateWithReceipts, StateProvider, StateRootProvider,
 };
 use reth_db::{
     cursor::{DbCursorRO, DbDupCursorRO},
@@ -17,6 +17,9 @@ use std::marker::PhantomData;
 pub struct LatestStateProviderRef<'a, 'b, TX: DbTx<'a>> {
     /// database transaction
     db: &'b TX,
+    /// Whether to read accounts and storage slots missing in the plain state from the hashed
+    /// state.
+    hashed_state_fallback: bool,
     /// Phantom data over lifetime
     phantom: PhantomData<&'a TX>,
 }
@@ -24,14 +27,28 @@ pub struct LatestStateProviderRef<'a, 'b, TX: DbTx<'a>> {
 impl<'a, 'b, TX: DbTx<'a>> LatestStateProviderRef<'a, 'b, TX> {
     /// Create new state provider
     pub fn new(db: &'b TX) -> Self {
-        Self { db, phantom: PhantomData {} }
+        Self { db, hashed_state_fallback: false, phantom: PhantomData {} }
+    }
+
+    /// Enables reading accounts and storage slots missing in the plain state from the hashed
+    /// state.
+    ///
+    /// This is required if the state was downloaded by hashed keys, see
+    /// [StageCheckpointReader::state_sync_pivot](crate::StageCheckpointReader). The hashed state
+    /// must not lag behind the plain state, or deleted entries would be read from it.
+    pub fn with_hashed_state_fallback(mut self, enabled: bool) -> Self {
+        self.hashed_state_fallback = enabled;
+        self
     }
 }
 
 impl<'a, 'b, TX: DbTx<'a>> AccountReader for LatestStateProviderRef<'a, 'b, TX> {
     /// Get basic account information.
     fn basic_account(&self, address: Address) -> Result<Option<Account>> {
-        self.db.get::<tables::PlainAccountState>(address).map_err(Into::into)
+        match self.db.get::<tables::PlainAccountState>(address)? {
+            None if self.hashed_state_fallback => hashed_account(self.db, address),
+            account => Ok(account),
+        }
     }
 }
 
@@ -72,6 +89,9 @@ impl<'a, 'b, TX: DbTx<'a>> StateProvider for LatestStateProviderRef<'a, 'b, TX>
                 return Ok(Some(entry.value))
             }
         }
+        if self.hashed_state_fallback {
+            return hashed_storage(self.db, account, storage_key)
+        }
         Ok(None)
     }
 
@@ -102,6 +122,9 @@ impl<'a, 'b, TX: DbTx<'a>> StateProvider for LatestStateProviderRef<'a, 'b, TX>
 pub struct LatestStateProvider<'a, TX: DbTx<'a>> {
     /// database transaction
     db: TX,
+    /// Whether to read accounts and storage slots missing in the plain state from the hashed
+    /// state.
+    hashed_state_fallback: bool,
     /// Phantom lifetime `'a`
     _phantom: PhantomData<&'a TX>,
 }
@@ -109,13 +132,20 @@ pub struct LatestStateProvider<'a, TX: DbTx<'a>> {
 impl<'a, TX: DbTx<'a>> LatestStateProvider<'a, TX> {
     /// Create new state provider
     pub fn new(db: TX) -> Self {
-        Self { db, _phantom: PhantomData {} }
+        Self { db, hashed_state_fallback: false, _phantom: PhantomData {} }
+    }
+
+    /// Enables reading accounts and storage slots missing in the plain state from the hashed
+    /// state, see [LatestStateProviderRef::with_hashed_state_fallback].
+    pub fn with_hashed_state_fallback(mut self, enabled: bool) -> Self {
+        self.hashed_state_fallback = enabled;
+        self
     }
 
     /// Returns a new provider that takes the `TX` as reference
     #[inline(always)]
     fn as_ref<'b>(&'b self) -> LatestStateProviderRef<'a, 'b, TX> {
-        LatestStateProviderRef::new(&self.db)
+        LatestStateProviderRef::new(&self.db).with_hashed_state_fallback(self.hashed_state_fallback)
     }
 }
 
@@ -125,10 +155,35 @@ delegate_provider_impls!(LatestStateProvider<'a, TX> where [TX: DbTx<'a>]);
 #[cfg(test)]
 mod tests {
     use super::*;
+    use reth_db::{database::Database, test_utils::create_test_rw_db, transaction::DbTxMut};
+    use reth_primitives::{StorageEntry, U256};
 
     fn assert_state_provider<T: StateProvider>() {}
     #[allow(unused)]
     fn assert_latest_state_provider<'txn, T: DbTx<'txn> + 'txn>() {
         assert_state_provider::<LatestStateProvider<'txn, T>>();
     }
+
+    #[test]
+    fn hashed_state_fallback() {
+        let db = create_test_rw_db();
+        let address = Address::random();
+        let account = Account { nonce: 1, ..Default::default() };
+        let slot = H256::random();
+        db.update(|tx| {
+            tx.put::<tables::HashedAccount>(keccak256(address), account).unwrap();
+            let entry = StorageEntry { key: keccak256(slot), value: U256::from(1) };
+            tx.put::<tables::HashedStorage>(keccak256(address), entry).unwrap();
+        })
+        .unwrap();
+
+        let tx = db.tx().unwrap();
+        let provider = LatestStateProviderRef::new(&tx);
+        assert_eq!(provider.basic_account(address).unwrap(), None);
+        assert_eq!(provider.storage(address, slot).unwrap(), None);
+
+        let provider = provider.with_hashed_state_fallback(true);
+        assert_eq!(provider.basic_account(address).unwrap(), Some(account));
+        assert_eq!(provider.storage(address, slot).unwrap(), Some(U256::from(1)));
+    }
 }
@@ -2,3 +2,32 @@
 pub(crate) mod historical;
 pub(crate) mod latest;
 pub(crate) mod macros;
+
+use reth_db::{cursor::DbDupCursorRO, tables, transaction::DbTx};
+use reth_interfaces::Result;
+use reth_primitives::{keccak256, Account, Address, StorageKey, StorageValue};
+
+/// Reads the account from the hashed state.
+///
+/// Used for accounts missing in the plain state of a database whose state was downloaded by
+/// hashed keys, see [StageCheckpointReader::state_sync_pivot](crate::StageCheckpointReader).
+pub(crate) fn hashed_account<'a, TX: DbTx<'a>>(
+    tx: &TX,
+    address: Address,
+) -> Result<Option<Account>> {
+    Ok(tx.get::<tables::HashedAccount>(keccak256(address))?)
+}
+
+/// Reads the storage slot from the hashed state, see [hashed_account].
+pub(crate) fn hashed_storage<'a, TX: DbTx<'a>>(
+    tx: &TX,
+    address: Address,
+    storage_key: StorageKey,
+) -> Result<Option<StorageValue>> {
+    let hashed_slot = keccak256(storage_key);
+    Ok(tx
+        .cursor_dup_read::<tables::HashedStorage>()?
+        .seek_by_key_subkey(keccak256(address), hashed_slot)?
+        .filter(|entry| entry.key == hashed_slot)
+        .map(|entry| entry.value))
+}
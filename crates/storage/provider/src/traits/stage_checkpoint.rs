@@ -12,6 +12,20 @@ pub trait StageCheckpointReader: Send + Sync {
 
     /// Get stage checkpoint progress.
     fn get_stage_checkpoint_progress(&self, id: StageId) -> Result<Option<Vec<u8>>>;
+
+    /// Returns the block whose state was downloaded by the [StageId::STATE_SYNC] stage, if the
+    /// download completed.
+    ///
+    /// The state is downloaded by hashed keys, so the plain state of such a database only contains
+    /// the accounts and storage slots that changed after the pivot. There is no history of the
+    /// state before the pivot.
+    fn state_sync_pivot(&self) -> Result<Option<BlockNumber>> {
+        Ok(self
+            .get_stage_checkpoint(StageId::STATE_SYNC)?
+            .and_then(|checkpoint| checkpoint.state_sync_stage_checkpoint())
+            .filter(|checkpoint| checkpoint.next_account.is_none())
+            .map(|checkpoint| checkpoint.pivot))
+    }
 }
 
 /// The trait for updating stage checkpoint related data.
@@ -32,11 +32,6 @@ pub enum StorageRootError {
 /// Proof error.
 #[derive(Error, PartialEq, Eq, Clone, Debug)]
 pub enum ProofError {
-    /// Leaf account missing
-    #[error(
-        "Expected leaf account with key greater or equal to {0:?} is missing from the database"
-    )]
-    LeafAccountMissing(H256),
     /// Storage root error.
     #[error(transparent)]
     StorageRootError(#[from] StorageRootError),
@@ -44,3 +39,34 @@ pub enum ProofError {
     #[error(transparent)]
     DB(#[from] reth_db::DatabaseError),
 }
+
+/// Range proof verification error.
+#[derive(Error, PartialEq, Eq, Clone, Debug)]
+pub enum RangeProofError {
+    /// The leaves are not ordered by their keys.
+    #[error("Leaf {0:?} is not ordered after the previous leaf")]
+    UnorderedLeaves(H256),
+    /// A leaf is before the start of the range.
+    #[error("Leaf {0:?} is before the start of the range")]
+    LeafBeforeOrigin(H256),
+    /// A leaf has an empty value, which the trie doesn't store.
+    #[error("Leaf with an empty value")]
+    EmptyValue,
+    /// A trie node required to verify the range is missing from the proof.
+    #[error("Trie node {0:?} is missing from the proof")]
+    MissingNode(H256),
+    /// A trie node of the proof is malformed.
+    #[error("Invalid trie node in the proof")]
+    InvalidNode,
+    /// A trie node could not be decoded.
+    #[error(transparent)]
+    Rlp(#[from] reth_rlp::DecodeError),
+    /// The root of the trie doesn't match the root computed from the range and the proof.
+    #[error("Range proof root mismatch: expected {expected:?}, got {got:?}")]
+    RootMismatch {
+        /// The expected root.
+        expected: H256,
+        /// The root computed from the range and the proof.
+        got: H256,
+    },
+}
@@ -1,9 +1,10 @@
 use super::{HashedAccountCursor, HashedCursorFactory, HashedStorageCursor};
 use crate::prefix_set::{PrefixSet, PrefixSetMut};
 use reth_db::{
-    cursor::{DbCursorRO, DbDupCursorRO},
+    cursor::{DbCursorRO, DbCursorRW, DbDupCursorRO, DbDupCursorRW},
     tables,
-    transaction::{DbTx, DbTxGAT},
+    transaction::{DbTx, DbTxGAT, DbTxMut},
+    DatabaseError,
 };
 use reth_primitives::{trie::Nibbles, Account, StorageEntry, H256, U256};
 use std::collections::{HashMap, HashSet};
@@ -147,6 +148,57 @@ impl HashedPostState {
             storage_prefix_set.into_iter().map(|(k, v)| (k, v.freeze())).collect(),
         )
     }
+
+    /// Write the hashed post state to the [tables::HashedAccount] and [tables::HashedStorage]
+    /// tables.
+    ///
+    /// Cleared accounts are removed, and the storage of wiped accounts is removed before the new
+    /// storage entries are written.
+    pub fn write_to_db<'a, 'tx, TX>(&self, tx: &'a TX) -> Result<(), DatabaseError>
+    where
+        TX: DbTx<'tx> + DbTxMut<'tx>,
+    {
+        let mut hashed_accounts_cursor = tx.cursor_write::<tables::HashedAccount>()?;
+        for hashed_address in &self.cleared_accounts {
+            if hashed_accounts_cursor.seek_exact(*hashed_address)?.is_some() {
+                hashed_accounts_cursor.delete_current()?;
+            }
+        }
+        for (hashed_address, account) in &self.accounts {
+            hashed_accounts_cursor.upsert(*hashed_address, *account)?;
+        }
+
+        let mut hashed_storage_cursor = tx.cursor_dup_write::<tables::HashedStorage>()?;
+        for (hashed_address, storage) in &self.storages {
+            if storage.wiped && hashed_storage_cursor.seek_exact(*hashed_address)?.is_some() {
+                hashed_storage_cursor.delete_current_duplicates()?;
+            }
+
+            for hashed_slot in &storage.zero_valued_slots {
+                if hashed_storage_cursor
+                    .seek_by_key_subkey(*hashed_address, *hashed_slot)?
+                    .filter(|entry| entry.key == *hashed_slot)
+                    .is_some()
+                {
+                    hashed_storage_cursor.delete_current()?;
+                }
+            }
+
+            for (hashed_slot, value) in &storage.non_zero_valued_storage {
+                if hashed_storage_cursor
+                    .seek_by_key_subkey(*hashed_address, *hashed_slot)?
+                    .filter(|entry| entry.key == *hashed_slot)
+                    .is_some()
+                {
+                    hashed_storage_cursor.delete_current()?;
+                }
+                hashed_storage_cursor
+                    .upsert(*hashed_address, StorageEntry { key: *hashed_slot, value: *value })?;
+            }
+        }
+
+        Ok(())
+    }
 }
 
 /// The hashed cursor factory for the post state.
@@ -167,8 +219,14 @@ impl<'a, 'b, 'tx, TX: DbTx<'tx>> HashedCursorFactory<'a>
 where
     'a: 'b,
 {
-    type AccountCursor = HashedPostStateAccountCursor<'b, <TX as DbTxGAT<'a>>::Cursor<tables::HashedAccount>> where Self: 'a;
-    type StorageCursor = HashedPostStateStorageCursor<'b, <TX as DbTxGAT<'a>>::DupCursor<tables::HashedStorage>> where Self: 'a;
+    type AccountCursor
+        = HashedPostStateAccountCursor<'b, <TX as DbTxGAT<'a>>::Cursor<tables::HashedAccount>>
+    where
+        Self: 'a;
+    type StorageCursor
+        = HashedPostStateStorageCursor<'b, <TX as DbTxGAT<'a>>::DupCursor<tables::HashedStorage>>
+    where
+        Self: 'a;
 
     fn hashed_account_cursor(&'a self) -> Result<Self::AccountCursor, reth_db::DatabaseError> {
         let cursor = self.tx.cursor_read::<tables::HashedAccount>()?;
@@ -1026,4 +1084,67 @@ mod tests {
             assert_storage_cursor_order(&factory, expected.into_iter());
         });
     }
+
+    #[test]
+    fn write_post_state_to_db() {
+        let address_1 = H256::from_low_u64_be(1);
+        let address_2 = H256::from_low_u64_be(2);
+        let address_3 = H256::from_low_u64_be(3);
+        let account = Account { nonce: 1, ..Default::default() };
+
+        let db = create_test_rw_db();
+        db.update(|tx| {
+            for address in [address_1, address_2, address_3] {
+                tx.put::<tables::HashedAccount>(address, Account::default()).unwrap();
+                for slot in 1..4 {
+                    let entry =
+                        StorageEntry { key: H256::from_low_u64_be(slot), value: U256::from(slot) };
+                    tx.put::<tables::HashedStorage>(address, entry).unwrap();
+                }
+            }
+        })
+        .unwrap();
+
+        let mut hashed_post_state = HashedPostState::default();
+        // the first account is updated, and one slot is updated and another removed
+        hashed_post_state.insert_account(address_1, account);
+        let mut storage = HashedStorage::new(false);
+        storage.insert_non_zero_valued_storage(H256::from_low_u64_be(1), U256::from(10));
+        storage.insert_zero_valued_slot(H256::from_low_u64_be(2));
+        hashed_post_state.insert_hashed_storage(address_1, storage);
+        // the second account is destroyed
+        hashed_post_state.insert_cleared_account(address_2);
+        hashed_post_state.insert_hashed_storage(address_2, HashedStorage::new(true));
+        // the storage of the third account is wiped and rewritten
+        let mut storage = HashedStorage::new(true);
+        storage.insert_non_zero_valued_storage(H256::from_low_u64_be(4), U256::from(4));
+        hashed_post_state.insert_hashed_storage(address_3, storage);
+        hashed_post_state.sort();
+
+        db.update(|tx| hashed_post_state.write_to_db(tx)).unwrap().unwrap();
+
+        let tx = db.tx().unwrap();
+        let empty = HashedPostState::default();
+        let factory = HashedPostStateCursorFactory::new(&tx, &empty);
+        assert_account_cursor_order(
+            &factory,
+            [(address_1, account), (address_3, Account::default())].into_iter(),
+        );
+        assert_storage_cursor_order(
+            &factory,
+            [
+                (
+                    address_1,
+                    BTreeMap::from([
+                        (H256::from_low_u64_be(1), U256::from(10)),
+                        (H256::from_low_u64_be(3), U256::from(3)),
+                    ]),
+                ),
+                (address_3, BTreeMap::from([(H256::from_low_u64_be(4), U256::from(4))])),
+            ]
+            .into_iter(),
+        );
+        let mut storage_cursor = tx.cursor_dup_read::<tables::HashedStorage>().unwrap();
+        assert!(storage_cursor.seek_exact(address_2).unwrap().is_none());
+    }
 }
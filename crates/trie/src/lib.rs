@@ -41,10 +41,18 @@ pub use errors::*;
 /// Merkle proof generation.
 pub mod proof;
 
+/// Verification of range proofs.
+mod range_proof;
+pub use range_proof::{branch_node_children, verify_range_proof};
+
 /// The implementation of the Merkle Patricia Trie.
 mod trie;
 pub use trie::{StateRoot, StorageRoot};
 
+/// Parallel computation of storage roots.
+mod parallel;
+pub use parallel::{parallel_storage_roots, StorageRootOutput};
+
 /// Buffer for trie updates.
 pub mod updates;
 
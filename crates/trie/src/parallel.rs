@@ -0,0 +1,48 @@
+use crate::{prefix_set::PrefixSet, updates::TrieUpdates, StorageRoot, StorageRootError};
+use rayon::prelude::*;
+use reth_db::database::Database;
+use reth_primitives::H256;
+use std::collections::HashMap;
+
+/// The result of a storage root computation.
+#[derive(Debug, Clone)]
+pub struct StorageRootOutput {
+    /// The storage root of the account.
+    pub root: H256,
+    /// The number of storage slots walked to compute the root.
+    pub slots_walked: usize,
+    /// The storage trie updates. Empty if updates were not retained.
+    pub updates: TrieUpdates,
+}
+
+/// Computes the storage roots of the given accounts in parallel.
+///
+/// Each storage root is computed on the rayon thread pool with its own read-only transaction, so
+/// only the state committed to the database is visible to the computation.
+///
+/// # Returns
+///
+/// The storage root outputs keyed by hashed address.
+pub fn parallel_storage_roots<DB: Database>(
+    db: &DB,
+    targets: HashMap<H256, PrefixSet>,
+    retain_updates: bool,
+) -> Result<HashMap<H256, StorageRootOutput>, StorageRootError> {
+    tracing::debug!(target: "trie::parallel", len = targets.len(), "calculating storage roots in parallel");
+    targets
+        .into_par_iter()
+        .map(|(hashed_address, prefix_set)| -> Result<_, StorageRootError> {
+            let tx = db.tx()?;
+            let calculator =
+                StorageRoot::new_hashed(&tx, hashed_address).with_changed_prefixes(prefix_set);
+            let output = if retain_updates {
+                let (root, slots_walked, updates) = calculator.root_with_updates()?;
+                StorageRootOutput { root, slots_walked, updates }
+            } else {
+                let root = calculator.root()?;
+                StorageRootOutput { root, slots_walked: 0, updates: TrieUpdates::default() }
+            };
+            Ok((hashed_address, output))
+        })
+        .collect()
+}
@@ -1,5 +1,5 @@
 use reth_primitives::trie::Nibbles;
-use std::rc::Rc;
+use std::sync::Arc;
 
 mod loader;
 pub use loader::{LoadedPrefixSets, PrefixSetLoader};
@@ -93,7 +93,7 @@ impl PrefixSetMut {
             self.keys.dedup();
         }
 
-        PrefixSet { keys: Rc::new(self.keys), index: self.index }
+        PrefixSet { keys: Arc::new(self.keys), index: self.index }
     }
 }
 
@@ -102,7 +102,7 @@ impl PrefixSetMut {
 /// See also [PrefixSetMut::freeze].
 #[derive(Debug, Default, Clone)]
 pub struct PrefixSet {
-    keys: Rc<Vec<Nibbles>>,
+    keys: Arc<Vec<Nibbles>>,
     index: usize,
 }
 
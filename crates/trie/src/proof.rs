@@ -1,39 +1,33 @@
 use crate::{
     account::EthAccount,
-    hashed_cursor::{HashedAccountCursor, HashedCursorFactory},
-    prefix_set::PrefixSet,
-    trie_cursor::{AccountTrieCursor, TrieCursor},
+    hashed_cursor::{HashedAccountCursor, HashedCursorFactory, HashedStorageCursor},
+    prefix_set::PrefixSetMut,
+    trie_cursor::{AccountTrieCursor, StorageTrieCursor},
     walker::TrieWalker,
     ProofError, StorageRoot,
 };
-use reth_db::{cursor::DbCursorRO, tables, transaction::DbTx};
+use reth_db::{tables, transaction::DbTx};
 use reth_primitives::{
     keccak256,
-    trie::{
-        nodes::{rlp_hash, BranchNode, LeafNode, CHILD_INDEX_RANGE},
-        BranchNodeCompact, HashBuilder, Nibbles,
-    },
-    Address, Bytes, H256,
+    trie::{HashBuilder, Nibbles, ProofRetainer},
+    Address, Bytes, StorageEntry, H256,
 };
 use reth_rlp::Encodable;
+use std::collections::BTreeMap;
 
 /// A struct for generating merkle proofs.
 ///
-/// Proof generator starts with acquiring the trie walker and restoring the root node in the trie.
-/// The root node is restored from its immediate children which are stored in the database.
+/// Proof generator walks the trie the same way the root computation does, except that the
+/// intermediate nodes on the paths to the targets are not taken from the database but rebuilt
+/// from their children. The hash builder retains the RLP encoded nodes on those paths, which form
+/// the proof.
 ///
-/// Upon encountering the child of the root node that matches the prefix of the requested account's
-/// hashed key, the proof generator traverses the path down to the leaf node (excluded as we don't
-/// store leaf nodes in the database). The proof generator stops traversing the path upon
-/// encountering a branch node with no children matching the hashed key.
+/// If the leaf node of a target exists, it's the last node of the proof, thus proving
+/// **inclusion**. Otherwise, the proof ends at the node where the path of the target diverges,
+/// thus proving **exclusion**.
 ///
-/// After traversing the branch node path, the proof generator attempts to restore the leaf node of
-/// the target account by looking up the target account info.
-/// If the leaf node exists, we encoded it and add it to the proof thus proving **inclusion**.
-/// If the leaf node does not exist, we return the proof as is thus proving **exclusion**.
-///
-/// After traversing the path, the proof generator continues to restore the root node of the trie
-/// until completion. The root node is then inserted at the start of the proof.
+/// The targets don't have to be full keys: the node starting at a given path is the last node
+/// retained for that path, which is how single trie nodes are looked up by their paths.
 pub struct Proof<'a, 'b, TX, H> {
     /// A reference to the database transaction.
     tx: &'a TX,
@@ -48,6 +42,16 @@ impl<'a, TX> Proof<'a, 'a, TX, TX> {
     }
 }
 
+impl<'a, 'b, TX, H> Proof<'a, 'b, TX, H> {
+    /// Set the hashed cursor factory.
+    pub fn with_hashed_cursor_factory<'c, HF>(
+        self,
+        hashed_cursor_factory: &'c HF,
+    ) -> Proof<'a, 'c, TX, HF> {
+        Proof { tx: self.tx, hashed_cursor_factory }
+    }
+}
+
 impl<'a, 'b, 'tx, TX, H> Proof<'a, 'b, TX, H>
 where
     TX: DbTx<'tx>,
@@ -55,221 +59,148 @@ where
 {
     /// Generate an account proof from intermediate nodes.
     pub fn account_proof(&self, address: Address) -> Result<Vec<Bytes>, ProofError> {
-        let hashed_address = keccak256(address);
-        let target_nibbles = Nibbles::unpack(hashed_address);
+        self.account_proof_hashed(keccak256(address))
+    }
 
-        let mut proof_restorer =
-            ProofRestorer::new(self.tx)?.with_hashed_cursor_factory(self.hashed_cursor_factory)?;
-        let mut trie_cursor =
-            AccountTrieCursor::new(self.tx.cursor_read::<tables::AccountsTrie>()?);
+    /// Generate an account proof for the account with the given hashed address.
+    pub fn account_proof_hashed(&self, hashed_address: H256) -> Result<Vec<Bytes>, ProofError> {
+        let proofs = self.account_multiproof([Nibbles::unpack(hashed_address)])?;
+        Ok(proofs.into_values().collect())
+    }
 
-        // Create the walker and immediately advance it from the root key.
-        let mut walker = TrieWalker::new(&mut trie_cursor, PrefixSet::default());
-        walker.advance()?;
+    /// Generate a storage proof for the slot with the given hashed key in the storage trie of the
+    /// account with the given hashed address.
+    pub fn storage_proof(
+        &self,
+        hashed_address: H256,
+        hashed_slot: H256,
+    ) -> Result<Vec<Bytes>, ProofError> {
+        let proofs = self.storage_multiproof(hashed_address, [Nibbles::unpack(hashed_slot)])?;
+        Ok(proofs.into_values().collect())
+    }
 
-        // Create a hash builder to rebuild the root node since it is not available in the database.
-        let mut root_node_hash_builder = HashBuilder::default();
+    /// Retains the nodes of the account trie on the paths to all targets, keyed by their paths.
+    pub fn account_multiproof(
+        &self,
+        targets: impl IntoIterator<Item = Nibbles>,
+    ) -> Result<BTreeMap<Nibbles, Bytes>, ProofError> {
+        let targets = targets.into_iter().collect::<Vec<_>>();
+        let mut prefix_set = PrefixSetMut::default();
+        targets.iter().for_each(|target| prefix_set.insert(target.clone()));
+        let retainer = ProofRetainer::new(targets);
+
+        let mut hashed_account_cursor = self.hashed_cursor_factory.hashed_account_cursor()?;
+        let mut trie_cursor =
+            AccountTrieCursor::new(self.tx.cursor_read::<tables::AccountsTrie>()?);
+        let mut walker = TrieWalker::new(&mut trie_cursor, prefix_set.freeze());
+        let mut hash_builder = HashBuilder::default().with_proof_retainer(retainer);
 
-        let mut proofs: Vec<Bytes> = Vec::new();
+        let mut account_rlp = Vec::with_capacity(128);
         while let Some(key) = walker.key() {
-            if target_nibbles.has_prefix(&key) {
-                debug_assert!(proofs.is_empty(), "Prefix must match a single key");
-                proofs = self.traverse_path(walker.cursor, &mut proof_restorer, hashed_address)?;
+            if walker.can_skip_current_node {
+                let value = walker.hash().unwrap();
+                let is_in_db_trie = walker.children_are_in_trie();
+                hash_builder.add_branch(key.clone(), value, is_in_db_trie);
             }
 
-            let value = walker.hash().unwrap();
-            let is_in_db_trie = walker.children_are_in_trie();
-            root_node_hash_builder.add_branch(key.clone(), value, is_in_db_trie);
-            walker.advance()?;
-        }
+            let seek_key = match walker.next_unprocessed_key() {
+                Some(key) => key,
+                None => break, // no more keys
+            };
+
+            let next_key = walker.advance()?;
+            let mut next_account_entry = hashed_account_cursor.seek(seek_key)?;
+            while let Some((hashed_address, account)) = next_account_entry {
+                let account_nibbles = Nibbles::unpack(hashed_address);
+                if let Some(ref key) = next_key {
+                    if key < &account_nibbles {
+                        break
+                    }
+                }
+
+                let storage_root = StorageRoot::new_hashed_with_factory(
+                    self.tx,
+                    self.hashed_cursor_factory,
+                    hashed_address,
+                )
+                .root()?;
 
-        // TODO: This is a hack to retrieve the root node from the hash builder.
-        // We should find a better way.
-        root_node_hash_builder.set_updates(true);
-        let _ = root_node_hash_builder.root();
-        let (_, mut updates) = root_node_hash_builder.split();
-        let root_node = updates.remove(&Nibbles::default()).expect("root node is present");
+                account_rlp.clear();
+                EthAccount::from(account).with_storage_root(storage_root).encode(&mut account_rlp);
+                hash_builder.add_leaf(account_nibbles, &account_rlp);
 
-        // Restore the root node RLP and prepend it to the proofs result
-        let root_node_rlp = proof_restorer.restore_branch_node(&Nibbles::default(), root_node)?;
-        proofs.insert(0, root_node_rlp);
+                next_account_entry = hashed_account_cursor.next()?;
+            }
+        }
 
-        Ok(proofs)
+        let _ = hash_builder.root();
+        Ok(hash_builder.take_proofs())
     }
 
-    fn traverse_path<T: DbCursorRO<'a, tables::AccountsTrie>>(
+    /// Retains the nodes of the storage trie of the account with the given hashed address on the
+    /// paths to all targets, keyed by their paths.
+    pub fn storage_multiproof(
         &self,
-        trie_cursor: &mut AccountTrieCursor<T>,
-        proof_restorer: &mut ProofRestorer<'a, 'b, TX, H>,
         hashed_address: H256,
-    ) -> Result<Vec<Bytes>, ProofError> {
-        let mut intermediate_proofs = Vec::new();
-
-        let target = Nibbles::unpack(hashed_address);
-        let mut current_prefix = target.slice(0, 1);
-        while let Some((_, node)) =
-            trie_cursor.seek_exact(current_prefix.hex_data.to_vec().into())?
-        {
-            let branch_node_rlp = proof_restorer.restore_branch_node(&current_prefix, node)?;
-            intermediate_proofs.push(branch_node_rlp);
-
-            if current_prefix.len() < target.len() {
-                current_prefix.extend([target.0[current_prefix.len()]]);
-            }
-        }
+        targets: impl IntoIterator<Item = Nibbles>,
+    ) -> Result<BTreeMap<Nibbles, Bytes>, ProofError> {
+        let mut hashed_storage_cursor = self.hashed_cursor_factory.hashed_storage_cursor()?;
 
-        if let Some(leaf_node_rlp) =
-            proof_restorer.restore_target_leaf_node(hashed_address, current_prefix.len())?
-        {
-            intermediate_proofs.push(leaf_node_rlp);
+        // short circuit on empty storage
+        if hashed_storage_cursor.is_storage_empty(hashed_address)? {
+            return Ok(BTreeMap::default())
         }
 
-        Ok(intermediate_proofs)
-    }
-}
-
-struct ProofRestorer<'a, 'b, TX, H>
-where
-    H: HashedCursorFactory<'b>,
-{
-    /// A reference to the database transaction.
-    tx: &'a TX,
-    /// The factory for hashed cursors.
-    hashed_cursor_factory: &'b H,
-    /// The hashed account cursor.
-    hashed_account_cursor: H::AccountCursor,
-    /// Pre-allocated buffer for account RLP encoding
-    account_rlp_buf: Vec<u8>,
-    /// Pre-allocated buffer for branch/leaf node RLP encoding
-    node_rlp_buf: Vec<u8>,
-}
+        let targets = targets.into_iter().collect::<Vec<_>>();
+        let mut prefix_set = PrefixSetMut::default();
+        targets.iter().for_each(|target| prefix_set.insert(target.clone()));
+        let retainer = ProofRetainer::new(targets);
 
-impl<'a, 'tx, TX> ProofRestorer<'a, 'a, TX, TX>
-where
-    TX: DbTx<'tx>,
-{
-    fn new(tx: &'a TX) -> Result<Self, ProofError> {
-        let hashed_account_cursor = tx.hashed_account_cursor()?;
-        Ok(Self {
-            tx,
-            hashed_cursor_factory: tx,
-            hashed_account_cursor,
-            account_rlp_buf: Vec::with_capacity(128),
-            node_rlp_buf: Vec::with_capacity(128),
-        })
-    }
-}
+        let mut trie_cursor = StorageTrieCursor::new(
+            self.tx.cursor_dup_read::<tables::StoragesTrie>()?,
+            hashed_address,
+        );
+        let mut walker = TrieWalker::new(&mut trie_cursor, prefix_set.freeze());
+        let mut hash_builder = HashBuilder::default().with_proof_retainer(retainer);
 
-impl<'a, 'b, 'tx, TX, H> ProofRestorer<'a, 'b, TX, H>
-where
-    TX: DbTx<'tx> + HashedCursorFactory<'a>,
-    H: HashedCursorFactory<'b>,
-{
-    /// Set the hashed cursor factory.
-    fn with_hashed_cursor_factory<'c, HF>(
-        self,
-        hashed_cursor_factory: &'c HF,
-    ) -> Result<ProofRestorer<'a, 'c, TX, HF>, ProofError>
-    where
-        HF: HashedCursorFactory<'c>,
-    {
-        let hashed_account_cursor = hashed_cursor_factory.hashed_account_cursor()?;
-        Ok(ProofRestorer {
-            tx: self.tx,
-            hashed_cursor_factory,
-            hashed_account_cursor,
-            account_rlp_buf: self.account_rlp_buf,
-            node_rlp_buf: self.node_rlp_buf,
-        })
-    }
+        while let Some(key) = walker.key() {
+            if walker.can_skip_current_node {
+                hash_builder.add_branch(key, walker.hash().unwrap(), walker.children_are_in_trie());
+            }
 
-    fn restore_branch_node(
-        &mut self,
-        prefix: &Nibbles,
-        node: BranchNodeCompact,
-    ) -> Result<Bytes, ProofError> {
-        let mut hash_idx = 0;
-        let mut branch_node_stack = Vec::with_capacity(node.state_mask.count_ones() as usize);
-
-        for child in CHILD_INDEX_RANGE.filter(|ch| node.state_mask.is_bit_set(*ch)) {
-            if node.hash_mask.is_bit_set(child) {
-                branch_node_stack.push(rlp_hash(node.hashes[hash_idx]));
-                hash_idx += 1;
-            } else {
-                let child_key = prefix.join(&Nibbles::from_hex(Vec::from([child])));
-                let mut child_key_to_seek = child_key.pack();
-                child_key_to_seek.resize(32, 0);
-
-                let leaf_node_rlp =
-                    self.restore_leaf_node(H256::from_slice(&child_key_to_seek), child_key.len())?;
-                branch_node_stack.push(leaf_node_rlp.to_vec());
+            let seek_key = match walker.next_unprocessed_key() {
+                Some(key) => key,
+                None => break, // no more keys
+            };
+
+            let next_key = walker.advance()?;
+            let mut storage = hashed_storage_cursor.seek(hashed_address, seek_key)?;
+            while let Some(StorageEntry { key: hashed_key, value }) = storage {
+                let storage_key_nibbles = Nibbles::unpack(hashed_key);
+                if let Some(ref key) = next_key {
+                    if key < &storage_key_nibbles {
+                        break
+                    }
+                }
+                hash_builder
+                    .add_leaf(storage_key_nibbles, reth_rlp::encode_fixed_size(&value).as_ref());
+                storage = hashed_storage_cursor.next()?;
             }
         }
 
-        self.node_rlp_buf.clear();
-        BranchNode::new(&branch_node_stack).rlp(node.state_mask, &mut self.node_rlp_buf);
-        Ok(Bytes::from(self.node_rlp_buf.as_slice()))
-    }
-
-    /// Restore leaf node.
-    /// The leaf nodes are always encoded as `RLP(node) or RLP(keccak(RLP(node)))`.
-    fn restore_leaf_node(&mut self, seek_key: H256, slice_at: usize) -> Result<Bytes, ProofError> {
-        let (hashed_address, account) = self
-            .hashed_account_cursor
-            .seek(seek_key)?
-            .ok_or(ProofError::LeafAccountMissing(seek_key))?;
-
-        // Restore account's storage root.
-        let storage_root = StorageRoot::new_hashed(self.tx, hashed_address)
-            .with_hashed_cursor_factory(self.hashed_cursor_factory)
-            .root()?;
-
-        self.account_rlp_buf.clear();
-        EthAccount::from(account).with_storage_root(storage_root).encode(&mut self.account_rlp_buf);
-
-        let leaf_node_key = Nibbles::unpack(hashed_address).slice_from(slice_at);
-        let leaf_node = LeafNode::new(&leaf_node_key, &self.account_rlp_buf);
-
-        self.node_rlp_buf.clear();
-        Ok(Bytes::from(leaf_node.rlp(&mut self.node_rlp_buf)))
-    }
-
-    /// Restore target leaf node.
-    /// The target node has to have an exactly matching key and is always encoded as `RLP(node)`.
-    /// The target node might be missing from the trie.
-    fn restore_target_leaf_node(
-        &mut self,
-        seek_key: H256,
-        slice_at: usize,
-    ) -> Result<Option<Bytes>, ProofError> {
-        let (hashed_address, account) = match self.hashed_account_cursor.seek(seek_key)? {
-            Some(entry) if entry.0 == seek_key => entry,
-            _ => return Ok(None),
-        };
-
-        // Restore account's storage root.
-        let storage_root = StorageRoot::new_hashed(self.tx, hashed_address)
-            .with_hashed_cursor_factory(self.hashed_cursor_factory)
-            .root()?;
-
-        self.account_rlp_buf.clear();
-        EthAccount::from(account).with_storage_root(storage_root).encode(&mut self.account_rlp_buf);
-
-        let leaf_node_key = Nibbles::unpack(hashed_address).slice_from(slice_at);
-        let leaf_node = LeafNode::new(&leaf_node_key, &self.account_rlp_buf);
-
-        self.node_rlp_buf.clear();
-        leaf_node.rlp(&mut self.node_rlp_buf);
-        Ok(Some(Bytes::from(self.node_rlp_buf.as_slice())))
+        let _ = hash_builder.root();
+        Ok(hash_builder.take_proofs())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::StateRoot;
-    use reth_db::{database::Database, test_utils::create_test_rw_db};
-    use reth_primitives::{ChainSpec, StorageEntry, MAINNET};
+    use crate::{verify_range_proof, StateRoot};
+    use reth_db::{
+        cursor::DbCursorRW, database::Database, test_utils::create_test_rw_db, transaction::DbTxMut,
+    };
+    use reth_primitives::{ChainSpec, StorageEntry, MAINNET, U256};
     use reth_provider::{HashingWriter, ProviderFactory};
     use std::{str::FromStr, sync::Arc};
 
@@ -354,4 +285,42 @@ mod tests {
         let proof = Proof::new(&tx).account_proof(target).unwrap();
         pretty_assertions::assert_eq!(proof, expected_account_proof);
     }
+
+    #[test]
+    fn storage_proof() {
+        let db = create_test_rw_db();
+        let factory = ProviderFactory::new(db.as_ref(), MAINNET.clone());
+        let provider = factory.provider_rw().unwrap();
+
+        let hashed_address = keccak256(Address::random());
+        let storage = (0..100u64)
+            .map(|i| (keccak256(H256::from_low_u64_be(i)), U256::from(i + 1)))
+            .collect::<BTreeMap<_, _>>();
+        let mut cursor = provider.tx_ref().cursor_dup_write::<tables::HashedStorage>().unwrap();
+        for (key, value) in &storage {
+            cursor.upsert(hashed_address, StorageEntry { key: *key, value: *value }).unwrap();
+        }
+        drop(cursor);
+
+        let (root, _, updates) =
+            StorageRoot::new_hashed(provider.tx_ref(), hashed_address).root_with_updates().unwrap();
+        updates.flush(provider.tx_ref()).unwrap();
+
+        let proof = Proof::new(provider.tx_ref());
+
+        // Inclusion proof.
+        let (slot, value) = storage.iter().nth(42).unwrap();
+        let nodes = proof.storage_proof(hashed_address, *slot).unwrap();
+        assert_eq!(keccak256(&nodes[0]), root);
+        let leaf = (*slot, reth_rlp::encode_fixed_size(value));
+        assert!(verify_range_proof(root, *slot, &[leaf], &nodes).is_ok());
+
+        // The root node is looked up by the empty path.
+        let nodes = proof.storage_multiproof(hashed_address, [Nibbles::default()]).unwrap();
+        assert_eq!(nodes.len(), 1);
+        assert_eq!(keccak256(&nodes[&Nibbles::default()]), root);
+
+        // No proof for an account without storage.
+        assert!(proof.storage_proof(H256::random(), *slot).unwrap().is_empty());
+    }
 }
@@ -0,0 +1,491 @@
+use crate::RangeProofError;
+use reth_primitives::{
+    keccak256,
+    proofs::EMPTY_ROOT,
+    trie::{HashBuilder, Nibbles},
+    Bytes, H256,
+};
+use reth_rlp::{Decodable, Header};
+use std::collections::HashMap;
+
+/// Verifies that the given leaves are all the leaves of the trie with the given root, starting at
+/// `origin` up to the last leaf, as served by `snap` protocol peers.
+///
+/// The proof has to contain the nodes on the paths to `origin` and to the last leaf. If there are
+/// no leaves, the proof is an absence proof for all keys starting at `origin`. If the proof is
+/// empty, the leaves have to be the entire trie.
+///
+/// The leaves have to be ordered by their keys, and their values are the values stored in the
+/// trie, e.g. the RLP encoded accounts of the state trie.
+///
+/// The verification doesn't need the full trie: the subtries entirely outside of the range are
+/// taken from the proof as they are, and combined with the leaves to recompute the root.
+///
+/// Returns `true` if the trie has more leaves after the last one.
+pub fn verify_range_proof<V: AsRef<[u8]>>(
+    root: H256,
+    origin: H256,
+    leaves: &[(H256, V)],
+    proof: &[Bytes],
+) -> Result<bool, RangeProofError> {
+    for window in leaves.windows(2) {
+        if window[0].0 >= window[1].0 {
+            return Err(RangeProofError::UnorderedLeaves(window[1].0))
+        }
+    }
+    if let Some((first, _)) = leaves.first() {
+        if *first < origin {
+            return Err(RangeProofError::LeafBeforeOrigin(*first))
+        }
+    }
+    if leaves.iter().any(|(_, value)| value.as_ref().is_empty()) {
+        return Err(RangeProofError::EmptyValue)
+    }
+
+    let mut collector = RangeCollector {
+        nodes: proof.iter().map(|node| (keccak256(node), node.as_ref())).collect(),
+        first: Nibbles::unpack(origin),
+        last: Nibbles::unpack(leaves.last().map_or(H256::repeat_byte(0xff), |(key, _)| *key)),
+        outside: Vec::new(),
+        has_more: false,
+    };
+
+    if proof.is_empty() {
+        // The leaves are the entire trie.
+        if origin != H256::zero() {
+            return Err(RangeProofError::MissingNode(root))
+        }
+    } else if root != EMPTY_ROOT {
+        collector.collect(NodeRef::Hash(root), Nibbles::default())?;
+    }
+
+    let RangeCollector { outside, has_more, .. } = collector;
+    let mut entries = outside;
+    entries.extend(
+        leaves
+            .iter()
+            .map(|(key, value)| (Nibbles::unpack(key), Entry::Leaf(value.as_ref().to_vec()))),
+    );
+    entries.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+
+    let mut hash_builder = HashBuilder::default();
+    for (path, entry) in entries {
+        match entry {
+            Entry::Hash(hash) => hash_builder.add_branch(path, hash, false),
+            Entry::Leaf(value) => hash_builder.add_leaf(path, &value),
+        }
+    }
+
+    let got = hash_builder.root();
+    if got != root {
+        return Err(RangeProofError::RootMismatch { expected: root, got })
+    }
+
+    Ok(has_more)
+}
+
+/// Decodes the trie node and returns the hashes of its children by their nibbles if it's a branch
+/// node, or `None` if it's an extension or a leaf node.
+///
+/// Children embedded into the node are returned as missing, as they have no hashes. Nodes of the
+/// state trie are never small enough to be embedded.
+pub fn branch_node_children(rlp: &[u8]) -> Result<Option<[Option<H256>; 16]>, RangeProofError> {
+    match decode_node(rlp)? {
+        Node::Branch(children) => Ok(Some(children.map(|child| match child {
+            Some(NodeRef::Hash(hash)) => Some(hash),
+            _ => None,
+        }))),
+        Node::Extension(..) | Node::Leaf(..) => Ok(None),
+    }
+}
+
+/// A subtrie outside of the verified range, or a leaf.
+enum Entry {
+    Hash(H256),
+    Leaf(Vec<u8>),
+}
+
+/// A reference to a child node, either by its hash or embedded into the parent.
+#[derive(Clone, Copy)]
+enum NodeRef<'a> {
+    Hash(H256),
+    Inline(&'a [u8]),
+}
+
+/// A decoded trie node.
+enum Node<'a> {
+    Branch([Option<NodeRef<'a>>; 16]),
+    Extension(Nibbles, NodeRef<'a>),
+    Leaf(Nibbles, &'a [u8]),
+}
+
+/// Where a subtrie is, relative to the verified range.
+enum Position {
+    /// All keys of the subtrie are before the range.
+    Before,
+    /// All keys of the subtrie are after the range.
+    After,
+    /// All keys of the subtrie are in the range.
+    Inside,
+    /// The subtrie contains the start or the end of the range.
+    Edge,
+}
+
+struct RangeCollector<'a> {
+    nodes: HashMap<H256, &'a [u8]>,
+    first: Nibbles,
+    last: Nibbles,
+    /// Subtries and leaves outside of the range, by their paths.
+    outside: Vec<(Nibbles, Entry)>,
+    has_more: bool,
+}
+
+impl<'a> RangeCollector<'a> {
+    fn position(&self, path: &Nibbles) -> Position {
+        let path = &path.hex_data[..];
+        let first = &self.first.hex_data[..path.len()];
+        let last = &self.last.hex_data[..path.len()];
+        if path < first {
+            Position::Before
+        } else if path > last {
+            Position::After
+        } else if path == first || path == last {
+            Position::Edge
+        } else {
+            Position::Inside
+        }
+    }
+
+    fn collect(&mut self, node: NodeRef<'a>, path: Nibbles) -> Result<(), RangeProofError> {
+        if path.len() > self.first.len() {
+            return Err(RangeProofError::InvalidNode)
+        }
+
+        match self.position(&path) {
+            Position::Inside => Ok(()),
+            position @ (Position::Before | Position::After) => {
+                if matches!(position, Position::After) {
+                    self.has_more = true;
+                }
+                match node {
+                    NodeRef::Hash(hash) => {
+                        self.outside.push((path, Entry::Hash(hash)));
+                        Ok(())
+                    }
+                    // Embedded nodes are too small to contain hashes, so they are added leaf by
+                    // leaf.
+                    NodeRef::Inline(rlp) => self.collect_leaves(rlp, path),
+                }
+            }
+            Position::Edge => {
+                let rlp = match node {
+                    NodeRef::Hash(hash) => {
+                        *self.nodes.get(&hash).ok_or(RangeProofError::MissingNode(hash))?
+                    }
+                    NodeRef::Inline(rlp) => rlp,
+                };
+                match decode_node(rlp)? {
+                    Node::Branch(children) => {
+                        for (nibble, child) in children.into_iter().enumerate() {
+                            if let Some(child) = child {
+                                self.collect(
+                                    child,
+                                    path.join(&Nibbles::from_hex(vec![nibble as u8])),
+                                )?;
+                            }
+                        }
+                        Ok(())
+                    }
+                    Node::Extension(key, child) => self.collect(child, path.join(&key)),
+                    Node::Leaf(key, value) => {
+                        let key = path.join(&key);
+                        if key.len() != self.first.len() {
+                            return Err(RangeProofError::InvalidNode)
+                        }
+                        // Leaves in the range have to be provided with the range itself.
+                        if key < self.first || key > self.last {
+                            self.has_more |= key > self.last;
+                            self.outside.push((key, Entry::Leaf(value.to_vec())));
+                        }
+                        Ok(())
+                    }
+                }
+            }
+        }
+    }
+
+    fn collect_leaves(&mut self, rlp: &'a [u8], path: Nibbles) -> Result<(), RangeProofError> {
+        match decode_node(rlp)? {
+            Node::Branch(children) => {
+                for (nibble, child) in children.into_iter().enumerate() {
+                    match child {
+                        Some(NodeRef::Inline(rlp)) => self.collect_leaves(
+                            rlp,
+                            path.join(&Nibbles::from_hex(vec![nibble as u8])),
+                        )?,
+                        Some(NodeRef::Hash(_)) => return Err(RangeProofError::InvalidNode),
+                        None => {}
+                    }
+                }
+            }
+            Node::Extension(key, NodeRef::Inline(rlp)) => {
+                self.collect_leaves(rlp, path.join(&key))?
+            }
+            Node::Extension(_, NodeRef::Hash(_)) => return Err(RangeProofError::InvalidNode),
+            Node::Leaf(key, value) => {
+                self.outside.push((path.join(&key), Entry::Leaf(value.to_vec())))
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Splits the RLP list into its raw items.
+fn list_items(mut buf: &[u8]) -> Result<Vec<&[u8]>, RangeProofError> {
+    let header = Header::decode(&mut buf)?;
+    if !header.list || header.payload_length != buf.len() {
+        return Err(RangeProofError::InvalidNode)
+    }
+
+    let mut items = Vec::new();
+    while !buf.is_empty() {
+        let mut payload = buf;
+        let header = Header::decode(&mut payload)?;
+        let len = buf.len() - payload.len() + header.payload_length;
+        if len > buf.len() {
+            return Err(RangeProofError::InvalidNode)
+        }
+        items.push(&buf[..len]);
+        buf = &buf[len..];
+    }
+    Ok(items)
+}
+
+/// Returns the payload of the RLP string.
+fn string_payload(mut item: &[u8]) -> Result<&[u8], RangeProofError> {
+    let header = Header::decode(&mut item)?;
+    if header.list {
+        return Err(RangeProofError::InvalidNode)
+    }
+    Ok(&item[..header.payload_length])
+}
+
+fn decode_child(item: &[u8]) -> Result<Option<NodeRef<'_>>, RangeProofError> {
+    let mut buf = item;
+    let header = Header::decode(&mut buf)?;
+    if header.list {
+        Ok(Some(NodeRef::Inline(item)))
+    } else if header.payload_length == 0 {
+        Ok(None)
+    } else {
+        Ok(Some(NodeRef::Hash(H256::decode(&mut &item[..])?)))
+    }
+}
+
+fn decode_node(rlp: &[u8]) -> Result<Node<'_>, RangeProofError> {
+    let items = list_items(rlp)?;
+    match items.len() {
+        17 => {
+            // Keys are of the same length, so branch nodes never have values.
+            if !string_payload(items[16])?.is_empty() {
+                return Err(RangeProofError::InvalidNode)
+            }
+            let mut children = [None; 16];
+            for (child, item) in children.iter_mut().zip(&items) {
+                *child = decode_child(item)?;
+            }
+            Ok(Node::Branch(children))
+        }
+        2 => {
+            let encoded_path = string_payload(items[0])?;
+            let flag = *encoded_path.first().ok_or(RangeProofError::InvalidNode)? >> 4;
+            let mut key = Nibbles::unpack(encoded_path);
+            // Drop the flag nibble and the padding nibble of even length paths.
+            key = key.slice_from(if flag & 1 == 1 { 1 } else { 2 });
+            match flag {
+                0 | 1 => Ok(Node::Extension(
+                    key,
+                    decode_child(items[1])?.ok_or(RangeProofError::InvalidNode)?,
+                )),
+                2 | 3 => Ok(Node::Leaf(key, string_payload(items[1])?)),
+                _ => Err(RangeProofError::InvalidNode),
+            }
+        }
+        _ => Err(RangeProofError::InvalidNode),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{range_proof, trie_root};
+    use proptest::prelude::*;
+    use reth_primitives::U256;
+    use std::collections::BTreeMap;
+
+    fn leaves(entries: &BTreeMap<H256, Vec<u8>>) -> Vec<(H256, Vec<u8>)> {
+        entries.iter().map(|(key, value)| (*key, value.clone())).collect()
+    }
+
+    fn storage_entries(seed: u64, count: u64) -> BTreeMap<H256, Vec<u8>> {
+        (0..count)
+            .map(|i| {
+                let key = keccak256((seed * 1000 + i).to_be_bytes());
+                (key, reth_rlp::encode_fixed_size(&U256::from(i + 1)).to_vec())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn entire_trie() {
+        let entries = storage_entries(1, 100);
+        let root = trie_root(&entries);
+
+        assert_eq!(verify_range_proof(root, H256::zero(), &leaves(&entries), &[]), Ok(false));
+
+        let mut missing = leaves(&entries);
+        missing.remove(50);
+        assert!(matches!(
+            verify_range_proof(root, H256::zero(), &missing, &[]),
+            Err(RangeProofError::RootMismatch { .. })
+        ));
+
+        assert_eq!(
+            verify_range_proof(EMPTY_ROOT, H256::zero(), &[] as &[(H256, Vec<u8>)], &[]),
+            Ok(false)
+        );
+    }
+
+    #[test]
+    fn partial_ranges() {
+        let entries = storage_entries(2, 200);
+        let root = trie_root(&entries);
+        let all = leaves(&entries);
+
+        // Ranges from the beginning, the middle and the end of the trie.
+        for (start, end) in [(0, 10), (50, 120), (150, 200), (0, 200), (199, 200)] {
+            let origin = if start == 0 { H256::zero() } else { all[start].0 };
+            let range = &all[start..end];
+            let proof = range_proof(&entries, origin, range.last().map(|(key, _)| *key));
+            assert_eq!(verify_range_proof(root, origin, range, &proof), Ok(end < all.len()));
+        }
+
+        // Origin between two leaves.
+        let mut origin = all[10].0;
+        origin.0[31] = origin.0[31].wrapping_add(1);
+        assert!(origin > all[10].0 && origin < all[11].0);
+        let range = &all[11..30];
+        let proof = range_proof(&entries, origin, Some(range.last().unwrap().0));
+        assert_eq!(verify_range_proof(root, origin, range, &proof), Ok(true));
+    }
+
+    #[test]
+    fn absence_proof() {
+        let entries = storage_entries(3, 50);
+        let root = trie_root(&entries);
+        let last = *entries.keys().last().unwrap();
+
+        // No leaves after the origin.
+        let mut origin = last;
+        origin.0[31] = origin.0[31].wrapping_add(1);
+        assert!(origin > last);
+        let proof = range_proof(&entries, origin, None);
+        assert_eq!(verify_range_proof(root, origin, &[] as &[(H256, Vec<u8>)], &proof), Ok(false));
+
+        // Leaves after the origin are hidden.
+        let origin = H256::zero();
+        let proof = range_proof(&entries, origin, None);
+        assert!(verify_range_proof(root, origin, &[] as &[(H256, Vec<u8>)], &proof).is_err());
+    }
+
+    #[test]
+    fn invalid_ranges() {
+        let entries = storage_entries(4, 100);
+        let root = trie_root(&entries);
+        let all = leaves(&entries);
+        let origin = all[20].0;
+        let proof = range_proof(&entries, origin, Some(all[40].0));
+
+        // Gap in the range.
+        let mut range = all[20..=40].to_vec();
+        range.remove(10);
+        assert!(matches!(
+            verify_range_proof(root, origin, &range, &proof),
+            Err(RangeProofError::RootMismatch { .. })
+        ));
+
+        // Modified value.
+        let mut range = all[20..=40].to_vec();
+        range[5].1 = vec![0x42];
+        assert!(matches!(
+            verify_range_proof(root, origin, &range, &proof),
+            Err(RangeProofError::RootMismatch { .. })
+        ));
+
+        // First leaf omitted.
+        let range = &all[21..=40];
+        assert!(verify_range_proof(root, origin, range, &proof).is_err());
+
+        // Unordered leaves.
+        let mut range = all[20..=40].to_vec();
+        range.swap(1, 2);
+        assert_eq!(
+            verify_range_proof(root, origin, &range, &proof),
+            Err(RangeProofError::UnorderedLeaves(range[2].0))
+        );
+
+        // Missing proof nodes.
+        assert!(matches!(
+            verify_range_proof(root, origin, &all[20..=40], &proof[1..]),
+            Err(RangeProofError::MissingNode(_))
+        ));
+    }
+
+    #[test]
+    fn branch_children() {
+        let entries = storage_entries(5, 100);
+        let root = trie_root(&entries);
+        let first = *entries.keys().next().unwrap();
+        let proof = range_proof(&entries, first, None);
+        assert_eq!(keccak256(&proof[0]), root);
+
+        let children = branch_node_children(&proof[0]).unwrap().unwrap();
+        for (nibble, child) in children.into_iter().enumerate() {
+            let has_leaves = entries.keys().any(|key| (key[0] >> 4) as usize == nibble);
+            assert_eq!(child.is_some(), has_leaves);
+        }
+        // The next node of the proof is the child of the root on the path to the first key.
+        assert_eq!(children[(first[0] >> 4) as usize], Some(keccak256(&proof[1])));
+
+        let leaf = range_proof(&storage_entries(6, 1), H256::zero(), None);
+        assert_eq!(branch_node_children(&leaf[0]), Ok(None));
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig { cases: 64, ..ProptestConfig::default() })]
+
+        #[test]
+        fn random_ranges(
+            entries in proptest::collection::btree_map(any::<[u8; 32]>(), 1..8u8, 1..300),
+            start in any::<prop::sample::Index>(),
+            len in 1..100usize,
+        ) {
+            let entries: BTreeMap<H256, Vec<u8>> = entries
+                .into_iter()
+                .map(|(key, value)| (H256(key), vec![value]))
+                .collect();
+            let root = trie_root(&entries);
+            let all = leaves(&entries);
+
+            let start = start.index(all.len());
+            let end = (start + len).min(all.len());
+            let origin = all[start].0;
+            let range = &all[start..end];
+            let proof = range_proof(&entries, origin, range.last().map(|(key, _)| *key));
+            prop_assert_eq!(
+                verify_range_proof(root, origin, range, &proof),
+                Ok(end < all.len())
+            );
+        }
+    }
+}
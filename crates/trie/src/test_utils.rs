@@ -1,6 +1,14 @@
 use crate::account::EthAccount;
-use reth_primitives::{proofs::KeccakHasher, Account, Address, H256, U256};
+use reth_primitives::{
+    proofs::KeccakHasher,
+    trie::{
+        nodes::{BranchNode, ExtensionNode, LeafNode, CHILD_INDEX_RANGE},
+        Nibbles, TrieMask,
+    },
+    Account, Address, Bytes, H256, U256,
+};
 use reth_rlp::{encode_fixed_size, Encodable};
+use std::collections::BTreeMap;
 
 /// Re-export of [triehash].
 pub use triehash;
@@ -49,3 +57,79 @@ pub fn storage_root_prehashed<I: Iterator<Item = (H256, U256)>>(storage: I) -> H
     let encoded_storage = storage.map(|(k, v)| (k, encode_fixed_size(&v).to_vec()));
     triehash::trie_root::<KeccakHasher, _, _, _>(encoded_storage)
 }
+
+/// Compute the root of the trie with the given prehashed keys and values using
+/// [triehash::trie_root].
+pub fn trie_root(entries: &BTreeMap<H256, Vec<u8>>) -> H256 {
+    triehash::trie_root::<KeccakHasher, _, _, _>(entries.iter())
+}
+
+/// Builds the trie with the given prehashed keys and values in memory, and returns all of its
+/// nodes that are not embedded into their parents, by their paths.
+pub fn trie_nodes(entries: &BTreeMap<H256, Vec<u8>>) -> BTreeMap<Nibbles, Vec<u8>> {
+    let entries: Vec<_> =
+        entries.iter().map(|(key, value)| (Nibbles::unpack(key), value.as_slice())).collect();
+    let mut nodes = Vec::new();
+    if !entries.is_empty() {
+        build_node(&entries, 0, &mut nodes);
+    }
+
+    nodes
+        .into_iter()
+        .filter(|(path, rlp)| path.is_empty() || rlp.len() >= H256::len_bytes())
+        .collect()
+}
+
+/// Builds the trie with the given prehashed keys and values in memory, and returns the proof for
+/// the range starting at `origin` and ending at `last`, or for all keys starting at `origin`.
+///
+/// The proof contains all nodes on the paths to `origin` and `last` that are not embedded into
+/// their parents.
+pub fn range_proof(
+    entries: &BTreeMap<H256, Vec<u8>>,
+    origin: H256,
+    last: Option<H256>,
+) -> Vec<Bytes> {
+    let targets =
+        [Some(origin), last].into_iter().flatten().map(Nibbles::unpack).collect::<Vec<_>>();
+    trie_nodes(entries)
+        .into_iter()
+        .filter(|(path, _)| targets.iter().any(|target| target.has_prefix(path)))
+        .map(|(_, rlp)| Bytes::from(rlp))
+        .collect()
+}
+
+/// Builds the node at the given depth of the trie with the given leaves, and returns the
+/// reference to it. All nodes of the subtrie are pushed to `nodes` with their paths.
+fn build_node(
+    entries: &[(Nibbles, &[u8])],
+    depth: usize,
+    nodes: &mut Vec<(Nibbles, Vec<u8>)>,
+) -> Vec<u8> {
+    let (first, value) = &entries[0];
+    let last = &entries[entries.len() - 1].0;
+
+    let mut rlp = Vec::new();
+    let node_ref = if entries.len() == 1 {
+        LeafNode::new(&first.slice_from(depth), value).rlp(&mut rlp)
+    } else if first.common_prefix_length(last) > depth {
+        let common = first.common_prefix_length(last);
+        let child = build_node(entries, common, nodes);
+        ExtensionNode::new(&first.slice(depth, common), &child).rlp(&mut rlp)
+    } else {
+        let mut stack = Vec::new();
+        let mut state_mask = 0u16;
+        for nibble in CHILD_INDEX_RANGE {
+            let children: Vec<_> =
+                entries.iter().filter(|(key, _)| key[depth] == nibble).cloned().collect();
+            if !children.is_empty() {
+                stack.push(build_node(&children, depth + 1, nodes));
+                state_mask |= 1 << nibble;
+            }
+        }
+        BranchNode::new(&stack).rlp(TrieMask::new(state_mask), &mut rlp)
+    };
+
+    nodes.push((first.slice(0, depth), rlp));
+    node_ref
+}
@@ -1,6 +1,7 @@
 use crate::{
     account::EthAccount,
     hashed_cursor::{HashedAccountCursor, HashedCursorFactory, HashedStorageCursor},
+    parallel::{parallel_storage_roots, StorageRootOutput},
     prefix_set::{PrefixSet, PrefixSetLoader, PrefixSetMut},
     progress::{IntermediateStateRootState, StateRootProgress},
     trie_cursor::{AccountTrieCursor, StorageTrieCursor},
@@ -8,7 +9,7 @@ use crate::{
     walker::TrieWalker,
     StateRootError, StorageRootError,
 };
-use reth_db::{tables, transaction::DbTx};
+use reth_db::{database::Database, tables, transaction::DbTx};
 use reth_primitives::{
     keccak256,
     proofs::EMPTY_ROOT,
@@ -34,6 +35,8 @@ pub struct StateRoot<'a, 'b, TX, H> {
     pub changed_storage_prefixes: HashMap<H256, PrefixSet>,
     /// A map containing keys of accounts that were destroyed.
     pub destroyed_accounts: HashSet<H256>,
+    /// Storage roots computed ahead of the account trie walk, keyed by hashed address.
+    pub precomputed_storage_roots: HashMap<H256, StorageRootOutput>,
     /// Previous intermediate state.
     previous_state: Option<IntermediateStateRootState>,
     /// The number of updates after which the intermediate progress should be returned.
@@ -59,6 +62,18 @@ impl<'a, 'b, TX, H> StateRoot<'a, 'b, TX, H> {
         self
     }
 
+    /// Set the storage roots computed ahead of the account trie walk.
+    ///
+    /// The precomputed roots are used instead of computing the storage root of a visited account
+    /// and must have been computed with the changed storage prefixes of the account.
+    pub fn with_precomputed_storage_roots(
+        mut self,
+        storage_roots: HashMap<H256, StorageRootOutput>,
+    ) -> Self {
+        self.precomputed_storage_roots = storage_roots;
+        self
+    }
+
     /// Set the threshold.
     pub fn with_threshold(mut self, threshold: u64) -> Self {
         self.threshold = threshold;
@@ -87,11 +102,36 @@ impl<'a, 'b, TX, H> StateRoot<'a, 'b, TX, H> {
             changed_account_prefixes: self.changed_account_prefixes,
             changed_storage_prefixes: self.changed_storage_prefixes,
             destroyed_accounts: self.destroyed_accounts,
+            precomputed_storage_roots: self.precomputed_storage_roots,
             threshold: self.threshold,
             previous_state: self.previous_state,
             hashed_cursor_factory,
         }
     }
+
+    /// Computes the storage roots of all accounts with changed storage in parallel, using a
+    /// separate read-only transaction of the given database for each account.
+    ///
+    /// Accounts that were already processed according to the intermediate state are skipped. The
+    /// storage trie updates are always retained. Since the storage roots are computed from the
+    /// committed state of the database, the storage tries and hashed storage of the transaction
+    /// this calculator was created with must not have uncommitted changes.
+    pub fn with_parallel_storage_roots<DB: Database>(
+        self,
+        db: &DB,
+    ) -> Result<Self, StorageRootError> {
+        let last_account_key = self.previous_state.as_ref().map(|state| state.last_account_key);
+        let targets = self
+            .changed_storage_prefixes
+            .iter()
+            .filter(|(hashed_address, _)| {
+                last_account_key.map_or(true, |last| **hashed_address > last)
+            })
+            .map(|(hashed_address, prefix_set)| (*hashed_address, prefix_set.clone()))
+            .collect();
+        let storage_roots = parallel_storage_roots(db, targets, true)?;
+        Ok(self.with_precomputed_storage_roots(storage_roots))
+    }
 }
 
 impl<'a, 'tx, TX> StateRoot<'a, 'a, TX, TX>
@@ -105,6 +145,7 @@ where
             changed_account_prefixes: PrefixSetMut::default().freeze(),
             changed_storage_prefixes: HashMap::default(),
             destroyed_accounts: HashSet::default(),
+            precomputed_storage_roots: HashMap::default(),
             previous_state: None,
             threshold: 100_000,
             hashed_cursor_factory: tx,
@@ -222,7 +263,7 @@ where
         self.calculate(true)
     }
 
-    fn calculate(self, retain_updates: bool) -> Result<StateRootProgress, StateRootError> {
+    fn calculate(mut self, retain_updates: bool) -> Result<StateRootProgress, StateRootError> {
         tracing::debug!(target: "loader", "calculating state root");
         let mut trie_updates = TrieUpdates::default();
 
@@ -298,23 +339,31 @@ where
                 // progress.
                 // TODO: We can consider introducing the TrieProgress::Progress/Complete
                 // abstraction inside StorageRoot, but let's give it a try as-is for now.
-                let storage_root_calculator = StorageRoot::new_hashed(self.tx, hashed_address)
-                    .with_hashed_cursor_factory(self.hashed_cursor_factory)
-                    .with_changed_prefixes(
-                        self.changed_storage_prefixes
-                            .get(&hashed_address)
-                            .cloned()
-                            .unwrap_or_default(),
-                    );
-
-                let storage_root = if retain_updates {
-                    let (root, storage_slots_walked, updates) =
-                        storage_root_calculator.root_with_updates()?;
-                    hashed_entries_walked += storage_slots_walked;
-                    trie_updates.extend(updates.into_iter());
-                    root
+                let storage_root = if let Some(output) =
+                    self.precomputed_storage_roots.remove(&hashed_address)
+                {
+                    hashed_entries_walked += output.slots_walked;
+                    trie_updates.extend(output.updates.into_iter());
+                    output.root
                 } else {
-                    storage_root_calculator.root()?
+                    let storage_root_calculator = StorageRoot::new_hashed(self.tx, hashed_address)
+                        .with_hashed_cursor_factory(self.hashed_cursor_factory)
+                        .with_changed_prefixes(
+                            self.changed_storage_prefixes
+                                .get(&hashed_address)
+                                .cloned()
+                                .unwrap_or_default(),
+                        );
+
+                    if retain_updates {
+                        let (root, storage_slots_walked, updates) =
+                            storage_root_calculator.root_with_updates()?;
+                        hashed_entries_walked += storage_slots_walked;
+                        trie_updates.extend(updates.into_iter());
+                        root
+                    } else {
+                        storage_root_calculator.root()?
+                    }
                 };
 
                 let account = EthAccount::from(account).with_storage_root(storage_root);
@@ -1279,6 +1328,84 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parallel_storage_roots_match_serial() {
+        let db = create_test_rw_db();
+        let factory = ProviderFactory::new(db.as_ref(), MAINNET.clone());
+
+        let mut state = BTreeMap::<H256, (Account, BTreeMap<H256, U256>)>::default();
+        for i in 1..=10u64 {
+            let storage = (1..=i * 10)
+                .map(|slot| (keccak256(H256::from_low_u64_be(slot)), U256::from(slot)))
+                .collect();
+            state.insert(
+                keccak256(H256::from_low_u64_be(i)),
+                (Account { nonce: i, ..Default::default() }, storage),
+            );
+        }
+
+        // build the initial trie
+        let tx = factory.provider_rw().unwrap();
+        for (hashed_address, (account, storage)) in &state {
+            tx.tx_ref().put::<tables::HashedAccount>(*hashed_address, *account).unwrap();
+            for (key, value) in storage {
+                tx.tx_ref()
+                    .put::<tables::HashedStorage>(
+                        *hashed_address,
+                        StorageEntry { key: *key, value: *value },
+                    )
+                    .unwrap();
+            }
+        }
+        let (_, updates) = StateRoot::new(tx.tx_ref()).root_with_updates().unwrap();
+        updates.flush(tx.tx_ref()).unwrap();
+        tx.commit().unwrap();
+
+        // change the storage of every other account
+        let tx = factory.provider_rw().unwrap();
+        let mut storage_cursor = tx.tx_ref().cursor_dup_write::<tables::HashedStorage>().unwrap();
+        let mut account_prefixes = PrefixSetMut::default();
+        let mut storage_prefixes = HashMap::new();
+        for (hashed_address, (_, storage)) in state.iter_mut().step_by(2) {
+            let mut prefix_set = PrefixSetMut::default();
+            for slot in [3u64, 1_000] {
+                let key = keccak256(H256::from_low_u64_be(slot));
+                let value = U256::from(slot * 7);
+                if storage_cursor.seek_by_key_subkey(*hashed_address, key).unwrap().is_some() {
+                    storage_cursor.delete_current().unwrap();
+                }
+                storage_cursor.upsert(*hashed_address, StorageEntry { key, value }).unwrap();
+                storage.insert(key, value);
+                prefix_set.insert(Nibbles::unpack(key));
+            }
+            account_prefixes.insert(Nibbles::unpack(*hashed_address));
+            storage_prefixes.insert(*hashed_address, prefix_set.freeze());
+        }
+        drop(storage_cursor);
+        tx.commit().unwrap();
+
+        let tx = db.tx().unwrap();
+        let calculator = || {
+            StateRoot::new(&tx)
+                .with_changed_account_prefixes(account_prefixes.clone().freeze())
+                .with_changed_storage_prefixes(storage_prefixes.clone())
+        };
+        let (serial_root, serial_updates) = calculator().root_with_updates().unwrap();
+        let (parallel_root, parallel_updates) = calculator()
+            .with_parallel_storage_roots(db.as_ref())
+            .unwrap()
+            .root_with_updates()
+            .unwrap();
+
+        let expected = state_root_prehashed(state.into_iter());
+        assert_eq!(serial_root, expected);
+        assert_eq!(parallel_root, expected);
+        assert_eq!(
+            parallel_updates.into_iter().collect::<BTreeMap<_, _>>(),
+            serial_updates.into_iter().collect::<BTreeMap<_, _>>()
+        );
+    }
+
     #[test]
     fn storage_trie_around_extension_node() {
         let db = create_test_rw_db();